@@ -0,0 +1,71 @@
+//! Benchmarks of the cost of adding a message for the thread adding it.
+//!
+//! Run them with `cargo bench --bench messages`. Every thread of the server adds messages, the
+//! game loop included, see `add_message`: adding one must stay a send on a channel, whatever the
+//! readers of the log do.
+//!
+//! - `add_message/alone` adds messages with no one reading the log;
+//! - `add_message/read` adds them while a thread takes a copy of the log in a loop, as the UI does
+//!   every frame;
+//! - `add_message/dropped` adds messages below the verbosity, which are dropped at once;
+//! - `add_messages/<count>` adds a batch of messages, as the event logger does after a step.
+//!
+//! The hub is flushed between the samples, so the messages piling up in the channel are not counted.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::types::{add_message, add_messages, MessageHub, MessageOrigin, MessageType};
+
+/// The sizes of the batches of `add_messages`.
+const BATCHES: [usize; 3] = [1, 10, 100];
+
+fn add_one(c: &mut Criterion) {
+    let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+    let mut group = c.benchmark_group("add_message");
+    group.bench_function("alone", |b| {
+        b.iter(|| add_message(&messages, "Client connected: 127.0.0.1:5000".to_string(), MessageType::Info, MessageOrigin::Network));
+        messages.flush();
+    });
+
+    // Un lecteur copie le journal sans arrêt pendant la mesure
+    let reading = Arc::new(AtomicBool::new(true));
+    let reader = {
+        let (messages, reading) = (messages.clone(), Arc::clone(&reading));
+        thread::spawn(move || {
+            while reading.load(Ordering::Relaxed) {
+                std::hint::black_box(messages.log().to_vec().len());
+            }
+        })
+    };
+    group.bench_function("read", |b| {
+        b.iter(|| add_message(&messages, "Client connected: 127.0.0.1:5000".to_string(), MessageType::Info, MessageOrigin::Network));
+        messages.flush();
+    });
+    reading.store(false, Ordering::Relaxed);
+    reader.join().unwrap();
+
+    group.bench_function("dropped", |b| {
+        b.iter(|| add_message(&messages, "Step took 2 ms".to_string(), MessageType::Debug, MessageOrigin::Game));
+    });
+    group.finish();
+}
+
+fn add_batch(c: &mut Criterion) {
+    let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+    let mut group = c.benchmark_group("add_messages");
+    for count in BATCHES {
+        let texts: Vec<String> = (0..count).map(|index| format!("bot{} entered the arena.", index)).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter_batched(|| texts.clone(), |texts| add_messages(&messages, texts, MessageType::GameEvent, MessageOrigin::Game), BatchSize::SmallInput);
+            messages.flush();
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, add_one, add_batch);
+criterion_main!(benches);
@@ -0,0 +1,203 @@
+//! Benchmarks of a simulation step under load.
+//!
+//! Run them all with `cargo bench --bench step`, or some of them with a filter on their name,
+//! e.g. `cargo bench --bench step -- collisions`. Every game is built by `StressScenario`
+//! from the same seed, so two runs measure the same work.
+//!
+//! Each group varies one load around a base game of 50 entities, 200 bullets and 25 obstacles:
+//! - `step/entities`, `step/bullets` and `step/obstacles` time a whole `GameLogic::step`;
+//! - `collisions/*` time the same steps, counting only the collision phase, that is
+//!   `handle_collisions` and the ramming, as measured by the step profiling;
+//! - `collision_lookup/scan` and `collision_lookup/index` time resolving the bodies of one contact
+//!   per bullet of the `step/bullets` games, the way `handle_collisions` did before and after it
+//!   indexed the bodies by handle: scanning the bullets then the entities twice per contact, against
+//!   building one map per step;
+//! - `update_ai/bots` times `GameLogic::update_ai` with bots only;
+//! - `sustained_fire/pooled` and `sustained_fire/unpooled` time one simulated second of the base
+//!   entities firing 500 bullets per second, with the bullet pool and without it, see
+//!   `PhysicsEngine::release_bullet`.
+//!
+//! Each iteration steps a freshly built game, so a measure is one step at the given load,
+//! or one second of steps for `sustained_fire`. The building is not timed. Criterion prints the estimated time of an iteration within its
+//! confidence interval, and the change from the last run saved in `target/criterion`:
+//! run the benchmarks before a change, then after it, and read the change line of each one.
+//! The HTML reports of `target/criterion/report` plot how the time grows with the load.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rapier2d::prelude::RigidBodyHandle;
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::game_logic::step_timings::StepPhase;
+use universal_rust_server_software::game_logic::stress::StressScenario;
+use universal_rust_server_software::game_logic::GameLogic;
+
+/// The base game the groups vary one load of: entities, bullets, obstacles.
+const BASE: (usize, usize, usize) = (50, 200, 25);
+
+/// The loads of each group.
+const ENTITIES: [usize; 3] = [10, 50, 200];
+const BULLETS: [usize; 3] = [0, 200, 1000];
+const OBSTACLES: [usize; 2] = [25, 100];
+
+/// Returns the scenarios of the groups, named after the load they vary.
+fn scenarios() -> Vec<(&'static str, usize, StressScenario)> {
+    let (entities, bullets, obstacles) = BASE;
+    let mut scenarios = Vec::new();
+    for count in ENTITIES {
+        scenarios.push(("entities", count, StressScenario::new(count, bullets, obstacles)));
+    }
+    for count in BULLETS {
+        scenarios.push(("bullets", count, StressScenario::new(entities, count, obstacles)));
+    }
+    for count in OBSTACLES {
+        scenarios.push(("obstacles", count, StressScenario::new(entities, bullets, count)));
+    }
+    scenarios
+}
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    for (load, count, scenario) in scenarios() {
+        group.bench_with_input(BenchmarkId::new(load, count), &scenario, |b, scenario| {
+            b.iter_batched(|| scenario.build(), |mut logic| logic.step(), BatchSize::LargeInput);
+        });
+    }
+    group.finish();
+}
+
+fn collisions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collisions");
+    for (load, count, scenario) in scenarios() {
+        group.bench_with_input(BenchmarkId::new(load, count), &scenario, |b, scenario| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let mut logic = scenario.build();
+                    logic.profiling_enabled = true;
+                    logic.step();
+                    total += logic.step_timings.total(StepPhase::Collisions);
+                }
+                total
+            });
+        });
+    }
+    group.finish();
+}
+
+/// What a body of a contact turned out to be.
+#[derive(Clone, Copy)]
+enum Body {
+    Bullet(usize),
+    Entity(usize),
+}
+
+/// Returns the index of a body in its list, as the handling of the contact would read it.
+fn index_of(body: Option<Body>) -> Option<usize> {
+    match body? {
+        Body::Bullet(index) | Body::Entity(index) => Some(index),
+    }
+}
+
+/// Resolves a body as `handle_collisions` did before the index: the bullets are scanned, then the
+/// entities, and the entities once more to find the shooter of a bullet.
+fn scan(logic: &GameLogic, handle: RigidBodyHandle) -> Option<Body> {
+    if let Some(index) = logic.bullets.iter().position(|bullet| bullet.handle == handle) {
+        let shooter = logic.bullets[index].shooter;
+        black_box(logic.entities.iter().position(|entity| entity.handle == shooter));
+        return Some(Body::Bullet(index));
+    }
+    logic.entities.iter().position(|entity| entity.handle == handle).map(Body::Entity)
+}
+
+fn collision_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision_lookup");
+    let (entities, _, obstacles) = BASE;
+    for count in BULLETS {
+        let logic = StressScenario::new(entities, count, obstacles).build();
+        // Chaque balle touche une entité : un contact par balle, comme un pas chargé
+        let contacts: Vec<(RigidBodyHandle, RigidBodyHandle)> = logic.bullets.iter().enumerate()
+            .map(|(index, bullet)| (bullet.handle, logic.entities[index % logic.entities.len()].handle))
+            .collect();
+        group.bench_with_input(BenchmarkId::new("scan", count), &contacts, |b, contacts| {
+            b.iter(|| {
+                for (first, second) in contacts {
+                    black_box((index_of(scan(&logic, *first)), index_of(scan(&logic, *second))));
+                }
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("index", count), &contacts, |b, contacts| {
+            b.iter(|| {
+                let mut bodies = HashMap::with_capacity(logic.bullets.len() + logic.entities.len());
+                bodies.extend(logic.bullets.iter().enumerate().map(|(index, bullet)| (bullet.handle, Body::Bullet(index))));
+                bodies.extend(logic.entities.iter().enumerate().map(|(index, entity)| (entity.handle, Body::Entity(index))));
+                for (first, second) in contacts {
+                    let first = bodies.get(first).copied();
+                    if let Some(Body::Bullet(index)) = first {
+                        black_box(bodies.get(&logic.bullets[index].shooter));
+                    }
+                    black_box((index_of(first), index_of(bodies.get(second).copied())));
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn update_ai(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_ai");
+    for count in ENTITIES {
+        let scenario = StressScenario { bots: count, ..StressScenario::new(0, 0, BASE.2) };
+        group.bench_with_input(BenchmarkId::new("bots", count), &scenario, |b, scenario| {
+            b.iter_batched(|| scenario.build(), |mut logic| logic.update_ai(), BatchSize::LargeInput);
+        });
+    }
+    group.finish();
+}
+
+/// The shots per simulated second of `sustained_fire`, spread over the base entities.
+const SHOTS_PER_SEC: usize = 500;
+
+/// Builds the base game with every entity firing nonstop, warmed up until the bullets expire
+/// as fast as they are fired, so the pool is as full as it gets.
+///
+/// # Parameters
+/// - `pool_capacity`: The most bodies the bullet pool keeps, 0 to remove every gone bullet.
+fn firing_game(pool_capacity: usize) -> GameLogic {
+    let (entities, _, obstacles) = BASE;
+    let mut logic = StressScenario::new(entities, 0, obstacles).build();
+    logic.physics_engine.set_bullet_pool_capacity(pool_capacity);
+    let config = &mut logic.game_config;
+    config.bullet_lifetime_ms = 1000;
+    config.max_bullets_per_entity = 0;
+    config.shot_energy_cost = 0.0;
+    let cooldown = Duration::from_secs(1) * entities as u32 / SHOTS_PER_SEC as u32;
+    for entity in &mut logic.entities {
+        entity.fire_cooldown = cooldown;
+        entity.gun_mut().trigger = 1.0;
+    }
+    for _ in 0..2 * AppDefines::TARGET_FPS_2D_PHYSICS as usize {
+        logic.step();
+    }
+    logic
+}
+
+fn sustained_fire(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sustained_fire");
+    let steps = AppDefines::TARGET_FPS_2D_PHYSICS as usize;
+    for (name, capacity) in [("pooled", AppDefines::BULLET_POOL_CAPACITY), ("unpooled", 0)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(|| firing_game(capacity), |mut logic| {
+                for _ in 0..steps {
+                    logic.step();
+                }
+            }, BatchSize::LargeInput);
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step, collisions, collision_lookup, update_ai, sustained_fire);
+criterion_main!(benches);
@@ -0,0 +1,194 @@
+//! Benchmark of the jitter of the ticks while clients play, with the game logic locked by the
+//! client handlers and with the commands sent to the thread stepping the game.
+//!
+//! Run it with `cargo bench --bench tick_jitter`. The game is built by `StressScenario` from the
+//! same seed, with one entity per client, and `CLIENTS` threads stand for the client handlers:
+//! every `QUERY_INTERVAL_MS` each sets a motor of its entity, asks for its status the way a `STATUS`
+//! command is answered, and every `LIDAR_EVERY` rounds casts a LIDAR scan.
+//! - `locked` does all of it while holding the game logic, as the handlers did before `GameCommands`;
+//! - `commands` sends the actuator and the scan to the stepping thread, which runs them between two
+//!   steps as `GameLoop` does, and reads the status from `WorldSlot::latest`, as the handlers do now.
+//!
+//! A step is timed from the moment it was due to the moment it publishes the world snapshot, so the
+//! time also counts the wait behind the clients, for the lock or for the commands run before it.
+//! The spread of these times is the jitter of the ticks: the benchmark prints their mean, their
+//! standard deviation, their median, their 99th percentile and the longest one, in milliseconds,
+//! then the same for the part of the step spent waiting for the lock (`<mode>/lock`).
+//!
+//! The lock wait is what the commands remove. The rest of the jitter is the scheduling of the
+//! stepping thread among the clients, which only more cores reduce: on a single core, the threads
+//! of the clients delay the step as much as the lock did.
+
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::game_logic::command::{GameCommand, GameCommands};
+use universal_rust_server_software::game_logic::stress::StressScenario;
+
+/// The number of clients playing.
+const CLIENTS: usize = 100;
+/// The time between two rounds of commands of a client, in milliseconds.
+const QUERY_INTERVAL_MS: u64 = 5;
+/// A client casts a LIDAR scan every this many rounds.
+const LIDAR_EVERY: usize = 10;
+/// The number of steps timed per mode.
+const STEPS: usize = 600;
+
+/// How the clients reach the game.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Locked,
+    Commands,
+}
+
+/// Writes the status of an entity, as a `STATUS` reply gives it.
+fn status_reply(health: i32, score: i32, x: f32, y: f32) -> String {
+    format!("STATUS=ALIVE={}={}={:.1}={:.1}", health, score, x, y)
+}
+
+/// Runs `STEPS` steps at the tick rate of the game while the clients play.
+///
+/// # Returns
+/// The time of each step and the time it waited for the lock, in milliseconds.
+fn run(mode: Mode) -> (Vec<f64>, Vec<f64>) {
+    let (entities, bullets, obstacles) = (CLIENTS, 200, 25);
+    let game_logic = Arc::new(Mutex::new(StressScenario::new(entities, bullets, obstacles).build()));
+    game_logic.lock().unwrap().publish_world();
+    let commands = GameCommands::new(Arc::clone(&game_logic));
+    // Le canal est ouvert avant l'arrivée des clients, comme par `GameLoop::spawn`
+    let receiver = matches!(mode, Mode::Commands).then(|| commands.open());
+    let stopping = Arc::new(AtomicBool::new(false));
+
+    let ids: Vec<u32> = game_logic.lock().unwrap().entities.iter().map(|entity| entity.id).collect();
+    let clients: Vec<_> = ids.into_iter().map(|id| {
+        let game_logic = Arc::clone(&game_logic);
+        let commands = commands.clone();
+        let stopping = Arc::clone(&stopping);
+        thread::spawn(move || {
+            let mut round = 0;
+            while !stopping.load(Ordering::Relaxed) {
+                round += 1;
+                let motor = (round % 10) as f32 / 10.0;
+                let scan = round % LIDAR_EVERY == 0;
+                let reply = match mode {
+                    Mode::Locked => {
+                        let mut logic = game_logic.lock().unwrap();
+                        if let Some(entity) = logic.get_entity_mut(id) {
+                            let _ = entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, motor, None);
+                        }
+                        if scan {
+                            black_box(logic.lidar_scan(id, AppDefines::LIDAR_MAX_RAYS, AppDefines::LIDAR_MAX_RANGE));
+                        }
+                        logic.entities.iter().find(|e| e.id == id).map(|e| status_reply(e.health, e.score, e.x, e.y))
+                    }
+                    Mode::Commands => {
+                        let outcome = commands.ask(|reply| GameCommand::SetActuator {
+                            entity_id: id,
+                            code: AppDefines::ACTUATOR_MOTOR_LEFT,
+                            turret: 0,
+                            value: motor,
+                            seq: None,
+                            reply: Some(reply),
+                        });
+                        let _ = black_box(outcome);
+                        if scan {
+                            let (range, rays) = (AppDefines::LIDAR_MAX_RANGE, AppDefines::LIDAR_MAX_RAYS);
+                            black_box(commands.ask(|reply| GameCommand::Lidar { entity_id: id, rays, range, reply }));
+                        }
+                        let snapshot = commands.world().latest();
+                        snapshot.entity(id).map(|e| status_reply(e.health, e.score, e.x, e.y))
+                    }
+                };
+                black_box(reply);
+                thread::sleep(Duration::from_millis(QUERY_INTERVAL_MS));
+            }
+        })
+    }).collect();
+
+    let tick = Duration::from_secs_f32(1.0 / AppDefines::TARGET_FPS_2D_PHYSICS);
+    let mut times = Vec::with_capacity(STEPS);
+    let mut lock_waits = Vec::with_capacity(STEPS);
+    let mut next = Instant::now();
+    for _ in 0..STEPS {
+        let asked = Instant::now();
+        let mut logic = game_logic.lock().unwrap();
+        lock_waits.push(asked.elapsed().as_secs_f64() * 1000.0);
+        // Les commandes arrivées depuis passent avant le pas, sous le même verrou
+        if let Some(receiver) = &receiver {
+            let mut changed = false;
+            for command in receiver.try_iter() {
+                changed |= command.apply(&mut logic);
+            }
+            if changed {
+                logic.publish_world();
+            }
+        }
+        logic.update_ai();
+        logic.step();
+        logic.publish_world();
+        drop(logic);
+        times.push(next.elapsed().as_secs_f64() * 1000.0);
+        next += tick;
+        // Entre deux pas, la boucle répond aux commandes au fil de leur arrivée
+        while let Some(receiver) = &receiver {
+            let Ok(command) = receiver.recv_timeout(next.saturating_duration_since(Instant::now())) else { break };
+            let mut logic = game_logic.lock().unwrap();
+            let mut changed = command.apply(&mut logic);
+            // Pas au-delà de l'échéance : le reste passe avant le pas
+            while Instant::now() < next {
+                let Ok(command) = receiver.try_recv() else { break };
+                changed |= command.apply(&mut logic);
+            }
+            if changed {
+                logic.publish_world();
+            }
+        }
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+    }
+
+    stopping.store(true, Ordering::Relaxed);
+    // Les clients qui attendent encore une réponse l'obtiennent sous le verrou
+    commands.close();
+    if let Some(receiver) = &receiver {
+        let mut logic = game_logic.lock().unwrap();
+        for command in receiver.try_iter() {
+            command.apply(&mut logic);
+        }
+    }
+    for client in clients {
+        client.join().unwrap();
+    }
+    (times, lock_waits)
+}
+
+/// Prints the spread of the times of a mode.
+fn report(name: &str, mut times: Vec<f64>) {
+    times.sort_by(f64::total_cmp);
+    let mean = times.iter().sum::<f64>() / times.len() as f64;
+    let deviation = (times.iter().map(|time| (time - mean).powi(2)).sum::<f64>() / times.len() as f64).sqrt();
+    let percentile = |share: f64| times[((times.len() - 1) as f64 * share).round() as usize];
+    println!(
+        "tick_jitter/{:<13} mean {:>7.3} ms  std dev {:>7.3} ms  p50 {:>7.3} ms  p99 {:>7.3} ms  max {:>7.3} ms",
+        name,
+        mean,
+        deviation,
+        percentile(0.5),
+        percentile(0.99),
+        times[times.len() - 1],
+    );
+}
+
+fn main() {
+    // `cargo test --benches` lance aussi ce binaire : il ne mesure rien dans ce cas
+    if std::env::args().any(|arg| arg == "--bench") {
+        for (name, mode) in [("locked", Mode::Locked), ("commands", Mode::Commands)] {
+            let (times, lock_waits) = run(mode);
+            report(name, times);
+            report(&format!("{}/lock", name), lock_waits);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Benchmarks of the layout of the movement trails for drawing.
+//!
+//! Run them with `cargo bench --bench trails`. The game UI lays the trails out every frame,
+//! see `TrailStore::assemble`; a store full of trails must not make the frame time spike.
+//!
+//! - `assemble/reused` refills the same buffers each iteration, as the game UI does;
+//! - `assemble/fresh` starts from empty buffers each time, for comparison.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use universal_rust_server_software::game_logic::replay::EntityFrame;
+use universal_rust_server_software::game_logic::trail_store::{TrailPolylines, TrailSettings, TrailStore};
+
+/// The numbers of entities of the stores, each with a full trail.
+const ENTITIES: [usize; 3] = [10, 50, 200];
+
+/// Builds a store whose entities walk in circles until their trails are full, and the entities at their last position.
+fn full_store(entities: usize) -> (TrailStore, Vec<EntityFrame>) {
+    let settings = TrailSettings { interval_ticks: 1, ..TrailSettings::default() };
+    let mut store = TrailStore::new(settings);
+    let position = |id: usize, tick: u64| {
+        let angle = tick as f32 * 0.05 + id as f32;
+        (500.0 + angle.cos() * 100.0, 500.0 + angle.sin() * 100.0)
+    };
+    for tick in 0..settings.max_samples as u64 {
+        store.record(tick, (0..entities).map(|id| (id as u32, position(id, tick), [200, 120, 40])));
+    }
+    let heads = (0..entities).map(|id| {
+        let (x, y) = position(id, settings.max_samples as u64);
+        EntityFrame { id: id as u32, x, y, ..EntityFrame::default() }
+    }).collect();
+    (store, heads)
+}
+
+fn assemble(c: &mut Criterion) {
+    let mut group = c.benchmark_group("assemble");
+    for count in ENTITIES {
+        let (store, heads) = full_store(count);
+        group.bench_with_input(BenchmarkId::new("reused", count), &count, |b, _| {
+            let mut polylines = TrailPolylines::default();
+            b.iter(|| {
+                store.assemble(&heads, &mut polylines);
+                polylines.points.len()
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("fresh", count), &count, |b, _| {
+            b.iter(|| {
+                let mut polylines = TrailPolylines::default();
+                store.assemble(&heads, &mut polylines);
+                polylines.points.len()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, assemble);
+criterion_main!(benches);
@@ -1,3 +1,5 @@
+use crate::types::MessageType;
+
 /// Struct containing constant definitions for the application.
 pub struct AppDefines;
 
@@ -5,13 +7,53 @@ impl AppDefines {
     /// ENVIRONMENT
     /// The target frames per second for 2D physics calculations.
     /// 60 = fine, 30 = cheaper.
-    pub const TARGET_FPS_2D_PHYSICS: f32 = 30.0;
-    /// The width of the arena.
-    pub const ARENA_WIDTH: f32 = 1200.0;
-    /// The height of the arena.
-    pub const ARENA_HEIGHT: f32 = 1000.0;
+    pub const TARGET_FPS_2D_PHYSICS: f32 = 60.0;
+    /// The maximum number of steps the game loop runs at once to catch up after a stall,
+    /// multiplied by the time scale.
+    pub const MAX_CATCH_UP_STEPS: u32 = 5;
+    /// The slowest simulation speed, relative to real time.
+    pub const MIN_TIME_SCALE: f32 = 0.25;
+    /// The fastest simulation speed, relative to real time.
+    pub const MAX_TIME_SCALE: f32 = 10.0;
+    /// The number of seconds in a row the game loop must fall behind a time scale above 1
+    /// before lowering it to the speed it actually reaches.
+    pub const TIME_SCALE_CLAMP_SECS: u32 = 3;
+    /// The width of the arena, unless set with `--arena` or in the server options.
+    pub const DEFAULT_ARENA_WIDTH: f32 = 1200.0;
+    /// The height of the arena, unless set with `--arena` or in the server options.
+    pub const DEFAULT_ARENA_HEIGHT: f32 = 1000.0;
+    /// The shortest side an arena can have.
+    pub const ARENA_MIN_SIZE: f32 = 100.0;
+    /// The speed of a ghost camera with a motor at full throttle, in units per second.
+    pub const GHOST_CAMERA_SPEED: f32 = 400.0;
+    /// The default thickness of the walls around the arena, built outside the playable area.
+    pub const DEFAULT_WALL_THICKNESS: f32 = 20.0;
+    /// The number of bullet hits an entity remembers for `DAMAGE`.
+    pub const RECENT_HITS: usize = 4;
+    /// Half the side of the square body of an entity.
+    pub const ENTITY_HALF_SIZE: f32 = 10.0;
     /// The probability of an obstacle appearing in the arena.
     pub const OBSTACLE_PROBABILITY: f64 = 0.3;
+    /// The number of obstacles of a generated map.
+    pub const OBSTACLE_COUNT: usize = 25;
+    /// The minimum gap between the bounding circles of two obstacles of a generated map.
+    pub const OBSTACLE_MIN_GAP: f32 = 12.0;
+    /// The minimum distance between an entity placed on a new map and the edge of an obstacle.
+    pub const ENTITY_OBSTACLE_CLEARANCE: f32 = 20.0;
+    /// Half the side of a square obstacle.
+    pub const OBSTACLE_HALF_SIZE: f32 = 10.0;
+    /// The smallest radius of a disc obstacle of a generated map.
+    pub const OBSTACLE_CIRCLE_MIN_RADIUS: f32 = 8.0;
+    /// The largest radius of a disc obstacle of a generated map.
+    pub const OBSTACLE_CIRCLE_MAX_RADIUS: f32 = 20.0;
+    /// The shortest half length of a wall of a generated map.
+    pub const OBSTACLE_WALL_MIN_HALF_LENGTH: f32 = 40.0;
+    /// The longest half length of a wall of a generated map.
+    pub const OBSTACLE_WALL_MAX_HALF_LENGTH: f32 = 100.0;
+    /// Half the thickness of a wall.
+    pub const OBSTACLE_WALL_HALF_THICKNESS: f32 = 5.0;
+    /// The name of a generated map once edited, kept for the next rounds like a map loaded from a file.
+    pub const EDITED_MAP_NAME: &'static str = "edited";
 
 
     /// BOT Gameplay
@@ -20,20 +62,420 @@ impl AppDefines {
     pub const GAME_MODES: [&'static str; 1] = ["FreeForAll"];
     /// The rate of fire for bots in ticks.
     pub const BOT_RATE_OF_FIRE: i32 = 750;
+    /// The default delay between two shots of an entity, in milliseconds.
+    pub const DEFAULT_FIRE_COOLDOWN_MS: u64 = 750;
+    /// The default health of a new entity.
+    pub const DEFAULT_STARTING_HEALTH: i32 = 1;
+    /// The default health removed by a bullet hit.
+    pub const DEFAULT_BULLET_DAMAGE: i32 = 1;
+    /// The default speed of the bullets.
+    pub const DEFAULT_BULLET_SPEED: f32 = 500.0;
+    /// The default radius of the bullets.
+    pub const DEFAULT_BULLET_RADIUS: f32 = 5.0;
+    /// The default contact force between two entities above which the physics engine reports it and they damage each other.
+    pub const DEFAULT_CONTACT_FORCE_THRESHOLD: f32 = 500_000.0;
+    /// The default health removed from two colliding entities per unit of relative speed.
+    pub const DEFAULT_RAMMING_DAMAGE_PER_SPEED: f32 = 0.02;
+    /// How long two entities must stay apart before colliding again counts as a new impact, in simulated milliseconds.
+    pub const RAMMING_COOLDOWN_MS: u64 = 1000;
+    /// The default armor of a new entity, in every game mode.
+    pub const DEFAULT_STARTING_ARMOR: f32 = 0.0;
+    /// The largest share of the bullet damage the armor of an entity shrugs off.
+    pub const ARMOR_MAX: f32 = 0.75;
+    /// The default largest deviation of a bullet from the aim of a still shooter, in radians.
+    pub const DEFAULT_BASE_SPREAD: f32 = 0.0;
+    /// The default deviation added per unit of linear speed of the shooter, in radians.
+    pub const DEFAULT_SPREAD_PER_SPEED: f32 = 0.0;
+    /// The default deviation added per radian per second of angular speed of the shooter, in radians.
+    pub const DEFAULT_SPREAD_PER_ANGULAR_SPEED: f32 = 0.0;
+    /// The default health removed by a mine at the center of its blast, less further away.
+    pub const DEFAULT_MINE_DAMAGE: i32 = 2;
+    /// The default radius of the blast of a mine.
+    pub const DEFAULT_MINE_BLAST_RADIUS: f32 = 80.0;
+    /// The default maximum number of mines an entity can have in the arena.
+    pub const DEFAULT_MAX_MINES: u32 = 3;
+    /// The radius of the sensor of a mine.
+    pub const MINE_RADIUS: f32 = 8.0;
+    /// The delay before a mine can explode, in simulated milliseconds.
+    pub const MINE_ARMING_MS: u64 = 1000;
+    /// The default time during which a new or repositioned entity can neither be hurt nor fire, in simulated milliseconds.
+    pub const DEFAULT_SPAWN_PROTECTION_MS: u64 = 2000;
+    /// The default standard deviation of the aim of the bots, in degrees.
+    pub const DEFAULT_BOT_AIM_ERROR_DEG: f32 = 5.0;
+    /// The default distance under which the bots fire at their target.
+    pub const DEFAULT_BOT_FIRE_RANGE: f32 = 600.0;
+    /// The default length of the rays a bot casts ahead to avoid walls and obstacles.
+    pub const DEFAULT_BOT_RAY_LENGTH: f32 = 80.0;
+    /// The default distance to a wall or an obstacle under which a bot turns fully away from it.
+    pub const DEFAULT_BOT_AVOIDANCE_RADIUS: f32 = 30.0;
+    /// The angle between the ray a bot casts straight ahead and each of its two side rays, in radians.
+    pub const BOT_FEELER_ANGLE: f32 = 0.5;
+    /// How long a bot may move less than `BOT_STUCK_DISTANCE` before it makes a detour, in simulated milliseconds.
+    pub const BOT_STUCK_MS: u64 = 1000;
+    /// The distance a bot must travel to count as making progress.
+    pub const BOT_STUCK_DISTANCE: f32 = 5.0;
+    /// The default delay between the start of a round and the start of the zone shrinking, in simulated seconds.
+    pub const DEFAULT_ZONE_DELAY_SECS: f32 = 60.0;
+    /// The default time the zone takes to shrink to its final radius, in simulated seconds.
+    pub const DEFAULT_ZONE_SHRINK_SECS: f32 = 120.0;
+    /// The default radius the zone shrinks to.
+    pub const DEFAULT_ZONE_FINAL_RADIUS: f32 = 100.0;
+    /// The default health removed per simulated second from an entity outside the zone.
+    pub const DEFAULT_ZONE_DAMAGE_PER_SEC: f32 = 1.0;
+    /// The default speed at which the guns turn, in radians per second.
+    pub const DEFAULT_GUN_TRAVERSE_RATE: f32 = std::f32::consts::PI;
+    /// The default time a bullet flies before it disappears, in simulated milliseconds.
+    pub const DEFAULT_BULLET_LIFETIME_MS: u64 = 2000;
+    /// The default maximum number of bullets of an entity flying at the same time.
+    pub const DEFAULT_MAX_BULLETS_PER_ENTITY: u32 = 10;
+    /// The default number of times a bullet bounces off walls and obstacles.
+    pub const DEFAULT_BULLET_BOUNCES: u8 = 0;
+    /// The default time a new bullet passes through its shooter, in simulated milliseconds.
+    pub const DEFAULT_SHOOTER_GRACE_MS: u64 = 250;
+    /// The default time a new bullet cannot damage its shooter, in simulated milliseconds.
+    pub const DEFAULT_SELF_HIT_GRACE_MS: u64 = 150;
+    /// The default fire energy of a full entity.
+    pub const DEFAULT_FIRE_ENERGY_MAX: f32 = 100.0;
+    /// The default fire energy regenerated per simulated second.
+    pub const DEFAULT_FIRE_ENERGY_REGEN_PER_SEC: f32 = 20.0;
+    /// The default fire energy a shot costs, none: the entities fire as much as their cooldown allows.
+    pub const DEFAULT_SHOT_ENERGY_COST: f32 = 0.0;
+    /// The fire energy drained per simulated second by both motors at full output, while the motors drain it.
+    pub const MOTOR_ENERGY_DRAIN_PER_SEC: f32 = 10.0;
+    /// How much each bounce extends the lifetime of a bullet, in simulated milliseconds.
+    pub const BULLET_BOUNCE_LIFETIME_MS: u64 = 500;
+    /// The number of bodies of gone bullets kept disabled for the next shots, see `PhysicsEngine::release_bullet`.
+    pub const BULLET_POOL_CAPACITY: usize = 512;
+    /// The default distance over which the damage of a bullet falls to its minimum, 0 for no falloff.
+    pub const DEFAULT_DAMAGE_FALLOFF_RANGE: f32 = 0.0;
+    /// The default share of the damage a bullet keeps past the falloff range.
+    pub const DEFAULT_DAMAGE_FALLOFF_MIN: f32 = 0.25;
+    /// The factor applied to the speed of a sniper bullet.
+    pub const SNIPER_SPEED_FACTOR: f32 = 2.0;
+    /// The factor applied to the fire energy a sniper shot costs.
+    pub const SNIPER_ENERGY_FACTOR: f32 = 3.0;
+    /// The factor applied to the fire cooldown of an entity firing sniper bullets.
+    pub const SNIPER_COOLDOWN_FACTOR: f32 = 2.0;
+    /// The number of bullets of a scatter shot.
+    pub const SCATTER_PELLETS: usize = 3;
+    /// The angle between two neighbouring bullets of a scatter shot, in radians.
+    pub const SCATTER_FAN_ANGLE: f32 = 0.15;
+    /// The share of the damage of a standard bullet each bullet of a scatter shot deals.
+    pub const SCATTER_DAMAGE_FACTOR: f32 = 0.5;
+    /// The file the game UI saves the state of the match to by default.
+    pub const DEFAULT_SNAPSHOT_FILE: &'static str = "snapshot.json";
+    /// The map file the game UI loads and saves by default.
+    pub const DEFAULT_MAP_FILE: &'static str = "maps/example.json";
+    /// The file the game UI saves replays to by default.
+    pub const DEFAULT_REPLAY_FILE: &'static str = "replay.jsonl";
+    /// The default points earned by the shooter for each hit.
+    pub const DEFAULT_POINTS_PER_HIT: i32 = 1;
+    /// The default points earned by the shooter for destroying an entity, on top of the hit.
+    pub const DEFAULT_POINTS_PER_KILL: i32 = 0;
+    /// The default points lost by an entity when it is destroyed.
+    pub const DEFAULT_DEATH_PENALTY: i32 = 0;
+    /// The default points lost by a shooter for hitting a teammate.
+    pub const DEFAULT_FRIENDLY_HIT_PENALTY: i32 = 1;
+    /// The default points lost by an entity for hitting itself, with its own bullet or its own mine.
+    pub const DEFAULT_SELF_HIT_PENALTY: i32 = 1;
+    /// The default time a side alone in a control point takes to capture it, in simulated seconds.
+    pub const DEFAULT_CONTROL_CAPTURE_SECS: f32 = 3.0;
+    /// The default points earned per simulated second inside a control point held by the side of the entity.
+    pub const DEFAULT_CONTROL_POINTS_PER_SEC: f32 = 1.0;
+    /// The default distance an entity must move away from where it stopped to stop camping.
+    pub const DEFAULT_CAMPING_RADIUS: f32 = 40.0;
+    /// The default time an entity stays in the same spot before it camps, in simulated seconds.
+    pub const DEFAULT_CAMPING_SECS: f32 = 15.0;
+    /// The default points a camping entity loses per simulated second.
+    pub const DEFAULT_CAMPING_POINTS_PER_SEC: f32 = 0.5;
+    /// How often the position of a camping entity is told again while the reveal rule is on, in simulated seconds.
+    pub const CAMPING_REVEAL_SECS: f32 = 5.0;
     /// The penalty time for infractions in ticks.
     pub const PENALTY_TIME: i64 = 1000;
+    /// How often the headless server prints its new messages, in milliseconds.
+    pub const HEADLESS_PRINT_MS: u64 = 100;
+    /// The settings file read at startup when `--config` is not given, if it exists.
+    pub const CONFIG_FILE: &'static str = "starnet.toml";
+    /// The address the server listens on for TCP clients, unless `--bind` says otherwise.
+    pub const SERVER_ADDRESS: &'static str = "127.0.0.1";
+    /// The port the server listens on for TCP clients.
+    pub const SERVER_PORT: u16 = 6969;
+    /// How often the listeners check for new clients and for a change of port, in milliseconds.
+    pub const ACCEPT_POLL_MS: u64 = 50;
     /// The delay before a connection times out in seconds.
     pub const CONNECTION_TIMEOUT_DELAY: i32 = 200;
+    /// The default number of worker threads handling the clients.
+    pub const CLIENT_WORKERS: usize = 4;
+    /// How long a worker whose clients sent nothing sleeps before polling them again, in milliseconds.
+    pub const CLIENT_POLL_MS: u64 = 2;
+    /// The maximum number of reads from a client socket each time its worker polls it.
+    pub const CLIENT_READS_PER_POLL: usize = 8;
+    /// The version of the command protocol, as `<major>.<minor>`.
+    /// Clients with a different major version are refused.
+    pub const PROTOCOL_VERSION: &'static str = "1.0";
+    /// The server name announced in the handshake.
+    pub const SERVER_NAME: &'static str = "URSS";
+    /// Whether clients may skip the handshake, for bots written before it existed.
+    pub const ALLOW_LEGACY_CLIENTS: bool = false;
+    /// How long the entity of a dropped client is kept alive for it to reconnect, in seconds.
+    pub const RECONNECT_GRACE_PERIOD_SECS: u64 = 30;
+    /// How long a write to a client may block before the client is considered too slow, in milliseconds.
+    pub const SOCKET_WRITE_TIMEOUT_MS: u64 = 200;
+    /// How long a stopping server waits for its threads to end, in milliseconds.
+    pub const SHUTDOWN_JOIN_TIMEOUT_MS: u64 = 2000;
+    /// How long a `BotClient` waits for a reply before its command fails, in milliseconds.
+    pub const BOT_CLIENT_READ_TIMEOUT_MS: u64 = 5000;
+    /// The password unlocking the admin commands (empty = admin commands disabled).
+    pub const ADMIN_PASSWORD: &'static str = "";
+    /// The maximum number of connected clients, TCP and WebSocket together (0 = unlimited).
+    pub const MAX_CLIENTS: usize = 64;
+    /// Whether the lobby starts locked, refusing new entities to the clients connecting mid-match.
+    pub const LOBBY_LOCKED: bool = false;
+    /// The port of the WebSocket listener, for browser bots (0 = disabled).
+    pub const WEB_SOCKET_PORT: u16 = 6970;
+    /// The address of the admin control port, loopback so that only the host of the server reaches it.
+    pub const ADMIN_CONTROL_ADDRESS: &'static str = "127.0.0.1";
+    /// The port of the admin control listener (0 = disabled).
+    pub const ADMIN_CONTROL_PORT: u16 = 6971;
+    /// The port of the read-only HTTP endpoint for dashboards (0 = disabled).
+    pub const HTTP_PORT: u16 = 0;
+    /// The largest HTTP request head read, in bytes, past which the request is refused.
+    pub const HTTP_MAX_REQUEST_BYTES: usize = 8192;
+    /// How long the HTTP endpoint waits for a request or for its response to be written, in milliseconds.
+    pub const HTTP_TIMEOUT_MS: u64 = 2000;
+    /// Whether the HTTP endpoint also serves the Prometheus metrics on `/metrics`.
+    pub const HTTP_PROMETHEUS_METRICS: bool = true;
+    /// Whether actuator commands are also accepted as UDP datagrams, on the port of the server.
+    pub const UDP_ACTUATORS_ENABLED: bool = true;
+    /// The maximum number of UDP datagrams processed per second and per source address.
+    pub const UDP_MAX_DATAGRAMS_PER_SEC: u32 = 120;
+    /// How long the traffic statistics of a disconnected client stay in the server UI, in seconds.
+    pub const CLIENT_STATS_GRACE_PERIOD_SECS: u64 = 60;
+    /// How long a disconnected client stays, greyed out, in the clients tab of the server UI, in seconds.
+    pub const CLIENT_LINGER_SECS: u64 = 30;
+    /// The file the cumulative leaderboard is kept in across server restarts.
+    pub const LEADERBOARD_FILE: &'static str = "leaderboard.json";
+    /// The directory the report of each round is written to, as JSON and CSV (empty = reports kept in memory only).
+    pub const REPORT_DIR: &'static str = "";
+    /// The file the server messages are appended to (empty = no log file).
+    pub const LOG_FILE: &'static str = "server.log";
+    /// The size past which the log file is rotated, in bytes.
+    pub const LOG_MAX_BYTES: u64 = 1024 * 1024;
+    /// The number of rotated log files kept, `server.log.1` being the most recent.
+    pub const LOG_KEPT_FILES: usize = 5;
+    /// The default number of messages the server keeps in memory for the server UI, the oldest dropped past it.
+    pub const MESSAGE_LOG_CAPACITY: usize = 10_000;
+    /// The least severe messages the server keeps, the others are dropped as they are added.
+    pub const LOG_VERBOSITY: MessageType = MessageType::Info;
+    /// The file the server UI exports the messages to, unless another one is chosen.
+    pub const DEFAULT_LOG_EXPORT_FILE: &'static str = "messages_export.log";
+    /// How often the log file is flushed while messages keep coming, in milliseconds.
+    pub const LOG_FLUSH_MS: u64 = 1000;
+    /// Whether the TCP and WebSocket clients must speak TLS, with the certificate and key below.
+    pub const ENABLE_TLS: bool = false;
+    /// The PEM file of the TLS certificate chain, server certificate first.
+    pub const TLS_CERT_FILE: &'static str = "server.crt";
+    /// The PEM file of the private key of the TLS certificate.
+    pub const TLS_KEY_FILE: &'static str = "server.key";
+    /// How long a client may take to complete the TLS handshake, in milliseconds.
+    pub const TLS_HANDSHAKE_TIMEOUT_MS: u64 = 5000;
+    /// The number of players returned by `LEADERBOARD` when the client gives none.
+    pub const LEADERBOARD_DEFAULT_TOP: usize = 10;
+    /// The maximum number of players returned by one `LEADERBOARD` command.
+    pub const LEADERBOARD_MAX_TOP: usize = 100;
+    /// The largest number of characters of an entity name.
+    pub const NAME_MAX_LEN: usize = 24;
+    /// The lowest world state push rate a client can subscribe to, in pushes per second.
+    pub const SUBSCRIBE_MIN_HZ: u32 = 1;
+    /// The highest world state push rate a client can subscribe to, in pushes per second.
+    pub const SUBSCRIBE_MAX_HZ: u32 = 30;
+    /// The number of frames of a delta subscription between two keyframes.
+    pub const DELTA_KEYFRAME_INTERVAL: u32 = 40;
+    /// The distance an entity or a bullet moves before a delta frame sends it again.
+    pub const DELTA_POSITION_EPSILON: f32 = 0.5;
+    /// The angle an entity turns before a delta frame sends it again, in radians.
+    pub const DELTA_ORIENTATION_EPSILON: f64 = 0.01;
+    /// The number of rays cast by `LIDAR` when the client gives none.
+    pub const LIDAR_DEFAULT_RAYS: usize = 16;
+    /// The maximum number of rays cast by one `LIDAR` command.
+    pub const LIDAR_MAX_RAYS: usize = 64;
+    /// The range of the lidar rays. A ray hitting nothing reports this distance.
+    pub const LIDAR_MAX_RANGE: f32 = 1000.0;
+    /// How close to the point looked at a wall may stand without blocking the line of sight.
+    pub const LINE_OF_SIGHT_TOLERANCE: f32 = 0.01;
+    /// The maximum length of a command line in bytes. Clients sending longer lines are disconnected.
+    pub const MAX_LINE_LENGTH: usize = 64 * 1024;
     /// The duration messages are displayed in ticks.
     pub const MESSAGE_DURATION: i32 = 1000;
     /// The maximum length of a message in characters.
     pub const MESSAGE_LENGTH: i32 = 40;
     /// The score limit for the game.
     pub const SCORE_LIMIT: i32 = -1;
+    /// The maximum number of actuator commands and queries an entity may issue per simulation step.
+    /// 0 = unlimited.
+    pub const COMMAND_BUDGET_PER_STEP: u32 = 0;
+    /// The fraction of its score an entity loses every simulated minute when score decay is enabled.
+    pub const SCORE_DECAY_PER_MINUTE: f32 = 0.05;
+    /// The fraction of the score gap awarded when killing a higher-scored opponent, if enabled.
+    pub const COMEBACK_BONUS_RATIO: f32 = 0.25;
+
+
+    /// HORDE mode
+    /// The number of bots in the first wave.
+    pub const HORDE_FIRST_WAVE_SIZE: u32 = 3;
+    /// The number of bots added to each following wave.
+    pub const HORDE_WAVE_GROWTH: u32 = 2;
+    /// The delay between two bot spawns of the same wave in milliseconds.
+    pub const HORDE_SPAWN_INTERVAL_MS: u64 = 500;
+    /// The pause between two waves in milliseconds.
+    pub const HORDE_INTERMISSION_MS: u64 = 5000;
+    /// The bonus score awarded to a player destroying a horde bot.
+    pub const HORDE_KILL_BONUS: i32 = 5;
+    /// The team all the players join while the horde mode runs, see `Entity::team`.
+    pub const HORDE_PLAYER_TEAM: u8 = 1;
+    /// The team of the horde bots.
+    pub const HORDE_BOT_TEAM: u8 = 2;
+
+    /// EVENTS
+    /// The number of arena events kept for the UI and the clients.
+    pub const EVENT_QUEUE_CAPACITY: usize = 256;
+    /// How long a line stays in the kill feed of the game UI, in seconds, the last of them fading out.
+    pub const KILL_FEED_SECS: f32 = 5.0;
+    /// The number of lines of the kill feed.
+    pub const KILL_FEED_LINES: usize = 6;
+    /// The number of characters of a name in the kill feed, longer names end with an ellipsis.
+    pub const KILL_FEED_NAME_CHARS: usize = 16;
+    /// The simulated time between two samples of the score history of the game UI, in seconds.
+    pub const SCORE_HISTORY_SAMPLE_SECS: f64 = 1.0;
+    /// The number of samples kept per entity in the score history, and of score changes marked.
+    pub const SCORE_HISTORY_SAMPLES: usize = 600;
+    /// The number of hit effects the game UI draws at once, the oldest giving way to the new ones.
+    pub const MAX_HIT_EFFECTS: usize = 64;
+    /// How long the ring of an impact grows, in milliseconds: two or three frames.
+    pub const HIT_RING_MS: u64 = 50;
+    /// The radius the ring of an impact grows to, in world units.
+    pub const HIT_RING_RADIUS: f64 = 12.0;
+    /// How long an entity struck is drawn lighter, in milliseconds.
+    pub const HIT_FLASH_MS: u64 = 150;
+    /// How long the points won float above an entity, in milliseconds.
+    pub const SCORE_POPUP_MS: u64 = 800;
+    /// How high the points won rise while they float, in world units.
+    pub const SCORE_POPUP_RISE: f64 = 20.0;
+
+    /// GAME UI
+    /// The default zoom of the camera following an entity in the game UI: the arena is this many times wider than the view.
+    pub const DEFAULT_FOLLOW_ZOOM: f32 = 4.0;
+    /// The highest zoom of the camera following an entity.
+    pub const MAX_FOLLOW_ZOOM: f32 = 12.0;
+    /// The colors of the teams in the game UI, given in turn by team id.
+    pub const TEAM_COLORS: [[u8; 3]; 6] = [
+        [230, 70, 70],
+        [70, 130, 240],
+        [80, 200, 90],
+        [240, 200, 60],
+        [190, 90, 220],
+        [60, 210, 210],
+    ];
+    /// The most past positions kept per bullet for its trail in the game UI, one per simulation step.
+    pub const MAX_BULLET_TRAIL: usize = 10;
+    /// The default number of past positions drawn in the trail of a bullet.
+    pub const DEFAULT_BULLET_TRAIL: usize = 6;
+    /// The number of simulation steps between two samples of the movement trail of an entity.
+    pub const DEFAULT_TRAIL_INTERVAL_TICKS: u64 = 10;
+    /// The most samples kept in the movement trail of an entity, the oldest are dropped first.
+    pub const DEFAULT_TRAIL_MAX_SAMPLES: usize = 300;
+    /// The number of simulation steps the trail of a removed entity is kept after it.
+    pub const DEFAULT_TRAIL_LINGER_TICKS: u64 = 300;
+    /// The opacity of the newest segment of a movement trail in the game UI, the older ones fade from it.
+    pub const MOVEMENT_TRAIL_OPACITY: f32 = 0.6;
+    /// The length of the health bar drawn above an entity, in world units.
+    pub const HEALTH_BAR_LENGTH: f64 = 24.0;
+    /// The shortest the health bar gets on screen when zoomed out, in points.
+    pub const HEALTH_BAR_MIN_POINTS: f64 = 20.0;
+    /// The height above an entity of its health bar and of its name, in world units.
+    pub const HEALTH_BAR_OFFSET: f64 = 20.0;
+    /// The distance between the health bar and the fire energy bar under it, on screen, in points.
+    pub const ENERGY_BAR_GAP_POINTS: f64 = 4.0;
+    /// The room the label of an entity takes above it, on screen, so that its health bar goes over it, in points.
+    pub const NAME_LABEL_POINTS: f64 = 14.0;
+    /// The default distance between two lines of the background grid of the arena, in world units.
+    pub const DEFAULT_GRID_SPACING: f64 = 50.0;
+    /// The smallest distance between two lines of the grid the view settings allow, in world units.
+    pub const MIN_GRID_SPACING: f64 = 10.0;
+    /// The largest distance between two lines of the grid the view settings allow, in world units.
+    pub const MAX_GRID_SPACING: f64 = 500.0;
+    /// The widest view of the arena the labels of the entities are drawn in, in world units.
+    pub const LABELS_MAX_VIEW_WIDTH: f64 = 2500.0;
+    /// How close to an entity a click in the arena selects it for the inspector, in points.
+    pub const SELECT_RADIUS_POINTS: f32 = 20.0;
+    /// How fast Q and E turn the gun traverse of the entity driven with the keyboard, in turns per second.
+    pub const KEYBOARD_GUN_TRAVERSE_RATE: f32 = 0.5;
+    /// The key the preferences of the window are kept under in the storage of eframe.
+    pub const UI_PREFERENCES_KEY: &'static str = "ui_preferences";
+    /// The version of the preferences of the window, raised when one of their fields changes meaning.
+    pub const UI_PREFERENCES_VERSION: u32 = 1;
+    /// How long a toast stays over the arena of the game UI, in seconds.
+    pub const TOAST_SECS: f32 = 3.0;
+    /// The number of edits of the map editor of the game UI that can be undone.
+    pub const MAP_EDITOR_UNDO_LIMIT: usize = 20;
+
+    /// PROFILING
+    /// The number of steps the durations of the step phases are averaged over.
+    pub const STEP_TIMINGS_WINDOW: usize = 120;
+    /// The number of seconds of server metrics history shown by the server UI.
+    pub const METRICS_HISTORY_SECS: usize = 60;
+    /// The time between two refreshes of the status bar of the game UI, in milliseconds.
+    pub const STATUS_BAR_REFRESH_MS: u64 = 500;
+    /// The age after which the frame rate of the game UI is no longer reported, its tab being hidden, in milliseconds.
+    pub const UI_METRICS_STALE_MS: u64 = 2000;
+    /// The prefix of the names of the metrics exported to Prometheus.
+    pub const PROMETHEUS_PREFIX: &'static str = "starnet_";
+    /// The upper bounds of the buckets of the step duration histogram, in seconds.
+    pub const STEP_DURATION_BUCKETS: [f64; 10] = [0.0005, 0.001, 0.002, 0.004, 0.008, 0.016, 0.032, 0.064, 0.128, 0.256];
+
+    /// MATCH
+    /// The default simulated duration of a round in seconds, 0 for no time limit.
+    pub const DEFAULT_MATCH_DURATION_SECS: u32 = 180;
+    /// The default score ending a round when an entity reaches it, 0 for no target.
+    pub const DEFAULT_SCORE_TARGET: i32 = 0;
+    /// The simulated pause between two rounds in seconds.
+    pub const MATCH_INTERMISSION_SECS: f32 = 5.0;
+
+    /// POWER-UPS
+    /// The maximum number of power-ups waiting in the arena.
+    pub const POWER_UP_MAX: usize = 3;
+    /// The simulated time between two power-up spawns, in seconds.
+    pub const POWER_UP_SPAWN_INTERVAL_SECS: f32 = 10.0;
+    /// The radius of the sensor of a power-up.
+    pub const POWER_UP_RADIUS: f32 = 8.0;
+    /// The health restored by a heal power-up, up to the starting health.
+    pub const POWER_UP_HEAL: i32 = 2;
+    /// The armor granted by an armor power-up, up to `ARMOR_MAX`.
+    pub const POWER_UP_ARMOR: f32 = 0.25;
+    /// The factor applied to the speed of an entity during a speed boost.
+    pub const POWER_UP_SPEED_FACTOR: f32 = 1.5;
+    /// The simulated duration of the speed boost and rapid fire effects, in seconds.
+    pub const POWER_UP_DURATION_SECS: f32 = 8.0;
 
 
     /// USER command keywords
-    /// Command to set the user's name. Argument: string.
+    /// Codes are matched whatever their case, and spaces around codes and arguments are ignored.
+    /// Handshake. Sent by the server on connection with arguments: protocol version, server name.
+    /// Answered by the client with arguments: client protocol version, then the optional flags `SPECTATE` and `BINARY`.
+    /// Reply: `OK=HELLO=<token>=<command_budget>=<text>`, with the reconnection token of the entity and the
+    /// actuator commands the entity may issue per simulation step, 0 for no limit, see `BUDGET_EXCEEDED`.
+    pub const HELLO: &'static str = "HELLO";
+    /// Command to watch the game without an entity. No arguments.
+    /// Also accepted as a flag after the version in `HELLO`.
+    pub const SPECTATE: &'static str = "SPECTATE";
+    /// Flag after the version in `HELLO` switching the connection to length-prefixed binary frames.
+    pub const BINARY: &'static str = "BINARY";
+    /// Command to reclaim the entity of a dropped connection. Argument: string (the reconnection token).
+    pub const RECONNECT: &'static str = "RECONNECT";
+    /// Command to set the user's name. Argument: string (1 to `NAME_MAX_LEN` printable characters,
+    /// without separators). A name held by another entity is refused with `ERR_NAME_TAKEN`, or
+    /// given with a `-2`, `-3`… suffix when the `auto_suffix_names` rule is on.
+    /// Reply: `OK=NAME=<text>`, the text telling the name given.
     pub const SET_NAME: &'static str = "NAME";
     /// Command to set the user's color. Arguments: 3 integers for RGB.
     pub const SET_COLOR: &'static str = "COL";
@@ -41,34 +483,368 @@ impl AppDefines {
     pub const QUIT: &'static str = "EXIT";
     /// Command to indicate the user is alive. No arguments.
     pub const ALIVE: &'static str = "LIVE";
-    /// Command to send a message. Argument: string (a short message).
+    /// Command to send a chat message to the other clients. Argument: string (at most `MESSAGE_LENGTH`
+    /// characters). Reply: `OK=MSG=<text>`. The other clients are pushed `MSG=<sender>=<text>`, with the
+    /// name of the entity, or `CHAT_SERVER_SENDER` for a message of the server UI. The messages of a player
+    /// the server muted are answered as usual but reach no one.
     pub const MESSAGE: &'static str = "MSG";
+    /// The sender of the chat messages of the server UI, see `MESSAGE`.
+    pub const CHAT_SERVER_SENDER: &'static str = "SERVER";
 
-    /// Command to query the closest bot. No arguments.
+    /// Command to join a team. Argument: integer (the team, 1 to 255, or 0 to fight alone).
+    pub const SET_TEAM: &'static str = "TEAM";
+    /// Command to choose the bullets the entity fires. Argument: `STANDARD` (the default), `SNIPER`
+    /// (faster, but costing more fire energy and cooling down longer) or `SCATTER` (a fan of
+    /// `SCATTER_PELLETS` bullets, each dealing less damage).
+    pub const SET_WEAPON: &'static str = "SET_WEAPON";
+    /// Command to lay a mine where the entity stands. No arguments.
+    /// The mine arms after `MINE_ARMING_MS` and explodes when another entity drives into it,
+    /// damaging every entity in its blast, the closer the more. Replies `ERR` with `ERR_MINE_LIMIT`
+    /// when the entity already has the maximum number of mines in the arena.
+    pub const DEPLOY_MINE: &'static str = "MINE";
+
+    /// Command to query the closest bot. Optional argument: `ENEMIES` to leave out the teammates.
+    /// Reply: `CBOT=<name>=<x>=<y>=<distance>`, or `EMPTY` when the entity is alone.
     pub const QUERY_CLOSEST_BOT: &'static str = "CBOT";
+    /// Argument of `CBOT` to leave out the teammates.
+    pub const CLOSEST_ENEMIES: &'static str = "ENEMIES";
     /// Command to query the closest projectile. No arguments.
     pub const QUERY_CLOSEST_PROJECTILE: &'static str = "CPROJ";
+    /// Command to query the closest obstacle. Optional argument: `WALLS` to count the walls of the arena.
+    /// Reply: `COBS=<distance>=<bearing>=<shape>`, with the distance from the center of the entity
+    /// to the closest point of the obstacle, the bearing of that point in degrees from the front of
+    /// the entity, counterclockwise, as in `SENSORS`, and the shape
+    /// as in `OBSTACLES` or `BOUNDARY` for a wall of the arena; `EMPTY` when there is none.
+    pub const QUERY_CLOSEST_OBSTACLE: &'static str = "COBS";
+    /// Argument of `COBS` to count the walls of the arena as obstacles.
+    pub const CLOSEST_WALLS: &'static str = "WALLS";
+    /// The shape sent by `COBS` for a wall of the arena.
+    pub const BOUNDARY_SHAPE: &'static str = "BOUNDARY";
     /// Command to query a bot by name. Argument: string (name of the player).
+    /// Reply: `NBOT=<id>=<x>=<y>=<health>=<score>`, or `EMPTY` when no entity has the name.
     pub const QUERY_BY_NAME: &'static str = "NBOT";
     /// Command to query the list of names. No arguments.
     pub const QUERY_NAME_LIST: &'static str = "NLIST";
-    /// Command to query the orientation. No arguments.
+    /// Command to query the orientation of the entity and of its gun. No arguments.
+    /// Reply: `ORIENT=<orientation>=<gun_orientation>=<gun_traverse>`, with the orientations in
+    /// radians as in `SENSORS`. The gun orientation and traverse are the ones applied, which
+    /// follow the `GunTrav` command at the traverse rate of the match; the traverse is in turns.
     pub const QUERY_ORIENTATION: &'static str = "ORIENT";
     /// Command to query messages from a user. Argument: string (name of the player).
     pub const QUERY_MESSAGES_FROM_USER: &'static str = "USRMSG";
+    /// Command to query every entity in one reply. No arguments.
+    /// Reply: `WORLD=<count>` followed by one argument per entity: `id,x,y,orientation,health,score,name`,
+    /// as after the last step.
+    pub const QUERY_WORLD: &'static str = "WORLD";
+    /// Command to query the size of the arena and its hazards. No arguments.
+    /// Reply: `ARENA=<width>=<height>=<obstacle_count>=<hazard_count>` followed by one argument per hazard:
+    /// `shape,x,y,half_width,half_height,effect,strength`. The shape is `RECT` or `CIRCLE` (both half sizes
+    /// are the radius), the effect `DAMAGE` with the health removed per second as its strength, or `SLOW`
+    /// with the factor of the motors inside the field.
+    pub const QUERY_ARENA: &'static str = "ARENA";
+    /// Command to query the seed the map was generated from. No arguments.
+    /// Reply: `SEED=<seed>`, or `EMPTY` before the first map is generated or when the map was loaded from a file.
+    pub const QUERY_MAP_SEED: &'static str = "SEED";
+    /// Command to query the safe zone of the round. No arguments.
+    /// Reply: `ZONE=<x>=<y>=<radius>=<next_radius>=<shrink_starts_in>=<shrink_ends_in>`, with the
+    /// times in seconds of simulated time, or `EMPTY` when the zone is disabled or between two rounds.
+    pub const QUERY_ZONE: &'static str = "ZONE";
+    /// Command to query the control points of the map. No arguments.
+    /// Reply: `POINTS=<count>` followed by one argument per control point: `x,y,radius,owner,progress,name`.
+    /// The owner is the side holding or capturing the point, `T<team>` for a team or `E<id>` for an entity
+    /// without a team, `EMPTY` before a side enters it; the progress goes from 0 to 1, the point being held at 1.
+    /// `EMPTY` outside of the control point mode.
+    pub const QUERY_CONTROL_POINTS: &'static str = "POINTS";
+    /// Command to query every obstacle in one reply. No arguments.
+    /// Reply: `OBSTACLES=<count>` followed by one argument per obstacle: `x,y,half_width,half_height,shape,angle`.
+    /// The shape is `SQUARE`, `CIRCLE` (both half sizes are the radius) or `WALL` (half its length,
+    /// half its thickness), and the angle the rotation of the shape in radians.
+    pub const QUERY_OBSTACLES: &'static str = "OBSTACLES";
+    /// Command to measure the latency. Optional argument: a client timestamp, echoed back unchanged.
+    /// Reply: `PING=<server_ms>=<client_timestamp>=<last_step_ms>`, with the server time in
+    /// monotonic milliseconds since the server started.
+    pub const PING: &'static str = "PING";
+    /// Command to receive the world state at a fixed rate. Argument: integer (pushes per second, 1 to 30).
+    /// Optional argument: `DELTA`, to receive `KEYFRAME` and `DELTA` frames instead of `STATE` lines.
+    /// A subscribed client also receives a `RESET` line whenever the simulation is reset.
+    pub const SUBSCRIBE: &'static str = "SUBSCRIBE";
+    /// Command of a spectator to move a ghost camera over the arena, seen by no entity.
+    /// Optional argument: float (the radius of the world pushed to the subscription around
+    /// the camera, 0 for the whole world). Once observing, `MotL` drives the camera along x and
+    /// `MotR` along y, 0.5 holding it still. Reply: `OBSERVE=<id>=<x>=<y>=<radius>`.
+    pub const OBSERVE: &'static str = "OBSERVE";
+    /// Argument of `SUBSCRIBE` for the delta-encoded frames.
+    pub const SUBSCRIBE_DELTA: &'static str = "DELTA";
+    /// Command to receive a `KEYFRAME` as the next frame of a delta subscription. No arguments.
+    pub const RESYNC: &'static str = "RESYNC";
+    /// Command to stop receiving the world state. No arguments.
+    pub const UNSUBSCRIBE: &'static str = "UNSUBSCRIBE";
+    /// Line sent to every client right before the server closes the connections.
+    /// Argument: string (a human-readable reason).
+    pub const SERVER_SHUTDOWN: &'static str = "SHUTDOWN";
+    /// Line pushed to subscribed clients, with the same arguments as the `WORLD` reply.
+    pub const PUSH_STATE: &'static str = "STATE";
+    /// Frame pushed to delta subscribers with the whole world: the entities as
+    /// `E,id,x,y,orientation,health,score,name` records and the bullets as `B,id,x,y` records.
+    pub const PUSH_KEYFRAME: &'static str = "KEYFRAME";
+    /// Frame pushed to delta subscribers with the changes since the last frame written to them:
+    /// the `E` and `B` records of the entities and bullets added or changed, and `-E,id` and
+    /// `-B,id` records for those removed.
+    pub const PUSH_DELTA: &'static str = "DELTA";
+    /// Line pushed to a client when its entity is destroyed.
+    /// Arguments: string (the name of the entity credited with the kill, the entity itself for
+    /// a mine of its own or the zone, `EMPTY` if it is unknown), float (the bearing the killing
+    /// bullet came from, as in `DAMAGE`, `EMPTY` for a mine, a ram or the zone).
+    pub const PUSH_DEAD: &'static str = "DEAD";
+    /// Line pushed to a client whose entity was destroyed when it gets a new one, right before the
+    /// reply to the command that brought it back, see `PUSH_WAVE`.
+    /// Arguments: float (x), float (y), the position of the new entity.
+    pub const PUSH_RESPAWN: &'static str = "RESPAWN";
+    /// Line pushed to subscribed clients when the simulation is reset. No arguments.
+    pub const PUSH_RESET: &'static str = "RESET";
+    /// Line pushed to every client when a horde wave starts, is cleared or is lost.
+    /// Arguments: integer (the wave number), string (`STARTED`, `CLEARED` or `LOST`), string (the announcement).
+    /// A cleared wave wins the round for the players still alive, a lost one ends it without winner.
+    /// Either way, the players destroyed during the wave get a new entity at their next command needing one,
+    /// told by a `PUSH_RESPAWN` line.
+    pub const PUSH_WAVE: &'static str = "WAVE";
+    /// State of `WAVE` when the bots of the wave start to spawn.
+    pub const WAVE_STARTED: &'static str = "STARTED";
+    /// State of `WAVE` when every bot of the wave was destroyed.
+    pub const WAVE_CLEARED: &'static str = "CLEARED";
+    /// State of `WAVE` when every player died.
+    pub const WAVE_LOST: &'static str = "LOST";
+    /// Command to choose the reply format of the connection. Argument: `JSON` or `TEXT` (the default).
+    pub const FORMAT: &'static str = "FORMAT";
+    /// Argument of `FORMAT` for JSON replies.
+    pub const FORMAT_JSON: &'static str = "JSON";
+    /// Argument of `FORMAT` for the legacy text replies.
+    pub const FORMAT_TEXT: &'static str = "TEXT";
+    /// Command to query the reconnection token of the entity. No arguments.
+    pub const QUERY_TOKEN: &'static str = "TOKEN";
+    /// Command to unlock the admin commands on the connection. Argument: the admin password.
+    pub const ADMIN: &'static str = "ADMIN";
+    /// Admin command to remove a player and close its connection. Argument: the name of the entity.
+    pub const KICK: &'static str = "KICK";
+    /// Admin command to kick and refuse every connection from an address. Argument: the IP address.
+    pub const BAN: &'static str = "BAN";
+    /// Admin command to add an AI entity. Arguments: the name of the entity, and optionally
+    /// its difficulty, `EASY`, `NORMAL` (the default) or `HARD`.
+    pub const ADD_AI: &'static str = "ADDAI";
+    /// Admin command to freeze the simulation. Argument: `ON` to pause, `OFF` to resume,
+    /// `STEP` to advance a paused simulation by one step.
+    pub const PAUSE: &'static str = "PAUSE";
+    /// Argument of `PAUSE` freezing the simulation.
+    pub const PAUSE_ON: &'static str = "ON";
+    /// Argument of `PAUSE` resuming the simulation.
+    pub const PAUSE_OFF: &'static str = "OFF";
+    /// Argument of `PAUSE` advancing a paused simulation by one step.
+    pub const PAUSE_STEP: &'static str = "STEP";
+    /// Admin command to change the simulation speed. Argument: float (the time scale,
+    /// `MIN_TIME_SCALE` to `MAX_TIME_SCALE`, 1 for real time).
+    pub const TIME_SCALE: &'static str = "SPEED";
+    /// Admin command to query how long the phases of the last `STEP_TIMINGS_WINDOW` steps took.
+    /// Optional argument: `ON` to start measuring them, `OFF` to stop.
+    /// Reply: `TIMINGS=<count>` followed by one argument per phase: `phase,avg_ms,max_ms,samples,total_ms`.
+    /// The phase is `actuators`, `physics`, `collisions`, `culling` or `ai`; the samples and the total
+    /// count every measured step since the server started. Nothing is measured until profiling is on.
+    pub const TIMINGS: &'static str = "TIMINGS";
+    /// Argument of `TIMINGS` starting the measures.
+    pub const TIMINGS_ON: &'static str = "ON";
+    /// Argument of `TIMINGS` stopping the measures.
+    pub const TIMINGS_OFF: &'static str = "OFF";
+    /// Admin command to lock the lobby: the clients connecting afterwards get no entity, only spectators
+    /// and reconnection token holders get in. Argument: `ON` to lock, `OFF` to unlock.
+    pub const LOCK: &'static str = "LOCK";
+    /// Argument of `LOCK` locking the lobby.
+    pub const LOCK_ON: &'static str = "ON";
+    /// Argument of `LOCK` unlocking the lobby.
+    pub const LOCK_OFF: &'static str = "OFF";
+    /// Command to query the shield energy and whether the shield is up. No arguments.
+    pub const QUERY_ENERGY: &'static str = "ENERGY";
+    /// Command to scan the surroundings with rays. Optional argument: integer (number of rays, 1 to 64, default 16).
+    /// Reply: `LIDAR=<count>` followed by one hit distance per ray, counterclockwise from the front
+    /// of the entity. Rays hitting nothing report `LIDAR_MAX_RANGE`.
+    pub const QUERY_LIDAR: &'static str = "LIDAR";
+    /// Command to list the entities in a cone around the gun. Arguments: float (half angle in degrees,
+    /// 0 to 180), float (range), and optionally `los=1` to leave out the entities hidden behind walls.
+    /// Reply: `FOV=<count>` followed by one argument per entity, closest first: `name,distance,bearing`,
+    /// with the bearing in degrees from the gun, counterclockwise.
+    pub const QUERY_FOV: &'static str = "FOV";
+    /// Command to list the bullets that damaged the entity since the previous `DAMAGE` of the connection,
+    /// the last `RECENT_HITS` at most. No arguments.
+    /// Reply: `DAMAGE=<count>` followed by one argument per hit, oldest first: `tick,bearing,damage`,
+    /// with the bearing the bullet came from in degrees from the front of the entity, counterclockwise,
+    /// as in `SENSORS`, taken from the direction the bullet flew in, after any bounce.
+    pub const QUERY_DAMAGE: &'static str = "DAMAGE";
+    /// Command to query the whole state of the entity in one reply. No arguments.
+    /// Reply: `SENSORS=<x>=<y>=<orientation>=<gun_orientation>=<vx>=<vy>=<angular_velocity>=<health>=<score>=<fire_cooldown_ms>=<enemy_distance>=<enemy_bearing>=<bullet_distance>=<bullet_bearing>`,
+    /// with the orientations in radians as in `WORLD` and the bearings in degrees from the front
+    /// of the entity, counterclockwise. The closest enemy and the closest bullet that can hit the
+    /// entity are `EMPTY=EMPTY` when there are none.
+    pub const QUERY_SENSORS: &'static str = "SENSORS";
+    /// Command to query the arena events that happened since the previous `EVENTS` of the client,
+    /// or since it connected. No arguments.
+    /// Reply: `EVENTS=<count>` followed by one argument per event, oldest first:
+    /// `seq,step,type,source,target,value`. The type is `spawn`, `bullet_fired`, `hit`, `kill`,
+    /// `detonation`, `despawn`, `out_of_bounds`, `pick_up`, `rule_change`, `round_start`, `round_end`, `match_end`, `penalty`, `bonus`, `camping`, `capture` or `wave`. The source is the entity
+    /// spawning, firing, shooting, owning the mine, leaving, brought back inside the walls, picking up a power-up, losing or earning points or camping, the side capturing a control point
+    /// as in `POINTS` or the number of a horde wave, the target the entity hit or destroyed, and the value the damage of a hit, the name of the power-up picked up, the points lost or earned, the name of the
+    /// control point captured or the state of the wave as in `WAVE`; unused fields are `EMPTY`. A client querying less often than
+    /// `EVENT_QUEUE_CAPACITY` events happen misses the oldest ones, a gap in `seq` shows it.
+    pub const QUERY_EVENTS: &'static str = "EVENTS";
+    /// Command to query the cumulative leaderboard, kept across server restarts.
+    /// Optional argument: integer (number of players, 1 to 100, default 10).
+    /// Reply: `LEADERBOARD=<count>` followed by one argument per player, best first:
+    /// `rank,name,score,kills,deaths,matches,shots_fired,hits,accuracy,waves`. The score is the sum
+    /// of the final scores of the player's matches, the accuracy the share of its shots that hit
+    /// an opponent, `EMPTY` before its first shot, and `waves` the horde waves it was alive to see cleared.
+    pub const QUERY_LEADERBOARD: &'static str = "LEADERBOARD";
+    /// Command to query the state of the match. No arguments.
+    /// Reply: `MATCH=<round>=<phase>=<remaining_s>`, with the phase `RUNNING` or `INTERMISSION`
+    /// and the simulated seconds left in it, `EMPTY` for a round without time limit.
+    pub const QUERY_MATCH: &'static str = "MATCH";
+    /// The phase of a round being played.
+    pub const MATCH_RUNNING: &'static str = "RUNNING";
+    /// The phase between two rounds, actuators are ignored.
+    pub const MATCH_INTERMISSION: &'static str = "INTERMISSION";
+    /// Command to query the state of the entity. No arguments.
+    /// Reply: `STATUS=ALIVE=<health>=<score>=<x>=<y>=<fire_cooldown_ms>=<spread>=<shots_fired>=<hits>=<kills>=<deaths>=<accuracy>=<bullets_left>=<fire_energy>=<last_hit_bearing>=<actuator_seq>=<armor>`,
+    /// or `STATUS=DEAD` once the entity was destroyed. The spread is the largest deviation of a bullet
+    /// fired now from the aim, in radians: it grows with the speed of the entity. The accuracy is the
+    /// share of the shots that hit an opponent, `EMPTY` before the first shot. The bullets left are how many
+    /// more bullets the entity can have flying at once, `EMPTY` when the match rules set no cap. The fire
+    /// energy pays for the shots when the match rules give them a cost. The last hit bearing is where the
+    /// last bullet that damaged the entity came from, as in `DAMAGE`, `EMPTY` before the first. The actuator
+    /// sequence is the number of the last sequenced actuator command applied, `EMPTY` before the first.
+    /// The armor is the share of the bullet damage the entity shrugs off, from 0 to `ARMOR_MAX`.
+    /// Like `WORLD`, the reply gives the state after the last step, not waiting for the step in progress.
+    pub const QUERY_STATUS: &'static str = "STATUS";
+    /// The state of an entity still in the game.
+    pub const STATUS_ALIVE: &'static str = "ALIVE";
+    /// The state of an entity destroyed while its client is still connected.
+    pub const STATUS_DEAD: &'static str = "DEAD";
 
     /// Command for an empty reply. No arguments.
     pub const EMPTY_REPLY: &'static str = "EMPTY";
+    /// Reply to a command ignored because the entity exhausted its budget for the current step.
+    /// Argument: string (the ignored command).
+    pub const BUDGET_EXCEEDED: &'static str = "BUDGET";
+    /// Reply to an actuator command whose value was out of range and has been clamped.
+    /// Arguments: string (the command), float (the value applied), float (the value requested).
+    pub const ACTUATOR_CLAMPED: &'static str = "CLAMPED";
+    /// Reply to a sequenced actuator command dropped because a command with a higher number was already
+    /// received for the same actuator. Arguments: string (the command), integer (its sequence number),
+    /// integer (the newest sequence number of the actuator).
+    pub const ACTUATOR_STALE: &'static str = "STALE";
+
+    /// Prefix of a successful reply to an action command. Arguments: the command code, then a readable text.
+    pub const OK_REPLY: &'static str = "OK";
+    /// Prefix of an error reply. Arguments: the error code, its name, then a readable text.
+    pub const ERROR_REPLY: &'static str = "ERR";
+
+    /// ERROR codes
+    /// The command code is not known.
+    pub const ERR_UNKNOWN_COMMAND: u8 = 1;
+    /// A required argument is missing.
+    pub const ERR_MISSING_ARG: u8 = 2;
+    /// An argument has an invalid value.
+    pub const ERR_BAD_VALUE: u8 = 3;
+    /// The client has no entity in the game.
+    pub const ERR_NO_ENTITY: u8 = 4;
+    /// A command was received before the handshake.
+    pub const ERR_HANDSHAKE_REQUIRED: u8 = 5;
+    /// The client protocol major version is not supported. The server disconnects the client.
+    pub const ERR_VERSION_MISMATCH: u8 = 6;
+    /// The reconnection token is not known.
+    pub const ERR_UNKNOWN_TOKEN: u8 = 7;
+    /// The grace period of the reconnection token expired.
+    pub const ERR_TOKEN_EXPIRED: u8 = 8;
+    /// The reconnection token belongs to a connected client.
+    pub const ERR_TOKEN_IN_USE: u8 = 9;
+    /// A spectator sent a command controlling an entity.
+    pub const ERR_SPECTATOR: u8 = 10;
+    /// The server already serves the maximum number of clients.
+    pub const ERR_SERVER_FULL: u8 = 11;
+    /// An admin command was sent before `ADMIN`.
+    pub const ERR_ADMIN_REQUIRED: u8 = 12;
+    /// The admin password is wrong, or admin commands are disabled.
+    pub const ERR_BAD_PASSWORD: u8 = 13;
+    /// The address of the client is banned.
+    pub const ERR_BANNED: u8 = 14;
+    /// The client was kicked by an admin.
+    pub const ERR_KICKED: u8 = 15;
+    /// The entity already has the maximum number of mines in the arena.
+    pub const ERR_MINE_LIMIT: u8 = 16;
+    /// The lobby is locked, no new entity is created until it is unlocked.
+    pub const ERR_MATCH_LOCKED: u8 = 17;
+    /// The entity is driven with the keyboard from the game UI, its actuators cannot be set.
+    pub const ERR_CONTROLLED_LOCALLY: u8 = 18;
+    /// The name asked for is held by another entity.
+    pub const ERR_NAME_TAKEN: u8 = 19;
 
     /// Separator for commands.
     pub const COMMAND_SEP: &'static str = "#";
     /// Separator for arguments.
     pub const ARGUMENT_SEP: &'static str = "=";
+    /// Separator for the fields of a single argument.
+    pub const FIELD_SEP: &'static str = ",";
 
 
     /// Actionneurs moteurs et armes
+    /// Every actuator command takes an optional sequence number as its last argument, `MotL=0.8=@1234`:
+    /// numbered commands are queued and applied at the start of the next step in sequence order, a command
+    /// not newer than the last one of its actuator is answered `STALE`. Without it, the value is set at once.
     pub const ACTUATOR_MOTOR_LEFT: &'static str = "MotL";
     pub const ACTUATOR_MOTOR_RIGHT: &'static str = "MotR";
+    /// Pulls (1) or releases (0) the trigger of the gun. Like `GunTrav`, it takes an optional turret
+    /// index before the value, `GunTrig=1=0.9` for the second turret; without it, the first turret.
     pub const ACTUATOR_GUN_TRIGGER: &'static str = "GunTrig";
+    /// Points the gun relative to the body, in turns. The gun turns toward it at the traverse rate of the match.
     pub const ACTUATOR_GUN_TRAVERSE: &'static str = "GunTrav";
+    /// Raises (1) or lowers (0) the shield.
+    pub const ACTUATOR_SHIELD: &'static str = "Shield";
+    /// The range of the actuator values, values outside of it are clamped.
+    pub const ACTUATOR_MIN: f32 = 0.0;
+    pub const ACTUATOR_MAX: f32 = 1.0;
+
+
+    /// SHIELD
+    /// The maximum shield energy of an entity.
+    pub const SHIELD_MAX_ENERGY: f32 = 100.0;
+    /// The energy drained per second while the shield is raised.
+    pub const SHIELD_DRAIN_PER_SECOND: f32 = 10.0;
+    /// The energy drained by each bullet blocked by the shield.
+    pub const SHIELD_DRAIN_PER_HIT: f32 = 20.0;
+    /// The energy regenerated per second while the shield is lowered.
+    pub const SHIELD_REGEN_PER_SECOND: f32 = 5.0;
+
+
+    /// ADMIN control port commands, one per line, arguments separated by spaces.
+    /// Codes are matched whatever their case. Replies are one line: `OK ...` or `ERR <reason>`.
+    /// Command dumping the health of the server as `key=value` pairs. No arguments.
+    pub const CONTROL_STATUS: &'static str = "STATUS";
+    /// Command removing a player and closing its connection. Argument: the name of the entity or the address of the client.
+    pub const CONTROL_KICK: &'static str = "KICK";
+    /// Command kicking and refusing every connection from an address. Argument: the IP address.
+    pub const CONTROL_BAN: &'static str = "BAN";
+    /// Command freezing the simulation. No arguments.
+    pub const CONTROL_PAUSE: &'static str = "PAUSE";
+    /// Command resuming a paused simulation. No arguments.
+    pub const CONTROL_RESUME: &'static str = "RESUME";
+    /// Command resetting the arena and starting the round over. No arguments.
+    pub const CONTROL_RESET: &'static str = "RESET";
+    /// Command generating a new map. Optional argument: the seed of the map.
+    pub const CONTROL_GENERATE_MAP: &'static str = "GENERATE_MAP";
+    /// Command changing a rule of the match. Arguments: the name of a `GameConfig` field and its value.
+    pub const CONTROL_SET_CONFIG: &'static str = "SET_CONFIG";
+    /// Command writing the messages kept by the server to a file on the server host.
+    /// Arguments: the path of the file, and optionally its format, `text` (default) or `json` for JSON lines.
+    pub const CONTROL_DUMP: &'static str = "DUMP";
+    /// Command stopping the server and its process, as Ctrl-C does. No arguments.
+    pub const CONTROL_SHUTDOWN: &'static str = "SHUTDOWN";
+    /// Command returning the report of the last round that ended, as one line of JSON. No arguments.
+    pub const CONTROL_LAST_REPORT: &'static str = "LAST_REPORT";
+    /// Command switching every AI entity, and the ones added next, to another behavior. Argument: the name of the behavior, or "random-walk" for "wanderer" and "aggressive" for "hunter".
+    pub const CONTROL_AI_BEHAVIOR: &'static str = "AI_BEHAVIOR";
 }
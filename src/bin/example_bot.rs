@@ -0,0 +1,166 @@
+//! An example bot built on `BotClient`: it drives toward the closest enemy, turns its gun to it
+//! and fires once the gun is aligned.
+//!
+//! Start a server, then `cargo run --bin example_bot -- --name Rusty`.
+
+use std::f32::consts::{PI, TAU};
+use std::process::exit;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::client::{BotClient, ClientError, Reply, Sensors};
+
+/// The time between two looks at the sensors, in milliseconds.
+const LOOP_MS: u64 = 50;
+/// The distance the bot stops at from its target.
+const KEEP_DISTANCE: f32 = 200.0;
+/// The largest angle between the gun and the target at which the bot fires, in degrees.
+const ALIGNED_DEG: f32 = 4.0;
+/// How often the bot prints its state, in seconds.
+const REPORT_SECS: u64 = 2;
+
+/// The options of the command line.
+struct BotOptions {
+    host: String,
+    port: u16,
+    name: String,
+}
+
+impl BotOptions {
+    /// Reads the command line, `--host`, `--port` and `--name`, each followed by its value.
+    ///
+    /// # Returns
+    /// The options, or why the command line is refused.
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = BotOptions {
+            host: AppDefines::SERVER_ADDRESS.to_string(),
+            port: AppDefines::SERVER_PORT,
+            name: "ExampleBot".to_string(),
+        };
+        let mut args = args.iter();
+        while let Some(option) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} expects a value", option));
+            match option.as_str() {
+                "--host" => options.host = value()?.clone(),
+                "--port" => {
+                    let port = value()?;
+                    options.port = port.parse().map_err(|_| format!("invalid port '{}'", port))?;
+                }
+                "--name" => options.name = value()?.clone(),
+                _ => return Err(format!("unknown option '{}'", option)),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Returns the usage printed when the command line is refused.
+    fn usage() -> String {
+        format!(
+            "Usage: example_bot [--host <HOST>] [--port <PORT>] [--name <NAME>]\n\
+             [default: --host {} --port {} --name ExampleBot]",
+            AppDefines::SERVER_ADDRESS,
+            AppDefines::SERVER_PORT
+        )
+    }
+}
+
+/// What the bot is doing, printed when it changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Activity {
+    Searching,
+    Chasing,
+    Firing,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match BotOptions::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, BotOptions::usage());
+            exit(2);
+        }
+    };
+    if let Err(e) = play(&options) {
+        eprintln!("Bot stopped: {}", e);
+        exit(1);
+    }
+}
+
+/// Connects, completes the handshake and plays until the entity is destroyed or the server stops.
+fn play(options: &BotOptions) -> Result<(), ClientError> {
+    println!("Connecting to {}:{}...", options.host, options.port);
+    let mut bot = BotClient::connect(&options.host, options.port)?;
+    println!("Server: {}", bot.greeting);
+    bot.hello()?;
+    bot.set_name(&options.name)?;
+    println!("Playing as {}.", options.name);
+
+    let mut activity = None;
+    let mut last_report = Instant::now();
+    loop {
+        for push in bot.take_pushes() {
+            let push = Reply::parse(&push);
+            match push.code.as_str() {
+                AppDefines::PUSH_DEAD => {
+                    println!("Destroyed by {}.", push.args.first().map_or(AppDefines::EMPTY_REPLY, String::as_str));
+                    return bot.quit();
+                }
+                AppDefines::SERVER_SHUTDOWN => {
+                    println!("The server is shutting down.");
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        let sensors = match bot.sensors() {
+            Ok(sensors) => sensors,
+            Err(ClientError::Refused { code: AppDefines::ERR_NO_ENTITY, .. }) => {
+                println!("Our entity was destroyed.");
+                return bot.quit();
+            }
+            Err(e) => return Err(e),
+        };
+        let next = drive(&mut bot, &sensors)?;
+        if activity != Some(next) {
+            match (next, sensors.enemy) {
+                (Activity::Searching, _) | (_, None) => println!("No enemy left, turning in place."),
+                (Activity::Chasing, Some(enemy)) => println!("Chasing an enemy {:.0} away, {:+.0} deg.", enemy.distance, enemy.bearing),
+                (Activity::Firing, Some(enemy)) => println!("Gun aligned, firing at {:.0}.", enemy.distance),
+            }
+            activity = Some(next);
+        }
+        if last_report.elapsed() >= Duration::from_secs(REPORT_SECS) {
+            println!("At ({:.0}, {:.0}), health {}, score {}.", sensors.x, sensors.y, sensors.health, sensors.score);
+            last_report = Instant::now();
+        }
+        thread::sleep(Duration::from_millis(LOOP_MS));
+    }
+}
+
+/// Sets the actuators from the sensors.
+///
+/// # Returns
+/// What the bot does.
+fn drive(bot: &mut BotClient, sensors: &Sensors) -> Result<Activity, ClientError> {
+    let Some(enemy) = sensors.enemy else {
+        bot.set_motors(0.4, 0.6)?;
+        bot.set_trigger(false)?;
+        return Ok(Activity::Searching);
+    };
+
+    // Moteur droit plus rapide pour tourner à gauche, dans le sens des relèvements positifs
+    let turn = (enemy.bearing / 90.0).clamp(-1.0, 1.0);
+    let ahead = if enemy.distance > KEEP_DISTANCE { 1.0 } else { 0.0 };
+    bot.set_motors(0.5 + 0.25 * ahead - 0.25 * turn, 0.5 + 0.25 * ahead + 0.25 * turn)?;
+
+    // Le canon tire vers l'arrière du corps pour un débattement nul, voir `GameLogic::barrel_angle`
+    let bearing = enemy.bearing.to_radians();
+    bot.set_gun_traverse(((bearing - PI) / TAU).rem_euclid(1.0))?;
+    let error = (sensors.orientation + bearing - sensors.gun_orientation + PI).rem_euclid(TAU) - PI;
+    let aligned = error.abs() <= ALIGNED_DEG.to_radians();
+    bot.set_trigger(aligned)?;
+    Ok(if aligned { Activity::Firing } else { Activity::Chasing })
+}
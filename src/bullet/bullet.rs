@@ -1,66 +1,325 @@
+use std::ops::Range;
+
 use rapier2d::prelude::*;
-use std::time::Instant;
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::game_config::GameConfig;
+use crate::physics::body_identity::BodyIdentity;
 use crate::physics::physics::PhysicsEngine;
 
+/// The bullets an entity fires, chosen with `SET_WEAPON`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulletKind {
+    /// A single bullet, as set by the match rules.
+    #[default]
+    Standard,
+    /// A faster bullet, costing more fire energy and cooling down longer.
+    Sniper,
+    /// A fan of standard bullets, each dealing less damage.
+    Scatter,
+}
+
+impl BulletKind {
+    /// Every kind, in the order of the protocol documentation.
+    pub const ALL: [BulletKind; 3] = [BulletKind::Standard, BulletKind::Sniper, BulletKind::Scatter];
+
+    /// Returns the name of the kind, as the protocol writes it.
+    pub fn name(self) -> &'static str {
+        match self {
+            BulletKind::Standard => "STANDARD",
+            BulletKind::Sniper => "SNIPER",
+            BulletKind::Scatter => "SCATTER",
+        }
+    }
+
+    /// Returns the kind with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        BulletKind::ALL.into_iter().find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the factor applied to the speed of the bullets.
+    pub fn speed_factor(self) -> f32 {
+        match self {
+            BulletKind::Sniper => AppDefines::SNIPER_SPEED_FACTOR,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the factor applied to the fire energy a shot costs.
+    pub fn energy_factor(self) -> f32 {
+        match self {
+            BulletKind::Sniper => AppDefines::SNIPER_ENERGY_FACTOR,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the factor applied to the fire cooldown of the entity.
+    pub fn cooldown_factor(self) -> f32 {
+        match self {
+            BulletKind::Sniper => AppDefines::SNIPER_COOLDOWN_FACTOR,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the factor applied to the damage of each bullet.
+    pub fn damage_factor(self) -> f32 {
+        match self {
+            BulletKind::Scatter => AppDefines::SCATTER_DAMAGE_FACTOR,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the direction of each bullet of a shot.
+    ///
+    /// A scatter shot spreads its bullets evenly around the aim, `SCATTER_FAN_ANGLE` apart.
+    ///
+    /// # Parameters
+    /// - `angle`: The direction the gun points at, in radians.
+    pub fn fan(self, angle: f32) -> Vec<f32> {
+        match self {
+            BulletKind::Scatter => {
+                let middle = (AppDefines::SCATTER_PELLETS - 1) as f32 / 2.0;
+                (0..AppDefines::SCATTER_PELLETS)
+                    .map(|pellet| angle + (pellet as f32 - middle) * AppDefines::SCATTER_FAN_ANGLE)
+                    .collect()
+            }
+            _ => vec![angle],
+        }
+    }
+
+    /// Returns the health a bullet of this kind removes.
+    ///
+    /// With a falloff range in the match rules, the damage falls linearly with the distance
+    /// the bullet traveled, down to `damage_falloff_min` of it at the end of the range and past it.
+    /// A bullet dealing damage removes at least 1.
+    ///
+    /// # Parameters
+    /// - `traveled`: The distance the bullet traveled, bounces included.
+    /// - `config`: The match rules giving the damage and its falloff.
+    pub fn damage(self, traveled: f32, config: &GameConfig) -> i32 {
+        if config.bullet_damage <= 0 {
+            return 0;
+        }
+        let falloff = if config.damage_falloff_range > 0.0 {
+            let reached = (traveled / config.damage_falloff_range).clamp(0.0, 1.0);
+            1.0 - (1.0 - config.damage_falloff_min.clamp(0.0, 1.0)) * reached
+        } else {
+            1.0
+        };
+        let damage = config.bullet_damage as f32 * self.damage_factor() * falloff;
+        (damage.round() as i32).max(1)
+    }
+}
+
 /// Represents a bullet in the physics simulation.
 pub struct Bullet {
     pub handle: RigidBodyHandle,
+    /// The body of the entity that fired the bullet, an invalid handle once it left the arena.
     pub shooter: RigidBodyHandle,
-    pub created_at: Instant,
+    /// The id of the entity that fired the bullet, `None` if it was not fired by an entity.
+    pub shooter_id: Option<u32>,
+    /// The simulation step at which the bullet was fired.
+    pub fired_at: u64,
+    /// The simulation step at which the bullet disappears.
+    /// Each bounce moves it back, extending the lifetime of the bullet.
+    pub expires_at: u64,
+    /// The number of bounces the bullet was fired with.
+    pub bounces: u8,
+    /// The number of times the bullet can still bounce off a wall or an obstacle.
+    pub bounces_remaining: u8,
+    /// The kind of the bullet, setting its speed and its damage.
+    pub kind: BulletKind,
+    /// The distance the bullet traveled since it was fired, see `track`.
+    pub traveled: f32,
+    /// The position of the bullet at the end of the last step `track` was called at.
+    pub last_position: Vector<f32>,
 }
 
 impl Bullet {
     /// Creates a new `Bullet`.
     ///
+    /// The bullet takes a body of the bullet pool when one is ready, see `PhysicsEngine::release_bullet`,
+    /// and gets a new body otherwise: either way, it flies as a freshly built one.
+    ///
     /// # Parameters
     /// - `shooter_handle`: The handle of the shooter entity.
     /// - `physics_engine`: A mutable reference to the physics engine.
-    /// - `speed`: The speed of the bullet.
-    /// - `radius`: The radius of the bullet's collider.
-    /// - `gun_traverse`: Optional normalized value [0,1], maps to 0..2π.
+    /// - `config`: The match rules giving the speed, radius, bounces and collision detection of the bullet.
+    /// - `kind`: The kind of the bullet, scaling its speed.
+    /// - `muzzle`: Where the bullet starts, see `Turret::muzzle`.
+    /// - `angle`: The direction of the bullet, in radians.
+    /// - `lifetime`: The simulation steps the bullet flies, from the current one to the one at which it disappears.
     ///
     /// # Returns
     /// A new instance of `Bullet`.
     pub fn new(
         shooter_handle: RigidBodyHandle,
         physics_engine: &mut PhysicsEngine,
-        speed: f32,
-        radius: f32,
-        gun_traverse: Option<f32>,
+        config: &GameConfig,
+        kind: BulletKind,
+        muzzle: Vector<f32>,
+        angle: f32,
+        lifetime: Range<u64>,
     ) -> Self {
-        let shooter_body = &physics_engine.bodies[shooter_handle];
-        let pos = shooter_body.translation().clone();
-        let base_angle = shooter_body.rotation().angle();
+        let direction = vector![angle.cos(), angle.sin()];
+        let shooter = match physics_engine.identify(shooter_handle) {
+            BodyIdentity::Entity(id) => Some(id),
+            _ => None,
+        };
+        let speed = config.bullet_speed * kind.speed_factor();
+        let rigid_body = Bullet::rigid_body(muzzle, direction * speed, config.bullet_ccd, shooter);
+        let collider = Bullet::collider(config.bullet_radius, config.bullet_bounces);
 
-        // Conversion gun_traverse [0,1] → angle 0..2π
-        let traverse_offset = gun_traverse
-            .map(|v| v * 2.0 * std::f32::consts::PI)
-            .unwrap_or(0.0);
+        let handle = match physics_engine.take_pooled_bullet(lifetime.start) {
+            Some(handle) => {
+                // Le corps recyclé reprend tout du corps neuf : position, vitesse, forme, matériau.
+                // Pas de `copy_from` : il remettrait à l'infini l'épaisseur CCD calculée à l'insertion du collider,
+                // et la balle traverserait les murs
+                let body = &mut physics_engine.bodies[handle];
+                body.set_position(Isometry::translation(muzzle.x, muzzle.y), false);
+                body.set_angvel(0.0, false);
+                body.set_linvel(*rigid_body.linvel(), false);
+                body.reset_forces(false);
+                body.reset_torques(false);
+                body.enable_ccd(config.bullet_ccd);
+                body.user_data = rigid_body.user_data;
+                body.set_enabled(true);
+                body.wake_up(true);
+                for &pooled in body.colliders() {
+                    let pooled = &mut physics_engine.colliders[pooled];
+                    pooled.copy_from(&collider);
+                    // La copie garde la place d'un collider attaché : il revient à la bouche du canon, comme à l'insertion
+                    pooled.set_translation(muzzle);
+                }
+                handle
+            }
+            None => {
+                let handle = physics_engine.bodies.insert(rigid_body);
+                physics_engine.colliders.insert_with_parent(collider, handle, &mut physics_engine.bodies);
+                handle
+            }
+        };
 
-        let angle = base_angle + traverse_offset + std::f32::consts::PI; // inversion avant/arrière
+        Self {
+            handle,
+            shooter: shooter_handle,
+            shooter_id: shooter,
+            fired_at: lifetime.start,
+            expires_at: lifetime.end,
+            bounces: config.bullet_bounces,
+            bounces_remaining: config.bullet_bounces,
+            kind,
+            traveled: 0.0,
+            last_position: muzzle,
+        }
+    }
 
-        let direction = vector![angle.cos(), angle.sin()];
+    /// Builds the rigid body of a bullet.
+    ///
+    /// # Parameters
+    /// - `position`: The position of the bullet.
+    /// - `velocity`: The velocity of the bullet.
+    /// - `ccd`: Whether the continuous collision detection keeps the bullet from passing through thin walls
+    ///   in a single step, see `GameConfig::bullet_ccd`.
+    /// - `shooter`: The id of the entity that fired the bullet, kept in the user data of the body.
+    pub fn rigid_body(position: Vector<f32>, velocity: Vector<f32>, ccd: bool, shooter: Option<u32>) -> RigidBody {
+        RigidBodyBuilder::dynamic()
+            .translation(position)
+            .linvel(velocity)
+            .ccd_enabled(ccd)
+            .user_data(BodyIdentity::Bullet(shooter).encode())
+            .build()
+    }
 
-        let offset_distance = 20.0;
-        let start_pos = pos + direction * offset_distance;
+    /// Builds the collider of a bullet.
+    ///
+    /// A bullet that can bounce is perfectly elastic and slides without friction,
+    /// whatever it hits, so the solver reflects its velocity about the contact normal.
+    /// Its contacts go through `ShooterFilter`, see `pass_through_shooter`.
+    ///
+    /// # Parameters
+    /// - `radius`: The radius of the bullet.
+    /// - `bounces`: The number of times the bullet can bounce.
+    pub fn collider(radius: f32, bounces: u8) -> Collider {
+        let builder = ColliderBuilder::ball(radius)
+            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .active_hooks(ActiveHooks::FILTER_CONTACT_PAIRS);
+        if bounces == 0 {
+            return builder.restitution(0.0).build();
+        }
+        builder
+            .restitution(1.0)
+            .restitution_combine_rule(CoefficientCombineRule::Max)
+            .friction(0.0)
+            .friction_combine_rule(CoefficientCombineRule::Min)
+            .build()
+    }
 
-        let rigid_body = RigidBodyBuilder::dynamic()
-            .translation(start_pos)
-            .linvel(direction * speed)
-            .build();
+    /// Adds the distance the bullet moved during the last step to `traveled`.
+    ///
+    /// # Parameters
+    /// - `physics_engine`: The physics engine holding the body of the bullet.
+    pub fn track(&mut self, physics_engine: &PhysicsEngine) {
+        let position = *physics_engine.bodies[self.handle].translation();
+        self.traveled += (position - self.last_position).norm();
+        self.last_position = position;
+    }
 
-        let collider = ColliderBuilder::ball(radius)
-            .restitution(0.0)
-            .active_events(ActiveEvents::COLLISION_EVENTS)
-            .build();
+    /// Returns the health the bullet removes from the entity it hits, see `BulletKind::damage`.
+    pub fn damage(&self, config: &GameConfig) -> i32 {
+        self.kind.damage(self.traveled, config)
+    }
 
-        let handle = physics_engine.bodies.insert(rigid_body);
-        physics_engine.colliders.insert_with_parent(collider, handle, &mut physics_engine.bodies);
+    /// Returns whether the bullet bounced at least once.
+    pub fn has_bounced(&self) -> bool {
+        self.bounces_remaining < self.bounces
+    }
 
-        Self {
-            handle,
-            shooter: shooter_handle,
-            created_at: Instant::now(),
+    /// Lets the bullet pass through its shooter until a simulation step.
+    ///
+    /// The shooter and the step are kept in the user data of the collider, read by `ShooterFilter`.
+    ///
+    /// # Parameters
+    /// - `physics_engine`: The physics engine holding the collider of the bullet.
+    /// - `until`: The first step at which the bullet touches its shooter again, `u64::MAX` for never.
+    pub fn pass_through_shooter(&self, physics_engine: &mut PhysicsEngine, until: u64) {
+        let (index, generation) = self.shooter.into_raw_parts();
+        let user_data = (until as u128) << 64 | (generation as u128) << 32 | index as u128;
+        for &collider in physics_engine.bodies[self.handle].colliders() {
+            physics_engine.colliders[collider].user_data = user_data;
+        }
+    }
+}
+
+/// Keeps the bullets from touching their shooter while `Bullet::pass_through_shooter` allows it,
+/// and the bullets fired together, as those of a scatter shot, from touching each other.
+pub struct ShooterFilter {
+    /// The simulation step being computed.
+    pub tick: u64,
+}
+
+impl ShooterFilter {
+    /// Returns whether a bullet collider still passes through a body.
+    fn passes_through(&self, bullet: &Collider, body: Option<RigidBodyHandle>) -> bool {
+        let until = (bullet.user_data >> 64) as u64;
+        let shooter = RigidBodyHandle::from_raw_parts(bullet.user_data as u32, (bullet.user_data >> 32) as u32);
+        until > self.tick && body == Some(shooter)
+    }
+}
+
+impl PhysicsHooks for ShooterFilter {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        let (first, second) = (&context.colliders[context.collider1], &context.colliders[context.collider2]);
+        if self.passes_through(first, context.rigid_body2) || self.passes_through(second, context.rigid_body1) {
+            return None;
+        }
+        // Les balles d'un même tir partent du même point et ne se détruisent pas entre elles
+        let bullets = [first, second].iter().all(|collider| collider.active_hooks().contains(ActiveHooks::FILTER_CONTACT_PAIRS));
+        if bullets && first.user_data != 0 && first.user_data == second.user_data {
+            return None;
         }
+        Some(SolverFlags::COMPUTE_IMPULSES)
     }
 }
@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use crate::app_defines::AppDefines;
+use crate::config::Config;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::timeline::TimelineMode;
+
+/// The options of the command line, read before anything is started.
+///
+/// The options left out are `None` and keep the value of the settings file, see `apply`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    /// Whether `--help` was given: the usage is printed and nothing is started.
+    pub help: bool,
+    /// Whether `config --print-default` was given: the default settings file is printed and nothing is started.
+    pub print_default_config: bool,
+    /// The timeline to summarize instead of starting the server, see `analyze_timeline`.
+    pub analyze: Option<PathBuf>,
+    /// The addresses the server listens on: IPv4, IPv6 (`::` for both) or host names.
+    pub addresses: Option<Vec<String>>,
+    /// The port the server listens on.
+    pub port: Option<u16>,
+    /// Whether the server runs without a window, printing its messages instead.
+    pub headless: bool,
+    /// The number of simulation steps per second.
+    pub tick_rate: Option<f32>,
+    /// The number of AI bots spawned at start.
+    pub ai: Option<u32>,
+    /// The map file loaded at start, see `MapFile`.
+    pub map: Option<PathBuf>,
+    /// The settings file, see `Config::parse`. `None` reads `AppDefines::CONFIG_FILE` if it exists.
+    pub config: Option<PathBuf>,
+    /// The size of the arena.
+    pub arena: Option<ArenaConfig>,
+    /// The file the events of the match are exported to.
+    pub timeline: Option<PathBuf>,
+    /// When the timeline is written.
+    pub timeline_mode: TimelineMode,
+}
+
+impl Default for CliArgs {
+    fn default() -> Self {
+        Self {
+            help: false,
+            print_default_config: false,
+            analyze: None,
+            addresses: None,
+            port: None,
+            headless: false,
+            tick_rate: None,
+            ai: None,
+            map: None,
+            config: None,
+            arena: None,
+            timeline: None,
+            timeline_mode: TimelineMode::Continuous,
+        }
+    }
+}
+
+impl CliArgs {
+    /// Reads the command line.
+    ///
+    /// A value follows its option, as `--port 7000` or `--port=7000`. The files given
+    /// must exist, so that a typo stops the server before any socket is bound.
+    ///
+    /// # Parameters
+    /// - `args`: The arguments, without the name of the program.
+    ///
+    /// # Returns
+    /// The options, or why the command line is refused.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut cli = CliArgs::default();
+        if args.first().is_some_and(|arg| arg == "config") {
+            return match &args[1..] {
+                [option] if option == "--print-default" => Ok(CliArgs { print_default_config: true, ..cli }),
+                _ => Err("config expects --print-default".to_string()),
+            };
+        }
+        let mut timeline_mode = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value.clone().or_else(|| args.next().cloned()).ok_or_else(|| format!("{} expects a value", option))
+            };
+            match option {
+                "-h" | "--help" => cli.help = true,
+                "--headless" => cli.headless = true,
+                "--analyze" => cli.analyze = Some(PathBuf::from(value()?)),
+                // --listen est l'ancien nom de --bind
+                "--bind" | "--listen" => {
+                    let addresses: Vec<String> = value()?
+                        .split(',')
+                        .map(|address| address.trim().to_string())
+                        .filter(|address| !address.is_empty())
+                        .collect();
+                    if addresses.is_empty() {
+                        return Err(format!("{} expects at least one address", option));
+                    }
+                    cli.addresses = Some(addresses);
+                }
+                "--port" => {
+                    let port = value()?;
+                    cli.port = port.parse().ok().filter(|port| *port != 0).map(Some)
+                        .ok_or_else(|| format!("invalid port '{}', expected 1 to 65535", port))?;
+                }
+                "--tick-rate" => {
+                    let rate = value()?;
+                    cli.tick_rate = rate.parse().ok().filter(|rate: &f32| rate.is_finite() && *rate > 0.0).map(Some)
+                        .ok_or_else(|| format!("invalid tick rate '{}', expected a number of steps per second above 0", rate))?;
+                }
+                "--ai" => {
+                    let count = value()?;
+                    cli.ai = count.parse().map(Some).map_err(|_| format!("invalid number of AI bots '{}'", count))?;
+                }
+                "--map" => cli.map = Some(PathBuf::from(value()?)),
+                "--config" => cli.config = Some(PathBuf::from(value()?)),
+                "--arena" => cli.arena = Some(value()?.parse()?),
+                "--timeline" => cli.timeline = Some(PathBuf::from(value()?)),
+                "--timeline-mode" => {
+                    timeline_mode = Some(match value()?.as_str() {
+                        "continuous" => TimelineMode::Continuous,
+                        "round" => TimelineMode::OnDemand,
+                        mode => return Err(format!("invalid timeline mode '{}', expected continuous or round", mode)),
+                    });
+                }
+                _ => return Err(format!("unknown option '{}', see --help", arg)),
+            }
+        }
+
+        if cli.help {
+            return Ok(cli);
+        }
+        if let Some(mode) = timeline_mode {
+            if cli.timeline.is_none() {
+                return Err("--timeline-mode needs --timeline".to_string());
+            }
+            cli.timeline_mode = mode;
+        }
+        for (option, path) in [("--analyze", &cli.analyze), ("--map", &cli.map), ("--config", &cli.config)] {
+            if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+                return Err(format!("{}: the file {} does not exist", option, path.display()));
+            }
+        }
+        Ok(cli)
+    }
+
+    /// Overrides the settings read from the file with the options given.
+    ///
+    /// # Parameters
+    /// - `config`: The settings of the file, or the defaults without one.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(addresses) = &self.addresses {
+            config.server.bind = addresses.clone();
+        }
+        if let Some(port) = self.port {
+            config.server.port = port;
+        }
+        if let Some(tick_rate) = self.tick_rate {
+            config.server.tick_hz = tick_rate;
+        }
+        if let Some(ai) = self.ai {
+            config.ai.count = ai;
+        }
+        if let Some(arena) = self.arena {
+            config.arena = arena;
+        }
+    }
+
+    /// Returns the usage printed by `--help`, with the default of each option.
+    pub fn usage() -> String {
+        let arena = ArenaConfig::default();
+        format!(
+            "Usage: universal_rust_server_software [OPTIONS]\n       \
+             universal_rust_server_software config --print-default\n\
+             \n\
+             The options override the settings file.\n\
+             \n\
+             Options:\n  \
+             --bind <ADDR>[,<ADDR>...]   Addresses to listen on: IPv4, IPv6 (\"::\" for both) or host names [default: {}]\n  \
+             --port <PORT>               TCP port of the bots [default: {}]\n  \
+             --headless                  Run without a window, printing the server messages\n  \
+             --tick-rate <HZ>            Simulation steps per second [default: {}]\n  \
+             --ai <N>                    AI bots spawned at start [default: 0]\n  \
+             --map <FILE>                Map file loaded at start [default: a generated map]\n  \
+             --config <FILE>             TOML settings file of the server, the game and the AI [default: {} if it exists]\n  \
+             --arena <W>x<H>             Size of the arena [default: {}x{}]\n  \
+             --timeline <FILE>           Export the events of the match as JSON lines\n  \
+             --timeline-mode <MODE>      continuous or round [default: continuous]\n  \
+             --analyze <FILE>            Print the summary of a timeline and exit\n  \
+             -h, --help                  Print this help and exit\n\
+             \n\
+             Commands:\n  \
+             config --print-default      Print a settings file giving the defaults, with comments, and exit\n",
+            AppDefines::SERVER_ADDRESS,
+            AppDefines::SERVER_PORT,
+            AppDefines::TARGET_FPS_2D_PHYSICS,
+            AppDefines::CONFIG_FILE,
+            arena.width,
+            arena.height,
+        )
+    }
+}
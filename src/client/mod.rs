@@ -0,0 +1,410 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+
+/// The lines the server sends without being asked, see `BotClient::take_pushes`.
+const PUSHES: [&str; 9] = [
+    AppDefines::PUSH_DEAD,
+    AppDefines::PUSH_RESPAWN,
+    AppDefines::PUSH_STATE,
+    AppDefines::PUSH_KEYFRAME,
+    AppDefines::PUSH_DELTA,
+    AppDefines::PUSH_RESET,
+    AppDefines::PUSH_WAVE,
+    AppDefines::SERVER_SHUTDOWN,
+    AppDefines::MESSAGE,
+];
+
+/// Why a command of a `BotClient` failed.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The connection failed, or no reply came within the read timeout.
+    Io(io::Error),
+    /// The server closed the connection.
+    Closed,
+    /// The server answered `ERR`.
+    Refused { code: u8, name: String, text: String },
+    /// The reply is not the one expected for the command.
+    Unexpected(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "{}", e),
+            ClientError::Closed => write!(f, "the server closed the connection"),
+            ClientError::Refused { code, name, text } => write!(f, "refused ({} {}): {}", code, name, text),
+            ClientError::Unexpected(reply) => write!(f, "unexpected reply: {}", reply),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+/// The reply to one command, in the text format: a code and its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    /// The code of the reply: `OK`, `ERR`, `EMPTY` or the code of the query.
+    pub code: String,
+    /// The arguments, in their order on the wire.
+    pub args: Vec<String>,
+}
+
+impl Reply {
+    /// Splits the reply to one command into its code and arguments.
+    ///
+    /// # Parameters
+    /// - `text`: The reply, without `COMMAND_SEP`.
+    pub fn parse(text: &str) -> Self {
+        let mut parts = text.trim().split(AppDefines::ARGUMENT_SEP).map(|part| part.trim().to_string());
+        let code = parts.next().unwrap_or_default();
+        Reply { code, args: parts.collect() }
+    }
+
+    /// Returns the argument at an index, parsed.
+    ///
+    /// # Returns
+    /// The value, or `None` if the argument is missing, `EMPTY` or not a value of this type.
+    pub fn arg<T: std::str::FromStr>(&self, index: usize) -> Option<T> {
+        self.args.get(index).filter(|arg| *arg != AppDefines::EMPTY_REPLY).and_then(|arg| arg.parse().ok())
+    }
+
+    /// Turns an `ERR` reply into an error, and a reply of another code than expected into `Unexpected`.
+    ///
+    /// # Parameters
+    /// - `code`: The code expected, `OK_REPLY` for an action.
+    pub fn expect(self, code: &str) -> Result<Reply, ClientError> {
+        if self.code == AppDefines::ERROR_REPLY {
+            return Err(ClientError::Refused {
+                code: self.arg(0).unwrap_or_default(),
+                name: self.args.get(1).cloned().unwrap_or_default(),
+                text: self.args.get(2..).map(|text| text.join(AppDefines::ARGUMENT_SEP)).unwrap_or_default(),
+            });
+        }
+        if self.code != code {
+            return Err(ClientError::Unexpected(self.to_string()));
+        }
+        Ok(self)
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        for arg in &self.args {
+            write!(f, "{}{}", AppDefines::ARGUMENT_SEP, arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a threat is, from the `SENSORS` reply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// The distance from the entity.
+    pub distance: f32,
+    /// The bearing from the front of the entity, counterclockwise, in degrees within `[-180, 180]`.
+    pub bearing: f32,
+}
+
+/// The state of the entity read by `SENSORS`, all from the same simulation step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensors {
+    pub x: f32,
+    pub y: f32,
+    /// The orientation of the entity, in radians.
+    pub orientation: f32,
+    /// The direction the gun fires in, in radians.
+    pub gun_orientation: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub angular_velocity: f32,
+    pub health: i32,
+    pub score: i32,
+    /// The time left before the gun can fire again, in milliseconds.
+    pub fire_cooldown_ms: u64,
+    /// The closest enemy, `None` when the entity is alone.
+    pub enemy: Option<Contact>,
+    /// The closest bullet that can hit the entity, `None` when there is none.
+    pub bullet: Option<Contact>,
+}
+
+impl Sensors {
+    /// Reads a `SENSORS` reply.
+    ///
+    /// # Returns
+    /// The sensors, or `None` if an argument is missing or invalid.
+    pub fn parse(reply: &Reply) -> Option<Self> {
+        let contact = |index: usize| Some(Contact { distance: reply.arg(index)?, bearing: reply.arg(index + 1)? });
+        Some(Sensors {
+            x: reply.arg(0)?,
+            y: reply.arg(1)?,
+            orientation: reply.arg(2)?,
+            gun_orientation: reply.arg(3)?,
+            vx: reply.arg(4)?,
+            vy: reply.arg(5)?,
+            angular_velocity: reply.arg(6)?,
+            health: reply.arg(7)?,
+            score: reply.arg(8)?,
+            fire_cooldown_ms: reply.arg(9)?,
+            enemy: contact(10),
+            bullet: contact(12),
+        })
+    }
+}
+
+/// The state of the entity read by `STATUS`, the first fields of the reply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Status {
+    pub health: i32,
+    pub score: i32,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Status {
+    /// Reads a `STATUS` reply.
+    ///
+    /// # Returns
+    /// The status, `None` once the entity was destroyed or if an argument is invalid.
+    pub fn parse(reply: &Reply) -> Option<Self> {
+        if reply.args.first().map(String::as_str) != Some(AppDefines::STATUS_ALIVE) {
+            return None;
+        }
+        Some(Status { health: reply.arg(1)?, score: reply.arg(2)?, x: reply.arg(3)?, y: reply.arg(4)? })
+    }
+}
+
+/// A bullet that damaged the entity, from the `DAMAGE` reply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Damage {
+    /// The simulation step of the hit.
+    pub tick: u64,
+    /// Where the bullet came from, from the front of the entity, counterclockwise, in degrees within `[-180, 180]`.
+    pub bearing: f32,
+    /// The health the bullet removed.
+    pub damage: i32,
+}
+
+impl Damage {
+    /// Reads the hits of a `DAMAGE` reply.
+    ///
+    /// # Returns
+    /// The hits, oldest first, or `None` if a record is invalid.
+    pub fn parse(reply: &Reply) -> Option<Vec<Self>> {
+        reply.args.iter().skip(1).map(|record| {
+            let mut fields = record.split(',').map(str::trim);
+            Some(Damage {
+                tick: fields.next()?.parse().ok()?,
+                bearing: fields.next()?.parse().ok()?,
+                damage: fields.next()?.parse().ok()?,
+            })
+        }).collect()
+    }
+}
+
+/// A client of the bot port speaking the text protocol, one line of commands at a time.
+///
+/// Each line is answered by one line, the replies to its commands joined by `COMMAND_SEP`.
+/// The lines the server pushes meanwhile, such as `DEAD`, are kept for `take_pushes`.
+///
+/// ```no_run
+/// use universal_rust_server_software::client::BotClient;
+///
+/// let mut bot = BotClient::connect("127.0.0.1", 6969)?;
+/// bot.hello()?;
+/// bot.set_name("Rusty")?;
+/// bot.set_motors(1.0, 1.0)?;
+/// # Ok::<(), universal_rust_server_software::client::ClientError>(())
+/// ```
+pub struct BotClient {
+    writer: TcpStream,
+    reader: BufReader<TcpStream>,
+    /// The `HELLO` line sent by the server on connection.
+    pub greeting: String,
+    /// The actuator commands the entity may issue per simulation step, as told by the `HELLO` reply, 0 for no limit.
+    pub command_budget: u32,
+    /// The lines pushed by the server, oldest first.
+    pushes: Vec<String>,
+}
+
+impl BotClient {
+    /// Connects to a server and reads its greeting.
+    ///
+    /// # Parameters
+    /// - `host`: The address or host name of the server.
+    /// - `port`: The bot port of the server.
+    pub fn connect(host: &str, port: u16) -> Result<Self, ClientError> {
+        let writer = TcpStream::connect((host, port))?;
+        writer.set_read_timeout(Some(Duration::from_millis(AppDefines::BOT_CLIENT_READ_TIMEOUT_MS)))?;
+        writer.set_nodelay(true)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        let mut client = BotClient { writer, reader, greeting: String::new(), command_budget: 0, pushes: Vec::new() };
+        client.greeting = client.read_line()?;
+        Ok(client)
+    }
+
+    /// Reads the next line of the server, pushes included.
+    pub fn read_line(&mut self) -> Result<String, ClientError> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err(ClientError::Closed),
+            Ok(_) => Ok(line.trim_end().to_string()),
+            Err(e) => Err(ClientError::Io(e)),
+        }
+    }
+
+    /// Sends a line of commands and reads the replies to it.
+    ///
+    /// # Parameters
+    /// - `line`: The commands, joined by `COMMAND_SEP`.
+    ///
+    /// # Returns
+    /// The reply to each command, in order.
+    pub fn send(&mut self, line: &str) -> Result<Vec<Reply>, ClientError> {
+        writeln!(self.writer, "{}", line)?;
+        loop {
+            let reply = self.read_line()?;
+            let code = reply.split(AppDefines::ARGUMENT_SEP).next().unwrap_or_default();
+            if PUSHES.contains(&code) {
+                self.pushes.push(reply);
+            } else {
+                return Ok(reply.split(AppDefines::COMMAND_SEP).map(Reply::parse).collect());
+            }
+        }
+    }
+
+    /// Sends a single command.
+    ///
+    /// # Returns
+    /// Its reply, `ERR` included.
+    pub fn command(&mut self, command: &str) -> Result<Reply, ClientError> {
+        let replies = self.send(command)?;
+        replies.into_iter().next().ok_or_else(|| ClientError::Unexpected(String::new()))
+    }
+
+    /// Sends an action and checks that it succeeded.
+    fn act(&mut self, command: &str) -> Result<Reply, ClientError> {
+        self.command(command)?.expect(AppDefines::OK_REPLY)
+    }
+
+    /// Returns the lines pushed by the server since the last call, oldest first.
+    pub fn take_pushes(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pushes)
+    }
+
+    /// Waits for a line pushed by the server, keeping the others.
+    ///
+    /// # Parameters
+    /// - `code`: The code of the line, e.g. `PUSH_DEAD`.
+    pub fn wait_push(&mut self, code: &str) -> Result<Reply, ClientError> {
+        let matches = |line: &String| line.split(AppDefines::ARGUMENT_SEP).next() == Some(code);
+        if let Some(index) = self.pushes.iter().position(matches) {
+            return Ok(Reply::parse(&self.pushes.remove(index)));
+        }
+        loop {
+            let line = self.read_line()?;
+            if matches(&line) {
+                return Ok(Reply::parse(&line));
+            }
+            self.pushes.push(line);
+        }
+    }
+
+    /// Completes the handshake at the protocol version of this crate, which gives the client an entity.
+    ///
+    /// # Returns
+    /// The reconnection token of the entity, see `RECONNECT`.
+    pub fn hello(&mut self) -> Result<String, ClientError> {
+        let reply = self.act(&format!("{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION))?;
+        self.command_budget = reply.arg(2).unwrap_or_default();
+        Ok(reply.args.get(1).cloned().unwrap_or_default())
+    }
+
+    /// Names the entity.
+    pub fn set_name(&mut self, name: &str) -> Result<Reply, ClientError> {
+        self.act(&format!("{}{}{}", AppDefines::SET_NAME, AppDefines::ARGUMENT_SEP, name))
+    }
+
+    /// Colors the entity.
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) -> Result<Reply, ClientError> {
+        let sep = AppDefines::ARGUMENT_SEP;
+        self.act(&format!("{}{sep}{}{sep}{}{sep}{}", AppDefines::SET_COLOR, r, g, b))
+    }
+
+    /// Sets both motors in one line, from 0 (full reverse) to 1 (full ahead), 0.5 standing still.
+    pub fn set_motors(&mut self, left: f32, right: f32) -> Result<(), ClientError> {
+        let sep = AppDefines::ARGUMENT_SEP;
+        let line = format!("{}{sep}{}{}{}{sep}{}", AppDefines::ACTUATOR_MOTOR_LEFT, left, AppDefines::COMMAND_SEP, AppDefines::ACTUATOR_MOTOR_RIGHT, right);
+        for reply in self.send(&line)? {
+            BotClient::check_actuator(reply)?;
+        }
+        Ok(())
+    }
+
+    /// Points the gun relative to the body, in turns from 0 to 1. It turns at the traverse rate of the match.
+    pub fn set_gun_traverse(&mut self, turns: f32) -> Result<(), ClientError> {
+        let reply = self.command(&format!("{}{}{}", AppDefines::ACTUATOR_GUN_TRAVERSE, AppDefines::ARGUMENT_SEP, turns))?;
+        BotClient::check_actuator(reply)
+    }
+
+    /// Pulls or releases the trigger: the gun fires whenever it can while the trigger is pulled.
+    pub fn set_trigger(&mut self, pulled: bool) -> Result<(), ClientError> {
+        let reply = self.command(&format!("{}{}{}", AppDefines::ACTUATOR_GUN_TRIGGER, AppDefines::ARGUMENT_SEP, pulled as u8))?;
+        BotClient::check_actuator(reply)
+    }
+
+    /// Accepts the reply to an actuator, clamped values included.
+    fn check_actuator(reply: Reply) -> Result<(), ClientError> {
+        if reply.code == AppDefines::ACTUATOR_CLAMPED {
+            return Ok(());
+        }
+        reply.expect(AppDefines::OK_REPLY).map(|_| ())
+    }
+
+    /// Queries the sensors of the entity.
+    ///
+    /// # Returns
+    /// The sensors, or `Refused` with `ERR_NO_ENTITY` once the entity was destroyed.
+    pub fn sensors(&mut self) -> Result<Sensors, ClientError> {
+        let reply = self.command(AppDefines::QUERY_SENSORS)?.expect(AppDefines::QUERY_SENSORS)?;
+        Sensors::parse(&reply).ok_or_else(|| ClientError::Unexpected(reply.to_string()))
+    }
+
+    /// Queries the status of the entity.
+    ///
+    /// # Returns
+    /// The status, `None` once the entity was destroyed.
+    pub fn status(&mut self) -> Result<Option<Status>, ClientError> {
+        let reply = self.command(AppDefines::QUERY_STATUS)?.expect(AppDefines::QUERY_STATUS)?;
+        match Status::parse(&reply) {
+            Some(status) => Ok(Some(status)),
+            None if reply.args.first().map(String::as_str) == Some(AppDefines::STATUS_DEAD) => Ok(None),
+            None => Err(ClientError::Unexpected(reply.to_string())),
+        }
+    }
+
+    /// Queries the bullets that damaged the entity since the previous call.
+    ///
+    /// # Returns
+    /// The hits, oldest first, or `Refused` with `ERR_NO_ENTITY` once the entity was destroyed.
+    pub fn damage(&mut self) -> Result<Vec<Damage>, ClientError> {
+        let reply = self.command(AppDefines::QUERY_DAMAGE)?.expect(AppDefines::QUERY_DAMAGE)?;
+        Damage::parse(&reply).ok_or_else(|| ClientError::Unexpected(reply.to_string()))
+    }
+
+    /// Leaves the game with `EXIT`, which removes the entity, and closes the connection.
+    pub fn quit(mut self) -> Result<(), ClientError> {
+        writeln!(self.writer, "{}", AppDefines::QUIT)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::ai_behavior::AiDifficulty;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::game_config::GameConfig;
+
+/// The rules of `[game]` written with a comment in the template, the others are listed commented out.
+const TEMPLATE_RULES: [(&str, &str); 8] = [
+    ("starting_health", "The health of a new entity, at least 1."),
+    ("bullet_damage", "The health removed by a bullet hit, at least 0."),
+    ("bullet_speed", "The speed of the bullets, above 0."),
+    ("bullet_lifetime_ms", "The time a bullet flies before it disappears, in simulated milliseconds, at least 1."),
+    ("points_per_hit", "The points earned by the shooter for each hit."),
+    ("points_per_kill", "The points earned by the shooter for destroying an entity, on top of the hit."),
+    ("death_penalty", "The points lost by an entity when it is destroyed."),
+    ("score_target", "The score ending a round when an entity reaches it, 0 for no target."),
+];
+
+/// The settings of `[server]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// The addresses the server listens on: IPv4, IPv6 (`::` for both) or host names.
+    pub bind: Vec<String>,
+    /// The port the server listens on for TCP clients, not 0.
+    pub port: u16,
+    /// The maximum number of connected clients, 0 for no limit.
+    pub max_clients: usize,
+    /// The delay before a silent connection times out, in seconds, at least 1.
+    pub timeout_secs: i32,
+    /// The number of simulation steps per second, above 0.
+    pub tick_hz: f32,
+    /// The port of the read-only HTTP endpoint for dashboards, 0 to disable it.
+    pub http_port: u16,
+    /// Whether the HTTP endpoint also serves the Prometheus metrics on `/metrics`.
+    pub prometheus_metrics: bool,
+    /// The directory the report of each round is written to, empty to write none.
+    pub report_dir: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: vec![AppDefines::SERVER_ADDRESS.to_string()],
+            port: AppDefines::SERVER_PORT,
+            max_clients: AppDefines::MAX_CLIENTS,
+            timeout_secs: AppDefines::CONNECTION_TIMEOUT_DELAY,
+            tick_hz: AppDefines::TARGET_FPS_2D_PHYSICS,
+            http_port: AppDefines::HTTP_PORT,
+            prometheus_metrics: AppDefines::HTTP_PROMETHEUS_METRICS,
+            report_dir: AppDefines::REPORT_DIR.to_string(),
+        }
+    }
+}
+
+/// The settings of `[ai]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AiConfig {
+    /// The number of AI bots spawned at start.
+    pub count: u32,
+    /// How well the bots spawned at start play.
+    pub difficulty: AiDifficulty,
+}
+
+/// The settings read at startup from a TOML file, see `Config::parse`.
+///
+/// The command line overrides them, see `CliArgs::apply`. `ServerSettings::from_config`,
+/// `GameLogic::with_arena` and `game` build the server and the game from them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub server: ServerConfig,
+    /// The rules of the match, `[game]` without the size of the arena.
+    pub game: GameConfig,
+    /// The size of the arena, `arena_width` and `arena_height` in `[game]`.
+    pub arena: ArenaConfig,
+    pub ai: AiConfig,
+}
+
+impl Config {
+    /// Reads the settings from a file, or returns the defaults when it does not exist.
+    ///
+    /// # Parameters
+    /// - `path`: The TOML file.
+    ///
+    /// # Returns
+    /// The settings, or why the file cannot be used, see `parse`.
+    pub fn load_or_default(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let text = fs::read_to_string(path).map_err(|e| format!("the file {} cannot be read: {}", path.display(), e))?;
+        Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Reads the settings from the text of a TOML file.
+    ///
+    /// The settings go in the `[server]`, `[game]` and `[ai]` sections, see `default_template`;
+    /// the keys left out keep their default. `[game]` takes every rule of `GameConfig`.
+    /// A file without sections holds the rules of the match alone, as `--config` used to read.
+    ///
+    /// # Parameters
+    /// - `text`: The text of the file.
+    ///
+    /// # Returns
+    /// The settings, or the line and the key refused: not TOML, an unknown section or key,
+    /// a value of the wrong type or out of range.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| match e.span() {
+            Some(span) => format!("line {}: {}", text[..span.start].matches('\n').count() + 1, e.message()),
+            None => e.message().to_string(),
+        })?;
+        let mut config = Config::default();
+
+        // Un fichier sans section ne contient que les règles du match, comme avant
+        if !table.values().any(toml::Value::is_table) {
+            config.read_game(text, None, &table)?;
+            return Ok(config);
+        }
+        for (name, value) in &table {
+            let Some(entries) = value.as_table() else {
+                return Err(key_error(text, None, name, "unknown key, the settings go in the [server], [game] and [ai] sections"));
+            };
+            match name.as_str() {
+                "server" => config.read_server(text, entries)?,
+                "game" => config.read_game(text, Some("game"), entries)?,
+                "ai" => config.read_ai(text, entries)?,
+                _ => {
+                    let line = text.lines().position(|line| line.trim() == format!("[{}]", name));
+                    return Err(match line {
+                        Some(index) => format!("line {}: unknown section [{}]", index + 1, name),
+                        None => format!("unknown section [{}]", name),
+                    });
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Reads `[server]`.
+    fn read_server(&mut self, text: &str, entries: &toml::Table) -> Result<(), String> {
+        let server = &mut self.server;
+        for (key, value) in entries {
+            let error = |message: &str| key_error(text, Some("server"), key, message);
+            match key.as_str() {
+                "bind" => {
+                    server.bind = read_value(value).map_err(|e| error(&e))?;
+                    if server.bind.iter().all(|address| address.trim().is_empty()) {
+                        return Err(error("expected at least one address"));
+                    }
+                }
+                "port" => {
+                    server.port = read_value(value).map_err(|e| error(&e))?;
+                    if server.port == 0 {
+                        return Err(error("expected a port from 1 to 65535"));
+                    }
+                }
+                "max_clients" => server.max_clients = read_value(value).map_err(|e| error(&e))?,
+                "timeout_secs" => {
+                    server.timeout_secs = read_value(value).map_err(|e| error(&e))?;
+                    if server.timeout_secs < 1 {
+                        return Err(error("expected at least 1 second"));
+                    }
+                }
+                "tick_hz" => {
+                    server.tick_hz = read_value(value).map_err(|e| error(&e))?;
+                    if !server.tick_hz.is_finite() || server.tick_hz <= 0.0 {
+                        return Err(error("expected a number of steps per second above 0"));
+                    }
+                }
+                "http_port" => server.http_port = read_value(value).map_err(|e| error(&e))?,
+                "prometheus_metrics" => server.prometheus_metrics = read_value(value).map_err(|e| error(&e))?,
+                "report_dir" => server.report_dir = read_value(value).map_err(|e| error(&e))?,
+                _ => return Err(error("unknown key")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads `[game]`, or the rules of a file without sections.
+    fn read_game(&mut self, text: &str, section: Option<&str>, entries: &toml::Table) -> Result<(), String> {
+        let known = serde_json::to_value(GameConfig::default()).map_err(|e| e.to_string())?;
+        let (mut width, mut height) = (self.arena.width, self.arena.height);
+        for (key, value) in entries {
+            let error = |message: &str| key_error(text, section, key, message);
+            match key.as_str() {
+                "arena_width" => width = read_value(value).map_err(|e| error(&e))?,
+                "arena_height" => height = read_value(value).map_err(|e| error(&e))?,
+                _ if known.get(key.as_str()).is_none() => return Err(error("unknown key")),
+                _ => {
+                    let json = serde_json::to_string(value).map_err(|e| error(&e.to_string()))?;
+                    self.game.set(key, &json).map_err(|_| error(&format!("invalid value {}", value)))?;
+                    check_rule(key, &self.game).map_err(|e| error(&e))?;
+                }
+            }
+        }
+        // La taille est vérifiée une fois les deux côtés lus
+        self.arena = ArenaConfig::new(width, height).map_err(|e| {
+            let key = if entries.contains_key("arena_width") { "arena_width" } else { "arena_height" };
+            key_error(text, section, key, &e)
+        })?;
+        Ok(())
+    }
+
+    /// Reads `[ai]`.
+    fn read_ai(&mut self, text: &str, entries: &toml::Table) -> Result<(), String> {
+        for (key, value) in entries {
+            let error = |message: &str| key_error(text, Some("ai"), key, message);
+            match key.as_str() {
+                "count" => self.ai.count = read_value(value).map_err(|e| error(&e))?,
+                "difficulty" => {
+                    let name: String = read_value(value).map_err(|e| error(&e))?;
+                    self.ai.difficulty = AiDifficulty::from_name(&name)
+                        .ok_or_else(|| error(&format!("invalid difficulty '{}', expected easy, normal or hard", name)))?;
+                }
+                _ => return Err(error("unknown key")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a settings file giving the defaults, each setting with a comment, as printed by `config --print-default`.
+    pub fn default_template() -> String {
+        let defaults = Config::default();
+        let rules = serde_json::to_value(&defaults.game).unwrap_or_default();
+        let server = &defaults.server;
+        let mut template = format!(
+            "# The settings of the server, read at startup from {} or the file given to --config.\n\
+             # The options of the command line override them, the keys left out keep the values below.\n\
+             \n\
+             [server]\n\
+             # The addresses listened on: IPv4, IPv6 (\"::\" for both) or host names.\n\
+             bind = {}\n\
+             # The TCP port of the bots, 1 to 65535.\n\
+             port = {}\n\
+             # The maximum number of connected clients, TCP and WebSocket together, 0 for no limit.\n\
+             max_clients = {}\n\
+             # The delay before a silent connection is closed, in seconds, at least 1.\n\
+             timeout_secs = {}\n\
+             # The simulation steps per second, above 0.\n\
+             tick_hz = {:?}\n\
+             # The port of the read-only HTTP endpoint for dashboards (/state, /leaderboard, /metrics-lite), 0 to disable it.\n\
+             http_port = {}\n\
+             # Whether the HTTP endpoint also serves the Prometheus metrics on /metrics.\n\
+             prometheus_metrics = {}\n\
+             # The directory the report of each round is written to, as JSON and CSV, \"\" to write none.\n\
+             report_dir = {}\n\
+             \n\
+             [game]\n\
+             # The size of the arena, at least {:?} each.\n\
+             arena_width = {:?}\n\
+             arena_height = {:?}\n",
+            AppDefines::CONFIG_FILE,
+            serde_json::Value::from(server.bind.clone()),
+            server.port,
+            server.max_clients,
+            server.timeout_secs,
+            server.tick_hz,
+            server.http_port,
+            server.prometheus_metrics,
+            serde_json::Value::from(server.report_dir.clone()),
+            AppDefines::ARENA_MIN_SIZE,
+            defaults.arena.width,
+            defaults.arena.height,
+        );
+        for (key, comment) in TEMPLATE_RULES {
+            template += &format!("# {}\n{} = {}\n", comment, key, rule_text(&rules[key]));
+        }
+        template += "# The other rules, named as in the game settings.\n";
+        // L'ordre des champs de GameConfig, que la valeur JSON ne garde pas
+        let fields = toml::to_string(&defaults.game).unwrap_or_default();
+        for key in fields.lines().filter_map(|line| line.split_once(" = ")).map(|(key, _)| key) {
+            if !TEMPLATE_RULES.iter().any(|(rule, _)| *rule == key) {
+                template += &format!("# {} = {}\n", key, rule_text(&rules[key]));
+            }
+        }
+        template += &format!(
+            "\n\
+             [ai]\n\
+             # The AI bots spawned at start.\n\
+             count = {}\n\
+             # How well they play: easy, normal or hard.\n\
+             difficulty = \"{}\"\n",
+            defaults.ai.count,
+            defaults.ai.difficulty.name().to_lowercase(),
+        );
+        template
+    }
+}
+
+/// Reads a value of a given type, as TOML writes it.
+fn read_value<T: DeserializeOwned>(value: &toml::Value) -> Result<T, String> {
+    value.clone().try_into().map_err(|e: toml::de::Error| e.message().to_string())
+}
+
+/// Writes the value of a rule as in a TOML file.
+fn rule_text(value: &serde_json::Value) -> String {
+    // Les règles décimales sont des f32, élargies par la valeur JSON
+    match value.as_f64() {
+        Some(number) if value.is_f64() => format!("{:?}", number as f32),
+        _ => value.to_string(),
+    }
+}
+
+/// Checks the range of the rules of `[game]` that have one.
+fn check_rule(key: &str, game: &GameConfig) -> Result<(), String> {
+    let positive = |value: f32| value.is_finite() && value > 0.0;
+    let not_negative = |value: f32| value.is_finite() && value >= 0.0;
+    match key {
+        "starting_health" if game.starting_health < 1 => Err("expected a health of at least 1".to_string()),
+        "bullet_damage" if game.bullet_damage < 0 => Err("expected a damage of at least 0".to_string()),
+        "bullet_speed" if !positive(game.bullet_speed) => Err("expected a speed above 0".to_string()),
+        "bullet_radius" if !positive(game.bullet_radius) => Err("expected a radius above 0".to_string()),
+        "camping_radius" if !positive(game.camping_radius) => Err("expected a radius above 0".to_string()),
+        "fire_energy_max" if !positive(game.fire_energy_max) => Err("expected an energy above 0".to_string()),
+        "shot_energy_cost" if game.shot_energy_cost != 0.0 && !positive(game.shot_energy_cost) => Err("expected a cost of at least 0".to_string()),
+        "bullet_lifetime_ms" if game.bullet_lifetime_ms == 0 => Err("expected a lifetime of at least 1 millisecond".to_string()),
+        "damage_falloff_range" if !not_negative(game.damage_falloff_range) => Err("expected a distance of at least 0".to_string()),
+        "damage_falloff_min" if !(0.0..=1.0).contains(&game.damage_falloff_min) => Err("expected a share from 0 to 1".to_string()),
+        "score_target" if game.score_target < 0 => Err("expected a score of at least 0".to_string()),
+        "control_capture_secs" if !not_negative(game.control_capture_secs) => Err("expected a time of at least 0".to_string()),
+        "control_points_per_sec" if !not_negative(game.control_points_per_sec) => Err("expected points of at least 0".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Tells which key of the file is refused, and on which line when it can be found.
+///
+/// # Parameters
+/// - `text`: The text of the file.
+/// - `section`: The section of the key, `None` above the first one.
+/// - `key`: The key refused.
+/// - `message`: Why it is refused.
+fn key_error(text: &str, section: Option<&str>, key: &str, message: &str) -> String {
+    let name = match section {
+        Some(section) => format!("{}.{}", section, key),
+        None => key.to_string(),
+    };
+    let mut current = None;
+    let mut line = None;
+    for (index, text) in text.lines().enumerate() {
+        let text = text.trim();
+        if text.starts_with('[') {
+            current = Some(text.trim_matches(['[', ']']).trim());
+        } else if current == section && text.split_once('=').is_some_and(|(written, _)| written.trim().trim_matches('"') == key) {
+            line = Some(index + 1);
+            break;
+        }
+    }
+    match line {
+        Some(line) => format!("line {}: {}: {}", line, name, message),
+        None => format!("{}: {}", name, message),
+    }
+}
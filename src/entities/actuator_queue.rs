@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+/// An actuator command waiting for the next step, see `ActuatorQueue`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingActuator {
+    /// The sequence number the client sent the command with.
+    pub seq: u64,
+    /// The code of the actuator command, e.g. `MotL`.
+    pub code: String,
+    /// The index of the turret of a gun actuator, 0 for the others.
+    pub turret: usize,
+    /// The clamped value.
+    pub value: f32,
+}
+
+/// The actuator commands of an entity sent with a sequence number, e.g. `MotL=0.8=@1234`.
+///
+/// They are applied at the start of the next step in sequence order rather than in the order
+/// they arrived in. A command not newer than the last one queued or applied for the same
+/// actuator is stale and dropped, so a late packet never undoes a more recent one.
+#[derive(Debug, Clone, Default)]
+pub struct ActuatorQueue {
+    pending: Vec<PendingActuator>,
+    /// The newest sequence number queued for each actuator, by code and turret.
+    newest: HashMap<(String, usize), u64>,
+    /// The sequence number of the last command applied, `None` before the first one.
+    applied: Option<u64>,
+}
+
+impl ActuatorQueue {
+    /// Queues a command for the next step.
+    ///
+    /// # Parameters
+    /// - `command`: The command, its value already clamped.
+    ///
+    /// # Returns
+    /// `Err` with the newest sequence number of the actuator when the command is stale and dropped.
+    pub fn push(&mut self, command: PendingActuator) -> Result<(), u64> {
+        let key = (command.code.clone(), command.turret);
+        if let Some(&newest) = self.newest.get(&key).filter(|&&newest| newest >= command.seq) {
+            return Err(newest);
+        }
+        self.newest.insert(key, command.seq);
+        self.pending.push(command);
+        Ok(())
+    }
+
+    /// Forgets the queued commands of an actuator, set by a command without a sequence number.
+    ///
+    /// # Parameters
+    /// - `code`: The code of the actuator command.
+    /// - `turret`: The index of the turret of a gun actuator, 0 for the others.
+    pub fn cancel(&mut self, code: &str, turret: usize) {
+        self.pending.retain(|command| command.code != code || command.turret != turret);
+    }
+
+    /// Takes the queued commands, oldest sequence number first.
+    ///
+    /// # Returns
+    /// The commands to apply in order, the sequence number of the last one becoming `applied`.
+    pub fn drain(&mut self) -> Vec<PendingActuator> {
+        let mut commands = std::mem::take(&mut self.pending);
+        commands.sort_by_key(|command| command.seq);
+        if let Some(last) = commands.last() {
+            self.applied = Some(self.applied.map_or(last.seq, |applied| applied.max(last.seq)));
+        }
+        commands
+    }
+
+    /// Drops the queued commands, keeping the sequence numbers seen so that late commands stay stale.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Returns the sequence number of the last command applied, `None` before the first one.
+    pub fn applied(&self) -> Option<u64> {
+        self.applied
+    }
+}
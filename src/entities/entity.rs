@@ -1,60 +1,167 @@
 use rand::Rng;
 use rapier2d::prelude::*;
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
 use eframe::egui;
+use crate::app_defines::AppDefines;
+use crate::bullet::bullet::BulletKind;
+use crate::entities::actuator_queue::{ActuatorQueue, PendingActuator};
+use crate::entities::turret::Turret;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::game_config::GameConfig;
+use crate::game_logic::scoring::ScoreBreakdown;
+use crate::physics::body_identity::BodyIdentity;
 use crate::physics::physics::PhysicsEngine;
+use crate::power_ups::PowerUpKind;
+
+/// A bullet that damaged an entity, see `Entity::record_hit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitRecord {
+    /// The number of the hit among those the entity took, from 1.
+    pub seq: u64,
+    /// The simulation step of the hit.
+    pub tick: u64,
+    /// Where the bullet came from, in radians from the front of the entity, counterclockwise, within `[-π, π]`.
+    pub bearing: f32,
+    /// The health the bullet removed.
+    pub damage: i32,
+}
 
 /// Represents an entity in the physics simulation.
 pub struct Entity {
     pub id: u32,
     pub name: String,
     pub score: i32,
+    /// What the entity did since its scores were last cleared.
+    pub breakdown: ScoreBreakdown,
     pub handle: RigidBodyHandle,
     pub is_ai: bool,
-    pub last_shot: Instant,
+    /// The number of bullets fired by the entity still flying.
+    pub live_bullets: u32,
+    /// The delay between two shots of each turret of the entity, before the factor of its weapon, see `shot_cooldown`.
+    pub fire_cooldown: Duration,
+    /// The bullets the entity fires.
+    pub weapon: BulletKind,
+    /// The simulated time until which the entity can neither be hurt nor fire.
+    pub spawn_protected_until: Duration,
+    /// The position of the body at the end of the last step, see `GameLogic::sync_entities`.
     pub x: f32,
     pub y: f32,
+    /// The rotation of the body at the end of the last step, in radians.
     pub self_orientation: f64,
-    pub gun_orientation: f64,
+    /// The linear velocity of the body at the end of the last step.
+    pub vx: f32,
+    pub vy: f32,
+    /// The guns of the entity, at least one, see `Turret`.
+    pub turrets: Vec<Turret>,
     pub target_x: f32,
     pub target_y: f32,
     pub color: egui::Color32,
     pub motor_left: f32,      // 0.0 à 1.0
     pub motor_right: f32,
-    pub gun_trigger: f32,
-    pub gun_traverse: f32,
+    pub shield: f32,          // 0.0 = baissé, 1.0 = levé
+    pub energy: f32,
+    /// The energy the shots are paid with, up to `GameConfig::fire_energy_max`. The shield has its own `energy`.
+    pub fire_energy: f32,
     pub health: i32,
+    /// The share of the bullet damage the entity shrugs off, from 0 to `ARMOR_MAX`, see `mitigate`.
+    pub armor: f32,
+    /// The last `RECENT_HITS` bullets that damaged the entity, oldest first.
+    pub recent_hits: VecDeque<HitRecord>,
+    /// The number of bullets that damaged the entity, numbering the `recent_hits`.
+    pub hits_taken: u64,
+    /// The team of the entity, `None` when it fights alone.
+    pub team: Option<u8>,
+    /// The simulation step at which the speed boost ends, `None` without a boost.
+    pub speed_boost_until: Option<u64>,
+    /// The simulation step at which the rapid fire ends, `None` without rapid fire.
+    pub rapid_fire_until: Option<u64>,
+    /// The number of actuator commands and queries received during the current step.
+    pub commands_this_step: u32,
+    /// The actuator commands sent with a sequence number, applied at the start of the next step.
+    pub pending_actuators: ActuatorQueue,
+    /// Whether the game UI drives the entity with the keyboard: the actuator commands of clients and the AI are ignored.
+    pub keyboard_controlled: bool,
 }
 
 impl Entity {
-    /// Creates a new `Entity`.
+    /// Creates a new `Entity`, controlled by a player until `is_ai` is set.
     ///
     /// # Parameters
     /// - `name`: The name of the entity.
     /// - `physics_engine`: A mutable reference to the physics engine.
-    /// - `is_ai`: A boolean indicating whether the entity is controlled by AI.
+    /// - `config`: The match rules giving the starting health, the fire cooldown and the contact force threshold.
+    /// - `arena`: The arena the starting position is drawn in.
+    /// - `rng`: The random number generator drawing the starting position and velocity.
+    /// - `now`: The simulated time, the fire cooldown and the spawn protection start from it.
     ///
     /// # Returns
     /// A new instance of `Entity`.
     ///
     /// # Examples
     /// ```
-    /// let entity = Entity::new("Player1".to_string(), &mut physics_engine, false);
+    /// # use std::time::Duration;
+    /// # use universal_rust_server_software::entities::entity::Entity;
+    /// # use universal_rust_server_software::game_logic::arena_config::ArenaConfig;
+    /// # use universal_rust_server_software::game_logic::game_config::GameConfig;
+    /// # use universal_rust_server_software::physics::physics::PhysicsEngine;
+    /// let mut physics_engine = PhysicsEngine::default();
+    /// let entity = Entity::new(1, "Player1".to_string(), &mut physics_engine, &GameConfig::default(), &ArenaConfig::default(), &mut rand::rng(), Duration::ZERO);
+    /// assert_eq!(entity.name, "Player1");
     /// ```
-    pub fn new(id: u32, name: String, physics_engine: &mut PhysicsEngine, is_ai: bool) -> Self {
-        let mut rng = rand::rng();
-        let random_x = rng.random_range(10.0..1190.0);
-        let random_y = rng.random_range(10.0..990.0);
+    pub fn new(
+        id: u32,
+        name: String,
+        physics_engine: &mut PhysicsEngine,
+        config: &GameConfig,
+        arena: &ArenaConfig,
+        rng: &mut impl Rng,
+        now: Duration,
+    ) -> Self {
+        let (random_x, random_y) = arena.random_position(rng, 10.0);
         let vx = rng.random_range(-100.0..100.0);
         let vy = rng.random_range(-100.0..100.0);
 
+        let mut entity = Self::new_at(id, name, physics_engine, config, (random_x, random_y), 0.0, now);
+        physics_engine.bodies[entity.handle].set_linvel(vector![vx, vy], true);
+        entity.vx = vx;
+        entity.vy = vy;
+        entity
+    }
+
+    /// Creates a new `Entity` at a given pose, standing still.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the entity.
+    /// - `physics_engine`: A mutable reference to the physics engine.
+    /// - `config`: The match rules giving the starting health, the fire cooldown and the contact force threshold.
+    /// - `position`: The starting position, which the caller checks is free.
+    /// - `angle`: The direction the entity faces, in radians.
+    /// - `now`: The simulated time, the fire cooldown and the spawn protection start from it.
+    ///
+    /// # Returns
+    /// A new instance of `Entity`, with no velocity.
+    pub fn new_at(
+        id: u32,
+        name: String,
+        physics_engine: &mut PhysicsEngine,
+        config: &GameConfig,
+        position: (f32, f32),
+        angle: f32,
+        now: Duration,
+    ) -> Self {
+        let (x, y) = position;
+        // Le CCD empêche un corps rapide de traverser les murs fins de l'arène
         let rigid_body = RigidBodyBuilder::dynamic()
-            .translation(vector![random_x, random_y])
-            .linvel(vector![vx, vy])
+            .translation(vector![x, y])
+            .rotation(angle)
+            .ccd_enabled(true)
+            .user_data(BodyIdentity::Entity(id).encode())
             .build();
-        let collider = ColliderBuilder::cuboid(10.0, 10.0)
+        let collider = ColliderBuilder::cuboid(AppDefines::ENTITY_HALF_SIZE, AppDefines::ENTITY_HALF_SIZE)
             .restitution(0.0)
-            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+            .contact_force_event_threshold(config.contact_force_threshold)
             .build();
 
         let handle = physics_engine.bodies.insert(rigid_body);
@@ -64,21 +171,37 @@ impl Entity {
             id,
             name,
             score: 0,
+            breakdown: ScoreBreakdown::default(),
             handle,
-            is_ai,
-            last_shot: Instant::now(),
-            x: random_x,
-            y: random_y,
-            self_orientation: 0.0,
-            gun_orientation: 0.0,
-            target_x: random_x,
-            target_y: random_y,
+            is_ai: false,
+            live_bullets: 0,
+            fire_cooldown: Duration::from_millis(config.fire_cooldown_ms),
+            weapon: BulletKind::Standard,
+            spawn_protected_until: now + Duration::from_millis(config.spawn_protection_ms),
+            x,
+            y,
+            self_orientation: angle as f64,
+            vx: 0.0,
+            vy: 0.0,
+            turrets: vec![Turret::new((0.0, 0.0), now)],
+            target_x: x,
+            target_y: y,
             color: egui::Color32::LIGHT_GRAY,
             motor_left: 0.5,
             motor_right: 0.5,
-            gun_trigger: 0.0,
-            gun_traverse: 0.5,
-            health: 1,
+            shield: 0.0,
+            energy: AppDefines::SHIELD_MAX_ENERGY,
+            fire_energy: config.fire_energy_max,
+            health: config.starting_health,
+            armor: config.starting_armor(),
+            recent_hits: VecDeque::with_capacity(AppDefines::RECENT_HITS),
+            hits_taken: 0,
+            team: None,
+            speed_boost_until: None,
+            rapid_fire_until: None,
+            commands_this_step: 0,
+            pending_actuators: ActuatorQueue::default(),
+            keyboard_controlled: false,
         }
     }
 
@@ -89,4 +212,373 @@ impl Entity {
     pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
         self.color = egui::Color32::from_rgb(r, g, b);
     }
+
+    /// Clamps an actuator value to the range `ACTUATOR_MIN..=ACTUATOR_MAX`.
+    ///
+    /// # Returns
+    /// The clamped value, or `None` if the value is NaN or infinite.
+    pub fn clamp_actuator(value: f32) -> Option<f32> {
+        value.is_finite().then(|| value.clamp(AppDefines::ACTUATOR_MIN, AppDefines::ACTUATOR_MAX))
+    }
+
+    /// Sets an actuator, clamping its value. The gun actuators drive the first turret.
+    ///
+    /// # Parameters
+    /// - `code`: The code of the actuator command, e.g. `MotL`.
+    /// - `value`: The requested value.
+    ///
+    /// # Returns
+    /// The value applied, or `None` if the value is NaN or infinite, or the code is not an actuator.
+    pub fn set_actuator(&mut self, code: &str, value: f32) -> Option<f32> {
+        let actuator = match code {
+            AppDefines::ACTUATOR_MOTOR_LEFT => &mut self.motor_left,
+            AppDefines::ACTUATOR_MOTOR_RIGHT => &mut self.motor_right,
+            AppDefines::ACTUATOR_SHIELD => &mut self.shield,
+            _ => return self.set_turret_actuator(0, code, value),
+        };
+        let value = Self::clamp_actuator(value)?;
+        *actuator = value;
+        Some(value)
+    }
+
+    /// Sets a gun actuator of one turret, clamping its value.
+    ///
+    /// # Parameters
+    /// - `turret`: The index of the turret in `turrets`.
+    /// - `code`: The code of the actuator command, `GunTrig` or `GunTrav`.
+    /// - `value`: The requested value.
+    ///
+    /// # Returns
+    /// The value applied, or `None` if the value is NaN or infinite, the turret does not exist
+    /// or the code is not a gun actuator.
+    pub fn set_turret_actuator(&mut self, turret: usize, code: &str, value: f32) -> Option<f32> {
+        let value = Self::clamp_actuator(value)?;
+        let turret = self.turrets.get_mut(turret)?;
+        let actuator = match code {
+            AppDefines::ACTUATOR_GUN_TRIGGER => &mut turret.trigger,
+            AppDefines::ACTUATOR_GUN_TRAVERSE => &mut turret.traverse,
+            _ => return None,
+        };
+        *actuator = value;
+        Some(value)
+    }
+
+    /// Sets an actuator now, or queues it for the next step when the client numbered the command.
+    ///
+    /// A command without a number drops the queued ones of the same actuator, so that the last command
+    /// received wins as before the numbering.
+    ///
+    /// # Parameters
+    /// - `code`: The code of the actuator command, e.g. `MotL`.
+    /// - `turret`: The index of the turret of a gun actuator, 0 for the others.
+    /// - `value`: The value, already clamped.
+    /// - `seq`: The sequence number of the command, `None` to set the actuator now.
+    ///
+    /// # Returns
+    /// `Err` with the newest sequence number of the actuator when the numbered command is stale, see `ActuatorQueue`.
+    pub fn command_actuator(&mut self, code: &str, turret: usize, value: f32, seq: Option<u64>) -> Result<(), u64> {
+        let Some(seq) = seq else {
+            self.pending_actuators.cancel(code, turret);
+            if turret == 0 {
+                self.set_actuator(code, value);
+            } else {
+                self.set_turret_actuator(turret, code, value);
+            }
+            return Ok(());
+        };
+        self.pending_actuators.push(PendingActuator { seq, code: code.to_string(), turret, value })
+    }
+
+    /// Applies the actuator commands queued with a sequence number, oldest sequence number first.
+    pub fn apply_pending_actuators(&mut self) {
+        for command in self.pending_actuators.drain() {
+            if command.turret == 0 {
+                self.set_actuator(&command.code, command.value);
+            } else {
+                self.set_turret_actuator(command.turret, &command.code, command.value);
+            }
+        }
+    }
+
+    /// Returns the first turret, the one the sensors and the AI use.
+    pub fn gun(&self) -> &Turret {
+        &self.turrets[0]
+    }
+
+    /// Returns the first turret, the one the sensors and the AI use.
+    pub fn gun_mut(&mut self) -> &mut Turret {
+        &mut self.turrets[0]
+    }
+
+    /// Replaces the turrets of the entity, e.g. to give it two guns.
+    ///
+    /// The new turrets point to the back of the body and wait the remaining cooldown of the first one.
+    ///
+    /// # Parameters
+    /// - `mounts`: The position of each turret relative to the center of the body; a single
+    ///   turret at the center is kept if it is empty.
+    pub fn mount_turrets(&mut self, mounts: &[(f32, f32)]) {
+        let last_shot = self.gun().last_shot;
+        self.turrets = mounts.iter().map(|&mount| Turret::new(mount, last_shot)).collect();
+        if self.turrets.is_empty() {
+            self.turrets.push(Turret::new((0.0, 0.0), last_shot));
+        }
+    }
+
+    /// Returns how long the entity must wait before one of its turrets can fire again.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    ///
+    /// # Returns
+    /// The remaining cooldown, zero if the entity can fire.
+    pub fn fire_cooldown_remaining(&self, now: Duration) -> Duration {
+        let cooldown = self.shot_cooldown();
+        self.turrets.iter()
+            .map(|turret| turret.cooldown_remaining(cooldown, now))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Returns the delay between two shots of each turret, with the weapon of the entity.
+    pub fn shot_cooldown(&self) -> Duration {
+        self.fire_cooldown.mul_f32(self.weapon.cooldown_factor())
+    }
+
+    /// Puts the entity back in the state of a new entity at its current position.
+    ///
+    /// The id, name, color, team, weapon, turret mounts and number of hits taken are kept, and so is the fire cooldown apart from
+    /// the rapid fire, since it carries the difficulty of a bot. The score is left to the caller.
+    ///
+    /// # Parameters
+    /// - `config`: The match rules giving the starting health and armor, the spawn protection and the fire energy.
+    /// - `now`: The current simulated time, the fire cooldown and the spawn protection start from it.
+    pub fn reset(&mut self, config: &GameConfig, now: Duration) {
+        if self.rapid_fire_until.take().is_some() {
+            self.fire_cooldown *= 2;
+        }
+        self.speed_boost_until = None;
+        self.health = config.starting_health;
+        self.armor = config.starting_armor();
+        self.spawn_protected_until = now + Duration::from_millis(config.spawn_protection_ms);
+        self.motor_left = 0.5;
+        self.motor_right = 0.5;
+        for turret in &mut self.turrets {
+            turret.reset(now);
+        }
+        self.shield = 0.0;
+        self.energy = AppDefines::SHIELD_MAX_ENERGY;
+        self.fire_energy = config.fire_energy_max;
+        self.target_x = self.x;
+        self.target_y = self.y;
+        self.recent_hits.clear();
+        self.pending_actuators.clear();
+    }
+
+    /// Returns the health a hit removes once the armor shrugs off its share of the damage.
+    ///
+    /// The damage is rounded to the nearest point, and a hit still removes at least one point.
+    ///
+    /// # Parameters
+    /// - `damage`: The health the hit would remove without armor.
+    ///
+    /// # Returns
+    /// The health removed, 0 for a hit without damage.
+    pub fn mitigate(&self, damage: i32) -> i32 {
+        if damage <= 0 {
+            return 0;
+        }
+        ((damage as f32 * (1.0 - self.armor)).round() as i32).max(1)
+    }
+
+    /// Remembers a bullet that damaged the entity, forgetting the oldest one past `RECENT_HITS`.
+    ///
+    /// # Parameters
+    /// - `tick`: The simulation step of the hit.
+    /// - `bearing`: Where the bullet came from, see `HitRecord::bearing`.
+    /// - `damage`: The health the bullet removed.
+    pub fn record_hit(&mut self, tick: u64, bearing: f32, damage: i32) {
+        self.hits_taken += 1;
+        if self.recent_hits.len() == AppDefines::RECENT_HITS {
+            self.recent_hits.pop_front();
+        }
+        self.recent_hits.push_back(HitRecord { seq: self.hits_taken, tick, bearing, damage });
+    }
+
+    /// Returns whether the entity is still protected after spawning or being repositioned.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn is_spawn_protected(&self, now: Duration) -> bool {
+        now < self.spawn_protected_until
+    }
+
+    /// Starts a shot of a turret if its fire cooldown and the spawn protection are over.
+    ///
+    /// # Parameters
+    /// - `turret`: The index of the turret in `turrets`.
+    /// - `now`: The current simulated time, recorded as the time of the shot.
+    ///
+    /// # Returns
+    /// `true` if the turret fires, `false` if it is still cooling down, the entity is protected
+    /// or the turret does not exist.
+    pub fn try_fire(&mut self, turret: usize, now: Duration) -> bool {
+        if self.is_spawn_protected(now) {
+            return false;
+        }
+        let cooldown = self.shot_cooldown();
+        let Some(turret) = self.turrets.get_mut(turret) else { return false };
+        if !turret.cooldown_remaining(cooldown, now).is_zero() {
+            return false;
+        }
+        turret.last_shot = now;
+        true
+    }
+
+    /// Applies the effect of a power-up.
+    ///
+    /// Picking up a timed effect that is already running extends it.
+    ///
+    /// # Parameters
+    /// - `kind`: The effect of the power-up.
+    /// - `until`: The simulation step at which a timed effect ends.
+    /// - `max_health`: The health a heal cannot go past.
+    pub fn pick_up(&mut self, kind: PowerUpKind, until: u64, max_health: i32) {
+        match kind {
+            PowerUpKind::Heal => {
+                // Ne retire jamais de vie à une entité déjà au-dessus du maximum
+                self.health = (self.health + AppDefines::POWER_UP_HEAL).min(max_health.max(self.health));
+            }
+            PowerUpKind::SpeedBoost => self.speed_boost_until = Some(until),
+            PowerUpKind::RapidFire => {
+                if self.rapid_fire_until.is_none() {
+                    self.fire_cooldown /= 2;
+                }
+                self.rapid_fire_until = Some(until);
+            }
+            PowerUpKind::ArmorUp => self.armor = (self.armor + AppDefines::POWER_UP_ARMOR).min(AppDefines::ARMOR_MAX),
+        }
+    }
+
+    /// Ends the timed effects that ran out.
+    ///
+    /// # Parameters
+    /// - `tick`: The current simulation step.
+    pub fn expire_effects(&mut self, tick: u64) {
+        if self.speed_boost_until.is_some_and(|until| tick >= until) {
+            self.speed_boost_until = None;
+        }
+        if self.rapid_fire_until.is_some_and(|until| tick >= until) {
+            self.rapid_fire_until = None;
+            self.fire_cooldown *= 2;
+        }
+    }
+
+    /// Returns whether another entity is in the same team.
+    pub fn is_teammate(&self, other: &Entity) -> bool {
+        self.team.is_some() && self.team == other.team
+    }
+
+    /// Returns the collision groups of the entity.
+    ///
+    /// With `teammates_pass_through`, an entity of a team belongs to the group of its team only and
+    /// collides with every other group: its teammates pass through it, while the entities of the other
+    /// teams or without a team, the obstacles, the walls and the bullets, all in every group, still hit it.
+    /// There are 32 groups, so two teams 32 apart share theirs.
+    ///
+    /// # Parameters
+    /// - `config`: The match rules telling whether teammates pass through each other.
+    pub fn collision_groups(&self, config: &GameConfig) -> InteractionGroups {
+        match self.team.filter(|_| config.teammates_pass_through) {
+            Some(team) => {
+                let group = Group::from_bits_truncate(1 << (team as u32 % 32));
+                InteractionGroups::new(group, Group::ALL ^ group)
+            }
+            None => InteractionGroups::all(),
+        }
+    }
+
+    /// Gives the collider of the entity its collision groups, see `collision_groups`.
+    ///
+    /// # Parameters
+    /// - `physics_engine`: The physics engine holding the body of the entity.
+    /// - `config`: The match rules telling whether teammates pass through each other.
+    pub fn update_collision_groups(&self, physics_engine: &mut PhysicsEngine, config: &GameConfig) {
+        let groups = self.collision_groups(config);
+        let Some(body) = physics_engine.bodies.get(self.handle) else { return };
+        for &collider in body.colliders() {
+            physics_engine.colliders[collider].set_collision_groups(groups);
+        }
+    }
+
+    /// Returns whether the shield is raised and still has energy.
+    pub fn shield_active(&self) -> bool {
+        self.shield > 0.5 && self.energy > 0.0
+    }
+
+    /// Drains the shield energy while it is raised, or regenerates it while lowered.
+    ///
+    /// The shield drops automatically when the energy runs out.
+    ///
+    /// # Parameters
+    /// - `dt`: The elapsed simulation time in seconds.
+    pub fn update_shield(&mut self, dt: f32) {
+        if self.shield_active() {
+            self.energy -= AppDefines::SHIELD_DRAIN_PER_SECOND * dt;
+        } else {
+            self.energy += AppDefines::SHIELD_REGEN_PER_SECOND * dt;
+        }
+        self.energy = self.energy.clamp(0.0, AppDefines::SHIELD_MAX_ENERGY);
+        if self.energy <= 0.0 {
+            self.shield = 0.0;
+        }
+    }
+
+    /// Regenerates the fire energy, less what the motors drain if the match rules say so.
+    ///
+    /// # Parameters
+    /// - `dt`: The elapsed simulation time in seconds.
+    /// - `config`: The match rules giving the maximum, the regeneration and the motor drain.
+    pub fn update_fire_energy(&mut self, dt: f32, config: &GameConfig) {
+        let mut change = config.fire_energy_regen_per_sec;
+        if config.motors_drain_energy {
+            // Chaque moteur compte pour moitié, en marche avant comme en marche arrière
+            let output = (self.motor_left - 0.5).abs() + (self.motor_right - 0.5).abs();
+            change -= AppDefines::MOTOR_ENERGY_DRAIN_PER_SEC * output;
+        }
+        self.fire_energy = (self.fire_energy + change * dt).clamp(0.0, config.fire_energy_max.max(0.0));
+    }
+
+    /// Turns each gun toward its commanded traverse, see `Turret::turn`.
+    ///
+    /// # Parameters
+    /// - `max_angle`: The largest rotation allowed, in radians.
+    pub fn turn_guns(&mut self, max_angle: f32) {
+        for turret in &mut self.turrets {
+            turret.turn(max_angle);
+        }
+    }
+
+    /// Blocks a bullet with the shield, draining the energy of one hit.
+    pub fn block_hit(&mut self) {
+        self.energy = (self.energy - AppDefines::SHIELD_DRAIN_PER_HIT).max(0.0);
+        if self.energy <= 0.0 {
+            self.shield = 0.0;
+        }
+    }
+
+    /// Counts one command against the entity's budget for the current step.
+    ///
+    /// # Parameters
+    /// - `budget`: The maximum number of commands per step, 0 meaning unlimited.
+    ///
+    /// # Returns
+    /// `true` if the command may be executed, `false` if the budget is exhausted.
+    pub fn consume_command_budget(&mut self, budget: u32) -> bool {
+        if budget > 0 && self.commands_this_step >= budget {
+            return false;
+        }
+        self.commands_this_step += 1;
+        true
+    }
 }
@@ -1 +1,3 @@
+pub mod actuator_queue;
 pub mod entity;
+pub mod turret;
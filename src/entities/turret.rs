@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use rapier2d::prelude::*;
+use crate::app_defines::AppDefines;
+use crate::game_logic::GameLogic;
+
+/// A gun of an entity, aimed and fired on its own.
+///
+/// An entity has at least one turret. The actuator commands without a turret index drive the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turret {
+    /// The position of the turret relative to the center of the body, in the frame of the body.
+    pub mount: (f32, f32),
+    /// The applied traverse of the gun relative to the body, in turns within `[0, 1)`.
+    /// It follows `traverse` at the traverse rate of the match.
+    pub orientation: f64,
+    /// The commanded traverse of the gun, in turns.
+    pub traverse: f32,
+    pub trigger: f32,
+    /// The simulated time of the last shot of the turret, see `GameLogic::sim_time`.
+    pub last_shot: Duration,
+}
+
+impl Turret {
+    /// Creates a turret pointing to the back of the body, its trigger released.
+    ///
+    /// # Parameters
+    /// - `mount`: The position of the turret relative to the center of the body.
+    /// - `now`: The simulated time, the fire cooldown starts from it.
+    pub fn new(mount: (f32, f32), now: Duration) -> Self {
+        Self { mount, orientation: 0.5, traverse: 0.5, trigger: 0.0, last_shot: now }
+    }
+
+    /// Puts the turret back in the state of a new turret on the same mount.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time, the fire cooldown starts from it.
+    pub fn reset(&mut self, now: Duration) {
+        *self = Turret::new(self.mount, now);
+    }
+
+    /// Returns how long the turret must wait before it can fire again.
+    ///
+    /// # Parameters
+    /// - `cooldown`: The delay between two shots of the entity.
+    /// - `now`: The current simulated time.
+    pub fn cooldown_remaining(&self, cooldown: Duration, now: Duration) -> Duration {
+        cooldown.saturating_sub(now.saturating_sub(self.last_shot))
+    }
+
+    /// Turns the gun toward the commanded traverse, the short way round.
+    ///
+    /// # Parameters
+    /// - `max_angle`: The largest rotation allowed, in radians.
+    pub fn turn(&mut self, max_angle: f32) {
+        let max_turns = max_angle as f64 / std::f64::consts::TAU;
+        // Écart ramené dans [-0.5, 0.5) pour passer par le plus court chemin
+        let difference = (self.traverse as f64 - self.orientation + 0.5).rem_euclid(1.0) - 0.5;
+        self.orientation = (self.orientation + difference.clamp(-max_turns, max_turns)).rem_euclid(1.0);
+    }
+
+    /// Returns the angle the gun points at, in radians, see `GameLogic::barrel_angle`.
+    ///
+    /// # Parameters
+    /// - `body_rotation`: The rotation of the body carrying the turret, in radians.
+    pub fn angle(&self, body_rotation: f32) -> f32 {
+        GameLogic::barrel_angle(body_rotation, self.orientation as f32)
+    }
+
+    /// Returns where the turret stands in the arena.
+    ///
+    /// # Parameters
+    /// - `body`: The rigid body carrying the turret.
+    pub fn position(&self, body: &RigidBody) -> Vector<f32> {
+        body.translation() + body.rotation() * vector![self.mount.0, self.mount.1]
+    }
+
+    /// Returns where the bullets of the turret leave it and in which direction.
+    ///
+    /// # Parameters
+    /// - `body`: The rigid body carrying the turret.
+    ///
+    /// # Returns
+    /// The position of the muzzle in the arena, and the angle of the gun in radians.
+    pub fn muzzle(&self, body: &RigidBody) -> (Vector<f32>, f32) {
+        let angle = self.angle(body.rotation().angle());
+        // Au bord de la coque : la balle traverse son tireur, voir `Bullet::pass_through_shooter`
+        let position = self.position(body) + vector![angle.cos(), angle.sin()] * AppDefines::ENTITY_HALF_SIZE;
+        (position, angle)
+    }
+}
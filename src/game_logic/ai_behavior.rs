@@ -0,0 +1,615 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::GameLogic;
+use crate::obstacles::ObstacleShape;
+
+/// The name of the behavior used when none is selected.
+pub const WANDERER: &str = "wanderer";
+
+/// The distances to the walls and obstacles along the rays an AI entity casts ahead of its body.
+///
+/// A ray that hits nothing reports its full length.
+#[derive(Debug, Clone, Copy)]
+pub struct Clearance {
+    /// Along the ray `BOT_FEELER_ANGLE` to the left of the body.
+    pub left: f32,
+    /// Along the ray straight ahead of the body.
+    pub ahead: f32,
+    /// Along the ray `BOT_FEELER_ANGLE` to the right of the body.
+    pub right: f32,
+    /// The length of the rays, see `GameConfig::bot_ray_length`.
+    pub ray_length: f32,
+    /// The distance under which a wall straight ahead makes the entity turn fully away,
+    /// see `GameConfig::bot_avoidance_radius`.
+    pub avoidance_radius: f32,
+}
+
+/// An obstacle of the arena, as an AI entity sees it.
+#[derive(Debug, Clone, Copy)]
+pub struct ObstacleView {
+    pub position: Vector<f32>,
+    /// The distance from the center of the obstacle to its farthest point.
+    pub radius: f32,
+    pub shape: ObstacleShape,
+}
+
+/// The point of the terrain closest to a position, see `GameLogic::closest_surface`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfacePoint {
+    pub point: Vector<f32>,
+    /// The distance from the position to `point`, 0 inside an obstacle.
+    pub distance: f32,
+    /// The index of the obstacle among the ones searched, `None` for a wall of the arena.
+    pub obstacle: Option<usize>,
+}
+
+/// What an AI behavior observes at each control tick.
+pub struct AiView {
+    /// The position of the controlled entity.
+    pub position: Vector<f32>,
+    /// The rotation of the body, in radians.
+    pub rotation: f32,
+    /// The health of the entity.
+    pub health: i32,
+    /// The direction the gun points at, in radians, see `GameLogic::gun_angle`.
+    pub gun_angle: f32,
+    /// The position of the target of the entity, if any: the closest player, or the closest
+    /// other bot when no player is left, see `GameLogic::bot_target`.
+    pub target: Option<Vector<f32>>,
+    /// The id of the target, `None` without a target.
+    pub target_id: Option<u32>,
+    /// Whether the target is within the fire range of the match and in sight.
+    pub target_in_sight: bool,
+    /// The positions of the other entities within the fire range of the match and in sight.
+    pub enemies: Vec<Vector<f32>>,
+    /// The obstacles of the arena.
+    pub obstacles: Vec<ObstacleView>,
+    /// The size of the arena.
+    pub arena: ArenaConfig,
+    /// The walls and obstacles ahead of the entity.
+    pub clearance: Clearance,
+    /// The simulated time, see `GameLogic::sim_time`.
+    pub now: Duration,
+}
+
+/// The actuator values an AI behavior sets for one control tick, the same a client sends.
+///
+/// `update_ai` writes them to the entity and the step applies them as it does for players.
+/// The gun turns toward the traverse at the traverse rate of the match. When the trigger is
+/// pulled, the aim error of the match is added to the traverse, and the shot only happens
+/// if the fire cooldown of the entity is over.
+#[derive(Debug, Clone, Copy)]
+pub struct AiCommand {
+    /// The left motor, from 0 for full reverse to 1 for full speed ahead, 0.5 stops it.
+    pub motor_left: f32,
+    /// The right motor, as the left one.
+    pub motor_right: f32,
+    /// The traverse of the gun relative to the body, in turns, see `aim`.
+    pub gun_traverse: f32,
+    /// The gun fires when the trigger is above 0.5.
+    pub gun_trigger: f32,
+    /// The position the entity is heading for, `None` to keep the previous one.
+    pub destination: Option<Vector<f32>>,
+    /// The entity the behavior chose to shoot at, shown by the AI debug view of the game UI.
+    /// `None` when the behavior does not pick a particular entity.
+    pub enemy: Option<u32>,
+}
+
+/// Drives an AI entity: decides its actuator values from what it observes.
+pub trait AiBehavior: Send {
+    /// Runs one control tick for the entity.
+    ///
+    /// # Parameters
+    /// - `view`: What the entity observes.
+    /// - `rng`: The random number generator of the game. Behaviors draw from it only,
+    ///   so that a match replays identically from the same map seed.
+    fn decide(&mut self, view: &AiView, rng: &mut StdRng) -> AiCommand;
+
+    /// Returns why the behavior can no longer drive the entity, `None` while it can.
+    ///
+    /// Checked after each tick: the entity of a failed behavior is stopped and left alone.
+    fn failure(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// How well an AI entity plays, whatever its behavior.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// The handicaps of an AI difficulty, see `AiDifficulty::params`.
+#[derive(Debug, Clone, Copy)]
+pub struct DifficultyParams {
+    /// The factor applied to the aim error of the match, see `GameConfig::bot_aim_error_deg`.
+    pub aim_error_factor: f32,
+    /// The number of control ticks an entity must have been in sight before the trigger can be pulled.
+    pub reaction_ticks: u32,
+    /// The factor applied to the fire cooldown of the match.
+    pub fire_cooldown_factor: f32,
+    /// The factor applied to the motor values, around the stop value.
+    pub speed_factor: f32,
+}
+
+impl AiDifficulty {
+    /// The difficulties, from the easiest.
+    pub const ALL: [AiDifficulty; 3] = [AiDifficulty::Easy, AiDifficulty::Normal, AiDifficulty::Hard];
+
+    /// Returns the handicaps of the difficulty. All the tuning of the difficulties is here.
+    pub fn params(self) -> DifficultyParams {
+        match self {
+            AiDifficulty::Easy => DifficultyParams { aim_error_factor: 3.0, reaction_ticks: 30, fire_cooldown_factor: 2.0, speed_factor: 0.6 },
+            AiDifficulty::Normal => DifficultyParams { aim_error_factor: 1.0, reaction_ticks: 10, fire_cooldown_factor: 1.0, speed_factor: 1.0 },
+            AiDifficulty::Hard => DifficultyParams { aim_error_factor: 0.25, reaction_ticks: 0, fire_cooldown_factor: 0.75, speed_factor: 1.25 },
+        }
+    }
+
+    /// Returns the name of the difficulty, as in the protocol.
+    pub fn name(self) -> &'static str {
+        match self {
+            AiDifficulty::Easy => "EASY",
+            AiDifficulty::Normal => "NORMAL",
+            AiDifficulty::Hard => "HARD",
+        }
+    }
+
+    /// Returns the difficulty with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        AiDifficulty::ALL.into_iter().find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Builds a new behavior instance for an AI entity.
+pub type BehaviorFactory = Box<dyn Fn() -> Box<dyn AiBehavior> + Send>;
+
+/// The registry of the AI behaviors selectable at runtime, by name.
+///
+/// The built-in behaviors are "wanderer", "hunter", "camper" and "defensive";
+/// embedders can register their own factories. "random-walk" and "aggressive" are
+/// other names of "wanderer" and "hunter".
+pub struct BehaviorRegistry {
+    factories: Vec<(String, BehaviorFactory)>,
+    /// The other names of the behaviors, with the name they stand for.
+    aliases: Vec<(String, String)>,
+}
+
+impl Default for BehaviorRegistry {
+    fn default() -> Self {
+        let mut registry = Self { factories: Vec::new(), aliases: Vec::new() };
+        registry.register(WANDERER, Box::new(|| Box::new(Wanderer::new())));
+        registry.register("hunter", Box::new(|| Box::new(Hunter::new())));
+        registry.register("camper", Box::new(|| Box::new(Camper::new())));
+        registry.register("defensive", Box::new(|| Box::new(Defensive::new())));
+        registry.alias("random-walk", WANDERER);
+        registry.alias("aggressive", "hunter");
+        registry
+    }
+}
+
+impl BehaviorRegistry {
+    /// Registers a behavior factory, replacing any factory with the same name.
+    ///
+    /// # Parameters
+    /// - `name`: The name used to select the behavior.
+    /// - `factory`: Builds a new behavior instance.
+    pub fn register(&mut self, name: &str, factory: BehaviorFactory) {
+        self.factories.retain(|(existing, _)| existing != name);
+        self.factories.push((name.to_string(), factory));
+    }
+
+    /// Gives another name to a behavior. A registered name always wins over an alias.
+    ///
+    /// # Parameters
+    /// - `alias`: The other name.
+    /// - `name`: The name of the behavior it stands for.
+    pub fn alias(&mut self, alias: &str, name: &str) {
+        self.aliases.retain(|(existing, _)| existing != alias);
+        self.aliases.push((alias.to_string(), name.to_string()));
+    }
+
+    /// Returns the names of the registered behaviors, in registration order, without the aliases.
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Returns the registered name of a behavior, given its name or one of its aliases.
+    ///
+    /// # Returns
+    /// The name, or `None` if no factory is registered under that name.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        let name = self.aliases.iter()
+            .find(|(alias, _)| alias == name && !self.factories.iter().any(|(existing, _)| existing == name))
+            .map_or(name, |(_, target)| target.as_str());
+        self.factories.iter().find(|(existing, _)| existing == name).map(|(existing, _)| existing.as_str())
+    }
+
+    /// Returns whether a behavior is registered under the given name or alias.
+    pub fn contains(&self, name: &str) -> bool {
+        self.resolve(name).is_some()
+    }
+
+    /// Builds a new instance of the named behavior.
+    ///
+    /// # Returns
+    /// The behavior, or `None` if no factory is registered under that name or alias.
+    pub fn create(&self, name: &str) -> Option<Box<dyn AiBehavior>> {
+        let name = self.resolve(name)?;
+        self.factories
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, factory)| factory())
+    }
+}
+
+/// Returns the angle from one position to another, in radians.
+pub fn angle_to(from: Vector<f32>, to: Vector<f32>) -> f32 {
+    let direction = to - from;
+    direction.y.atan2(direction.x)
+}
+
+/// Returns the gun traverse pointing the gun of the entity in a direction.
+///
+/// # Parameters
+/// - `view`: What the entity observes.
+/// - `angle`: The direction, in radians.
+pub fn aim(view: &AiView, angle: f32) -> f32 {
+    // Même convention que GameLogic::gun_angle : le canon tire vers l'arrière du corps
+    ((angle - view.rotation - PI) / TAU).rem_euclid(1.0)
+}
+
+/// Returns the motor values turning the body toward a heading and driving along it.
+///
+/// The entity turns on the spot when the heading is behind it, and speeds up as it faces it.
+///
+/// # Parameters
+/// - `rotation`: The rotation of the body, in radians.
+/// - `heading`: The direction to drive in, in radians.
+/// - `throttle`: The fraction of the full speed to drive at, from 0 to 1.
+///
+/// # Returns
+/// The left and right motor values.
+pub fn drive_toward(rotation: f32, heading: f32, throttle: f32) -> (f32, f32) {
+    // Écart ramené dans [-π, π) pour tourner par le plus court chemin
+    let error = (heading - rotation + PI).rem_euclid(TAU) - PI;
+    let turn = (error * 2.0).clamp(-1.0, 1.0);
+    let forward = throttle.clamp(0.0, 1.0) * error.cos().max(0.0);
+    ((0.5 + (forward - turn) * 0.5).clamp(0.0, 1.0), (0.5 + (forward + turn) * 0.5).clamp(0.0, 1.0))
+}
+
+/// The motor values stopping the entity.
+const STOP: (f32, f32) = (0.5, 0.5);
+
+/// The distance under which an entity has reached its destination.
+pub(crate) const ARRIVAL_DISTANCE: f32 = 15.0;
+
+/// Drives an AI entity to a destination, around the walls and obstacles.
+///
+/// The closer a wall is to one of the rays of the entity, the more it turns away: a
+/// quarter turn for a wall straight ahead within the avoidance radius, half as much for a
+/// wall on a side. Walls beyond the destination are ignored, and so are walls the entity
+/// already turns away from to reach its destination. In front of a wall, it turns toward the
+/// freer side, and keeps that side until it reaches its destination, so that it does not
+/// hesitate. An entity that barely moves for `BOT_STUCK_MS`, wedged
+/// against a corner for instance, backs off for a moment then heads for a random detour
+/// point before going on.
+#[derive(Default)]
+pub struct Navigator {
+    /// The side the entity turns to in front of a wall, 1 for the left, -1 for the right, 0 before the first wall.
+    avoidance_side: f32,
+    /// Where and when the entity last made progress.
+    progress: Option<(Vector<f32>, Duration)>,
+    /// The point the entity heads for first, after it got stuck.
+    detour: Option<Vector<f32>>,
+    /// The simulated time until which the entity backs off, after it got stuck.
+    backing_off_until: Duration,
+}
+
+impl Navigator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the motor values stopping the entity where it stands.
+    pub fn stop(&mut self) -> (f32, f32) {
+        self.avoidance_side = 0.0;
+        self.progress = None;
+        STOP
+    }
+
+    /// Returns the motor values driving toward a destination.
+    ///
+    /// # Parameters
+    /// - `view`: What the entity observes.
+    /// - `destination`: The position to reach.
+    /// - `throttle`: The fraction of the full speed to drive at, from 0 to 1.
+    /// - `rng`: The random number generator of the game, drawing the detour points.
+    ///
+    /// # Returns
+    /// The left and right motor values, stopped once the destination is reached.
+    pub fn drive(&mut self, view: &AiView, destination: Vector<f32>, throttle: f32, rng: &mut StdRng) -> (f32, f32) {
+        if self.detour.is_some_and(|detour| (detour - view.position).norm() <= ARRIVAL_DISTANCE) {
+            self.detour = None;
+        }
+        let goal = self.detour.unwrap_or(destination);
+        let remaining = (goal - view.position).norm();
+        if remaining <= ARRIVAL_DISTANCE {
+            return self.stop();
+        }
+
+        // Une entité qui n'avance plus malgré ses moteurs fait un détour
+        match self.progress {
+            Some((position, since)) if (view.position - position).norm() < AppDefines::BOT_STUCK_DISTANCE => {
+                if view.now.saturating_sub(since) >= Duration::from_millis(AppDefines::BOT_STUCK_MS) {
+                    let (x, y) = view.arena.random_position(rng, 10.0);
+                    self.detour = Some(vector![x, y]);
+                    self.avoidance_side = 0.0;
+                    self.backing_off_until = view.now + Duration::from_millis(AppDefines::BOT_STUCK_MS / 2);
+                    self.progress = Some((view.position, view.now));
+                }
+            }
+            _ => self.progress = Some((view.position, view.now)),
+        }
+        if view.now < self.backing_off_until {
+            let reverse = 0.5 - throttle.clamp(0.0, 1.0) * 0.5;
+            return (reverse, reverse);
+        }
+
+        let clearance = view.clearance;
+        let urgency = |distance: f32| {
+            if distance >= remaining {
+                return 0.0;
+            }
+            ((clearance.ray_length - distance) / (clearance.ray_length - clearance.avoidance_radius).max(f32::EPSILON)).clamp(0.0, 1.0)
+        };
+        // Les angles croissent vers la gauche : un mur à droite fait tourner à gauche
+        let mut turn = (urgency(clearance.right) - urgency(clearance.left)) * 0.5 * FRAC_PI_2;
+        if urgency(clearance.ahead) > 0.0 {
+            if self.avoidance_side == 0.0 {
+                self.avoidance_side = if clearance.left >= clearance.right { 1.0 } else { -1.0 };
+            }
+            turn += urgency(clearance.ahead) * FRAC_PI_2 * self.avoidance_side;
+        }
+
+        let goal_heading = angle_to(view.position, goal);
+        let goal_turn = (goal_heading - view.rotation + PI).rem_euclid(TAU) - PI;
+        // La destination se trouve déjà du côté où l'entité s'écarte du mur
+        let heading = if turn == 0.0 || (goal_turn * turn > 0.0 && goal_turn.abs() >= turn.abs()) {
+            goal_heading
+        } else {
+            view.rotation + turn
+        };
+        drive_toward(view.rotation, heading, throttle)
+    }
+}
+
+/// Wanders between random destinations and fires at its target when in sight.
+pub struct Wanderer {
+    destination: Option<Vector<f32>>,
+    /// The simulated time of the last destination change.
+    last_destination_change: Duration,
+    navigator: Navigator,
+}
+
+impl Wanderer {
+    pub fn new() -> Self {
+        Self { destination: None, last_destination_change: Duration::ZERO, navigator: Navigator::new() }
+    }
+}
+
+impl Default for Wanderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AiBehavior for Wanderer {
+    fn decide(&mut self, view: &AiView, rng: &mut StdRng) -> AiCommand {
+        // Randomly change the destination every few seconds
+        if self.destination.is_none() || view.now.saturating_sub(self.last_destination_change).as_secs_f32() > rng.random_range(1.0..3.0) {
+            let (x, y) = view.arena.random_position(rng, 10.0);
+            self.destination = Some(vector![x, y]);
+            self.last_destination_change = view.now;
+        }
+        let destination = self.destination.unwrap_or(view.position);
+        let (motor_left, motor_right) = self.navigator.drive(view, destination, 0.6, rng);
+
+        let gun_angle = view.target.map_or(view.gun_angle, |target| angle_to(view.position, target));
+        AiCommand {
+            motor_left,
+            motor_right,
+            gun_traverse: aim(view, gun_angle),
+            gun_trigger: if view.target_in_sight { 1.0 } else { 0.0 },
+            destination: Some(destination),
+            enemy: None,
+        }
+    }
+}
+
+/// Chases its target, and fires at it when in sight.
+pub struct Hunter {
+    navigator: Navigator,
+}
+
+impl Hunter {
+    /// The distance at which the hunter stops closing in on its target.
+    const STANDOFF: f32 = 150.0;
+
+    pub fn new() -> Self {
+        Self { navigator: Navigator::new() }
+    }
+}
+
+impl Default for Hunter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AiBehavior for Hunter {
+    fn decide(&mut self, view: &AiView, rng: &mut StdRng) -> AiCommand {
+        let Some(target) = view.target else {
+            let (motor_left, motor_right) = self.navigator.stop();
+            return AiCommand {
+                motor_left,
+                motor_right,
+                gun_traverse: aim(view, view.gun_angle),
+                gun_trigger: 0.0,
+                destination: Some(view.position),
+                enemy: None,
+            };
+        };
+
+        // Ne s'arrête à distance que si la cible est en vue, sinon va la débusquer
+        let (motor_left, motor_right) = if view.target_in_sight && (target - view.position).norm() <= Hunter::STANDOFF {
+            self.navigator.stop()
+        } else {
+            self.navigator.drive(view, target, 0.9, rng)
+        };
+
+        AiCommand {
+            motor_left,
+            motor_right,
+            gun_traverse: aim(view, angle_to(view.position, target)),
+            gun_trigger: if view.target_in_sight { 1.0 } else { 0.0 },
+            destination: Some(target),
+            enemy: view.target_id,
+        }
+    }
+}
+
+/// Takes cover beside the closest obstacle and holds there, sweeping the arena with its gun.
+/// It fires at the closest entity in sight.
+pub struct Camper {
+    /// The position the camper holds, picked on its first tick.
+    post: Option<Vector<f32>>,
+    navigator: Navigator,
+}
+
+impl Camper {
+    /// The speed at which the gun sweeps the arena while no target is in sight, in turns per second.
+    const SWEEP_RATE: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self { post: None, navigator: Navigator::new() }
+    }
+
+    /// Returns the position beside the closest obstacle, on the side facing the entity.
+    /// Without obstacles, the entity holds where it stands.
+    fn pick_post(view: &AiView) -> Vector<f32> {
+        let Some(closest) = GameLogic::closest_surface(view.position, &view.obstacles, None) else { return view.position };
+        let obstacle = view.obstacles[closest.obstacle.unwrap_or_default()];
+        // Le poste se tient à deux demi-tailles du bord le plus proche, en s'éloignant du centre
+        let offset = closest.point - obstacle.position;
+        let direction = if offset.norm() > 0.0 { offset.normalize() } else { vector![1.0, 0.0] };
+        let post = closest.point + direction * AppDefines::ENTITY_HALF_SIZE * 2.0;
+        let (x, y) = view.arena.clamp(post.x, post.y, 10.0);
+        vector![x, y]
+    }
+}
+
+impl Default for Camper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AiBehavior for Camper {
+    fn decide(&mut self, view: &AiView, rng: &mut StdRng) -> AiCommand {
+        let post = *self.post.get_or_insert_with(|| Camper::pick_post(view));
+        let (motor_left, motor_right) = self.navigator.drive(view, post, 0.6, rng);
+
+        let closest = view.enemies.iter()
+            .min_by(|a, b| (*a - view.position).norm().total_cmp(&(*b - view.position).norm()));
+        let gun_traverse = match closest {
+            Some(enemy) => aim(view, angle_to(view.position, *enemy)),
+            None => (view.now.as_secs_f32() * Camper::SWEEP_RATE).rem_euclid(1.0),
+        };
+        AiCommand {
+            motor_left,
+            motor_right,
+            gun_traverse,
+            gun_trigger: if closest.is_some() { 1.0 } else { 0.0 },
+            destination: Some(post),
+            enemy: None,
+        }
+    }
+}
+
+/// Keeps its distance from its target and only fires occasionally when it comes near.
+pub struct Defensive {
+    /// The simulated time of the last shot, `None` before the first tick.
+    last_fire: Option<Duration>,
+    navigator: Navigator,
+}
+
+impl Defensive {
+    /// The distance under which the bot backs away from its target.
+    const SAFE_DISTANCE: f32 = 300.0;
+    /// The distance under which the bot fires at its target.
+    const FIRE_RANGE: f32 = 400.0;
+
+    pub fn new() -> Self {
+        Self { last_fire: None, navigator: Navigator::new() }
+    }
+}
+
+impl Default for Defensive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AiBehavior for Defensive {
+    fn decide(&mut self, view: &AiView, rng: &mut StdRng) -> AiCommand {
+        let Some(target) = view.target else {
+            let (motor_left, motor_right) = self.navigator.stop();
+            return AiCommand {
+                motor_left,
+                motor_right,
+                gun_traverse: aim(view, view.gun_angle),
+                gun_trigger: 0.0,
+                destination: Some(view.position),
+                enemy: None,
+            };
+        };
+        let last_fire = *self.last_fire.get_or_insert(view.now);
+
+        let away = view.position - target;
+        let distance = away.norm();
+        let destination = if distance < Defensive::SAFE_DISTANCE && distance > 0.0 {
+            let retreat = view.position + away.normalize() * Defensive::SAFE_DISTANCE;
+            let (x, y) = view.arena.clamp(retreat.x, retreat.y, 10.0);
+            vector![x, y]
+        } else {
+            view.position
+        };
+        let (motor_left, motor_right) = self.navigator.drive(view, destination, 0.45, rng);
+
+        let mut gun_trigger = 0.0;
+        if view.target_in_sight && distance < Defensive::FIRE_RANGE && view.now.saturating_sub(last_fire).as_millis() >= 1500 {
+            self.last_fire = Some(view.now);
+            gun_trigger = 1.0;
+        }
+
+        AiCommand {
+            motor_left,
+            motor_right,
+            gun_traverse: aim(view, angle_to(view.position, target)),
+            gun_trigger,
+            destination: Some(destination),
+            enemy: None,
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rapier2d::prelude::*;
+
+use crate::game_logic::ai_behavior::{aim, angle_to, AiBehavior, AiCommand, AiView, Navigator, ARRIVAL_DISTANCE};
+
+/// The throttle of the entity driving to a waypoint.
+const CRUISE_THROTTLE: f32 = 0.7;
+
+/// How far ahead of the entity a flight aims, away from the closest enemy.
+const FLEE_DISTANCE: f32 = 300.0;
+
+/// A position of a script: a point of the arena, or the name of a waypoint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptPoint {
+    At(f32, f32),
+    /// Looked up among the waypoints when the action runs.
+    Waypoint(String),
+}
+
+/// A condition of a transition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptCondition {
+    /// An enemy in sight is closer than the distance.
+    EnemyWithin(f32),
+    /// The health of the entity is below the value.
+    HealthBelow(i32),
+    /// The entity stands at a waypoint.
+    AtWaypoint(ScriptPoint),
+    /// The condition does not hold.
+    Not(Box<ScriptCondition>),
+}
+
+/// What the entity does while in a state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptAction {
+    /// Drives to a position and stops there.
+    Goto(ScriptPoint),
+    /// Drives from one position to the next, back to the first after the last.
+    Patrol(Vec<ScriptPoint>),
+    /// Points the gun at the closest enemy in sight, or else at the target of the entity.
+    AimNearest,
+    /// Pulls the trigger.
+    Fire,
+    /// Drives away from the closest enemy.
+    Flee,
+}
+
+/// A transition of a state, taken when its condition holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptTransition {
+    pub condition: ScriptCondition,
+    /// The index of the state it leads to.
+    pub to: usize,
+    /// The line of the transition in the script.
+    pub line: usize,
+}
+
+/// A state of a script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptState {
+    pub name: String,
+    /// The actions and their lines in the script, run in order at each tick.
+    pub actions: Vec<(ScriptAction, usize)>,
+    /// The transitions, checked in order before the actions: the first one holding is taken.
+    pub transitions: Vec<ScriptTransition>,
+}
+
+/// A state machine driving an AI entity, read from a script file, see `AiScript::parse`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiScript {
+    /// The named positions of the arena.
+    pub waypoints: BTreeMap<String, (f32, f32)>,
+    /// The states, the first one is where the entity starts.
+    pub states: Vec<ScriptState>,
+}
+
+impl AiScript {
+    /// Reads a script from a file.
+    ///
+    /// # Parameters
+    /// - `path`: The script file.
+    ///
+    /// # Returns
+    /// The script, or why it cannot be read: the file and, for a parse error, the line and the reason.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        AiScript::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Reads a script.
+    ///
+    /// Each line holds one instruction, `#` starts a comment:
+    /// - `waypoint NAME X Y` names a position of the arena;
+    /// - `state NAME` starts a state, the first one is where the entity starts;
+    /// - `when [not] CONDITION -> STATE` adds a transition to the current state, with the conditions
+    ///   `enemy_within DISTANCE`, `health_below HEALTH` and `at_waypoint POINT`;
+    /// - the actions `goto POINT`, `patrol POINT...`, `aim_nearest`, `fire` and `flee` are run by the current state.
+    ///
+    /// A point is a waypoint name or `X,Y`. A state has at most one movement action: `goto`, `patrol`
+    /// or `flee`, and stops the entity without one.
+    ///
+    /// # Parameters
+    /// - `text`: The text of the script.
+    ///
+    /// # Returns
+    /// The script, or the line of the first error and its reason.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut waypoints = BTreeMap::new();
+        let mut states: Vec<ScriptState> = Vec::new();
+        // Les états cibles sont résolus à la fin, un état pouvant mener à un état déclaré plus bas
+        let mut targets: Vec<(usize, usize, String)> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let error = |message: String| format!("line {}: {}", number, message);
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else { continue };
+            let words: Vec<&str> = words.collect();
+
+            match keyword {
+                "waypoint" => {
+                    let [name, x, y] = words[..] else { return Err(error("expected waypoint NAME X Y".to_string())) };
+                    let position = (number_of(x).map_err(&error)?, number_of(y).map_err(&error)?);
+                    if waypoints.insert(name.to_string(), position).is_some() {
+                        return Err(error(format!("waypoint {} is defined twice", name)));
+                    }
+                }
+                "state" => {
+                    let [name] = words[..] else { return Err(error("expected state NAME".to_string())) };
+                    if states.iter().any(|state| state.name == name) {
+                        return Err(error(format!("state {} is defined twice", name)));
+                    }
+                    states.push(ScriptState { name: name.to_string(), actions: Vec::new(), transitions: Vec::new() });
+                }
+                _ => {
+                    let current = states.len().checked_sub(1).ok_or_else(|| error(format!("{} outside of a state", keyword)))?;
+                    let state = &mut states[current];
+                    if keyword == "when" {
+                        let arrow = words.iter().position(|word| *word == "->").ok_or_else(|| error("expected when CONDITION -> STATE".to_string()))?;
+                        let [target] = words[arrow + 1..] else { return Err(error("expected one state after ->".to_string())) };
+                        let condition = parse_condition(&words[..arrow]).map_err(&error)?;
+                        targets.push((current, state.transitions.len(), target.to_string()));
+                        state.transitions.push(ScriptTransition { condition, to: 0, line: number });
+                        continue;
+                    }
+                    let action = parse_action(keyword, &words).map_err(&error)?;
+                    let moves = |action: &ScriptAction| matches!(action, ScriptAction::Goto(_) | ScriptAction::Patrol(_) | ScriptAction::Flee);
+                    if moves(&action) && state.actions.iter().any(|(other, _)| moves(other)) {
+                        return Err(error(format!("state {} already has a movement action", state.name)));
+                    }
+                    state.actions.push((action, number));
+                }
+            }
+        }
+
+        if states.is_empty() {
+            return Err("the script has no state".to_string());
+        }
+        for (state, transition, target) in targets {
+            let line = states[state].transitions[transition].line;
+            let to = states.iter().position(|other| other.name == target)
+                .ok_or_else(|| format!("line {}: unknown state {}", line, target))?;
+            states[state].transitions[transition].to = to;
+        }
+        Ok(AiScript { waypoints, states })
+    }
+}
+
+/// Reads a number of a script.
+fn number_of(word: &str) -> Result<f32, String> {
+    word.parse::<f32>().ok().filter(|value| value.is_finite()).ok_or_else(|| format!("{} is not a number", word))
+}
+
+/// Reads a point of a script: `X,Y` or a waypoint name.
+fn point_of(word: &str) -> Result<ScriptPoint, String> {
+    match word.split_once(',') {
+        Some((x, y)) => Ok(ScriptPoint::At(number_of(x)?, number_of(y)?)),
+        None => Ok(ScriptPoint::Waypoint(word.to_string())),
+    }
+}
+
+/// Reads the condition of a transition, the words between `when` and `->`.
+fn parse_condition(words: &[&str]) -> Result<ScriptCondition, String> {
+    match words {
+        ["not", rest @ ..] => Ok(ScriptCondition::Not(Box::new(parse_condition(rest)?))),
+        ["enemy_within", distance] => Ok(ScriptCondition::EnemyWithin(number_of(distance)?)),
+        ["health_below", health] => {
+            let health = health.parse::<i32>().map_err(|_| format!("{} is not a health", health))?;
+            Ok(ScriptCondition::HealthBelow(health))
+        }
+        ["at_waypoint", point] => Ok(ScriptCondition::AtWaypoint(point_of(point)?)),
+        [] => Err("missing condition".to_string()),
+        [condition, ..] => Err(format!("unknown condition {}, or wrong arguments", condition)),
+    }
+}
+
+/// Reads an action.
+fn parse_action(keyword: &str, words: &[&str]) -> Result<ScriptAction, String> {
+    match (keyword, words) {
+        ("goto", [x, y]) => Ok(ScriptAction::Goto(ScriptPoint::At(number_of(x)?, number_of(y)?))),
+        ("goto", [point]) => Ok(ScriptAction::Goto(point_of(point)?)),
+        ("goto", _) => Err("expected goto X Y or goto POINT".to_string()),
+        ("patrol", []) => Err("expected patrol POINT...".to_string()),
+        ("patrol", points) => Ok(ScriptAction::Patrol(points.iter().map(|point| point_of(point)).collect::<Result<_, _>>()?)),
+        ("aim_nearest", []) => Ok(ScriptAction::AimNearest),
+        ("fire", []) => Ok(ScriptAction::Fire),
+        ("flee", []) => Ok(ScriptAction::Flee),
+        ("aim_nearest" | "fire" | "flee", _) => Err(format!("{} takes no argument", keyword)),
+        _ => Err(format!("unknown instruction {}", keyword)),
+    }
+}
+
+/// Drives an AI entity with a script, see `GameLogic::add_ai_scripted`.
+///
+/// At each control tick the transitions of the current state are checked first, then the
+/// actions of the state, maybe the new one, are run. A point the script cannot resolve, an
+/// unknown waypoint or a position outside the arena, stops the behavior: see `failure`.
+pub struct ScriptedBehavior {
+    script: Arc<AiScript>,
+    /// The index of the current state.
+    state: usize,
+    /// The index of the next point of the patrol of each state, kept when the state is left
+    /// so that a patrol interrupted by a fight goes on where it stopped.
+    patrol_indices: Vec<usize>,
+    navigator: Navigator,
+    /// Why the script stopped, `None` while it runs.
+    failure: Option<String>,
+}
+
+impl ScriptedBehavior {
+    pub fn new(script: Arc<AiScript>) -> Self {
+        let patrol_indices = vec![0; script.states.len()];
+        Self { script, state: 0, patrol_indices, navigator: Navigator::new(), failure: None }
+    }
+
+    /// Returns the name of the current state.
+    pub fn state(&self) -> &str {
+        &self.script.states[self.state].name
+    }
+
+    /// Returns the position of a point, inside the arena.
+    ///
+    /// # Parameters
+    /// - `view`: What the entity observes.
+    /// - `point`: The point.
+    /// - `line`: The line of the script using it, for the error.
+    fn resolve(&self, view: &AiView, point: &ScriptPoint, line: usize) -> Result<Vector<f32>, String> {
+        let (x, y) = match point {
+            ScriptPoint::At(x, y) => (*x, *y),
+            ScriptPoint::Waypoint(name) => *self.script.waypoints.get(name)
+                .ok_or_else(|| format!("line {}: unknown waypoint {}", line, name))?,
+        };
+        if !view.arena.contains_with_margin(x, y, 0.0) {
+            return Err(format!("line {}: ({:.0}, {:.0}) is outside the arena", line, x, y));
+        }
+        Ok(vector![x, y])
+    }
+
+    /// Returns whether a condition holds.
+    fn holds(&self, view: &AiView, condition: &ScriptCondition, line: usize) -> Result<bool, String> {
+        Ok(match condition {
+            ScriptCondition::EnemyWithin(distance) => view.enemies.iter().any(|enemy| (enemy - view.position).norm() <= *distance),
+            ScriptCondition::HealthBelow(health) => view.health < *health,
+            ScriptCondition::AtWaypoint(point) => (self.resolve(view, point, line)? - view.position).norm() <= ARRIVAL_DISTANCE,
+            ScriptCondition::Not(condition) => !self.holds(view, condition, line)?,
+        })
+    }
+
+    /// Runs one control tick: the transitions, then the actions of the state.
+    fn run(&mut self, view: &AiView, rng: &mut StdRng) -> Result<AiCommand, String> {
+        let script = Arc::clone(&self.script);
+        for transition in &script.states[self.state].transitions {
+            if self.holds(view, &transition.condition, transition.line)? {
+                self.state = transition.to;
+                break;
+            }
+        }
+
+        let closest = view.enemies.iter()
+            .min_by(|a, b| (*a - view.position).norm().total_cmp(&(*b - view.position).norm()))
+            .copied();
+        let mut command = AiCommand {
+            motor_left: 0.5,
+            motor_right: 0.5,
+            gun_traverse: aim(view, view.gun_angle),
+            gun_trigger: 0.0,
+            destination: Some(view.position),
+            enemy: None,
+        };
+        let mut moved = false;
+        for (action, line) in &script.states[self.state].actions {
+            let destination = match action {
+                ScriptAction::Goto(point) => Some(self.resolve(view, point, *line)?),
+                ScriptAction::Patrol(points) => {
+                    let mut index = self.patrol_indices[self.state];
+                    let mut point = self.resolve(view, &points[index], *line)?;
+                    // Arrivée au point : la patrouille repart vers le suivant
+                    if (point - view.position).norm() <= ARRIVAL_DISTANCE {
+                        index = (index + 1) % points.len();
+                        point = self.resolve(view, &points[index], *line)?;
+                        self.patrol_indices[self.state] = index;
+                    }
+                    Some(point)
+                }
+                ScriptAction::Flee => closest.or(view.target).map(|enemy| {
+                    let away = view.position - enemy;
+                    let direction = if away.norm() > 0.0 { away.normalize() } else { vector![1.0, 0.0] };
+                    let escape = view.position + direction * FLEE_DISTANCE;
+                    let (x, y) = view.arena.clamp(escape.x, escape.y, 10.0);
+                    vector![x, y]
+                }),
+                ScriptAction::AimNearest => {
+                    if let Some(enemy) = closest.or(view.target) {
+                        command.gun_traverse = aim(view, angle_to(view.position, enemy));
+                    }
+                    None
+                }
+                ScriptAction::Fire => {
+                    command.gun_trigger = 1.0;
+                    None
+                }
+            };
+            if let Some(destination) = destination {
+                let throttle = if matches!(action, ScriptAction::Flee) { 1.0 } else { CRUISE_THROTTLE };
+                (command.motor_left, command.motor_right) = self.navigator.drive(view, destination, throttle, rng);
+                command.destination = Some(destination);
+                moved = true;
+            }
+        }
+        if !moved {
+            (command.motor_left, command.motor_right) = self.navigator.stop();
+        }
+        Ok(command)
+    }
+}
+
+impl AiBehavior for ScriptedBehavior {
+    fn decide(&mut self, view: &AiView, rng: &mut StdRng) -> AiCommand {
+        match self.run(view, rng) {
+            Ok(command) => command,
+            Err(reason) => {
+                self.failure = Some(reason);
+                let (motor_left, motor_right) = self.navigator.stop();
+                AiCommand { motor_left, motor_right, gun_traverse: aim(view, view.gun_angle), gun_trigger: 0.0, destination: None, enemy: None }
+            }
+        }
+    }
+
+    fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+}
@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+
+/// The size of the arena, from its corner at the origin to the opposite wall.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ArenaConfig {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self { width: AppDefines::DEFAULT_ARENA_WIDTH, height: AppDefines::DEFAULT_ARENA_HEIGHT }
+    }
+}
+
+impl ArenaConfig {
+    /// Creates an arena of the given size.
+    ///
+    /// # Parameters
+    /// - `width`, `height`: The size of the arena, at least `ARENA_MIN_SIZE` each.
+    ///
+    /// # Returns
+    /// The arena, or why the size is refused.
+    pub fn new(width: f32, height: f32) -> Result<Self, String> {
+        let arena = Self { width, height };
+        arena.validate()?;
+        Ok(arena)
+    }
+
+    /// Checks that both sides are finite and at least `ARENA_MIN_SIZE` long.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = |side: f32| side.is_finite() && side >= AppDefines::ARENA_MIN_SIZE;
+        if !valid(self.width) || !valid(self.height) {
+            return Err(format!(
+                "invalid arena size {}x{}, both sides must be at least {}",
+                self.width,
+                self.height,
+                AppDefines::ARENA_MIN_SIZE,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the center of the arena.
+    pub fn center(&self) -> (f32, f32) {
+        (self.width / 2.0, self.height / 2.0)
+    }
+
+    /// Returns whether a position lies in the arena, its edges included, NaN excluded.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        self.contains_with_margin(x, y, 0.0)
+    }
+
+    /// Returns whether a position lies in the arena grown by a margin on every side, NaN excluded.
+    ///
+    /// # Parameters
+    /// - `x`, `y`: The position.
+    /// - `margin`: How far past the edges a position still counts as inside.
+    pub fn contains_with_margin(&self, x: f32, y: f32, margin: f32) -> bool {
+        (-margin..=self.width + margin).contains(&x) && (-margin..=self.height + margin).contains(&y)
+    }
+
+    /// Returns the closest position at least `margin` away from the walls.
+    ///
+    /// # Parameters
+    /// - `x`, `y`: The position.
+    /// - `margin`: The distance to keep from the walls.
+    pub fn clamp(&self, x: f32, y: f32, margin: f32) -> (f32, f32) {
+        (x.clamp(margin, self.width - margin), y.clamp(margin, self.height - margin))
+    }
+
+    /// Draws a random position at least `margin` away from the walls.
+    ///
+    /// # Parameters
+    /// - `rng`: The random number generator, drawn from twice: the x first, then the y.
+    /// - `margin`: The distance to keep from the walls.
+    pub fn random_position(&self, rng: &mut impl Rng, margin: f32) -> (f32, f32) {
+        let x = rng.random_range(margin..self.width - margin);
+        let y = rng.random_range(margin..self.height - margin);
+        (x, y)
+    }
+}
+
+impl FromStr for ArenaConfig {
+    type Err = String;
+
+    /// Reads a size written `<width>x<height>`, e.g. `1200x1000`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid arena size '{}', expected <width>x<height>", text);
+        let (width, height) = text.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+        let width = width.trim().parse().map_err(|_| invalid())?;
+        let height = height.trim().parse().map_err(|_| invalid())?;
+        ArenaConfig::new(width, height)
+    }
+}
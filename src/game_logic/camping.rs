@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::game_config::GameConfig;
+
+/// What the anti-camping rule does to an entity at one step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CampingVerdict {
+    /// The points the entity loses.
+    pub points: i32,
+    /// Whether the position of the entity is told to everyone.
+    pub reveal: bool,
+}
+
+/// Watches an entity staying in the same spot, for the anti-camping rule.
+///
+/// An entity camps once it stayed within `camping_radius` of the point it stopped at for
+/// `camping_secs` of simulated time. It then loses `camping_points_per_sec`, a whole point
+/// at a time, until it moves out of the radius, which starts the watch again from there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CampWatch {
+    /// Where the entity stopped.
+    pub anchor: (f32, f32),
+    /// When the entity stopped, in simulated time.
+    pub since: Duration,
+    /// The points owed and not removed from the score yet.
+    owed: f32,
+    /// When the position of the camping entity was last told, `None` before it camps.
+    revealed_at: Option<Duration>,
+}
+
+impl CampWatch {
+    /// Starts watching an entity at a position.
+    ///
+    /// # Parameters
+    /// - `position`: Where the entity stands.
+    /// - `now`: The current simulated time.
+    pub fn new(position: (f32, f32), now: Duration) -> Self {
+        Self { anchor: position, since: now, owed: 0.0, revealed_at: None }
+    }
+
+    /// Returns whether the entity camps.
+    pub fn is_camping(&self) -> bool {
+        self.revealed_at.is_some()
+    }
+
+    /// Follows the entity for one step.
+    ///
+    /// The position is told when the entity starts camping, then every `CAMPING_REVEAL_SECS`
+    /// while it keeps camping if `camping_reveal` is on.
+    ///
+    /// # Parameters
+    /// - `position`: Where the entity stands.
+    /// - `now`: The current simulated time.
+    /// - `dt`: The simulated duration of the step, in seconds.
+    /// - `config`: The match rules of the anti-camping rule.
+    pub fn observe(&mut self, position: (f32, f32), now: Duration, dt: f32, config: &GameConfig) -> CampingVerdict {
+        let (x, y) = self.anchor;
+        if (position.0 - x).hypot(position.1 - y) > config.camping_radius {
+            *self = CampWatch::new(position, now);
+            return CampingVerdict::default();
+        }
+        if now.saturating_sub(self.since) < Duration::from_secs_f32(config.camping_secs.max(0.0)) {
+            return CampingVerdict::default();
+        }
+
+        self.owed += config.camping_points_per_sec.max(0.0) * dt;
+        // Une marge pour que l'arrondi des flottants ne retarde pas un point d'un pas
+        let points = (self.owed + 1e-4).floor();
+        self.owed -= points;
+        let reveal = match self.revealed_at {
+            None => true,
+            Some(revealed_at) => config.camping_reveal && now.saturating_sub(revealed_at) >= Duration::from_secs_f32(AppDefines::CAMPING_REVEAL_SECS),
+        };
+        if reveal {
+            self.revealed_at = Some(now);
+        }
+        CampingVerdict { points: points as i32, reveal }
+    }
+}
@@ -0,0 +1,312 @@
+use std::sync::mpsc::{self, Receiver, SendError, Sender, SyncSender};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::game_logic::render_snapshot::RenderSlot;
+use crate::game_logic::world_snapshot::WorldSlot;
+use crate::game_logic::GameLogic;
+
+/// Why an actuator command was not applied, see `GameCommand::SetActuator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActuatorRefusal {
+    /// The entity is no longer in the game.
+    NoEntity,
+    /// The entity has no turret at the index given.
+    NoTurret,
+    /// The game UI drives the entity with the keyboard, see `Entity::keyboard_controlled`.
+    ControlledLocally,
+    /// A newer command of the same actuator was already applied, with this sequence number.
+    Stale(u64),
+}
+
+/// What the server and the UI ask of the game, carried to the game loop by `GameCommands`.
+///
+/// The frequent commands of the clients have their own variant, the rare ones and the
+/// controls of the UI run as a closure. The answer, if any, comes back on the `reply`
+/// channel of the command, the only place the caller waits for the game loop.
+pub enum GameCommand {
+    /// Sets an actuator of an entity, see `Entity::command_actuator`.
+    SetActuator {
+        entity_id: u32,
+        /// The actuator, one of the `ACTUATOR_*` codes.
+        code: &'static str,
+        turret: usize,
+        /// The value, already clamped by `Entity::clamp_actuator`.
+        value: f32,
+        /// The sequence number of the command, `None` when it is not numbered.
+        seq: Option<u64>,
+        /// Receives the outcome, `None` when nobody waits for it, as for the UDP datagrams.
+        reply: Option<SyncSender<Result<(), ActuatorRefusal>>>,
+    },
+    /// Charges commands of a client against the budget of its entity for the step, before they
+    /// are run, see `Entity::consume_command_budget`.
+    ChargeBudget {
+        entity_id: u32,
+        /// The number of commands to charge.
+        count: u32,
+        /// The number of commands the entity may issue per step, not 0.
+        budget: u32,
+        /// Receives the number of commands granted, the first ones; all of them if the entity is gone.
+        reply: SyncSender<u32>,
+    },
+    /// Drives a ghost camera with a motor actuator, see `GhostCamera::motor_left`.
+    SteerCamera {
+        camera_id: u32,
+        /// `ACTUATOR_MOTOR_LEFT` or `ACTUATOR_MOTOR_RIGHT`.
+        code: &'static str,
+        value: f32,
+        /// Receives `false` if the camera is gone.
+        reply: SyncSender<bool>,
+    },
+    /// Adds an entity at a free spawn point, see `GameLogic::add_entity`.
+    Spawn {
+        name: String,
+        /// Receives the id of the new entity.
+        reply: SyncSender<u32>,
+    },
+    /// Removes an entity, see `GameLogic::remove_entity_by_id`.
+    Remove {
+        entity_id: u32,
+        /// Told once the entity is gone from the world snapshot.
+        reply: SyncSender<()>,
+    },
+    /// Casts the rays of a LIDAR scan, see `GameLogic::lidar_scan`.
+    Lidar {
+        entity_id: u32,
+        rays: usize,
+        range: f32,
+        reply: SyncSender<Option<Vec<f32>>>,
+    },
+    /// Lists the entities in a field of view, see `GameLogic::field_of_view`.
+    FieldOfView {
+        entity_id: u32,
+        /// In radians.
+        half_angle: f32,
+        range: f32,
+        line_of_sight: bool,
+        reply: SyncSender<Option<Vec<(String, f32, f32)>>>,
+    },
+    /// Reads the game, without changing it.
+    Query(Box<dyn FnOnce(&GameLogic) + Send>),
+    /// Changes the game; the world snapshot is published again afterwards.
+    Update(Box<dyn FnOnce(&mut GameLogic) + Send>),
+}
+
+impl GameCommand {
+    /// Runs the command and sends its answer. A caller that stopped waiting is ignored.
+    ///
+    /// # Parameters
+    /// - `logic`: The game logic, owned by the game loop.
+    ///
+    /// # Returns
+    /// Whether the command may have changed what the world snapshot shows.
+    pub fn apply(self, logic: &mut GameLogic) -> bool {
+        match self {
+            GameCommand::SetActuator { entity_id, code, turret, value, seq, reply } => {
+                let outcome = GameCommand::set_actuator(logic, entity_id, code, turret, value, seq);
+                if let Some(reply) = reply {
+                    let _ = reply.send(outcome);
+                }
+                false
+            }
+            GameCommand::ChargeBudget { entity_id, count, budget, reply } => {
+                let granted = match logic.get_entity_mut(entity_id) {
+                    Some(entity) => (0..count).take_while(|_| entity.consume_command_budget(budget)).count() as u32,
+                    // La commande elle-même répondra que l'entité n'est plus là
+                    None => count,
+                };
+                let _ = reply.send(granted);
+                false
+            }
+            GameCommand::SteerCamera { camera_id, code, value, reply } => {
+                let steered = logic.ghost_camera_mut(camera_id).map(|camera| {
+                    if code == crate::app_defines::AppDefines::ACTUATOR_MOTOR_LEFT {
+                        camera.motor_left = value;
+                    } else {
+                        camera.motor_right = value;
+                    }
+                });
+                let _ = reply.send(steered.is_some());
+                false
+            }
+            // L'ajout et le retrait publient eux-mêmes le monde
+            GameCommand::Spawn { name, reply } => {
+                let _ = reply.send(logic.add_entity(name));
+                false
+            }
+            GameCommand::Remove { entity_id, reply } => {
+                logic.remove_entity_by_id(entity_id);
+                let _ = reply.send(());
+                false
+            }
+            GameCommand::Lidar { entity_id, rays, range, reply } => {
+                let _ = reply.send(logic.lidar_scan(entity_id, rays, range));
+                false
+            }
+            GameCommand::FieldOfView { entity_id, half_angle, range, line_of_sight, reply } => {
+                let _ = reply.send(logic.field_of_view(entity_id, half_angle, range, line_of_sight));
+                false
+            }
+            GameCommand::Query(read) => {
+                read(logic);
+                false
+            }
+            GameCommand::Update(change) => {
+                change(logic);
+                true
+            }
+        }
+    }
+
+    /// Applies an actuator command to an entity, already charged to its command budget.
+    ///
+    /// # Returns
+    /// Why the command was not applied, if it was not.
+    fn set_actuator(logic: &mut GameLogic, entity_id: u32, code: &str, turret: usize, value: f32, seq: Option<u64>) -> Result<(), ActuatorRefusal> {
+        let entity = logic.get_entity_mut(entity_id).ok_or(ActuatorRefusal::NoEntity)?;
+        if turret >= entity.turrets.len() {
+            return Err(ActuatorRefusal::NoTurret);
+        }
+        // Le clavier de l'UI et le client ne se disputent pas l'entité : le client attend qu'elle soit rendue
+        if entity.keyboard_controlled {
+            return Err(ActuatorRefusal::ControlledLocally);
+        }
+        entity.command_actuator(code, turret, value, seq).map_err(ActuatorRefusal::Stale)
+    }
+}
+
+/// The way into the game for every thread but the game loop: the commands are sent to the loop,
+/// which runs them between two steps, and the state is read from the snapshots it publishes.
+///
+/// While the loop runs, it is the only thread locking the game logic, so a slow client or a slow
+/// frame of the UI never delays a step. The mutex stays as the container of the game logic: it is
+/// built and loaded before the loop starts and shut down after it stops, and the tests reach into it.
+/// Without a running loop, before `GameLoop::spawn` or after it stopped, the commands are applied
+/// at once under the lock, so the callers need not know whether the loop runs.
+///
+/// A command waits behind the step in progress, at most a tick; the snapshots never wait.
+#[derive(Clone)]
+pub struct GameCommands {
+    game_logic: Arc<Mutex<GameLogic>>,
+    /// The way to the running loop, `None` while no loop takes the commands.
+    sender: Arc<RwLock<Option<Sender<GameCommand>>>>,
+    world: WorldSlot,
+    render: RenderSlot,
+}
+
+impl GameCommands {
+    /// Creates the way into a game logic, with no loop taking the commands yet.
+    ///
+    /// # Parameters
+    /// - `game_logic`: The game logic, stepped by the loop once it runs.
+    pub fn new(game_logic: Arc<Mutex<GameLogic>>) -> Self {
+        let (world, render) = {
+            let logic = game_logic.lock().unwrap();
+            (logic.world_slot(), logic.render_slot())
+        };
+        GameCommands { game_logic, sender: Arc::new(RwLock::new(None)), world, render }
+    }
+
+    /// Returns the game logic, for the game loop to own.
+    pub(crate) fn game_logic(&self) -> &Arc<Mutex<GameLogic>> {
+        &self.game_logic
+    }
+
+    /// Returns where the world snapshots are published, see `WorldSnapshot`.
+    pub fn world(&self) -> &WorldSlot {
+        &self.world
+    }
+
+    /// Returns where the render snapshots are published, see `RenderSnapshot`.
+    pub fn render(&self) -> &RenderSlot {
+        &self.render
+    }
+
+    /// Opens the channel of the commands, for the game loop to take them from.
+    ///
+    /// # Returns
+    /// The end the loop receives the commands from.
+    pub fn open(&self) -> Receiver<GameCommand> {
+        let (sender, receiver) = mpsc::channel();
+        *self.sender.write().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Closes the channel of the commands: the next ones are applied under the lock.
+    ///
+    /// No command is sent once it returns, so the loop can drain the channel and stop.
+    pub fn close(&self) {
+        *self.sender.write().unwrap() = None;
+    }
+
+    /// Sends a command to the game loop, or applies it at once if no loop runs.
+    ///
+    /// # Parameters
+    /// - `command`: The command, answered on its own reply channel.
+    pub fn send(&self, command: GameCommand) {
+        let sender = self.sender.read().unwrap();
+        let command = match sender.as_ref() {
+            Some(loop_end) => match loop_end.send(command) {
+                Ok(()) => return,
+                // La boucle s'est arrêtée sans fermer le canal : la commande revient
+                Err(SendError(command)) => command,
+            },
+            None => command,
+        };
+        drop(sender);
+        let mut logic = self.game_logic.lock().unwrap();
+        if command.apply(&mut logic) {
+            logic.publish_world();
+        }
+    }
+
+    /// Reads the game between two steps.
+    ///
+    /// # Parameters
+    /// - `read`: Reads what the caller needs from the game logic.
+    ///
+    /// # Returns
+    /// What `read` returned.
+    pub fn query<R: Send + 'static>(&self, read: impl FnOnce(&GameLogic) -> R + Send + 'static) -> R {
+        let (reply, answer) = mpsc::sync_channel(1);
+        self.send(GameCommand::Query(Box::new(move |logic| {
+            let _ = reply.send(read(logic));
+        })));
+        answer.recv().expect("the game loop dropped a command")
+    }
+
+    /// Changes the game between two steps and waits for the change to be made.
+    ///
+    /// # Parameters
+    /// - `change`: Changes the game logic.
+    ///
+    /// # Returns
+    /// What `change` returned.
+    pub fn update<R: Send + 'static>(&self, change: impl FnOnce(&mut GameLogic) -> R + Send + 'static) -> R {
+        let (reply, answer) = mpsc::sync_channel(1);
+        self.send(GameCommand::Update(Box::new(move |logic| {
+            let _ = reply.send(change(logic));
+        })));
+        answer.recv().expect("the game loop dropped a command")
+    }
+
+    /// Changes the game between two steps, without waiting.
+    ///
+    /// # Parameters
+    /// - `change`: Changes the game logic.
+    pub fn post(&self, change: impl FnOnce(&mut GameLogic) + Send + 'static) {
+        self.send(GameCommand::Update(Box::new(change)));
+    }
+
+    /// Sends a command answered on a reply channel, and waits for the answer.
+    ///
+    /// # Parameters
+    /// - `command`: Builds the command around the end the answer is sent to.
+    ///
+    /// # Returns
+    /// The answer.
+    pub fn ask<R>(&self, command: impl FnOnce(SyncSender<R>) -> GameCommand) -> R {
+        let (reply, answer) = mpsc::sync_channel(1);
+        self.send(command(reply));
+        answer.recv().expect("the game loop dropped a command")
+    }
+}
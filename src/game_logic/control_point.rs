@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::SQRT_2;
+
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::entities::entity::Entity;
+use crate::game_logic::game_config::GameConfig;
+use crate::game_logic::map_file::MapControlPoint;
+use crate::physics::body_identity::BodyIdentity;
+use crate::physics::physics::PhysicsEngine;
+
+/// Who fights over the control points: a team, or an entity without a team.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Team(u8),
+    Entity(u32),
+}
+
+impl Side {
+    /// Returns the side of an entity: its team if it has one, itself otherwise.
+    pub fn of(entity: &Entity) -> Self {
+        match entity.team {
+            Some(team) => Side::Team(team),
+            None => Side::Entity(entity.id),
+        }
+    }
+
+    /// Returns the side as the protocol writes it: `T` and the team, or `E` and the id of the entity.
+    pub fn code(&self) -> String {
+        match self {
+            Side::Team(team) => format!("T{}", team),
+            Side::Entity(id) => format!("E{}", id),
+        }
+    }
+
+    /// Reads a side written by `code`, `None` for any other text.
+    pub fn parse(code: &str) -> Option<Self> {
+        if let Some(team) = code.strip_prefix('T') {
+            team.parse().ok().map(Side::Team)
+        } else {
+            code.strip_prefix('E')?.parse().ok().map(Side::Entity)
+        }
+    }
+}
+
+/// What a control point did at one step, see `ControlPoint::advance`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ControlVerdict {
+    /// The side that just captured the point.
+    pub captured: Option<Side>,
+    /// The points earned by each entity of the owner inside the point.
+    pub points: i32,
+}
+
+/// A circular zone of the map, scored in the control point mode.
+///
+/// The side with the most entities inside the point captures it in `control_capture_secs`,
+/// after undoing the capture of the previous owner, and once it holds the point each of its entities
+/// inside earns `control_points_per_sec`, a whole point at a time. A point with no one inside
+/// or contested, two sides or more tied for the most entities, neither moves nor scores.
+#[derive(Debug, Clone)]
+pub struct ControlPoint {
+    pub name: String,
+    pub position: (f32, f32),
+    pub radius: f32,
+    pub collider_handle: ColliderHandle,
+    /// The side holding the point or capturing it, `None` until a side enters it.
+    pub owner: Option<Side>,
+    /// How far the owner captured the point, from 0 to 1: the point is held at 1.
+    pub progress: f32,
+    /// The entities the sensor saw entering and not leaving yet.
+    sensed: HashSet<u32>,
+    /// The points earned by the owner and not added to the scores yet.
+    owed: f32,
+}
+
+impl ControlPoint {
+    /// Creates a control point of the map and adds its sensor to the physics engine.
+    ///
+    /// # Parameters
+    /// - `point`: The control point of the map file.
+    /// - `physics_engine`: The physics engine the sensor is added to.
+    pub fn new(point: &MapControlPoint, physics_engine: &mut PhysicsEngine) -> Self {
+        let collider_handle = physics_engine.insert_sensor(
+            SharedShape::ball(point.radius),
+            vector![point.x, point.y],
+            BodyIdentity::ControlPoint,
+        );
+        Self {
+            name: point.name.clone(),
+            position: (point.x, point.y),
+            radius: point.radius,
+            collider_handle,
+            owner: None,
+            progress: 0.0,
+            sensed: HashSet::new(),
+            owed: 0.0,
+        }
+    }
+
+    /// Removes the sensor of the point from the physics engine.
+    pub fn remove(self, physics_engine: &mut PhysicsEngine) {
+        physics_engine.remove_sensor(self.collider_handle);
+    }
+
+    /// Returns the point as a map file writes it.
+    pub fn to_map(&self) -> MapControlPoint {
+        MapControlPoint { name: self.name.clone(), x: self.position.0, y: self.position.1, radius: self.radius }
+    }
+
+    /// Returns whether the owner captured the point.
+    pub fn is_held(&self) -> bool {
+        self.progress >= 1.0
+    }
+
+    /// Gives the point back to no one, as at the start of a round.
+    pub fn reset(&mut self) {
+        self.owner = None;
+        self.progress = 0.0;
+        self.owed = 0.0;
+    }
+
+    /// Follows an entity entering or leaving the sensor of the point.
+    ///
+    /// # Parameters
+    /// - `id`: The id of the entity.
+    /// - `inside`: Whether the intersection started rather than stopped.
+    pub fn sense(&mut self, id: u32, inside: bool) {
+        if inside {
+            self.sensed.insert(id);
+        } else {
+            self.sensed.remove(&id);
+        }
+    }
+
+    /// Returns the entities inside the point.
+    ///
+    /// The sensor sees the body of an entity touching the circle, but not an entity moved by the
+    /// game rather than by the physics until the next step, nor one removed from the arena: an entity
+    /// whose center is in the circle is inside, one the sensor saw is inside while its body can still
+    /// touch the circle. The entities no longer in the arena are forgotten.
+    ///
+    /// # Parameters
+    /// - `entities`: The id, the side and the position of every entity of the arena.
+    pub fn occupants(&mut self, entities: &[(u32, Side, (f32, f32))]) -> Vec<(u32, Side)> {
+        self.sensed.retain(|id| entities.iter().any(|(entity, ..)| entity == id));
+        // Le coin du corps d'une entité est à cette distance de son centre
+        let reach = self.radius + AppDefines::ENTITY_HALF_SIZE * SQRT_2;
+        entities.iter()
+            .filter(|(id, _, (x, y))| {
+                let distance = (x - self.position.0).hypot(y - self.position.1);
+                distance <= self.radius || (self.sensed.contains(id) && distance <= reach)
+            })
+            .map(|(id, side, _)| (*id, *side))
+            .collect()
+    }
+
+    /// Returns the side with the most entities inside the point, `None` when it is empty or contested.
+    ///
+    /// # Parameters
+    /// - `occupants`: The entities inside the point, see `occupants`.
+    pub fn majority(occupants: &[(u32, Side)]) -> Option<Side> {
+        let mut counts: HashMap<Side, usize> = HashMap::new();
+        for (_, side) in occupants {
+            *counts.entry(*side).or_insert(0) += 1;
+        }
+        let most = counts.values().copied().max()?;
+        let mut leaders = counts.into_iter().filter(|(_, count)| *count == most);
+        match (leaders.next(), leaders.next()) {
+            (Some((side, _)), None) => Some(side),
+            _ => None,
+        }
+    }
+
+    /// Advances the capture of the point by one step.
+    ///
+    /// # Parameters
+    /// - `majority`: The side with the most entities inside, see `majority`.
+    /// - `dt`: The simulated duration of the step, in seconds.
+    /// - `config`: The match rules giving the capture time and the points.
+    pub fn advance(&mut self, majority: Option<Side>, dt: f32, config: &GameConfig) -> ControlVerdict {
+        let Some(side) = majority else { return ControlVerdict::default() };
+        let rate = if config.control_capture_secs > 0.0 { dt / config.control_capture_secs } else { 1.0 };
+        if self.owner != Some(side) {
+            // Un autre camp défait d'abord la prise du précédent
+            if self.owner.is_some() && self.progress > 0.0 {
+                self.progress -= rate;
+                if self.progress < 1e-4 {
+                    self.progress = 0.0;
+                }
+                return ControlVerdict::default();
+            }
+            self.owner = Some(side);
+            self.progress = 0.0;
+            self.owed = 0.0;
+        }
+        if !self.is_held() {
+            self.progress += rate;
+            // Une marge pour que l'arrondi des flottants ne retarde pas la prise d'un pas
+            if self.progress + 1e-4 < 1.0 {
+                return ControlVerdict::default();
+            }
+            self.progress = 1.0;
+            return ControlVerdict { captured: Some(side), points: 0 };
+        }
+
+        self.owed += config.control_points_per_sec.max(0.0) * dt;
+        let points = (self.owed + 1e-4).floor();
+        self.owed -= points;
+        ControlVerdict { captured: None, points: points as i32 }
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::observer::GameEvent;
+
+/// An event kept in the queue, numbered in the order it happened.
+#[derive(Debug, Clone)]
+pub struct QueuedEvent {
+    /// The number of the event, starting at 1.
+    pub seq: u64,
+    /// The simulation step at which the event happened.
+    pub step: u64,
+    /// The event.
+    pub event: GameEvent,
+    /// A description of the event with the names of the entities, for display.
+    pub text: String,
+}
+
+/// The last arena events, read by the UI and the clients at their own pace.
+///
+/// Each consumer keeps a cursor, the number of the last event it read. The queue
+/// keeps at most `EVENT_QUEUE_CAPACITY` events: a consumer reading too rarely
+/// misses the oldest ones instead of making the queue grow.
+#[derive(Debug, Clone)]
+pub struct EventQueue {
+    events: VecDeque<QueuedEvent>,
+    /// The number of the last event pushed, 0 before the first one.
+    last_seq: u64,
+}
+
+impl EventQueue {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self { events: VecDeque::new(), last_seq: 0 }
+    }
+
+    /// Adds an event, dropping the oldest one if the queue is full.
+    ///
+    /// # Parameters
+    /// - `step`: The simulation step at which the event happened.
+    /// - `event`: The event.
+    /// - `text`: A description of the event, for display.
+    pub fn push(&mut self, step: u64, event: GameEvent, text: String) {
+        if self.events.len() >= AppDefines::EVENT_QUEUE_CAPACITY {
+            self.events.pop_front();
+        }
+        self.last_seq += 1;
+        self.events.push_back(QueuedEvent { seq: self.last_seq, step, event, text });
+    }
+
+    /// Drops every event. The numbering goes on, so the cursors of the consumers stay valid.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Returns the number of the last event pushed, the cursor of a consumer up to date.
+    pub fn cursor(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Returns the number of events kept.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns whether no event is kept.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns the events a consumer has not read yet, oldest first.
+    ///
+    /// # Parameters
+    /// - `cursor`: The number of the last event the consumer read.
+    pub fn since(&self, cursor: u64) -> impl Iterator<Item = &QueuedEvent> {
+        self.events.iter().filter(move |queued| queued.seq > cursor)
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
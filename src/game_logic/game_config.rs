@@ -0,0 +1,285 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+
+/// How the rounds of a match are scored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    /// The entities score by hitting and destroying each other.
+    #[default]
+    Deathmatch,
+    /// The entities also score by holding the control points of the map, see `ControlPoint`.
+    ControlPoint,
+}
+
+impl GameMode {
+    /// Returns the name of the mode, as written in the configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Deathmatch => "deathmatch",
+            GameMode::ControlPoint => "control_point",
+        }
+    }
+}
+
+/// The match rules that are not about scoring bonuses.
+///
+/// Changes apply to the entities spawned and bullets fired afterwards:
+/// existing entities keep their current health.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// How the rounds are scored, see `GameMode`.
+    pub game_mode: GameMode,
+    /// Whether bullets hurt the teammates of the shooter.
+    pub friendly_fire: bool,
+    /// Whether the entities of a team pass through each other, still colliding with everything else,
+    /// see `Entity::collision_groups`. Bullets hit teammates either way.
+    pub teammates_pass_through: bool,
+    /// The health of a new entity.
+    pub starting_health: i32,
+    /// The armor of a new or respawned entity in deathmatch, see `Entity::armor`.
+    pub deathmatch_starting_armor: f32,
+    /// The armor of a new or respawned entity in control point.
+    pub control_point_starting_armor: f32,
+    /// Whether the armor also shrugs off the damage of ramming and of the damage zones of the map,
+    /// not only that of the bullets.
+    pub armor_blocks_ramming_and_hazards: bool,
+    /// Whether armor power-ups are spawned with the other kinds, see `PowerUpKind::ArmorUp`.
+    pub armor_power_ups: bool,
+    /// The health removed by a bullet hit.
+    pub bullet_damage: i32,
+    /// The speed of the bullets.
+    pub bullet_speed: f32,
+    /// The radius of the bullets.
+    pub bullet_radius: f32,
+    /// Whether the bullets use continuous collision detection, so that fast bullets
+    /// cannot pass through the thin walls of the arena between two steps.
+    pub bullet_ccd: bool,
+    /// The maximum number of bullets of an entity flying at the same time, 0 for no cap.
+    pub max_bullets_per_entity: u32,
+    /// Whether an entity firing at the cap replaces its oldest bullet, instead of not firing.
+    pub replace_oldest_bullet: bool,
+    /// The time a bullet flies before it disappears, in simulated milliseconds.
+    pub bullet_lifetime_ms: u64,
+    /// The number of times a bullet bounces off walls and obstacles before it is destroyed.
+    pub bullet_bounces: u8,
+    /// The distance a bullet travels before its damage falls to `damage_falloff_min`, 0 for a damage
+    /// that does not fall with the distance, see `BulletKind::damage`.
+    pub damage_falloff_range: f32,
+    /// The share of its damage a bullet keeps once it traveled `damage_falloff_range`, from 0 to 1.
+    pub damage_falloff_min: f32,
+    /// The time a new bullet passes through its shooter, in simulated milliseconds.
+    pub shooter_grace_ms: u64,
+    /// Whether bullets pass through their shooter for their whole flight, bounces included.
+    pub bullets_ignore_shooter: bool,
+    /// The time a new bullet cannot damage its shooter, in simulated milliseconds. After it,
+    /// a bullet touching its shooter hits it as any other entity, see `self_hit_penalty`.
+    pub self_hit_grace_ms: u64,
+    /// The delay between two shots of a new entity, in milliseconds.
+    pub fire_cooldown_ms: u64,
+    /// The fire energy of a full entity, see `Entity::fire_energy`.
+    pub fire_energy_max: f32,
+    /// The fire energy regenerated per simulated second.
+    pub fire_energy_regen_per_sec: f32,
+    /// The fire energy a shot costs, 0 for shots limited by the cooldown alone.
+    pub shot_energy_cost: f32,
+    /// Whether the motors drain the fire energy in proportion to their output, see `MOTOR_ENERGY_DRAIN_PER_SEC`.
+    pub motors_drain_energy: bool,
+    /// The largest deviation of a bullet from the aim of a still shooter, in radians.
+    pub base_spread: f32,
+    /// The deviation added per unit of linear speed of the shooter, in radians.
+    pub spread_per_speed: f32,
+    /// The deviation added per radian per second of angular speed of the shooter, in radians.
+    pub spread_per_angular_speed: f32,
+    /// How fast the guns turn toward their commanded traverse, in radians per second.
+    pub gun_traverse_rate: f32,
+    /// The points earned by the shooter for each hit.
+    pub points_per_hit: i32,
+    /// The points earned by the shooter for destroying an entity, on top of the hit.
+    pub points_per_kill: i32,
+    /// The points lost by an entity when it is destroyed.
+    pub death_penalty: i32,
+    /// The points lost by a shooter for hitting a teammate, instead of earning the points of a hit.
+    pub friendly_hit_penalty: i32,
+    /// The points lost by an entity for hitting itself, with its own bullet or its own mine.
+    pub self_hit_penalty: i32,
+    /// The health removed by a mine at the center of its blast, less further away.
+    pub mine_damage: i32,
+    /// The radius of the blast of a mine.
+    pub mine_blast_radius: f32,
+    /// The maximum number of mines an entity can have in the arena.
+    pub max_mines: u32,
+    /// The time during which a new or repositioned entity can neither be hurt nor fire, in simulated milliseconds.
+    pub spawn_protection_ms: u64,
+    /// The standard deviation of the aim of the bots, in degrees, 0 for a perfect aim.
+    pub bot_aim_error_deg: f32,
+    /// The distance under which the bots fire at their target, when they see it.
+    pub bot_fire_range: f32,
+    /// How far ahead the bots look for walls and obstacles.
+    pub bot_ray_length: f32,
+    /// The distance to a wall or an obstacle under which a bot turns fully away from it.
+    pub bot_avoidance_radius: f32,
+    /// Whether entities colliding hard enough damage each other.
+    pub ramming_enabled: bool,
+    /// The health removed from two colliding entities per unit of relative speed, shared between them.
+    pub ramming_damage_per_speed: f32,
+    /// The contact force above which a collision of an entity is reported, and counts as ramming.
+    /// Lower values report the gentle pushes too.
+    pub contact_force_threshold: f32,
+    /// Whether a safe zone shrinks during each round, hurting the entities outside it, see `Zone`.
+    pub zone_enabled: bool,
+    /// The delay between the start of a round and the start of the zone shrinking, in simulated seconds.
+    pub zone_delay_secs: f32,
+    /// The time the zone takes to shrink to its final radius, in simulated seconds.
+    pub zone_shrink_secs: f32,
+    /// The radius the zone shrinks to.
+    pub zone_final_radius: f32,
+    /// The health removed per simulated second from an entity outside the zone.
+    /// Neither the shield nor the spawn protection block it.
+    pub zone_damage_per_sec: f32,
+    /// Whether the damage zones of the map hurt the entities under spawn protection, see `Hazard`.
+    /// The shield never blocks them.
+    pub hazards_ignore_spawn_protection: bool,
+    /// The simulated duration of a round in seconds, 0 for no time limit.
+    pub match_duration_secs: u32,
+    /// The score ending a round when an entity reaches it, 0 for no target.
+    pub score_target: i32,
+    /// The time a side alone in a control point takes to capture it, in simulated seconds, see `ControlPoint`.
+    pub control_capture_secs: f32,
+    /// The points earned per simulated second by each entity inside a control point its side holds.
+    pub control_points_per_sec: f32,
+    /// Whether an entity staying in the same spot loses points, see `CampWatch`.
+    pub camping_enabled: bool,
+    /// The distance an entity must move away from where it stopped to stop camping.
+    pub camping_radius: f32,
+    /// The time an entity stays in the same spot before it camps, in simulated seconds.
+    pub camping_secs: f32,
+    /// The points a camping entity loses per simulated second.
+    pub camping_points_per_sec: f32,
+    /// Whether the position of a camping entity is told to everyone every `CAMPING_REVEAL_SECS`,
+    /// instead of only when it starts camping.
+    pub camping_reveal: bool,
+    /// Whether a name already taken is given with the first free `-2`, `-3`… suffix, instead of being refused.
+    pub auto_suffix_names: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            game_mode: GameMode::Deathmatch,
+            friendly_fire: false,
+            teammates_pass_through: false,
+            starting_health: AppDefines::DEFAULT_STARTING_HEALTH,
+            deathmatch_starting_armor: AppDefines::DEFAULT_STARTING_ARMOR,
+            control_point_starting_armor: AppDefines::DEFAULT_STARTING_ARMOR,
+            armor_blocks_ramming_and_hazards: false,
+            armor_power_ups: false,
+            bullet_damage: AppDefines::DEFAULT_BULLET_DAMAGE,
+            bullet_speed: AppDefines::DEFAULT_BULLET_SPEED,
+            bullet_radius: AppDefines::DEFAULT_BULLET_RADIUS,
+            bullet_ccd: true,
+            bullet_lifetime_ms: AppDefines::DEFAULT_BULLET_LIFETIME_MS,
+            bullet_bounces: AppDefines::DEFAULT_BULLET_BOUNCES,
+            damage_falloff_range: AppDefines::DEFAULT_DAMAGE_FALLOFF_RANGE,
+            damage_falloff_min: AppDefines::DEFAULT_DAMAGE_FALLOFF_MIN,
+            shooter_grace_ms: AppDefines::DEFAULT_SHOOTER_GRACE_MS,
+            bullets_ignore_shooter: false,
+            self_hit_grace_ms: AppDefines::DEFAULT_SELF_HIT_GRACE_MS,
+            max_bullets_per_entity: AppDefines::DEFAULT_MAX_BULLETS_PER_ENTITY,
+            replace_oldest_bullet: false,
+            fire_cooldown_ms: AppDefines::DEFAULT_FIRE_COOLDOWN_MS,
+            fire_energy_max: AppDefines::DEFAULT_FIRE_ENERGY_MAX,
+            fire_energy_regen_per_sec: AppDefines::DEFAULT_FIRE_ENERGY_REGEN_PER_SEC,
+            shot_energy_cost: AppDefines::DEFAULT_SHOT_ENERGY_COST,
+            motors_drain_energy: false,
+            base_spread: AppDefines::DEFAULT_BASE_SPREAD,
+            spread_per_speed: AppDefines::DEFAULT_SPREAD_PER_SPEED,
+            spread_per_angular_speed: AppDefines::DEFAULT_SPREAD_PER_ANGULAR_SPEED,
+            gun_traverse_rate: AppDefines::DEFAULT_GUN_TRAVERSE_RATE,
+            points_per_hit: AppDefines::DEFAULT_POINTS_PER_HIT,
+            points_per_kill: AppDefines::DEFAULT_POINTS_PER_KILL,
+            death_penalty: AppDefines::DEFAULT_DEATH_PENALTY,
+            friendly_hit_penalty: AppDefines::DEFAULT_FRIENDLY_HIT_PENALTY,
+            self_hit_penalty: AppDefines::DEFAULT_SELF_HIT_PENALTY,
+            mine_damage: AppDefines::DEFAULT_MINE_DAMAGE,
+            mine_blast_radius: AppDefines::DEFAULT_MINE_BLAST_RADIUS,
+            max_mines: AppDefines::DEFAULT_MAX_MINES,
+            spawn_protection_ms: AppDefines::DEFAULT_SPAWN_PROTECTION_MS,
+            bot_aim_error_deg: AppDefines::DEFAULT_BOT_AIM_ERROR_DEG,
+            bot_fire_range: AppDefines::DEFAULT_BOT_FIRE_RANGE,
+            bot_ray_length: AppDefines::DEFAULT_BOT_RAY_LENGTH,
+            bot_avoidance_radius: AppDefines::DEFAULT_BOT_AVOIDANCE_RADIUS,
+            ramming_enabled: true,
+            ramming_damage_per_speed: AppDefines::DEFAULT_RAMMING_DAMAGE_PER_SPEED,
+            contact_force_threshold: AppDefines::DEFAULT_CONTACT_FORCE_THRESHOLD,
+            zone_enabled: false,
+            zone_delay_secs: AppDefines::DEFAULT_ZONE_DELAY_SECS,
+            zone_shrink_secs: AppDefines::DEFAULT_ZONE_SHRINK_SECS,
+            zone_final_radius: AppDefines::DEFAULT_ZONE_FINAL_RADIUS,
+            zone_damage_per_sec: AppDefines::DEFAULT_ZONE_DAMAGE_PER_SEC,
+            hazards_ignore_spawn_protection: false,
+            match_duration_secs: AppDefines::DEFAULT_MATCH_DURATION_SECS,
+            score_target: AppDefines::DEFAULT_SCORE_TARGET,
+            control_capture_secs: AppDefines::DEFAULT_CONTROL_CAPTURE_SECS,
+            control_points_per_sec: AppDefines::DEFAULT_CONTROL_POINTS_PER_SEC,
+            camping_enabled: false,
+            camping_radius: AppDefines::DEFAULT_CAMPING_RADIUS,
+            camping_secs: AppDefines::DEFAULT_CAMPING_SECS,
+            camping_points_per_sec: AppDefines::DEFAULT_CAMPING_POINTS_PER_SEC,
+            camping_reveal: false,
+            auto_suffix_names: false,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Returns the armor of a new or respawned entity in the current game mode, within `0..=ARMOR_MAX`.
+    pub fn starting_armor(&self) -> f32 {
+        let armor = match self.game_mode {
+            GameMode::Deathmatch => self.deathmatch_starting_armor,
+            GameMode::ControlPoint => self.control_point_starting_armor,
+        };
+        armor.clamp(0.0, AppDefines::ARMOR_MAX)
+    }
+
+    /// Reads the match rules from a TOML file, as given to `--config`.
+    ///
+    /// The keys are named as the fields, e.g. `bullet_damage = 12`; the rules left out keep their default.
+    ///
+    /// # Parameters
+    /// - `path`: The TOML file.
+    ///
+    /// # Returns
+    /// The rules, or why the file cannot be used: unreadable, not TOML, an unknown key or a value that does not fit.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("the file {} cannot be read: {}", path.display(), e))?;
+        let table: toml::Table = text.parse().map_err(|e| format!("the file {} is not valid TOML: {}", path.display(), e))?;
+        // Une clé mal orthographiée serait sinon ignorée en silence
+        let known = serde_json::to_value(GameConfig::default()).map_err(|e| e.to_string())?;
+        if let Some(key) = table.keys().find(|key| known.get(key.as_str()).is_none()) {
+            return Err(format!("unknown setting {} in {}", key, path.display()));
+        }
+        toml::from_str(&text).map_err(|e| format!("invalid rules in {}: {}", path.display(), e))
+    }
+
+    /// Changes one rule, named as in the serialized configuration.
+    ///
+    /// # Parameters
+    /// - `key`: The name of the field, e.g. `bullet_damage`.
+    /// - `value`: The new value, as JSON: `12`, `0.5`, `true`.
+    ///
+    /// # Returns
+    /// Why the rule was not changed: the field does not exist or the value does not fit it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let mut fields = serde_json::to_value(&*self).map_err(|e| e.to_string())?;
+        let field = fields.get_mut(key).ok_or_else(|| format!("Unknown setting {}", key))?;
+        *field = serde_json::from_str(value).map_err(|_| format!("Invalid value {} for {}", value, key))?;
+        *self = serde_json::from_value(fields).map_err(|e| format!("Invalid value {} for {}: {}", value, key, e))?;
+        Ok(())
+    }
+}
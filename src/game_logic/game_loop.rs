@@ -0,0 +1,322 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::command::{GameCommand, GameCommands};
+use crate::game_logic::GameLogic;
+use crate::server::event_logger::EventLogger;
+use crate::server::metrics::ServerMetrics;
+use crate::types::MessageType;
+
+/// Steps the simulation at a fixed tick rate, on its own thread.
+///
+/// The elapsed time, multiplied by `GameLogic::time_scale`, is added to an
+/// accumulator and one step runs for every whole tick in it, so the simulation
+/// speed does not depend on how often the loop wakes up.
+///
+/// Started by `spawn`, the loop owns the game logic: it is the only thread locking it.
+/// The UI and the server send their commands through `GameCommands`, run between two
+/// steps while the loop waits for the next tick, and read the state from the render
+/// and world snapshots published after each step. A step longer than a tick delays the
+/// commands and is reported once per second. A time scale above 1 the loop cannot keep
+/// up with for `TIME_SCALE_CLAMP_SECS` is lowered to the speed reached.
+pub struct GameLoop {
+    /// The game logic shared with the UI and the server.
+    game_logic: Arc<Mutex<GameLogic>>,
+    /// The duration of one step.
+    tick: Duration,
+    /// The time not simulated yet.
+    accumulator: Duration,
+    /// When the loop last advanced.
+    previous: Instant,
+    /// The number of steps since the achieved tick rate was last measured.
+    steps_in_window: u32,
+    /// When the achieved tick rate was last measured.
+    window_start: Instant,
+    /// The number of steps that held the game logic longer than a tick since `window_start`.
+    overruns: u32,
+    /// The longest time a step held the game logic since `window_start`.
+    longest_hold: Duration,
+    /// The total time the steps held the game logic since `window_start`.
+    total_hold: Duration,
+    /// Whether late time was dropped since `window_start`, the loop falling behind the time scale.
+    fell_behind: bool,
+    /// The number of seconds in a row the loop fell behind, see `TIME_SCALE_CLAMP_SECS`.
+    behind_secs: u32,
+    /// The health metrics of the server, recorded every second, `None` without a server.
+    metrics: Option<ServerMetrics>,
+    /// Writes the game events to the server messages after each step, `None` without a server.
+    event_logger: Option<EventLogger>,
+    /// The commands of the UI and the server, `None` for a loop stepped by `advance` alone.
+    commands: Option<(GameCommands, Receiver<GameCommand>)>,
+}
+
+/// A game loop running on its own thread, see `GameLoop::spawn`.
+pub struct GameLoopHandle {
+    /// Cleared to stop the loop.
+    running: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl GameLoopHandle {
+    /// Stops the loop and waits for the step in progress to end.
+    pub fn stop(self) {
+        self.running.store(false, Ordering::SeqCst);
+        let _ = self.thread.join();
+    }
+}
+
+impl GameLoop {
+    /// Creates a loop stepping at a given rate, and sets the duration of a physics step to match.
+    ///
+    /// # Parameters
+    /// - `game_logic`: The game logic to step.
+    /// - `tick_rate`: The number of steps per second.
+    /// - `now`: When the loop starts.
+    /// - `metrics`: The health metrics of the server to record every second, `None` without a server.
+    /// - `event_logger`: Writes the game events to the server messages, `None` without a server.
+    pub(crate) fn new(game_logic: Arc<Mutex<GameLogic>>, tick_rate: f32, now: Instant, metrics: Option<ServerMetrics>, event_logger: Option<EventLogger>) -> Self {
+        let mut game_loop = Self {
+            game_logic,
+            tick: Duration::ZERO,
+            accumulator: Duration::ZERO,
+            previous: now,
+            steps_in_window: 0,
+            window_start: now,
+            overruns: 0,
+            longest_hold: Duration::ZERO,
+            total_hold: Duration::ZERO,
+            fell_behind: false,
+            behind_secs: 0,
+            metrics,
+            event_logger,
+            commands: None,
+        };
+        // Un pas simule exactement la durée qui sépare deux pas
+        game_loop.set_tick_rate(tick_rate);
+        game_loop
+    }
+
+    /// Creates a loop without a server, stepped by calling `advance`.
+    ///
+    /// # Parameters
+    /// - `game_logic`: The game logic to step.
+    /// - `tick_rate`: The number of steps per second.
+    /// - `now`: When the loop starts.
+    pub fn headless(game_logic: Arc<Mutex<GameLogic>>, tick_rate: f32, now: Instant) -> Self {
+        GameLoop::new(game_logic, tick_rate, now, None, None)
+    }
+
+    /// Starts the loop on a new thread, taking the commands sent through `commands` until it stops.
+    ///
+    /// # Parameters
+    /// - `commands`: The way into the game logic to step.
+    /// - `target_rate`: Returns the number of steps per second, read at the start and then every second.
+    /// - `metrics`: The health metrics of the server to record every second, `None` without a server.
+    /// - `event_logger`: Writes the game events to the server messages, `None` without a server.
+    ///
+    /// # Returns
+    /// The handle stopping the loop, the loop runs until the end of the program if it is dropped.
+    pub(crate) fn spawn(
+        commands: GameCommands,
+        target_rate: impl Fn() -> f32 + Send + 'static,
+        metrics: Option<ServerMetrics>,
+        event_logger: Option<EventLogger>,
+    ) -> GameLoopHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let loop_running = Arc::clone(&running);
+        // Le canal est ouvert avant le retour : aucune commande envoyée ensuite ne passe par le verrou
+        let receiver = commands.open();
+        let thread = thread::spawn(move || {
+            let mut game_loop = GameLoop::new(Arc::clone(commands.game_logic()), target_rate(), Instant::now(), metrics, event_logger);
+            game_loop.commands = Some((commands, receiver));
+            game_loop.run(target_rate, loop_running)
+        });
+        GameLoopHandle { running, thread }
+    }
+
+    /// Changes the rate of the loop and the duration of a physics step to match.
+    ///
+    /// # Parameters
+    /// - `tick_rate`: The number of steps per second.
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.game_logic.lock().unwrap().physics_engine.set_tick_rate(tick_rate);
+        self.tick = Duration::from_nanos((1e9 / tick_rate as f64) as u64);
+    }
+
+    /// Steps the simulation until stopped, sleeping until the next tick is due.
+    ///
+    /// # Parameters
+    /// - `target_rate`: Returns the number of steps per second, applied when it changes.
+    /// - `running`: Cleared to stop the loop, checked each time the loop wakes up.
+    fn run(mut self, target_rate: impl Fn() -> f32, running: Arc<AtomicBool>) {
+        let mut checked_at = Instant::now();
+        while running.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now.saturating_duration_since(checked_at) >= Duration::from_secs(1) {
+                checked_at = now;
+                let tick_rate = target_rate();
+                let current = self.game_logic.lock().unwrap().physics_engine.tick_rate();
+                if tick_rate != current {
+                    self.set_tick_rate(tick_rate);
+                    self.game_logic.lock().unwrap().report(format!("[INFO] Tick rate changed from {} Hz to {} Hz.", current, tick_rate), MessageType::Info);
+                }
+            }
+            self.advance(now);
+            // Le prochain pas est dû quand l'accumulateur atteint un tick, en temps réel
+            let time_scale = self.game_logic.lock().unwrap().time_scale;
+            self.serve(now + self.tick.saturating_sub(self.accumulator).div_f32(time_scale));
+        }
+        // Les commandes envoyées avant la fermeture du canal reçoivent encore leur réponse
+        if let Some((commands, _)) = &self.commands {
+            commands.close();
+        }
+        self.apply_commands();
+    }
+
+    /// Runs the commands as they arrive until the next step is due.
+    ///
+    /// # Parameters
+    /// - `due`: When the next step is due.
+    fn serve(&mut self, due: Instant) {
+        let Some((_, receiver)) = &self.commands else {
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+            return;
+        };
+        loop {
+            match receiver.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                Ok(command) => {
+                    // Les commandes arrivées entre-temps passent sous le même verrou, sans retarder le pas :
+                    // celles qui restent à l'échéance passent au début du pas
+                    let mut game_logic = self.game_logic.lock().unwrap();
+                    let mut changed = command.apply(&mut game_logic);
+                    while Instant::now() < due {
+                        let Ok(command) = receiver.try_recv() else { break };
+                        changed |= command.apply(&mut game_logic);
+                    }
+                    if changed {
+                        game_logic.publish_world();
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return,
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(due.saturating_duration_since(Instant::now()));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Runs the commands waiting in the channel, see `GameCommands`.
+    fn apply_commands(&mut self) {
+        let Some((_, receiver)) = &self.commands else {
+            return;
+        };
+        let mut pending = receiver.try_iter().peekable();
+        if pending.peek().is_none() {
+            return;
+        }
+        let mut game_logic = self.game_logic.lock().unwrap();
+        let mut changed = false;
+        for command in pending {
+            changed |= command.apply(&mut game_logic);
+        }
+        if changed {
+            game_logic.publish_world();
+        }
+    }
+
+    /// Runs the steps due at a given time.
+    ///
+    /// After a long stall at most `MAX_CATCH_UP_STEPS` steps per unit of time
+    /// scale run, the rest of the late time is dropped rather than simulated
+    /// in a burst.
+    ///
+    /// # Parameters
+    /// - `now`: The current time.
+    ///
+    /// # Returns
+    /// The number of steps run.
+    pub fn advance(&mut self, now: Instant) -> u32 {
+        // Une commande arrivée pendant le dernier pas passe avant le suivant
+        self.apply_commands();
+        let time_scale = self.game_logic.lock().unwrap().time_scale;
+        self.accumulator += now.saturating_duration_since(self.previous).mul_f32(time_scale);
+        self.previous = now;
+        let max_steps = (AppDefines::MAX_CATCH_UP_STEPS as f32 * time_scale).ceil() as u32;
+        if self.accumulator > self.tick * max_steps {
+            self.fell_behind = true;
+            self.accumulator = self.tick * max_steps;
+        }
+
+        let mut steps = 0;
+        while self.accumulator >= self.tick {
+            // Verrou relâché entre deux pas, le temps des commandes et de l'écriture des événements
+            let mut game_logic = self.game_logic.lock().unwrap();
+            let locked_at = Instant::now();
+            game_logic.update_ai();
+            game_logic.step();
+            // L'UI dessine entre ce pas et le précédent, jusqu'au prochain en temps réel
+            let interval = if time_scale > 0.0 { self.tick.div_f32(time_scale) } else { Duration::ZERO };
+            game_logic.publish_render(now, interval);
+            game_logic.publish_world();
+            if let Some(metrics) = &self.metrics {
+                metrics.record_step(&game_logic);
+            }
+            // Les noms sont lus sous le verrou, les messages écrits après l'avoir relâché
+            let events = self.event_logger.as_mut().map(|logger| logger.read(&game_logic));
+            drop(game_logic);
+            if let (Some(logger), Some(events)) = (&self.event_logger, events) {
+                logger.write(events);
+            }
+            let hold = locked_at.elapsed();
+            if hold > self.tick {
+                self.overruns += 1;
+            }
+            self.longest_hold = self.longest_hold.max(hold);
+            self.total_hold += hold;
+            self.accumulator -= self.tick;
+            steps += 1;
+        }
+
+        self.steps_in_window += steps;
+        let window = now.saturating_duration_since(self.window_start);
+        if window >= Duration::from_secs(1) {
+            let mut game_logic = self.game_logic.lock().unwrap();
+            game_logic.achieved_tick_rate = self.steps_in_window as f32 / window.as_secs_f32();
+            // Le temps simulé par seconde réelle : la vitesse vraiment atteinte
+            let applied_scale = self.steps_in_window as f32 * self.tick.as_secs_f32() / window.as_secs_f32();
+            self.behind_secs = if std::mem::take(&mut self.fell_behind) { self.behind_secs + 1 } else { 0 };
+            // Un ralentissement passager ne compte pas, ni un retard en temps réel : seule une vitesse trop haute est baissée
+            if self.behind_secs >= AppDefines::TIME_SCALE_CLAMP_SECS && game_logic.time_scale > 1.0 && applied_scale < game_logic.time_scale {
+                let clamped = applied_scale.max(1.0);
+                game_logic.report(format!("[WARNING] The simulation cannot keep up with {:.2}x, speed lowered to {:.2}x.", game_logic.time_scale, clamped), MessageType::Warning);
+                game_logic.time_scale = clamped;
+                self.behind_secs = 0;
+            }
+            if let Some(metrics) = &self.metrics {
+                let average_step = self.total_hold.checked_div(self.steps_in_window).unwrap_or_default();
+                metrics.record(&game_logic, game_logic.physics_engine.tick_rate(), average_step, applied_scale);
+            }
+            if self.overruns > 0 {
+                let overruns = format!(
+                    "[WARNING] {} step(s) held the game logic longer than the {:.2} ms tick in the last second, up to {:.1} ms.",
+                    self.overruns,
+                    self.tick.as_secs_f64() * 1000.0,
+                    self.longest_hold.as_secs_f64() * 1000.0,
+                );
+                game_logic.report(overruns, MessageType::Warning);
+            }
+            drop(game_logic);
+            self.steps_in_window = 0;
+            self.window_start = now;
+            self.overruns = 0;
+            self.longest_hold = Duration::ZERO;
+            self.total_hold = Duration::ZERO;
+        }
+        steps
+    }
+}
@@ -0,0 +1,49 @@
+use crate::app_defines::AppDefines;
+use crate::game_logic::arena_config::ArenaConfig;
+
+/// A camera a spectator moves over the arena, see `GameLogic::add_ghost_camera`.
+///
+/// It has no body: no entity, bullet or sensor sees it, and it is neither listed nor scored.
+/// Its motors are read as a velocity, `MotL` along x and `MotR` along y, 0.5 holding it still.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GhostCamera {
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub motor_left: f32,
+    pub motor_right: f32,
+    /// The radius of the world pushed to the subscription of the spectator, 0 for the whole world.
+    pub view_radius: f32,
+}
+
+impl GhostCamera {
+    /// Creates a camera standing still.
+    ///
+    /// # Parameters
+    /// - `id`: The id of the camera, never given to an entity.
+    /// - `position`: Where the camera starts.
+    pub fn new(id: u32, position: (f32, f32)) -> Self {
+        Self { id, x: position.0, y: position.1, motor_left: 0.5, motor_right: 0.5, view_radius: 0.0 }
+    }
+
+    /// Returns the velocity the motors give the camera, in units per second.
+    pub fn velocity(&self) -> (f32, f32) {
+        let speed = |motor: f32| (motor - 0.5) * 2.0 * AppDefines::GHOST_CAMERA_SPEED;
+        (speed(self.motor_left), speed(self.motor_right))
+    }
+
+    /// Moves the camera by one step, without leaving the arena.
+    ///
+    /// # Parameters
+    /// - `dt`: The simulated duration of the step, in seconds.
+    /// - `arena`: The arena the camera stays in.
+    pub fn advance(&mut self, dt: f32, arena: &ArenaConfig) {
+        let (vx, vy) = self.velocity();
+        (self.x, self.y) = arena.clamp(self.x + vx * dt, self.y + vy * dt, 0.0);
+    }
+
+    /// Returns whether a position is in the view of the camera.
+    pub fn sees(&self, x: f32, y: f32) -> bool {
+        self.view_radius <= 0.0 || (x - self.x).hypot(y - self.y) <= self.view_radius
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game_logic::map_file::MapHazard;
+use crate::physics::body_identity::BodyIdentity;
+use crate::physics::physics::PhysicsEngine;
+
+/// The area of a hazard, around its position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum HazardShape {
+    /// An axis-aligned rectangle.
+    Rect { half_width: f32, half_height: f32 },
+    /// A disc.
+    Circle { radius: f32 },
+}
+
+impl HazardShape {
+    /// Returns the name of the shape, as sent to the clients.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HazardShape::Rect { .. } => "RECT",
+            HazardShape::Circle { .. } => "CIRCLE",
+        }
+    }
+
+    /// Returns half the width and half the height of the shape. For a disc, both are the radius.
+    pub fn half_extents(&self) -> (f32, f32) {
+        match *self {
+            HazardShape::Rect { half_width, half_height } => (half_width, half_height),
+            HazardShape::Circle { radius } => (radius, radius),
+        }
+    }
+
+    /// Returns the outline of a hazard of this shape, as a closed polygon.
+    ///
+    /// # Parameters
+    /// - `center`: The position of the hazard.
+    pub fn outline(&self, center: (f64, f64)) -> Vec<[f64; 2]> {
+        let (half_width, half_height) = self.half_extents();
+        let (half_width, half_height) = (half_width as f64, half_height as f64);
+        let mut points: Vec<[f64; 2]> = match self {
+            HazardShape::Circle { .. } => (0..32)
+                .map(|i| {
+                    let angle = i as f64 * std::f64::consts::TAU / 32.0;
+                    [center.0 + half_width * angle.cos(), center.1 + half_width * angle.sin()]
+                })
+                .collect(),
+            HazardShape::Rect { .. } => [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .iter()
+                .map(|&(sx, sy)| [center.0 + sx * half_width, center.1 + sy * half_height])
+                .collect(),
+        };
+        points.push(points[0]);
+        points
+    }
+
+    /// Returns the shape of the sensor covering the area.
+    fn sensor_shape(&self) -> SharedShape {
+        match *self {
+            HazardShape::Rect { half_width, half_height } => SharedShape::cuboid(half_width, half_height),
+            HazardShape::Circle { radius } => SharedShape::ball(radius),
+        }
+    }
+}
+
+/// What a hazard does to the entities inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "effect", rename_all = "snake_case")]
+pub enum HazardEffect {
+    /// Removes `per_sec` health per simulated second, see `GameLogic::apply_hazard_damage`.
+    Damage { per_sec: f32 },
+    /// Multiplies the output of the motors by `factor`, from 0 to 1.
+    Slow { factor: f32 },
+}
+
+impl HazardEffect {
+    /// Returns the name of the effect, as sent to the clients.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HazardEffect::Damage { .. } => "DAMAGE",
+            HazardEffect::Slow { .. } => "SLOW",
+        }
+    }
+
+    /// Returns the health removed per second for a damage zone, the factor of the motors for a slow field.
+    pub fn strength(&self) -> f32 {
+        match *self {
+            HazardEffect::Damage { per_sec } => per_sec,
+            HazardEffect::Slow { factor } => factor,
+        }
+    }
+}
+
+/// A region of the map hurting or slowing down the entities inside it.
+///
+/// Its sensor sees the entities whose body touches the area; bullets cross it unaffected.
+#[derive(Debug, Clone)]
+pub struct Hazard {
+    pub position: (f32, f32),
+    pub shape: HazardShape,
+    pub effect: HazardEffect,
+    pub collider_handle: ColliderHandle,
+    /// The entities the sensor saw entering and not leaving yet.
+    sensed: HashSet<u32>,
+}
+
+impl Hazard {
+    /// Creates a hazard of the map and adds its sensor to the physics engine.
+    ///
+    /// # Parameters
+    /// - `hazard`: The hazard of the map file.
+    /// - `physics_engine`: The physics engine the sensor is added to.
+    pub fn new(hazard: &MapHazard, physics_engine: &mut PhysicsEngine) -> Self {
+        let collider_handle = physics_engine.insert_sensor(
+            hazard.shape.sensor_shape(),
+            vector![hazard.x, hazard.y],
+            BodyIdentity::Hazard,
+        );
+        Self {
+            position: (hazard.x, hazard.y),
+            shape: hazard.shape,
+            effect: hazard.effect,
+            collider_handle,
+            sensed: HashSet::new(),
+        }
+    }
+
+    /// Removes the sensor of the hazard from the physics engine.
+    pub fn remove(self, physics_engine: &mut PhysicsEngine) {
+        physics_engine.remove_sensor(self.collider_handle);
+    }
+
+    /// Returns the hazard as a map file writes it.
+    pub fn to_map(&self) -> MapHazard {
+        MapHazard { x: self.position.0, y: self.position.1, shape: self.shape, effect: self.effect }
+    }
+
+    /// Follows an entity entering or leaving the sensor of the hazard.
+    ///
+    /// # Parameters
+    /// - `id`: The id of the entity.
+    /// - `inside`: Whether the intersection started rather than stopped.
+    pub fn sense(&mut self, id: u32, inside: bool) {
+        if inside {
+            self.sensed.insert(id);
+        } else {
+            self.sensed.remove(&id);
+        }
+    }
+
+    /// Returns whether an entity is inside the hazard.
+    pub fn holds(&self, id: u32) -> bool {
+        self.sensed.contains(&id)
+    }
+
+    /// Forgets the entities no longer in the arena, whose end of intersection is never reported.
+    ///
+    /// # Parameters
+    /// - `alive`: Whether an entity is still in the arena.
+    pub fn forget(&mut self, alive: impl Fn(u32) -> bool) {
+        self.sensed.retain(|id| alive(*id));
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game_logic::scoring::ScoreBreakdown;
+
+/// The totals of a player over all the matches it played.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerTotals {
+    pub kills: u32,
+    pub deaths: u32,
+    pub matches_played: u32,
+    /// The sum of the scores of the player at the end of its matches.
+    pub score: i64,
+    /// The bullets fired.
+    #[serde(default)]
+    pub shots_fired: u32,
+    /// The shots that hit an opponent.
+    #[serde(default)]
+    pub hits: u32,
+    /// The horde waves cleared while the player was alive.
+    #[serde(default)]
+    pub waves_survived: u32,
+}
+
+impl PlayerTotals {
+    /// Returns the share of the shots of the player that hit an opponent, `None` before its first shot.
+    pub fn accuracy(&self) -> Option<f32> {
+        ScoreBreakdown { shots_fired: self.shots_fired, hits: self.hits, ..ScoreBreakdown::default() }.accuracy()
+    }
+}
+
+/// The cumulative scores of the players, kept across server restarts.
+///
+/// Players are identified by their name: two entities with the same name count
+/// as the same player, and entities with a blank name are left out. The score breakdown
+/// of an entity is counted when it leaves the arena or its scores are cleared, and added
+/// to the totals at the end of the match, together with the final scores, then the
+/// leaderboard is saved.
+#[derive(Debug, Default)]
+pub struct Leaderboard {
+    /// The totals of each player, sorted by name so that the file is stable.
+    totals: BTreeMap<String, PlayerTotals>,
+    /// The score breakdown of each player in the current match.
+    current: HashMap<String, ScoreBreakdown>,
+    /// The file the leaderboard is saved to, `None` to keep it in memory only.
+    path: Option<PathBuf>,
+}
+
+impl Leaderboard {
+    /// Reads the leaderboard saved in a file. A missing file gives an empty leaderboard.
+    ///
+    /// # Parameters
+    /// - `path`: The file the leaderboard is read from and saved to.
+    ///
+    /// # Returns
+    /// The leaderboard, or an error if the file exists but cannot be read.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let totals = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { totals, current: HashMap::new(), path: Some(path.to_path_buf()) })
+    }
+
+    /// Writes the leaderboard to its file, if it has one.
+    ///
+    /// The leaderboard is written to a temporary file first, then renamed over the
+    /// previous one, so a crash while saving leaves the previous file intact.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(&self.totals)?)?;
+        fs::rename(&temporary, path)
+    }
+
+    /// Counts the score breakdown of an entity in the current match.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the entity.
+    /// - `breakdown`: What the entity did since its breakdown was last counted.
+    pub fn record_breakdown(&mut self, name: &str, breakdown: &ScoreBreakdown) {
+        if *breakdown == ScoreBreakdown::default() {
+            return;
+        }
+        if let Some(name) = Leaderboard::player_name(name) {
+            self.current.entry(name.to_string()).or_default().add(breakdown);
+        }
+    }
+
+    /// Adds the current match to the totals.
+    ///
+    /// Every player that killed, died or is still in the arena played the match.
+    /// Only the players still in the arena add their score.
+    ///
+    /// # Parameters
+    /// - `scores`: The names and final scores of the entities in the arena.
+    pub fn record_match(&mut self, scores: &[(String, i32)]) {
+        let mut played: HashMap<String, i64> = HashMap::new();
+        for (name, score) in scores {
+            if let Some(name) = Leaderboard::player_name(name) {
+                *played.entry(name.to_string()).or_default() += *score as i64;
+            }
+        }
+        for (name, breakdown) in self.current.drain() {
+            let totals = self.totals.entry(name.clone()).or_default();
+            totals.kills += breakdown.kills;
+            totals.deaths += breakdown.deaths;
+            totals.shots_fired += breakdown.shots_fired;
+            totals.hits += breakdown.hits;
+            totals.waves_survived += breakdown.waves_survived;
+            played.entry(name).or_default();
+        }
+        for (name, score) in played {
+            let totals = self.totals.entry(name).or_default();
+            totals.matches_played += 1;
+            totals.score += score;
+        }
+    }
+
+    /// Returns the best players, by cumulative score, then kills, then name.
+    ///
+    /// # Parameters
+    /// - `count`: The maximum number of players returned.
+    pub fn top(&self, count: usize) -> Vec<(&str, &PlayerTotals)> {
+        let mut players: Vec<(&str, &PlayerTotals)> = self.totals
+            .iter()
+            .map(|(name, totals)| (name.as_str(), totals))
+            .collect();
+        players.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(b.1.kills.cmp(&a.1.kills)).then(a.0.cmp(b.0)));
+        players.truncate(count);
+        players
+    }
+
+    /// Returns the name a player is recorded under, `None` for a blank name.
+    fn player_name(name: &str) -> Option<&str> {
+        let name = name.trim();
+        (!name.is_empty()).then_some(name)
+    }
+}
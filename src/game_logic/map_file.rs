@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::hazard::{HazardEffect, HazardShape};
+use crate::obstacles::ObstacleShape;
+
+/// The version of the map file format.
+pub const MAP_FORMAT_VERSION: u32 = 1;
+
+/// An obstacle of a map file.
+///
+/// The shape is written next to the position, e.g.
+/// `{ "x": 600, "y": 500, "type": "wall", "half_length": 80, "half_thickness": 5, "angle": 0.5 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapObstacle {
+    pub x: f32,
+    pub y: f32,
+    #[serde(flatten)]
+    pub shape: ObstacleShape,
+}
+
+/// A named position the entities are placed at when a round starts, instead of a random one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnPoint {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A circular zone of the map the sides fight over in the control point mode, see `ControlPoint`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapControlPoint {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+/// A region of the map hurting or slowing down the entities inside it, see `Hazard`.
+///
+/// The shape and the effect are written next to the position, e.g.
+/// `{ "x": 600, "y": 500, "shape": "circle", "radius": 60, "effect": "damage", "per_sec": 10 }`
+/// or `{ "x": 300, "y": 200, "shape": "rect", "half_width": 80, "half_height": 40, "effect": "slow", "factor": 0.5 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapHazard {
+    pub x: f32,
+    pub y: f32,
+    #[serde(flatten)]
+    pub shape: HazardShape,
+    #[serde(flatten)]
+    pub effect: HazardEffect,
+}
+
+/// A curated layout of the arena, read by `GameLogic::load_map` and written by `GameLogic::save_map`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapFile {
+    /// The version of the file format.
+    pub format_version: u32,
+    /// The name of the map, the name of the file when missing.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub obstacles: Vec<MapObstacle>,
+    #[serde(default)]
+    pub spawn_points: Vec<SpawnPoint>,
+    #[serde(default)]
+    pub control_points: Vec<MapControlPoint>,
+    #[serde(default)]
+    pub hazards: Vec<MapHazard>,
+}
+
+impl MapFile {
+    /// Reads a map file and checks that it describes a valid map.
+    ///
+    /// # Parameters
+    /// - `path`: The file to read.
+    /// - `arena`: The arena the map must fit in.
+    ///
+    /// # Returns
+    /// The map, or an error telling which entry of the file is wrong.
+    pub fn load(path: &Path, arena: &ArenaConfig) -> io::Result<Self> {
+        let mut map: MapFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if map.format_version != MAP_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported map format version {}", map.format_version),
+            ));
+        }
+        map.validate(arena).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if map.name.is_none() {
+            map.name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+        }
+        Ok(map)
+    }
+
+    /// Writes the map to a JSON file, read back by `load`.
+    ///
+    /// # Parameters
+    /// - `path`: The file to write.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Checks that the obstacles have a size and lie in the arena, that the spawn points
+    /// have distinct names and leave room for an entity, that the control points have
+    /// distinct names and a radius, and that the hazards lie in the arena with a size and an effect.
+    ///
+    /// # Parameters
+    /// - `arena`: The arena the map must fit in.
+    ///
+    /// # Returns
+    /// A description of the first wrong entry, counting from 1.
+    pub fn validate(&self, arena: &ArenaConfig) -> Result<(), String> {
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            let number = index + 1;
+            if !arena.contains(obstacle.x, obstacle.y) {
+                return Err(format!("obstacle {} at ({}, {}) is outside the arena", number, obstacle.x, obstacle.y));
+            }
+            let (half_width, half_height) = obstacle.shape.half_extents();
+            let sizes_valid = [half_width, half_height].iter().all(|size| size.is_finite() && *size > 0.0);
+            if !sizes_valid || !obstacle.shape.angle().is_finite() {
+                return Err(format!("obstacle {} has an invalid size or angle: {:?}", number, obstacle.shape));
+            }
+        }
+
+        let mut names = HashSet::new();
+        for (index, spawn_point) in self.spawn_points.iter().enumerate() {
+            let number = index + 1;
+            if spawn_point.name.trim().is_empty() {
+                return Err(format!("spawn point {} has no name", number));
+            }
+            if !names.insert(spawn_point.name.as_str()) {
+                return Err(format!("spawn point {} reuses the name '{}'", number, spawn_point.name));
+            }
+            if !arena.contains(spawn_point.x, spawn_point.y) {
+                return Err(format!("spawn point '{}' at ({}, {}) is outside the arena", spawn_point.name, spawn_point.x, spawn_point.y));
+            }
+            let blocking = self.obstacles.iter().position(|obstacle| {
+                obstacle.shape.distance((obstacle.x, obstacle.y), (spawn_point.x, spawn_point.y)) < AppDefines::ENTITY_OBSTACLE_CLEARANCE
+            });
+            if let Some(blocking) = blocking {
+                return Err(format!(
+                    "spawn point '{}' is closer than {} to obstacle {}",
+                    spawn_point.name,
+                    AppDefines::ENTITY_OBSTACLE_CLEARANCE,
+                    blocking + 1,
+                ));
+            }
+        }
+
+        let mut names = HashSet::new();
+        for (index, control_point) in self.control_points.iter().enumerate() {
+            let number = index + 1;
+            if control_point.name.trim().is_empty() {
+                return Err(format!("control point {} has no name", number));
+            }
+            if !names.insert(control_point.name.as_str()) {
+                return Err(format!("control point {} reuses the name '{}'", number, control_point.name));
+            }
+            if !arena.contains(control_point.x, control_point.y) {
+                return Err(format!("control point '{}' at ({}, {}) is outside the arena", control_point.name, control_point.x, control_point.y));
+            }
+            if !control_point.radius.is_finite() || control_point.radius <= 0.0 {
+                return Err(format!("control point '{}' has an invalid radius: {}", control_point.name, control_point.radius));
+            }
+        }
+
+        for (index, hazard) in self.hazards.iter().enumerate() {
+            let number = index + 1;
+            if !arena.contains(hazard.x, hazard.y) {
+                return Err(format!("hazard {} at ({}, {}) is outside the arena", number, hazard.x, hazard.y));
+            }
+            let (half_width, half_height) = hazard.shape.half_extents();
+            if ![half_width, half_height].iter().all(|size| size.is_finite() && *size > 0.0) {
+                return Err(format!("hazard {} has an invalid size: {:?}", number, hazard.shape));
+            }
+            let effect_valid = match hazard.effect {
+                HazardEffect::Damage { per_sec } => per_sec.is_finite() && per_sec > 0.0,
+                HazardEffect::Slow { factor } => (0.0..=1.0).contains(&factor),
+            };
+            if !effect_valid {
+                return Err(format!("hazard {} has an invalid effect: {:?}", number, hazard.effect));
+            }
+        }
+        Ok(())
+    }
+}
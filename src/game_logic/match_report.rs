@@ -0,0 +1,246 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::entity::Entity;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::scoring::ScoreBreakdown;
+use crate::types::format_timestamp;
+
+/// A player as the report is built from it: who it is, its score and what it did in the round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerRecord {
+    pub id: u32,
+    pub name: String,
+    pub team: Option<u8>,
+    pub score: i32,
+    pub breakdown: ScoreBreakdown,
+}
+
+impl PlayerRecord {
+    /// Captures an entity as it is now.
+    pub fn of(entity: &Entity) -> Self {
+        Self { id: entity.id, name: entity.name.clone(), team: entity.team, score: entity.score, breakdown: entity.breakdown }
+    }
+}
+
+/// How a round went, apart from the players and the events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundSummary {
+    /// The round number.
+    pub round: u32,
+    /// Why the round ended.
+    pub reason: String,
+    /// The names of the entities with the highest score, several on a tie.
+    pub winners: Vec<String>,
+    /// The seed of the map, `None` for a map loaded from a file.
+    pub seed: Option<u64>,
+    /// The name of the map loaded from a file, `None` for a generated map.
+    pub map: Option<String>,
+    /// The simulation step the round started at.
+    pub start_tick: u64,
+    /// The simulation step the round ended at.
+    pub end_tick: u64,
+    /// The duration of a step.
+    pub step: Duration,
+    /// When the round ended, the time of the host.
+    pub ended_at: SystemTime,
+}
+
+/// A player of the round, as the report lists it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerReport {
+    pub id: u32,
+    pub name: String,
+    pub team: Option<u8>,
+    pub score: i32,
+    pub shots_fired: u32,
+    /// The bullets that hurt an opponent, see `ScoreBreakdown::hits`.
+    pub hits: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    /// The share of the bullets fired that hit an opponent, `None` without a shot.
+    pub accuracy: Option<f32>,
+    /// The health removed from opponents by the bullets and mines of the player.
+    pub damage_dealt: i32,
+    /// The health the player lost to bullets and mines, its own included.
+    pub damage_taken: i32,
+    /// The health the armor of the player kept it from losing, see `ScoreBreakdown::damage_mitigated`.
+    #[serde(default)]
+    pub damage_mitigated: i32,
+}
+
+/// A kill of the round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillReport {
+    /// The simulation step of the kill.
+    pub tick: u64,
+    /// The simulated time since the start of the round, in seconds.
+    pub time_secs: f64,
+    /// The id of the entity credited with the kill, the victim itself for a self-destruction.
+    pub shooter: u32,
+    pub shooter_name: Option<String>,
+    pub victim: u32,
+    pub victim_name: Option<String>,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// The summary of a round, written at its end for the tournament tools, see `MatchReport::build`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchReport {
+    /// The name of the report: the time it ended at and the round number, e.g. `match_20240501T134512Z_round3`.
+    pub match_id: String,
+    pub round: u32,
+    /// When the round ended, as an ISO 8601 UTC time.
+    pub ended_at: String,
+    pub seed: Option<u64>,
+    pub map: Option<String>,
+    pub reason: String,
+    pub winners: Vec<String>,
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub duration_ticks: u64,
+    /// The simulated duration of the round, in seconds.
+    pub duration_secs: f64,
+    /// The players, best score first.
+    pub players: Vec<PlayerReport>,
+    /// The kills, oldest first.
+    pub kills: Vec<KillReport>,
+}
+
+impl MatchReport {
+    /// Builds the report of a round.
+    ///
+    /// The damage comes from the `Hit` events and the kills from the `Kill` events; the events
+    /// outside of the round are left out, so the whole event queue can be given.
+    ///
+    /// # Parameters
+    /// - `summary`: How the round went.
+    /// - `players`: The players of the round, those who left it included.
+    /// - `events`: The events of the arena and their simulation steps, oldest first.
+    pub fn build<'a, I>(summary: &RoundSummary, players: &[PlayerRecord], events: I) -> Self
+    where
+        I: IntoIterator<Item = (u64, &'a GameEvent)>,
+    {
+        let team_of = |id: u32| players.iter().find(|player| player.id == id).and_then(|player| player.team);
+        let name_of = |id: u32| players.iter().find(|player| player.id == id).map(|player| player.name.clone());
+        let mut rows: Vec<PlayerReport> = players.iter().map(|player| PlayerReport {
+            id: player.id,
+            name: player.name.clone(),
+            team: player.team,
+            score: player.score,
+            shots_fired: player.breakdown.shots_fired,
+            hits: player.breakdown.hits,
+            kills: player.breakdown.kills,
+            deaths: player.breakdown.deaths,
+            accuracy: player.breakdown.accuracy(),
+            damage_dealt: 0,
+            damage_taken: 0,
+            damage_mitigated: player.breakdown.damage_mitigated,
+        }).collect();
+
+        let mut kills = Vec::new();
+        let round = summary.start_tick..=summary.end_tick;
+        for (tick, event) in events.into_iter().filter(|(tick, _)| round.contains(tick)) {
+            match *event {
+                GameEvent::Hit { shooter, victim, damage, .. } => {
+                    // Comme pour les touches, ni soi-même ni un coéquipier ne comptent
+                    let opponent = shooter != victim && !team_of(shooter).is_some_and(|team| team_of(victim) == Some(team));
+                    for row in &mut rows {
+                        if row.id == victim {
+                            row.damage_taken += damage;
+                        } else if row.id == shooter && opponent {
+                            row.damage_dealt += damage;
+                        }
+                    }
+                }
+                GameEvent::Kill { shooter, victim, x, y } => kills.push(KillReport {
+                    tick,
+                    time_secs: (tick - summary.start_tick) as f64 * summary.step.as_secs_f64(),
+                    shooter,
+                    shooter_name: name_of(shooter),
+                    victim,
+                    victim_name: name_of(victim),
+                    x,
+                    y,
+                }),
+                _ => {}
+            }
+        }
+        rows.sort_by(|a, b| b.score.cmp(&a.score).then(b.kills.cmp(&a.kills)).then(a.name.cmp(&b.name)));
+
+        let ended_at = format_timestamp(summary.ended_at);
+        // 2024-05-01T13:45:12.345Z devient 20240501T134512Z, sans caractère gênant dans un nom de fichier
+        let stamp: String = ended_at.chars().take(19).filter(|c| *c != '-' && *c != ':').collect();
+        let duration_ticks = summary.end_tick.saturating_sub(summary.start_tick);
+        MatchReport {
+            match_id: format!("match_{}Z_round{}", stamp, summary.round),
+            round: summary.round,
+            ended_at,
+            seed: summary.seed,
+            map: summary.map.clone(),
+            reason: summary.reason.clone(),
+            winners: summary.winners.clone(),
+            start_tick: summary.start_tick,
+            end_tick: summary.end_tick,
+            duration_ticks,
+            duration_secs: duration_ticks as f64 * summary.step.as_secs_f64(),
+            players: rows,
+            kills,
+        }
+    }
+
+    /// Writes the players of the report as CSV, one row per player after a header row.
+    ///
+    /// The kills are only in the JSON form of the report.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("match_id,round,name,team,score,shots_fired,hits,kills,deaths,accuracy,damage_dealt,damage_taken,damage_mitigated\n");
+        for player in &self.players {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                csv_field(&self.match_id),
+                self.round,
+                csv_field(&player.name),
+                player.team.map_or(String::new(), |team| team.to_string()),
+                player.score,
+                player.shots_fired,
+                player.hits,
+                player.kills,
+                player.deaths,
+                player.accuracy.map_or(String::new(), |accuracy| format!("{:.4}", accuracy)),
+                player.damage_dealt,
+                player.damage_taken,
+                player.damage_mitigated,
+            );
+        }
+        csv
+    }
+
+    /// Writes the report to a directory, as `<match_id>.json` and `<match_id>.csv`.
+    ///
+    /// # Parameters
+    /// - `dir`: The directory, created if missing.
+    ///
+    /// # Returns
+    /// The path of the JSON file.
+    pub fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let json = dir.join(format!("{}.json", self.match_id));
+        fs::write(&json, serde_json::to_string_pretty(self)?)?;
+        fs::write(dir.join(format!("{}.csv", self.match_id)), self.to_csv())?;
+        Ok(json)
+    }
+}
+
+/// Quotes a field of a CSV row when it holds a separator, a quote or a line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::game_config::GameConfig;
+
+/// The phase of the current round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchPhase {
+    /// The round is being played.
+    Running,
+    /// The round is over: actuators are ignored until the next round starts.
+    Intermission,
+}
+
+/// Tracks the rounds of the match: when the current round ends and how the last one ended.
+///
+/// The state does not own any entity. `GameLogic::update_match` checks the win
+/// conditions against the entities and starts the next round after the intermission.
+pub struct MatchState {
+    /// The current round number, starting at 1.
+    pub round: u32,
+    /// The phase of the current round.
+    pub phase: MatchPhase,
+    /// The result of the last round, shown in the UI.
+    pub last_result: Option<String>,
+    /// The simulated time the current phase started at.
+    phase_start: Duration,
+    /// Whether an entity played in the current round, so that an empty arena does not end it.
+    contested: bool,
+}
+
+impl MatchState {
+    /// Creates the state of a match whose first round starts at a given time.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn new(now: Duration) -> Self {
+        Self {
+            round: 1,
+            phase: MatchPhase::Running,
+            last_result: None,
+            phase_start: now,
+            contested: false,
+        }
+    }
+
+    /// Returns the simulated time left in the current phase.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    /// - `config`: The match rules giving the round duration.
+    ///
+    /// # Returns
+    /// The time left, or `None` while a round without time limit is running.
+    pub fn remaining(&self, now: Duration, config: &GameConfig) -> Option<Duration> {
+        let length = match self.phase {
+            MatchPhase::Running if config.match_duration_secs == 0 => return None,
+            MatchPhase::Running => Duration::from_secs(config.match_duration_secs as u64),
+            MatchPhase::Intermission => Duration::from_secs_f32(AppDefines::MATCH_INTERMISSION_SECS),
+        };
+        Some(length.saturating_sub(self.elapsed(now)))
+    }
+
+    /// Returns the simulated time since the current phase started.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn elapsed(&self, now: Duration) -> Duration {
+        now.saturating_sub(self.phase_start)
+    }
+
+    /// Checks whether the running round is over.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    /// - `config`: The match rules giving the round duration and score target.
+    /// - `scores`: The scores of the entities in the game.
+    ///
+    /// # Returns
+    /// Why the round is over, or `None` if it goes on.
+    pub fn end_reason(&mut self, now: Duration, config: &GameConfig, scores: &[i32]) -> Option<&'static str> {
+        if self.phase != MatchPhase::Running {
+            return None;
+        }
+        if !scores.is_empty() {
+            self.contested = true;
+        }
+
+        if self.remaining(now, config).is_some_and(|remaining| remaining.is_zero()) {
+            Some("time is up")
+        } else if config.score_target > 0 && scores.iter().any(|&score| score >= config.score_target) {
+            Some("score target reached")
+        } else if self.contested && scores.is_empty() {
+            Some("no entity left")
+        } else {
+            None
+        }
+    }
+
+    /// Ends the running round.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    /// - `result`: The result of the round, shown in the UI.
+    pub fn end_round(&mut self, now: Duration, result: String) {
+        self.phase = MatchPhase::Intermission;
+        self.phase_start = now;
+        self.last_result = Some(result);
+    }
+
+    /// Returns whether the intermission is over and the next round should start.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    /// - `config`: The match rules.
+    pub fn intermission_over(&self, now: Duration, config: &GameConfig) -> bool {
+        self.phase == MatchPhase::Intermission
+            && self.remaining(now, config).is_some_and(|remaining| remaining.is_zero())
+    }
+
+    /// Describes how a round ended, for the UI and the server messages.
+    ///
+    /// # Parameters
+    /// - `round`: The round number.
+    /// - `reason`: Why the round ended.
+    /// - `winners`: The names of the entities with the highest score, several on a tie.
+    /// - `score`: The highest score, `None` if no entity was left.
+    pub fn result_text(round: u32, reason: &str, winners: &[String], score: Option<i32>) -> String {
+        match (winners, score) {
+            ([winner], Some(score)) => format!("Round {} over, {}: {} wins with {} points", round, reason, winner, score),
+            ([_, ..], Some(score)) => format!("Round {} over, {}: tie between {} with {} points", round, reason, winners.join(", "), score),
+            _ => format!("Round {} over, {}: no winner", round, reason),
+        }
+    }
+
+    /// Restarts the current round from the beginning, keeping its number.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn restart_round(&mut self, now: Duration) {
+        self.phase = MatchPhase::Running;
+        self.phase_start = now;
+        self.contested = false;
+    }
+
+    /// Starts the next round.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn start_round(&mut self, now: Duration) {
+        self.round += 1;
+        self.phase = MatchPhase::Running;
+        self.phase_start = now;
+        self.contested = false;
+    }
+}
@@ -1,24 +1,251 @@
-use std::time::Instant;
-
-use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use rapier2d::prelude::*;
 use crate::app_defines::AppDefines;
-use crate::bullet::bullet::Bullet;
+use crate::bullet::bullet::{Bullet, BulletKind, ShooterFilter};
 use crate::entities::entity::Entity;
-use crate::obstacles::Obstacle;
+use crate::entities::turret::Turret;
+use crate::mines::{DeployError, Mine};
+use crate::obstacles::{Obstacle, ObstacleShape};
+use crate::physics::body_identity::BodyIdentity;
 use crate::physics::physics::PhysicsEngine;
+use crate::power_ups::{PowerUp, PowerUpKind};
+use crate::types::{add_message, MessageHub, MessageOrigin, MessageType};
+
+pub mod ai_behavior;
+pub mod ai_script;
+pub mod arena_config;
+pub mod camping;
+pub mod command;
+pub mod control_point;
+pub mod event_queue;
+pub mod game_config;
+pub mod game_loop;
+pub mod ghost_camera;
+pub mod hazard;
+pub mod leaderboard;
+pub mod map_file;
+pub mod match_report;
+pub mod match_state;
+pub mod name_index;
+pub mod observer;
+pub mod render_snapshot;
+pub mod replay;
+pub mod scoring;
+pub mod snapshot;
+pub mod step_timings;
+pub mod stress;
+pub mod timeline;
+pub mod trail_store;
+pub mod wave_director;
+pub mod world_snapshot;
+pub mod zone;
+
+use ai_behavior::{AiBehavior, AiDifficulty, AiView, BehaviorRegistry, Clearance, ObstacleView, SurfacePoint, WANDERER};
+use ai_script::{AiScript, ScriptedBehavior};
+use arena_config::ArenaConfig;
+use camping::CampWatch;
+use control_point::{ControlPoint, Side};
+use event_queue::EventQueue;
+use game_config::{GameConfig, GameMode};
+use ghost_camera::GhostCamera;
+use hazard::{Hazard, HazardEffect};
+use leaderboard::Leaderboard;
+use map_file::{MapControlPoint, MapFile, MapHazard, MapObstacle, SpawnPoint, MAP_FORMAT_VERSION};
+use match_report::{MatchReport, PlayerRecord, RoundSummary};
+use match_state::{MatchPhase, MatchState};
+use name_index::{NameError, NameIndex};
+use observer::{BonusReason, GameEvent, GameObserver, PenaltyReason};
+use render_snapshot::{AiIntent, GameControls, InspectedEntity, MineFrame, PowerUpFrame, RenderSlot, RenderSnapshot};
+use replay::{ControlPointFrame, EntityFrame, ObstacleFrame, Recorder, ReplayFrame, TurretFrame};
+use scoring::ScoreRules;
+use step_timings::{StepPhase, StepTimings};
+use trail_store::TrailStore;
+use snapshot::{BulletSnapshot, EntitySnapshot, GameSnapshot, ObstacleSnapshot, TurretSnapshot, SNAPSHOT_FORMAT_VERSION};
+use wave_director::{WaveDirector, WaveState};
+use world_snapshot::{BulletStatus, ControlPointStatus, EntityStatus, WorldSlot, WorldSnapshot};
+use zone::Zone;
 
 /// Represents the game logic and manages the state of the game.
-#[derive(Default)]
 pub struct GameLogic {
     /// The physics engine managing the physical simulation.
     pub physics_engine: PhysicsEngine,
+    /// The size of the arena, see `set_arena`.
+    arena: ArenaConfig,
     /// A list of entities in the game.
     pub entities: Vec<Entity>,
     /// A list of bullets currently in the game.
     pub bullets: Vec<Bullet>,
     /// A list of obstacles in the game.
     pub obstacles: Vec<Obstacle>,
+    /// The seed the current map was generated from, `None` before the first map or for a map loaded from a file.
+    map_seed: Option<u64>,
+    /// The name of the map loaded from a file, `None` for a generated map.
+    map_name: Option<String>,
+    /// The positions of the map the entities are placed at first, see `reposition_entities`.
+    spawn_points: Vec<SpawnPoint>,
+    /// The power-ups waiting to be picked up.
+    pub power_ups: Vec<PowerUp>,
+    /// The mines laid by the entities.
+    pub mines: Vec<Mine>,
+    /// The control points of the map, scored in the control point mode, see `update_control_points`.
+    control_points: Vec<ControlPoint>,
+    /// The damage zones and slow fields of the map, see `apply_hazard_damage` and `hazard_slows`.
+    hazards: Vec<Hazard>,
+    /// The cameras of the spectators, outside of the physics, see `add_ghost_camera`.
+    ghost_cameras: Vec<GhostCamera>,
+    /// The tick at which the last power-up spawned.
+    last_power_up_tick: u64,
+    /// The rounds of the match.
+    pub match_state: MatchState,
+    /// The horde wave director, present while the cooperative horde mode is running.
+    pub wave_director: Option<WaveDirector>,
+    /// The number of horde waves that ended, see `respawns_granted`.
+    respawns_granted: u64,
+    /// The optional score decay and comeback rules.
+    pub score_rules: ScoreRules,
+    /// The match rules, such as friendly fire.
+    pub game_config: GameConfig,
+    /// The number of simulation steps since the game started.
+    pub tick: u64,
+    /// How long the last simulation step took to compute.
+    pub last_step_duration: Duration,
+    /// The number of steps per second the game loop achieved over the last second.
+    pub achieved_tick_rate: f32,
+    /// Whether the phases of each step are timed into `step_timings`, off by default to keep the steps free of it.
+    pub profiling_enabled: bool,
+    /// How long the phases of the last steps took, while `profiling_enabled` is on.
+    pub step_timings: StepTimings,
+    /// Whether the render snapshots carry the intentions of the AI entities for the debug view of the game UI.
+    pub ai_debug_enabled: bool,
+    /// The entity whose live state the render snapshots carry for the inspector of the game UI.
+    pub inspected: Option<u32>,
+    /// Whether the simulation is paused.
+    paused: bool,
+    /// The number of simulation steps run per wall-clock tick of the game loop.
+    pub time_scale: f32,
+    /// The random number generator of the game, reseeded with the map seed so that
+    /// a match replays identically from the same seed and inputs.
+    rng: StdRng,
+    /// The tick at which scores last decayed.
+    last_decay_tick: u64,
+    /// The AI behaviors selectable at runtime.
+    pub behavior_registry: BehaviorRegistry,
+    /// The name of the behavior given to the AI entities added without one.
+    default_behavior: String,
+    /// What drives each AI entity.
+    ai_drivers: HashMap<u32, AiDriver>,
+    /// The observers notified of the arena events.
+    observers: Vec<Box<dyn GameObserver>>,
+    /// The messages of the server, where the game reports what is not an arena event, `None` without a server.
+    messages: Option<MessageHub>,
+    /// The last arena events, read by the UI and the clients.
+    pub events: EventQueue,
+    /// The replay being recorded, `None` when not recording.
+    recorder: Option<Recorder>,
+    /// The cumulative scores of the players across matches.
+    pub leaderboard: Leaderboard,
+    /// What the report of the running round is built from.
+    round_log: RoundLog,
+    /// The directory the report of each round is written to when it ends, `None` to keep it in memory only.
+    pub report_dir: Option<PathBuf>,
+    /// The report of the last round that ended, see `last_report`.
+    last_report: Option<MatchReport>,
+    /// For each pair of entities in contact, by increasing ids, until when their contact is the same impact.
+    ram_cooldowns: HashMap<(u32, u32), Duration>,
+    /// The last id given to an entity. Ids only grow, so that a removed entity's id is never reused.
+    last_entity_id: u32,
+    /// The entity holding each name, kept in step with the entities.
+    names: NameIndex,
+    /// For each entity outside the zone, the damage taken from the zone not yet removed from its health.
+    zone_damage: HashMap<u32, f32>,
+    /// For each entity inside a damage zone, the damage taken from the hazards not yet removed from its health,
+    /// and the damage its armor shrugged off not yet counted in its `damage_mitigated`.
+    hazard_damage: HashMap<u32, (f32, f32)>,
+    /// For each entity, where it stopped and since when, for the anti-camping rule.
+    camp_watches: HashMap<u32, CampWatch>,
+    /// The number of times the simulation was reset, see `reset_simulation`.
+    reset_count: u64,
+    /// The movement trails of the entities, sampled after each step; its settings can be changed at any time.
+    pub trails: TrailStore,
+    /// The copy of `trails` handed to the last render snapshot, copied again only when they changed.
+    published_trails: Arc<TrailStore>,
+    /// The entities moved by the game rather than by the physics since the last render snapshot.
+    teleported: HashSet<u32>,
+    /// Where the render snapshots are published for the UI, see `publish_render`.
+    render_slot: RenderSlot,
+    /// Where the world snapshots are published for the server, see `publish_world`.
+    world_slot: WorldSlot,
+    /// The copy of `events` handed to the last snapshots, copied again only when an event came.
+    published_events: Arc<EventQueue>,
+}
+
+/// The outcome of a bullet hitting an entity.
+enum HitOutcome {
+    /// The hit was blocked or ignored.
+    Ignored,
+    /// The entity lost health.
+    Damaged,
+    /// The entity was destroyed, earning the shooter a comeback bonus, 0 without one.
+    Destroyed { comeback_bonus: i32 },
+}
+
+/// The behavior driving an AI entity, and how well it plays.
+struct AiDriver {
+    /// The name of the behavior in `behavior_registry`.
+    name: String,
+    behavior: Box<dyn AiBehavior>,
+    difficulty: AiDifficulty,
+    /// The number of consecutive control ticks with another entity in sight.
+    sighted_ticks: u32,
+    /// The entity the behavior last chose to shoot at and the direction it aimed in, see `AiCommand::enemy`.
+    aim: Option<(u32, f32)>,
+    /// Whether the behavior failed, see `AiBehavior::failure`: the entity is no longer driven.
+    failed: bool,
+}
+
+/// What the report of the running round is built from, see `MatchReport::build`.
+#[derive(Default)]
+struct RoundLog {
+    /// The simulation step the round started at.
+    start_tick: u64,
+    /// The hits and kills of the round, with their simulation step.
+    events: Vec<(u64, GameEvent)>,
+    /// The players who left the arena during the round, as they were when they left.
+    departed: Vec<PlayerRecord>,
+}
+
+/// What a rigid body of the arena belongs to, with its index, see `GameLogic::body_kinds`.
+#[derive(Clone, Copy)]
+enum BodyKind {
+    Entity(usize),
+    Bullet(usize),
+}
+
+impl BodyKind {
+    /// Returns the index of the entity, `None` for a bullet.
+    fn entity(self) -> Option<usize> {
+        match self {
+            BodyKind::Entity(index) => Some(index),
+            BodyKind::Bullet(_) => None,
+        }
+    }
+
+    /// Returns the index of the bullet, `None` for an entity.
+    fn bullet(self) -> Option<usize> {
+        match self {
+            BodyKind::Bullet(index) => Some(index),
+            BodyKind::Entity(_) => None,
+        }
+    }
 }
 
 impl GameLogic {
@@ -29,17 +256,77 @@ impl GameLogic {
     ///
     /// # Examples
     /// ```
+    /// # use universal_rust_server_software::game_logic::GameLogic;
     /// let game_logic = GameLogic::new();
+    /// assert!(game_logic.entities.is_empty());
     /// ```
     pub fn new() -> Self {
+        GameLogic::with_arena(ArenaConfig::default())
+    }
+
+    /// Creates a new `GameLogic` instance with an arena of the given size.
+    ///
+    /// # Parameters
+    /// - `arena`: The size of the arena, see `ArenaConfig::new`.
+    pub fn with_arena(arena: ArenaConfig) -> Self {
         let mut physics_engine = PhysicsEngine::default();
-        physics_engine.setup_boundaries();
+        physics_engine.setup_boundaries(&arena);
 
         Self {
             physics_engine,
+            arena,
             entities: Vec::new(),
             bullets: Vec::new(),
             obstacles: Vec::new(),
+            map_seed: None,
+            map_name: None,
+            spawn_points: Vec::new(),
+            power_ups: Vec::new(),
+            mines: Vec::new(),
+            control_points: Vec::new(),
+            hazards: Vec::new(),
+            ghost_cameras: Vec::new(),
+            last_power_up_tick: 0,
+            match_state: MatchState::new(Duration::ZERO),
+            wave_director: None,
+            respawns_granted: 0,
+            score_rules: ScoreRules::default(),
+            game_config: GameConfig::default(),
+            tick: 0,
+            last_step_duration: Duration::ZERO,
+            achieved_tick_rate: 0.0,
+            profiling_enabled: false,
+            ai_debug_enabled: false,
+            inspected: None,
+            step_timings: StepTimings::default(),
+            paused: false,
+            time_scale: 1.0,
+            rng: StdRng::seed_from_u64(rand::rng().random()),
+            last_decay_tick: 0,
+            behavior_registry: BehaviorRegistry::default(),
+            default_behavior: WANDERER.to_string(),
+            ai_drivers: HashMap::new(),
+            observers: Vec::new(),
+            messages: None,
+            events: EventQueue::new(),
+            recorder: None,
+            leaderboard: Leaderboard::default(),
+            round_log: RoundLog::default(),
+            report_dir: None,
+            last_report: None,
+            ram_cooldowns: HashMap::new(),
+            last_entity_id: 0,
+            names: NameIndex::default(),
+            zone_damage: HashMap::new(),
+            hazard_damage: HashMap::new(),
+            camp_watches: HashMap::new(),
+            trails: TrailStore::default(),
+            published_trails: Arc::default(),
+            reset_count: 0,
+            teleported: HashSet::new(),
+            render_slot: RenderSlot::default(),
+            world_slot: WorldSlot::default(),
+            published_events: Arc::default(),
         }
     }
 
@@ -49,21 +336,83 @@ impl GameLogic {
     /// - `name`: The name of the entity.
     pub fn add_entity(&mut self, name: String) -> u32 {
         let entity_id = self.next_entity_id();
-        let entity = Entity::new(entity_id, name, &mut self.physics_engine, false);
-        self.entities.push(entity);
+        let now = self.sim_time();
+        let name = self.names.free_name(&name, entity_id);
+        let entity = Entity::new(entity_id, name, &mut self.physics_engine, &self.game_config, &self.arena, &mut self.rng, now);
+        self.notify_spawn(&entity);
+        self.push_entity(entity);
+        entity_id
+    }
 
-        println!("Current entities in game:");
-        for entity in &self.entities {
-            println!("Entity ID: {}, Name: {}", entity.id, entity.name);
+    /// Adds a new entity standing still at a point of the arena, instead of a random one.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the entity.
+    /// - `x`, `y`: Where the entity appears.
+    /// - `angle`: The direction the entity faces, in radians.
+    /// - `is_ai`: Whether the entity is driven by the default behavior, at `AiDifficulty::Normal`.
+    ///
+    /// # Returns
+    /// The id of the entity, or why it cannot stand there: outside the walls or in an obstacle.
+    pub fn add_entity_at(&mut self, name: String, x: f32, y: f32, angle: f32, is_ai: bool) -> Result<u32, String> {
+        if is_ai {
+            return self.add_ai_at(name, x, y, angle, AiDifficulty::Normal);
         }
+        self.check_spawn_position(x, y)?;
+        let entity_id = self.next_entity_id();
+        let now = self.sim_time();
+        let name = self.names.free_name(&name, entity_id);
+        let entity = Entity::new_at(entity_id, name, &mut self.physics_engine, &self.game_config, (x, y), angle, now);
+        self.notify_spawn(&entity);
+        self.push_entity(entity);
+        Ok(entity_id)
+    }
 
-        entity_id
+    /// Adds a new AI entity standing still at a point of the arena, driven by the default behavior.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the entity.
+    /// - `x`, `y`: Where the entity appears.
+    /// - `angle`: The direction the entity faces, in radians.
+    /// - `difficulty`: How well it plays.
+    ///
+    /// # Returns
+    /// The id of the entity, or why it cannot stand there: outside the walls or in an obstacle.
+    pub fn add_ai_at(&mut self, name: String, x: f32, y: f32, angle: f32, difficulty: AiDifficulty) -> Result<u32, String> {
+        self.check_spawn_position(x, y)?;
+        let id = self.next_entity_id();
+        let now = self.sim_time();
+        let name = self.names.free_name(&name, id);
+        let entity = Entity::new_at(id, name, &mut self.physics_engine, &self.game_config, (x, y), angle, now);
+        Ok(self.register_ai(entity, None, difficulty))
+    }
+
+    /// Checks that an entity fits at a point: inside the walls and clear of the obstacles.
+    ///
+    /// # Parameters
+    /// - `x`, `y`: The center of the entity.
+    fn check_spawn_position(&self, x: f32, y: f32) -> Result<(), String> {
+        // Une marge négative rétrécit l'arène : l'entité entière doit tenir entre les murs
+        if !self.arena.contains_with_margin(x, y, -AppDefines::ENTITY_HALF_SIZE) {
+            return Err(format!("({:.0}, {:.0}) is outside the arena", x, y));
+        }
+        if self.obstacles.iter().any(|obstacle| obstacle.distance((x, y)) < AppDefines::ENTITY_HALF_SIZE) {
+            return Err(format!("({:.0}, {:.0}) is inside an obstacle", x, y));
+        }
+        Ok(())
     }
 
     /// Removes an entity from the game by its ID.
+    ///
+    /// The bullets it fired keep flying, but no longer belong to anyone: they hurt and credit no one.
     pub fn remove_entity_by_id(&mut self, entity_id: u32) {
         if let Some(index) = self.entities.iter().position(|e| e.id == entity_id) {
+            // Notifié avant le retrait, pour que la description garde le nom de l'entité
+            self.notify(GameEvent::Despawn { id: entity_id });
             let entity = self.entities.remove(index);
+            self.names.remove(&entity.name, entity_id);
+            self.leaderboard.record_breakdown(&entity.name, &entity.breakdown);
+            self.round_log.departed.push(PlayerRecord::of(&entity));
             self.physics_engine.bodies.remove(
                 entity.handle,
                 &mut self.physics_engine.islands,
@@ -72,28 +421,335 @@ impl GameLogic {
                 &mut self.physics_engine.multibody_joints,
                 true,
             );
-            println!("Entity with ID {} has been removed from the game.", entity_id);
+            self.ai_drivers.remove(&entity_id);
+            self.zone_damage.remove(&entity_id);
+            self.hazard_damage.remove(&entity_id);
+            self.camp_watches.remove(&entity_id);
+            self.trails.remove(entity_id, self.tick);
+            // Les mines d'une entité partent avec elle
+            while let Some(index) = self.mines.iter().position(|mine| mine.owner == entity_id) {
+                self.remove_mine(index);
+            }
+            // Ses balles deviennent orphelines, comme celles d'un tireur absent d'une sauvegarde
+            for bullet in self.bullets.iter_mut().filter(|bullet| bullet.shooter == entity.handle) {
+                bullet.shooter = RigidBodyHandle::invalid();
+                self.physics_engine.bodies[bullet.handle].user_data = BodyIdentity::Bullet(None).encode();
+            }
+            self.publish_world();
+        }
+    }
+
+    /// Sets the messages of the server the game reports to, see `report`.
+    ///
+    /// # Parameters
+    /// - `messages`: The messages of the server.
+    pub fn set_messages(&mut self, messages: MessageHub) {
+        self.messages = Some(messages);
+    }
+
+    /// Adds a message of the game to the messages of the server, dropped without a server.
+    ///
+    /// The arena events are written to the messages by the `EventLogger`, this is for the rest:
+    /// the files that cannot be written, the AI entities that fail, the changes of the simulation.
+    ///
+    /// # Parameters
+    /// - `text`: The text of the message.
+    /// - `message_type`: The type of the message.
+    pub(crate) fn report(&self, text: String, message_type: MessageType) {
+        if let Some(messages) = &self.messages {
+            add_message(messages, text, message_type, MessageOrigin::Game);
+        }
+    }
+
+    /// Registers an observer notified of every arena event from now on.
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Notifies all observers of an event at the current step, and adds it to the event queue.
+    fn notify(&mut self, event: GameEvent) {
+        for observer in &mut self.observers {
+            observer.on_event(self.tick, &event);
         }
+        if let GameEvent::Kill { shooter, victim, .. } = event {
+            self.record_kill(shooter, victim);
+        }
+        // Les tirs, bien plus nombreux, ne servent pas au rapport de la manche
+        if matches!(event, GameEvent::Hit { .. } | GameEvent::Kill { .. }) {
+            self.round_log.events.push((self.tick, event.clone()));
+        }
+        let text = self.describe(&event);
+        self.events.push(self.tick, event, text);
+    }
+
+    /// Counts a kill in the score breakdowns and applies the death penalty of the match rules.
+    /// An entity destroying itself only counts a death.
+    ///
+    /// # Parameters
+    /// - `shooter`: The id of the entity credited with the kill.
+    /// - `victim`: The id of the entity destroyed.
+    fn record_kill(&mut self, shooter: u32, victim: u32) {
+        let death_penalty = self.game_config.death_penalty;
+        for entity in &mut self.entities {
+            if entity.id == victim {
+                entity.breakdown.deaths += 1;
+                entity.score -= death_penalty;
+            } else if entity.id == shooter {
+                entity.breakdown.kills += 1;
+            }
+        }
+    }
+
+    /// Describes an event with the names of the entities, for display.
+    fn describe(&self, event: &GameEvent) -> String {
+        let name = |id: &u32| {
+            self.entities.iter().find(|e| e.id == *id).map_or_else(|| format!("#{}", id), |e| e.name.clone())
+        };
+        match event {
+            GameEvent::Spawn { name, .. } => format!("{} entered the arena", name),
+            GameEvent::BulletFired { shooter, .. } => format!("{} fired", name(shooter)),
+            GameEvent::Hit { shooter, victim, damage, .. } => format!("{} hit {} ({} damage)", name(shooter), name(victim), damage),
+            GameEvent::Kill { shooter, victim, .. } if shooter == victim => format!("{} was destroyed", name(victim)),
+            GameEvent::Kill { shooter, victim, .. } => format!("{} destroyed {}", name(shooter), name(victim)),
+            GameEvent::Detonation { owner, .. } => format!("A mine of {} exploded", name(owner)),
+            GameEvent::Despawn { id } => format!("{} left the arena", name(id)),
+            GameEvent::OutOfBounds { id, .. } => format!("{} got out of the arena and was brought back", name(id)),
+            GameEvent::PickUp { id, power_up, .. } => format!("{} picked up {}", name(id), power_up),
+            GameEvent::RuleChange { rule, value } => format!("Rule {} set to {}", rule, value),
+            GameEvent::RoundStart { round } => format!("Round {} started", round),
+            GameEvent::RoundEnd => "The simulation was reset".to_string(),
+            GameEvent::MatchEnd { round, reason, winners, score } => MatchState::result_text(*round, reason, winners, *score),
+            GameEvent::Penalty { id, points, reason } => format!("{} loses {} points for {}", name(id), points, reason.text()),
+            GameEvent::Bonus { id, points, reason } => format!("{} earns {} points for {}", name(id), points, reason.text()),
+            GameEvent::Camping { id, .. } => format!("{} is camping", name(id)),
+            GameEvent::Capture { point, owner: Side::Team(team) } => format!("Team {} captured {}", team, point),
+            GameEvent::Capture { point, owner: Side::Entity(id) } => format!("{} captured {}", name(id), point),
+            GameEvent::Wave { text, .. } => text.clone(),
+        }
+    }
+
+    /// Notifies all observers that an entity entered the arena.
+    fn notify_spawn(&mut self, entity: &Entity) {
+        self.notify(GameEvent::Spawn {
+            id: entity.id,
+            name: entity.name.clone(),
+            is_ai: entity.is_ai,
+            x: entity.x,
+            y: entity.y,
+        });
+    }
+
+    /// Notifies all observers that a game rule changed.
+    fn notify_rule_change(&mut self, rule: &str, value: &str) {
+        self.notify(GameEvent::RuleChange { rule: rule.to_string(), value: value.to_string() });
+    }
+
+    /// Selects the behavior given to the AI entities added without one.
+    ///
+    /// Existing AI entities keep their behavior.
+    ///
+    /// # Parameters
+    /// - `name`: The name or an alias of a behavior registered in `behavior_registry`.
+    pub fn set_default_behavior(&mut self, name: &str) -> Result<(), String> {
+        // Un alias est gardé sous le nom du comportement
+        let Some(name) = self.behavior_registry.resolve(name).map(str::to_string) else {
+            return Err(format!("Unknown AI behavior: {}", name));
+        };
+        self.notify_rule_change("ai_behavior", &name);
+        self.default_behavior = name;
+        Ok(())
+    }
+
+    /// Switches every AI entity to a behavior, and makes it the default for the AI entities added next.
+    ///
+    /// Each entity gets a new instance of the behavior, used from its next control tick, and keeps
+    /// its difficulty. The entities driven by a script keep their script.
+    ///
+    /// # Parameters
+    /// - `name`: The name or an alias of a behavior registered in `behavior_registry`.
+    ///
+    /// # Returns
+    /// The number of AI entities switched, or why the behavior cannot be used.
+    pub fn swap_behavior(&mut self, name: &str) -> Result<usize, String> {
+        self.set_default_behavior(name)?;
+        let name = self.default_behavior.clone();
+        let ids: Vec<u32> = self.ai_drivers.iter()
+            .filter(|(_, driver)| self.behavior_registry.contains(&driver.name))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &ids {
+            let difficulty = self.ai_drivers[id].difficulty;
+            let driver = self.new_ai_driver(&name, difficulty);
+            self.ai_drivers.insert(*id, driver);
+        }
+        Ok(ids.len())
+    }
+
+    /// Returns the name of the behavior given to the AI entities added without one.
+    pub fn default_behavior(&self) -> &str {
+        &self.default_behavior
+    }
+
+    /// Returns a new entity id, never given before.
+    ///
+    /// A client still holding the id of a destroyed entity must not end up controlling another one.
+    fn next_entity_id(&mut self) -> u32 {
+        self.last_entity_id += 1;
+        self.last_entity_id
+    }
+
+    /// Adds a new entity to the entities, its name to the names.
+    fn push_entity(&mut self, entity: Entity) {
+        self.names.insert(&entity.name, entity.id);
+        self.entities.push(entity);
+        // Le client de l'entité la voit dès sa création, sans attendre le pas suivant
+        self.publish_world();
+    }
+
+    /// Returns the entity holding a name.
+    pub fn entity_by_name(&self, name: &str) -> Option<&Entity> {
+        let id = self.names.get(name)?;
+        self.entities.iter().find(|entity| entity.id == id)
+    }
+
+    /// Renames an entity.
+    ///
+    /// A name held by another entity is refused, or followed by the first free `-2`, `-3`… suffix
+    /// when `auto_suffix_names` is on. The entity frees its previous name.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the entity.
+    /// - `name`: The new name, checked by `NameIndex::validate`.
+    ///
+    /// # Returns
+    /// The name given, or why the entity keeps its name.
+    pub fn rename_entity(&mut self, entity_id: u32, name: &str) -> Result<String, NameError> {
+        NameIndex::validate(name)?;
+        let name = match self.names.is_free_for(name, entity_id) {
+            true => name.to_string(),
+            false if self.game_config.auto_suffix_names => self.names.free_name(name, entity_id),
+            false => return Err(NameError::Taken),
+        };
+        let entity = self.entities.iter_mut().find(|entity| entity.id == entity_id).ok_or(NameError::NoEntity)?;
+        self.names.remove(&entity.name, entity_id);
+        self.names.insert(&name, entity_id);
+        entity.set_name(name.clone());
+        Ok(name)
     }
 
-    fn next_entity_id(&self) -> u32 {
-        // Par exemple un simple compteur ou max + 1
-        self.entities.iter().map(|e| e.id).max().unwrap_or(0) + 1
+    /// Puts an entity in a team, or out of any, updating its collisions with its new teammates at once.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the entity.
+    /// - `team`: The team, `None` to leave it.
+    ///
+    /// # Returns
+    /// `false` if there is no entity with this id.
+    pub fn set_team(&mut self, entity_id: u32, team: Option<u8>) -> bool {
+        let Some(entity) = self.entities.iter_mut().find(|entity| entity.id == entity_id) else { return false };
+        entity.team = team;
+        entity.update_collision_groups(&mut self.physics_engine, &self.game_config);
+        true
     }
 
     pub fn get_entity_mut(&mut self, id: u32) -> Option<&mut Entity> {
         self.entities.iter_mut().find(|e| e.id == id)
     }
 
+    /// Returns what drives an AI entity.
+    ///
+    /// # Returns
+    /// The name of its behavior and its difficulty, `None` if the entity is not an AI.
+    pub fn ai_driver_of(&self, entity_id: u32) -> Option<(&str, AiDifficulty)> {
+        self.ai_drivers.get(&entity_id).map(|driver| (driver.name.as_str(), driver.difficulty))
+    }
+
+    /// Destroys an entity as if it had destroyed itself: the kill is reported, then the entity is removed.
+    ///
+    /// # Returns
+    /// Whether the entity existed.
+    pub fn kill_entity(&mut self, entity_id: u32) -> bool {
+        let Some(entity) = self.entities.iter().find(|entity| entity.id == entity_id) else {
+            return false;
+        };
+        let (x, y) = (entity.x, entity.y);
+        self.notify(GameEvent::Kill { shooter: entity_id, victim: entity_id, x, y });
+        self.remove_entity_by_id(entity_id);
+        true
+    }
+
+    /// Gives the control of an entity to the keyboard of the game UI, or gives it back.
+    ///
+    /// While the keyboard drives it, the actuator commands of its client are refused and the AI
+    /// leaves it alone. Given back, it stops: its motors, trigger and gun traverse are set to neutral.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The entity.
+    /// - `controlled`: Whether the keyboard drives the entity.
+    ///
+    /// # Returns
+    /// Whether the entity existed.
+    pub fn set_keyboard_control(&mut self, entity_id: u32, controlled: bool) -> bool {
+        let Some(entity) = self.get_entity_mut(entity_id) else {
+            return false;
+        };
+        entity.keyboard_controlled = controlled;
+        if !controlled {
+            for (code, neutral) in [
+                (AppDefines::ACTUATOR_MOTOR_LEFT, 0.5),
+                (AppDefines::ACTUATOR_MOTOR_RIGHT, 0.5),
+                (AppDefines::ACTUATOR_GUN_TRIGGER, 0.0),
+                (AppDefines::ACTUATOR_GUN_TRAVERSE, 0.5),
+            ] {
+                entity.set_actuator(code, neutral);
+            }
+        }
+        true
+    }
+
+    /// Moves an entity to a point of the arena and stops it.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The entity to move.
+    /// - `x`, `y`: Where to, brought back inside the walls if needed.
+    ///
+    /// # Returns
+    /// Whether the entity existed.
+    pub fn teleport_entity(&mut self, entity_id: u32, x: f32, y: f32) -> bool {
+        let Some(entity) = self.entities.iter_mut().find(|entity| entity.id == entity_id) else {
+            return false;
+        };
+        let (x, y) = self.arena.clamp(x, y, AppDefines::ENTITY_HALF_SIZE);
+        let body = &mut self.physics_engine.bodies[entity.handle];
+        body.set_translation(vector![x, y], true);
+        body.set_linvel(vector![0.0, 0.0], true);
+        body.set_angvel(0.0, true);
+        entity.x = x;
+        entity.y = y;
+        entity.vx = 0.0;
+        entity.vy = 0.0;
+        // Le rendu ne doit pas la faire glisser depuis son ancienne position
+        self.teleported.insert(entity_id);
+        true
+    }
+
     fn apply_actuators(
         entities: &mut Vec<Entity>,
         physics_engine: &mut PhysicsEngine,
         bullets: &mut Vec<Bullet>,
-    ) {
+        slows: &HashMap<u32, f32>,
+        config: &GameConfig,
+        rng: &mut StdRng,
+        tick: u64,
+    ) -> Vec<GameEvent> {
+        let mut fired = Vec::new();
         for entity in entities.iter_mut() {
             let Some(rb) = physics_engine.bodies.get_mut(entity.handle) else { continue };
+            // Les commandes arrivées dans le désordre s'appliquent dans l'ordre de leurs numéros
+            entity.apply_pending_actuators();
 
-            let max_speed = 100.0;
+            let max_speed = if entity.speed_boost_until.is_some() { 100.0 * AppDefines::POWER_UP_SPEED_FACTOR } else { 100.0 };
+            let max_speed = max_speed * slows.get(&entity.id).copied().unwrap_or(1.0);
             let left_speed = (entity.motor_left - 0.5) * 2.0 * max_speed;
             let right_speed = (entity.motor_right - 0.5) * 2.0 * max_speed;
 
@@ -108,21 +764,17 @@ impl GameLogic {
             rb.set_linvel(vector![vx, vy], true);
             rb.set_angvel(rotation, true);
 
-            if entity.gun_trigger > 0.5 {
-                // Appelle la fonction shoot_ball pour gérer le tir
-                let before_bullets_len = bullets.len();
-                // shoot_ball va gérer le cooldown et la création de la balle
-                GameLogic::shoot_ball(entity, physics_engine, bullets);
-                // Optionnel: mettre à jour last_shot ici si shoot_ball ne le fait pas
-
-                // Si shoot_ball a ajouté une balle, met à jour last_shot
-                if bullets.len() > before_bullets_len {
-                    entity.last_shot = Instant::now();
+            entity.turn_guns(config.gun_traverse_rate * physics_engine.integration_parameters.dt);
+            for turret in 0..entity.turrets.len() {
+                if entity.turrets[turret].trigger > 0.5 {
+                    fired.extend(GameLogic::shoot_ball(entity, turret, physics_engine, bullets, config, rng, tick));
                 }
             }
 
-            entity.gun_orientation = entity.gun_traverse as f64;
+            entity.update_shield(physics_engine.integration_parameters.dt);
+            entity.update_fire_energy(physics_engine.integration_parameters.dt, config);
         }
+        fired
     }
 
     /// Makes an entity shoot a bullet.
@@ -150,150 +802,2114 @@ impl GameLogic {
         self.entities[shooter_index].last_shot = Instant::now();
     }*/
 
-    /// Makes an entity shoot a bullet.
+    /// Makes a turret of an entity shoot a bullet, unless its fire cooldown is still running.
+    ///
+    /// Players and AI bots both fire through here, so they share the same cooldown rules.
+    /// The turrets of an entity cool down each on their own, but share its cap on the bullets.
+    /// The weapon of the entity sets the bullets of the shot, see `BulletKind`: every bullet
+    /// of a scatter shot counts against the cap, for a single cooldown and energy cost.
     ///
     /// # Parameters
-    /// - `shooter_index`: The index of the entity that is shooting.
+    /// - `shooter`: The entity that is shooting.
+    /// - `turret`: The index of the turret firing in `shooter.turrets`.
+    /// - `physics_engine`: The physics engine the bullet is added to.
+    /// - `bullets`: The bullets of the game.
+    /// - `config`: The match rules giving the speed, radius, spread and lifetime of the bullet.
+    /// - `rng`: The random number generator of the game, drawing the deviation of the bullet.
+    /// - `tick`: The current simulation step.
+    ///
+    /// # Returns
+    /// A `BulletFired` event per bullet, none if the turret is still cooling down, the entity lacks the fire energy
+    /// of a shot or already has as many bullets flying as the match rules allow, unless they replace its oldest bullets.
     pub fn shoot_ball(
-        shooter: &Entity,
+        shooter: &mut Entity,
+        turret: usize,
         physics_engine: &mut PhysicsEngine,
-        bullets: &mut Vec<Bullet>
-    ) {
-        if shooter.last_shot.elapsed().as_millis() < AppDefines::BOT_RATE_OF_FIRE as u128 {
-            return;
+        bullets: &mut Vec<Bullet>,
+        config: &GameConfig,
+        rng: &mut StdRng,
+        tick: u64,
+    ) -> Vec<GameEvent> {
+        let dt = physics_engine.integration_parameters.dt;
+        let now = GameLogic::time_at(tick, dt);
+        let kind = shooter.weapon;
+        let pellets = kind.fan(0.0).len() as u32;
+        let cap = config.max_bullets_per_entity;
+        let at_cap = cap > 0 && shooter.live_bullets + pellets > cap;
+        let cost = config.shot_energy_cost * kind.energy_factor();
+        // Refusé avant la recharge : le tir raté ne relance pas l'attente
+        if shooter.fire_energy < cost {
+            return Vec::new();
+        }
+        if (at_cap && !config.replace_oldest_bullet) || !shooter.try_fire(turret, now) {
+            return Vec::new();
+        }
+        shooter.fire_energy -= cost;
+        while cap > 0 && shooter.live_bullets + pellets > cap {
+            // Les balles sont rangées par ordre de tir : la première du tireur est la plus ancienne
+            let Some(index) = bullets.iter().position(|bullet| bullet.shooter == shooter.handle) else { break };
+            let oldest = bullets.remove(index);
+            physics_engine.release_bullet(oldest.handle, tick);
+            shooter.live_bullets -= 1;
         }
 
-        let bullet = Bullet::new(
-            shooter.handle,
-            physics_engine,
-            500.0,  // speed
-            5.0,    // radius
-            Some(shooter.gun_orientation as f32),
-        );
+        let body = &physics_engine.bodies[shooter.handle];
+        let (muzzle, angle) = shooter.turrets[turret].muzzle(body);
+        // Sans dispersion, aucun tirage : les parties enregistrées restent identiques
+        let spread = GameLogic::spread(config, body);
+        let angle = if spread > 0.0 { angle + rng.random_range(-spread..=spread) } else { angle };
+        let expires_at = tick + GameLogic::ticks_in(config.bullet_lifetime_ms as f32 / 1000.0, dt);
+        // Le tireur lancé à pleine vitesse ne rattrape pas sa balle au départ
+        let grace_until = if config.bullets_ignore_shooter {
+            u64::MAX
+        } else {
+            tick + GameLogic::ticks_in(config.shooter_grace_ms as f32 / 1000.0, dt)
+        };
+
+        let mut fired = Vec::new();
+        for angle in kind.fan(angle) {
+            let bullet = Bullet::new(shooter.handle, physics_engine, config, kind, muzzle, angle, tick..expires_at);
+            bullet.pass_through_shooter(physics_engine, grace_until);
+            let position = *physics_engine.bodies[bullet.handle].translation();
+            bullets.push(bullet);
+            shooter.live_bullets += 1;
+            shooter.breakdown.shots_fired += 1;
+            fired.push(GameEvent::BulletFired { shooter: shooter.id, x: position.x, y: position.y, angle });
+        }
+        fired
+    }
 
-        bullets.push(bullet);
+    /// Returns how many more bullets an entity can have flying at once.
+    ///
+    /// # Parameters
+    /// - `entity`: The entity.
+    ///
+    /// # Returns
+    /// The number of bullets left, or `None` when the match rules set no cap.
+    pub fn bullets_left(&self, entity: &Entity) -> Option<u32> {
+        let cap = self.game_config.max_bullets_per_entity;
+        (cap > 0).then(|| cap.saturating_sub(entity.live_bullets))
     }
 
+    /// Returns the largest deviation of a bullet from the aim of a shooter.
+    ///
+    /// # Parameters
+    /// - `config`: The match rules giving the spread.
+    /// - `body`: The rigid body of the shooter, whose speed widens the spread.
+    ///
+    /// # Returns
+    /// The deviation, in radians.
+    pub fn spread(config: &GameConfig, body: &RigidBody) -> f32 {
+        config.base_spread
+            + config.spread_per_speed * body.linvel().norm()
+            + config.spread_per_angular_speed * body.angvel().abs()
+    }
 
-    /// Advances the simulation by one step.
-    pub fn step(&mut self) {
-        let physics = &mut self.physics_engine;
-        let entities = &mut self.entities;
-        let bullets = &mut self.bullets;
+    /// Casts rays evenly spread around an entity, starting from its front.
+    ///
+    /// The rays ignore the entity itself and the bullets. The query pipeline is
+    /// refreshed by every physics step, so colliders added since the last step are not seen yet.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the scanning entity.
+    /// - `rays`: The number of rays.
+    /// - `max_range`: The range of the rays.
+    ///
+    /// # Returns
+    /// The hit distance of each ray, counterclockwise, `max_range` for rays hitting nothing,
+    /// or `None` if the entity does not exist.
+    pub fn lidar_scan(&self, entity_id: u32, rays: usize, max_range: f32) -> Option<Vec<f32>> {
+        let entity = self.entities.iter().find(|e| e.id == entity_id)?;
+        let body = self.physics_engine.bodies.get(entity.handle)?;
+        let origin = *body.translation();
+        let heading = body.rotation().angle();
+
+        let bullet_handles: Vec<RigidBodyHandle> = self.bullets.iter().map(|bullet| bullet.handle).collect();
+        let not_a_bullet = |_: ColliderHandle, collider: &Collider| {
+            collider.parent().map_or(true, |parent| !bullet_handles.contains(&parent))
+        };
+        let filter = QueryFilter::default()
+            .exclude_sensors()
+            .exclude_rigid_body(entity.handle)
+            .predicate(&not_a_bullet);
+
+        let distances = (0..rays).map(|i| {
+            let angle = heading + i as f32 * std::f32::consts::TAU / rays as f32;
+            self.physics_engine
+                .raycast(origin, vector![angle.cos(), angle.sin()], max_range, filter)
+                .map_or(max_range, |(_, distance)| distance)
+        }).collect();
+        Some(distances)
+    }
 
-        GameLogic::apply_actuators(entities, physics, bullets);
+    /// Finds the entity closest to another one.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the reference entity.
+    /// - `enemies_only`: Whether the teammates of the entity are left out.
+    ///
+    /// # Returns
+    /// The closest entity and its distance, or `None` if the entity does not exist or is alone.
+    pub fn closest_entity(&self, entity_id: u32, enemies_only: bool) -> Option<(&Entity, f32)> {
+        let entity = self.entities.iter().find(|e| e.id == entity_id)?;
+        self.entities
+            .iter()
+            .filter(|other| other.id != entity_id && !(enemies_only && entity.is_teammate(other)))
+            .map(|other| (other, (other.x - entity.x).hypot(other.y - entity.y)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
 
-        physics.step();
-        self.handle_collisions();
-        self.remove_out_of_bounds_bullets();
-        self.remove_expired_bullets();
+    /// Finds the bullet closest to an entity among the bullets that can hit it.
+    ///
+    /// Bullets fired by the entity itself are left out, and so are the bullets of
+    /// its teammates unless friendly fire is enabled.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the reference entity.
+    ///
+    /// # Returns
+    /// The distance and bearing of the bullet, with the bearing from the front of the entity,
+    /// counterclockwise, in radians within `[-π, π]`. `None` if the entity does not exist
+    /// or no bullet can hit it.
+    pub fn closest_hostile_bullet(&self, entity_id: u32) -> Option<(f32, f32)> {
+        let entity = self.entities.iter().find(|e| e.id == entity_id)?;
+        let body = self.physics_engine.bodies.get(entity.handle)?;
+        let origin = (body.translation().x, body.translation().y);
+        let heading = body.rotation().angle();
+        self.bullets
+            .iter()
+            .filter(|bullet| bullet.shooter != entity.handle)
+            .filter(|bullet| {
+                self.game_config.friendly_fire
+                    || !self.entities.iter().any(|shooter| shooter.handle == bullet.shooter && entity.is_teammate(shooter))
+            })
+            .filter_map(|bullet| self.physics_engine.bodies.get(bullet.handle))
+            .map(|bullet_body| {
+                let (x, y) = (bullet_body.translation().x, bullet_body.translation().y);
+                ((x - origin.0).hypot(y - origin.1), GameLogic::bearing(origin, heading, (x, y)))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
     }
 
-    /// Handles collisions between entities and bullets.
-    fn handle_collisions(&mut self) {
-        let mut bullet_indices_to_remove = Vec::new();
-        let mut entity_ids_to_remove = Vec::new();
-        for event in self.physics_engine.collision_events.drain(..) {
-            if let CollisionEvent::Started(collider1, collider2, _) = event {
-                let body1 = self.physics_engine.colliders[collider1].parent();
-                let body2 = self.physics_engine.colliders[collider2].parent();
+    /// Finds the obstacle whose edge is closest to an entity.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the reference entity.
+    /// - `with_walls`: Whether the walls of the arena count as obstacles.
+    ///
+    /// # Returns
+    /// The distance from the center of the entity to the closest point of the obstacle, the bearing
+    /// of that point as in `closest_hostile_bullet`, and the obstacle, `None` for a wall of the arena.
+    /// `None` if the entity does not exist or there is no obstacle.
+    pub fn closest_obstacle(&self, entity_id: u32, with_walls: bool) -> Option<(f32, f32, Option<&Obstacle>)> {
+        let entity = self.entities.iter().find(|e| e.id == entity_id)?;
+        let body = self.physics_engine.bodies.get(entity.handle)?;
+        let origin = *body.translation();
+        let closest = GameLogic::closest_surface(origin, &self.obstacle_views(), with_walls.then_some(&self.arena))?;
+        let bearing = GameLogic::bearing((origin.x, origin.y), body.rotation().angle(), (closest.point.x, closest.point.y));
+        Some((closest.distance, bearing, closest.obstacle.map(|index| &self.obstacles[index])))
+    }
 
-                if let (Some(body1), Some(body2)) = (body1, body2) {
-                    for (bullet_index, bullet) in self.bullets.iter().enumerate() {
-                        if bullet.handle == body1 || bullet.handle == body2 {
-                            bullet_indices_to_remove.push(bullet_index);
-
-                            if let Some(entity_index) = self.entities.iter().position(|e| e.handle == body1 || e.handle == body2) {
-                                // Éviter que le tireur s'inflige des dégâts à lui-même
-                                if bullet.shooter != self.entities[entity_index].handle {
-                                    if let Some(shooter_index) = self.entities.iter().position(|e| e.handle == bullet.shooter) {
-                                        // Obtenir 2 références mutables distinctes aux entités pour éviter le conflit d'emprunts
-                                        let (first, second) = if entity_index < shooter_index {
-                                            self.entities.split_at_mut(shooter_index)
-                                        } else {
-                                            self.entities.split_at_mut(entity_index)
-                                        };
-
-                                        if entity_index < shooter_index {
-                                            let entity = &mut first[entity_index];
-                                            let shooter = &mut second[0];
-                                            entity.health -= 1;
-                                            shooter.score += 1;
-                                            if entity.health <= 0 {
-                                                entity_ids_to_remove.push(entity.id);
-                                            }
-                                        } else {
-                                            let shooter = &mut first[shooter_index];
-                                            let entity = &mut second[0];
-                                            entity.health -= 1;
-                                            shooter.score += 1;
-                                            if entity.health <= 0 {
-                                                entity_ids_to_remove.push(entity.id);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            break; // On a trouvé la balle, pas besoin de continuer la boucle
-                        }
-                    }
-                }
-            }
-        }
+    /// Returns the obstacles of the arena, as the AI entities see them.
+    fn obstacle_views(&self) -> Vec<ObstacleView> {
+        self.obstacles.iter().map(|obstacle| ObstacleView {
+            position: vector![obstacle.position.0 as f32, obstacle.position.1 as f32],
+            radius: obstacle.shape.bounding_radius(),
+            shape: obstacle.shape,
+        }).collect()
+    }
 
-        // Supprimer les balles (dans l'ordre décroissant pour éviter les décalages d'indices)
-        bullet_indices_to_remove.sort_unstable_by(|a, b| b.cmp(a));
-        for &index in &bullet_indices_to_remove {
-            self.remove_bullet(index);
-        }
+    /// Finds the point of the terrain closest to a position: the edge of an obstacle or, if given, a wall of the arena.
+    ///
+    /// # Parameters
+    /// - `position`: The position, usually the center of an entity.
+    /// - `obstacles`: The obstacles searched.
+    /// - `arena`: The arena whose walls are searched too, `None` to leave them out.
+    ///
+    /// # Returns
+    /// The closest point, `None` without obstacles nor walls. The first obstacle wins a tie, then the walls.
+    pub fn closest_surface(position: Vector<f32>, obstacles: &[ObstacleView], arena: Option<&ArenaConfig>) -> Option<SurfacePoint> {
+        let edges = obstacles.iter().enumerate().map(|(index, obstacle)| {
+            let (x, y) = obstacle.shape.closest_point((obstacle.position.x, obstacle.position.y), (position.x, position.y));
+            (vector![x, y], Some(index))
+        });
+        // Les murs de l'arène bordent [0, largeur] × [0, hauteur]
+        let walls = arena.into_iter().flat_map(|arena| [
+            vector![0.0, position.y],
+            vector![arena.width, position.y],
+            vector![position.x, 0.0],
+            vector![position.x, arena.height],
+        ]).map(|point| (point, None));
+        edges.chain(walls)
+            .map(|(point, obstacle)| SurfacePoint { point, distance: (point - position).norm(), obstacle })
+            .fold(None, |closest: Option<SurfacePoint>, candidate| match closest {
+                Some(closest) if closest.distance <= candidate.distance => Some(closest),
+                _ => Some(candidate),
+            })
+    }
 
-        // Supprimer les entités mortes par ID
-        for id in entity_ids_to_remove {
-            self.remove_entity_by_id(id);
-        }
+    /// Picks the target of a bot: the closest player, or the closest other bot when no player is left.
+    ///
+    /// # Parameters
+    /// - `bot_id`: The id of the bot.
+    /// - `position`: The position of the bot.
+    /// - `entities`: The id, AI flag and position of every entity in the arena.
+    ///
+    /// # Returns
+    /// The id and the position of the target, `None` if the bot is alone.
+    pub fn bot_target(bot_id: u32, position: Vector<f32>, entities: &[(u32, bool, Vector<f32>)]) -> Option<(u32, Vector<f32>)> {
+        let closest = |ai: bool| {
+            entities.iter()
+                .filter(|(id, is_ai, _)| *id != bot_id && *is_ai == ai)
+                .map(|(id, _, other)| (*id, *other))
+                .min_by(|a, b| (a.1 - position).norm().total_cmp(&(b.1 - position).norm()))
+        };
+        closest(false).or_else(|| closest(true))
     }
 
-    /// Removes a bullet from the game.
+    /// Draws a value from the standard normal distribution, with the Box-Muller transform.
+    fn gaussian(rng: &mut StdRng) -> f32 {
+        let uniform: f32 = rng.random_range(f32::EPSILON..1.0);
+        let angle: f32 = rng.random_range(0.0..std::f32::consts::TAU);
+        (-2.0 * uniform.ln()).sqrt() * angle.cos()
+    }
+
+    /// Returns the angle the first gun of an entity points at, in radians.
     ///
     /// # Parameters
-    /// - `index`: The index of the bullet to remove.
-    fn remove_bullet(&mut self, index: usize) {
-        let bullet = self.bullets.remove(index);
-        self.physics_engine.bodies.remove(
-            bullet.handle,
-            &mut self.physics_engine.islands,
-            &mut self.physics_engine.colliders,
-            &mut self.physics_engine.impulse_joints,
-            &mut self.physics_engine.multibody_joints,
-            true,
-        );
+    /// - `entity`: The entity.
+    /// - `body`: The rigid body of the entity.
+    pub fn gun_angle(entity: &Entity, body: &RigidBody) -> f32 {
+        entity.gun().angle(body.rotation().angle())
     }
 
-    /// Removes bullets that are out of bounds.
-    fn remove_out_of_bounds_bullets(&mut self) {
-        let bounds = 1200.0;
-        let mut bullet_indices_to_remove = Vec::new();
+    /// Returns the angle a gun points at, from the rotation of its body and its traverse.
+    ///
+    /// The UI draws the barrels with it, so that they line up with the bullets fired.
+    ///
+    /// # Parameters
+    /// - `body_rotation`: The rotation of the body, in radians.
+    /// - `gun_orientation`: The applied traverse of the gun relative to the body, in turns.
+    pub fn barrel_angle(body_rotation: f32, gun_orientation: f32) -> f32 {
+        // Le canon tire vers l'arrière du corps pour un débattement nul, voir `Turret::muzzle`
+        body_rotation + gun_orientation * std::f32::consts::TAU + std::f32::consts::PI
+    }
 
-        for (index, bullet) in self.bullets.iter().enumerate() {
-            let position = self.physics_engine.bodies[bullet.handle].translation();
-            if position.x < 0.0 || position.x > bounds || position.y < 0.0 || position.y > bounds {
-                bullet_indices_to_remove.push(index);
-            }
-        }
+    /// Returns the bearing of a point seen from an origin.
+    ///
+    /// # Parameters
+    /// - `origin`: The point the bearing is measured from.
+    /// - `direction`: The angle the bearing is measured from, in radians.
+    /// - `point`: The point.
+    ///
+    /// # Returns
+    /// The angle from `direction`, counterclockwise, in radians within `[-π, π]`.
+    pub fn bearing(origin: (f32, f32), direction: f32, point: (f32, f32)) -> f32 {
+        let (dx, dy) = (point.0 - origin.0, point.1 - origin.1);
+        // Ramène l'écart dans [-π, π] pour gérer le passage de π à -π
+        (dy.atan2(dx) - direction + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI
+    }
 
-        bullet_indices_to_remove.sort_unstable_by(|a, b| b.cmp(a));
-        for &index in &bullet_indices_to_remove {
-            self.remove_bullet(index);
+    /// Lists the other entities inside a cone centered on the gun of an entity.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the looking entity.
+    /// - `half_angle`: The half angle of the cone, in radians.
+    /// - `range`: The length of the cone.
+    /// - `line_of_sight`: Whether entities hidden behind a wall or an obstacle are left out.
+    ///
+    /// # Returns
+    /// The name, distance and bearing of each entity, closest first,
+    /// or `None` if the entity does not exist.
+    pub fn field_of_view(&self, entity_id: u32, half_angle: f32, range: f32, line_of_sight: bool) -> Option<Vec<(String, f32, f32)>> {
+        let entity = self.entities.iter().find(|e| e.id == entity_id)?;
+        let body = self.physics_engine.bodies.get(entity.handle)?;
+        let origin = (entity.x, entity.y);
+        let gun_angle = GameLogic::gun_angle(entity, body);
+
+        let others: Vec<&Entity> = self.entities.iter().filter(|e| e.id != entity_id).collect();
+        let positions: Vec<(f32, f32)> = others.iter().map(|other| (other.x, other.y)).collect();
+
+        let visible = GameLogic::in_cone(origin, gun_angle, half_angle, range, &positions)
+            .into_iter()
+            .filter(|&(index, _, _)| {
+                let (x, y) = positions[index];
+                !line_of_sight || self.line_of_sight(vector![origin.0, origin.1], vector![x, y], &[])
+            })
+            .map(|(index, distance, bearing)| (others[index].name.clone(), distance, bearing))
+            .collect();
+        Some(visible)
+    }
+
+    /// Returns whether no wall or obstacle stands between two points. Entities and bullets do not block the view.
+    ///
+    /// A wall only touching `to` does not block the view: the point is seen, not what lies behind it.
+    ///
+    /// # Parameters
+    /// - `from`: The point looking.
+    /// - `to`: The point looked at.
+    /// - `ignore`: The walls and obstacles that do not block the view.
+    pub fn line_of_sight(&self, from: Vector<f32>, to: Vector<f32>, ignore: &[ColliderHandle]) -> bool {
+        let distance = (to - from).norm();
+        if distance <= AppDefines::LINE_OF_SIGHT_TOLERANCE {
+            return true;
         }
+        // La tolérance évite qu'un mur effleurant le point visé ne le cache
+        let reach = distance - AppDefines::LINE_OF_SIGHT_TOLERANCE;
+        GameLogic::wall_distance(&self.physics_engine, from, (to - from) / distance, reach, ignore).is_none()
     }
 
-    /// Removes bullets that have expired.
-    fn remove_expired_bullets(&mut self) {
-        let now = Instant::now();
+    /// Returns the distance to the first wall or obstacle along a ray. Entities, bullets and sensors are ignored.
+    ///
+    /// # Parameters
+    /// - `physics_engine`: The physics engine holding the walls and obstacles.
+    /// - `from`: The origin of the ray.
+    /// - `direction`: The direction of the ray, of length 1.
+    /// - `max_distance`: How far the ray goes.
+    /// - `ignore`: The walls and obstacles the ray goes through.
+    ///
+    /// # Returns
+    /// The distance to the wall or obstacle hit, `None` if there is none within `max_distance`.
+    fn wall_distance(physics_engine: &PhysicsEngine, from: Vector<f32>, direction: Vector<f32>, max_distance: f32, ignore: &[ColliderHandle]) -> Option<f32> {
+        let walls_only = |handle: ColliderHandle, _: &Collider| physics_engine.identify_collider(handle).is_wall() && !ignore.contains(&handle);
+        let filter = QueryFilter::default().exclude_sensors().predicate(&walls_only);
+        physics_engine.raycast(from, direction, max_distance, filter).map(|(_, distance)| distance)
+    }
+
+    /// Casts the rays of an AI entity: straight ahead of its body, and `BOT_FEELER_ANGLE` to each side.
+    ///
+    /// # Parameters
+    /// - `physics_engine`: The physics engine holding the walls and obstacles.
+    /// - `config`: The match rules giving the ray length and the avoidance radius.
+    /// - `position`: The position of the entity.
+    /// - `rotation`: The rotation of its body, in radians.
+    fn clearance(physics_engine: &PhysicsEngine, config: &GameConfig, position: Vector<f32>, rotation: f32) -> Clearance {
+        let length = config.bot_ray_length;
+        let distance = |angle: f32| {
+            let direction = vector![(rotation + angle).cos(), (rotation + angle).sin()];
+            GameLogic::wall_distance(physics_engine, position, direction, length, &[]).unwrap_or(length)
+        };
+        Clearance {
+            left: distance(AppDefines::BOT_FEELER_ANGLE),
+            ahead: distance(0.0),
+            right: distance(-AppDefines::BOT_FEELER_ANGLE),
+            ray_length: length,
+            avoidance_radius: config.bot_avoidance_radius,
+        }
+    }
+
+    /// Finds the points inside a cone.
+    ///
+    /// # Parameters
+    /// - `origin`: The apex of the cone.
+    /// - `direction`: The angle of the axis of the cone, in radians.
+    /// - `half_angle`: The half angle of the cone, in radians.
+    /// - `range`: The length of the cone.
+    /// - `points`: The points to test.
+    ///
+    /// # Returns
+    /// The index, distance and bearing of each point inside the cone, closest first. The
+    /// bearing is the angle from the axis, counterclockwise, in radians within `[-π, π]`.
+    pub fn in_cone(origin: (f32, f32), direction: f32, half_angle: f32, range: f32, points: &[(f32, f32)]) -> Vec<(usize, f32, f32)> {
+        let mut inside: Vec<(usize, f32, f32)> = points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &(x, y))| {
+                let distance = (x - origin.0).hypot(y - origin.1);
+                let bearing = GameLogic::bearing(origin, direction, (x, y));
+                (distance <= range && bearing.abs() <= half_angle).then_some((index, distance, bearing))
+            })
+            .collect();
+        inside.sort_by(|a, b| a.1.total_cmp(&b.1));
+        inside
+    }
+
+    /// Freezes the simulation: `step` and `update_ai` do nothing until `resume`.
+    ///
+    /// The timers of the game run on simulated time, so nothing ages during a pause.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes a paused simulation.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns whether the simulation is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Advances a paused simulation by exactly one step, AI included.
+    ///
+    /// # Returns
+    /// `false` if the simulation is not paused.
+    pub fn step_once(&mut self) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        self.resume();
+        self.update_ai();
+        self.step();
+        self.pause();
+        true
+    }
+
+    /// Returns the simulated time since the game started: the number of steps times
+    /// the step duration. Bullets, fire cooldowns and horde timers run on it, so the
+    /// game plays the same at any `time_scale`.
+    pub fn sim_time(&self) -> Duration {
+        GameLogic::time_at(self.tick, self.physics_engine.integration_parameters.dt)
+    }
+
+    /// Returns the simulated time at a simulation step.
+    ///
+    /// # Parameters
+    /// - `tick`: The simulation step.
+    /// - `dt`: The duration of a step, in seconds.
+    fn time_at(tick: u64, dt: f32) -> Duration {
+        Duration::from_secs_f64(tick as f64 * dt as f64)
+    }
+
+    /// Advances the simulation by one step, unless it is paused.
+    ///
+    /// Between two rounds the arena stays frozen and only the intermission runs.
+    pub fn step(&mut self) {
+        if self.is_paused() {
+            return;
+        }
+        let started = Instant::now();
+
+        // Les caméras n'ont pas de corps, leur position s'intègre directement
+        let dt = self.physics_engine.integration_parameters.dt;
+        for camera in &mut self.ghost_cameras {
+            camera.advance(dt, &self.arena);
+        }
+
+        // A new step opens a new command budget for every entity
+        for entity in &mut self.entities {
+            entity.commands_this_step = 0;
+        }
+
+        if self.match_state.phase == MatchPhase::Running {
+            self.step_arena();
+        } else {
+            self.tick += 1;
+        }
+        self.sync_entities();
+        let tick = self.tick;
+        self.trails.record(tick, self.entities.iter().map(|entity| (entity.id, (entity.x, entity.y), [entity.color.r(), entity.color.g(), entity.color.b()])));
+        self.update_match();
+        self.record_frame();
+        self.last_step_duration = started.elapsed();
+    }
+
+    /// Copies the position, rotation and velocity of each body into its entity, so that
+    /// the UI, the clients and the bots read them without looking up the physics engine.
+    fn sync_entities(&mut self) {
+        for entity in &mut self.entities {
+            let Some(body) = self.physics_engine.bodies.get(entity.handle) else { continue };
+            entity.x = body.translation().x;
+            entity.y = body.translation().y;
+            entity.self_orientation = body.rotation().angle() as f64;
+            entity.vx = body.linvel().x;
+            entity.vy = body.linvel().y;
+        }
+    }
+
+    /// Returns where the render snapshots are published, kept by the UI to draw without locking the game logic.
+    pub fn render_slot(&self) -> RenderSlot {
+        self.render_slot.clone()
+    }
+
+    /// Publishes what the arena looks like after a step, for the UI to draw.
+    ///
+    /// The entities and bullets are paired with their position in the previous snapshot,
+    /// except the entities teleported since then, which the UI must not slide across the arena.
+    ///
+    /// # Parameters
+    /// - `stepped_at`: When the step ran.
+    /// - `interval`: The real time until the next step.
+    pub fn publish_render(&mut self, stepped_at: Instant, interval: Duration) {
+        let previous = self.render_slot.latest();
+        let frame = self.replay_frame();
+        let previous_entities = frame.entities.iter().map(|entity| {
+            if self.teleported.contains(&entity.id) {
+                return None;
+            }
+            previous.frame.entities.iter()
+                .find(|before| before.id == entity.id)
+                .map(|before| (before.x, before.y, before.rotation))
+        }).collect();
+        let bullet_handles: Vec<RigidBodyHandle> = self.bullets.iter().map(|bullet| bullet.handle).collect();
+        let previous_bullets = bullet_handles.iter().map(|handle| {
+            previous.bullet_handles.iter()
+                .position(|before| before == handle)
+                .map(|index| previous.frame.bullets[index])
+        }).collect();
+        self.teleported.clear();
+        if self.published_trails.revision() != self.trails.revision() {
+            self.published_trails = Arc::new(self.trails.clone());
+        }
+        let events = self.published_events();
+        self.render_slot.publish(RenderSnapshot {
+            frame,
+            previous_entities,
+            previous_bullets,
+            bullet_handles,
+            ai_intents: if self.ai_debug_enabled { self.ai_intents() } else { Vec::new() },
+            ghost_cameras: self.ghost_cameras.clone(),
+            trails: Arc::clone(&self.published_trails),
+            arena: self.arena,
+            max_health: self.game_config.starting_health,
+            bullet_range: self.game_config.bullet_speed * self.game_config.bullet_lifetime_ms as f32 / 1000.0,
+            stepped_at,
+            interval,
+            sim_time: self.sim_time(),
+            power_ups: self.power_ups.iter().map(|power_up| PowerUpFrame { kind: power_up.kind, position: power_up.position }).collect(),
+            mines: self.mines.iter().map(|mine| MineFrame {
+                position: mine.position,
+                color: self.entities.iter().find(|entity| entity.id == mine.owner).map(|owner| [owner.color.r(), owner.color.g(), owner.color.b()]),
+                armed: mine.is_armed(self.sim_time()),
+            }).collect(),
+            events,
+            inspected: self.inspected.and_then(|id| self.inspected_entity(id)),
+            controls: self.game_controls(),
+        });
+    }
+
+    /// Returns the live state of an entity for the inspector of the game UI, `None` if it left the game.
+    fn inspected_entity(&self, id: u32) -> Option<InspectedEntity> {
+        let entity = self.entities.iter().find(|entity| entity.id == id)?;
+        Some(InspectedEntity {
+            id,
+            name: entity.name.clone(),
+            color: [entity.color.r(), entity.color.g(), entity.color.b()],
+            team: entity.team,
+            health: entity.health,
+            armor: entity.armor,
+            score: entity.score,
+            breakdown: entity.breakdown,
+            x: entity.x,
+            y: entity.y,
+            vx: entity.vx,
+            vy: entity.vy,
+            orientation: entity.self_orientation as f32,
+            gun_angle: entity.gun().angle(entity.self_orientation as f32),
+            motor_left: entity.motor_left,
+            motor_right: entity.motor_right,
+            trigger: entity.gun().trigger,
+            traverse: entity.gun().traverse,
+            shield: entity.shield,
+            energy: entity.energy,
+            fire_energy: entity.fire_energy,
+            fire_cooldown: entity.fire_cooldown_remaining(self.sim_time()),
+            ai_driver: self.ai_driver_of(id).map(|(behavior, difficulty)| (behavior.to_string(), difficulty)),
+        })
+    }
+
+    /// Returns what the menus of the game UI show, see `GameControls`.
+    fn game_controls(&self) -> GameControls {
+        GameControls {
+            paused: self.is_paused(),
+            recording: self.is_recording(),
+            time_scale: self.time_scale,
+            map_seed: self.map_seed(),
+            map_name: self.map_name().map(str::to_string),
+            score_rules: self.score_rules.clone(),
+            game_config: self.game_config.clone(),
+            profiling_enabled: self.profiling_enabled,
+            step_timings: StepPhase::ALL.iter()
+                .map(|&phase| (phase, self.step_timings.average(phase), self.step_timings.worst(phase)))
+                .collect(),
+            default_behavior: self.default_behavior().to_string(),
+            behaviors: self.behavior_registry.names().into_iter().map(str::to_string).collect(),
+            horde_active: self.horde_active(),
+            horde_status: self.horde_status(),
+            achieved_tick_rate: self.achieved_tick_rate,
+            round: self.match_state.round,
+            phase: self.match_state.phase,
+            last_result: self.match_state.last_result.clone(),
+            round_remaining: self.match_state.remaining(self.sim_time(), &self.game_config),
+        }
+    }
+
+    /// Returns where the world snapshots are published, kept by the server to answer the queries without locking the game logic.
+    pub fn world_slot(&self) -> WorldSlot {
+        self.world_slot.clone()
+    }
+
+    /// Publishes what the clients can query of the game as it is now, see `WorldSnapshot`.
+    ///
+    /// The game loop publishes after each step; adding or removing an entity publishes too,
+    /// so that a client never finds its new entity missing or its removed entity alive.
+    ///
+    /// The id of a bullet is made of the index of its rigid body and of its generation plus the step
+    /// the bullet was fired at, so it stays the same while the bullet flies and is not reused right away,
+    /// even by a bullet taking the body back from the pool.
+    pub fn publish_world(&mut self) {
+        let now = self.sim_time();
+        let entities = self.entities.iter().map(|entity| EntityStatus {
+            id: entity.id,
+            name: entity.name.clone(),
+            x: entity.x,
+            y: entity.y,
+            orientation: entity.self_orientation,
+            health: entity.health,
+            score: entity.score,
+            fire_cooldown: entity.fire_cooldown_remaining(now),
+            spread: self.physics_engine.bodies.get(entity.handle)
+                .map_or(0.0, |body| GameLogic::spread(&self.game_config, body)),
+            breakdown: entity.breakdown,
+            bullets_left: self.bullets_left(entity),
+            fire_energy: entity.fire_energy,
+            last_hit_bearing: entity.recent_hits.back().map(|hit| hit.bearing),
+            actuator_seq: entity.pending_actuators.applied(),
+            armor: entity.armor,
+        }).collect();
+        let bullets = self.bullets.iter().map(|bullet| {
+            let (index, generation) = bullet.handle.into_raw_parts();
+            let position = self.physics_engine.bodies[bullet.handle].translation();
+            let id = (generation as u64 + bullet.fired_at) << 32 | index as u64;
+            BulletStatus { id, x: position.x, y: position.y }
+        }).collect();
+        let control_points = match self.game_config.game_mode {
+            GameMode::ControlPoint => self.control_points.iter().map(|point| ControlPointStatus {
+                name: point.name.clone(),
+                x: point.position.0,
+                y: point.position.1,
+                radius: point.radius,
+                owner: point.owner,
+                progress: point.progress,
+            }).collect(),
+            GameMode::Deathmatch => Vec::new(),
+        };
+        let events = self.published_events();
+        self.world_slot.publish(WorldSnapshot {
+            tick: self.tick,
+            entities,
+            bullets,
+            control_points,
+            ghost_cameras: self.ghost_cameras.clone(),
+            events,
+            reset_count: self.reset_count,
+            last_step: self.last_step_duration,
+            respawns_granted: self.respawns_granted,
+        });
+    }
+
+    /// Returns the events for the snapshots, copied only if an event came or the queue was cleared since the last copy.
+    fn published_events(&mut self) -> Arc<EventQueue> {
+        if self.published_events.cursor() != self.events.cursor() || self.published_events.len() != self.events.len() {
+            self.published_events = Arc::new(self.events.clone());
+        }
+        Arc::clone(&self.published_events)
+    }
+
+    /// Returns what each AI entity is heading for and shooting at, for the debug view of the game UI.
+    fn ai_intents(&self) -> Vec<AiIntent> {
+        self.entities.iter()
+            .filter(|entity| entity.is_ai)
+            .map(|entity| AiIntent {
+                id: entity.id,
+                destination: (entity.target_x, entity.target_y),
+                aim: self.ai_drivers.get(&entity.id).and_then(|driver| driver.aim),
+            })
+            .collect()
+    }
+
+    /// Starts recording a replay of the match, one frame per step.
+    ///
+    /// A recording already running is discarded.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::new(self.events.cursor(), &self.arena, self.physics_engine.tick_rate()));
+    }
+
+    /// Stops recording.
+    ///
+    /// # Returns
+    /// The recording, to be saved with `Recorder::save`, or `None` if none was running.
+    pub fn stop_recording(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    }
+
+    /// Writes what is kept in memory before the server stops: the events the observers
+    /// did not write yet, the leaderboard, and the replay being recorded, to `DEFAULT_REPLAY_FILE`.
+    pub fn shut_down(&mut self) {
+        for observer in &mut self.observers {
+            observer.on_shutdown();
+        }
+        if let Err(e) = self.leaderboard.save() {
+            self.report(format!("[ERROR] Failed to save the leaderboard: {}", e), MessageType::Error);
+        }
+        if let Some(recorder) = self.stop_recording() {
+            match recorder.save(Path::new(AppDefines::DEFAULT_REPLAY_FILE)) {
+                Ok(()) => self.report(format!("[STOP] Replay saved to {}.", AppDefines::DEFAULT_REPLAY_FILE), MessageType::Default),
+                Err(e) => self.report(format!("[ERROR] Failed to save the replay: {}", e), MessageType::Error),
+            }
+        }
+    }
+
+    /// Returns whether a replay is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Captures what the arena looks like, for the UI and the replays.
+    ///
+    /// # Returns
+    /// The entities, bullets and obstacles of the arena, without events.
+    pub fn replay_frame(&self) -> ReplayFrame {
+        let now = self.sim_time();
+        let entities = self.entities.iter().map(|entity| {
+            EntityFrame {
+                id: entity.id,
+                name: entity.name.clone(),
+                color: [entity.color.r(), entity.color.g(), entity.color.b()],
+                x: entity.x,
+                y: entity.y,
+                rotation: entity.self_orientation as f32,
+                gun_orientation: entity.gun().orientation as f32,
+                turrets: if entity.turrets.len() > 1 || entity.gun().mount != (0.0, 0.0) {
+                    entity.turrets.iter()
+                        .map(|turret| TurretFrame { mount: turret.mount, orientation: turret.orientation as f32 })
+                        .collect()
+                } else {
+                    Vec::new()
+                },
+                health: entity.health,
+                score: entity.score,
+                breakdown: entity.breakdown,
+                team: entity.team,
+                shield: entity.shield_active(),
+                protected: entity.is_spawn_protected(now),
+                is_ai: entity.is_ai,
+                fire_energy: (self.game_config.shot_energy_cost > 0.0)
+                    .then(|| entity.fire_energy / self.game_config.fire_energy_max.max(f32::EPSILON)),
+            }
+        }).collect();
+        let bullets = self.bullets.iter().map(|bullet| {
+            let position = self.physics_engine.bodies[bullet.handle].translation();
+            (position.x, position.y)
+        }).collect();
+        // Les parties sans armes spéciales gardent des images aussi légères qu'avant
+        let bullet_kinds = if self.bullets.iter().all(|bullet| bullet.kind == BulletKind::Standard) {
+            Vec::new()
+        } else {
+            self.bullets.iter().map(|bullet| bullet.kind).collect()
+        };
+        ReplayFrame {
+            tick: self.tick,
+            entities,
+            bullets,
+            bullet_kinds,
+            obstacles: Some(self.obstacles.iter().map(|obstacle| ObstacleFrame {
+                x: obstacle.position.0,
+                y: obstacle.position.1,
+                shape: obstacle.shape,
+            }).collect()),
+            zone: self.zone().map(|zone| (zone.center.0, zone.center.1, zone.radius)),
+            control_points: self.control_point_frames(),
+            hazards: self.hazards.iter().map(Hazard::to_map).collect(),
+            events: Vec::new(),
+            trails: None,
+        }
+    }
+
+    /// Adds the current frame and the events since the previous one to the recording, if any.
+    fn record_frame(&mut self) {
+        let Some((event_cursor, trail_clears)) = self.recorder.as_ref().map(|recorder| (recorder.event_cursor, recorder.trail_clears)) else { return };
+        let mut frame = self.replay_frame();
+        frame.events = self.events.since(event_cursor).map(|queued| queued.event.clone()).collect();
+        // Les traînées sont entières dans la première image et après chaque effacement, les suivantes les refont
+        if trail_clears != Some(self.trails.clear_count()) {
+            frame.trails = Some(self.trails.clone());
+        }
+        let cursor = self.events.cursor();
+        let clears = self.trails.clear_count();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.event_cursor = cursor;
+            recorder.trail_clears = Some(clears);
+            recorder.record(frame);
+        }
+    }
+
+    /// Advances the arena by one step: actuators, physics, collisions and timed rules.
+    fn step_arena(&mut self) {
+        let tick = self.tick;
+        let started = self.phase_started();
+        let physics = &mut self.physics_engine;
+        let entities = &mut self.entities;
+        let bullets = &mut self.bullets;
+        let slows = GameLogic::hazard_slows(&self.hazards, entities);
+
+        let fired = GameLogic::apply_actuators(entities, physics, bullets, &slows, &self.game_config, &mut self.rng, tick);
+        for event in fired {
+            self.notify(event);
+        }
+        self.phase_ended(StepPhase::Actuators, started);
+
+        // Le seuil et la traversée des coéquipiers peuvent changer en cours de partie depuis les menus
+        let threshold = self.game_config.contact_force_threshold;
+        for entity in &self.entities {
+            for &collider in self.physics_engine.bodies[entity.handle].colliders() {
+                self.physics_engine.colliders[collider].set_contact_force_event_threshold(threshold);
+            }
+            entity.update_collision_groups(&mut self.physics_engine, &self.game_config);
+        }
+        // Les vitesses avant le choc, le solveur les annule ou renvoie les balles
+        let velocities: HashMap<RigidBodyHandle, Vector<f32>> = self.entities
+            .iter()
+            .map(|e| e.handle)
+            .chain(self.bullets.iter().map(|bullet| bullet.handle))
+            .map(|handle| (handle, *self.physics_engine.bodies[handle].linvel()))
+            .collect();
+        let started = self.phase_started();
+        self.physics_engine.step(&ShooterFilter { tick: self.tick });
+        for bullet in &mut self.bullets {
+            bullet.track(&self.physics_engine);
+        }
+        self.phase_ended(StepPhase::Physics, started);
+        let started = self.phase_started();
+        self.handle_collisions(&velocities);
+        self.handle_ramming(&velocities);
+        self.phase_ended(StepPhase::Collisions, started);
+        self.contain_entities();
+        self.apply_zone_damage();
+        self.apply_hazard_damage();
+        self.apply_camping_penalty();
+        self.update_control_points();
+        let started = self.phase_started();
+        self.remove_out_of_bounds_bullets();
+        self.remove_expired_bullets();
+        self.phase_ended(StepPhase::Culling, started);
+        self.update_waves();
+        self.tick += 1;
+        self.decay_scores();
+        self.spawn_power_ups();
+        let tick = self.tick;
+        for entity in &mut self.entities {
+            entity.expire_effects(tick);
+        }
+    }
+
+    /// Starts timing a phase of the step.
+    ///
+    /// # Returns
+    /// When the phase started, `None` when profiling is off so the step does not even read the clock.
+    fn phase_started(&self) -> Option<Instant> {
+        self.profiling_enabled.then(Instant::now)
+    }
+
+    /// Records how long a phase took, see `phase_started`.
+    ///
+    /// # Parameters
+    /// - `phase`: The phase that just ended.
+    /// - `started`: When it started, `None` when profiling is off.
+    fn phase_ended(&mut self, phase: StepPhase, started: Option<Instant>) {
+        if let Some(started) = started {
+            self.step_timings.record(phase, started.elapsed());
+        }
+    }
+
+    /// Ends the round when a win condition is met, and starts the next one after the intermission.
+    fn update_match(&mut self) {
+        let now = self.sim_time();
+        let scores: Vec<i32> = self.entities.iter().map(|e| e.score).collect();
+        if let Some(reason) = self.match_state.end_reason(now, &self.game_config, &scores) {
+            self.end_round(reason);
+        } else if self.match_state.intermission_over(now, &self.game_config) {
+            self.start_next_round();
+        }
+    }
+
+    /// Ends the running round: the entities with the highest score win it, several on a tie.
+    ///
+    /// # Parameters
+    /// - `reason`: Why the round ended.
+    fn end_round(&mut self, reason: &str) {
+        let score = self.entities.iter().map(|e| e.score).max();
+        let winners: Vec<String> = self.entities.iter()
+            .filter(|e| Some(e.score) == score)
+            .map(|e| e.name.clone())
+            .collect();
+        self.close_round(reason, winners, score);
+    }
+
+    /// Ends the running round with given winners, then counts it in the leaderboard.
+    ///
+    /// # Parameters
+    /// - `reason`: Why the round ended.
+    /// - `winners`: The names of the entities winning the round, empty for no winner.
+    /// - `score`: The score shown with the winners, `None` for no winner.
+    fn close_round(&mut self, reason: &str, winners: Vec<String>, score: Option<i32>) {
+        let round = self.match_state.round;
+        let result = MatchState::result_text(round, reason, &winners, score);
+        self.report_round(reason, &winners);
+        self.notify(GameEvent::MatchEnd { round, reason: reason.to_string(), winners, score });
+        self.match_state.end_round(self.sim_time(), result);
+
+        for entity in &mut self.entities {
+            self.leaderboard.record_breakdown(&entity.name, &std::mem::take(&mut entity.breakdown));
+        }
+        let scores: Vec<(String, i32)> = self.entities.iter().map(|e| (e.name.clone(), e.score)).collect();
+        self.leaderboard.record_match(&scores);
+        if let Err(e) = self.leaderboard.save() {
+            self.report(format!("[ERROR] Failed to save the leaderboard: {}", e), MessageType::Error);
+        }
+    }
+
+    /// Builds the report of the round ending, before the breakdowns are counted in the leaderboard,
+    /// and writes it to `report_dir`.
+    ///
+    /// # Parameters
+    /// - `reason`: Why the round ended.
+    /// - `winners`: The names of the entities with the highest score.
+    fn report_round(&mut self, reason: &str, winners: &[String]) {
+        let summary = RoundSummary {
+            round: self.match_state.round,
+            reason: reason.to_string(),
+            winners: winners.to_vec(),
+            seed: self.map_seed,
+            map: self.map_name.clone(),
+            start_tick: self.round_log.start_tick,
+            end_tick: self.tick,
+            step: GameLogic::time_at(1, self.physics_engine.integration_parameters.dt),
+            ended_at: SystemTime::now(),
+        };
+        let mut players = std::mem::take(&mut self.round_log.departed);
+        players.extend(self.entities.iter().map(PlayerRecord::of));
+        let events = std::mem::take(&mut self.round_log.events);
+        let report = MatchReport::build(&summary, &players, events.iter().map(|(tick, event)| (*tick, event)));
+        if let Some(dir) = &self.report_dir {
+            if let Err(e) = report.write(dir) {
+                self.report(format!("[ERROR] Failed to write the report of round {}: {}", summary.round, e), MessageType::Error);
+            }
+        }
+        self.last_report = Some(report);
+    }
+
+    /// Returns the report of the last round that ended, `None` before the end of the first round.
+    pub fn last_report(&self) -> Option<&MatchReport> {
+        self.last_report.as_ref()
+    }
+
+    /// Starts the next round on a new map, drawn from the random number generator of the game.
+    /// A map loaded from a file is kept for every round.
+    fn start_next_round(&mut self) {
+        self.reset_arena();
+        if self.map_name.is_none() {
+            let seed = self.rng.random();
+            self.generate_map(Some(seed));
+        }
+        let now = self.sim_time();
+        if let Some(mut director) = self.wave_director.take() {
+            // Une horde perdue repart de la première vague, sans les robots de la précédente
+            if director.state == WaveState::Lost {
+                for &id in director.spawned_ids() {
+                    self.remove_entity_by_id(id);
+                }
+                director = WaveDirector::new(now);
+            } else {
+                director.resume(now);
+            }
+            self.wave_director = Some(director);
+        }
+        self.match_state.start_round(now);
+        self.notify(GameEvent::RoundStart { round: self.match_state.round });
+    }
+
+    /// Converts a simulated duration into a number of steps.
+    fn seconds_to_ticks(&self, seconds: f32) -> u64 {
+        GameLogic::ticks_in(seconds, self.physics_engine.integration_parameters.dt)
+    }
+
+    /// Returns the number of steps of a simulated duration, rounded to the nearest step.
+    ///
+    /// # Parameters
+    /// - `seconds`: The duration, in seconds.
+    /// - `dt`: The duration of a step, in seconds.
+    fn ticks_in(seconds: f32, dt: f32) -> u64 {
+        (seconds / dt).round() as u64
+    }
+
+    /// Spawns a power-up at a random free location every `POWER_UP_SPAWN_INTERVAL_SECS`,
+    /// while fewer than `POWER_UP_MAX` are waiting. Armor power-ups are only drawn while `armor_power_ups` is on.
+    fn spawn_power_ups(&mut self) {
+        if self.tick - self.last_power_up_tick < self.seconds_to_ticks(AppDefines::POWER_UP_SPAWN_INTERVAL_SECS) {
+            return;
+        }
+        self.last_power_up_tick = self.tick;
+        if self.power_ups.len() >= AppDefines::POWER_UP_MAX {
+            return;
+        }
+
+        let margin = AppDefines::POWER_UP_RADIUS * 2.0;
+        let clearance = Ball::new(margin);
+        // Quelques essais suffisent, l'arène est largement vide
+        for _ in 0..10 {
+            let (x, y) = self.arena.random_position(&mut self.rng, margin);
+            let occupied = self.physics_engine.query_pipeline.intersection_with_shape(
+                &self.physics_engine.bodies,
+                &self.physics_engine.colliders,
+                &Isometry::translation(x, y),
+                &clearance,
+                QueryFilter::default(),
+            );
+            if occupied.is_none() {
+                // L'armure vient en dernier : sans elle, les tirages d'une graine ne changent pas
+                let kinds = if self.game_config.armor_power_ups { PowerUpKind::ALL.len() } else { PowerUpKind::ALL.len() - 1 };
+                let kind = PowerUpKind::ALL[self.rng.random_range(0..kinds)];
+                self.power_ups.push(PowerUp::new(kind, (x as f64, y as f64), &mut self.physics_engine));
+                return;
+            }
+        }
+    }
+
+    /// Removes a power-up from the game.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the power-up to remove.
+    fn remove_power_up(&mut self, index: usize) {
+        let power_up = self.power_ups.remove(index);
+        self.physics_engine.remove_sensor(power_up.collider_handle);
+    }
+
+    /// Lays a mine where an entity stands.
+    ///
+    /// # Parameters
+    /// - `entity_id`: The id of the entity laying the mine.
+    ///
+    /// # Returns
+    /// An error if the entity does not exist or already has the maximum number of mines.
+    pub fn deploy_mine(&mut self, entity_id: u32) -> Result<(), DeployError> {
+        let entity = self.entities.iter().find(|e| e.id == entity_id).ok_or(DeployError::NoEntity)?;
+        let laid = self.mines.iter().filter(|mine| mine.owner == entity_id).count();
+        if laid >= self.game_config.max_mines as usize {
+            return Err(DeployError::Limit);
+        }
+        let position = *self.physics_engine.bodies[entity.handle].translation();
+        let now = self.sim_time();
+        self.mines.push(Mine::new(entity_id, (position.x as f64, position.y as f64), &mut self.physics_engine, now));
+        Ok(())
+    }
+
+    /// Removes a mine from the game.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the mine to remove.
+    fn remove_mine(&mut self, index: usize) -> Mine {
+        let mine = self.mines.remove(index);
+        self.physics_engine.remove_sensor(mine.collider_handle);
+        mine
+    }
+
+    /// Makes a mine explode: every entity within the blast radius loses health,
+    /// from the full damage at the center down to nothing at the edge of the blast.
+    ///
+    /// The blast counts as a hit from the owner of the mine, with the same rules as a bullet hit.
+    /// It also hurts the owner if it stands in the blast.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the mine.
+    fn detonate_mine(&mut self, index: usize) {
+        let mine = self.remove_mine(index);
+        let (x, y) = (mine.position.0 as f32, mine.position.1 as f32);
+        self.notify(GameEvent::Detonation { owner: mine.owner, x, y });
+        let Some(owner_index) = self.entities.iter().position(|e| e.id == mine.owner) else { return };
+
+        let now = self.sim_time();
+        let config = self.game_config.clone();
+        let mut entity_ids_to_remove = Vec::new();
+        let mut events = Vec::new();
+        for entity_index in 0..self.entities.len() {
+            let position = *self.physics_engine.bodies[self.entities[entity_index].handle].translation();
+            let distance = ((position.x - x).powi(2) + (position.y - y).powi(2)).sqrt();
+            if distance >= config.mine_blast_radius {
+                continue;
+            }
+            let damage = (config.mine_damage as f32 * (1.0 - distance / config.mine_blast_radius)).ceil() as i32;
+            if damage <= 0 {
+                continue;
+            }
+
+            let outcome = self.hit_entity(entity_index, owner_index, damage, now);
+            let (shooter, victim, x, y) = (mine.owner, self.entities[entity_index].id, position.x, position.y);
+            if let Some(penalty) = self.self_hit_penalty(shooter, victim, &outcome) {
+                events.push(penalty);
+            }
+            match outcome {
+                HitOutcome::Ignored => {}
+                HitOutcome::Damaged => {
+                    events.push(GameEvent::Hit { shooter, victim, damage, x, y, bearing: None });
+                }
+                HitOutcome::Destroyed { comeback_bonus } => {
+                    events.push(GameEvent::Hit { shooter, victim, damage, x, y, bearing: None });
+                    events.push(GameEvent::Kill { shooter, victim, x, y });
+                    events.extend(GameLogic::comeback_event(shooter, comeback_bonus));
+                    entity_ids_to_remove.push(victim);
+                }
+            }
+        }
+
+        for event in events {
+            self.notify(event);
+        }
+        for id in entity_ids_to_remove {
+            self.remove_entity_by_id(id);
+        }
+    }
+
+    /// Applies the score decay once every simulated minute, if enabled.
+    fn decay_scores(&mut self) {
+        let ticks_per_minute = self.seconds_to_ticks(60.0);
+        if self.tick - self.last_decay_tick < ticks_per_minute {
+            return;
+        }
+        self.last_decay_tick = self.tick;
+
+        let mut events = Vec::new();
+        for entity in &mut self.entities {
+            let decay = self.score_rules.decay(entity.score);
+            if decay > 0 {
+                entity.score -= decay;
+                events.push(GameEvent::Penalty { id: entity.id, points: decay, reason: PenaltyReason::Decay });
+            }
+        }
+        for event in events {
+            self.notify(event);
+        }
+    }
+
+    /// Starts the cooperative horde mode: players fight escalating waves of AI bots.
+    ///
+    /// The players form the team `HORDE_PLAYER_TEAM` against the bots of `HORDE_BOT_TEAM`. A cleared
+    /// wave wins the round for the players still alive, and the clients of the players destroyed during
+    /// a wave get a new entity once it is over, see `respawns_granted`. When every player is dead, the
+    /// round is lost and the next one starts the horde over from the first wave.
+    pub fn start_horde(&mut self) {
+        self.wave_director = Some(WaveDirector::new(self.sim_time()));
+        self.notify_rule_change("horde", "on");
+    }
+
+    /// Stops the horde mode. Bots already spawned stay in the game, and everybody leaves the horde teams.
+    pub fn stop_horde(&mut self) {
+        self.wave_director = None;
+        let members: Vec<u32> = self.entities.iter()
+            .filter(|e| matches!(e.team, Some(AppDefines::HORDE_PLAYER_TEAM | AppDefines::HORDE_BOT_TEAM)))
+            .map(|e| e.id)
+            .collect();
+        for id in members {
+            self.set_team(id, None);
+        }
+        self.notify_rule_change("horde", "off");
+    }
+
+    /// Returns whether the cooperative horde mode is running.
+    pub fn horde_active(&self) -> bool {
+        self.wave_director.is_some()
+    }
+
+    /// Returns the number of horde waves that ended, each letting the players destroyed before it back in.
+    pub fn respawns_granted(&self) -> u64 {
+        self.respawns_granted
+    }
+
+    /// Spawns the queued horde bots, advances the wave state and ends the round when the wave is over.
+    fn update_waves(&mut self) {
+        let Some(mut director) = self.wave_director.take() else { return };
+        let now = self.sim_time();
+        let mut events = Vec::new();
+
+        if director.intermission_over(now) {
+            director.start_next_wave();
+            events.push(director.event());
+        }
+
+        if let Some(name) = director.next_spawn(now) {
+            let id = self.add_ai(name, None, AiDifficulty::Normal);
+            self.set_team(id, Some(AppDefines::HORDE_BOT_TEAM));
+            director.register_spawn(id);
+        }
+        // Les joueurs arrivés depuis la dernière vague rejoignent l'équipe
+        let recruits: Vec<u32> = self.entities.iter()
+            .filter(|e| !e.is_ai && e.team != Some(AppDefines::HORDE_PLAYER_TEAM))
+            .map(|e| e.id)
+            .collect();
+        for id in recruits {
+            self.set_team(id, Some(AppDefines::HORDE_PLAYER_TEAM));
+        }
+
+        let alive_ids: Vec<u32> = self.entities.iter().map(|e| e.id).collect();
+        let players_alive = self.entities.iter().filter(|e| !e.is_ai).count();
+        let state = director.state;
+        director.update(&alive_ids, players_alive, now);
+        let outcome = (director.state != state).then_some((director.wave, director.state));
+        if outcome.is_some() {
+            events.push(director.event());
+        }
+
+        self.wave_director = Some(director);
+        for event in events {
+            self.notify(event);
+        }
+        if outcome.is_some() {
+            self.respawns_granted += 1;
+        }
+        match outcome {
+            Some((wave, WaveState::Intermission)) => {
+                let survivors: Vec<String> = self.entities.iter_mut()
+                    .filter(|e| !e.is_ai)
+                    .map(|e| {
+                        e.breakdown.waves_survived += 1;
+                        e.name.clone()
+                    })
+                    .collect();
+                let score = self.entities.iter().filter(|e| !e.is_ai).map(|e| e.score).max();
+                self.close_round(&format!("wave {} cleared", wave), survivors, score);
+            }
+            Some((wave, WaveState::Lost)) => {
+                self.close_round(&format!("all players died on wave {}", wave), Vec::new(), None);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns a short description of the horde state for display.
+    pub fn horde_status(&self) -> Option<String> {
+        let director = self.wave_director.as_ref()?;
+        let alive_ids: Vec<u32> = self.entities.iter().map(|e| e.id).collect();
+        let status = match director.state {
+            WaveState::InProgress => format!("Wave {} - {} bots left", director.wave, director.remaining(&alive_ids)),
+            WaveState::Intermission => format!("Waves survived: {}", director.waves_survived),
+            WaveState::Lost => format!("Lost - waves survived: {}", director.waves_survived),
+        };
+        Some(format!("{} | {}", director.announcement, status))
+    }
+
+    /// Handles the collisions of the bullets, and the entities entering a power-up or a mine.
+    ///
+    /// An entity damaged by a bullet remembers where the bullet came from, see `Entity::record_hit`:
+    /// the direction it flew in before the step, so a bullet that bounced gives its last leg.
+    /// The armor of the entity hit shrugs off its share of the damage, see `Entity::mitigate`.
+    ///
+    /// # Parameters
+    /// - `velocities`: The velocity of each bullet body before the last step.
+    fn handle_collisions(&mut self, velocities: &HashMap<RigidBodyHandle, Vector<f32>>) {
+        let mut bullet_indices_to_remove = Vec::new();
+        let mut entity_ids_to_remove = Vec::new();
+        let mut events = Vec::new();
+        let mut pickups: Vec<(usize, usize)> = Vec::new();
+        let mut detonations: Vec<ColliderHandle> = Vec::new();
+        let now = self.sim_time();
+        let bounce_ticks = self.seconds_to_ticks(AppDefines::BULLET_BOUNCE_LIFETIME_MS as f32 / 1000.0);
+        let self_hit_grace = self.seconds_to_ticks(self.game_config.self_hit_grace_ms as f32 / 1000.0);
+        // Les indices restent valides jusqu'aux suppressions, faites après la boucle
+        let bodies = self.body_kinds();
+        let intersections: Vec<CollisionEvent> = self.physics_engine.sensor_events.drain(..).collect();
+        for event in intersections {
+            // Les points de contrôle et les dangers suivent les entrées et les sorties, les autres capteurs les seules entrées
+            if self.sense_control_point(&event) || self.sense_hazard(&event) {
+                continue;
+            }
+            if let CollisionEvent::Started(collider1, collider2, _) = event {
+                let identity1 = self.physics_engine.identify_collider(collider1);
+                let identity2 = self.physics_engine.identify_collider(collider2);
+                let (sensor, sensor_identity, other) = match identity1 {
+                    BodyIdentity::PowerUp | BodyIdentity::Mine(_) => (collider1, identity1, identity2),
+                    _ => (collider2, identity2, identity1),
+                };
+                let BodyIdentity::Entity(entity_id) = other else { continue };
+                match sensor_identity {
+                    // Une entité qui traverse un bonus le ramasse, les balles le traversent
+                    BodyIdentity::PowerUp => {
+                        let power_up_index = self.power_ups.iter().position(|p| p.collider_handle == sensor);
+                        let entity_index = self.entities.iter().position(|e| e.id == entity_id);
+                        if let (Some(power_up_index), Some(entity_index)) = (power_up_index, entity_index) {
+                            pickups.push((power_up_index, entity_index));
+                        }
+                    }
+                    // Une mine armée explose quand une autre entité y entre, les balles la traversent
+                    BodyIdentity::Mine(owner) if entity_id != owner && self.mines.iter().any(|m| m.collider_handle == sensor && m.is_armed(now)) => {
+                        detonations.push(sensor);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let collisions: Vec<CollisionEvent> = self.physics_engine.collision_events.drain(..).collect();
+        for event in collisions {
+            if let CollisionEvent::Started(collider1, collider2, _) = event {
+                let body1 = self.physics_engine.colliders[collider1].parent();
+                let body2 = self.physics_engine.colliders[collider2].parent();
+                let kind1 = body1.and_then(|body| bodies.get(&body).copied());
+                let kind2 = body2.and_then(|body| bodies.get(&body).copied());
+                let identity1 = self.physics_engine.identify_collider(collider1);
+                let identity2 = self.physics_engine.identify_collider(collider2);
+
+                // Une balle qui touche un mur ou un obstacle rebondit s'il lui reste des rebonds,
+                // sinon elle disparaît sans rapporter de points, y compris si elle apparaît à l'intérieur
+                let wall_hit = match (kind1, kind2) {
+                    (Some(kind), None) if identity2.is_wall() => kind.bullet(),
+                    (None, Some(kind)) if identity1.is_wall() => kind.bullet(),
+                    _ => None,
+                };
+                if let Some(bullet_index) = wall_hit {
+                    let bullet = &mut self.bullets[bullet_index];
+                    if bullet.bounces_remaining > 0 {
+                        // Le solveur a déjà réfléchi la vitesse, la balle est élastique
+                        bullet.bounces_remaining -= 1;
+                        bullet.expires_at += bounce_ticks;
+                    } else {
+                        bullet_indices_to_remove.push(bullet_index);
+                    }
+                    continue;
+                }
+
+                let (bullet_index, entity_index) = match (kind1, kind2) {
+                    (Some(BodyKind::Bullet(bullet)), Some(BodyKind::Entity(entity)))
+                    | (Some(BodyKind::Entity(entity)), Some(BodyKind::Bullet(bullet))) => (bullet, entity),
+                    // Deux balles qui se croisent disparaissent toutes les deux
+                    (Some(BodyKind::Bullet(first)), Some(BodyKind::Bullet(second))) => {
+                        bullet_indices_to_remove.extend([first, second]);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let bullet = &self.bullets[bullet_index];
+                let full_damage = bullet.damage(&self.game_config);
+                let damage = self.entities[entity_index].mitigate(full_damage);
+                let victim = self.entities[entity_index].id;
+                // Pendant le délai de grâce, la balle continue sa course sans toucher son tireur
+                if bullet.shooter_id == Some(victim) && self.tick < bullet.fired_at + self_hit_grace {
+                    continue;
+                }
+                bullet_indices_to_remove.push(bullet_index);
+
+                // Le corps du tireur a pu changer de main : seule l'entité qui a tiré marque des points
+                let Some(shooter_index) = bodies.get(&bullet.shooter).copied()
+                    .and_then(BodyKind::entity)
+                    .filter(|&index| bullet.shooter_id == Some(self.entities[index].id)) else { continue };
+                // Une entité détruite plus tôt dans le pas ne l'est pas une seconde fois
+                if entity_ids_to_remove.contains(&victim) {
+                    continue;
+                }
+
+                let incoming = velocities.get(&bullet.handle).copied().unwrap_or_else(|| *self.physics_engine.bodies[bullet.handle].linvel());
+                let outcome = self.hit_entity(entity_index, shooter_index, damage, now);
+                let opponent = shooter_index != entity_index && !self.entities[entity_index].is_teammate(&self.entities[shooter_index]);
+                if opponent && matches!(outcome, HitOutcome::Damaged | HitOutcome::Destroyed { .. }) {
+                    self.entities[shooter_index].breakdown.hits += 1;
+                }
+                let body = &self.physics_engine.bodies[self.entities[entity_index].handle];
+                let (position, rotation) = (*body.translation(), body.rotation().angle());
+                let (shooter, x, y) = (self.entities[shooter_index].id, position.x, position.y);
+                // La balle vient du côté opposé à sa vitesse
+                let bearing = GameLogic::bearing((0.0, 0.0), rotation, (-incoming.x, -incoming.y));
+                if matches!(outcome, HitOutcome::Damaged | HitOutcome::Destroyed { .. }) {
+                    let entity = &mut self.entities[entity_index];
+                    entity.record_hit(self.tick, bearing, damage);
+                    entity.breakdown.damage_mitigated += full_damage - damage;
+                }
+                if let Some(penalty) = self.self_hit_penalty(shooter, victim, &outcome) {
+                    events.push(penalty);
+                }
+                match outcome {
+                    HitOutcome::Ignored => {}
+                    HitOutcome::Damaged => {
+                        events.push(GameEvent::Hit { shooter, victim, damage, x, y, bearing: Some(bearing) });
+                    }
+                    HitOutcome::Destroyed { comeback_bonus } => {
+                        events.push(GameEvent::Hit { shooter, victim, damage, x, y, bearing: Some(bearing) });
+                        events.push(GameEvent::Kill { shooter, victim, x, y });
+                        events.extend(GameLogic::comeback_event(shooter, comeback_bonus));
+                        entity_ids_to_remove.push(victim);
+                    }
+                }
+            }
+        }
+
+        for event in events {
+            self.notify(event);
+        }
+
+        // Le premier arrivé ramasse le bonus
+        pickups.sort_by_key(|&(power_up_index, _)| std::cmp::Reverse(power_up_index));
+        pickups.dedup_by_key(|(power_up_index, _)| *power_up_index);
+        let until = self.tick + self.seconds_to_ticks(AppDefines::POWER_UP_DURATION_SECS);
+        for (power_up_index, entity_index) in pickups {
+            let kind = self.power_ups[power_up_index].kind;
+            let entity = &mut self.entities[entity_index];
+            entity.pick_up(kind, until, self.game_config.starting_health);
+            let event = GameEvent::PickUp { id: entity.id, power_up: kind.name().to_string(), x: entity.x, y: entity.y };
+            self.remove_power_up(power_up_index);
+            self.notify(event);
+        }
+
+        // Supprimer les balles (dans l'ordre décroissant pour éviter les décalages d'indices)
+        bullet_indices_to_remove.sort_unstable_by(|a, b| b.cmp(a));
+        // Une balle peut toucher un obstacle et une entité dans le même pas
+        bullet_indices_to_remove.dedup();
+        for &index in &bullet_indices_to_remove {
+            self.remove_bullet(index);
+        }
+
+        // Supprimer les entités mortes par ID
+        for id in entity_ids_to_remove {
+            self.remove_entity_by_id(id);
+        }
+
+        // La mine a pu partir avec son propriétaire ou exploser plus tôt dans le pas
+        for collider_handle in detonations {
+            if let Some(index) = self.mines.iter().position(|mine| mine.collider_handle == collider_handle) {
+                self.detonate_mine(index);
+            }
+        }
+    }
+
+    /// Damages the entities that collided hard enough with each other during the last step.
+    ///
+    /// The health removed grows with the relative speed of the two entities and is shared
+    /// between them: the faster one takes the smaller part, and each takes at least 1.
+    /// A collision counts as a hit from the other entity, with the same rules as a bullet hit.
+    /// The same two entities cannot damage each other again until they stayed apart
+    /// for `RAMMING_COOLDOWN_MS`, so an impact counts once however long the bodies push.
+    /// The armor shrugs off its share only while `armor_blocks_ramming_and_hazards` is on.
+    ///
+    /// # Parameters
+    /// - `velocities`: The velocity of each entity body before the last step.
+    fn handle_ramming(&mut self, velocities: &HashMap<RigidBodyHandle, Vector<f32>>) {
+        // Les événements restent lisibles jusqu'au pas suivant, pour l'interface
+        let contacts = self.physics_engine.contact_force_events.clone();
+        if !self.game_config.ramming_enabled {
+            return;
+        }
+
+        let now = self.sim_time();
+        self.ram_cooldowns.retain(|_, until| *until > now);
+        let horde_active = self.horde_active();
+        let score_rules = self.score_rules.clone();
+        let config = self.game_config.clone();
+        let mut entity_ids_to_remove = Vec::new();
+        let mut events = Vec::new();
+        for contact in contacts {
+            let body1 = self.physics_engine.colliders.get(contact.collider1).and_then(|c| c.parent());
+            let body2 = self.physics_engine.colliders.get(contact.collider2).and_then(|c| c.parent());
+            let (Some(body1), Some(body2)) = (body1, body2) else { continue };
+            let index1 = self.entities.iter().position(|e| e.handle == body1);
+            let index2 = self.entities.iter().position(|e| e.handle == body2);
+            let (Some(index1), Some(index2)) = (index1, index2) else { continue };
+
+            let (id1, id2) = (self.entities[index1].id, self.entities[index2].id);
+            if entity_ids_to_remove.contains(&id1) || entity_ids_to_remove.contains(&id2) {
+                continue;
+            }
+            // Tant que les deux entités restent en contact, c'est le même choc
+            let pair = (id1.min(id2), id1.max(id2));
+            let until = now + Duration::from_millis(AppDefines::RAMMING_COOLDOWN_MS);
+            if self.ram_cooldowns.insert(pair, until).is_some() {
+                continue;
+            }
+
+            let (Some(velocity1), Some(velocity2)) = (velocities.get(&body1), velocities.get(&body2)) else { continue };
+            let (speed1, speed2) = (velocity1.norm(), velocity2.norm());
+            let total = (velocity1 - velocity2).norm() * config.ramming_damage_per_speed;
+            // Chacun prend la part de la vitesse de l'autre
+            let share1 = if speed1 + speed2 > 0.0 { speed2 / (speed1 + speed2) } else { 0.5 };
+            let full_damage1 = ((total * share1).round() as i32).max(1);
+            let full_damage2 = ((total * (1.0 - share1)).round() as i32).max(1);
+
+            let (entity1, entity2) = GameLogic::entity_pair_mut(&mut self.entities, index1, index2);
+            let (damage1, damage2) = if config.armor_blocks_ramming_and_hazards {
+                (entity1.mitigate(full_damage1), entity2.mitigate(full_damage2))
+            } else {
+                (full_damage1, full_damage2)
+            };
+            let outcomes = [
+                (GameLogic::apply_hit(entity1, entity2, damage1, horde_active, &config, &score_rules, now), id2, id1, damage1, body1),
+                (GameLogic::apply_hit(entity2, entity1, damage2, horde_active, &config, &score_rules, now), id1, id2, damage2, body2),
+            ];
+            if matches!(outcomes[0].0, HitOutcome::Damaged | HitOutcome::Destroyed { .. }) {
+                entity1.breakdown.damage_mitigated += full_damage1 - damage1;
+            }
+            if matches!(outcomes[1].0, HitOutcome::Damaged | HitOutcome::Destroyed { .. }) {
+                entity2.breakdown.damage_mitigated += full_damage2 - damage2;
+            }
+            for (outcome, shooter, victim, damage, body) in outcomes {
+                let position = *self.physics_engine.bodies[body].translation();
+                let (x, y) = (position.x, position.y);
+                match outcome {
+                    HitOutcome::Ignored => {}
+                    HitOutcome::Damaged => {
+                        events.push(GameEvent::Hit { shooter, victim, damage, x, y, bearing: None });
+                    }
+                    HitOutcome::Destroyed { comeback_bonus } => {
+                        events.push(GameEvent::Hit { shooter, victim, damage, x, y, bearing: None });
+                        events.push(GameEvent::Kill { shooter, victim, x, y });
+                        events.extend(GameLogic::comeback_event(shooter, comeback_bonus));
+                        entity_ids_to_remove.push(victim);
+                    }
+                }
+            }
+        }
+
+        for event in events {
+            self.notify(event);
+        }
+        for id in entity_ids_to_remove {
+            self.remove_entity_by_id(id);
+        }
+    }
+
+    /// Applies the damage of an entity hitting itself, with its own bullet after the grace period or its own mine.
+    ///
+    /// The hit costs the entity the self hit penalty and never earns points.
+    /// A raised shield or the spawn protection blocks it.
+    ///
+    /// # Parameters
+    /// - `entity`: The entity hit.
+    /// - `damage`: The health removed from the entity.
+    /// - `now`: The current simulated time.
+    ///
+    /// # Returns
+    /// Whether the hit was blocked, damaged or destroyed the entity.
+    fn apply_self_hit(entity: &mut Entity, damage: i32, config: &GameConfig, now: Duration) -> HitOutcome {
+        if entity.is_spawn_protected(now) {
+            return HitOutcome::Ignored;
+        }
+        if entity.shield_active() {
+            entity.block_hit();
+            return HitOutcome::Ignored;
+        }
+        entity.health -= damage;
+        entity.score -= config.self_hit_penalty;
+        if entity.health > 0 { HitOutcome::Damaged } else { HitOutcome::Destroyed { comeback_bonus: 0 } }
+    }
+
+    /// Returns the event telling that an entity lost points for hitting itself.
+    ///
+    /// # Parameters
+    /// - `shooter`: The id of the entity credited with the hit.
+    /// - `victim`: The id of the entity hit.
+    /// - `outcome`: What the hit did.
+    ///
+    /// # Returns
+    /// The event, `None` if the hit was on another entity, blocked, or cost nothing.
+    fn self_hit_penalty(&self, shooter: u32, victim: u32, outcome: &HitOutcome) -> Option<GameEvent> {
+        let points = self.game_config.self_hit_penalty;
+        (shooter == victim && points != 0 && !matches!(outcome, HitOutcome::Ignored))
+            .then_some(GameEvent::Penalty { id: victim, points, reason: PenaltyReason::SelfHit })
+    }
+
+    /// Returns the event telling that a kill earned the shooter a comeback bonus.
+    ///
+    /// # Parameters
+    /// - `shooter`: The id of the entity credited with the kill.
+    /// - `bonus`: The bonus of the kill, see `HitOutcome::Destroyed`.
+    ///
+    /// # Returns
+    /// The event, `None` without bonus.
+    fn comeback_event(shooter: u32, bonus: i32) -> Option<GameEvent> {
+        (bonus > 0).then_some(GameEvent::Bonus { id: shooter, points: bonus, reason: BonusReason::Comeback })
+    }
+
+    /// Indexes the bodies of the entities and the bullets by their handle.
+    fn body_kinds(&self) -> HashMap<RigidBodyHandle, BodyKind> {
+        let entities = self.entities.iter().enumerate().map(|(index, entity)| (entity.handle, BodyKind::Entity(index)));
+        let bullets = self.bullets.iter().enumerate().map(|(index, bullet)| (bullet.handle, BodyKind::Bullet(index)));
+        entities.chain(bullets).collect()
+    }
+
+    /// Applies a hit on an entity, credited to an entity that may be the same one.
+    ///
+    /// # Parameters
+    /// - `victim_index`: The index of the entity hit.
+    /// - `shooter_index`: The index of the entity credited with the hit.
+    /// - `damage`: The health removed from the entity.
+    /// - `now`: The current simulated time.
+    ///
+    /// # Returns
+    /// Whether the hit was ignored, damaged or destroyed the entity.
+    fn hit_entity(&mut self, victim_index: usize, shooter_index: usize, damage: i32, now: Duration) -> HitOutcome {
+        if victim_index == shooter_index {
+            return GameLogic::apply_self_hit(&mut self.entities[victim_index], damage, &self.game_config, now);
+        }
+        let horde_active = self.horde_active();
+        let (victim, shooter) = GameLogic::entity_pair_mut(&mut self.entities, victim_index, shooter_index);
+        GameLogic::apply_hit(victim, shooter, damage, horde_active, &self.game_config, &self.score_rules, now)
+    }
+
+    /// Returns mutable references to two different entities.
+    ///
+    /// # Parameters
+    /// - `first`: The index of the first entity.
+    /// - `second`: The index of the second entity, different from `first`.
+    fn entity_pair_mut(entities: &mut [Entity], first: usize, second: usize) -> (&mut Entity, &mut Entity) {
+        // Obtenir 2 références mutables distinctes aux entités pour éviter le conflit d'emprunts
+        if first < second {
+            let (head, tail) = entities.split_at_mut(second);
+            (&mut head[first], &mut tail[0])
+        } else {
+            let (head, tail) = entities.split_at_mut(first);
+            (&mut tail[0], &mut head[second])
+        }
+    }
+
+    /// Applies the damage and score of a hit, as set by the match rules.
+    ///
+    /// In horde mode, players form one implicit team against the AI bots:
+    /// hits between two players or between two bots are ignored, and
+    /// destroying a bot awards a bonus. Hits between teammates are ignored
+    /// unless friendly fire is enabled, in which case they cost the shooter
+    /// the friendly hit penalty and never award a bonus. A raised shield blocks the hit,
+    /// and an entity under spawn protection ignores it.
+    /// Destroying a higher-scored opponent may award a comeback bonus.
+    ///
+    /// # Parameters
+    /// - `entity`: The entity hit.
+    /// - `shooter`: The entity credited with the hit.
+    /// - `damage`: The health removed from the entity.
+    /// - `now`: The current simulated time.
+    ///
+    /// # Returns
+    /// Whether the hit was ignored, damaged or destroyed the entity.
+    fn apply_hit(entity: &mut Entity, shooter: &mut Entity, damage: i32, horde_active: bool, config: &GameConfig, score_rules: &ScoreRules, now: Duration) -> HitOutcome {
+        if entity.is_spawn_protected(now) {
+            return HitOutcome::Ignored;
+        }
+        if horde_active && entity.is_ai == shooter.is_ai {
+            return HitOutcome::Ignored;
+        }
+        if !config.friendly_fire && entity.is_teammate(shooter) {
+            return HitOutcome::Ignored;
+        }
+
+        // Le bouclier détruit la balle sans dégâts
+        if entity.shield_active() {
+            entity.block_hit();
+            return HitOutcome::Ignored;
+        }
+
+        let shooter_score = shooter.score;
+        entity.health -= damage;
+        if entity.is_teammate(shooter) {
+            shooter.score -= config.friendly_hit_penalty;
+            return if entity.health > 0 { HitOutcome::Damaged } else { HitOutcome::Destroyed { comeback_bonus: 0 } };
+        }
+        shooter.score += config.points_per_hit;
+        if entity.health > 0 {
+            return HitOutcome::Damaged;
+        }
+        shooter.score += config.points_per_kill;
+
+        let comeback_bonus = score_rules.comeback_bonus(shooter_score, entity.score);
+        shooter.score += comeback_bonus;
+
+        if horde_active && entity.is_ai {
+            shooter.score += AppDefines::HORDE_KILL_BONUS;
+        }
+        HitOutcome::Destroyed { comeback_bonus }
+    }
+
+    /// Removes a bullet from the game.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the bullet to remove.
+    fn remove_bullet(&mut self, index: usize) {
+        let bullet = self.bullets.remove(index);
+        if let Some(shooter) = self.entities.iter_mut().find(|e| e.handle == bullet.shooter) {
+            shooter.live_bullets = shooter.live_bullets.saturating_sub(1);
+        }
+        self.physics_engine.release_bullet(bullet.handle, self.tick);
+    }
+
+    /// Returns the safe zone of the running round.
+    ///
+    /// # Returns
+    /// The zone, or `None` when the match rules disable it or between two rounds.
+    pub fn zone(&self) -> Option<Zone> {
+        if self.match_state.phase != MatchPhase::Running {
+            return None;
+        }
+        Zone::at(&self.game_config, &self.arena, self.match_state.elapsed(self.sim_time()))
+    }
+
+    /// Removes health from the entities outside the zone, at the rate of the match rules.
+    ///
+    /// The damage builds up step after step and is removed from the health a whole point at a time.
+    /// Neither the shield nor the spawn protection block it. An entity destroyed by the zone
+    /// is reported as destroying itself.
+    fn apply_zone_damage(&mut self) {
+        let Some(zone) = self.zone() else {
+            self.zone_damage.clear();
+            return;
+        };
+        let damage_per_step = self.game_config.zone_damage_per_sec * self.physics_engine.integration_parameters.dt;
+        let mut events = Vec::new();
+        let mut entity_ids_to_remove = Vec::new();
+        for entity in &mut self.entities {
+            let position = *self.physics_engine.bodies[entity.handle].translation();
+            if !zone.is_outside(position.x, position.y) {
+                self.zone_damage.remove(&entity.id);
+                continue;
+            }
+            let pending = self.zone_damage.entry(entity.id).or_insert(0.0);
+            *pending += damage_per_step;
+            // Une marge pour que l'arrondi des flottants ne retarde pas un point d'un pas
+            let damage = (*pending + 1e-4).floor();
+            if damage >= 1.0 {
+                *pending -= damage;
+                entity.health -= damage as i32;
+                if entity.health <= 0 {
+                    events.push(GameEvent::Kill { shooter: entity.id, victim: entity.id, x: position.x, y: position.y });
+                    entity_ids_to_remove.push(entity.id);
+                }
+            }
+        }
+        for event in events {
+            self.notify(event);
+        }
+        for id in entity_ids_to_remove {
+            self.remove_entity_by_id(id);
+        }
+    }
+
+    /// Removes health from the entities inside the damage zones of the map, at the rate of each zone.
+    ///
+    /// As for the safe zone, the damage builds up step after step and is removed from the health
+    /// a whole point at a time, and an entity destroyed by a hazard is reported as destroying itself.
+    /// The shield does not block it, the spawn protection only while `hazards_ignore_spawn_protection` is off,
+    /// and the armor, which lowers the rate of every zone, only while `armor_blocks_ramming_and_hazards` is on.
+    fn apply_hazard_damage(&mut self) {
+        let alive: HashSet<u32> = self.entities.iter().map(|entity| entity.id).collect();
+        for hazard in &mut self.hazards {
+            hazard.forget(|id| alive.contains(&id));
+        }
+        let now = self.sim_time();
+        let dt = self.physics_engine.integration_parameters.dt;
+        let mut events = Vec::new();
+        let mut entity_ids_to_remove = Vec::new();
+        for entity in &mut self.entities {
+            let per_sec: f32 = self.hazards.iter()
+                .filter(|hazard| hazard.holds(entity.id))
+                .map(|hazard| match hazard.effect {
+                    HazardEffect::Damage { per_sec } => per_sec,
+                    HazardEffect::Slow { .. } => 0.0,
+                })
+                .sum();
+            let spared = entity.is_spawn_protected(now) && !self.game_config.hazards_ignore_spawn_protection;
+            if per_sec <= 0.0 || spared {
+                self.hazard_damage.remove(&entity.id);
+                continue;
+            }
+            // Un dégât continu n'a pas de minimum : l'armure réduit le taux, pas chaque point
+            let shrugged = if self.game_config.armor_blocks_ramming_and_hazards { entity.armor } else { 0.0 };
+            let (pending, pending_mitigated) = self.hazard_damage.entry(entity.id).or_insert((0.0, 0.0));
+            *pending += per_sec * (1.0 - shrugged) * dt;
+            *pending_mitigated += per_sec * shrugged * dt;
+            // Une marge pour que l'arrondi des flottants ne retarde pas un point d'un pas
+            let mitigated = (*pending_mitigated + 1e-4).floor();
+            if mitigated >= 1.0 {
+                *pending_mitigated -= mitigated;
+                entity.breakdown.damage_mitigated += mitigated as i32;
+            }
+            let damage = (*pending + 1e-4).floor();
+            if damage >= 1.0 {
+                *pending -= damage;
+                entity.health -= damage as i32;
+                if entity.health <= 0 {
+                    let position = *self.physics_engine.bodies[entity.handle].translation();
+                    events.push(GameEvent::Kill { shooter: entity.id, victim: entity.id, x: position.x, y: position.y });
+                    entity_ids_to_remove.push(entity.id);
+                }
+            }
+        }
+        for event in events {
+            self.notify(event);
+        }
+        for id in entity_ids_to_remove {
+            self.remove_entity_by_id(id);
+        }
+    }
+
+    /// Removes points from the entities staying in the same spot, if the anti-camping rule is on.
+    ///
+    /// The rule only applies while a round is running. An entity losing points gets a `Penalty`
+    /// event, and an entity starting to camp a `Camping` event telling where it is, see `CampWatch`.
+    fn apply_camping_penalty(&mut self) {
+        if !self.game_config.camping_enabled || self.match_state.phase != MatchPhase::Running {
+            self.camp_watches.clear();
+            return;
+        }
+        let now = self.sim_time();
+        let dt = self.physics_engine.integration_parameters.dt;
+        let mut events = Vec::new();
+        for entity in &mut self.entities {
+            let position = *self.physics_engine.bodies[entity.handle].translation();
+            let position = (position.x, position.y);
+            let watch = self.camp_watches.entry(entity.id).or_insert_with(|| CampWatch::new(position, now));
+            let verdict = watch.observe(position, now, dt, &self.game_config);
+            if verdict.reveal {
+                events.push(GameEvent::Camping { id: entity.id, x: position.0, y: position.1 });
+            }
+            if verdict.points > 0 {
+                entity.score -= verdict.points;
+                events.push(GameEvent::Penalty { id: entity.id, points: verdict.points, reason: PenaltyReason::Camping });
+            }
+        }
+        for event in events {
+            self.notify(event);
+        }
+    }
+
+    /// Adds a ghost camera at the center of the arena.
+    ///
+    /// # Returns
+    /// The id of the camera, taken from the ids of the entities so that no entity ever shares it.
+    pub fn add_ghost_camera(&mut self) -> u32 {
+        let id = self.next_entity_id();
+        self.ghost_cameras.push(GhostCamera::new(id, self.arena.center()));
+        id
+    }
+
+    /// Removes a ghost camera.
+    ///
+    /// # Returns
+    /// `false` if there is no camera with this id.
+    pub fn remove_ghost_camera(&mut self, id: u32) -> bool {
+        let count = self.ghost_cameras.len();
+        self.ghost_cameras.retain(|camera| camera.id != id);
+        self.ghost_cameras.len() < count
+    }
+
+    /// Returns the ghost camera with an id.
+    pub fn ghost_camera(&self, id: u32) -> Option<&GhostCamera> {
+        self.ghost_cameras.iter().find(|camera| camera.id == id)
+    }
+
+    /// Returns the ghost camera with an id, to steer it.
+    pub fn ghost_camera_mut(&mut self, id: u32) -> Option<&mut GhostCamera> {
+        self.ghost_cameras.iter_mut().find(|camera| camera.id == id)
+    }
+
+    /// Returns the ghost cameras of the spectators.
+    pub fn ghost_cameras(&self) -> &[GhostCamera] {
+        &self.ghost_cameras
+    }
+
+    /// Returns the control points of the map, see `ControlPoint`.
+    pub fn control_points(&self) -> &[ControlPoint] {
+        &self.control_points
+    }
+
+    /// Replaces the control points, owned by no one.
+    ///
+    /// # Parameters
+    /// - `points`: The control points of the map, none for a generated map.
+    fn place_control_points(&mut self, points: &[MapControlPoint]) {
+        for point in self.control_points.drain(..) {
+            point.remove(&mut self.physics_engine);
+        }
+        for point in points {
+            self.control_points.push(ControlPoint::new(point, &mut self.physics_engine));
+        }
+    }
+
+    /// Follows an entity entering or leaving a control point.
+    ///
+    /// # Parameters
+    /// - `event`: An intersection with a sensor.
+    ///
+    /// # Returns
+    /// Whether the sensor is a control point.
+    fn sense_control_point(&mut self, event: &CollisionEvent) -> bool {
+        let (collider1, collider2) = (event.collider1(), event.collider2());
+        let identity1 = self.physics_engine.identify_collider(collider1);
+        let identity2 = self.physics_engine.identify_collider(collider2);
+        let (sensor, other) = match (identity1, identity2) {
+            (BodyIdentity::ControlPoint, _) => (collider1, identity2),
+            (_, BodyIdentity::ControlPoint) => (collider2, identity1),
+            _ => return false,
+        };
+        // Un corps retiré n'a plus d'identité : l'entité sortie de l'arène est oubliée à l'étape suivante
+        if let BodyIdentity::Entity(entity_id) = other {
+            if let Some(point) = self.control_points.iter_mut().find(|point| point.collider_handle == sensor) {
+                point.sense(entity_id, event.started());
+            }
+        }
+        true
+    }
+
+    /// Captures the control points and gives their points, in the control point mode.
+    ///
+    /// The points only move while a round is running. Each entity of the side holding a point
+    /// earns its points while inside, and a side capturing a point gets a `Capture` event.
+    fn update_control_points(&mut self) {
+        if self.game_config.game_mode != GameMode::ControlPoint || self.match_state.phase != MatchPhase::Running {
+            return;
+        }
+        let dt = self.physics_engine.integration_parameters.dt;
+        let entities: Vec<(u32, Side, (f32, f32))> = self.entities.iter().map(|entity| {
+            let position = self.physics_engine.bodies[entity.handle].translation();
+            (entity.id, Side::of(entity), (position.x, position.y))
+        }).collect();
+        let mut events = Vec::new();
+        let mut earned: Vec<(u32, i32)> = Vec::new();
+        for point in &mut self.control_points {
+            let occupants = point.occupants(&entities);
+            let verdict = point.advance(ControlPoint::majority(&occupants), dt, &self.game_config);
+            if let Some(owner) = verdict.captured {
+                events.push(GameEvent::Capture { point: point.name.clone(), owner });
+            }
+            if verdict.points > 0 {
+                earned.extend(occupants.iter().filter(|(_, side)| point.owner == Some(*side)).map(|(id, _)| (*id, verdict.points)));
+            }
+        }
+        for (id, points) in earned {
+            if let Some(entity) = self.entities.iter_mut().find(|entity| entity.id == id) {
+                entity.score += points;
+            }
+        }
+        for event in events {
+            self.notify(event);
+        }
+    }
+
+    /// Returns the control points as the replay frames and the UI draw them, none outside of the control point mode.
+    fn control_point_frames(&self) -> Vec<ControlPointFrame> {
+        if self.game_config.game_mode != GameMode::ControlPoint {
+            return Vec::new();
+        }
+        self.control_points.iter().map(|point| ControlPointFrame {
+            name: point.name.clone(),
+            x: point.position.0,
+            y: point.position.1,
+            radius: point.radius,
+            owner: point.owner,
+            progress: point.progress,
+        }).collect()
+    }
+
+    /// Returns the damage zones and slow fields of the map, see `Hazard`.
+    pub fn hazards(&self) -> &[Hazard] {
+        &self.hazards
+    }
+
+    /// Replaces the hazards, with no entity inside.
+    ///
+    /// # Parameters
+    /// - `hazards`: The hazards of the map, none for a generated map.
+    fn place_hazards(&mut self, hazards: &[MapHazard]) {
+        self.hazard_damage.clear();
+        for hazard in self.hazards.drain(..) {
+            hazard.remove(&mut self.physics_engine);
+        }
+        for hazard in hazards {
+            self.hazards.push(Hazard::new(hazard, &mut self.physics_engine));
+        }
+    }
+
+    /// Follows an entity entering or leaving a hazard.
+    ///
+    /// # Parameters
+    /// - `event`: An intersection with a sensor.
+    ///
+    /// # Returns
+    /// Whether the sensor is a hazard.
+    fn sense_hazard(&mut self, event: &CollisionEvent) -> bool {
+        let (collider1, collider2) = (event.collider1(), event.collider2());
+        let identity1 = self.physics_engine.identify_collider(collider1);
+        let identity2 = self.physics_engine.identify_collider(collider2);
+        let (sensor, other) = match (identity1, identity2) {
+            (BodyIdentity::Hazard, _) => (collider1, identity2),
+            (_, BodyIdentity::Hazard) => (collider2, identity1),
+            _ => return false,
+        };
+        if let BodyIdentity::Entity(entity_id) = other {
+            if let Some(hazard) = self.hazards.iter_mut().find(|hazard| hazard.collider_handle == sensor) {
+                hazard.sense(entity_id, event.started());
+            }
+        }
+        true
+    }
+
+    /// Returns the factor of the motors of each entity inside a slow field, the lowest one where fields overlap.
+    ///
+    /// # Parameters
+    /// - `hazards`: The hazards of the map.
+    /// - `entities`: The entities of the arena.
+    fn hazard_slows(hazards: &[Hazard], entities: &[Entity]) -> HashMap<u32, f32> {
+        let mut slows = HashMap::new();
+        for hazard in hazards {
+            let HazardEffect::Slow { factor } = hazard.effect else { continue };
+            for entity in entities.iter().filter(|entity| hazard.holds(entity.id)) {
+                let slow = slows.entry(entity.id).or_insert(1.0_f32);
+                *slow = slow.min(factor);
+            }
+        }
+        slows
+    }
+
+    /// Brings the entities that got through the walls back inside the arena.
+    ///
+    /// The entity is moved to the nearest point inside the walls and stopped.
+    fn contain_entities(&mut self) {
+        let margin = AppDefines::ENTITY_HALF_SIZE;
+        let mut events = Vec::new();
+        for entity in &mut self.entities {
+            let body = &mut self.physics_engine.bodies[entity.handle];
+            let position = *body.translation();
+            if self.arena.contains(position.x, position.y) {
+                continue;
+            }
+
+            let (x, y) = self.arena.clamp(position.x, position.y, margin);
+            body.set_translation(vector![x, y], true);
+            body.set_linvel(vector![0.0, 0.0], true);
+            body.set_angvel(0.0, true);
+            entity.x = x;
+            entity.y = y;
+            entity.vx = 0.0;
+            entity.vy = 0.0;
+            self.teleported.insert(entity.id);
+            events.push(GameEvent::OutOfBounds { id: entity.id, x, y });
+        }
+        for event in events {
+            self.notify(event);
+        }
+    }
+
+    /// Removes the bullets that went through the walls of the arena.
+    ///
+    /// A bullet only sinking into a wall for a step is kept, the solver pushes it back out.
+    fn remove_out_of_bounds_bullets(&mut self) {
+        let mut bullet_indices_to_remove = Vec::new();
+        let margin = self.physics_engine.wall_thickness;
+
+        for (index, bullet) in self.bullets.iter().enumerate() {
+            let position = self.physics_engine.bodies[bullet.handle].translation();
+            if !self.arena.contains_with_margin(position.x, position.y, margin) {
+                bullet_indices_to_remove.push(index);
+            }
+        }
+
+        bullet_indices_to_remove.sort_unstable_by(|a, b| b.cmp(a));
+        for &index in &bullet_indices_to_remove {
+            self.remove_bullet(index);
+        }
+    }
+
+    /// Removes bullets that have expired.
+    ///
+    /// Lifetimes are counted in simulation steps, so pausing the game or changing
+    /// its time scale does not change how far a bullet flies.
+    fn remove_expired_bullets(&mut self) {
         let mut bullet_indices_to_remove = Vec::new();
 
         for (index, bullet) in self.bullets.iter().enumerate() {
-            if now.duration_since(bullet.created_at).as_secs() >= 2 {
+            if bullet.expires_at <= self.tick {
                 bullet_indices_to_remove.push(index);
             }
         }
@@ -305,173 +2921,837 @@ impl GameLogic {
     }
 
     /// Resets the simulation.
+    ///
+    /// The arena is reset as between two rounds, see `reset_arena`. The pending events
+    /// are dropped and the current round starts over.
     pub fn reset_simulation(&mut self) {
+        // Les événements d'avant ne concernent plus la partie : seuls la fin et le début de manche restent
+        self.events.clear();
+        self.reset_arena();
+        self.match_state.restart_round(self.sim_time());
+        self.notify(GameEvent::RoundStart { round: self.match_state.round });
+        self.reset_count += 1;
+    }
+
+    /// Returns the number of times the simulation was reset, the server tells the clients when it grows.
+    pub fn reset_count(&self) -> u64 {
+        self.reset_count
+    }
+
+    /// Resets the arena: bullets, power-ups and mines are removed, the entities are
+    /// repositioned, stopped and put back in the state of new entities.
+    ///
+    /// Scores are cleared, team assignments are kept.
+    fn reset_arena(&mut self) {
+        self.notify(GameEvent::RoundEnd);
+        self.round_log = RoundLog { start_tick: self.tick, ..RoundLog::default() };
+
         for entity in &mut self.entities {
+            self.leaderboard.record_breakdown(&entity.name, &std::mem::take(&mut entity.breakdown));
             entity.score = 0;
         }
 
         // Delete all bullets
-        for bullet in &self.bullets {
-            self.physics_engine.bodies.remove(
-                bullet.handle,
-                &mut self.physics_engine.islands,
-                &mut self.physics_engine.colliders,
-                &mut self.physics_engine.impulse_joints,
-                &mut self.physics_engine.multibody_joints,
-                true,
-            );
+        for bullet in self.bullets.drain(..) {
+            self.physics_engine.release_bullet(bullet.handle, self.tick);
         }
-        self.bullets.clear();
+        for entity in &mut self.entities {
+            entity.live_bullets = 0;
+        }
+        self.remove_all_power_ups();
+        self.remove_all_mines();
+        self.zone_damage.clear();
+        self.hazard_damage.clear();
+        self.camp_watches.clear();
+        for point in &mut self.control_points {
+            point.reset();
+        }
+        self.trails.clear();
+        self.ram_cooldowns.clear();
 
         // Reposition entities
         self.reposition_entities();
+        let now = self.sim_time();
+        for entity in &mut self.entities {
+            let body = &mut self.physics_engine.bodies[entity.handle];
+            body.set_rotation(Rotation::new(0.0), true);
+            body.set_linvel(vector![0.0, 0.0], true);
+            body.set_angvel(0.0, true);
+            entity.reset(&self.game_config, now);
+        }
+        self.sync_entities();
     }
 
     /// Removes all obstacles from the game.
     fn remove_all_obstacles(&mut self) {
-        for obstacle in &self.obstacles {
-            self.physics_engine.colliders.remove(
-                obstacle.collider_handle,
-                &mut self.physics_engine.islands,
-                &mut self.physics_engine.bodies,
-                true,
-            );
+        for obstacle in self.obstacles.drain(..) {
+            obstacle.remove(&mut self.physics_engine);
         }
-        self.obstacles.clear();
     }
 
-    /// Generates obstacles in the game.
-    fn generate_obstacles(&mut self) {
-        let mut rng = rand::thread_rng();
+    /// Removes all mines from the game.
+    fn remove_all_mines(&mut self) {
+        while !self.mines.is_empty() {
+            self.remove_mine(self.mines.len() - 1);
+        }
+    }
 
-        for _ in 0..25 {
-            let random_x = rng.gen_range(10.0..1190.0) as f64;
-            let random_y = rng.gen_range(10.0..990.0) as f64;
+    /// Removes all power-ups from the game.
+    fn remove_all_power_ups(&mut self) {
+        while !self.power_ups.is_empty() {
+            self.remove_power_up(self.power_ups.len() - 1);
+        }
+    }
 
-            let collider = ColliderBuilder::cuboid(10.0, 10.0)
-                .translation(vector![random_x as f32, random_y as f32])
-                .build();
-            let collider_handle = self.physics_engine.colliders.insert(collider);
+    /// Draws a random position in the arena.
+    ///
+    /// # Parameters
+    /// - `arena`: The arena.
+    /// - `rng`: The random number generator.
+    /// - `is_free`: Whether a position is acceptable.
+    ///
+    /// # Returns
+    /// The position, or the last one drawn if no free position was found.
+    fn free_position(arena: &ArenaConfig, rng: &mut StdRng, is_free: impl Fn((f32, f32)) -> bool) -> (f32, f32) {
+        let mut position = (0.0, 0.0);
+        // Un nombre fixe d'essais garde la génération déterministe pour une graine donnée
+        for _ in 0..100 {
+            position = arena.random_position(rng, 10.0);
+            if is_free(position) {
+                break;
+            }
+        }
+        position
+    }
 
-            self.obstacles.push(Obstacle::new((random_x, random_y), collider_handle));
+    /// Generates obstacles of various shapes in the game, apart from each other.
+    ///
+    /// # Parameters
+    /// - `count`: The number of obstacles, `OBSTACLE_COUNT` for a map.
+    fn generate_obstacles(&mut self, count: usize) {
+        let mut taken: Vec<((f32, f32), f32)> = Vec::new();
+
+        for _ in 0..count {
+            let shape = ObstacleShape::random(&mut self.rng);
+            let radius = shape.bounding_radius();
+            let (x, y) = GameLogic::free_position(&self.arena, &mut self.rng, |position| {
+                taken.iter().all(|&((x, y), other)| {
+                    (x - position.0).hypot(y - position.1) >= radius + other + AppDefines::OBSTACLE_MIN_GAP
+                })
+            });
+            taken.push(((x, y), radius));
+
+            self.obstacles.push(Obstacle::new((x as f64, y as f64), shape, &mut self.physics_engine));
         }
     }
 
-    /// Repositions entities to new random locations.
+    /// Repositions entities to new locations, clear of the obstacles.
+    ///
+    /// The spawn points of the map are handed out in a random order. Once they are all
+    /// taken, the other entities are placed at random.
+    /// The entities are protected again, as if they had just spawned.
     fn reposition_entities(&mut self) {
-        let mut rng = rand::thread_rng();
+        let protected_until = self.sim_time() + Duration::from_millis(self.game_config.spawn_protection_ms);
+        let mut spawn_points: Vec<(f32, f32)> = self.spawn_points.iter().map(|point| (point.x, point.y)).collect();
+        spawn_points.shuffle(&mut self.rng);
+        let mut spawn_points = spawn_points.into_iter();
+        let obstacles = &self.obstacles;
         for entity in &mut self.entities {
-            let random_x = rng.gen_range(10.0..1190.0);
-            let random_y = rng.gen_range(10.0..990.0);
+            // Une distance au bord : les longs murs gardent leurs distances comme les petits obstacles
+            let (random_x, random_y) = spawn_points.next().unwrap_or_else(|| {
+                GameLogic::free_position(&self.arena, &mut self.rng, |position| {
+                    obstacles.iter().all(|obstacle| obstacle.distance(position) >= AppDefines::ENTITY_OBSTACLE_CLEARANCE)
+                })
+            });
             let body = &mut self.physics_engine.bodies[entity.handle];
             body.set_translation(vector![random_x, random_y], true);
 
             // Update entity's internal position
             entity.x = random_x;
             entity.y = random_y;
+            self.teleported.insert(entity.id);
+            entity.spawn_protected_until = protected_until;
         }
     }
 
     /// Generates a new map with obstacles and repositions entities.
-    pub fn generate_map(&mut self) {
-        // Remove all obstacles
+    ///
+    /// The obstacles only depend on the seed, so a seed always gives the same map.
+    /// The positions of the entities also depend on how many there are. The random
+    /// number generator of the game carries on from the seed.
+    ///
+    /// # Parameters
+    /// - `seed`: The seed of the map, or `None` to draw one at random. Either way the seed
+    ///   is kept, see `map_seed`.
+    pub fn generate_map(&mut self, seed: Option<u64>) {
+        let seed = seed.unwrap_or_else(|| rand::rng().random());
+        self.map_seed = Some(seed);
+        self.map_name = None;
+        self.spawn_points.clear();
+        self.rng = StdRng::seed_from_u64(seed);
+
+        // Remove all obstacles, and the power-ups that could end up inside the new ones
         self.remove_all_obstacles();
+        self.remove_all_power_ups();
+        self.remove_all_mines();
+        self.place_control_points(&[]);
+        self.place_hazards(&[]);
 
         // Generate new obstacles
-        self.generate_obstacles();
+        self.generate_obstacles(AppDefines::OBSTACLE_COUNT);
 
         // Reposition entities
         self.reposition_entities();
+        self.physics_engine.refresh_queries();
     }
 
-    /// Adds a new AI-controlled entity to the game.
+    /// Returns the seed the current map was generated from, `None` before the first map or for a map loaded from a file.
+    pub fn map_seed(&self) -> Option<u64> {
+        self.map_seed
+    }
+
+    /// Returns the name of the map loaded from a file, `None` for a generated map.
+    pub fn map_name(&self) -> Option<&str> {
+        self.map_name.as_deref()
+    }
+
+    /// Replaces the obstacles with the ones of a map file and repositions entities,
+    /// preferring the spawn points of the map.
+    ///
+    /// The map is kept for the next rounds, until another map is generated or loaded.
     ///
     /// # Parameters
-    /// - `name`: The name of the AI entity.
-    pub fn add_ai(&mut self, name: String) -> u32 {
-        let id = self.next_entity_id();
-        let entity = Entity::new(id, name, &mut self.physics_engine, true);
-        self.entities.push(entity);
-        id
+    /// - `path`: The map file, see `MapFile`.
+    ///
+    /// # Returns
+    /// An error describing what is wrong with the file. The arena is left untouched then.
+    pub fn load_map(&mut self, path: &Path) -> io::Result<()> {
+        // Tout le fichier est lu et vérifié avant de toucher au monde physique
+        let map = MapFile::load(path, &self.arena)?;
+
+        self.remove_all_obstacles();
+        self.remove_all_power_ups();
+        self.remove_all_mines();
+
+        for obstacle in &map.obstacles {
+            self.obstacles.push(Obstacle::new((obstacle.x as f64, obstacle.y as f64), obstacle.shape, &mut self.physics_engine));
+        }
+        self.place_control_points(&map.control_points);
+        self.place_hazards(&map.hazards);
+        self.map_seed = None;
+        self.map_name = map.name;
+        self.spawn_points = map.spawn_points;
+
+        self.reposition_entities();
+        self.physics_engine.refresh_queries();
+        let loaded = format!(
+            "[INFO] Map {} loaded: {} obstacles, {} spawn points, {} control points, {} hazards",
+            self.map_name.as_deref().unwrap_or_default(),
+            self.obstacles.len(),
+            self.spawn_points.len(),
+            self.control_points.len(),
+            self.hazards.len(),
+        );
+        self.report(loaded, MessageType::Info);
+        Ok(())
     }
 
-    /// Updates AI entities in the game.
-    pub fn update_ai(&mut self) {
-        let mut rng = rand::thread_rng();
-
-        // Gather data first
-        let updates: Vec<(RigidBodyHandle, Vector<f32>, Vector<f32>)> = self.entities.iter_mut()
-            .filter_map(|entity| {
-                if entity.is_ai {
-                    // Randomly change the target position every few seconds
-                    if entity.last_shot.elapsed().as_secs_f32() > rng.gen_range(1.0..3.0) {
-                        entity.target_x = rng.gen_range(10.0..1190.0);
-                        entity.target_y = rng.gen_range(10.0..990.0);
-                        entity.last_shot = Instant::now();
-
-                        // Change the gun orientation randomly at each target change
-                        entity.gun_orientation = rng.gen_range(0.0..std::f64::consts::TAU);
-                    }
+    /// Saves the obstacles, the spawn points, the control points and the hazards of the current map to a file read by `load_map`.
+    ///
+    /// # Parameters
+    /// - `path`: The file to write.
+    pub fn save_map(&self, path: &Path) -> io::Result<()> {
+        let obstacles = self.obstacles.iter().map(|obstacle| {
+            MapObstacle { x: obstacle.position.0 as f32, y: obstacle.position.1 as f32, shape: obstacle.shape }
+        }).collect();
+        let map = MapFile {
+            format_version: MAP_FORMAT_VERSION,
+            name: self.map_name.clone(),
+            obstacles,
+            spawn_points: self.spawn_points.clone(),
+            control_points: self.control_points.iter().map(ControlPoint::to_map).collect(),
+            hazards: self.hazards.iter().map(Hazard::to_map).collect(),
+        };
+        map.save(path)
+    }
+
+    /// Adds an obstacle to the map, after the others.
+    ///
+    /// # Parameters
+    /// - `x`, `y`: The position of the obstacle, inside the arena.
+    /// - `shape`: The shape of the obstacle.
+    ///
+    /// # Returns
+    /// The index of the new obstacle in `obstacles`, `None` if the position is outside the arena.
+    pub fn add_obstacle_at(&mut self, x: f32, y: f32, shape: ObstacleShape) -> Option<usize> {
+        let index = self.obstacles.len();
+        self.insert_obstacle(index, MapObstacle { x, y, shape }).then_some(index)
+    }
 
-                    // Move towards the target position
-                    let current_pos = self.physics_engine.bodies[entity.handle].translation().clone();
-                    let target_pos = vector![entity.target_x as f32, entity.target_y as f32];
-                    let direction = target_pos - current_pos;
-                    let distance = direction.norm();
+    /// Puts an obstacle back at a given index of `obstacles`, the following ones moving up one index.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the obstacle, at most the number of obstacles.
+    /// - `obstacle`: The position and the shape of the obstacle, as returned by `remove_obstacle`.
+    ///
+    /// # Returns
+    /// Whether the obstacle was added, not if the index or the position is out of range.
+    pub fn insert_obstacle(&mut self, index: usize, obstacle: MapObstacle) -> bool {
+        if index > self.obstacles.len() || !self.arena.contains(obstacle.x, obstacle.y) {
+            return false;
+        }
+        let position = (obstacle.x as f64, obstacle.y as f64);
+        self.obstacles.insert(index, Obstacle::new(position, obstacle.shape, &mut self.physics_engine));
+        self.map_edited();
+        true
+    }
 
-                    if distance > 1.0 {
-                        let movement = direction.normalize() * 1.0; // adjust the speed here
-                        return Some((entity.handle, current_pos, movement));
-                    }
-                }
-                None
-            }).collect();
+    /// Removes an obstacle from the map, with its collider.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the obstacle in `obstacles`, the following ones moving down one index.
+    ///
+    /// # Returns
+    /// The position and the shape of the obstacle removed, `None` if there is none at this index.
+    pub fn remove_obstacle(&mut self, index: usize) -> Option<MapObstacle> {
+        if index >= self.obstacles.len() {
+            return None;
+        }
+        let obstacle = self.obstacles.remove(index);
+        obstacle.remove(&mut self.physics_engine);
+        self.map_edited();
+        Some(MapObstacle { x: obstacle.position.0 as f32, y: obstacle.position.1 as f32, shape: obstacle.shape })
+    }
+
+    /// Moves an obstacle of the map, with its collider.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the obstacle in `obstacles`.
+    /// - `x`, `y`: The new position, inside the arena.
+    ///
+    /// # Returns
+    /// Whether the obstacle moved, not if there is none at this index or the position is outside the arena.
+    pub fn move_obstacle(&mut self, index: usize, x: f32, y: f32) -> bool {
+        if !self.arena.contains(x, y) {
+            return false;
+        }
+        let Some(obstacle) = self.obstacles.get_mut(index) else {
+            return false;
+        };
+        // Un corps fixe déplacé ne met ses colliders à jour qu'au pas suivant : l'obstacle est recréé à sa place
+        obstacle.remove(&mut self.physics_engine);
+        *obstacle = Obstacle::new((x as f64, y as f64), obstacle.shape, &mut self.physics_engine);
+        self.map_edited();
+        true
+    }
+
+    /// Returns the index of the obstacle under a point, the last one drawn if they overlap.
+    ///
+    /// # Parameters
+    /// - `x`, `y`: The point.
+    pub fn obstacle_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.obstacles.iter().rposition(|obstacle| obstacle.distance((x, y)) == 0.0)
+    }
+
+    /// Takes an edit of the obstacles into account: the queries see the new colliders at once, and the map
+    /// is no longer the one of its seed but is kept for the next rounds, like a map loaded from a file.
+    fn map_edited(&mut self) {
+        self.physics_engine.refresh_queries();
+        self.map_seed = None;
+        if self.map_name.is_none() {
+            self.map_name = Some(AppDefines::EDITED_MAP_NAME.to_string());
+        }
+    }
+
+    /// Returns the size of the arena.
+    pub fn arena(&self) -> ArenaConfig {
+        self.arena
+    }
+
+    /// Resizes the arena, rebuilding its walls at once.
+    ///
+    /// The obstacles, spawn points, control points, power-ups and mines left outside the new walls are removed,
+    /// and the entities outside are brought back in. The bullets outside disappear at the next step.
+    ///
+    /// # Parameters
+    /// - `arena`: The new size, see `ArenaConfig::new`.
+    pub fn set_arena(&mut self, arena: ArenaConfig) {
+        self.arena = arena;
+        self.physics_engine.setup_boundaries(&arena);
+
+        let outside = |position: (f64, f64)| !arena.contains(position.0 as f32, position.1 as f32);
+        while let Some(index) = self.obstacles.iter().position(|obstacle| outside(obstacle.position)) {
+            self.obstacles.remove(index).remove(&mut self.physics_engine);
+        }
+        while let Some(index) = self.power_ups.iter().position(|power_up| outside(power_up.position)) {
+            self.remove_power_up(index);
+        }
+        while let Some(index) = self.mines.iter().position(|mine| outside(mine.position)) {
+            self.remove_mine(index);
+        }
+        self.spawn_points.retain(|point| arena.contains(point.x, point.y));
+        while let Some(index) = self.control_points.iter().position(|point| !arena.contains(point.position.0, point.position.1)) {
+            self.control_points.remove(index).remove(&mut self.physics_engine);
+        }
+        while let Some(index) = self.hazards.iter().position(|hazard| !arena.contains(hazard.position.0, hazard.position.1)) {
+            self.hazards.remove(index).remove(&mut self.physics_engine);
+        }
+        self.contain_entities();
+        self.physics_engine.refresh_queries();
+        self.report(format!("[INFO] Arena resized to {}x{}.", arena.width, arena.height), MessageType::Info);
+    }
+
+    /// Captures the state of the match.
+    ///
+    /// # Returns
+    /// A snapshot of the entities, bullets, obstacles and match rules, see `GameSnapshot`.
+    pub fn save_state(&self) -> GameSnapshot {
+        let now = self.sim_time();
+        let dt = self.physics_engine.integration_parameters.dt;
+
+        let entities = self.entities.iter().map(|entity| {
+            let body = &self.physics_engine.bodies[entity.handle];
+            EntitySnapshot {
+                id: entity.id,
+                name: entity.name.clone(),
+                is_ai: entity.is_ai,
+                behavior: self.ai_drivers.get(&entity.id).map(|driver| driver.name.clone()),
+                difficulty: self.ai_drivers.get(&entity.id).map(|driver| driver.difficulty),
+                color: [entity.color.r(), entity.color.g(), entity.color.b()],
+                health: entity.health,
+                score: entity.score,
+                breakdown: entity.breakdown,
+                team: entity.team,
+                energy: entity.energy,
+                fire_energy: Some(entity.fire_energy),
+                armor: Some(entity.armor),
+                motor_left: entity.motor_left,
+                motor_right: entity.motor_right,
+                gun_trigger: entity.gun().trigger,
+                gun_traverse: entity.gun().traverse,
+                shield: entity.shield,
+                gun_orientation: entity.gun().orientation,
+                fire_cooldown_ms: entity.fire_cooldown.as_millis() as u64,
+                fire_cooldown_remaining_ms: entity.gun().cooldown_remaining(entity.shot_cooldown(), now).as_millis() as u64,
+                weapon: entity.weapon,
+                gun_mount: entity.gun().mount,
+                extra_turrets: entity.turrets[1..].iter().map(|turret| TurretSnapshot {
+                    mount: turret.mount,
+                    trigger: turret.trigger,
+                    traverse: turret.traverse,
+                    orientation: turret.orientation,
+                    fire_cooldown_remaining_ms: turret.cooldown_remaining(entity.shot_cooldown(), now).as_millis() as u64,
+                }).collect(),
+                spawn_protection_remaining_ms: entity.spawn_protected_until.saturating_sub(now).as_millis() as u64,
+                speed_boost_until: entity.speed_boost_until,
+                rapid_fire_until: entity.rapid_fire_until,
+                position: (body.translation().x, body.translation().y),
+                rotation: body.rotation().angle(),
+                linear_velocity: (body.linvel().x, body.linvel().y),
+                angular_velocity: body.angvel(),
+            }
+        }).collect();
+
+        let bullets = self.bullets.iter().map(|bullet| {
+            let body = &self.physics_engine.bodies[bullet.handle];
+            BulletSnapshot {
+                position: (body.translation().x, body.translation().y),
+                velocity: (body.linvel().x, body.linvel().y),
+                shooter: match self.physics_engine.identify(bullet.handle) {
+                    BodyIdentity::Bullet(shooter) => shooter,
+                    _ => None,
+                },
+                remaining_lifetime_ms: GameLogic::time_at(bullet.expires_at.saturating_sub(self.tick), dt).as_millis() as u64,
+                age_ms: GameLogic::time_at(self.tick.saturating_sub(bullet.fired_at), dt).as_millis() as u64,
+                bounces: bullet.bounces,
+                bounces_remaining: bullet.bounces_remaining,
+                kind: bullet.kind,
+                traveled: bullet.traveled,
+            }
+        }).collect();
+
+        let obstacles = self.obstacles.iter().map(|obstacle| {
+            ObstacleSnapshot { position: obstacle.position, shape: obstacle.shape }
+        }).collect();
+
+        GameSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            arena: self.arena,
+            tick: self.tick,
+            map_seed: self.map_seed,
+            map_name: self.map_name.clone(),
+            spawn_points: self.spawn_points.clone(),
+            control_points: self.control_points.iter().map(ControlPoint::to_map).collect(),
+            hazards: self.hazards.iter().map(Hazard::to_map).collect(),
+            config: self.game_config.clone(),
+            entities,
+            bullets,
+            obstacles,
+        }
+    }
 
-        // Apply updates
-        for (handle, current_pos, movement) in updates {
-            self.physics_engine.bodies[handle].set_next_kinematic_position(
-                Isometry::translation(
-                    current_pos.x + movement.x,
-                    current_pos.y + movement.y,
-                ),
+    /// Replaces the state of the match with a snapshot.
+    ///
+    /// Entities keep their ids, so the clients keep controlling the same entities.
+    /// Bodies and colliders are inserted again. The power-ups and mines are removed, the horde
+    /// mode is stopped, the AI entities get new instances of their behaviors and the round clock restarts.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The state to restore, from `save_state`.
+    pub fn load_state(&mut self, snapshot: GameSnapshot) {
+        // Retire tous les corps actuels, leurs handles ne servent plus
+        self.names.clear();
+        for entity in self.entities.drain(..) {
+            self.physics_engine.bodies.remove(
+                entity.handle,
+                &mut self.physics_engine.islands,
+                &mut self.physics_engine.colliders,
+                &mut self.physics_engine.impulse_joints,
+                &mut self.physics_engine.multibody_joints,
+                true,
             );
         }
+        while !self.bullets.is_empty() {
+            self.remove_bullet(self.bullets.len() - 1);
+        }
+        self.remove_all_obstacles();
+        self.remove_all_power_ups();
+        self.remove_all_mines();
+        self.ai_drivers.clear();
+        self.ram_cooldowns.clear();
+        self.zone_damage.clear();
+        self.hazard_damage.clear();
+        self.camp_watches.clear();
+        self.trails.clear();
+        self.wave_director = None;
+
+        self.arena = snapshot.arena;
+        self.physics_engine.setup_boundaries(&self.arena);
+        self.tick = snapshot.tick;
+        self.last_power_up_tick = snapshot.tick;
+        self.last_decay_tick = snapshot.tick;
+        self.map_seed = snapshot.map_seed;
+        self.map_name = snapshot.map_name;
+        self.spawn_points = snapshot.spawn_points;
+        self.place_control_points(&snapshot.control_points);
+        self.place_hazards(&snapshot.hazards);
+        self.game_config = snapshot.config;
+        let now = self.sim_time();
+        self.match_state = MatchState::new(now);
+
+        for saved in snapshot.obstacles {
+            self.obstacles.push(Obstacle::new(saved.position, saved.shape, &mut self.physics_engine));
+        }
 
-        // Update entity positions and handle shooting
-        for entity in &mut self.entities {
-            if entity.is_ai {
-                let current_pos = self.physics_engine.bodies[entity.handle].translation();
-                let target_pos = vector![entity.target_x, entity.target_y];
-                let direction = target_pos - current_pos;
-                entity.self_orientation = direction.y.atan2(direction.x) as f64;
-
-                // Randomly shoot a bullet every 500ms
-                if entity.last_shot.elapsed().as_millis() >= 500 {
-                    // Change the gun orientation randomly at each shoot
-                    let random_angle = rng.gen_range(0.0..std::f64::consts::TAU);
-                    let (sin, cos) = random_angle.sin_cos();
-
-                    let bullet_handle = self.physics_engine.bodies.insert(
-                        RigidBodyBuilder::dynamic()
-                            .translation(*current_pos)
-                            .linvel(vector![cos as f32 * 500.0, sin as f32 * 500.0])
-                            .build(),
-                    );
-                    let bullet_collider = ColliderBuilder::ball(5.0)
-                        .restitution(1.0)
-                        .build();
-                    self.physics_engine.colliders.insert_with_parent(bullet_collider, bullet_handle, &mut self.physics_engine.bodies);
-
-                    let bullet = Bullet {
-                        handle: bullet_handle,
-                        shooter: entity.handle.clone(),
-                        created_at: Instant::now(),
-                    };
-
-                    self.bullets.push(bullet);
-                    entity.last_shot = Instant::now();
+        for saved in snapshot.entities {
+            // Les entités créées ensuite ne reprennent pas un id restauré
+            self.last_entity_id = self.last_entity_id.max(saved.id);
+            let name = self.names.free_name(&saved.name, saved.id);
+            let mut entity = Entity::new(saved.id, name, &mut self.physics_engine, &self.game_config, &self.arena, &mut self.rng, now);
+            entity.is_ai = saved.is_ai;
+            let body = &mut self.physics_engine.bodies[entity.handle];
+            body.set_translation(vector![saved.position.0, saved.position.1], true);
+            body.set_rotation(Rotation::new(saved.rotation), true);
+            body.set_linvel(vector![saved.linear_velocity.0, saved.linear_velocity.1], true);
+            body.set_angvel(saved.angular_velocity, true);
+            self.teleported.insert(saved.id);
+
+            entity.color = egui::Color32::from_rgb(saved.color[0], saved.color[1], saved.color[2]);
+            entity.health = saved.health;
+            entity.score = saved.score;
+            entity.breakdown = saved.breakdown;
+            entity.team = saved.team;
+            entity.update_collision_groups(&mut self.physics_engine, &self.game_config);
+            entity.energy = saved.energy;
+            entity.fire_energy = saved.fire_energy.unwrap_or(self.game_config.fire_energy_max);
+            entity.armor = saved.armor.unwrap_or(self.game_config.starting_armor());
+            entity.motor_left = saved.motor_left;
+            entity.motor_right = saved.motor_right;
+            entity.shield = saved.shield;
+            entity.fire_cooldown = Duration::from_millis(saved.fire_cooldown_ms);
+            entity.weapon = saved.weapon;
+            // Le dernier tir est daté pour qu'il reste le même temps de recharge
+            let cooldown = entity.shot_cooldown();
+            let last_shot = |remaining_ms: u64| (now + Duration::from_millis(remaining_ms)).saturating_sub(cooldown);
+            let first = TurretSnapshot {
+                mount: saved.gun_mount,
+                trigger: saved.gun_trigger,
+                traverse: saved.gun_traverse,
+                orientation: saved.gun_orientation,
+                fire_cooldown_remaining_ms: saved.fire_cooldown_remaining_ms,
+            };
+            entity.turrets = std::iter::once(&first).chain(&saved.extra_turrets).map(|turret| Turret {
+                mount: turret.mount,
+                orientation: turret.orientation,
+                traverse: turret.traverse,
+                trigger: turret.trigger,
+                last_shot: last_shot(turret.fire_cooldown_remaining_ms),
+            }).collect();
+            entity.spawn_protected_until = now + Duration::from_millis(saved.spawn_protection_remaining_ms);
+            entity.speed_boost_until = saved.speed_boost_until;
+            entity.rapid_fire_until = saved.rapid_fire_until;
+            entity.target_x = saved.position.0;
+            entity.target_y = saved.position.1;
+            if let Some(behavior) = &saved.behavior {
+                let driver = self.new_ai_driver(behavior, saved.difficulty.unwrap_or_default());
+                self.ai_drivers.insert(entity.id, driver);
+            }
+            self.push_entity(entity);
+        }
+
+        for saved in snapshot.bullets {
+            let position = vector![saved.position.0, saved.position.1];
+            let velocity = vector![saved.velocity.0, saved.velocity.1];
+            let (shooter, shooter_id) = match saved.shooter.and_then(|id| self.entities.iter_mut().find(|e| e.id == id)) {
+                Some(entity) => {
+                    entity.live_bullets += 1;
+                    (entity.handle, Some(entity.id))
                 }
+                None => (RigidBodyHandle::invalid(), None),
+            };
+            let rigid_body = Bullet::rigid_body(position, velocity, self.game_config.bullet_ccd, shooter_id);
+            let collider = Bullet::collider(self.game_config.bullet_radius, saved.bounces);
+            let handle = self.physics_engine.bodies.insert(rigid_body);
+            self.physics_engine.colliders.insert_with_parent(collider, handle, &mut self.physics_engine.bodies);
+
+            let expires_at = self.tick + self.seconds_to_ticks(saved.remaining_lifetime_ms as f32 / 1000.0);
+            let fired_at = self.tick.saturating_sub(self.seconds_to_ticks(saved.age_ms as f32 / 1000.0));
+            self.bullets.push(Bullet {
+                handle,
+                shooter,
+                shooter_id,
+                fired_at,
+                expires_at,
+                bounces: saved.bounces,
+                bounces_remaining: saved.bounces_remaining,
+                kind: saved.kind,
+                traveled: saved.traveled,
+                last_position: position,
+            });
+        }
+        self.sync_entities();
+        self.physics_engine.refresh_queries();
+    }
+
+    /// Saves the state of the match to a JSON file.
+    ///
+    /// # Parameters
+    /// - `path`: The file to write.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(&self.save_state())?)
+    }
+
+    /// Restores the state of the match from a JSON file written by `save_to_file`.
+    ///
+    /// # Parameters
+    /// - `path`: The file to read.
+    pub fn load_from_file(&mut self, path: &Path) -> io::Result<()> {
+        let snapshot: GameSnapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if snapshot.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported snapshot format version {}", snapshot.format_version),
+            ));
+        }
+        snapshot.arena.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.load_state(snapshot);
+        Ok(())
+    }
+
+    /// Adds a new AI-controlled entity to the game.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the AI entity.
+    /// - `behavior`: The name of the behavior driving it, registered in `behavior_registry`.
+    ///   `None` selects the default behavior, see `set_default_behavior`.
+    /// - `difficulty`: How well it plays, `AiDifficulty::Normal` unless asked otherwise.
+    pub fn add_ai(&mut self, name: String, behavior: Option<&str>, difficulty: AiDifficulty) -> u32 {
+        let id = self.next_entity_id();
+        let now = self.sim_time();
+        let name = self.names.free_name(&name, id);
+        let entity = Entity::new(id, name, &mut self.physics_engine, &self.game_config, &self.arena, &mut self.rng, now);
+        self.register_ai(entity, behavior, difficulty)
+    }
+
+    /// Adds a new AI entity driven by a script file, see `AiScript::parse`.
+    ///
+    /// A script failing at runtime, on an unknown waypoint for instance, stops its entity
+    /// with a warning; the other entities play on.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the AI entity.
+    /// - `path`: The script file.
+    ///
+    /// # Returns
+    /// The id of the entity, or why the script cannot be read, with the line of a parse error.
+    pub fn add_ai_scripted(&mut self, name: String, path: &Path) -> Result<u32, String> {
+        let script = Arc::new(AiScript::load(path)?);
+        let id = self.next_entity_id();
+        let now = self.sim_time();
+        let name = self.names.free_name(&name, id);
+        let entity = Entity::new(id, name, &mut self.physics_engine, &self.game_config, &self.arena, &mut self.rng, now);
+        let behavior = format!("script:{}", path.file_stem().unwrap_or_default().to_string_lossy());
+        let driver = AiDriver {
+            name: behavior,
+            behavior: Box::new(ScriptedBehavior::new(script)),
+            difficulty: AiDifficulty::Normal,
+            sighted_ticks: 0,
+            aim: None,
+            failed: false,
+        };
+        Ok(self.admit_ai(entity, driver))
+    }
+
+    /// Hands a new entity over to an AI behavior and adds it to the game.
+    ///
+    /// # Parameters
+    /// - `entity`: The new entity.
+    /// - `behavior`: The name of the behavior driving it, `None` for the default behavior.
+    /// - `difficulty`: How well it plays.
+    ///
+    /// # Returns
+    /// The id of the entity.
+    fn register_ai(&mut self, entity: Entity, behavior: Option<&str>, difficulty: AiDifficulty) -> u32 {
+        let behavior = behavior.unwrap_or(&self.default_behavior).to_string();
+        let driver = self.new_ai_driver(&behavior, difficulty);
+        if driver.name != behavior {
+            self.report(format!("[WARNING] Unknown AI behavior {} for {}, using {}.", behavior, entity.name, driver.name), MessageType::Warning);
+        }
+        self.admit_ai(entity, driver)
+    }
+
+    /// Adds a new AI entity to the game, driven by its driver.
+    ///
+    /// # Returns
+    /// The id of the entity.
+    fn admit_ai(&mut self, mut entity: Entity, driver: AiDriver) -> u32 {
+        let id = entity.id;
+        entity.is_ai = true;
+        entity.fire_cooldown = entity.fire_cooldown.mul_f32(driver.difficulty.params().fire_cooldown_factor);
+        self.ai_drivers.insert(id, driver);
+        self.notify_spawn(&entity);
+        self.push_entity(entity);
+        id
+    }
+
+    /// Builds what drives an AI entity.
+    ///
+    /// # Parameters
+    /// - `behavior`: The name of the behavior. An unknown name falls back to the default behavior.
+    /// - `difficulty`: How well the entity plays.
+    fn new_ai_driver(&self, behavior: &str, difficulty: AiDifficulty) -> AiDriver {
+        let (name, behavior) = match self.behavior_registry.create(behavior) {
+            Some(instance) => (self.behavior_registry.resolve(behavior).unwrap_or(behavior).to_string(), instance),
+            None => match self.behavior_registry.create(&self.default_behavior) {
+                Some(instance) => (self.default_behavior.clone(), instance),
+                None => (WANDERER.to_string(), Box::new(ai_behavior::Wanderer::new()) as Box<dyn AiBehavior>),
+            },
+        };
+        AiDriver { name, behavior, difficulty, sighted_ticks: 0, aim: None, failed: false }
+    }
+
+    /// Updates AI entities in the game.
+    ///
+    /// Each AI entity is driven by its own behavior instance, which sets its actuators as a
+    /// client would; the next step applies them. An entity without a behavior, restored from
+    /// a snapshot or added with an unknown one, gets the default behavior.
+    pub fn update_ai(&mut self) {
+        if self.is_paused() || self.match_state.phase != MatchPhase::Running {
+            return;
+        }
+        let started = self.phase_started();
+        let now = self.sim_time();
+
+        let positions: Vec<(u32, bool, Vector<f32>)> = self.entities.iter()
+            .map(|entity| (entity.id, entity.is_ai, vector![entity.x, entity.y]))
+            .collect();
+        let obstacles = self.obstacle_views();
+        let fire_range = self.game_config.bot_fire_range;
+        let aim_error = self.game_config.bot_aim_error_deg.to_radians();
+
+        for index in 0..self.entities.len() {
+            // Une IA conduite au clavier depuis l'UI garde ses actionneurs
+            if !self.entities[index].is_ai || self.entities[index].keyboard_controlled {
+                continue;
+            }
+            let (id, handle) = (self.entities[index].id, self.entities[index].handle);
+            if self.ai_drivers.get(&id).is_some_and(|driver| driver.failed) {
+                continue;
+            }
+            let body = &self.physics_engine.bodies[handle];
+            let position = vector![self.entities[index].x, self.entities[index].y];
+            let rotation = self.entities[index].self_orientation as f32;
+            let target = GameLogic::bot_target(id, position, &positions);
+            let (target_id, target) = (target.map(|(id, _)| id), target.map(|(_, position)| position));
+            // La ligne de vue se calcule avant d'emprunter l'entité
+            let visible = |other: Vector<f32>| (other - position).norm() <= fire_range && self.line_of_sight(position, other, &[]);
+            let view = AiView {
+                position,
+                rotation,
+                health: self.entities[index].health,
+                gun_angle: GameLogic::gun_angle(&self.entities[index], body),
+                target,
+                target_id,
+                target_in_sight: target.is_some_and(visible),
+                enemies: positions.iter()
+                    .filter(|(other, _, _)| *other != id)
+                    .map(|(_, _, other)| *other)
+                    .filter(|other| visible(*other))
+                    .collect(),
+                obstacles: obstacles.clone(),
+                arena: self.arena,
+                clearance: GameLogic::clearance(&self.physics_engine, &self.game_config, position, rotation),
+                now,
+            };
+
+            if !self.ai_drivers.contains_key(&id) {
+                let driver = self.new_ai_driver(&self.default_behavior, AiDifficulty::Normal);
+                self.ai_drivers.insert(id, driver);
+            }
+            let driver = self.ai_drivers.get_mut(&id).unwrap();
+            let command = driver.behavior.decide(&view, &mut self.rng);
+            if let Some(reason) = driver.behavior.failure() {
+                // Le comportement en échec lâche son entité, arrêtée, sans interrompre le pas
+                let entity = &mut self.entities[index];
+                let warning = format!("[WARNING] AI {} stopped, its behavior {} failed: {}", entity.name, driver.name, reason);
+                driver.failed = true;
+                driver.aim = None;
+                entity.motor_left = 0.5;
+                entity.motor_right = 0.5;
+                entity.gun_mut().trigger = 0.0;
+                self.report(warning, MessageType::Warning);
+                continue;
+            }
+            let params = driver.difficulty.params();
+            driver.sighted_ticks = if view.enemies.is_empty() { 0 } else { driver.sighted_ticks.saturating_add(1) };
+            // Le bot ne réagit qu'après avoir vu un ennemi assez longtemps
+            let gun_trigger = if driver.sighted_ticks > params.reaction_ticks { command.gun_trigger } else { 0.0 };
+
+            let entity = &mut self.entities[index];
+            entity.motor_left = (0.5 + (command.motor_left - 0.5) * params.speed_factor).clamp(0.0, 1.0);
+            entity.motor_right = (0.5 + (command.motor_right - 0.5) * params.speed_factor).clamp(0.0, 1.0);
+            entity.gun_mut().trigger = gun_trigger;
+            // Sans erreur de visée, aucun tirage : les parties enregistrées restent identiques
+            let aim_error = aim_error * params.aim_error_factor;
+            let error = if gun_trigger > 0.5 && aim_error > 0.0 {
+                GameLogic::gaussian(&mut self.rng) * aim_error / std::f32::consts::TAU
+            } else {
+                0.0
+            };
+            entity.gun_mut().traverse = (command.gun_traverse + error).rem_euclid(1.0);
+            // La visée voulue, sans l'erreur : ce que la vue de débogage montre
+            driver.aim = command.enemy.map(|enemy| (enemy, GameLogic::barrel_angle(rotation, command.gun_traverse)));
+            if let Some(destination) = command.destination {
+                entity.target_x = destination.x;
+                entity.target_y = destination.y;
             }
         }
+        self.phase_ended(StepPhase::Ai, started);
+    }
+}
+
+impl Default for GameLogic {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::app_defines::AppDefines;
+
+/// Why an entity cannot take a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    /// The entity does not exist.
+    NoEntity,
+    /// The name is empty or longer than `NAME_MAX_LEN` characters.
+    Length,
+    /// The name holds a separator of the protocol or a character that cannot be printed.
+    Character,
+    /// Another entity has the name.
+    Taken,
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::NoEntity => write!(f, "Entity not found"),
+            NameError::Length => write!(f, "A name has 1 to {} characters", AppDefines::NAME_MAX_LEN),
+            NameError::Character => write!(f, "A name has no separator and only printable characters"),
+            NameError::Taken => write!(f, "Name already taken"),
+        }
+    }
+}
+
+/// The names of the entities in the arena, each given to one entity only.
+#[derive(Debug, Default)]
+pub struct NameIndex {
+    /// The id of the entity holding each name.
+    ids: HashMap<String, u32>,
+}
+
+impl NameIndex {
+    /// Checks that a name can be given to an entity: 1 to `NAME_MAX_LEN` printable characters,
+    /// without the separators of the protocol.
+    ///
+    /// # Parameters
+    /// - `name`: The name asked for.
+    pub fn validate(name: &str) -> Result<(), NameError> {
+        let length = name.chars().count();
+        if length == 0 || length > AppDefines::NAME_MAX_LEN {
+            return Err(NameError::Length);
+        }
+        let separators = [AppDefines::COMMAND_SEP, AppDefines::ARGUMENT_SEP, AppDefines::FIELD_SEP];
+        if name.chars().any(char::is_control) || separators.iter().any(|separator| name.contains(separator)) {
+            return Err(NameError::Character);
+        }
+        Ok(())
+    }
+
+    /// Returns the id of the entity holding a name.
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    /// Returns whether a name is free for an entity: not held, or held by the entity itself.
+    pub fn is_free_for(&self, name: &str, entity_id: u32) -> bool {
+        !matches!(self.get(name), Some(holder) if holder != entity_id)
+    }
+
+    /// Returns a name free for an entity: the name itself, or the name followed by the first free
+    /// `-2`, `-3`… suffix, shortened so that the whole fits in `NAME_MAX_LEN` characters.
+    ///
+    /// # Parameters
+    /// - `name`: The name asked for.
+    /// - `entity_id`: The entity taking the name.
+    pub fn free_name(&self, name: &str, entity_id: u32) -> String {
+        if self.is_free_for(name, entity_id) {
+            return name.to_string();
+        }
+        (2u32..)
+            .map(|number| {
+                let suffix = format!("-{}", number);
+                let base: String = name.chars().take(AppDefines::NAME_MAX_LEN.saturating_sub(suffix.len())).collect();
+                base + &suffix
+            })
+            .find(|candidate| self.is_free_for(candidate, entity_id))
+            .unwrap_or_default()
+    }
+
+    /// Gives a name to an entity. The name must be free for it, see `free_name`.
+    pub fn insert(&mut self, name: &str, entity_id: u32) {
+        self.ids.insert(name.to_string(), entity_id);
+    }
+
+    /// Frees the name of an entity, if the entity holds it.
+    pub fn remove(&mut self, name: &str, entity_id: u32) {
+        if self.get(name) == Some(entity_id) {
+            self.ids.remove(name);
+        }
+    }
+
+    /// Frees every name.
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game_logic::control_point::Side;
+use crate::game_logic::wave_director::WaveState;
+
+/// An event of the arena, reported to the game observers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// An entity entered the arena.
+    Spawn { id: u32, name: String, is_ai: bool, x: f32, y: f32 },
+    /// An entity fired a bullet.
+    BulletFired { shooter: u32, x: f32, y: f32, angle: f32 },
+    /// A bullet damaged an entity, or a mine or a ram did.
+    Hit {
+        shooter: u32,
+        victim: u32,
+        damage: i32,
+        x: f32,
+        y: f32,
+        /// Where the bullet came from, see `HitRecord::bearing`; `None` for a mine or a ram.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bearing: Option<f32>,
+    },
+    /// An entity was destroyed. An entity destroyed by its own mine or by the zone is its own shooter.
+    Kill { shooter: u32, victim: u32, x: f32, y: f32 },
+    /// A mine exploded, damaging the entities around it with `Hit` events.
+    Detonation { owner: u32, x: f32, y: f32 },
+    /// An entity left the arena, destroyed or disconnected.
+    Despawn { id: u32 },
+    /// An entity got through the walls and was brought back inside the arena, at `x`, `y`.
+    OutOfBounds { id: u32, x: f32, y: f32 },
+    /// An entity picked up a power-up, named as in `PowerUpKind::name`.
+    PickUp { id: u32, power_up: String, x: f32, y: f32 },
+    /// A game rule was changed while the game was running.
+    RuleChange { rule: String, value: String },
+    /// A round of the match started, on a new arena or after a reset.
+    RoundStart { round: u32 },
+    /// The round ended.
+    RoundEnd,
+    /// A round of the match was won, or ended without winner.
+    MatchEnd { round: u32, reason: String, winners: Vec<String>, score: Option<i32> },
+    /// An entity lost points to a rule of the match.
+    Penalty { id: u32, points: i32, reason: PenaltyReason },
+    /// An entity earned points from a rule of the match, on top of those of its hits and kills.
+    Bonus { id: u32, points: i32, reason: BonusReason },
+    /// An entity stayed in the same spot long enough to camp, at `x`, `y`.
+    Camping { id: u32, x: f32, y: f32 },
+    /// A side captured a control point, named as in the map file.
+    Capture { point: String, owner: Side },
+    /// A horde wave started, was cleared or was lost, with the announcement of the director.
+    Wave { wave: u32, state: WaveState, text: String },
+}
+
+/// Why an entity lost points in a `Penalty` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyReason {
+    /// The entity hit itself, with a bullet after a bounce or its own mine.
+    SelfHit,
+    /// The entity camps, see `CampWatch`.
+    Camping,
+    /// The score of the entity decays with the simulated time, see `ScoreRules::decay`.
+    Decay,
+}
+
+impl PenaltyReason {
+    /// Returns what the entity did, to complete "loses N points for…".
+    pub fn text(&self) -> &'static str {
+        match self {
+            PenaltyReason::SelfHit => "hitting itself",
+            PenaltyReason::Camping => "camping",
+            PenaltyReason::Decay => "score decay",
+        }
+    }
+}
+
+/// Why an entity earned points in a `Bonus` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BonusReason {
+    /// The entity destroyed a higher-scored opponent, see `ScoreRules::comeback_bonus`.
+    Comeback,
+}
+
+impl BonusReason {
+    /// Returns what the entity did, to complete "earns N points for…".
+    pub fn text(&self) -> &'static str {
+        match self {
+            BonusReason::Comeback => "a comeback kill",
+        }
+    }
+}
+
+impl GameEvent {
+    /// Returns the name of the kind of event, as in the `type` field of its JSON form.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameEvent::Spawn { .. } => "spawn",
+            GameEvent::BulletFired { .. } => "bullet_fired",
+            GameEvent::Hit { .. } => "hit",
+            GameEvent::Kill { .. } => "kill",
+            GameEvent::Detonation { .. } => "detonation",
+            GameEvent::Despawn { .. } => "despawn",
+            GameEvent::OutOfBounds { .. } => "out_of_bounds",
+            GameEvent::PickUp { .. } => "pick_up",
+            GameEvent::RuleChange { .. } => "rule_change",
+            GameEvent::RoundStart { .. } => "round_start",
+            GameEvent::RoundEnd => "round_end",
+            GameEvent::MatchEnd { .. } => "match_end",
+            GameEvent::Penalty { .. } => "penalty",
+            GameEvent::Bonus { .. } => "bonus",
+            GameEvent::Camping { .. } => "camping",
+            GameEvent::Capture { .. } => "capture",
+            GameEvent::Wave { .. } => "wave",
+        }
+    }
+}
+
+/// Receives the events of the arena as they happen.
+pub trait GameObserver: Send {
+    /// Called for every event of the arena.
+    ///
+    /// # Parameters
+    /// - `step`: The simulation step at which the event happened.
+    /// - `event`: The event.
+    fn on_event(&mut self, step: u64, event: &GameEvent);
+
+    /// Called once when the server stops, to write what is still kept in memory.
+    fn on_shutdown(&mut self) {}
+}
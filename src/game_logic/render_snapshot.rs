@@ -0,0 +1,276 @@
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rapier2d::prelude::RigidBodyHandle;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::ai_behavior::AiDifficulty;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::event_queue::EventQueue;
+use crate::game_logic::game_config::GameConfig;
+use crate::game_logic::ghost_camera::GhostCamera;
+use crate::game_logic::match_state::MatchPhase;
+use crate::game_logic::replay::ReplayFrame;
+use crate::game_logic::scoring::{ScoreBreakdown, ScoreRules};
+use crate::game_logic::step_timings::StepPhase;
+use crate::game_logic::trail_store::TrailStore;
+use crate::power_ups::PowerUpKind;
+
+/// What an AI entity is heading for and shooting at, drawn by the AI debug view of the game UI.
+#[derive(Debug, Clone, Copy)]
+pub struct AiIntent {
+    /// The id of the AI entity.
+    pub id: u32,
+    /// The position it is heading for, see `Entity::target_x`.
+    pub destination: (f32, f32),
+    /// The entity its behavior chose to shoot at and the direction it aimed in, in radians,
+    /// without the aim error; `None` unless the behavior picks an entity, see `AiCommand::enemy`.
+    pub aim: Option<(u32, f32)>,
+}
+
+/// A power-up waiting to be picked up, as the UI draws it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerUpFrame {
+    pub kind: PowerUpKind,
+    pub position: (f64, f64),
+}
+
+/// A mine, as the debug view of the UI draws it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MineFrame {
+    pub position: (f64, f64),
+    /// The color of the entity that laid it, `None` once that entity left the game.
+    pub color: Option<[u8; 3]>,
+    pub armed: bool,
+}
+
+/// The live state of the entity shown in the inspector of the UI, see `GameLogic::inspected`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedEntity {
+    pub id: u32,
+    pub name: String,
+    pub color: [u8; 3],
+    pub team: Option<u8>,
+    pub health: i32,
+    pub armor: f32,
+    pub score: i32,
+    pub breakdown: ScoreBreakdown,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    /// The rotation of the body, in radians.
+    pub orientation: f32,
+    /// The angle the first gun points at, in radians.
+    pub gun_angle: f32,
+    pub motor_left: f32,
+    pub motor_right: f32,
+    pub trigger: f32,
+    pub traverse: f32,
+    pub shield: f32,
+    pub energy: f32,
+    pub fire_energy: f32,
+    /// The time before the entity can fire again.
+    pub fire_cooldown: Duration,
+    /// The behavior and the difficulty of the AI driving the entity, `None` for a player.
+    pub ai_driver: Option<(String, AiDifficulty)>,
+}
+
+/// What the menus of the UI show of the game and let the user change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameControls {
+    pub paused: bool,
+    /// Whether a replay is being recorded, the speed is then fixed.
+    pub recording: bool,
+    pub time_scale: f32,
+    /// The seed of the map, `None` for a map loaded from a file.
+    pub map_seed: Option<u64>,
+    /// The name of the map loaded from a file.
+    pub map_name: Option<String>,
+    pub score_rules: ScoreRules,
+    pub game_config: GameConfig,
+    pub profiling_enabled: bool,
+    /// The average and the worst duration of each phase of the last steps, see `StepTimings`.
+    pub step_timings: Vec<(StepPhase, Duration, Duration)>,
+    /// The behavior given to the AI entities added without one.
+    pub default_behavior: String,
+    /// The names of the AI behaviors selectable.
+    pub behaviors: Vec<String>,
+    pub horde_active: bool,
+    /// The wave of the horde mode and what is left of it, `None` outside of the horde mode.
+    pub horde_status: Option<String>,
+    /// The number of steps per second the game loop achieved over the last second.
+    pub achieved_tick_rate: f32,
+    pub round: u32,
+    pub phase: MatchPhase,
+    /// The result of the last round.
+    pub last_result: Option<String>,
+    /// The time left in the current phase of the round, `None` without a limit.
+    pub round_remaining: Option<Duration>,
+}
+
+impl Default for GameControls {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            recording: false,
+            time_scale: 1.0,
+            map_seed: None,
+            map_name: None,
+            score_rules: ScoreRules::default(),
+            game_config: GameConfig::default(),
+            profiling_enabled: false,
+            step_timings: Vec::new(),
+            default_behavior: String::new(),
+            behaviors: Vec::new(),
+            horde_active: false,
+            horde_status: None,
+            achieved_tick_rate: 0.0,
+            round: 1,
+            phase: MatchPhase::Running,
+            last_result: None,
+            round_remaining: None,
+        }
+    }
+}
+
+/// What the UI draws of the live game, published by the game loop after each step.
+///
+/// The entities and bullets are kept at the two last steps, so that the UI draws them
+/// between the two at the fraction of the tick elapsed, see `interpolated`.
+#[derive(Debug, Clone)]
+pub struct RenderSnapshot {
+    /// The arena after the last step, where the interpolation ends.
+    pub frame: ReplayFrame,
+    /// For each entity of `frame`, its position and rotation after the previous step,
+    /// `None` when it must be drawn where it is: it just spawned or was teleported.
+    pub previous_entities: Vec<Option<(f32, f32, f32)>>,
+    /// For each bullet of `frame`, its position after the previous step, `None` when it was just fired.
+    pub previous_bullets: Vec<Option<(f32, f32)>>,
+    /// The body of each bullet of `frame`, to find them again in the next snapshot.
+    pub bullet_handles: Vec<RigidBodyHandle>,
+    /// The intentions of the AI entities, empty unless `GameLogic::ai_debug_enabled` is on.
+    pub ai_intents: Vec<AiIntent>,
+    /// The cameras of the spectators, drawn by the debug view of the game UI.
+    pub ghost_cameras: Vec<GhostCamera>,
+    /// The movement trails of the entities, shared with the previous snapshots while they do not change.
+    pub trails: Arc<TrailStore>,
+    pub arena: ArenaConfig,
+    /// The health of a fresh entity, see `GameConfig::starting_health`: a full health bar in the UI.
+    pub max_health: i32,
+    /// The distance a bullet flies before it disappears, without bouncing.
+    pub bullet_range: f32,
+    /// When the last step ran.
+    pub stepped_at: Instant,
+    /// The real time between two steps, zero when the simulation does not advance.
+    pub interval: Duration,
+    /// The simulated time of the last step, see `GameLogic::sim_time`.
+    pub sim_time: Duration,
+    /// The power-ups waiting to be picked up.
+    pub power_ups: Vec<PowerUpFrame>,
+    /// The mines laid, hidden from the players: drawn by a debug view only.
+    pub mines: Vec<MineFrame>,
+    /// The last arena events, for the kill feed, the score history and the hit effects.
+    pub events: Arc<EventQueue>,
+    /// The entity the inspector shows, `None` when none is selected or it left the game.
+    pub inspected: Option<InspectedEntity>,
+    /// What the menus show.
+    pub controls: GameControls,
+}
+
+impl RenderSnapshot {
+    /// Returns how far the time is between the last step and the next one.
+    ///
+    /// # Parameters
+    /// - `now`: The time of the rendering.
+    ///
+    /// # Returns
+    /// The fraction of the tick elapsed, between 0 and 1.
+    pub fn alpha(&self, now: Instant) -> f32 {
+        if self.interval.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_duration_since(self.stepped_at);
+        (elapsed.as_secs_f32() / self.interval.as_secs_f32()).min(1.0)
+    }
+
+    /// Returns the frame to draw at some point between the two last steps.
+    ///
+    /// The entities and bullets without a previous position are drawn where they are.
+    ///
+    /// # Parameters
+    /// - `alpha`: The fraction of the tick elapsed, see `alpha`. 0 draws the previous step, 1 the last one.
+    pub fn interpolated(&self, alpha: f32) -> ReplayFrame {
+        let mut frame = self.frame.clone();
+        for (entity, previous) in frame.entities.iter_mut().zip(&self.previous_entities) {
+            if let Some((x, y, rotation)) = *previous {
+                entity.x = lerp(x, entity.x, alpha);
+                entity.y = lerp(y, entity.y, alpha);
+                entity.rotation = lerp_angle(rotation, entity.rotation, alpha);
+            }
+        }
+        for (bullet, previous) in frame.bullets.iter_mut().zip(&self.previous_bullets) {
+            if let Some((x, y)) = *previous {
+                *bullet = (lerp(x, bullet.0, alpha), lerp(y, bullet.1, alpha));
+            }
+        }
+        frame
+    }
+}
+
+impl Default for RenderSnapshot {
+    fn default() -> Self {
+        Self {
+            frame: ReplayFrame::default(),
+            previous_entities: Vec::new(),
+            previous_bullets: Vec::new(),
+            bullet_handles: Vec::new(),
+            ai_intents: Vec::new(),
+            ghost_cameras: Vec::new(),
+            trails: Arc::default(),
+            arena: ArenaConfig::default(),
+            max_health: AppDefines::DEFAULT_STARTING_HEALTH,
+            bullet_range: AppDefines::DEFAULT_BULLET_SPEED * AppDefines::DEFAULT_BULLET_LIFETIME_MS as f32 / 1000.0,
+            stepped_at: Instant::now(),
+            interval: Duration::ZERO,
+            sim_time: Duration::ZERO,
+            power_ups: Vec::new(),
+            mines: Vec::new(),
+            events: Arc::default(),
+            inspected: None,
+            controls: GameControls::default(),
+        }
+    }
+}
+
+/// The place the game loop puts the last `RenderSnapshot` in and the UI takes it from.
+///
+/// Only the `Arc` is swapped under the lock, the UI draws without holding the game logic.
+#[derive(Debug, Clone, Default)]
+pub struct RenderSlot(Arc<Mutex<Arc<RenderSnapshot>>>);
+
+impl RenderSlot {
+    /// Replaces the snapshot.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The snapshot of the last step.
+    pub fn publish(&self, snapshot: RenderSnapshot) {
+        *self.0.lock().unwrap() = Arc::new(snapshot);
+    }
+
+    /// Returns the last snapshot published.
+    pub fn latest(&self) -> Arc<RenderSnapshot> {
+        Arc::clone(&self.0.lock().unwrap())
+    }
+}
+
+fn lerp(from: f32, to: f32, alpha: f32) -> f32 {
+    from + (to - from) * alpha
+}
+
+/// Interpolates between two angles, in radians, the short way around.
+fn lerp_angle(from: f32, to: f32, alpha: f32) -> f32 {
+    let delta = (to - from + PI).rem_euclid(2.0 * PI) - PI;
+    from + delta * alpha
+}
@@ -0,0 +1,330 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bullet::bullet::BulletKind;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::control_point::Side;
+use crate::game_logic::map_file::MapHazard;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::scoring::ScoreBreakdown;
+use crate::game_logic::timeline::MatchHeader;
+use crate::game_logic::trail_store::TrailStore;
+use crate::game_logic::GameLogic;
+use crate::obstacles::ObstacleShape;
+
+/// The state of an entity in a replay frame, everything needed to draw it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntityFrame {
+    pub id: u32,
+    pub name: String,
+    /// The color of the entity, as red, green and blue.
+    pub color: [u8; 3],
+    pub x: f32,
+    pub y: f32,
+    /// The rotation of the body, in radians.
+    pub rotation: f32,
+    /// The applied traverse of the first gun relative to the body, in turns, see `Turret::orientation`.
+    #[serde(default)]
+    pub gun_orientation: f32,
+    /// The turrets of an entity with several of them or with its gun off the center of the body.
+    /// Empty for an entity with a single gun at the center, given by `gun_orientation`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub turrets: Vec<TurretFrame>,
+    pub health: i32,
+    pub score: i32,
+    #[serde(default)]
+    pub breakdown: ScoreBreakdown,
+    pub team: Option<u8>,
+    /// Whether the shield of the entity is up.
+    pub shield: bool,
+    /// Whether the entity is protected after spawning.
+    #[serde(default)]
+    pub protected: bool,
+    /// Whether the entity is driven by the AI.
+    #[serde(default)]
+    pub is_ai: bool,
+    /// The share of the fire energy left, `None` while the shots cost no energy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fire_energy: Option<f32>,
+}
+
+impl EntityFrame {
+    /// Returns the angle the first gun of the entity points at, in radians, see `GameLogic::barrel_angle`.
+    pub fn gun_angle(&self) -> f32 {
+        GameLogic::barrel_angle(self.rotation, self.gun_orientation)
+    }
+
+    /// Returns where each gun of the entity stands and the angle it points at, in radians.
+    pub fn barrels(&self) -> Vec<((f32, f32), f32)> {
+        if self.turrets.is_empty() {
+            return vec![((self.x, self.y), self.gun_angle())];
+        }
+        let (sin, cos) = self.rotation.sin_cos();
+        self.turrets.iter().map(|turret| {
+            let (x, y) = turret.mount;
+            let position = (self.x + x * cos - y * sin, self.y + x * sin + y * cos);
+            (position, GameLogic::barrel_angle(self.rotation, turret.orientation))
+        }).collect()
+    }
+}
+
+/// A turret of an entity in a replay frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TurretFrame {
+    /// The position of the turret relative to the center of the body, in the frame of the body.
+    pub mount: (f32, f32),
+    /// The applied traverse of the gun relative to the body, in turns.
+    pub orientation: f32,
+}
+
+/// An obstacle in a replay frame.
+///
+/// The replays recorded before obstacles had a shape store `[x, y]` pairs, read as squares.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObstacleFrame {
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub shape: ObstacleShape,
+}
+
+/// The state of a control point in a replay frame, see `ControlPoint`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlPointFrame {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub owner: Option<Side>,
+    /// How far the owner captured the point, from 0 to 1.
+    pub progress: f32,
+}
+
+/// The state of the arena after a simulation step.
+///
+/// Entities joining or leaving the match simply appear in or disappear from
+/// the frames. The obstacles are only stored when they changed since the previous frame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// The simulation step of the frame.
+    pub tick: u64,
+    pub entities: Vec<EntityFrame>,
+    /// The positions of the bullets.
+    pub bullets: Vec<(f32, f32)>,
+    /// The kind of each bullet, in the order of `bullets`; empty when they are all standard bullets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bullet_kinds: Vec<BulletKind>,
+    /// The obstacles, `None` if they did not change since the previous frame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obstacles: Option<Vec<ObstacleFrame>>,
+    /// The safe zone, as its center and radius, `None` when there is none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<(f32, f32, f32)>,
+    /// The control points, empty outside of the control point mode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub control_points: Vec<ControlPointFrame>,
+    /// The hazards of the map, as the map file writes them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hazards: Vec<MapHazard>,
+    /// The events that happened since the previous frame.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<GameEvent>,
+    /// The movement trails, stored whole in the first frame and when they were cleared,
+    /// `None` in the other frames: the trails follow their entities from there, see `TrailStore::follow`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trails: Option<TrailStore>,
+}
+
+/// Records the frames of a match in memory, see `GameLogic::start_recording`.
+pub struct Recorder {
+    header: MatchHeader,
+    frames: Vec<ReplayFrame>,
+    /// The obstacles of the last frame storing them.
+    last_obstacles: Option<Vec<ObstacleFrame>>,
+    /// The number of the last game event added to a frame.
+    pub(crate) event_cursor: u64,
+    /// The clear count of the trails stored whole last, `None` before the first frame, see `TrailStore::clear_count`.
+    pub(crate) trail_clears: Option<u64>,
+}
+
+impl Recorder {
+    /// Creates an empty recording.
+    ///
+    /// # Parameters
+    /// - `event_cursor`: The number of the last game event before the recording starts.
+    /// - `arena`: The size of the arena, written in the header.
+    /// - `steps_per_second`: The tick rate of the simulation, written in the header.
+    pub fn new(event_cursor: u64, arena: &ArenaConfig, steps_per_second: f32) -> Self {
+        Self { header: MatchHeader::new(arena, steps_per_second), frames: Vec::new(), last_obstacles: None, event_cursor, trail_clears: None }
+    }
+
+    /// Adds a frame, dropping its obstacles if they did not change.
+    pub fn record(&mut self, mut frame: ReplayFrame) {
+        if frame.obstacles.is_some() && frame.obstacles == self.last_obstacles {
+            frame.obstacles = None;
+        } else if frame.obstacles.is_some() {
+            self.last_obstacles = frame.obstacles.clone();
+        }
+        self.frames.push(frame);
+    }
+
+    /// Returns the number of frames recorded.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Writes the recording as JSON lines: the match header, then one frame per line.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the replay file, overwritten if it exists.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", serde_json::to_string(&self.header)?)?;
+        for frame in &self.frames {
+            writeln!(writer, "{}", serde_json::to_string(frame)?)?;
+        }
+        writer.flush()
+    }
+}
+
+/// A recorded match, read back from a replay file.
+pub struct Replay {
+    pub header: MatchHeader,
+    pub frames: Vec<ReplayFrame>,
+    /// For each frame, the index of the last frame at or before it storing the obstacles.
+    obstacle_frames: Vec<Option<usize>>,
+    /// For each frame, the index of the last frame at or before it storing the trails whole.
+    trail_frames: Vec<Option<usize>>,
+}
+
+impl Replay {
+    /// Reads a replay file written by `Recorder::save`.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the replay file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header_line = lines
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty replay"))??;
+        let header: MatchHeader = serde_json::from_str(&header_line)?;
+
+        let mut frames = Vec::new();
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            frames.push(serde_json::from_str::<ReplayFrame>(&line)?);
+        }
+
+        let obstacle_frames = Replay::last_storing(&frames, |frame| frame.obstacles.is_some());
+        let trail_frames = Replay::last_storing(&frames, |frame| frame.trails.is_some());
+        Ok(Self { header, frames, obstacle_frames, trail_frames })
+    }
+
+    /// Returns, for each frame, the index of the last frame at or before it storing something.
+    ///
+    /// # Parameters
+    /// - `frames`: The frames of the replay.
+    /// - `stores`: Whether a frame stores it.
+    fn last_storing(frames: &[ReplayFrame], stores: impl Fn(&ReplayFrame) -> bool) -> Vec<Option<usize>> {
+        let mut last = None;
+        frames.iter().enumerate().map(|(index, frame)| {
+            if stores(frame) {
+                last = Some(index);
+            }
+            last
+        }).collect()
+    }
+
+    /// Returns the obstacles shown at a frame.
+    ///
+    /// # Parameters
+    /// - `index`: The index of the frame.
+    pub fn obstacles_at(&self, index: usize) -> &[ObstacleFrame] {
+        self.obstacle_frames
+            .get(index)
+            .copied()
+            .flatten()
+            .and_then(|stored| self.frames[stored].obstacles.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// Plays a replay back: the current frame, moved forward in time or by the scrub bar.
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    /// The index of the frame shown.
+    pub position: usize,
+    /// Whether the frames move forward on their own.
+    pub playing: bool,
+    /// The time not played yet, in frames.
+    accumulator: f32,
+    /// The movement trails at `trails_position`, see `trails`.
+    trails: TrailStore,
+    /// The frame `trails` were last brought to, `None` before the first call to `trails`.
+    trails_position: Option<usize>,
+}
+
+impl ReplayPlayer {
+    /// Creates a player paused on the first frame.
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, position: 0, playing: false, accumulator: 0.0, trails: TrailStore::default(), trails_position: None }
+    }
+
+    /// Moves forward by the frames recorded during some real time, when playing.
+    ///
+    /// Playback stops at the last frame.
+    ///
+    /// # Parameters
+    /// - `elapsed_secs`: The real time since the previous call, in seconds.
+    pub fn advance(&mut self, elapsed_secs: f32) {
+        if !self.playing {
+            return;
+        }
+        self.accumulator += elapsed_secs * self.replay.header.steps_per_second;
+        let frames = self.accumulator.floor();
+        self.accumulator -= frames;
+        self.position = (self.position + frames as usize).min(self.last_index());
+        if self.position == self.last_index() {
+            self.playing = false;
+        }
+    }
+
+    /// Returns the index of the last frame.
+    pub fn last_index(&self) -> usize {
+        self.replay.frames.len().saturating_sub(1)
+    }
+
+    /// Returns the frame shown, `None` for a replay without frames.
+    pub fn frame(&self) -> Option<&ReplayFrame> {
+        self.replay.frames.get(self.position)
+    }
+
+    /// Returns the movement trails at the frame shown.
+    ///
+    /// Moving forward follows the frames played since the previous call; moving back, or past
+    /// a frame storing the trails whole, starts again from the last such frame.
+    pub fn trails(&mut self) -> &TrailStore {
+        let keyframe = self.replay.trail_frames.get(self.position).copied().flatten();
+        let start = match self.trails_position {
+            Some(done) if done <= self.position && !keyframe.is_some_and(|keyframe| keyframe > done) => done + 1,
+            _ => {
+                self.trails = match keyframe {
+                    Some(keyframe) => self.replay.frames[keyframe].trails.clone().unwrap_or_default(),
+                    None => TrailStore::default(),
+                };
+                keyframe.map_or(0, |keyframe| keyframe + 1)
+            }
+        };
+        for frame in self.replay.frames.iter().take(self.position + 1).skip(start) {
+            self.trails.follow(frame);
+        }
+        self.trails_position = Some(self.position);
+        &self.trails
+    }
+}
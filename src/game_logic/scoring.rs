@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+
+/// What an entity did during the match, alongside its score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreBreakdown {
+    /// The bullets fired.
+    pub shots_fired: u32,
+    /// The bullets that hurt an opponent. Hits on the shooter itself or on a teammate are left out.
+    pub hits: u32,
+    /// The entities destroyed, the entity itself left out.
+    pub kills: u32,
+    /// The times the entity was destroyed.
+    pub deaths: u32,
+    /// The health the armor of the entity kept it from losing to bullets and ramming, see `Entity::mitigate`.
+    pub damage_mitigated: i32,
+    /// The horde waves cleared while the entity was alive, see `WaveDirector`.
+    pub waves_survived: u32,
+}
+
+impl ScoreBreakdown {
+    /// Returns the share of the bullets fired that hit an opponent, `None` before the first shot.
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.shots_fired > 0).then(|| self.hits as f32 / self.shots_fired as f32)
+    }
+
+    /// Adds the counts of another breakdown to this one.
+    pub fn add(&mut self, other: &ScoreBreakdown) {
+        self.shots_fired += other.shots_fired;
+        self.hits += other.hits;
+        self.kills += other.kills;
+        self.deaths += other.deaths;
+        self.damage_mitigated += other.damage_mitigated;
+        self.waves_survived += other.waves_survived;
+    }
+}
+
+/// Optional scoring rules limiting snowballing in long sessions.
+///
+/// Both rules are disabled by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreRules {
+    /// Whether scores decay over simulated time.
+    pub decay_enabled: bool,
+    /// The fraction of its score an entity loses every simulated minute.
+    pub decay_per_minute: f32,
+    /// Whether kills against higher-scored opponents award a bonus.
+    pub comeback_enabled: bool,
+    /// The fraction of the score gap awarded as bonus on such a kill.
+    pub comeback_ratio: f32,
+}
+
+impl Default for ScoreRules {
+    fn default() -> Self {
+        Self {
+            decay_enabled: false,
+            decay_per_minute: AppDefines::SCORE_DECAY_PER_MINUTE,
+            comeback_enabled: false,
+            comeback_ratio: AppDefines::COMEBACK_BONUS_RATIO,
+        }
+    }
+}
+
+impl ScoreRules {
+    /// Returns the points a score loses at one decay tick.
+    ///
+    /// A positive score always loses at least one point, a null or negative score never decays.
+    ///
+    /// # Parameters
+    /// - `score`: The current score.
+    pub fn decay(&self, score: i32) -> i32 {
+        if !self.decay_enabled || score <= 0 {
+            return 0;
+        }
+        ((score as f32 * self.decay_per_minute).round() as i32).max(1)
+    }
+
+    /// Returns the bonus awarded for destroying an opponent.
+    ///
+    /// # Parameters
+    /// - `shooter_score`: The score of the shooter before the kill.
+    /// - `victim_score`: The score of the destroyed entity.
+    pub fn comeback_bonus(&self, shooter_score: i32, victim_score: i32) -> i32 {
+        let gap = victim_score - shooter_score;
+        if !self.comeback_enabled || gap <= 0 {
+            return 0;
+        }
+        (gap as f32 * self.comeback_ratio).round() as i32
+    }
+}
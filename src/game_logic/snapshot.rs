@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bullet::bullet::BulletKind;
+use crate::game_logic::ai_behavior::AiDifficulty;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::game_config::GameConfig;
+use crate::game_logic::map_file::{MapControlPoint, MapHazard, SpawnPoint};
+use crate::game_logic::scoring::ScoreBreakdown;
+use crate::obstacles::ObstacleShape;
+
+/// The version of the snapshot file format.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// The state of an entity in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub id: u32,
+    pub name: String,
+    pub is_ai: bool,
+    /// The name of the behavior driving the entity, `None` for a player.
+    #[serde(default)]
+    pub behavior: Option<String>,
+    /// How well the entity plays, `None` for a player.
+    #[serde(default)]
+    pub difficulty: Option<AiDifficulty>,
+    /// The color of the entity, as red, green and blue.
+    pub color: [u8; 3],
+    pub health: i32,
+    pub score: i32,
+    #[serde(default)]
+    pub breakdown: ScoreBreakdown,
+    pub team: Option<u8>,
+    pub energy: f32,
+    /// The fire energy, `None` in the snapshots saved before the shots cost energy: the entity is restored full.
+    #[serde(default)]
+    pub fire_energy: Option<f32>,
+    /// The armor, `None` in the snapshots saved before the entities had armor: the entity gets the starting armor.
+    #[serde(default)]
+    pub armor: Option<f32>,
+    pub motor_left: f32,
+    pub motor_right: f32,
+    /// The trigger of the first turret.
+    pub gun_trigger: f32,
+    /// The commanded traverse of the first turret.
+    pub gun_traverse: f32,
+    pub shield: f32,
+    /// The applied traverse of the first turret.
+    pub gun_orientation: f64,
+    /// The delay between two shots of the entity, in milliseconds.
+    pub fire_cooldown_ms: u64,
+    /// How long the first turret still had to wait before firing again, in milliseconds.
+    pub fire_cooldown_remaining_ms: u64,
+    /// The bullets the entity fires.
+    #[serde(default)]
+    pub weapon: BulletKind,
+    /// The position of the first turret relative to the center of the body.
+    #[serde(default)]
+    pub gun_mount: (f32, f32),
+    /// The turrets after the first one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_turrets: Vec<TurretSnapshot>,
+    /// How long the entity was still protected after spawning, in milliseconds.
+    #[serde(default)]
+    pub spawn_protection_remaining_ms: u64,
+    pub speed_boost_until: Option<u64>,
+    pub rapid_fire_until: Option<u64>,
+    pub position: (f32, f32),
+    /// The rotation of the body, in radians.
+    pub rotation: f32,
+    pub linear_velocity: (f32, f32),
+    pub angular_velocity: f32,
+}
+
+/// The state of a turret after the first one in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurretSnapshot {
+    pub mount: (f32, f32),
+    pub trigger: f32,
+    pub traverse: f32,
+    pub orientation: f64,
+    /// How long the turret still had to wait before firing again, in milliseconds.
+    pub fire_cooldown_remaining_ms: u64,
+}
+
+/// The state of a bullet in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulletSnapshot {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    /// The id of the entity that fired the bullet, `None` if it left the arena since.
+    pub shooter: Option<u32>,
+    /// How long the bullet still had to fly, in milliseconds.
+    pub remaining_lifetime_ms: u64,
+    /// How long the bullet had been flying, in milliseconds.
+    #[serde(default)]
+    pub age_ms: u64,
+    /// The number of bounces the bullet was fired with.
+    #[serde(default)]
+    pub bounces: u8,
+    /// The number of times the bullet could still bounce.
+    #[serde(default)]
+    pub bounces_remaining: u8,
+    #[serde(default)]
+    pub kind: BulletKind,
+    /// The distance the bullet had traveled, see `Bullet::traveled`.
+    #[serde(default)]
+    pub traveled: f32,
+}
+
+/// The state of an obstacle in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObstacleSnapshot {
+    pub position: (f64, f64),
+    /// The shape of the obstacle, a square for the snapshots saved before obstacles had a shape.
+    #[serde(default)]
+    pub shape: ObstacleShape,
+}
+
+/// The state of a match, saved by `GameLogic::save_state` and restored by `GameLogic::load_state`.
+///
+/// The snapshot holds no rapier handle: bodies and colliders are inserted again on load.
+/// The power-ups, the mines, the horde mode and the inner state of the AI behaviors are not saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    /// The version of the file format.
+    pub format_version: u32,
+    /// The simulation step the snapshot was taken at.
+    pub tick: u64,
+    /// The size of the arena, the default one for the snapshots saved before it could change.
+    #[serde(default)]
+    pub arena: ArenaConfig,
+    /// The seed of the map, `None` if no map was generated.
+    pub map_seed: Option<u64>,
+    /// The name of the map loaded from a file, `None` for a generated map.
+    #[serde(default)]
+    pub map_name: Option<String>,
+    /// The spawn points of the map loaded from a file.
+    #[serde(default)]
+    pub spawn_points: Vec<SpawnPoint>,
+    /// The control points of the map loaded from a file.
+    #[serde(default)]
+    pub control_points: Vec<MapControlPoint>,
+    /// The hazards of the map loaded from a file.
+    #[serde(default)]
+    pub hazards: Vec<MapHazard>,
+    pub config: GameConfig,
+    pub entities: Vec<EntitySnapshot>,
+    pub bullets: Vec<BulletSnapshot>,
+    pub obstacles: Vec<ObstacleSnapshot>,
+}
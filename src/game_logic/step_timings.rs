@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+
+/// A part of a simulation step whose duration is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepPhase {
+    /// Applying the actuators of the entities, firing included.
+    Actuators,
+    /// The physics step.
+    Physics,
+    /// Handling the collisions, the sensors and the ramming.
+    Collisions,
+    /// Removing the bullets out of the arena or expired.
+    Culling,
+    /// Running the AI behaviors, before the step.
+    Ai,
+}
+
+impl StepPhase {
+    /// Every phase, in the order they run in.
+    pub const ALL: [StepPhase; 5] = [StepPhase::Actuators, StepPhase::Physics, StepPhase::Collisions, StepPhase::Culling, StepPhase::Ai];
+
+    /// Returns the name of the phase in the `TIMINGS` replies.
+    pub fn name(self) -> &'static str {
+        match self {
+            StepPhase::Actuators => "actuators",
+            StepPhase::Physics => "physics",
+            StepPhase::Collisions => "collisions",
+            StepPhase::Culling => "culling",
+            StepPhase::Ai => "ai",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The durations measured for one phase.
+#[derive(Debug, Clone, Default)]
+struct PhaseTimings {
+    /// The last durations, oldest first.
+    window: VecDeque<Duration>,
+    /// The number of durations measured since the start.
+    samples: u64,
+    /// The sum of the durations measured since the start.
+    total: Duration,
+}
+
+/// How long the phases of the last steps took, measured while `GameLogic::profiling_enabled` is on.
+///
+/// Each phase keeps its last `STEP_TIMINGS_WINDOW` durations for the average and the worst case,
+/// and counters only growing since the start.
+#[derive(Debug, Clone, Default)]
+pub struct StepTimings {
+    phases: [PhaseTimings; 5],
+}
+
+impl StepTimings {
+    /// Adds the duration of a phase, dropping the oldest one of the window if it is full.
+    ///
+    /// # Parameters
+    /// - `phase`: The phase measured.
+    /// - `duration`: How long it took.
+    pub fn record(&mut self, phase: StepPhase, duration: Duration) {
+        let timings = &mut self.phases[phase.index()];
+        if timings.window.len() >= AppDefines::STEP_TIMINGS_WINDOW {
+            timings.window.pop_front();
+        }
+        timings.window.push_back(duration);
+        timings.samples += 1;
+        timings.total += duration;
+    }
+
+    /// Returns the average duration of a phase over the window, zero before the first measure.
+    pub fn average(&self, phase: StepPhase) -> Duration {
+        let window = &self.phases[phase.index()].window;
+        if window.is_empty() {
+            return Duration::ZERO;
+        }
+        window.iter().sum::<Duration>() / window.len() as u32
+    }
+
+    /// Returns the longest duration of a phase over the window.
+    pub fn worst(&self, phase: StepPhase) -> Duration {
+        self.phases[phase.index()].window.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Returns the number of times a phase was measured since the start.
+    pub fn samples(&self, phase: StepPhase) -> u64 {
+        self.phases[phase.index()].samples
+    }
+
+    /// Returns the time spent in a phase since the start, counting the measured steps only.
+    pub fn total(&self, phase: StepPhase) -> Duration {
+        self.phases[phase.index()].total
+    }
+}
@@ -0,0 +1,112 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rapier2d::prelude::*;
+
+use crate::app_defines::AppDefines;
+use crate::bullet::bullet::{Bullet, BulletKind};
+use crate::game_logic::ai_behavior::AiDifficulty;
+use crate::game_logic::GameLogic;
+
+/// A loaded game built the same way from the same seed, for the benchmarks and the stress tests.
+///
+/// The entities drive with random motors, the bullets of random shooters fly from random points
+/// in random directions and the obstacles are placed as by `generate_map`. Nothing dies and the round
+/// never ends, so the load stays the same from one step to the next, but for the bullets
+/// leaving the arena.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressScenario {
+    /// The number of entities driven by the motors drawn at setup.
+    pub entities: usize,
+    /// The number of entities driven by the default AI behavior.
+    pub bots: usize,
+    /// The number of bullets flying at setup, spread over the entities and bots.
+    pub bullets: usize,
+    /// The number of obstacles.
+    pub obstacles: usize,
+    /// The seed of every random draw of the setup and of the game.
+    pub seed: u64,
+}
+
+impl StressScenario {
+    /// Creates a scenario without bots, with the default seed.
+    ///
+    /// # Parameters
+    /// - `entities`: The number of entities.
+    /// - `bullets`: The number of bullets, none if there is no entity to fire them.
+    /// - `obstacles`: The number of obstacles.
+    pub fn new(entities: usize, bullets: usize, obstacles: usize) -> Self {
+        Self { entities, bots: 0, bullets, obstacles, seed: 0 }
+    }
+
+    /// Builds the game of the scenario, stepped once so every collider is in the broad phase.
+    ///
+    /// # Returns
+    /// The game, identical for the same scenario.
+    pub fn build(&self) -> GameLogic {
+        let mut logic = GameLogic::new();
+        logic.rng = StdRng::seed_from_u64(self.seed);
+        let config = &mut logic.game_config;
+        config.starting_health = 1_000_000;
+        config.bullet_lifetime_ms = 600_000;
+        config.match_duration_secs = 0;
+        config.score_target = 0;
+        config.zone_enabled = false;
+        config.spawn_protection_ms = 0;
+
+        logic.generate_obstacles(self.obstacles);
+        for index in 0..self.entities {
+            logic.add_entity(format!("Player{}", index + 1));
+        }
+        for index in 0..self.bots {
+            logic.add_ai(format!("AI Bot {}", index + 1), None, AiDifficulty::default());
+        }
+        logic.reposition_entities();
+        for entity in &mut logic.entities {
+            entity.motor_left = logic.rng.random_range(0.0..=1.0);
+            entity.motor_right = logic.rng.random_range(0.0..=1.0);
+        }
+
+        // Deux balles qui se croisent disparaissent : elles sont semées dans l'arène, à distance
+        if !logic.entities.is_empty() {
+            let mut placed: Vec<(f32, f32)> = Vec::new();
+            let dt = logic.physics_engine.integration_parameters.dt;
+            let expires_at = GameLogic::ticks_in(logic.game_config.bullet_lifetime_ms as f32 / 1000.0, dt);
+            let config = &logic.game_config;
+            for _ in 0..self.bullets {
+                let shooter = logic.rng.random_range(0..logic.entities.len());
+                let angle = logic.rng.random_range(0.0..std::f32::consts::TAU);
+                let entities = &logic.entities;
+                let obstacles = &logic.obstacles;
+                let (x, y) = GameLogic::free_position(&logic.arena, &mut logic.rng, |position| {
+                    obstacles.iter().all(|obstacle| obstacle.distance(position) >= AppDefines::ENTITY_HALF_SIZE)
+                        && entities.iter().all(|entity| (entity.x - position.0).hypot(entity.y - position.1) >= 2.0 * AppDefines::ENTITY_HALF_SIZE)
+                        && placed.iter().all(|other| (other.0 - position.0).hypot(other.1 - position.1) >= 4.0 * config.bullet_radius)
+                });
+                placed.push((x, y));
+                let shooter = &mut logic.entities[shooter];
+                let velocity = vector![angle.cos(), angle.sin()] * config.bullet_speed;
+                let body = Bullet::rigid_body(vector![x, y], velocity, config.bullet_ccd, Some(shooter.id));
+                let handle = logic.physics_engine.bodies.insert(body);
+                let physics = &mut logic.physics_engine;
+                physics.colliders.insert_with_parent(Bullet::collider(config.bullet_radius, config.bullet_bounces), handle, &mut physics.bodies);
+                logic.bullets.push(Bullet {
+                    handle,
+                    shooter: shooter.handle,
+                    shooter_id: Some(shooter.id),
+                    fired_at: logic.tick,
+                    expires_at,
+                    bounces: config.bullet_bounces,
+                    bounces_remaining: config.bullet_bounces,
+                    kind: BulletKind::Standard,
+                    traveled: 0.0,
+                    last_position: vector![x, y],
+                });
+                shooter.live_bullets += 1;
+            }
+        }
+
+        logic.physics_engine.refresh_queries();
+        logic.step();
+        logic
+    }
+}
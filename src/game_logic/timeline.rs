@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::observer::{GameEvent, GameObserver};
+use crate::types::{add_message, MessageHub, MessageOrigin, MessageType};
+
+/// The version of the timeline file format.
+pub const TIMELINE_FORMAT_VERSION: u32 = 1;
+
+/// The metadata of a match, written as the first line of timelines and replays.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchHeader {
+    /// The version of the file format.
+    pub format_version: u32,
+    /// The time the match started, in seconds since the Unix epoch.
+    pub started_at: u64,
+    /// The width of the arena.
+    pub arena_width: f32,
+    /// The height of the arena.
+    pub arena_height: f32,
+    /// The number of simulation steps per second.
+    pub steps_per_second: f32,
+}
+
+impl MatchHeader {
+    /// Creates the header of a match starting now.
+    ///
+    /// # Parameters
+    /// - `arena`: The size of the arena of the match.
+    /// - `steps_per_second`: The tick rate of the simulation.
+    pub fn new(arena: &ArenaConfig, steps_per_second: f32) -> Self {
+        Self {
+            format_version: TIMELINE_FORMAT_VERSION,
+            started_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            arena_width: arena.width,
+            arena_height: arena.height,
+            steps_per_second,
+        }
+    }
+
+    /// Returns the size of the arena of the match.
+    pub fn arena(&self) -> ArenaConfig {
+        ArenaConfig { width: self.arena_width, height: self.arena_height }
+    }
+}
+
+/// One line of a timeline: an event and the step at which it happened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineRecord {
+    /// The simulation step of the event.
+    pub step: u64,
+    /// The event.
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
+
+/// When the timeline is written to its file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineMode {
+    /// Every event is appended to the file as it happens.
+    Continuous,
+    /// Events are kept in memory and written at the end of each round, or on `flush`.
+    OnDemand,
+}
+
+/// A game observer exporting the arena events as JSON lines for post-match analysis.
+pub struct TimelineWriter {
+    writer: BufWriter<File>,
+    mode: TimelineMode,
+    pending: Vec<TimelineRecord>,
+    messages: Option<MessageHub>,
+}
+
+impl TimelineWriter {
+    /// Creates the timeline file and writes the match header.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the timeline file, overwritten if it exists.
+    /// - `mode`: When events are written to the file.
+    /// - `arena`: The size of the arena, written in the header.
+    /// - `steps_per_second`: The tick rate of the simulation, written in the header.
+    pub fn create(path: &Path, mode: TimelineMode, arena: &ArenaConfig, steps_per_second: f32) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", serde_json::to_string(&MatchHeader::new(arena, steps_per_second))?)?;
+        writer.flush()?;
+        Ok(Self { writer, mode, pending: Vec::new(), messages: None })
+    }
+
+    /// Sets the messages of the server the write errors are reported to, dropped without a server.
+    ///
+    /// # Parameters
+    /// - `messages`: The messages of the server.
+    pub fn set_messages(&mut self, messages: MessageHub) {
+        self.messages = Some(messages);
+    }
+
+    /// Writes the events kept in memory to the file, reporting the error to the messages of the server.
+    fn flush_or_report(&mut self) {
+        if let (Err(e), Some(messages)) = (self.flush(), &self.messages) {
+            add_message(messages, format!("[ERROR] Failed to write the timeline: {}", e), MessageType::Error, MessageOrigin::Game);
+        }
+    }
+
+    /// Writes the events kept in memory to the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        for record in self.pending.drain(..) {
+            writeln!(self.writer, "{}", serde_json::to_string(&record)?)?;
+        }
+        self.writer.flush()
+    }
+}
+
+impl GameObserver for TimelineWriter {
+    fn on_event(&mut self, step: u64, event: &GameEvent) {
+        self.pending.push(TimelineRecord { step, event: event.clone() });
+        if self.mode == TimelineMode::Continuous || matches!(event, GameEvent::RoundEnd) {
+            self.flush_or_report();
+        }
+    }
+
+    fn on_shutdown(&mut self) {
+        self.flush_or_report();
+    }
+}
+
+/// The first kill of a match.
+#[derive(Debug, Clone)]
+pub struct FirstBlood {
+    pub step: u64,
+    pub shooter: u32,
+    pub victim: u32,
+}
+
+/// The life of an entity, from its spawn to its despawn or the end of the timeline.
+#[derive(Debug, Clone)]
+pub struct Life {
+    pub id: u32,
+    pub name: String,
+    pub steps: u64,
+}
+
+/// Statistics computed from a timeline.
+#[derive(Debug, Clone)]
+pub struct MatchSummary {
+    /// The header of the match.
+    pub header: MatchHeader,
+    /// The step of the last event.
+    pub last_step: u64,
+    /// The total number of kills.
+    pub kills: usize,
+    /// The first kill, if any.
+    pub first_blood: Option<FirstBlood>,
+    /// The longest life of the match, if any entity spawned.
+    pub longest_life: Option<Life>,
+    /// For each shooter, its cumulative damage after each of its hits, as `(step, total)`.
+    pub damage: BTreeMap<u32, Vec<(u64, i32)>>,
+    /// The last known name of each entity.
+    pub names: BTreeMap<u32, String>,
+}
+
+impl MatchSummary {
+    /// Returns the name of an entity, or its id if it never spawned in the timeline.
+    fn name_of(&self, id: u32) -> String {
+        self.names.get(&id).cloned().unwrap_or_else(|| format!("#{}", id))
+    }
+}
+
+impl fmt::Display for MatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.last_step as f32 / self.header.steps_per_second;
+        writeln!(f, "Match started at {} - {} steps ({:.1} s)", self.header.started_at, self.last_step, seconds)?;
+        writeln!(f, "Kills: {}", self.kills)?;
+        if let Some(first_blood) = &self.first_blood {
+            writeln!(
+                f,
+                "First blood: {} on {} at step {}",
+                self.name_of(first_blood.shooter),
+                self.name_of(first_blood.victim),
+                first_blood.step
+            )?;
+        }
+        if let Some(life) = &self.longest_life {
+            writeln!(f, "Longest life: {} (#{}, {} steps)", life.name, life.id, life.steps)?;
+        }
+        writeln!(f, "Damage dealt:")?;
+        for (shooter, graph) in &self.damage {
+            let points: Vec<String> = graph.iter().map(|(step, total)| format!("{}:{}", step, total)).collect();
+            writeln!(f, "  {} - {}", self.name_of(*shooter), points.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a timeline file and computes its match summary.
+///
+/// # Parameters
+/// - `path`: The path of a timeline written by `TimelineWriter`.
+pub fn analyze_timeline(path: &Path) -> io::Result<MatchSummary> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header_line = lines
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty timeline"))??;
+    let header: MatchHeader = serde_json::from_str(&header_line)?;
+
+    let mut summary = MatchSummary {
+        header,
+        last_step: 0,
+        kills: 0,
+        first_blood: None,
+        longest_life: None,
+        damage: BTreeMap::new(),
+        names: BTreeMap::new(),
+    };
+    let mut spawned_at: BTreeMap<u32, u64> = BTreeMap::new();
+    let mut lives: Vec<Life> = Vec::new();
+
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: TimelineRecord = serde_json::from_str(&line)?;
+        summary.last_step = summary.last_step.max(record.step);
+
+        match record.event {
+            GameEvent::Spawn { id, name, .. } => {
+                spawned_at.insert(id, record.step);
+                summary.names.insert(id, name);
+            }
+            GameEvent::Hit { shooter, damage, .. } => {
+                let graph = summary.damage.entry(shooter).or_default();
+                let total = graph.last().map_or(0, |(_, total)| *total) + damage;
+                graph.push((record.step, total));
+            }
+            GameEvent::Kill { shooter, victim, .. } => {
+                summary.kills += 1;
+                if summary.first_blood.is_none() {
+                    summary.first_blood = Some(FirstBlood { step: record.step, shooter, victim });
+                }
+            }
+            GameEvent::Despawn { id } => {
+                if let Some(start) = spawned_at.remove(&id) {
+                    let name = summary.name_of(id);
+                    lives.push(Life { id, name, steps: record.step - start });
+                }
+            }
+            GameEvent::BulletFired { .. }
+            | GameEvent::Detonation { .. }
+            | GameEvent::OutOfBounds { .. }
+            | GameEvent::PickUp { .. }
+            | GameEvent::RuleChange { .. }
+            | GameEvent::RoundStart { .. }
+            | GameEvent::RoundEnd
+            | GameEvent::MatchEnd { .. }
+            | GameEvent::Penalty { .. }
+            | GameEvent::Bonus { .. }
+            | GameEvent::Camping { .. }
+            | GameEvent::Capture { .. }
+            | GameEvent::Wave { .. } => {}
+        }
+    }
+
+    // Les entités encore en vie à la fin vivent jusqu'au dernier évènement
+    for (id, start) in spawned_at {
+        let name = summary.name_of(id);
+        lives.push(Life { id, name, steps: summary.last_step - start });
+    }
+    summary.longest_life = lives.into_iter().max_by_key(|life| life.steps);
+
+    Ok(summary)
+}
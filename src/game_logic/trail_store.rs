@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::replay::{EntityFrame, ReplayFrame};
+
+/// How the movement trails are sampled and how long they are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrailSettings {
+    /// The number of simulation steps between two samples, at least 1.
+    pub interval_ticks: u64,
+    /// The most samples kept per entity, the oldest are dropped first.
+    pub max_samples: usize,
+    /// The number of simulation steps the trail of a removed entity is kept after it, 0 to drop it at once.
+    pub linger_ticks: u64,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        Self {
+            interval_ticks: AppDefines::DEFAULT_TRAIL_INTERVAL_TICKS,
+            max_samples: AppDefines::DEFAULT_TRAIL_MAX_SAMPLES,
+            linger_ticks: AppDefines::DEFAULT_TRAIL_LINGER_TICKS,
+        }
+    }
+}
+
+/// The past positions of one entity.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trail {
+    /// The color of the entity, to draw the trail in.
+    pub color: [u8; 3],
+    /// The sampled positions, oldest first.
+    pub points: VecDeque<(f32, f32)>,
+    /// The step at which the entity was removed, `None` while it is in the arena.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<u64>,
+}
+
+/// One trail of `TrailPolylines`: the entity and how many points it has.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailRun {
+    pub id: u32,
+    pub color: [u8; 3],
+    /// The number of points of the trail in `TrailPolylines::points`.
+    pub length: usize,
+}
+
+/// The trails laid out for drawing: the points of every trail one after the other, see `TrailStore::assemble`.
+///
+/// Kept from one frame to the next, so that its buffers are refilled rather than allocated again.
+#[derive(Debug, Clone, Default)]
+pub struct TrailPolylines {
+    pub points: Vec<(f32, f32)>,
+    pub runs: Vec<TrailRun>,
+}
+
+impl TrailPolylines {
+    /// Empties the buffers, keeping their capacity.
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.runs.clear();
+    }
+
+    /// Returns the points of each trail, in the order of `runs`.
+    pub fn trails(&self) -> impl Iterator<Item = (&TrailRun, &[(f32, f32)])> {
+        let mut start = 0;
+        self.runs.iter().map(move |run| {
+            let points = &self.points[start..start + run.length];
+            start += run.length;
+            (run, points)
+        })
+    }
+}
+
+/// The movement trails of the entities, for the analysis of a match.
+///
+/// Each entity leaves a sample of its position every `interval_ticks` steps, at most `max_samples`
+/// of them. The trails live here rather than in the entities, which the steps go through all the time.
+/// The trail of a removed entity lingers for `linger_ticks` steps, then is dropped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrailStore {
+    pub settings: TrailSettings,
+    trails: BTreeMap<u32, Trail>,
+    /// Grows with every change, so that a copy can tell it is out of date.
+    #[serde(skip)]
+    revision: u64,
+    /// The number of times the trails were cleared.
+    #[serde(skip)]
+    clears: u64,
+}
+
+impl TrailStore {
+    /// Creates a store without trails.
+    ///
+    /// # Parameters
+    /// - `settings`: How the trails are sampled.
+    pub fn new(settings: TrailSettings) -> Self {
+        Self { settings, ..Self::default() }
+    }
+
+    /// Adds the positions of the entities to their trails if the step is a sampling step,
+    /// and drops the trails of the entities removed more than `linger_ticks` ago.
+    ///
+    /// # Parameters
+    /// - `tick`: The current simulation step.
+    /// - `positions`: The id, position and color of each entity in the arena, only read at a sampling step.
+    pub fn record<I>(&mut self, tick: u64, positions: I)
+    where
+        I: IntoIterator<Item = (u32, (f32, f32), [u8; 3])>,
+    {
+        let linger = self.settings.linger_ticks;
+        let count = self.trails.len();
+        self.trails.retain(|_, trail| !trail.removed_at.is_some_and(|removed_at| tick >= removed_at + linger));
+        if self.trails.len() != count {
+            self.revision += 1;
+        }
+        if tick % self.settings.interval_ticks.max(1) != 0 {
+            return;
+        }
+        let max_samples = self.settings.max_samples;
+        for (id, position, color) in positions {
+            let trail = self.trails.entry(id).or_default();
+            // Une entité revenue sous le même id reprend une traînée neuve
+            if trail.removed_at.take().is_some() {
+                trail.points.clear();
+            }
+            trail.color = color;
+            trail.points.push_back(position);
+            while trail.points.len() > max_samples {
+                trail.points.pop_front();
+            }
+        }
+        self.revision += 1;
+    }
+
+    /// Starts the lingering of the trail of a removed entity.
+    ///
+    /// # Parameters
+    /// - `id`: The id of the entity.
+    /// - `tick`: The step at which it was removed.
+    pub fn remove(&mut self, id: u32, tick: u64) {
+        if self.settings.linger_ticks == 0 {
+            self.trails.remove(&id);
+        } else if let Some(trail) = self.trails.get_mut(&id) {
+            trail.removed_at = Some(tick);
+        }
+        self.revision += 1;
+    }
+
+    /// Forgets every trail.
+    pub fn clear(&mut self) {
+        self.trails.clear();
+        self.revision += 1;
+        self.clears += 1;
+    }
+
+    /// Follows a replay frame as `record` follows the arena: the entities missing from the frame are removed.
+    ///
+    /// # Parameters
+    /// - `frame`: The frame after the last one followed.
+    pub fn follow(&mut self, frame: &ReplayFrame) {
+        let gone: Vec<u32> = self.trails.iter()
+            .filter(|(id, trail)| trail.removed_at.is_none() && !frame.entities.iter().any(|entity| entity.id == **id))
+            .map(|(id, _)| *id)
+            .collect();
+        // Retirée entre deux pas, l'entité l'a été au pas de l'image précédente
+        for id in gone {
+            self.remove(id, frame.tick.saturating_sub(1));
+        }
+        self.record(frame.tick, frame.entities.iter().map(|entity| (entity.id, (entity.x, entity.y), entity.color)));
+    }
+
+    /// Returns the trail of an entity, `None` if it has none.
+    pub fn get(&self, id: u32) -> Option<&Trail> {
+        self.trails.get(&id)
+    }
+
+    /// Returns the trails by increasing entity id.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Trail)> {
+        self.trails.iter().map(|(id, trail)| (*id, trail))
+    }
+
+    /// Returns the number of trails, lingering ones included.
+    pub fn len(&self) -> usize {
+        self.trails.len()
+    }
+
+    /// Returns whether there is no trail.
+    pub fn is_empty(&self) -> bool {
+        self.trails.is_empty()
+    }
+
+    /// Returns a number that changes whenever the trails change.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the number of times the trails were cleared, a recording stores them whole after each.
+    pub fn clear_count(&self) -> u64 {
+        self.clears
+    }
+
+    /// Lays the trails out for drawing, each one ending at the drawn position of its entity.
+    ///
+    /// The trails of the entities drawn come first, in the order of `heads`, then the lingering ones.
+    ///
+    /// # Parameters
+    /// - `heads`: The entities drawn, at their drawn position.
+    /// - `polylines`: Emptied, then filled with the trails.
+    pub fn assemble(&self, heads: &[EntityFrame], polylines: &mut TrailPolylines) {
+        polylines.clear();
+        for entity in heads {
+            let Some(trail) = self.trails.get(&entity.id).filter(|trail| trail.removed_at.is_none()) else { continue };
+            polylines.points.extend(trail.points.iter().copied());
+            polylines.points.push((entity.x, entity.y));
+            polylines.runs.push(TrailRun { id: entity.id, color: entity.color, length: trail.points.len() + 1 });
+        }
+        for (id, trail) in self.trails.iter().filter(|(_, trail)| trail.removed_at.is_some()) {
+            polylines.points.extend(trail.points.iter().copied());
+            polylines.runs.push(TrailRun { id: *id, color: trail.color, length: trail.points.len() });
+        }
+    }
+}
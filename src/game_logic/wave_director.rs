@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::observer::GameEvent;
+
+/// The state of the current horde wave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaveState {
+    /// Waiting before the next wave starts.
+    Intermission,
+    /// The wave is being spawned or fought.
+    InProgress,
+    /// Every player died: the round is lost.
+    Lost,
+}
+
+impl WaveState {
+    /// Returns what just happened to the wave in this state, as the `WAVE` push writes it.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WaveState::Intermission => AppDefines::WAVE_CLEARED,
+            WaveState::InProgress => AppDefines::WAVE_STARTED,
+            WaveState::Lost => AppDefines::WAVE_LOST,
+        }
+    }
+}
+
+/// Drives the cooperative horde mode: all connected players form one team
+/// and fight escalating waves of AI bots.
+///
+/// The director does not own any entity. It queues the bots of the next wave
+/// and releases them one by one through `GameLogic::update_waves`, then watches
+/// the ids it spawned to decide when the wave is cleared.
+pub struct WaveDirector {
+    /// The current wave number, starting at 1.
+    pub wave: u32,
+    /// The state of the current wave.
+    pub state: WaveState,
+    /// The number of waves cleared since the horde started.
+    pub waves_survived: u32,
+    /// The last announcement, shown in the UI.
+    pub announcement: String,
+    /// Bots waiting to be spawned for the current wave.
+    pending_spawns: VecDeque<String>,
+    /// Ids of the bots spawned for the current wave.
+    spawned_ids: Vec<u32>,
+    /// The simulated time the last bot of the wave was spawned at.
+    last_spawn: Duration,
+    /// The simulated time the current intermission started at.
+    intermission_start: Duration,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl WaveDirector {
+    /// Creates a new `WaveDirector`, starting with an intermission before wave 1.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    ///
+    /// # Returns
+    /// A new instance of `WaveDirector`.
+    pub fn new(now: Duration) -> Self {
+        Self {
+            wave: 0,
+            state: WaveState::Intermission,
+            waves_survived: 0,
+            announcement: "Horde mode: first wave incoming".to_string(),
+            pending_spawns: VecDeque::new(),
+            spawned_ids: Vec::new(),
+            last_spawn: now,
+            intermission_start: now,
+        }
+    }
+
+    /// Returns the number of bots making up a given wave.
+    ///
+    /// # Parameters
+    /// - `wave`: The wave number, starting at 1.
+    pub fn wave_size(wave: u32) -> u32 {
+        AppDefines::HORDE_FIRST_WAVE_SIZE + (wave.saturating_sub(1)) * AppDefines::HORDE_WAVE_GROWTH
+    }
+
+    /// Returns the number of bots of the current wave still alive or waiting to spawn.
+    ///
+    /// # Parameters
+    /// - `alive_ids`: The ids of the entities currently in the game.
+    pub fn remaining(&self, alive_ids: &[u32]) -> usize {
+        self.pending_spawns.len() + self.spawned_ids.iter().filter(|id| alive_ids.contains(id)).count()
+    }
+
+    /// Returns whether the next intermission is over and a new wave should be queued.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn intermission_over(&self, now: Duration) -> bool {
+        self.state == WaveState::Intermission
+            && now.saturating_sub(self.intermission_start) >= Duration::from_millis(AppDefines::HORDE_INTERMISSION_MS)
+    }
+
+    /// Queues the bots of the next wave and announces it.
+    pub fn start_next_wave(&mut self) {
+        self.wave += 1;
+        self.state = WaveState::InProgress;
+        self.spawned_ids.clear();
+        for index in 0..Self::wave_size(self.wave) {
+            self.pending_spawns.push_back(format!("Horde {}-{}", self.wave, index + 1));
+        }
+        self.announcement = format!("Wave {} started: {} bots", self.wave, Self::wave_size(self.wave));
+    }
+
+    /// Pops the next bot to spawn if the spawn interval has elapsed.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    ///
+    /// # Returns
+    /// The name of the bot to spawn, if any.
+    pub fn next_spawn(&mut self, now: Duration) -> Option<String> {
+        if self.state != WaveState::InProgress
+            || now.saturating_sub(self.last_spawn) < Duration::from_millis(AppDefines::HORDE_SPAWN_INTERVAL_MS)
+        {
+            return None;
+        }
+        let name = self.pending_spawns.pop_front()?;
+        self.last_spawn = now;
+        Some(name)
+    }
+
+    /// Records the id of a bot spawned for the current wave.
+    pub fn register_spawn(&mut self, id: u32) {
+        self.spawned_ids.push(id);
+    }
+
+    /// Returns the ids of the bots spawned for the current wave, destroyed or not.
+    pub fn spawned_ids(&self) -> &[u32] {
+        &self.spawned_ids
+    }
+
+    /// Starts the intermission over, so that the players get the whole pause once the next round starts.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn resume(&mut self, now: Duration) {
+        self.intermission_start = now;
+        self.last_spawn = now;
+    }
+
+    /// Returns the event announcing the current state of the wave.
+    pub fn event(&self) -> GameEvent {
+        GameEvent::Wave { wave: self.wave, state: self.state, text: self.announcement.clone() }
+    }
+
+    /// Updates the wave state from the entities currently alive.
+    ///
+    /// # Parameters
+    /// - `alive_ids`: The ids of the entities currently in the game.
+    /// - `players_alive`: The number of non-AI entities currently in the game.
+    /// - `now`: The current simulated time.
+    pub fn update(&mut self, alive_ids: &[u32], players_alive: usize, now: Duration) {
+        if self.state != WaveState::InProgress {
+            return;
+        }
+
+        if players_alive == 0 {
+            self.state = WaveState::Lost;
+            self.pending_spawns.clear();
+            self.announcement = format!("All players are dead: lost on wave {}", self.wave);
+        } else if self.remaining(alive_ids) == 0 {
+            self.waves_survived += 1;
+            self.state = WaveState::Intermission;
+            self.intermission_start = now;
+            self.announcement = format!("Wave {} cleared!", self.wave);
+        }
+    }
+}
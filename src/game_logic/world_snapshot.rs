@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::game_logic::control_point::Side;
+use crate::game_logic::event_queue::EventQueue;
+use crate::game_logic::ghost_camera::GhostCamera;
+use crate::game_logic::scoring::ScoreBreakdown;
+
+/// What the clients can ask about an entity without locking the game logic, see `WorldSnapshot`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityStatus {
+    pub id: u32,
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    /// The rotation of the body, in radians.
+    pub orientation: f64,
+    pub health: i32,
+    pub score: i32,
+    /// The time before the entity can fire again, see `Entity::fire_cooldown_remaining`.
+    pub fire_cooldown: Duration,
+    /// The largest deviation of a bullet fired now from the aim, in radians, see `GameLogic::spread`.
+    pub spread: f32,
+    pub breakdown: ScoreBreakdown,
+    /// How many more bullets the entity can have flying at once, `None` without a cap.
+    pub bullets_left: Option<u32>,
+    pub fire_energy: f32,
+    /// Where the last bullet that damaged the entity came from, in radians, `None` before the first.
+    pub last_hit_bearing: Option<f32>,
+    /// The sequence number of the last sequenced actuator command applied, see `ActuatorQueue::applied`.
+    pub actuator_seq: Option<u64>,
+    /// The share of the bullet damage the entity shrugs off, see `Entity::armor`.
+    pub armor: f32,
+}
+
+/// A bullet flying, as the state pushes show it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BulletStatus {
+    /// The id of the bullet in the state pushes, see `GameLogic::publish_world`.
+    pub id: u64,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A control point of the map, as the state pushes show it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlPointStatus {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    /// The side holding the point or capturing it, see `ControlPoint::owner`.
+    pub owner: Option<Side>,
+    /// How far the owner captured the point, from 0 to 1.
+    pub progress: f32,
+}
+
+/// The read-only view of the game the server answers the queries from, published by the game loop
+/// after each step and whenever an entity joins or leaves the game.
+///
+/// A query answered from it may be one step behind the game logic: the state it reports is the one
+/// of the last step, as the clients would have seen it had they asked just before the current one.
+#[derive(Debug, Clone, Default)]
+pub struct WorldSnapshot {
+    /// The simulation step the snapshot was taken at.
+    pub tick: u64,
+    /// The entities, in the order of the game logic.
+    pub entities: Vec<EntityStatus>,
+    /// The bullets flying.
+    pub bullets: Vec<BulletStatus>,
+    /// The control points, in the order of the map, only in the control point mode.
+    pub control_points: Vec<ControlPointStatus>,
+    /// The cameras of the spectators.
+    pub ghost_cameras: Vec<GhostCamera>,
+    /// The last arena events, shared with the next snapshots until a new event comes.
+    pub events: Arc<EventQueue>,
+    /// The number of times the simulation was reset, see `GameLogic::reset_count`.
+    pub reset_count: u64,
+    /// How long the last simulation step took to compute.
+    pub last_step: Duration,
+    /// The number of horde waves that ended, see `GameLogic::respawns_granted`.
+    pub respawns_granted: u64,
+}
+
+impl WorldSnapshot {
+    /// Returns an entity of the snapshot by its id.
+    pub fn entity(&self, id: u32) -> Option<&EntityStatus> {
+        self.entities.iter().find(|entity| entity.id == id)
+    }
+
+    /// Returns a camera of the snapshot by its id.
+    pub fn ghost_camera(&self, id: u32) -> Option<&GhostCamera> {
+        self.ghost_cameras.iter().find(|camera| camera.id == id)
+    }
+}
+
+/// The place the game logic puts the last `WorldSnapshot` in and the server takes it from.
+///
+/// As for `RenderSlot`, only the `Arc` is swapped under the lock: the client handlers and the
+/// state broadcaster read the snapshot without holding the game logic, so they never delay a step.
+#[derive(Debug, Clone, Default)]
+pub struct WorldSlot(Arc<Mutex<Arc<WorldSnapshot>>>);
+
+impl WorldSlot {
+    /// Replaces the snapshot.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The snapshot of the game as it is now.
+    pub fn publish(&self, snapshot: WorldSnapshot) {
+        *self.0.lock().unwrap() = Arc::new(snapshot);
+    }
+
+    /// Returns the last snapshot published.
+    pub fn latest(&self) -> Arc<WorldSnapshot> {
+        Arc::clone(&self.0.lock().unwrap())
+    }
+}
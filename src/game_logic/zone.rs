@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::game_config::GameConfig;
+
+/// The safe zone of a round, a circle centered on the arena.
+///
+/// The zone first covers the whole arena. Once the delay of the match rules is over,
+/// its radius shrinks linearly down to the final radius. Entities outside the zone take damage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zone {
+    pub center: (f32, f32),
+    /// The current radius.
+    pub radius: f32,
+    /// The radius the zone shrinks to.
+    pub next_radius: f32,
+    /// The simulated time before the zone starts shrinking, zero once it has started.
+    pub shrink_starts_in: Duration,
+    /// The simulated time before the zone reaches `next_radius`, zero once it has.
+    pub shrink_ends_in: Duration,
+}
+
+impl Zone {
+    /// Returns the zone at some point of a round.
+    ///
+    /// # Parameters
+    /// - `config`: The match rules giving the delay, the shrinking time and the final radius.
+    /// - `arena`: The arena the zone is centered on.
+    /// - `elapsed`: The simulated time since the round started.
+    ///
+    /// # Returns
+    /// The zone, or `None` when the match rules disable it.
+    pub fn at(config: &GameConfig, arena: &ArenaConfig, elapsed: Duration) -> Option<Self> {
+        if !config.zone_enabled {
+            return None;
+        }
+        let start_radius = Zone::start_radius(arena);
+        let next_radius = config.zone_final_radius.min(start_radius);
+        let delay = Duration::from_secs_f32(config.zone_delay_secs.max(0.0));
+        let shrink = Duration::from_secs_f32(config.zone_shrink_secs.max(0.0));
+
+        let shrinking = elapsed.saturating_sub(delay);
+        let progress = if shrinking >= shrink { 1.0 } else { shrinking.as_secs_f32() / shrink.as_secs_f32() };
+        Some(Self {
+            center: arena.center(),
+            radius: start_radius + (next_radius - start_radius) * progress,
+            next_radius,
+            shrink_starts_in: delay.saturating_sub(elapsed),
+            shrink_ends_in: (delay + shrink).saturating_sub(elapsed),
+        })
+    }
+
+    /// Returns the radius of the zone before it shrinks: it reaches the corners of the arena.
+    pub fn start_radius(arena: &ArenaConfig) -> f32 {
+        let (half_width, half_height) = arena.center();
+        half_width.hypot(half_height)
+    }
+
+    /// Returns whether a position is outside the zone.
+    ///
+    /// # Parameters
+    /// - `x`, `y`: The position.
+    pub fn is_outside(&self, x: f32, y: f32) -> bool {
+        (x - self.center.0).hypot(y - self.center.1) > self.radius
+    }
+}
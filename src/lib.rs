@@ -0,0 +1,33 @@
+//! The arena simulation and the server the bots play it through.
+//!
+//! `game_logic` steps the arena: its entities, bullets, obstacles and the rules of the match,
+//! over the `physics` engine. `server` accepts the clients and drives the entities with their
+//! commands, and `types` holds the messages of the server. `config` reads the settings file,
+//! `ui` and `cli` are the window and the command line of the binary. `client` speaks the
+//! protocol from the other side, for the bots written in Rust.
+//!
+//! ```
+//! use universal_rust_server_software::game_logic::GameLogic;
+//!
+//! let mut logic = GameLogic::new();
+//! let id = logic.add_entity("Player1".to_string());
+//! logic.step();
+//! assert!(logic.entities.iter().any(|entity| entity.id == id));
+//! ```
+
+pub mod app_defines;
+pub mod bullet;
+pub mod cli;
+pub mod client;
+pub mod config;
+pub mod entities;
+pub mod game_logic;
+pub mod obstacles;
+pub mod physics;
+pub mod power_ups;
+pub mod server;
+pub mod types;
+pub mod ui;
+pub(crate) mod mines;
+
+pub use types::StyledMessage;
@@ -1,49 +1,115 @@
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
+use std::fmt::Display;
 
-pub use types::StyledMessage;
-use crate::game_logic::GameLogic;
-use crate::server::server_thread::{ServerSettings, ServerThread};
-use crate::ui::CombinedUI;  // <-- Import de ta nouvelle UI combinée
-
-mod server;
-mod ui;
-mod app_defines;
-pub mod types;
-mod physics;
-mod entities;
-mod bullet;
-mod game_logic;
-mod obstacles;
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::cli::CliArgs;
+use universal_rust_server_software::config::Config;
+use universal_rust_server_software::game_logic::GameLogic;
+use universal_rust_server_software::game_logic::leaderboard::Leaderboard;
+use universal_rust_server_software::game_logic::timeline::{analyze_timeline, TimelineWriter};
+use universal_rust_server_software::server::server_thread::{ServerSettings, ServerThread};
+use universal_rust_server_software::types::{add_message, format_timestamp, MessageHub, MessageOrigin, MessageType};
+use universal_rust_server_software::ui::CombinedUI;
+
+/// Prints why the server cannot start and exits, before any socket is bound.
+fn exit_with_error(error: impl Display) -> ! {
+    eprintln!("error: {}", error);
+    std::process::exit(2);
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let messages = Arc::new(Mutex::new(Vec::new()));
-    let settings = Arc::new(Mutex::new(ServerSettings::new()));
-    let game_logic = Arc::new(Mutex::new(GameLogic::new())); // ✅ ici
-
-    let server_messages = Arc::clone(&messages);
-    let server_settings = Arc::clone(&settings);
-    let server_game_logic = Arc::clone(&game_logic); // ✅
-
-    thread::spawn(move || {
-        let serv = ServerThread {
-            address: "127.0.0.1".to_string(),
-            /*address: "192.168.135.251".to_string(),*/
-            port: 6969,
-            messages: server_messages,
-            settings: server_settings,
-            game_logic: server_game_logic, // ✅ partagé
-            client_entity_map: Arc::new(Mutex::new(HashMap::new())),
-        };
-        serv.start();
-    });
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = CliArgs::parse(&args).unwrap_or_else(|e| exit_with_error(e));
+    if cli.help {
+        print!("{}", CliArgs::usage());
+        return Ok(());
+    }
+    if cli.print_default_config {
+        print!("{}", Config::default_template());
+        return Ok(());
+    }
+
+    // --analyze <timeline> : affiche le résumé d'un match et quitte
+    if let Some(path) = &cli.analyze {
+        print!("{}", analyze_timeline(path)?);
+        return Ok(());
+    }
+
+    // --config désigne un fichier existant, le fichier par défaut est facultatif
+    let config_path = cli.config.clone().unwrap_or_else(|| PathBuf::from(AppDefines::CONFIG_FILE));
+    let mut config = Config::load_or_default(&config_path).unwrap_or_else(|e| exit_with_error(e));
+    cli.apply(&mut config);
+
+    let settings = Arc::new(Mutex::new(ServerSettings::from_config(&config.server)));
+    let messages = MessageHub::new(settings.lock().unwrap().message_log_capacity);
+    let mut logic = GameLogic::with_arena(config.arena);
+    logic.game_config = config.game;
+    if !config.server.report_dir.is_empty() {
+        logic.report_dir = Some(PathBuf::from(&config.server.report_dir));
+    }
+    let leaderboard_path = settings.lock().unwrap().leaderboard_path.clone();
+    match Leaderboard::load(Path::new(&leaderboard_path)) {
+        Ok(leaderboard) => logic.leaderboard = leaderboard,
+        // Le fichier illisible est laissé intact, le classement reste en mémoire
+        Err(e) => add_message(
+            &messages,
+            format!("[WARNING] Could not load the leaderboard {}: {}", leaderboard_path, e),
+            MessageType::Warning,
+            MessageOrigin::Game,
+        ),
+    }
+    if let Some(path) = &cli.map {
+        if let Err(e) = logic.load_map(path) {
+            exit_with_error(format!("--map: the map {} cannot be loaded: {}", path.display(), e));
+        }
+    }
+    for index in 1..=config.ai.count {
+        logic.add_ai(format!("AI Bot {}", index), None, config.ai.difficulty);
+    }
+
+    if let Some(path) = &cli.timeline {
+        let mut writer = TimelineWriter::create(path, cli.timeline_mode, &logic.arena(), logic.physics_engine.tick_rate())
+            .unwrap_or_else(|e| exit_with_error(format!("--timeline: the file {} cannot be created: {}", path.display(), e)));
+        writer.set_messages(messages.clone());
+        logic.add_observer(Box::new(writer));
+    }
+
+    // La simulation avance à pas fixe sur le thread du serveur, indépendamment du rafraîchissement de la fenêtre
+    let serv = ServerThread::new(config.server.bind, config.server.port, messages.clone(), settings, Arc::new(Mutex::new(logic)));
+
+    // Ctrl-C prévient les clients, arrête les threads et écrit les fichiers avant de quitter, comme le bouton "Stop Server"
+    let interrupted_server = serv.clone();
+    ctrlc::set_handler(move || {
+        let closed = interrupted_server.stop();
+        println!("Server stopped, {} client(s) notified.", closed);
+        std::process::exit(0);
+    })?;
+
+    let ui_server = serv.clone();
+    thread::spawn(move || serv.start());
+
+    if cli.headless {
+        // Sans fenêtre, les messages du serveur vont sur la sortie standard jusqu'à Ctrl-C
+        let _ = ui_server;
+        let mut printed = 0;
+        loop {
+            let log = messages.log();
+            for message in log.from_sequence(printed) {
+                println!("{} [{}] {}", format_timestamp(message.timestamp), message.origin.tag(), message.text);
+            }
+            printed = log.added();
+            thread::sleep(Duration::from_millis(AppDefines::HEADLESS_PRINT_MS));
+        }
+    }
 
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "Physics Simulation & Server GUI",
         native_options,
-        Box::new(|_cc| Box::new(CombinedUI::new(messages, settings, game_logic))), // ✅ ici aussi
+        Box::new(|cc| Box::new(CombinedUI::new(cc, ui_server))),
     )?;
 
     Ok(())
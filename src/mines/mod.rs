@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use rapier2d::prelude::*;
+
+use crate::app_defines::AppDefines;
+use crate::physics::body_identity::BodyIdentity;
+use crate::physics::physics::PhysicsEngine;
+
+/// Why an entity could not lay a mine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployError {
+    /// The entity does not exist.
+    NoEntity,
+    /// The entity already has the maximum number of mines in the arena.
+    Limit,
+}
+
+/// Represents a mine laid by an entity.
+///
+/// A mine is a sensor collider: once armed, it detonates when another entity drives into it.
+pub struct Mine {
+    /// The id of the entity that laid the mine.
+    pub owner: u32,
+    pub position: (f64, f64),
+    pub collider_handle: ColliderHandle,
+    /// The simulated time from which the mine can detonate, see `GameLogic::sim_time`.
+    pub armed_at: Duration,
+}
+
+impl Mine {
+    /// Creates a new mine and adds its sensor to the physics engine.
+    ///
+    /// # Parameters
+    /// - `owner`: The id of the entity laying the mine.
+    /// - `position`: A tuple representing the (x, y) position of the mine.
+    /// - `physics_engine`: A mutable reference to the physics engine.
+    /// - `now`: The current simulated time, the arming delay starts from it.
+    ///
+    /// # Returns
+    /// A new instance of `Mine`.
+    pub fn new(owner: u32, position: (f64, f64), physics_engine: &mut PhysicsEngine, now: Duration) -> Self {
+        let collider_handle = physics_engine.insert_sensor(
+            SharedShape::ball(AppDefines::MINE_RADIUS),
+            vector![position.0 as f32, position.1 as f32],
+            BodyIdentity::Mine(owner),
+        );
+
+        Self {
+            owner,
+            position,
+            collider_handle,
+            armed_at: now + Duration::from_millis(AppDefines::MINE_ARMING_MS),
+        }
+    }
+
+    /// Returns whether the mine is armed.
+    ///
+    /// # Parameters
+    /// - `now`: The current simulated time.
+    pub fn is_armed(&self, now: Duration) -> bool {
+        now >= self.armed_at
+    }
+}
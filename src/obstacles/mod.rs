@@ -1,26 +1,217 @@
+use std::f32::consts::PI;
+
+use rand::Rng;
 use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::physics::body_identity::BodyIdentity;
+use crate::physics::physics::PhysicsEngine;
+
+/// The shape of an obstacle, around its position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObstacleShape {
+    /// An axis-aligned square.
+    Square { half: f32 },
+    /// A disc.
+    Circle { radius: f32 },
+    /// A long thin rectangle, turned by `angle` radians.
+    Wall { half_length: f32, half_thickness: f32, angle: f32 },
+}
+
+impl Default for ObstacleShape {
+    /// The square every obstacle had before obstacles had a shape.
+    fn default() -> Self {
+        ObstacleShape::Square { half: AppDefines::OBSTACLE_HALF_SIZE }
+    }
+}
+
+impl ObstacleShape {
+    /// Draws the shape of a new obstacle: half of them are squares, a quarter discs and a quarter walls.
+    ///
+    /// # Parameters
+    /// - `rng`: The random number generator of the map.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        match rng.random_range(0..4) {
+            0 => ObstacleShape::Circle {
+                radius: rng.random_range(AppDefines::OBSTACLE_CIRCLE_MIN_RADIUS..=AppDefines::OBSTACLE_CIRCLE_MAX_RADIUS),
+            },
+            1 => ObstacleShape::Wall {
+                half_length: rng.random_range(AppDefines::OBSTACLE_WALL_MIN_HALF_LENGTH..=AppDefines::OBSTACLE_WALL_MAX_HALF_LENGTH),
+                half_thickness: AppDefines::OBSTACLE_WALL_HALF_THICKNESS,
+                angle: rng.random_range(0.0..PI),
+            },
+            _ => ObstacleShape::default(),
+        }
+    }
+
+    /// Returns the name of the shape, as sent to the clients.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObstacleShape::Square { .. } => "SQUARE",
+            ObstacleShape::Circle { .. } => "CIRCLE",
+            ObstacleShape::Wall { .. } => "WALL",
+        }
+    }
+
+    /// Returns half the width and half the height of the shape before its rotation.
+    /// For a disc, both are the radius.
+    pub fn half_extents(&self) -> (f32, f32) {
+        match *self {
+            ObstacleShape::Square { half } => (half, half),
+            ObstacleShape::Circle { radius } => (radius, radius),
+            ObstacleShape::Wall { half_length, half_thickness, .. } => (half_length, half_thickness),
+        }
+    }
+
+    /// Returns the rotation of the shape, in radians.
+    pub fn angle(&self) -> f32 {
+        match *self {
+            ObstacleShape::Wall { angle, .. } => angle,
+            ObstacleShape::Square { .. } | ObstacleShape::Circle { .. } => 0.0,
+        }
+    }
+
+    /// Returns the distance from the center of the shape to its farthest point.
+    pub fn bounding_radius(&self) -> f32 {
+        match *self {
+            ObstacleShape::Circle { radius } => radius,
+            _ => {
+                let (half_width, half_height) = self.half_extents();
+                half_width.hypot(half_height)
+            }
+        }
+    }
+
+    /// Builds the collider of an obstacle of this shape, around the body it is attached to.
+    pub fn collider(&self) -> Collider {
+        let builder = match *self {
+            ObstacleShape::Square { half } => ColliderBuilder::cuboid(half, half),
+            ObstacleShape::Circle { radius } => ColliderBuilder::ball(radius),
+            ObstacleShape::Wall { half_length, half_thickness, angle } => {
+                ColliderBuilder::cuboid(half_length, half_thickness).rotation(angle)
+            }
+        };
+        builder.build()
+    }
+
+    /// Returns the distance from a point to the edge of an obstacle of this shape, 0 inside it.
+    ///
+    /// # Parameters
+    /// - `center`: The position of the obstacle.
+    /// - `point`: The point.
+    pub fn distance(&self, center: (f32, f32), point: (f32, f32)) -> f32 {
+        let closest = self.closest_point(center, point);
+        (point.0 - closest.0).hypot(point.1 - closest.1)
+    }
+
+    /// Returns the point of an obstacle of this shape closest to a point, the point itself inside it.
+    ///
+    /// # Parameters
+    /// - `center`: The position of the obstacle.
+    /// - `point`: The point.
+    pub fn closest_point(&self, center: (f32, f32), point: (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = (point.0 - center.0, point.1 - center.1);
+        if let ObstacleShape::Circle { radius } = *self {
+            let length = dx.hypot(dy);
+            if length <= radius {
+                return point;
+            }
+            return (center.0 + dx / length * radius, center.1 + dy / length * radius);
+        }
+        // Dans le repère du rectangle, le point ramené dans ses bords
+        let (sin, cos) = self.angle().sin_cos();
+        let (local_x, local_y) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+        let (half_width, half_height) = self.half_extents();
+        let (x, y) = (local_x.clamp(-half_width, half_width), local_y.clamp(-half_height, half_height));
+        (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos)
+    }
+
+    /// Returns the outline of an obstacle of this shape, as a closed polygon.
+    ///
+    /// # Parameters
+    /// - `center`: The position of the obstacle.
+    ///
+    /// # Returns
+    /// The corners, or points along the circle, the first one repeated at the end.
+    pub fn outline(&self, center: (f64, f64)) -> Vec<[f64; 2]> {
+        let mut points: Vec<[f64; 2]> = match *self {
+            ObstacleShape::Circle { radius } => (0..32)
+                .map(|i| {
+                    let angle = i as f64 * std::f64::consts::TAU / 32.0;
+                    [center.0 + radius as f64 * angle.cos(), center.1 + radius as f64 * angle.sin()]
+                })
+                .collect(),
+            _ => {
+                let (half_width, half_height) = self.half_extents();
+                let (sin, cos) = (self.angle() as f64).sin_cos();
+                [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                    .iter()
+                    .map(|&(sx, sy)| {
+                        let (x, y) = (sx * half_width as f64, sy * half_height as f64);
+                        [center.0 + x * cos - y * sin, center.1 + x * sin + y * cos]
+                    })
+                    .collect()
+            }
+        };
+        points.push(points[0]);
+        points
+    }
+}
 
 /// Represents an obstacle in the game.
 ///
-/// An obstacle has a position and a handle to its collider.
+/// An obstacle has a position, a shape, and a fixed body holding its collider.
 pub struct Obstacle {
     pub position: (f64, f64),
-    pub collider_handle: ColliderHandle,
+    pub shape: ObstacleShape,
+    /// The fixed body of the obstacle, holding its collider: removing it removes the collider too.
+    pub body_handle: RigidBodyHandle,
 }
 
 impl Obstacle {
-    /// Creates a new obstacle.
+    /// Creates a new obstacle and adds its body and collider to the physics engine.
     ///
     /// # Parameters
     /// - `position`: A tuple representing the (x, y) position of the obstacle.
-    /// - `collider_handle`: The handle to the collider associated with this obstacle.
+    /// - `shape`: The shape of the obstacle, the one of its collider.
+    /// - `physics_engine`: A mutable reference to the physics engine.
     ///
     /// # Returns
     /// A new instance of `Obstacle`.
-    pub fn new(position: (f64, f64), collider_handle: ColliderHandle) -> Self {
+    pub fn new(position: (f64, f64), shape: ObstacleShape, physics_engine: &mut PhysicsEngine) -> Self {
+        let rigid_body = RigidBodyBuilder::fixed()
+            .translation(vector![position.0 as f32, position.1 as f32])
+            .user_data(BodyIdentity::Obstacle.encode())
+            .build();
+        let body_handle = physics_engine.bodies.insert(rigid_body);
+        physics_engine.colliders.insert_with_parent(shape.collider(), body_handle, &mut physics_engine.bodies);
+
         Self {
             position,
-            collider_handle,
+            shape,
+            body_handle,
         }
     }
+
+    /// Removes the body and the collider of the obstacle from the physics engine.
+    ///
+    /// # Parameters
+    /// - `physics_engine`: A mutable reference to the physics engine.
+    pub fn remove(&self, physics_engine: &mut PhysicsEngine) {
+        physics_engine.bodies.remove(
+            self.body_handle,
+            &mut physics_engine.islands,
+            &mut physics_engine.colliders,
+            &mut physics_engine.impulse_joints,
+            &mut physics_engine.multibody_joints,
+            true,
+        );
+    }
+
+    /// Returns the distance from a point to the edge of the obstacle, 0 inside it.
+    pub fn distance(&self, point: (f32, f32)) -> f32 {
+        self.shape.distance((self.position.0 as f32, self.position.1 as f32), point)
+    }
 }
@@ -0,0 +1,90 @@
+use std::fmt;
+
+/// What a body or a parentless collider of the arena is, kept in its `user_data`.
+///
+/// The kind is written in the highest byte and the id, if any, in the lowest 32 bits,
+/// so a `user_data` left at 0 reads as `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyIdentity {
+    /// Nothing was written, e.g. a body created by a test or a future feature.
+    Unknown,
+    /// The body of an entity, with its id.
+    Entity(u32),
+    /// The body of a bullet, with the id of the entity that fired it, `None` once it left the arena.
+    Bullet(Option<u32>),
+    /// The collider of an obstacle.
+    Obstacle,
+    /// A collider closing the arena.
+    Boundary,
+    /// The sensor of a power-up.
+    PowerUp,
+    /// The sensor of a mine, with the id of the entity that laid it.
+    Mine(u32),
+    /// The sensor of a control point.
+    ControlPoint,
+    /// The sensor of a hazard.
+    Hazard,
+}
+
+impl BodyIdentity {
+    /// The bit the kind starts at.
+    const KIND_SHIFT: u32 = 120;
+
+    /// Packs the identity into a `user_data`, read back by `decode`.
+    pub fn encode(self) -> u128 {
+        let (kind, id): (u8, u32) = match self {
+            BodyIdentity::Unknown => (0, 0),
+            BodyIdentity::Entity(id) => (1, id),
+            // Les identifiants d'entité commencent à 1, 0 marque une balle orpheline
+            BodyIdentity::Bullet(shooter) => (2, shooter.unwrap_or(0)),
+            BodyIdentity::Obstacle => (3, 0),
+            BodyIdentity::Boundary => (4, 0),
+            BodyIdentity::PowerUp => (5, 0),
+            BodyIdentity::Mine(owner) => (6, owner),
+            BodyIdentity::ControlPoint => (7, 0),
+            BodyIdentity::Hazard => (8, 0),
+        };
+        (kind as u128) << BodyIdentity::KIND_SHIFT | id as u128
+    }
+
+    /// Reads an identity written by `encode`, `Unknown` for any other value.
+    ///
+    /// # Parameters
+    /// - `user_data`: The user data of a body or a collider.
+    pub fn decode(user_data: u128) -> Self {
+        let id = user_data as u32;
+        match (user_data >> BodyIdentity::KIND_SHIFT) as u8 {
+            1 => BodyIdentity::Entity(id),
+            2 => BodyIdentity::Bullet(Some(id).filter(|&id| id != 0)),
+            3 => BodyIdentity::Obstacle,
+            4 => BodyIdentity::Boundary,
+            5 => BodyIdentity::PowerUp,
+            6 => BodyIdentity::Mine(id),
+            7 => BodyIdentity::ControlPoint,
+            8 => BodyIdentity::Hazard,
+            _ => BodyIdentity::Unknown,
+        }
+    }
+
+    /// Returns whether bullets bounce off it: an obstacle or an arena boundary.
+    pub fn is_wall(self) -> bool {
+        matches!(self, BodyIdentity::Obstacle | BodyIdentity::Boundary)
+    }
+}
+
+impl fmt::Display for BodyIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyIdentity::Unknown => write!(f, "unknown"),
+            BodyIdentity::Entity(id) => write!(f, "entity {}", id),
+            BodyIdentity::Bullet(Some(shooter)) => write!(f, "bullet of entity {}", shooter),
+            BodyIdentity::Bullet(None) => write!(f, "orphan bullet"),
+            BodyIdentity::Obstacle => write!(f, "obstacle"),
+            BodyIdentity::Boundary => write!(f, "boundary"),
+            BodyIdentity::PowerUp => write!(f, "power-up"),
+            BodyIdentity::Mine(owner) => write!(f, "mine of entity {}", owner),
+            BodyIdentity::ControlPoint => write!(f, "control point"),
+            BodyIdentity::Hazard => write!(f, "hazard"),
+        }
+    }
+}
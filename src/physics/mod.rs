@@ -1 +1,2 @@
+pub mod body_identity;
 pub mod physics;
@@ -5,6 +5,8 @@ use rapier2d::prelude::*;
 use rapier2d::prelude::{ChannelEventCollector, CollisionEvent};
 
 use crate::app_defines::AppDefines;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::physics::body_identity::BodyIdentity;
 
 /// Represents the physics engine and its components.
 pub struct PhysicsEngine {
@@ -22,11 +24,32 @@ pub struct PhysicsEngine {
     pub query_pipeline: QueryPipeline,
     pub start_time: Instant,
     pub loop_duration: Duration,
+    /// The contacts between solid colliders that started or stopped during the last step.
     pub collision_events: Vec<CollisionEvent>,
+    /// The intersections with a sensor that started or stopped during the last step, see `insert_sensor`.
+    pub sensor_events: Vec<CollisionEvent>,
+    /// The sensors removed since the last step, whose end of intersection the next step reports.
+    removed_sensors: Vec<ColliderHandle>,
     pub event_receiver: Receiver<CollisionEvent>,
+    /// The contact forces above the threshold of their colliders during the last step.
+    pub contact_force_events: Vec<ContactForceEvent>,
+    pub contact_receiver: Receiver<ContactForceEvent>,
     pub event_collector: ChannelEventCollector,
+    /// The colliders closing the arena, see `setup_boundaries`.
+    pub boundary_colliders: Vec<ColliderHandle>,
+    /// The thickness of the walls built by `setup_boundaries`.
+    pub wall_thickness: f32,
+    /// The number of steps per simulated second, kept exact rather than computed back from `dt`.
+    tick_rate: f32,
+    /// The disabled bodies of gone bullets and the step from which they can fly again, see `release_bullet`.
+    bullet_pool: Vec<(RigidBodyHandle, u64)>,
+    /// The most bodies `bullet_pool` keeps, the others are removed.
+    bullet_pool_capacity: usize,
 }
 
+/// Where the disabled bodies of the bullet pool wait, far outside any arena.
+const BULLET_PARKING: Vector<f32> = Vector::new(-1.0e6, -1.0e6);
+
 impl Default for PhysicsEngine {
     /// Creates a new default `PhysicsEngine` instance.
     ///
@@ -34,12 +57,15 @@ impl Default for PhysicsEngine {
     /// A new instance of `PhysicsEngine` with default settings.
     fn default() -> Self {
         let (collision_sender, collision_receiver): (Sender<CollisionEvent>, Receiver<CollisionEvent>) = unbounded();
-        let (contact_sender, _contact_receiver): (Sender<ContactForceEvent>, Receiver<ContactForceEvent>) = unbounded();
+        let (contact_sender, contact_receiver): (Sender<ContactForceEvent>, Receiver<ContactForceEvent>) = unbounded();
 
         Self {
             physics_pipeline: PhysicsPipeline::new(),
             gravity: vector![0.0, 0.0],
-            integration_parameters: IntegrationParameters::default(),
+            integration_parameters: IntegrationParameters {
+                dt: 1.0 / AppDefines::TARGET_FPS_2D_PHYSICS,
+                ..IntegrationParameters::default()
+            },
             islands: IslandManager::new(),
             broad_phase: DefaultBroadPhase::new(),
             narrow_phase: NarrowPhase::new(),
@@ -52,8 +78,17 @@ impl Default for PhysicsEngine {
             start_time: Instant::now(),
             loop_duration: Duration::new(5, 0),
             collision_events: Vec::new(),
+            sensor_events: Vec::new(),
+            removed_sensors: Vec::new(),
             event_collector: ChannelEventCollector::new(collision_sender, contact_sender),
             event_receiver: collision_receiver,
+            boundary_colliders: Vec::new(),
+            wall_thickness: AppDefines::DEFAULT_WALL_THICKNESS,
+            contact_force_events: Vec::new(),
+            contact_receiver,
+            tick_rate: AppDefines::TARGET_FPS_2D_PHYSICS,
+            bullet_pool: Vec::new(),
+            bullet_pool_capacity: AppDefines::BULLET_POOL_CAPACITY,
         }
     }
 }
@@ -61,9 +96,14 @@ impl Default for PhysicsEngine {
 impl PhysicsEngine {
     /// Advances the physics simulation by one step.
     ///
-    /// Clears previous collision events and updates the physics world.
-    pub fn step(&mut self) {
+    /// Clears previous collision, sensor and contact force events and updates the physics world.
+    ///
+    /// # Parameters
+    /// - `hooks`: The filter of the contacts of the colliders asking for it.
+    pub fn step(&mut self, hooks: &dyn PhysicsHooks) {
         self.collision_events.clear();
+        self.sensor_events.clear();
+        self.contact_force_events.clear();
 
         self.physics_pipeline.step(
             &self.gravity,
@@ -77,40 +117,211 @@ impl PhysicsEngine {
             &mut self.multibody_joints,
             &mut self.ccd_solver,
             Some(&mut self.query_pipeline),
-            &(),
+            hooks,
             &self.event_collector,
         );
 
         // Récupère tous les événements générés
+        let removed_sensors = std::mem::take(&mut self.removed_sensors);
         while let Ok(event) = self.event_receiver.try_recv() {
-            self.collision_events.push(event);
+            if removed_sensors.contains(&event.collider1()) || removed_sensors.contains(&event.collider2()) {
+                continue;
+            }
+            if event.sensor() {
+                self.sensor_events.push(event);
+            } else {
+                self.collision_events.push(event);
+            }
+        }
+        while let Ok(event) = self.contact_receiver.try_recv() {
+            self.contact_force_events.push(event);
         }
     }
 
-    /// Sets up the boundary colliders for the simulation area.
-    pub fn setup_boundaries(&mut self) {
-        let half_extents = vector![AppDefines::ARENA_WIDTH / 2.0, AppDefines::ARENA_HEIGHT / 2.0];
-        let top_boundary = ColliderBuilder::cuboid(half_extents.x, 1.0)
-            .translation(vector![half_extents.x, AppDefines::ARENA_HEIGHT])
-            .build();
-        let bottom_boundary = ColliderBuilder::cuboid(half_extents.x, 1.0)
-            .translation(vector![half_extents.x, 0.0])
-            .build();
-        let left_boundary = ColliderBuilder::cuboid(1.0, half_extents.y)
-            .translation(vector![0.0, half_extents.y])
-            .build();
-        let right_boundary = ColliderBuilder::cuboid(1.0, half_extents.y)
-            .translation(vector![AppDefines::ARENA_WIDTH, half_extents.y])
+    /// Sets the number of steps per simulated second, the duration of a step being its inverse.
+    ///
+    /// The timers of the game are counted in steps, so the rate is set once, before the match starts.
+    ///
+    /// # Parameters
+    /// - `hz`: The number of steps per second.
+    pub fn set_tick_rate(&mut self, hz: f32) {
+        self.tick_rate = hz;
+        self.integration_parameters.dt = 1.0 / hz;
+    }
+
+    /// Returns the number of steps per simulated second.
+    pub fn tick_rate(&self) -> f32 {
+        self.tick_rate
+    }
+
+    /// Adds a fixed trigger volume reporting in `sensor_events` the colliders entering and leaving it.
+    ///
+    /// # Parameters
+    /// - `shape`: The shape of the volume.
+    /// - `position`: The center of the volume.
+    /// - `identity`: What the volume belongs to, see `identify_collider`.
+    ///
+    /// # Returns
+    /// The handle of the sensor, to give to `remove_sensor`.
+    pub fn insert_sensor(&mut self, shape: SharedShape, position: Vector<f32>, identity: BodyIdentity) -> ColliderHandle {
+        let collider = ColliderBuilder::new(shape)
+            .translation(position)
+            .sensor(true)
+            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .user_data(identity.encode())
             .build();
+        self.colliders.insert(collider)
+    }
+
+    /// Removes a sensor and every event involving it, those of the last step and the end of
+    /// intersection the next step reports, so that no one reacts to a removed volume.
+    ///
+    /// # Parameters
+    /// - `handle`: The handle returned by `insert_sensor`.
+    pub fn remove_sensor(&mut self, handle: ColliderHandle) {
+        self.colliders.remove(handle, &mut self.islands, &mut self.bodies, true);
+        self.removed_sensors.push(handle);
+        let involved = |event: &CollisionEvent| event.collider1() == handle || event.collider2() == handle;
+        self.sensor_events.retain(|event| !involved(event));
+        self.collision_events.retain(|event| !involved(event));
+    }
+
+    /// Takes the body of a gone bullet out of the game, keeping it for a next shot while the pool has room.
+    ///
+    /// Inserting and removing a body and its collider at every shot costs more than moving it: a kept body
+    /// is disabled and parked far outside the arena, where it takes part in no step, event or query,
+    /// until `take_pooled_bullet` hands it to `Bullet::new` again. Beyond `bullet_pool_capacity`, the body is removed.
+    ///
+    /// # Parameters
+    /// - `handle`: The body of the bullet, no longer among the bullets of the game.
+    /// - `tick`: The current simulation step.
+    pub fn release_bullet(&mut self, handle: RigidBodyHandle, tick: u64) {
+        if self.bullet_pool.len() >= self.bullet_pool_capacity || !self.bodies.contains(handle) {
+            self.bodies.remove(handle, &mut self.islands, &mut self.colliders, &mut self.impulse_joints, &mut self.multibody_joints, true);
+            return;
+        }
+        let body = &mut self.bodies[handle];
+        body.set_enabled(false);
+        body.set_linvel(Vector::zeros(), false);
+        body.set_translation(BULLET_PARKING, false);
+        body.user_data = BodyIdentity::Unknown.encode();
+        // Le collider quitte aussitôt sa place : les requêtes d'ici le prochain pas ne le touchent plus
+        for &collider in body.colliders() {
+            let collider = &mut self.colliders[collider];
+            collider.set_enabled(false);
+            collider.set_translation(BULLET_PARKING);
+        }
+        // Un pas entier passe avant le prochain tir, le temps d'oublier les contacts du corps
+        self.bullet_pool.push((handle, tick + 2));
+    }
+
+    /// Returns a body of the bullet pool ready to fly again, still disabled, see `release_bullet`.
+    ///
+    /// # Parameters
+    /// - `tick`: The current simulation step.
+    pub fn take_pooled_bullet(&mut self, tick: u64) -> Option<RigidBodyHandle> {
+        let index = self.bullet_pool.iter().position(|&(_, ready_at)| ready_at <= tick)?;
+        Some(self.bullet_pool.swap_remove(index).0)
+    }
 
-        self.colliders.insert(top_boundary);
-        self.colliders.insert(bottom_boundary);
-        self.colliders.insert(left_boundary);
-        self.colliders.insert(right_boundary);
+    /// Returns the number of bodies waiting in the bullet pool.
+    pub fn pooled_bullets(&self) -> usize {
+        self.bullet_pool.len()
+    }
+
+    /// Changes the most bodies the bullet pool keeps, removing those over it. 0 turns the pool off.
+    ///
+    /// # Parameters
+    /// - `capacity`: The number of bodies, `BULLET_POOL_CAPACITY` by default.
+    pub fn set_bullet_pool_capacity(&mut self, capacity: usize) {
+        self.bullet_pool_capacity = capacity;
+        while self.bullet_pool.len() > capacity {
+            let (handle, _) = self.bullet_pool.pop().unwrap();
+            self.bodies.remove(handle, &mut self.islands, &mut self.colliders, &mut self.impulse_joints, &mut self.multibody_joints, true);
+        }
+    }
+
+    /// Returns what a rigid body belongs to, `Unknown` for a removed body.
+    ///
+    /// # Parameters
+    /// - `handle`: The handle of the body.
+    pub fn identify(&self, handle: RigidBodyHandle) -> BodyIdentity {
+        self.bodies.get(handle).map_or(BodyIdentity::Unknown, |body| BodyIdentity::decode(body.user_data))
+    }
+
+    /// Returns what a collider belongs to: its body for a collider with a parent, itself otherwise.
+    ///
+    /// # Parameters
+    /// - `handle`: The handle of the collider.
+    pub fn identify_collider(&self, handle: ColliderHandle) -> BodyIdentity {
+        match self.colliders.get(handle) {
+            Some(collider) => match collider.parent() {
+                Some(parent) => self.identify(parent),
+                None => BodyIdentity::decode(collider.user_data),
+            },
+            None => BodyIdentity::Unknown,
+        }
+    }
+
+    /// Casts a ray and returns the first collider it hits.
+    ///
+    /// The query pipeline is refreshed by every step, and by `refresh_queries`: colliders
+    /// added or moved since then are not seen yet.
+    ///
+    /// # Parameters
+    /// - `from`: The origin of the ray.
+    /// - `direction`: The direction of the ray, of length 1.
+    /// - `max_distance`: How far the ray goes.
+    /// - `filter`: The colliders the ray may hit.
+    ///
+    /// # Returns
+    /// The collider hit and its distance, `None` if the ray hits nothing within `max_distance`.
+    pub fn raycast(&self, from: Vector<f32>, direction: Vector<f32>, max_distance: f32, filter: QueryFilter) -> Option<(ColliderHandle, f32)> {
+        let ray = Ray::new(point![from.x, from.y], direction);
+        self.query_pipeline.cast_ray(&self.bodies, &self.colliders, &ray, max_distance, true, filter)
+    }
+
+    /// Rebuilds the query pipeline from the colliders, so that the colliders added or removed
+    /// outside a step are seen by the queries at once.
+    pub fn refresh_queries(&mut self) {
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+    }
+
+    /// Sets up the boundary colliders for the simulation area, replacing the previous ones.
+    ///
+    /// The walls are `wall_thickness` thick and lie just outside the arena, so the whole
+    /// of (0..width)×(0..height) is playable. The top and bottom walls run over the corners,
+    /// leaving no gap where the side walls meet them.
+    ///
+    /// # Parameters
+    /// - `arena`: The size of the arena the walls close.
+    pub fn setup_boundaries(&mut self, arena: &ArenaConfig) {
+        for handle in self.boundary_colliders.drain(..) {
+            self.colliders.remove(handle, &mut self.islands, &mut self.bodies, true);
+        }
+
+        let half_thickness = self.wall_thickness / 2.0;
+        let (half_width, half_height) = (arena.width / 2.0, arena.height / 2.0);
+        // Centre et demi-dimensions de chaque mur, collé à l'extérieur de l'arène
+        let walls = [
+            (vector![half_width, arena.height + half_thickness], half_width + self.wall_thickness, half_thickness),
+            (vector![half_width, -half_thickness], half_width + self.wall_thickness, half_thickness),
+            (vector![-half_thickness, half_height], half_thickness, half_height),
+            (vector![arena.width + half_thickness, half_height], half_thickness, half_height),
+        ];
+
+        for (center, half_x, half_y) in walls {
+            let boundary = ColliderBuilder::cuboid(half_x, half_y)
+                .translation(center)
+                .user_data(BodyIdentity::Boundary.encode())
+                .build();
+            let handle = self.colliders.insert(boundary);
+            self.boundary_colliders.push(handle);
+        }
     }
 
     /// Sets up the physics simulation, including boundaries.
     pub fn setup_physics(&mut self) {
-        self.setup_boundaries();
+        self.setup_boundaries(&ArenaConfig::default());
     }
 }
@@ -0,0 +1,68 @@
+use rapier2d::prelude::*;
+
+use crate::app_defines::AppDefines;
+use crate::physics::body_identity::BodyIdentity;
+use crate::physics::physics::PhysicsEngine;
+
+/// The effect of a power-up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Restores some health.
+    Heal,
+    /// Makes the entity faster for a while.
+    SpeedBoost,
+    /// Halves the fire cooldown of the entity for a while.
+    RapidFire,
+    /// Adds some armor, up to `ARMOR_MAX`.
+    ArmorUp,
+}
+
+impl PowerUpKind {
+    /// Every kind, in the order they are drawn at random. `ArmorUp` comes last, as it is only drawn
+    /// while `GameConfig::armor_power_ups` is on.
+    pub const ALL: [PowerUpKind; 4] = [PowerUpKind::Heal, PowerUpKind::SpeedBoost, PowerUpKind::RapidFire, PowerUpKind::ArmorUp];
+
+    /// Returns the name of the kind, as shown in the logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::Heal => "heal",
+            PowerUpKind::SpeedBoost => "speed boost",
+            PowerUpKind::RapidFire => "rapid fire",
+            PowerUpKind::ArmorUp => "armor",
+        }
+    }
+}
+
+/// Represents a power-up waiting to be picked up.
+///
+/// A power-up is a sensor collider: entities drive through it and pick it up on contact.
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub position: (f64, f64),
+    pub collider_handle: ColliderHandle,
+}
+
+impl PowerUp {
+    /// Creates a new power-up and adds its sensor to the physics engine.
+    ///
+    /// # Parameters
+    /// - `kind`: The effect of the power-up.
+    /// - `position`: A tuple representing the (x, y) position of the power-up.
+    /// - `physics_engine`: A mutable reference to the physics engine.
+    ///
+    /// # Returns
+    /// A new instance of `PowerUp`.
+    pub fn new(kind: PowerUpKind, position: (f64, f64), physics_engine: &mut PhysicsEngine) -> Self {
+        let collider_handle = physics_engine.insert_sensor(
+            SharedShape::ball(AppDefines::POWER_UP_RADIUS),
+            vector![position.0 as f32, position.1 as f32],
+            BodyIdentity::PowerUp,
+        );
+
+        Self {
+            kind,
+            position,
+            collider_handle,
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::command::GameCommand;
+use crate::game_logic::GameLogic;
+use crate::server::server_thread::ServerThread;
+use crate::types::{add_message, export_messages, ExportFormat, MessageOrigin, MessageType};
+
+/// A struct accepting the connections of the admin control port.
+///
+/// The admin tooling speaks its own line protocol, separate from the player protocol:
+/// a leaked admin password no longer lets a bot run admin commands, and changes to the
+/// parsing of the player commands cannot break the tooling. The port listens on the
+/// loopback address by default, which is what keeps other hosts out: it has no password.
+pub(crate) struct AdminPort {
+    listener: TcpListener,
+    server: ServerThread,
+}
+
+impl AdminPort {
+    /// Creates the admin control port.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The listener of the port.
+    /// * `server` - The server the commands act on.
+    ///
+    /// # Returns
+    ///
+    /// A new `AdminPort`.
+    ///
+    pub fn new(listener: TcpListener, server: ServerThread) -> Self {
+        AdminPort { listener, server }
+    }
+
+    /// Accepts admin connections until the server stops, each one on a server thread of its own.
+    pub fn run(&self) {
+        // Une écoute bloquante ne verrait jamais l'arrêt du serveur
+        if let Err(e) = self.listener.set_nonblocking(true) {
+            add_message(&self.server.messages, format!("[ERROR] Could not poll the admin port: {}", e), MessageType::Error, MessageOrigin::Admin);
+        }
+        while !self.server.stopping.load(Ordering::SeqCst) {
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    add_message(&self.server.messages, format!("[ADMIN] Admin tool connected from {}.", peer), MessageType::Info, MessageOrigin::Admin);
+                    let mut session = AdminSession { stream, peer, server: self.server.clone() };
+                    self.server.spawn_thread(move || session.run());
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(AppDefines::ACCEPT_POLL_MS));
+                }
+                Err(e) => {
+                    add_message(&self.server.messages, format!("[ERROR] Admin connection failed: {}", e), MessageType::Error, MessageOrigin::Admin);
+                }
+            }
+        }
+    }
+}
+
+/// A struct handling one connection of the admin control port.
+///
+/// Each line holds a command and its arguments separated by spaces, and is answered
+/// by one line: `OK` followed by the result, or `ERR` followed by the reason.
+struct AdminSession {
+    stream: TcpStream,
+    peer: SocketAddr,
+    server: ServerThread,
+}
+
+impl AdminSession {
+    /// Answers the commands of the connection until it closes or the server stops.
+    fn run(&mut self) {
+        // Les lectures rendent la main pour voir l'arrêt du serveur
+        let _ = self.stream.set_nonblocking(false);
+        let _ = self.stream.set_read_timeout(Some(Duration::from_millis(AppDefines::ACCEPT_POLL_MS)));
+        let _ = self.stream.set_write_timeout(Some(Duration::from_millis(AppDefines::SOCKET_WRITE_TIMEOUT_MS)));
+        let mut pending: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !self.server.stopping.load(Ordering::SeqCst) {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => pending.extend_from_slice(&chunk[..read]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => continue,
+                Err(_) => break,
+            }
+            while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reply = match self.execute(line.trim()) {
+                    Ok(text) => format!("OK {}\n", text),
+                    Err(reason) => format!("ERR {}\n", reason),
+                };
+                if self.stream.write_all(reply.as_bytes()).is_err() {
+                    return;
+                }
+            }
+            if pending.len() > AppDefines::MAX_LINE_LENGTH {
+                let _ = self.stream.write_all(b"ERR Line too long\n");
+                break;
+            }
+        }
+        add_message(&self.server.messages, format!("[ADMIN] Admin tool {} disconnected.", self.peer), MessageType::Info, MessageOrigin::Admin);
+    }
+
+    /// Executes one command.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The command and its arguments, separated by spaces.
+    ///
+    /// # Returns
+    ///
+    /// The result of the command, or why it failed.
+    ///
+    fn execute(&self, line: &str) -> Result<String, String> {
+        let mut parts = line.split_whitespace();
+        let code = parts.next().unwrap_or("").to_ascii_uppercase();
+        let args: Vec<&str> = parts.collect();
+        let by = format!("the admin port ({})", self.peer);
+
+        match code.as_str() {
+            AppDefines::CONTROL_STATUS => Ok(self.status()),
+
+            AppDefines::CONTROL_KICK => {
+                let target = args.first().ok_or("Missing name or address")?;
+                self.kick(target, &by)
+            }
+
+            AppDefines::CONTROL_BAN => {
+                let ip = args.first().ok_or("Missing address")?.parse::<IpAddr>().map_err(|_| "Invalid IP address")?;
+                let banned = self.server.connections.lock().unwrap().ban(ip);
+                for client_id in &banned {
+                    let entity_id = self.server.client_entity_map.lock().unwrap().get(client_id).copied();
+                    if let Some(entity_id) = entity_id {
+                        self.server.commands.ask(|reply| GameCommand::Remove { entity_id, reply });
+                        self.server.sessions.lock().unwrap().close(entity_id);
+                    }
+                }
+                add_message(
+                    &self.server.messages,
+                    format!("[ADMIN] Address {} banned from {}, {} connection(s) closed.", ip, by, banned.len()),
+                    MessageType::Warning,
+                    MessageOrigin::Admin,
+                );
+                Ok(format!("{} banned, {} connection(s) closed", ip, banned.len()))
+            }
+
+            AppDefines::CONTROL_PAUSE => {
+                self.server.commands.update(GameLogic::pause);
+                add_message(&self.server.messages, format!("[ADMIN] Simulation paused from {}.", by), MessageType::Info, MessageOrigin::Admin);
+                Ok("Simulation paused".to_string())
+            }
+
+            AppDefines::CONTROL_RESUME => {
+                self.server.commands.update(GameLogic::resume);
+                add_message(&self.server.messages, format!("[ADMIN] Simulation resumed from {}.", by), MessageType::Info, MessageOrigin::Admin);
+                Ok("Simulation resumed".to_string())
+            }
+
+            AppDefines::CONTROL_RESET => {
+                self.server.commands.update(GameLogic::reset_simulation);
+                add_message(&self.server.messages, format!("[ADMIN] Simulation reset from {}.", by), MessageType::Info, MessageOrigin::Admin);
+                Ok("Simulation reset".to_string())
+            }
+
+            AppDefines::CONTROL_GENERATE_MAP => {
+                let seed = match args.first() {
+                    Some(seed) => Some(seed.parse::<u64>().map_err(|_| "The seed must be a positive integer")?),
+                    None => None,
+                };
+                let seed = self.server.commands.update(move |logic| {
+                    logic.generate_map(seed);
+                    logic.map_seed().unwrap_or_default()
+                });
+                add_message(&self.server.messages, format!("[ADMIN] Map {} generated from {}.", seed, by), MessageType::Info, MessageOrigin::Admin);
+                Ok(format!("Map generated, seed {}", seed))
+            }
+
+            AppDefines::CONTROL_SET_CONFIG => {
+                let (Some(key), Some(value)) = (args.first(), args.get(1)) else {
+                    return Err("Usage: SET_CONFIG <key> <value>".to_string());
+                };
+                let (rule, setting) = (key.to_string(), value.to_string());
+                self.server.commands.update(move |logic| logic.game_config.set(&rule, &setting))?;
+                add_message(&self.server.messages, format!("[ADMIN] Rule {} set to {} from {}.", key, value, by), MessageType::Info, MessageOrigin::Admin);
+                Ok(format!("{}={}", key, value))
+            }
+
+            AppDefines::CONTROL_DUMP => {
+                let path = args.first().ok_or("Usage: DUMP <path> [text|json]")?;
+                let format = match args.get(1) {
+                    Some(name) => ExportFormat::from_name(name).ok_or_else(|| format!("Unknown format {}, expected text or json", name))?,
+                    None => ExportFormat::Text,
+                };
+                let messages = self.server.messages.log().to_vec();
+                let written = export_messages(&messages, Path::new(path), format)?;
+                add_message(&self.server.messages, format!("[ADMIN] Messages exported to {} from {}.", path, by), MessageType::Info, MessageOrigin::Admin);
+                Ok(format!("{} messages written to {}", written, path))
+            }
+
+            AppDefines::CONTROL_LAST_REPORT => {
+                let report = self.server.commands.query(|logic| logic.last_report().map(serde_json::to_string));
+                report.ok_or("No round has ended yet")?.map_err(|e| e.to_string())
+            }
+
+            AppDefines::CONTROL_AI_BEHAVIOR => {
+                let Some(name) = args.first() else {
+                    let names = self.server.commands.query(|logic| logic.behavior_registry.names().join("|"));
+                    return Err(format!("Usage: AI_BEHAVIOR <{}>", names));
+                };
+                // Les alias, comme "aggressive", sont répondus sous le nom du comportement
+                let behavior = name.to_string();
+                let (switched, name) = self.server.commands.update(move |logic| {
+                    logic.swap_behavior(&behavior).map(|switched| (switched, logic.default_behavior().to_string()))
+                })?;
+                add_message(&self.server.messages, format!("[ADMIN] AI behavior switched to {} from {}.", name, by), MessageType::Info, MessageOrigin::Admin);
+                Ok(format!("{} AI entities switched to {}", switched, name))
+            }
+
+            AppDefines::CONTROL_SHUTDOWN => {
+                add_message(&self.server.messages, format!("[ADMIN] Server shut down from {}.", by), MessageType::Warning, MessageOrigin::Admin);
+                // L'arrêt attend les threads du serveur, dont celui-ci : il se fait à côté, après la réponse
+                let server = self.server.clone();
+                thread::spawn(move || {
+                    // `stop` écrit lui-même le nombre de clients prévenus dans les messages
+                    server.stop();
+                    std::process::exit(0);
+                });
+                Ok("Server stopping".to_string())
+            }
+
+            "" => Err("Empty command".to_string()),
+            _ => Err(format!("Unknown command {}", code)),
+        }
+    }
+
+    /// Dumps the health of the server as `key=value` pairs, separated by spaces.
+    fn status(&self) -> String {
+        let metrics = self.server.metrics.sample();
+        let (paused, map_seed) = self.server.commands.query(|logic| (logic.is_paused(), logic.map_seed()));
+        let (lobby_locked, max_clients) = {
+            let settings = self.server.settings.lock().unwrap();
+            (settings.lobby_locked, settings.max_clients)
+        };
+        format!(
+            "clients={} max_clients={} players={} ai={} bullets={} tick_rate={:.1} target_tick_rate={:.1} step_ms={:.2} uptime_secs={} paused={} lobby_locked={} map_seed={}",
+            metrics.connected_clients,
+            max_clients,
+            metrics.players,
+            metrics.ai_entities,
+            metrics.bullets,
+            metrics.tick_rate,
+            metrics.target_tick_rate,
+            metrics.average_step_ms,
+            metrics.uptime.as_secs(),
+            paused,
+            lobby_locked,
+            map_seed.map_or("none".to_string(), |seed| seed.to_string()),
+        )
+    }
+
+    /// Kicks a client by the address of its connection, or a player or an AI by the name of its entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The address of the client, e.g. `127.0.0.1:50000`, or the name of the entity.
+    /// * `by` - Who kicks, for the messages.
+    ///
+    /// # Returns
+    ///
+    /// The result of the command, or why nothing was kicked.
+    ///
+    fn kick(&self, target: &str, by: &str) -> Result<String, String> {
+        if let Ok(address) = target.parse::<SocketAddr>() {
+            let client_id = self.server.clients.lock().unwrap().rows().into_iter()
+                .find(|(client_id, info)| client_id.addr() == address && info.disconnected_at.is_none())
+                .map(|(client_id, _)| client_id);
+            return match client_id {
+                Some(client_id) if self.server.kick_client(&client_id, by) => Ok(format!("{} kicked", client_id)),
+                _ => Err(format!("No client connected from {}", address)),
+            };
+        }
+
+        let name = target.to_string();
+        let entity_id = self.server.commands.query(move |logic| logic.entity_by_name(&name).map(|e| e.id))
+            .ok_or_else(|| format!("No entity named {}", target))?;
+        let owner = self.server.client_entity_map.lock().unwrap().iter()
+            .find(|(_, id)| **id == entity_id)
+            .map(|(owner, _)| *owner);
+        // Une entité sans client connecté (IA, joueur en attente de reconnexion) est simplement retirée
+        if owner.map_or(true, |owner| !self.server.kick_client(&owner, by)) {
+            self.server.commands.ask(|reply| GameCommand::Remove { entity_id, reply });
+            self.server.sessions.lock().unwrap().close(entity_id);
+            add_message(
+                &self.server.messages,
+                format!("[ADMIN] Entity {} ({}) removed from {}.", entity_id, target, by),
+                MessageType::Warning,
+                MessageOrigin::Admin,
+            );
+        }
+        Ok(format!("{} kicked", target))
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::app_defines::AppDefines;
+use crate::server::client_handler::reply::{Reply, Value};
+use crate::server::client_handler::ClientHandler;
+use crate::server::client_id::ClientId;
+use crate::server::client_stats::ClientStatsRegistry;
+use crate::server::connections::ConnectionRegistry;
+use crate::types::{MessageOrigin, StyledMessage};
+
+/// A struct keeping the players muted in the chat by the server UI, by name.
+///
+/// The messages of a muted player are hidden from the chat tab. Those of a player also dropped
+/// on the server reach no other client: the player is still answered `OK`, and the server keeps
+/// its messages, so that unmuting shows them again.
+#[derive(Debug, Clone, Default)]
+pub struct ChatMutes {
+    /// Whether the messages of each muted player are also dropped on the server.
+    muted: HashMap<String, bool>,
+}
+
+impl ChatMutes {
+    /// Creates a list without any muted player.
+    pub fn new() -> Self {
+        ChatMutes::default()
+    }
+
+    /// Mutes a player, or changes whether its messages are dropped on the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the player, as it sends its messages.
+    /// * `drop_on_server` - Whether its messages no longer reach the other clients.
+    ///
+    pub fn mute(&mut self, name: &str, drop_on_server: bool) {
+        self.muted.insert(name.to_string(), drop_on_server);
+    }
+
+    /// Unmutes a player, whose messages are shown and relayed again.
+    pub fn unmute(&mut self, name: &str) {
+        self.muted.remove(name);
+    }
+
+    /// Returns whether a player is muted.
+    pub fn is_muted(&self, name: &str) -> bool {
+        self.muted.contains_key(name)
+    }
+
+    /// Returns whether the messages of a player are dropped on the server.
+    pub fn drops(&self, name: &str) -> bool {
+        self.muted.get(name).copied().unwrap_or(false)
+    }
+
+    /// Returns whether a message is shown in the chat tab: a chat message of a player not muted.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - A message of the server.
+    ///
+    pub fn shows(&self, message: &StyledMessage) -> bool {
+        message.origin == MessageOrigin::Chat && message.chat().is_some_and(|(sender, _)| !self.is_muted(sender))
+    }
+}
+
+/// Checks the text of a chat message: 1 to `MESSAGE_LENGTH` characters, without control characters.
+///
+/// # Arguments
+///
+/// * `text` - The text sent.
+///
+/// # Returns
+///
+/// Why the text is refused, as sent in the `ERR` reply.
+///
+pub(crate) fn check_text(text: &str) -> Result<(), &'static str> {
+    let length = text.chars().count();
+    if length == 0 || length > AppDefines::MESSAGE_LENGTH as usize {
+        return Err("Invalid message length");
+    }
+    if text.chars().any(char::is_control) {
+        return Err("Invalid message characters");
+    }
+    Ok(())
+}
+
+/// Pushes a chat message to the connected clients as `MSG=<sender>=<text>`.
+///
+/// The writers are taken out of the registry first, so that no lock of the registry is held
+/// while a slow client is written to.
+///
+/// # Arguments
+///
+/// * `connections` - The live connections.
+/// * `client_stats` - The traffic statistics, counting the bytes written.
+/// * `sender` - The name of the sender, see `MESSAGE`.
+/// * `text` - The text of the message, checked by `check_text`.
+/// * `except` - The client the message comes from, which is not pushed its own message.
+///
+/// # Returns
+///
+/// The number of clients the message was written to.
+///
+pub(crate) fn relay(
+    connections: &Mutex<ConnectionRegistry>,
+    client_stats: &Mutex<ClientStatsRegistry>,
+    sender: &str,
+    text: &str,
+    except: Option<ClientId>,
+) -> usize {
+    let notice = Reply::Query {
+        cmd: AppDefines::MESSAGE,
+        fields: vec![("sender", Value::Text(sender.to_string())), ("text", Value::Text(text.to_string()))],
+    };
+    let writers = connections.lock().unwrap().writers();
+    let mut reached = 0;
+    for (client_id, writer, encoding) in writers {
+        if Some(client_id) == except {
+            continue;
+        }
+        let line = if encoding.json { notice.to_json().to_string() } else { notice.to_legacy() };
+        let Some(bytes) = ClientHandler::encode_line(&line, encoding) else { continue };
+        // Comme la mort, un message n'est dit qu'une fois : on attend la réponse en cours
+        let Ok(mut writer) = writer.lock() else { continue };
+        if writer.write_all(&bytes).and_then(|_| writer.flush()).is_ok() {
+            client_stats.lock().unwrap().record_written(client_id, bytes.len());
+            reached += 1;
+        }
+    }
+    reached
+}
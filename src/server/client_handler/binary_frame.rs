@@ -0,0 +1,250 @@
+use crate::app_defines::AppDefines;
+use crate::server::client_handler::protocol_error::ProtocolError;
+
+/// The command id of the frames sent by the server, holding a reply line.
+pub(crate) const REPLY_ID: u8 = 0xFF;
+
+/// Enum representing the payload layout of a framed command.
+#[derive(Clone, Copy)]
+enum Payload {
+    /// No payload.
+    Empty,
+    /// A little-endian f32.
+    Float,
+    /// A little-endian u32.
+    Integer,
+    /// A u16 little-endian length followed by UTF-8 bytes.
+    Text,
+}
+
+/// The framed commands: id, command code and payload layout.
+const COMMANDS: [(u8, &str, Payload); 30] = [
+    (0x01, AppDefines::ACTUATOR_MOTOR_LEFT, Payload::Float),
+    (0x02, AppDefines::ACTUATOR_MOTOR_RIGHT, Payload::Float),
+    (0x03, AppDefines::ACTUATOR_GUN_TRIGGER, Payload::Float),
+    (0x04, AppDefines::ACTUATOR_GUN_TRAVERSE, Payload::Float),
+    (0x05, AppDefines::ACTUATOR_SHIELD, Payload::Float),
+    (0x10, AppDefines::SET_NAME, Payload::Text),
+    (0x11, AppDefines::SET_COLOR, Payload::Text),
+    (0x12, AppDefines::QUIT, Payload::Empty),
+    (0x13, AppDefines::SET_TEAM, Payload::Integer),
+    (0x14, AppDefines::DEPLOY_MINE, Payload::Empty),
+    (0x20, AppDefines::QUERY_ENERGY, Payload::Empty),
+    (0x21, AppDefines::QUERY_WORLD, Payload::Empty),
+    (0x22, AppDefines::QUERY_TOKEN, Payload::Empty),
+    (0x23, AppDefines::PING, Payload::Text),
+    (0x24, AppDefines::QUERY_STATUS, Payload::Empty),
+    (0x25, AppDefines::QUERY_ARENA, Payload::Empty),
+    (0x26, AppDefines::QUERY_OBSTACLES, Payload::Empty),
+    (0x27, AppDefines::QUERY_LIDAR, Payload::Integer),
+    (0x28, AppDefines::QUERY_SENSORS, Payload::Empty),
+    (0x29, AppDefines::QUERY_MAP_SEED, Payload::Empty),
+    (0x2A, AppDefines::QUERY_MATCH, Payload::Empty),
+    (0x2B, AppDefines::QUERY_EVENTS, Payload::Empty),
+    (0x2C, AppDefines::QUERY_LEADERBOARD, Payload::Integer),
+    (0x2D, AppDefines::QUERY_ORIENTATION, Payload::Empty),
+    (0x2E, AppDefines::QUERY_ZONE, Payload::Empty),
+    (0x30, AppDefines::SUBSCRIBE, Payload::Integer),
+    (0x31, AppDefines::UNSUBSCRIBE, Payload::Empty),
+    (0x32, AppDefines::RECONNECT, Payload::Text),
+    (0x33, AppDefines::SPECTATE, Payload::Empty),
+    (0x34, AppDefines::FORMAT, Payload::Text),
+];
+
+/// Decodes the first frame of a buffer into a command in the legacy text format.
+///
+/// A frame is a u16 little-endian length, then as many bytes: a one-byte command
+/// id followed by its payload. The decoder never panics on malformed input.
+///
+/// # Arguments
+///
+/// * `buffer` - The bytes received so far.
+///
+/// # Returns
+///
+/// `None` if the buffer does not hold a complete frame yet. Otherwise the number of
+/// bytes consumed, with the decoded command or the reason it is invalid.
+///
+pub(crate) fn decode(buffer: &[u8]) -> Option<(usize, Result<String, ProtocolError>)> {
+    let length = u16::from_le_bytes([*buffer.first()?, *buffer.get(1)?]) as usize;
+    let frame = buffer.get(2..2 + length)?;
+    Some((2 + length, decode_command(frame)))
+}
+
+/// Decodes the id and payload of a frame.
+fn decode_command(frame: &[u8]) -> Result<String, ProtocolError> {
+    let (&id, payload) = frame.split_first().ok_or(ProtocolError::BadValue("Empty frame"))?;
+    let &(_, code, layout) = COMMANDS
+        .iter()
+        .find(|(command_id, _, _)| *command_id == id)
+        .ok_or_else(|| ProtocolError::UnknownCommand(format!("0x{:02X}", id)))?;
+
+    let argument = match layout {
+        Payload::Empty if payload.is_empty() => None,
+        Payload::Float => {
+            let bytes: [u8; 4] = payload.try_into().map_err(|_| ProtocolError::BadValue("Invalid float payload"))?;
+            Some(f32::from_le_bytes(bytes).to_string())
+        }
+        Payload::Integer => {
+            let bytes: [u8; 4] = payload.try_into().map_err(|_| ProtocolError::BadValue("Invalid integer payload"))?;
+            Some(u32::from_le_bytes(bytes).to_string())
+        }
+        Payload::Text => {
+            if payload.len() < 2 {
+                return Err(ProtocolError::BadValue("Invalid text payload"));
+            }
+            let (length, text) = payload.split_at(2);
+            if u16::from_le_bytes([length[0], length[1]]) as usize != text.len() {
+                return Err(ProtocolError::BadValue("Invalid text payload"));
+            }
+            let text = std::str::from_utf8(text).map_err(|_| ProtocolError::BadValue("Invalid UTF-8 text"))?;
+            // Le texte ne doit pas introduire d'autres arguments
+            if text.contains(AppDefines::ARGUMENT_SEP) {
+                return Err(ProtocolError::BadValue("Text must not contain the argument separator"));
+            }
+            Some(text.to_string())
+        }
+        Payload::Empty => return Err(ProtocolError::BadValue("Unexpected payload")),
+    };
+
+    Ok(match argument {
+        Some(argument) => format!("{}{}{}", code, AppDefines::ARGUMENT_SEP, argument),
+        None => code.to_string(),
+    })
+}
+
+/// Encodes a reply line into a frame.
+///
+/// # Arguments
+///
+/// * `line` - The reply line, without its trailing newline.
+///
+/// # Returns
+///
+/// The frame, or `None` if the line is too long to fit in one frame.
+///
+pub(crate) fn encode_reply(line: &str) -> Option<Vec<u8>> {
+    let length = u16::try_from(line.len() + 1).ok()?;
+    let mut frame = Vec::with_capacity(2 + length as usize);
+    frame.extend_from_slice(&length.to_le_bytes());
+    frame.push(REPLY_ID);
+    frame.extend_from_slice(line.as_bytes());
+    Some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// Encodes a command as a client would: its id, then its argument laid out as the table says.
+    fn encode(id: u8, layout: Payload, argument: &str) -> Vec<u8> {
+        let mut frame = vec![id];
+        match layout {
+            Payload::Empty => {}
+            Payload::Float => frame.extend_from_slice(&argument.parse::<f32>().unwrap().to_le_bytes()),
+            Payload::Integer => frame.extend_from_slice(&argument.parse::<u32>().unwrap().to_le_bytes()),
+            Payload::Text => {
+                frame.extend_from_slice(&(argument.len() as u16).to_le_bytes());
+                frame.extend_from_slice(argument.as_bytes());
+            }
+        }
+        let mut bytes = (frame.len() as u16).to_le_bytes().to_vec();
+        bytes.extend(frame);
+        bytes
+    }
+
+    /// Returns an argument of each layout, and the text the decoder turns it into.
+    fn sample(layout: Payload) -> (&'static str, Option<&'static str>) {
+        match layout {
+            Payload::Empty => ("", None),
+            Payload::Float => ("-0.75", Some("-0.75")),
+            Payload::Integer => ("4000000000", Some("4000000000")),
+            Payload::Text => ("Zoé", Some("Zoé")),
+        }
+    }
+
+    #[test]
+    fn every_command_decodes_back_to_its_text_form() {
+        for &(id, code, layout) in &COMMANDS {
+            let (argument, text) = sample(layout);
+            let frame = encode(id, layout, argument);
+            let expected = match text {
+                Some(text) => format!("{}{}{}", code, AppDefines::ARGUMENT_SEP, text),
+                None => code.to_string(),
+            };
+            match decode(&frame) {
+                Some((consumed, Ok(command))) => assert_eq!((consumed, command), (frame.len(), expected)),
+                _ => panic!("frame 0x{:02X} was not decoded", id),
+            }
+        }
+    }
+
+    #[test]
+    fn frames_are_decoded_one_at_a_time() {
+        let mut buffer = encode(0x01, Payload::Float, "1");
+        buffer.extend(encode(0x23, Payload::Text, "abc"));
+        let (consumed, first) = decode(&buffer).unwrap();
+        assert_eq!(first.ok().as_deref(), Some("MotL=1"));
+        let (_, second) = decode(&buffer[consumed..]).unwrap();
+        assert_eq!(second.ok().as_deref(), Some("PING=abc"));
+    }
+
+    #[test]
+    fn truncated_frames_wait_for_more_bytes() {
+        for &(id, _, layout) in &COMMANDS {
+            let frame = encode(id, layout, sample(layout).0);
+            for end in 0..frame.len() {
+                assert!(decode(&frame[..end]).is_none(), "frame 0x{:02X} cut at {} bytes", id, end);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_frames_are_rejected() {
+        let frames: [&[u8]; 7] = [
+            &[0, 0],
+            &[1, 0, 0x7F],
+            &[3, 0, 0x01, 0, 0],
+            &[2, 0, 0x12, 0],
+            &[5, 0, 0x10, 9, 0, b'a', b'b'],
+            &[4, 0, 0x10, 2, 0, 0xFF],
+            &[5, 0, 0x10, 2, 0, b'a', b'='],
+        ];
+        for frame in frames {
+            let (consumed, command) = decode(frame).unwrap();
+            assert_eq!(consumed, frame.len());
+            assert!(command.is_err(), "{:?} was accepted", frame);
+        }
+    }
+
+    #[test]
+    fn random_bytes_never_panic_the_decoder() {
+        let mut rng = StdRng::seed_from_u64(1768);
+        for _ in 0..20_000 {
+            let length = rng.random_range(0..48);
+            let mut buffer: Vec<u8> = (0..length).map(|_| rng.random()).collect();
+            // Une longueur plausible de temps en temps, pour atteindre le décodage des charges
+            if length >= 2 && rng.random_bool(0.5) {
+                buffer[0] = rng.random_range(0..length as u8);
+                buffer[1] = 0;
+            }
+            let mut rest = buffer.as_slice();
+            while let Some((consumed, _)) = decode(rest) {
+                assert!((2..=rest.len()).contains(&consumed));
+                rest = &rest[consumed..];
+            }
+        }
+    }
+
+    #[test]
+    fn replies_fit_in_one_frame_or_are_refused() {
+        let frame = encode_reply("OK=PONG").unwrap();
+        assert_eq!(frame[..3], [8, 0, REPLY_ID]);
+        assert_eq!(&frame[3..], b"OK=PONG");
+        assert!(encode_reply(&"x".repeat(u16::MAX as usize - 1)).is_some());
+        assert!(encode_reply(&"x".repeat(u16::MAX as usize)).is_none());
+    }
+}
@@ -1,110 +1,461 @@
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
 
 use crate::app_defines::AppDefines;
+use crate::bullet::bullet::BulletKind;
+use crate::entities::entity::Entity;
+use crate::game_logic::ai_behavior::AiDifficulty;
+use crate::game_logic::command::{ActuatorRefusal, GameCommand, GameCommands};
+use crate::game_logic::game_config::GameMode;
+use crate::game_logic::ghost_camera::GhostCamera;
+use crate::game_logic::match_state::MatchPhase;
+use crate::game_logic::name_index::NameError;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::step_timings::StepPhase;
+use crate::game_logic::world_snapshot::{EntityStatus, WorldSlot, WorldSnapshot};
 use crate::game_logic::GameLogic;
-use crate::server::server_thread::ServerSettings;
-use crate::types::{add_message, MessageType, StyledMessage};
+use crate::mines::DeployError;
+use crate::server::chat::{self, ChatMutes};
+use crate::server::client_id::ClientId;
+use crate::server::client_registry::{ClientRegistration, ClientRegistry};
+use crate::server::client_stats::{ClientStats, ClientStatsRegistry};
+use crate::server::metrics::registry::MetricsRegistry;
+use crate::server::client_stream::{ClientStream, SocketReader, SocketWriter};
+use crate::server::connections::ConnectionRegistry;
+use crate::server::server_thread::{ServerSettings, ServerThread};
+use crate::server::sessions::SessionRegistry;
+use crate::server::subscriptions::{Encoding, SharedWriter, SubscriptionRegistry};
 
-/// A struct representing a client handler, responsible for communicating with a client via a TCP socket.
+pub(crate) mod binary_frame;
+pub(crate) mod protocol_error;
+pub(crate) mod reply;
+pub(crate) mod web_socket;
+
+use protocol_error::ProtocolError;
+use reply::{Fields, Reply, Value};
+use web_socket::Frame;
+use crate::types::{add_chat_message, add_message, is_logged, MessageHub, MessageOrigin, MessageType};
+
+/// The command codes understood by `execute_command`, in their canonical case.
+const COMMAND_CODES: &[&str] = &[
+    AppDefines::HELLO,
+    AppDefines::SPECTATE,
+    AppDefines::OBSERVE,
+    AppDefines::RECONNECT,
+    AppDefines::SET_NAME,
+    AppDefines::SET_COLOR,
+    AppDefines::MESSAGE,
+    AppDefines::SET_TEAM,
+    AppDefines::SET_WEAPON,
+    AppDefines::DEPLOY_MINE,
+    AppDefines::QUIT,
+    AppDefines::QUERY_CLOSEST_BOT,
+    AppDefines::QUERY_CLOSEST_OBSTACLE,
+    AppDefines::QUERY_BY_NAME,
+    AppDefines::QUERY_WORLD,
+    AppDefines::QUERY_ARENA,
+    AppDefines::QUERY_MAP_SEED,
+    AppDefines::QUERY_ZONE,
+    AppDefines::QUERY_CONTROL_POINTS,
+    AppDefines::QUERY_MATCH,
+    AppDefines::QUERY_EVENTS,
+    AppDefines::QUERY_LEADERBOARD,
+    AppDefines::QUERY_OBSTACLES,
+    AppDefines::QUERY_LIDAR,
+    AppDefines::QUERY_FOV,
+    AppDefines::QUERY_DAMAGE,
+    AppDefines::QUERY_SENSORS,
+    AppDefines::QUERY_ORIENTATION,
+    AppDefines::QUERY_STATUS,
+    AppDefines::QUERY_ENERGY,
+    AppDefines::QUERY_TOKEN,
+    AppDefines::PING,
+    AppDefines::SUBSCRIBE,
+    AppDefines::UNSUBSCRIBE,
+    AppDefines::RESYNC,
+    AppDefines::FORMAT,
+    AppDefines::ADMIN,
+    AppDefines::KICK,
+    AppDefines::BAN,
+    AppDefines::ADD_AI,
+    AppDefines::PAUSE,
+    AppDefines::TIME_SCALE,
+    AppDefines::TIMINGS,
+    AppDefines::LOCK,
+    AppDefines::ACTUATOR_MOTOR_LEFT,
+    AppDefines::ACTUATOR_MOTOR_RIGHT,
+    AppDefines::ACTUATOR_GUN_TRIGGER,
+    AppDefines::ACTUATOR_GUN_TRAVERSE,
+    AppDefines::ACTUATOR_SHIELD,
+];
+
+/// A struct representing a client handler, responsible for communicating with a client via a TCP socket,
+/// either directly, through TLS or through a WebSocket.
 pub(crate) struct ClientHandler {
-    /// The TCP socket associated with the client.
-    pub(crate) socket: TcpStream,
-    /// A buffer for writing data to the socket.
-    pub(crate) buf_writer: BufWriter<TcpStream>,
-    /// A buffer for reading data from the socket.
-    pub(crate) buf_reader: BufReader<TcpStream>,
-    /// The time in seconds since the Unix epoch of the client's last activity.
-    pub(crate) previous_time: u64,
-    /// A thread-safe, shared vector of styled messages.
-    pub(crate) messages: Arc<Mutex<Vec<StyledMessage>>>,
+    /// The identity of the client, keying its entity and subscription.
+    pub(crate) client_id: ClientId,
+    /// A buffer for writing data to the connection, shared with the state broadcaster so that lines never interleave.
+    pub(crate) buf_writer: SharedWriter,
+    /// A buffer for reading data from the connection.
+    pub(crate) buf_reader: BufReader<SocketReader>,
+    /// The bytes received and not processed yet: the end of a line or a frame still to come.
+    pending: Vec<u8>,
+    /// When the client last sent a command, on the monotonic clock so that a change of the system time cannot end the connection.
+    pub(crate) last_activity: Instant,
+    /// Whether the client is still connected.
+    pub(crate) connected: bool,
+    /// Whether the client completed the `HELLO` handshake.
+    pub(crate) handshake_done: bool,
+    /// Whether the client only watches the game, without an entity.
+    pub(crate) spectator: bool,
+    /// The ghost camera of a spectator sent `OBSERVE`, steered by its motor commands.
+    camera: Option<u32>,
+    /// Whether replies are sent as JSON instead of the legacy text format.
+    pub(crate) json_mode: bool,
+    /// Whether commands and replies are exchanged as binary frames instead of lines.
+    pub(crate) binary_mode: bool,
+    /// Whether the WebSocket upgrade of the connection completed.
+    web_socket_open: bool,
+    /// Whether the client must be disconnected once the current line is answered.
+    disconnect_pending: bool,
+    /// Whether the client unlocked the admin commands.
+    admin: bool,
+    /// Set from outside the handler to close the connection, e.g. by a kick.
+    shutdown: Arc<AtomicBool>,
+    /// Set when the server stops, see `ServerThread::stop`.
+    stopping: Arc<AtomicBool>,
+    /// Who the connected clients are, shown in the server UI.
+    clients: Arc<Mutex<ClientRegistry>>,
+    /// The row of the client in `clients`, marked as disconnected when the handler is dropped.
+    _registration: ClientRegistration,
+    /// When the server started, the origin of the time sent by `PING`.
+    server_started_at: Instant,
+    /// The messages of the server, see `MessageHub`.
+    pub(crate) messages: MessageHub,
     /// Thread-safe, shared server settings.
     pub(crate) settings: Arc<Mutex<ServerSettings>>,
-    game_logic: Arc<Mutex<GameLogic>>,
-    client_entity_map: Arc<Mutex<HashMap<SocketAddr, u32>>>,
+    /// The way into the game logic, owned by the game loop: the commands acting on the game go through it.
+    commands: GameCommands,
+    /// Where the game loop publishes the state the queries are answered from, see `WorldSnapshot`.
+    world: WorldSlot,
+    client_entity_map: Arc<Mutex<HashMap<ClientId, u32>>>,
+    sessions: Arc<Mutex<SessionRegistry>>,
+    subscriptions: Arc<Mutex<SubscriptionRegistry>>,
+    connections: Arc<Mutex<ConnectionRegistry>>,
+    client_stats: Arc<Mutex<ClientStatsRegistry>>,
+    /// The counters exported to Prometheus, for the errors by kind.
+    counters: Arc<MetricsRegistry>,
+    /// The traffic counted since the statistics were last shared.
+    stats_batch: ClientStats,
+    /// The number of the last game event sent by `EVENTS`.
+    event_cursor: u64,
+    /// The entity and the number of the last hit sent by `DAMAGE`, see `HitRecord::seq`.
+    damage_cursor: (u32, u64),
+    /// The id of the entity of the client once it was destroyed, `None` while it lives.
+    destroyed_entity: Option<u32>,
+    /// The number of horde waves that had ended when the entity of the client was destroyed, see `GameLogic::respawns_granted`.
+    respawns_seen: u64,
+    /// Whether the server was warned that the actuator commands of the client are refused while
+    /// the game UI drives its entity, once per takeover.
+    warned_controlled: bool,
+    /// The players muted in the chat, whose messages may not be relayed.
+    chat_mutes: Arc<Mutex<ChatMutes>>,
 }
 
 impl ClientHandler {
-    /// Creates a new client handler with the specified socket, messages, and server settings.
+    /// Creates a new client handler with the specified connection, sharing the state of a server.
     ///
     /// # Arguments
     ///
-    /// * `socket` - The client's TCP socket.
-    /// * `messages` - A thread-safe, shared vector of styled messages.
-    /// * `settings` - Thread-safe, shared server settings.
+    /// * `stream` - The client's connection: its TCP socket, or its TLS session once the handshake completed.
+    /// * `client_id` - The identity of the client. WebSocket clients must first send an upgrade request.
+    /// * `server` - The server accepting the client: its messages, settings, game logic and registries are shared.
     ///
     /// # Returns
     ///
-    /// A new `ClientHandler`.
-    ///
-    pub fn new(socket: TcpStream,
-               messages: Arc<Mutex<Vec<StyledMessage>>>,
-               settings: Arc<Mutex<ServerSettings>>,
-               game_logic: Arc<Mutex<GameLogic>>,
-               client_entity_map: Arc<Mutex<HashMap<SocketAddr, u32>>>,
-        ) -> Self {
-        let buf_writer = BufWriter::new(socket.try_clone().unwrap());
-        let buf_reader = BufReader::new(socket.try_clone().unwrap());
-        ClientHandler {
-            socket,
+    /// A new `ClientHandler`, or why the connection cannot be handled, before anything is registered for it.
+    ///
+    pub fn new(stream: Box<dyn ClientStream>, client_id: ClientId, server: &ServerThread) -> io::Result<Self> {
+        let messages = server.messages.clone();
+        // Reads must return at once so that a worker can poll its other clients,
+        // the writes keep a deadline through `SocketWriter`. A blocking socket would stall the worker.
+        stream.socket().set_nonblocking(true)?;
+        let stream = Arc::new(Mutex::new(stream));
+        let buf_writer = Arc::new(Mutex::new(BufWriter::new(SocketWriter::new(Arc::clone(&stream)))));
+        let buf_reader = BufReader::new(SocketReader::new(stream));
+        let allow_legacy_clients = server.settings.lock().unwrap().allow_legacy_clients;
+        // Les clients WebSocket ne reçoivent des lignes qu'après l'upgrade
+        let encoding = (!client_id.is_web_socket())
+            .then_some(Encoding { json: false, binary: false, web_socket: false });
+        let shutdown = server.connections.lock().unwrap().register(client_id, Arc::clone(&buf_writer), encoding);
+        server.client_stats.lock().unwrap().connect(client_id);
+        // Un client ne reçoit que les événements survenus après sa connexion
+        let event_cursor = server.commands.query(|logic| logic.events.cursor());
+        Ok(ClientHandler {
+            client_id,
             buf_writer,
             buf_reader,
-            previous_time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            pending: Vec::new(),
+            last_activity: Instant::now(),
+            connected: true,
+            handshake_done: allow_legacy_clients,
+            spectator: false,
+            camera: None,
+            json_mode: false,
+            binary_mode: false,
+            web_socket_open: false,
+            disconnect_pending: false,
+            admin: false,
+            shutdown,
+            stopping: Arc::clone(&server.stopping),
+            clients: Arc::clone(&server.clients),
+            _registration: ClientRegistration::new(client_id, &server.clients),
+            server_started_at: server.started_at,
             messages,
-            settings,
-            game_logic,
-            client_entity_map,
+            settings: Arc::clone(&server.settings),
+            commands: server.commands.clone(),
+            world: server.commands.world().clone(),
+            client_entity_map: Arc::clone(&server.client_entity_map),
+            sessions: Arc::clone(&server.sessions),
+            subscriptions: Arc::clone(&server.subscriptions),
+            connections: Arc::clone(&server.connections),
+            client_stats: Arc::clone(&server.client_stats),
+            counters: Arc::clone(&server.metrics.registry),
+            stats_batch: ClientStats::default(),
+            event_cursor,
+            damage_cursor: (0, 0),
+            destroyed_entity: None,
+            respawns_seen: 0,
+            warned_controlled: false,
+            chat_mutes: Arc::clone(&server.chat_mutes),
+        })
+    }
+
+    /// Starts the client handler: unless legacy clients are allowed, the server first greets
+    /// the client with `HELLO=<protocol_version>=<server_name>`, once the WebSocket upgrade
+    /// completed for WebSocket clients.
+    pub fn start(&mut self) {
+        if !self.client_id.is_web_socket() {
+            self.greet();
         }
     }
 
-    /// Starts the client handler, reading messages from the client and processing them until disconnection or timeout.
-    pub fn run(&mut self) {
-        let mut received_message = String::new();
-        let mut running = true;
-        while running {
-            if self.check_timeout() {
-                break;
+    /// Reads what the client sent since the last call and processes it, without waiting for more.
+    ///
+    /// Incoming bytes are accumulated until a newline, or a whole frame in binary or
+    /// WebSocket mode, is received, so commands split across several TCP segments are
+    /// only processed once complete. Empty lines are ignored. At most
+    /// `CLIENT_READS_PER_POLL` reads are made, so that a chatty client leaves its worker
+    /// to the other clients.
+    ///
+    /// # Returns
+    ///
+    /// Whether the client is still connected, and whether bytes were read.
+    ///
+    pub fn poll(&mut self) -> (bool, bool) {
+        if !self.connected || self.check_timeout() {
+            return (false, false);
+        }
+
+        // Le serveur s'arrête : le client a déjà reçu SHUTDOWN
+        if self.stopping.load(Ordering::SeqCst) {
+            self.handle_disconnection(false);
+            return (false, false);
+        }
+
+        // Un admin a demandé la fermeture de la connexion
+        if self.shutdown.load(Ordering::SeqCst) {
+            self.send_replies(&[Reply::from(ProtocolError::Kicked)]);
+            self.handle_disconnection(true);
+            return (false, false);
+        }
+
+        let mut chunk = [0u8; 1024];
+        let mut read_any = false;
+        for _ in 0..AppDefines::CLIENT_READS_PER_POLL {
+            match self.buf_reader.read(&mut chunk) {
+                // The client closed the connection, a TLS client possibly without its close_notify alert
+                Ok(0) => {
+                    self.handle_disconnection(false);
+                    break;
+                }
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                    self.handle_disconnection(false);
+                    break;
+                }
+                Ok(read) => {
+                    read_any = true;
+                    self.stats_batch.bytes_read += read as u64;
+                    let mut pending = std::mem::take(&mut self.pending);
+                    pending.extend_from_slice(&chunk[..read]);
+                    self.process_pending(&mut pending);
+                    self.pending = pending;
+                    self.flush_stats();
+
+                    // Une trame binaire complète fait au plus 2 + u16::MAX octets
+                    if !self.binary_mode && self.pending.len() > AppDefines::MAX_LINE_LENGTH {
+                        add_message(
+                            &self.messages,
+                            format!("[WARNING] Line or frame longer than {} bytes, disconnecting client", AppDefines::MAX_LINE_LENGTH),
+                            MessageType::Warning,
+                            MessageOrigin::Network,
+                        );
+                        self.handle_disconnection(false);
+                    }
+                    if !self.connected {
+                        break;
+                    }
+                }
+                // Nothing more to read for now: the worker polls the other clients
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::Interrupted => break,
+                // Un client qui coupe brutalement sa connexion part comme un client qui la ferme
+                Err(e) if e.kind() == ErrorKind::ConnectionReset || e.kind() == ErrorKind::ConnectionAborted => {
+                    add_message(
+                        &self.messages,
+                        format!("[INFO] Connection of {} reset by the client.", self.client_id),
+                        MessageType::Info,
+                        MessageOrigin::Network,
+                    );
+                    self.handle_disconnection(false);
+                    break;
+                }
+                Err(e) => {
+                    add_message(
+                        &self.messages,
+                        format!("[ERROR] Read failed: {}", e),
+                        MessageType::Error,
+                        MessageOrigin::Network,
+                    );
+                    self.handle_disconnection(false);
+                    break;
+                }
             }
+        }
+        if read_any {
+            self.clients.lock().unwrap().touch(&self.client_id);
+        }
+        (self.connected, read_any)
+    }
 
-            if let Ok(message_length) = self.buf_reader.read_line(&mut received_message) {
-                if message_length > 1 {
-                    self.handle_received_message(&received_message);
-                    received_message.clear();
-                } else {
-                    self.handle_disconnection();
-                    running = false;
+    /// Sends the `HELLO` greeting, unless legacy clients may skip the handshake.
+    fn greet(&mut self) {
+        if self.handshake_done {
+            return;
+        }
+        let greeting = format!(
+            "{}{sep}{}{sep}{}",
+            AppDefines::HELLO,
+            AppDefines::PROTOCOL_VERSION,
+            AppDefines::SERVER_NAME,
+            sep = AppDefines::ARGUMENT_SEP
+        );
+        self.send_line(&greeting);
+    }
+
+    /// Processes the complete lines, or frames in binary or WebSocket mode, of the received bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `pending` - The bytes received and not processed yet. Processed bytes are removed.
+    ///
+    fn process_pending(&mut self, pending: &mut Vec<u8>) {
+        while self.connected {
+            if self.client_id.is_web_socket() {
+                if !self.process_web_socket(pending) {
                     break;
                 }
+            } else if self.binary_mode {
+                let Some((consumed, command)) = binary_frame::decode(pending) else { break };
+                pending.drain(..consumed);
+                match command {
+                    Ok(command) => self.handle_commands(vec![command]),
+                    Err(error) => self.send_replies(&[Reply::from(error)]),
+                }
+            } else {
+                let Some(end) = pending.iter().position(|&byte| byte == b'\n') else { break };
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if !line.trim().is_empty() {
+                    self.handle_received_message(&line);
+                }
             }
         }
     }
 
-    /// Checks if the client has exceeded the inactivity timeout.
+    /// Processes the upgrade request or the first frame of a WebSocket connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `pending` - The bytes received and not processed yet. Processed bytes are removed.
     ///
     /// # Returns
     ///
-    /// `true` if the client has exceeded the inactivity timeout, `false` otherwise.
+    /// `false` if the bytes do not hold a complete request or frame yet.
     ///
-    fn check_timeout(&mut self) -> bool {
-        let now = SystemTime::now();
-        let current_time = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
-        if current_time - self.previous_time > AppDefines::CONNECTION_TIMEOUT_DELAY as u64 {
-            let peer_addr = self.socket.peer_addr().unwrap();
+    fn process_web_socket(&mut self, pending: &mut Vec<u8>) -> bool {
+        if !self.web_socket_open {
+            let Some((consumed, response)) = web_socket::accept(pending) else { return false };
+            pending.drain(..consumed);
+            match response {
+                Ok(response) => {
+                    self.send_raw(response.as_bytes());
+                    self.web_socket_open = true;
+                    self.update_encoding();
+                    self.greet();
+                }
+                Err(reason) => {
+                    add_message(
+                        &self.messages,
+                        format!("[WARNING] WebSocket upgrade refused for {}: {}", self.client_id, reason),
+                        MessageType::Warning,
+                        MessageOrigin::Network,
+                    );
+                    self.send_raw(web_socket::refuse("400 Bad Request", reason).as_bytes());
+                    self.handle_disconnection(false);
+                }
+            }
+            return true;
+        }
 
-            if let Some(entity_id) = self.client_entity_map.lock().unwrap().remove(&peer_addr) {
-                let mut logic = self.game_logic.lock().unwrap();
-                logic.remove_entity_by_id(entity_id);
+        let Some((consumed, frame)) = web_socket::decode(pending) else { return false };
+        pending.drain(..consumed);
+        match frame {
+            Ok(Frame::Text(text)) => {
+                if !text.trim().is_empty() {
+                    self.handle_received_message(&text);
+                }
             }
+            Ok(Frame::Ping(payload)) => self.send_raw(&web_socket::encode_pong(&payload)),
+            Ok(Frame::Pong) => {}
+            // Fermer la WebSocket équivaut à couper la connexion TCP
+            Ok(Frame::Close) => self.handle_disconnection(false),
+            Err(error) => self.send_replies(&[Reply::from(error)]),
+        }
+        true
+    }
 
+    /// Checks if the client has exceeded the inactivity timeout.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the client has exceeded the inactivity timeout, `false` otherwise.
+    ///
+    fn check_timeout(&mut self) -> bool {
+        // Le délai peut changer pendant la connexion, il est relu à chaque vérification
+        let delay = self.settings.lock().unwrap().connection_timeout_delay();
+        if self.last_activity.elapsed() > Duration::from_secs(delay as u64) {
             add_message(
                 &self.messages,
-                format!("[WARNING] Connection timeout: {}", self.socket.peer_addr().unwrap()),
+                format!("[WARNING] Connection timeout: {}", self.client_id),
                 MessageType::Warning,
+                MessageOrigin::Network,
             );
-            self.socket.shutdown(std::net::Shutdown::Both).unwrap();
+            self.handle_disconnection(false);
             true
         } else {
             false
@@ -113,25 +464,180 @@ impl ClientHandler {
 
     /// Handles a message received from the client.
     ///
+    /// Every command of the line is processed in order and their replies are
+    /// sent back as a single line, so that the n-th reply always matches the
+    /// n-th command. The line holds either legacy commands joined with
+    /// `COMMAND_SEP`, or a JSON command object or array of objects.
+    ///
     /// # Arguments
     ///
     /// * `received_message` - The received message as a string.
     ///
     fn handle_received_message(&mut self, received_message: &str) {
-        let all_messages: Vec<&str> = received_message.trim().split(AppDefines::COMMAND_SEP).collect();
-        for message in all_messages {
-            // println!("Message {:?}", message);
-            match message {
-                AppDefines::QUIT => {
-                    self.handle_disconnection();
-                    return;
-                }
-                _ => self.process_message(message),
-            };
-            self.previous_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        match ClientHandler::split_commands(received_message) {
+            Ok(commands) => self.handle_commands(commands),
+            Err(error) => self.send_replies(&[Reply::from(error)]),
+        }
+    }
+
+    /// Processes commands in order and sends their replies back as a single line.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The commands, in the legacy text format.
+    ///
+    fn handle_commands(&mut self, commands: Vec<String>) {
+        let mut replies = Vec::new();
+        for message in commands {
+            if message.trim().eq_ignore_ascii_case(AppDefines::QUIT) {
+                self.stats_batch.commands += 1;
+                self.stats_batch.last_command = AppDefines::QUIT.to_string();
+                // Answer the commands preceding QUIT before closing the connection
+                if !replies.is_empty() {
+                    self.send_replies(&replies);
+                }
+                self.handle_disconnection(true);
+                return;
+            }
+
+            if message.trim().is_empty() {
+                replies.push(Reply::Empty);
+                continue;
+            }
+            self.stats_batch.commands += 1;
+            replies.push(self.process_message(&message));
+            self.last_activity = Instant::now();
+
+            if self.disconnect_pending {
+                self.send_replies(&replies);
+                self.handle_disconnection(true);
+                return;
+            }
+        }
+        self.send_replies(&replies);
+    }
+
+    /// Splits a received line into its commands, in the legacy text format.
+    ///
+    /// # Arguments
+    ///
+    /// * `received_message` - The received line, legacy or JSON.
+    ///
+    /// # Returns
+    ///
+    /// The commands, or an error if the line is invalid JSON.
+    ///
+    fn split_commands(received_message: &str) -> Result<Vec<String>, ProtocolError> {
+        let line = received_message.trim();
+        if !line.starts_with('{') && !line.starts_with('[') {
+            return Ok(line.split(AppDefines::COMMAND_SEP).map(str::to_string).collect());
+        }
+
+        let invalid = || ProtocolError::BadValue("Invalid JSON command");
+        let json: serde_json::Value = serde_json::from_str(line).map_err(|_| invalid())?;
+        match &json {
+            serde_json::Value::Array(commands) => commands
+                .iter()
+                .map(|command| reply::legacy_command(command).ok_or_else(invalid))
+                .collect(),
+            command => Ok(vec![reply::legacy_command(command).ok_or_else(invalid)?]),
+        }
+    }
+
+    /// Sends the replies to the commands of one line, in the format of the connection.
+    ///
+    /// In JSON mode, a single reply is sent as an object and several replies as an array.
+    ///
+    /// # Arguments
+    ///
+    /// * `replies` - The replies, in the order of the commands.
+    ///
+    fn send_replies(&mut self, replies: &[Reply]) {
+        for reply in replies {
+            if let Reply::Error(error) = reply {
+                self.stats_batch.errors += 1;
+                self.counters.errors.increment(error.name());
+            }
+        }
+        let line = if self.json_mode {
+            match replies {
+                [reply] => reply.to_json().to_string(),
+                _ => serde_json::Value::Array(replies.iter().map(Reply::to_json).collect()).to_string(),
+            }
+        } else {
+            let mut response = String::new();
+            for reply in replies {
+                ClientHandler::append_response(&mut response, &reply.to_legacy());
+            }
+            response
+        };
+        self.send_line(&line);
+    }
+
+    /// Writes a line to the client and flushes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to send, without its trailing newline.
+    ///
+    fn send_line(&mut self, line: &str) {
+        if self.client_id.is_web_socket() && !self.web_socket_open {
+            return;
+        }
+        let encoding = self.encoding();
+        let bytes = ClientHandler::encode_line(line, encoding).unwrap_or_else(|| {
+            let error = Reply::from(ProtocolError::BadValue("Reply too long for a frame")).to_legacy();
+            ClientHandler::encode_line(&error, encoding).unwrap_or_default()
+        });
+        self.send_raw(&bytes);
+    }
+
+    /// Writes bytes to the client, as they are, and flushes them.
+    fn send_raw(&mut self, bytes: &[u8]) {
+        let mut writer = self.buf_writer.lock().unwrap();
+        if writer.write_all(bytes).and_then(|_| writer.flush()).is_ok() {
+            self.stats_batch.bytes_written += bytes.len() as u64;
+        }
+    }
+
+    /// Shares the traffic counted since the last call with the server UI.
+    fn flush_stats(&mut self) {
+        let batch = std::mem::take(&mut self.stats_batch);
+        self.client_stats.lock().unwrap().record(self.client_id, &batch);
+    }
+
+    /// Encodes a line for the wire.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line, without its trailing newline.
+    /// * `encoding` - How the connection frames its lines.
+    ///
+    /// # Returns
+    ///
+    /// The bytes to send, or `None` if the line is too long for a frame.
+    ///
+    pub(crate) fn encode_line(line: &str, encoding: Encoding) -> Option<Vec<u8>> {
+        if encoding.web_socket {
+            Some(web_socket::encode_text(line))
+        } else if encoding.binary {
+            binary_frame::encode_reply(line)
+        } else {
+            Some(format!("{}\n", line).into_bytes())
         }
     }
 
+    /// Returns how the pushes to this client are encoded.
+    fn encoding(&self) -> Encoding {
+        Encoding { json: self.json_mode, binary: self.binary_mode, web_socket: self.client_id.is_web_socket() }
+    }
+
+    /// Shares a change of encoding with the state broadcaster and the connection registry.
+    fn update_encoding(&self) {
+        self.subscriptions.lock().unwrap().set_encoding(&self.client_id, self.encoding());
+        self.connections.lock().unwrap().set_encoding(&self.client_id, self.encoding());
+    }
+
     /// Processes an individual message from the client.
     ///
     /// # Arguments
@@ -198,169 +704,1494 @@ impl ClientHandler {
     ///
     /// * `received` - The received message as a string.
     ///
-    fn process_message(&mut self, received: &str) {
-        let peer_addr = self.socket.peer_addr().unwrap();
-        let entity_id = {
-            let map = self.client_entity_map.lock().unwrap();
-            *map.get(&peer_addr).unwrap_or(&0)
-        };
+    /// # Returns
+    ///
+    /// The reply to the command, or the error preventing its execution.
+    ///
+    fn process_message(&mut self, received: &str) -> Reply {
+        // Le message n'est formaté que si le niveau Debug est gardé
+        if is_logged(MessageType::Debug) {
+            add_message(&self.messages, format!("Processing message from client {}: {:?}", self.client_id, received.trim()), MessageType::Debug, MessageOrigin::Network);
+        }
+        self.execute_command(received).unwrap_or_else(Reply::from)
+    }
+
+    /// Executes an individual command from the client.
+    ///
+    /// The commands acting on the game are sent to the game loop, see `GameCommands`, and run between
+    /// two steps: they wait at most for the step in progress and see the game as it is after it. The
+    /// queries of the state, `STATUS`, `WORLD`, `EVENTS` and `PING`, are answered from the world snapshot
+    /// instead, without waiting for the loop: their reply may be one step stale, the state after the last step.
+    ///
+    /// # Arguments
+    ///
+    /// * `received` - The received message as a string.
+    ///
+    /// # Returns
+    ///
+    /// The reply to the command, or the error preventing its execution.
+    ///
+    fn execute_command(&mut self, received: &str) -> Result<Reply, ProtocolError> {
+        let client_id = self.client_id;
+        let mut entity_id = self.client_entity_map.lock().unwrap().get(&client_id).copied()
+            .or(self.destroyed_entity)
+            .unwrap_or(0);
+        // Le client garde l'id de son entité détruite pour répondre DEAD, sans en recevoir une autre avant la fin d'une vague.
+        // L'instantané suffit : il est republié dès qu'une entité quitte la partie
+        if entity_id != 0
+            && self.destroyed_entity != Some(entity_id)
+            && self.world.latest().entity(entity_id).is_none()
+        {
+            self.forget_entity(client_id, entity_id);
+        }
+        let command_budget = self.settings.lock().unwrap().command_budget_per_step;
 
         // On split d'abord sur le séparateur "=" pour récupérer le code et tous les arguments
-        let mut parts = received.trim().split(AppDefines::ARGUMENT_SEP);
-        let code = parts.next().unwrap_or("").trim();
+        let mut parts = received.trim().split(AppDefines::ARGUMENT_SEP).map(str::trim);
+        let code = ClientHandler::canonical_code(parts.next().unwrap_or(""));
         let args: Vec<&str> = parts.collect(); // Tous les arguments restants
+        self.stats_batch.last_command = code.to_string();
 
-        let response = match code {
-            AppDefines::SET_NAME => {
-                if let Some(name) = args.get(0) {
-                    let mut logic = self.game_logic.lock().unwrap();
-                    if let Some(entity) = logic.get_entity_mut(entity_id) {
-                        entity.set_name(name.to_string());
-                        format!("Name set to {}", name)
-                    } else {
-                        "Entity not found".to_string()
+        // PING sert aussi à mesurer la latence avant le handshake
+        if !self.handshake_done && code != AppDefines::HELLO && code != AppDefines::PING {
+            return Err(ProtocolError::HandshakeRequired);
+        }
+
+        if self.spectator {
+            let steers_camera = self.camera.is_some()
+                && matches!(code, AppDefines::ACTUATOR_MOTOR_LEFT | AppDefines::ACTUATOR_MOTOR_RIGHT);
+            if ClientHandler::controls_entity(code) && !steers_camera {
+                return Err(ProtocolError::SpectatorForbidden);
+            }
+        } else if ClientHandler::controls_entity(code) && (entity_id == 0 || self.may_respawn(entity_id)) {
+            // Les clients sans handshake obtiennent leur entité à leur première commande,
+            // les joueurs abattus pendant une vague de la horde à leur première commande après elle
+            if self.settings.lock().unwrap().lobby_locked {
+                return Err(self.refuse_locked(client_id));
+            }
+            let respawned = self.destroyed_entity.take().is_some();
+            entity_id = self.spawn_entity(client_id);
+            if respawned {
+                self.push_respawn(entity_id);
+            }
+        }
+
+        // Chaque commande compte une fois dans le budget de l'entité, avant son exécution, qu'elle réussisse ou non
+        if command_budget > 0 && code != AppDefines::HELLO && entity_id != 0 && !self.spectator {
+            let granted = self.commands.ask(|reply| GameCommand::ChargeBudget { entity_id, count: 1, budget: command_budget, reply });
+            if granted == 0 {
+                return Ok(Reply::Query {
+                    cmd: AppDefines::BUDGET_EXCEEDED,
+                    fields: vec![("command", Value::Text(code.to_string()))],
+                });
+            }
+        }
+
+        match code {
+            AppDefines::HELLO => {
+                let version = args.first().ok_or(ProtocolError::MissingArgument("client version"))?.trim();
+                if ClientHandler::major_version(version) != ClientHandler::major_version(AppDefines::PROTOCOL_VERSION) {
+                    add_message(
+                        &self.messages,
+                        format!("[WARNING] Client {} refused: protocol version {}", client_id, version),
+                        MessageType::Warning,
+                        MessageOrigin::Network,
+                    );
+                    self.disconnect_pending = true;
+                    return Err(ProtocolError::VersionMismatch(version.to_string()));
+                }
+                let flags: Vec<&str> = args.iter().skip(1).map(|flag| flag.trim()).collect();
+                if flags.iter().any(|flag| ![AppDefines::SPECTATE, AppDefines::BINARY].contains(flag)) {
+                    return Err(ProtocolError::BadValue("Unknown handshake flag, use SPECTATE or BINARY"));
+                }
+                if client_id.is_web_socket() && flags.contains(&AppDefines::BINARY) {
+                    return Err(ProtocolError::BadValue("Binary frames are not available over WebSocket"));
+                }
+                self.handshake_done = true;
+                // Le passage en binaire s'applique dès la réponse au HELLO
+                self.binary_mode = flags.contains(&AppDefines::BINARY);
+                self.update_encoding();
+                // Partie verrouillée : pas d'entité, le client peut encore envoyer RECONNECT ou SPECTATE
+                let locked = self.settings.lock().unwrap().lobby_locked;
+                if flags.contains(&AppDefines::SPECTATE) {
+                    self.become_spectator(client_id, entity_id);
+                } else if !self.spectator && entity_id == 0 && !locked {
+                    entity_id = self.spawn_entity(client_id);
+                }
+                let token = self.sessions.lock().unwrap().token_of(entity_id).unwrap_or_default().to_string();
+                let mut text = format!("Welcome, protocol {}", AppDefines::PROTOCOL_VERSION);
+                if !self.spectator && entity_id == 0 {
+                    text = format!("{}. {}", text, ProtocolError::MatchLocked.text());
+                }
+                let budget = self.settings.lock().unwrap().command_budget_per_step;
+                let fields = vec![("token", Value::Text(token)), ("command_budget", Value::Int(budget as i64))];
+                Ok(Reply::Ack { cmd: code.to_string(), fields, text })
+            }
+
+            AppDefines::FORMAT => {
+                let format = args.first().ok_or(ProtocolError::MissingArgument("format"))?.trim();
+                self.json_mode = match format {
+                    AppDefines::FORMAT_JSON => true,
+                    AppDefines::FORMAT_TEXT => false,
+                    _ => return Err(ProtocolError::BadValue("Unknown format, use JSON or TEXT")),
+                };
+                self.update_encoding();
+                Ok(Reply::ack(code, format!("Format set to {}", format)))
+            }
+
+            AppDefines::SPECTATE => {
+                self.become_spectator(client_id, entity_id);
+                Ok(Reply::ack(code, "Spectating"))
+            }
+
+            AppDefines::OBSERVE => {
+                if !self.spectator {
+                    return Err(ProtocolError::BadValue("Only spectators observe, send SPECTATE first"));
+                }
+                let radius = match args.first() {
+                    Some(radius) => Some(radius.trim().parse::<f32>().ok().filter(|radius| radius.is_finite() && *radius >= 0.0)
+                        .ok_or(ProtocolError::BadValue("The view radius must be a positive number"))?),
+                    None => None,
+                };
+                let current = self.camera;
+                let (id, reply) = self.commands.update(move |logic| {
+                    let id = current.unwrap_or_else(|| logic.add_ghost_camera());
+                    let reply = logic.ghost_camera_mut(id).ok_or(ProtocolError::NoEntity).map(|camera| {
+                        if let Some(radius) = radius {
+                            camera.view_radius = radius;
+                        }
+                        Reply::Query {
+                            cmd: AppDefines::OBSERVE,
+                            fields: vec![
+                                ("id", Value::Int(camera.id as i64)),
+                                ("x", Value::Float(camera.x as f64, 1)),
+                                ("y", Value::Float(camera.y as f64, 1)),
+                                ("radius", Value::Float(camera.view_radius as f64, 1)),
+                            ],
+                        }
+                    });
+                    (id, reply)
+                });
+                if self.camera.is_none() {
+                    self.camera = Some(id);
+                    self.subscriptions.lock().unwrap().set_camera(client_id, Some(id));
+                }
+                reply
+            }
+
+            AppDefines::QUERY_WORLD => {
+                Ok(ClientHandler::world_state(AppDefines::QUERY_WORLD, &self.world.latest(), None))
+            }
+
+            AppDefines::QUERY_ARENA => self.commands.query(|logic| {
+                let arena = logic.arena();
+                let hazards: Vec<Fields> = logic.hazards().iter().map(|hazard| {
+                    let (half_width, half_height) = hazard.shape.half_extents();
+                    vec![
+                        ("shape", Value::Text(hazard.shape.name().to_string())),
+                        ("x", Value::Float(hazard.position.0 as f64, 1)),
+                        ("y", Value::Float(hazard.position.1 as f64, 1)),
+                        ("half_width", Value::Float(half_width as f64, 1)),
+                        ("half_height", Value::Float(half_height as f64, 1)),
+                        ("effect", Value::Text(hazard.effect.name().to_string())),
+                        ("strength", Value::Float(hazard.effect.strength() as f64, 2)),
+                    ]
+                }).collect();
+                Ok(Reply::Query {
+                    cmd: AppDefines::QUERY_ARENA,
+                    fields: vec![
+                        ("width", Value::Float(arena.width as f64, 1)),
+                        ("height", Value::Float(arena.height as f64, 1)),
+                        ("obstacle_count", Value::Int(logic.obstacles.len() as i64)),
+                        ("hazard_count", Value::Int(hazards.len() as i64)),
+                        ("hazards", Value::List(hazards)),
+                    ],
+                })
+            }),
+
+            AppDefines::QUERY_MAP_SEED => {
+                let Some(seed) = self.commands.query(|logic| logic.map_seed()) else {
+                    return Ok(Reply::Empty);
+                };
+                // En texte : une graine u64 ne tient pas dans un entier JSON sûr
+                Ok(Reply::Query {
+                    cmd: AppDefines::QUERY_MAP_SEED,
+                    fields: vec![("seed", Value::Text(seed.to_string()))],
+                })
+            }
+
+            AppDefines::QUERY_ZONE => {
+                let Some(zone) = self.commands.query(|logic| logic.zone()) else {
+                    return Ok(Reply::Empty);
+                };
+                Ok(Reply::Query {
+                    cmd: AppDefines::QUERY_ZONE,
+                    fields: vec![
+                        ("x", Value::Float(zone.center.0 as f64, 1)),
+                        ("y", Value::Float(zone.center.1 as f64, 1)),
+                        ("radius", Value::Float(zone.radius as f64, 1)),
+                        ("next_radius", Value::Float(zone.next_radius as f64, 1)),
+                        ("shrink_starts_in", Value::Float(zone.shrink_starts_in.as_secs_f64(), 1)),
+                        ("shrink_ends_in", Value::Float(zone.shrink_ends_in.as_secs_f64(), 1)),
+                    ],
+                })
+            }
+
+            AppDefines::QUERY_CONTROL_POINTS => Ok(self.commands.query(|logic| {
+                if logic.game_config.game_mode != GameMode::ControlPoint {
+                    return Reply::Empty;
+                }
+                ClientHandler::control_points(logic)
+            })),
+
+            AppDefines::QUERY_MATCH => self.commands.query(|logic| {
+                let state = &logic.match_state;
+                let phase = match state.phase {
+                    MatchPhase::Running => AppDefines::MATCH_RUNNING,
+                    MatchPhase::Intermission => AppDefines::MATCH_INTERMISSION,
+                };
+                let remaining = match state.remaining(logic.sim_time(), &logic.game_config) {
+                    Some(remaining) => Value::Float(remaining.as_secs_f64(), 1),
+                    None => Value::Text(AppDefines::EMPTY_REPLY.to_string()),
+                };
+                Ok(Reply::Query {
+                    cmd: AppDefines::QUERY_MATCH,
+                    fields: vec![
+                        ("round", Value::Int(state.round as i64)),
+                        ("phase", Value::Text(phase.to_string())),
+                        ("remaining_s", remaining),
+                    ],
+                })
+            }),
+
+            AppDefines::QUERY_EVENTS => {
+                let world = self.world.latest();
+                let reply = ClientHandler::events(&world, self.event_cursor);
+                // Un instantané plus ancien que la connexion ne fait pas reculer le curseur
+                self.event_cursor = self.event_cursor.max(world.events.cursor());
+                Ok(reply)
+            }
+
+            AppDefines::QUERY_LEADERBOARD => {
+                let count = match args.first() {
+                    Some(count) => count.trim().parse::<usize>().map_err(|_| ProtocolError::BadValue("Invalid player count"))?,
+                    None => AppDefines::LEADERBOARD_DEFAULT_TOP,
+                };
+                let count = count.clamp(1, AppDefines::LEADERBOARD_MAX_TOP);
+                let records = self.commands.query(move |logic| logic.leaderboard.top(count).into_iter().enumerate().map(|(index, (name, totals))| vec![
+                    ("rank", Value::Int(index as i64 + 1)),
+                    ("name", Value::Text(name.to_string())),
+                    ("score", Value::Int(totals.score)),
+                    ("kills", Value::Int(totals.kills as i64)),
+                    ("deaths", Value::Int(totals.deaths as i64)),
+                    ("matches", Value::Int(totals.matches_played as i64)),
+                    ("shots_fired", Value::Int(totals.shots_fired as i64)),
+                    ("hits", Value::Int(totals.hits as i64)),
+                    ("accuracy", ClientHandler::accuracy(totals.accuracy())),
+                    ("waves", Value::Int(totals.waves_survived as i64)),
+                ]).collect());
+                Ok(Reply::Records { cmd: AppDefines::QUERY_LEADERBOARD, records })
+            }
+
+            AppDefines::QUERY_OBSTACLES => Ok(self.commands.query(ClientHandler::obstacles)),
+
+            AppDefines::QUERY_BY_NAME => {
+                let name = args.first().ok_or(ProtocolError::MissingArgument("name"))?.to_string();
+                Ok(self.commands.query(move |logic| {
+                    let Some(entity) = logic.entity_by_name(&name) else {
+                        return Reply::Empty;
+                    };
+                    Reply::Query {
+                        cmd: AppDefines::QUERY_BY_NAME,
+                        fields: vec![
+                            ("id", Value::Int(entity.id as i64)),
+                            ("x", Value::Float(entity.x as f64, 1)),
+                            ("y", Value::Float(entity.y as f64, 1)),
+                            ("health", Value::Int(entity.health as i64)),
+                            ("score", Value::Int(entity.score as i64)),
+                        ],
+                    }
+                }))
+            }
+
+            AppDefines::SUBSCRIBE => {
+                let hz = args.first().ok_or(ProtocolError::MissingArgument("rate"))?
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| ProtocolError::BadValue("Invalid rate"))?;
+                let delta = match args.get(1).map(|mode| mode.trim()) {
+                    None => false,
+                    Some(mode) if mode.eq_ignore_ascii_case(AppDefines::SUBSCRIBE_DELTA) => true,
+                    Some(_) => return Err(ProtocolError::BadValue("Invalid mode, expected DELTA")),
+                };
+                let hz = self.subscriptions.lock().unwrap().subscribe(client_id, Arc::clone(&self.buf_writer), hz, self.encoding(), delta);
+                let mode = if delta { ", delta frames" } else { "" };
+                Ok(Reply::ack(code, format!("Subscribed at {} Hz{}", hz, mode)))
+            }
+
+            AppDefines::RESYNC => {
+                if !self.subscriptions.lock().unwrap().request_keyframe(&client_id) {
+                    return Err(ProtocolError::BadValue("Not subscribed to delta frames"));
+                }
+                Ok(Reply::ack(code, "Keyframe requested"))
+            }
+
+            AppDefines::UNSUBSCRIBE => {
+                self.subscriptions.lock().unwrap().unsubscribe(&client_id);
+                Ok(Reply::ack(code, "Unsubscribed"))
+            }
+
+            AppDefines::RECONNECT => {
+                if self.spectator {
+                    return Err(ProtocolError::SpectatorForbidden);
+                }
+                let token = args.first().ok_or(ProtocolError::MissingArgument("token"))?.trim();
+                let grace = Duration::from_secs(self.settings.lock().unwrap().reconnect_grace_secs);
+                let reclaimed_id = self.sessions.lock().unwrap().reclaim(token, grace)?;
+
+                // L'entité créée pour cette connexion est remplacée par l'entité récupérée
+                let replaced = (entity_id != 0 && entity_id != reclaimed_id).then_some(entity_id);
+                let name = self.commands.update(move |logic| {
+                    let name = logic.entities.iter().find(|e| e.id == reclaimed_id).map(|e| e.name.clone())?;
+                    if let Some(replaced) = replaced {
+                        logic.remove_entity_by_id(replaced);
                     }
-                } else {
-                    "Missing name".to_string()
+                    Some(name)
+                });
+                let Some(name) = name else {
+                    self.sessions.lock().unwrap().close(reclaimed_id);
+                    return Err(ProtocolError::TokenExpired);
+                };
+                if let Some(replaced) = replaced {
+                    self.sessions.lock().unwrap().close(replaced);
+                }
+                self.client_entity_map.lock().unwrap().insert(client_id, reclaimed_id);
+                self.clients.lock().unwrap().set_entity(&client_id, Some((reclaimed_id, name)));
+
+                add_message(
+                    &self.messages,
+                    format!("[INFO] Client {} reclaimed entity {}.", client_id, reclaimed_id),
+                    MessageType::Info,
+                    MessageOrigin::Network,
+                );
+                Ok(Reply::ack(code, format!("Entity {} reclaimed", reclaimed_id)))
+            }
+
+            AppDefines::PING => {
+                let client_timestamp = args.first().copied().unwrap_or("");
+                let last_step = self.world.latest().last_step;
+                Ok(Reply::Query {
+                    cmd: AppDefines::PING,
+                    fields: vec![
+                        ("server_ms", Value::Int(self.server_started_at.elapsed().as_millis() as i64)),
+                        ("client_timestamp", Value::Text(client_timestamp.to_string())),
+                        ("last_step_ms", Value::Float(last_step.as_secs_f64() * 1000.0, 3)),
+                    ],
+                })
+            }
+
+            AppDefines::ADMIN => {
+                let password = args.first().ok_or(ProtocolError::MissingArgument("password"))?.trim();
+                let expected = self.settings.lock().unwrap().admin_password.clone();
+                if expected.is_empty() || password != expected {
+                    add_message(
+                        &self.messages,
+                        format!("[ADMIN] Client {} sent a wrong admin password.", client_id),
+                        MessageType::Warning,
+                        MessageOrigin::Admin,
+                    );
+                    return Err(ProtocolError::BadPassword);
+                }
+                self.admin = true;
+                add_message(
+                    &self.messages,
+                    format!("[ADMIN] Client {} unlocked the admin commands.", client_id),
+                    MessageType::Info,
+                    MessageOrigin::Admin,
+                );
+                Ok(Reply::ack(code, "Admin commands unlocked"))
+            }
+
+            AppDefines::KICK => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
                 }
+                let name = args.first().ok_or(ProtocolError::MissingArgument("name"))?.trim();
+                let target = name.to_string();
+                let kicked_id = self.commands.update(move |logic| {
+                    let kicked_id = logic.entity_by_name(&target).map(|e| e.id)?;
+                    logic.remove_entity_by_id(kicked_id);
+                    Some(kicked_id)
+                }).ok_or(ProtocolError::NoEntity)?;
+                self.release_entity(kicked_id);
+                add_message(
+                    &self.messages,
+                    format!("[ADMIN] Entity {} ({}) kicked by {}.", kicked_id, name, client_id),
+                    MessageType::Warning,
+                    MessageOrigin::Admin,
+                );
+                Ok(Reply::ack(code, format!("{} kicked", name)))
+            }
+
+            AppDefines::BAN => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
+                }
+                let ip = args
+                    .first()
+                    .ok_or(ProtocolError::MissingArgument("address"))?
+                    .trim()
+                    .parse::<IpAddr>()
+                    .map_err(|_| ProtocolError::BadValue("Invalid IP address"))?;
+                let banned = self.connections.lock().unwrap().ban(ip);
+                for banned_id in &banned {
+                    let banned_entity = self.client_entity_map.lock().unwrap().get(banned_id).copied();
+                    if let Some(banned_entity) = banned_entity {
+                        self.commands.ask(|reply| GameCommand::Remove { entity_id: banned_entity, reply });
+                        self.release_entity(banned_entity);
+                    }
+                }
+                add_message(
+                    &self.messages,
+                    format!("[ADMIN] Address {} banned by {}, {} connection(s) closed.", ip, client_id, banned.len()),
+                    MessageType::Warning,
+                    MessageOrigin::Admin,
+                );
+                Ok(Reply::ack(code, format!("{} banned", ip)))
+            }
+
+            AppDefines::ADD_AI => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
+                }
+                let name = args.first().ok_or(ProtocolError::MissingArgument("name"))?.trim();
+                if name.is_empty() {
+                    return Err(ProtocolError::MissingArgument("name"));
+                }
+                let difficulty = match args.get(1) {
+                    Some(difficulty) => AiDifficulty::from_name(difficulty.trim())
+                        .ok_or(ProtocolError::BadValue("Unknown difficulty, use EASY, NORMAL or HARD"))?,
+                    None => AiDifficulty::Normal,
+                };
+                let ai_name = name.to_string();
+                let ai_id = self.commands.update(move |logic| logic.add_ai(ai_name, None, difficulty));
+                add_message(
+                    &self.messages,
+                    format!("[ADMIN] {} AI {} ({}) added by {}.", difficulty.name(), ai_id, name, client_id),
+                    MessageType::Info,
+                    MessageOrigin::Admin,
+                );
+                Ok(Reply::ack(code, format!("{} added", name)))
+            }
+
+            AppDefines::PAUSE => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
+                }
+                let action = args.first().ok_or(ProtocolError::MissingArgument("action"))?.trim();
+                let text = match action {
+                    AppDefines::PAUSE_ON => {
+                        self.commands.update(GameLogic::pause);
+                        "Simulation paused"
+                    }
+                    AppDefines::PAUSE_OFF => {
+                        self.commands.update(GameLogic::resume);
+                        "Simulation resumed"
+                    }
+                    AppDefines::PAUSE_STEP => {
+                        if !self.commands.update(GameLogic::step_once) {
+                            return Err(ProtocolError::BadValue("The simulation is not paused"));
+                        }
+                        "Simulation stepped"
+                    }
+                    _ => return Err(ProtocolError::BadValue("Unknown action, use ON, OFF or STEP")),
+                };
+                add_message(&self.messages, format!("[ADMIN] {} by {}.", text, client_id), MessageType::Info, MessageOrigin::Admin);
+                Ok(Reply::ack(code, text))
+            }
+
+            AppDefines::TIME_SCALE => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
+                }
+                let scale = args
+                    .first()
+                    .ok_or(ProtocolError::MissingArgument("scale"))?
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| ProtocolError::BadValue("The time scale must be a number"))?;
+                if !(AppDefines::MIN_TIME_SCALE..=AppDefines::MAX_TIME_SCALE).contains(&scale) {
+                    return Err(ProtocolError::BadValue("The time scale must be between 0.25 and 10"));
+                }
+                self.commands.update(move |logic| logic.time_scale = scale);
+                add_message(
+                    &self.messages,
+                    format!("[ADMIN] Simulation speed set to {}x by {}.", scale, client_id),
+                    MessageType::Info,
+                    MessageOrigin::Admin,
+                );
+                Ok(Reply::ack(code, format!("Time scale set to {}", scale)))
+            }
+
+            AppDefines::TIMINGS => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
+                }
+                let profiling = match args.first().map(|arg| arg.trim()) {
+                    None | Some("") => None,
+                    Some(AppDefines::TIMINGS_ON) => Some(true),
+                    Some(AppDefines::TIMINGS_OFF) => Some(false),
+                    Some(_) => return Err(ProtocolError::BadValue("Unknown action, use ON or OFF")),
+                };
+                let reply = self.commands.update(move |logic| {
+                    if let Some(profiling) = profiling {
+                        logic.profiling_enabled = profiling;
+                    }
+                    ClientHandler::step_timings(logic)
+                });
+                if let Some(profiling) = profiling {
+                    let text = if profiling { "started" } else { "stopped" };
+                    add_message(&self.messages, format!("[ADMIN] Step profiling {} by {}.", text, client_id), MessageType::Info, MessageOrigin::Admin);
+                }
+                Ok(reply)
+            }
+
+            AppDefines::LOCK => {
+                if !self.admin {
+                    return Err(ProtocolError::AdminRequired);
+                }
+                let locked = match args.first().map(|arg| arg.trim()) {
+                    Some(AppDefines::LOCK_ON) => true,
+                    Some(AppDefines::LOCK_OFF) => false,
+                    Some(_) => return Err(ProtocolError::BadValue("Unknown action, use ON or OFF")),
+                    None => return Err(ProtocolError::MissingArgument("action")),
+                };
+                self.settings.lock().unwrap().lobby_locked = locked;
+                let text = if locked { "locked" } else { "unlocked" };
+                add_message(&self.messages, format!("[ADMIN] Lobby {} by {}.", text, client_id), MessageType::Info, MessageOrigin::Admin);
+                Ok(Reply::ack(code, format!("Lobby {}", text)))
+            }
+
+            AppDefines::QUERY_TOKEN => {
+                let sessions = self.sessions.lock().unwrap();
+                let token = sessions.token_of(entity_id).ok_or(ProtocolError::NoEntity)?;
+                Ok(Reply::Query { cmd: AppDefines::QUERY_TOKEN, fields: vec![("token", Value::Text(token.to_string()))] })
+            }
+
+            AppDefines::SET_NAME => {
+                let name = args.first().ok_or(ProtocolError::MissingArgument("name"))?.to_string();
+                let name = self.commands.update(move |logic| logic.rename_entity(entity_id, &name)).map_err(|error| match error {
+                    NameError::NoEntity => ProtocolError::NoEntity,
+                    NameError::Length => ProtocolError::BadValue("Invalid name length"),
+                    NameError::Character => ProtocolError::BadValue("Invalid name characters"),
+                    NameError::Taken => ProtocolError::NameTaken,
+                })?;
+                self.clients.lock().unwrap().set_name(&client_id, &name);
+                Ok(Reply::ack(code, format!("Name set to {}", name)))
+            }
+
+            AppDefines::SET_TEAM => {
+                let team = args.first().ok_or(ProtocolError::MissingArgument("team"))?
+                    .trim()
+                    .parse::<u8>()
+                    .map_err(|_| ProtocolError::BadValue("Invalid team, use 0 to 255"))?;
+                let team = (team != 0).then_some(team);
+                if !self.commands.update(move |logic| logic.set_team(entity_id, team)) {
+                    return Err(ProtocolError::NoEntity);
+                }
+                match team {
+                    Some(team) => Ok(Reply::ack(code, format!("Team set to {}", team))),
+                    None => Ok(Reply::ack(code, "Team left")),
+                }
+            }
+
+            AppDefines::SET_WEAPON => {
+                let kind = args.first().ok_or(ProtocolError::MissingArgument("weapon"))?;
+                let kind = BulletKind::from_name(kind.trim())
+                    .ok_or(ProtocolError::BadValue("Unknown weapon, use STANDARD, SNIPER or SCATTER"))?;
+                let entity = self.commands.update(move |logic| logic.get_entity_mut(entity_id).map(|entity| entity.weapon = kind));
+                entity.ok_or(ProtocolError::NoEntity)?;
+                Ok(Reply::ack(code, format!("Weapon set to {}", kind.name())))
+            }
+
+            AppDefines::DEPLOY_MINE => {
+                self.commands.update(move |logic| logic.deploy_mine(entity_id)).map_err(|error| match error {
+                    DeployError::NoEntity => ProtocolError::NoEntity,
+                    DeployError::Limit => ProtocolError::MineLimit,
+                })?;
+                Ok(Reply::ack(code, "Mine deployed"))
+            }
+
+            AppDefines::QUERY_CLOSEST_BOT => {
+                let enemies_only = match args.first().map(|arg| arg.trim()) {
+                    None | Some("") => false,
+                    Some(AppDefines::CLOSEST_ENEMIES) => true,
+                    Some(_) => return Err(ProtocolError::BadValue("Unknown filter, use ENEMIES")),
+                };
+                self.commands.query(move |logic| {
+                    if !logic.entities.iter().any(|e| e.id == entity_id) {
+                        return Err(ProtocolError::NoEntity);
+                    }
+                    let Some((closest, distance)) = logic.closest_entity(entity_id, enemies_only) else {
+                        return Ok(Reply::Empty);
+                    };
+                    Ok(Reply::Query {
+                        cmd: AppDefines::QUERY_CLOSEST_BOT,
+                        fields: vec![
+                            ("name", Value::Text(closest.name.clone())),
+                            ("x", Value::Float(closest.x as f64, 1)),
+                            ("y", Value::Float(closest.y as f64, 1)),
+                            ("distance", Value::Float(distance as f64, 1)),
+                        ],
+                    })
+                })
+            }
+
+            AppDefines::QUERY_CLOSEST_OBSTACLE => {
+                let with_walls = match args.first().map(|arg| arg.trim()) {
+                    None | Some("") => false,
+                    Some(AppDefines::CLOSEST_WALLS) => true,
+                    Some(_) => return Err(ProtocolError::BadValue("Unknown filter, use WALLS")),
+                };
+                self.commands.query(move |logic| {
+                    if !logic.entities.iter().any(|e| e.id == entity_id) {
+                        return Err(ProtocolError::NoEntity);
+                    }
+                    let Some((distance, bearing, obstacle)) = logic.closest_obstacle(entity_id, with_walls) else {
+                        return Ok(Reply::Empty);
+                    };
+                    let shape = obstacle.map_or(AppDefines::BOUNDARY_SHAPE, |obstacle| obstacle.shape.name());
+                    Ok(Reply::Query {
+                        cmd: AppDefines::QUERY_CLOSEST_OBSTACLE,
+                        fields: vec![
+                            ("distance", Value::Float(distance as f64, 1)),
+                            ("bearing", Value::Float(bearing.to_degrees() as f64, 1)),
+                            ("shape", Value::Text(shape.to_string())),
+                        ],
+                    })
+                })
             }
 
             AppDefines::SET_COLOR => {
-                if args.is_empty() {
-                    "Missing color value".to_string()
-                } else if args.len() == 1 {
+                let (r, g, b) = match args.len() {
+                    0 => return Err(ProtocolError::MissingArgument("color value")),
                     // Cas couleur hexadécimale unique, ex: COL=FF00FF
-                    if let Ok(hex) = u32::from_str_radix(args[0], 16) {
-                        let r = ((hex >> 16) & 0xFF) as u8;
-                        let g = ((hex >> 8) & 0xFF) as u8;
-                        let b = (hex & 0xFF) as u8;
-                        let mut logic = self.game_logic.lock().unwrap();
-                        if let Some(entity) = logic.get_entity_mut(entity_id) {
-                            entity.color = egui::Color32::from_rgb(r, g, b);
-                            format!("Color set to RGB({}, {}, {})", r, g, b)
-                        } else {
-                            "Entity not found".to_string()
-                        }
-                    } else {
-                        "Invalid color hex value".to_string()
+                    1 => {
+                        let hex = u32::from_str_radix(args[0].trim(), 16)
+                            .map_err(|_| ProtocolError::BadValue("Invalid color hex value"))?;
+                        (((hex >> 16) & 0xFF) as u8, ((hex >> 8) & 0xFF) as u8, (hex & 0xFF) as u8)
                     }
-                } else if args.len() == 3 {
                     // Cas RGB séparé par "=", ex: COL=255=234=234
-                    if let (Ok(r), Ok(g), Ok(b)) = (
+                    3 => match (
                         args[0].trim().parse::<u8>(),
                         args[1].trim().parse::<u8>(),
                         args[2].trim().parse::<u8>(),
                     ) {
-                        let mut logic = self.game_logic.lock().unwrap();
-                        if let Some(entity) = logic.get_entity_mut(entity_id) {
-                            entity.color = egui::Color32::from_rgb(r, g, b);
-                            format!("Color set to RGB({}, {}, {})", r, g, b)
-                        } else {
-                            "Entity not found".to_string()
-                        }
-                    } else {
-                        "Invalid RGB values".to_string()
-                    }
-                } else {
-                    "Invalid color format. Use hex or R=G=B".to_string()
+                        (Ok(r), Ok(g), Ok(b)) => (r, g, b),
+                        _ => return Err(ProtocolError::BadValue("Invalid RGB values")),
+                    },
+                    _ => return Err(ProtocolError::BadValue("Invalid color format. Use hex or R=G=B")),
+                };
+                let entity = self.commands.update(move |logic| logic.get_entity_mut(entity_id).map(|entity| entity.color = egui::Color32::from_rgb(r, g, b)));
+                entity.ok_or(ProtocolError::NoEntity)?;
+                Ok(Reply::ack(code, format!("Color set to RGB({}, {}, {})", r, g, b)))
+            }
+
+            AppDefines::MESSAGE => {
+                // Le texte peut contenir le séparateur des arguments
+                let text = args.join(AppDefines::ARGUMENT_SEP);
+                if text.is_empty() {
+                    return Err(ProtocolError::MissingArgument("message"));
                 }
+                chat::check_text(&text).map_err(ProtocolError::BadValue)?;
+                let sender = self.world.latest().entity(entity_id).ok_or(ProtocolError::NoEntity)?.name.clone();
+                add_chat_message(&self.messages, &sender, &text);
+                // Un joueur coupé côté serveur reçoit la même réponse, sans que personne ne lise son message
+                if !self.chat_mutes.lock().unwrap().drops(&sender) {
+                    chat::relay(&self.connections, &self.client_stats, &sender, &text, Some(client_id));
+                }
+                Ok(Reply::ack(code, text))
             }
 
             AppDefines::ACTUATOR_MOTOR_LEFT |
             AppDefines::ACTUATOR_MOTOR_RIGHT |
             AppDefines::ACTUATOR_GUN_TRIGGER |
-            AppDefines::ACTUATOR_GUN_TRAVERSE => {
-                if let Some(val_str) = args.get(0) {
-                    match val_str.trim().parse::<f32>() {
-                        Ok(val) => {
-                            let mut logic = self.game_logic.lock().unwrap();
-                            if let Some(ent) = logic.get_entity_mut(entity_id) {
-                                match code {
-                                    AppDefines::ACTUATOR_MOTOR_LEFT => ent.motor_left = val,
-                                    AppDefines::ACTUATOR_MOTOR_RIGHT => ent.motor_right = val,
-                                    AppDefines::ACTUATOR_GUN_TRIGGER => ent.gun_trigger = val,
-                                    AppDefines::ACTUATOR_GUN_TRAVERSE => ent.gun_traverse = val,
-                                    _ => {}
-                                }
-                                format!("{} set to {}", code, val)
-                            } else {
-                                "Entity not found".to_string()
-                            }
+            AppDefines::ACTUATOR_GUN_TRAVERSE |
+            AppDefines::ACTUATOR_SHIELD => {
+                let gun = matches!(code, AppDefines::ACTUATOR_GUN_TRIGGER | AppDefines::ACTUATOR_GUN_TRAVERSE);
+                let (args, seq) = ClientHandler::split_sequence(&args)?;
+                // `GunTrig=1=0.9` vise la tourelle 1, sans indice la première
+                let (turret, val_str) = match args {
+                    [turret, value] if gun => {
+                        let turret = turret.trim().parse::<usize>()
+                            .map_err(|_| ProtocolError::BadValue("Invalid turret index"))?;
+                        (turret, *value)
+                    }
+                    _ => (0, *args.first().ok_or(ProtocolError::MissingArgument("value"))?),
+                };
+                let val = val_str.trim().parse::<f32>()
+                    .map_err(|_| ProtocolError::BadValue("Invalid float value"))?;
+                let clamped = Entity::clamp_actuator(val)
+                    .ok_or(ProtocolError::BadValue("Actuator values must be finite"))?;
+                let code = ClientHandler::static_code(code);
+                // Un observateur n'a pas d'entité : ses moteurs pilotent sa caméra, sans budget
+                if let Some(camera_id) = self.camera {
+                    if !self.commands.ask(|reply| GameCommand::SteerCamera { camera_id, code, value: clamped, reply }) {
+                        return Err(ProtocolError::NoEntity);
+                    }
+                    return Ok(ClientHandler::actuator_reply(code, val, clamped));
+                }
+                let outcome = self.commands.ask(|reply| GameCommand::SetActuator {
+                    entity_id,
+                    code,
+                    turret,
+                    value: clamped,
+                    seq,
+                    reply: Some(reply),
+                });
+                match outcome {
+                    Ok(()) => {}
+                    Err(ActuatorRefusal::NoEntity) => return Err(ProtocolError::NoEntity),
+                    Err(ActuatorRefusal::NoTurret) => return Err(ProtocolError::BadValue("No such turret")),
+                    Err(ActuatorRefusal::ControlledLocally) => {
+                        if !std::mem::replace(&mut self.warned_controlled, true) {
+                            add_message(
+                                &self.messages,
+                                format!("[WARNING] Entity {} is driven from the game UI, the actuator commands of client {} are refused.", entity_id, self.client_id),
+                                MessageType::Warning,
+                                MessageOrigin::Network,
+                            );
                         }
-                        Err(_) => "Invalid float value".to_string(),
+                        return Err(ProtocolError::ControlledLocally);
+                    }
+                    Err(ActuatorRefusal::Stale(newest)) => {
+                        self.warned_controlled = false;
+                        return Ok(Reply::Query {
+                            cmd: AppDefines::ACTUATOR_STALE,
+                            fields: vec![
+                                ("command", Value::Text(code.to_string())),
+                                ("sequence", Value::Int(seq.unwrap_or_default() as i64)),
+                                ("newest", Value::Int(newest as i64)),
+                            ],
+                        });
                     }
-                } else {
-                    "Missing value".to_string()
                 }
+                self.warned_controlled = false;
+                Ok(ClientHandler::actuator_reply(code, val, clamped))
             }
 
-            AppDefines::QUIT => {
-                self.handle_disconnection();
-                return;
+            AppDefines::QUERY_STATUS => {
+                let world = self.world.latest();
+                // Le client garde son id d'entité après sa destruction
+                let Some(ent) = world.entity(entity_id) else {
+                    return Ok(Reply::Query {
+                        cmd: AppDefines::QUERY_STATUS,
+                        fields: vec![("state", Value::Text(AppDefines::STATUS_DEAD.to_string()))],
+                    });
+                };
+                Ok(ClientHandler::status(ent))
+            }
+
+            AppDefines::QUERY_LIDAR => {
+                let rays = match args.first() {
+                    Some(rays) => rays.trim().parse::<usize>().map_err(|_| ProtocolError::BadValue("Invalid ray count"))?,
+                    None => AppDefines::LIDAR_DEFAULT_RAYS,
+                };
+                let rays = rays.clamp(1, AppDefines::LIDAR_MAX_RAYS);
+                let distances = self.commands
+                    .ask(|reply| GameCommand::Lidar { entity_id, rays, range: AppDefines::LIDAR_MAX_RANGE, reply })
+                    .ok_or(ProtocolError::NoEntity)?;
+                let records = distances.into_iter().map(|distance| vec![("distance", Value::Float(distance as f64, 1))]).collect();
+                Ok(Reply::Records { cmd: AppDefines::QUERY_LIDAR, records })
+            }
+
+            AppDefines::QUERY_FOV => {
+                let half_angle = args.first().ok_or(ProtocolError::MissingArgument("half angle"))?
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| ProtocolError::BadValue("Invalid half angle"))?;
+                if !(0.0..=180.0).contains(&half_angle) {
+                    return Err(ProtocolError::BadValue("Half angle must be between 0 and 180 degrees"));
+                }
+                let range = args.get(1).ok_or(ProtocolError::MissingArgument("range"))?
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| ProtocolError::BadValue("Invalid range"))?;
+                if !range.is_finite() || range < 0.0 {
+                    return Err(ProtocolError::BadValue("Invalid range"));
+                }
+                // `los=1` arrive découpé en deux arguments par le séparateur
+                let line_of_sight = match args.get(2..).unwrap_or_default().join(AppDefines::ARGUMENT_SEP).trim() {
+                    "" | "los=0" => false,
+                    "los=1" => true,
+                    _ => return Err(ProtocolError::BadValue("Unknown option, use los=1")),
+                };
+                let targets = self.commands
+                    .ask(|reply| GameCommand::FieldOfView { entity_id, half_angle: half_angle.to_radians(), range, line_of_sight, reply })
+                    .ok_or(ProtocolError::NoEntity)?;
+                let records = targets.into_iter().map(|(name, distance, bearing)| vec![
+                    ("name", Value::Text(name)),
+                    ("distance", Value::Float(distance as f64, 1)),
+                    ("bearing", Value::Float(bearing.to_degrees() as f64, 1)),
+                ]).collect();
+                Ok(Reply::Records { cmd: AppDefines::QUERY_FOV, records })
+            }
+
+            AppDefines::QUERY_DAMAGE => {
+                // Le curseur d'une autre entité, celle d'avant une reconnexion, ne compte pas
+                let (cursor_entity, cursor) = self.damage_cursor;
+                let since = if cursor_entity == entity_id { cursor } else { 0 };
+                let (records, hits_taken) = self.commands.query(move |logic| {
+                    let entity = logic.entities.iter().find(|entity| entity.id == entity_id)?;
+                    let records: Vec<Fields> = entity.recent_hits.iter().filter(|hit| hit.seq > since).map(|hit| vec![
+                        ("tick", Value::Int(hit.tick as i64)),
+                        ("bearing", Value::Float(hit.bearing.to_degrees() as f64, 1)),
+                        ("damage", Value::Int(hit.damage as i64)),
+                    ]).collect();
+                    Some((records, entity.hits_taken))
+                }).ok_or(ProtocolError::NoEntity)?;
+                self.damage_cursor = (entity_id, hits_taken);
+                Ok(Reply::Records { cmd: AppDefines::QUERY_DAMAGE, records })
+            }
+
+            AppDefines::QUERY_SENSORS => {
+                // Une seule commande : toutes les valeurs viennent du même pas de simulation
+                self.commands.query(move |logic| ClientHandler::sensors(logic, entity_id)).ok_or(ProtocolError::NoEntity)
+            }
+
+            AppDefines::QUERY_ORIENTATION => {
+                self.commands.query(move |logic| {
+                    let entity = logic.entities.iter().find(|e| e.id == entity_id).ok_or(ProtocolError::NoEntity)?;
+                    let body = logic.physics_engine.bodies.get(entity.handle).ok_or(ProtocolError::NoEntity)?;
+                    Ok(Reply::Query {
+                        cmd: AppDefines::QUERY_ORIENTATION,
+                        fields: vec![
+                            ("orientation", Value::Float(entity.self_orientation, 3)),
+                            ("gun_orientation", Value::Float(GameLogic::gun_angle(entity, body) as f64, 3)),
+                            ("gun_traverse", Value::Float(entity.gun().orientation, 3)),
+                        ],
+                    })
+                })
+            }
+
+            AppDefines::QUERY_ENERGY => {
+                self.commands.query(move |logic| {
+                    let ent = logic.entities.iter().find(|e| e.id == entity_id).ok_or(ProtocolError::NoEntity)?;
+                    Ok(Reply::Query {
+                        cmd: AppDefines::QUERY_ENERGY,
+                        fields: vec![
+                            ("energy", Value::Float(ent.energy as f64, 1)),
+                            ("shield", Value::Int(ent.shield_active() as i64)),
+                        ],
+                    })
+                })
+            }
+
+            _ => Err(ProtocolError::UnknownCommand(code.to_string())),
+        }
+    }
+
+    /// Returns the canonical spelling of a command code, whatever its case.
+    ///
+    /// # Returns
+    ///
+    /// The matching `AppDefines` constant, or the code unchanged if it is unknown.
+    ///
+    pub(crate) fn canonical_code(code: &str) -> &str {
+        COMMAND_CODES.iter().find(|known| known.eq_ignore_ascii_case(code)).copied().unwrap_or(code)
+    }
+
+    /// Returns the code of a known command as the `AppDefines` constant itself, for the commands sent to the game loop.
+    pub(crate) fn static_code(code: &str) -> &'static str {
+        COMMAND_CODES.iter().find(|known| **known == code).copied().unwrap_or_default()
+    }
+
+    /// Returns whether a command sets an actuator.
+    pub(crate) fn is_actuator(code: &str) -> bool {
+        matches!(
+            code,
+            AppDefines::ACTUATOR_MOTOR_LEFT
+                | AppDefines::ACTUATOR_MOTOR_RIGHT
+                | AppDefines::ACTUATOR_GUN_TRIGGER
+                | AppDefines::ACTUATOR_GUN_TRAVERSE
+                | AppDefines::ACTUATOR_SHIELD
+        )
+    }
+
+    /// Splits the sequence number off the arguments of an actuator command, e.g. `MotL=0.8=@1234`.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments of the command.
+    ///
+    /// # Returns
+    ///
+    /// The other arguments and the sequence number, `None` when the last argument does not start with `@`.
+    ///
+    pub(crate) fn split_sequence<'a, 'b>(args: &'a [&'b str]) -> Result<(&'a [&'b str], Option<u64>), ProtocolError> {
+        match args.split_last() {
+            Some((last, rest)) if last.trim().starts_with('@') => {
+                let seq = last.trim()[1..].parse::<u64>().map_err(|_| ProtocolError::BadValue("Invalid sequence number"))?;
+                Ok((rest, Some(seq)))
             }
+            _ => Ok((args, None)),
+        }
+    }
 
-            _ => format!("Unknown command: {}", code),
+    /// Builds the reply to an actuator command.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The code of the actuator command.
+    /// * `requested` - The value sent by the client.
+    /// * `clamped` - The value actually set, see `Entity::clamp_actuator`.
+    ///
+    /// # Returns
+    ///
+    /// An `ACTUATOR_CLAMPED` reply if the value was clamped, an acknowledgement otherwise.
+    ///
+    fn actuator_reply(code: &str, requested: f32, clamped: f32) -> Reply {
+        if clamped != requested {
+            return Reply::Query {
+                cmd: AppDefines::ACTUATOR_CLAMPED,
+                fields: vec![
+                    ("command", Value::Text(code.to_string())),
+                    ("value", Value::Float(clamped as f64, 3)),
+                    ("requested", Value::Float(requested as f64, 6)),
+                ],
+            };
+        }
+        Reply::ack(code, format!("{} set to {}", code, requested))
+    }
+
+    /// Returns whether a command needs an entity of the client: everything but the
+    /// handshake, spectating, reconnection, world queries, `PING` and admin commands.
+    fn controls_entity(code: &str) -> bool {
+        !matches!(
+            code,
+            AppDefines::HELLO
+                | AppDefines::SPECTATE
+                | AppDefines::OBSERVE
+                | AppDefines::RECONNECT
+                | AppDefines::QUERY_WORLD
+                | AppDefines::QUERY_ARENA
+                | AppDefines::QUERY_MAP_SEED
+                | AppDefines::QUERY_ZONE
+                | AppDefines::QUERY_CONTROL_POINTS
+                | AppDefines::QUERY_MATCH
+                | AppDefines::QUERY_EVENTS
+                | AppDefines::QUERY_LEADERBOARD
+                | AppDefines::QUERY_OBSTACLES
+                | AppDefines::QUERY_BY_NAME
+                | AppDefines::FORMAT
+                | AppDefines::SUBSCRIBE
+                | AppDefines::UNSUBSCRIBE
+                | AppDefines::RESYNC
+                | AppDefines::PING
+                | AppDefines::ADMIN
+                | AppDefines::KICK
+                | AppDefines::BAN
+                | AppDefines::ADD_AI
+                | AppDefines::PAUSE
+                | AppDefines::TIME_SCALE
+                | AppDefines::TIMINGS
+                | AppDefines::LOCK
+        )
+    }
+
+    /// Builds the reply to `STATUS` for an entity still in the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `ent` - The entity, as the last world snapshot saw it.
+    ///
+    fn status(ent: &EntityStatus) -> Reply {
+        // Arrondi au-dessus : attendre ce délai garantit que le tir sera accepté
+        let cooldown_ms = ent.fire_cooldown.as_micros().div_ceil(1000);
+        Reply::Query {
+            cmd: AppDefines::QUERY_STATUS,
+            fields: vec![
+                ("state", Value::Text(AppDefines::STATUS_ALIVE.to_string())),
+                ("health", Value::Int(ent.health as i64)),
+                ("score", Value::Int(ent.score as i64)),
+                ("x", Value::Float(ent.x as f64, 1)),
+                ("y", Value::Float(ent.y as f64, 1)),
+                ("fire_cooldown_ms", Value::Int(cooldown_ms as i64)),
+                ("spread", Value::Float(ent.spread as f64, 3)),
+                ("shots_fired", Value::Int(ent.breakdown.shots_fired as i64)),
+                ("hits", Value::Int(ent.breakdown.hits as i64)),
+                ("kills", Value::Int(ent.breakdown.kills as i64)),
+                ("deaths", Value::Int(ent.breakdown.deaths as i64)),
+                ("accuracy", ClientHandler::accuracy(ent.breakdown.accuracy())),
+                ("bullets_left", match ent.bullets_left {
+                    Some(left) => Value::Int(left as i64),
+                    None => Value::Text(AppDefines::EMPTY_REPLY.to_string()),
+                }),
+                ("fire_energy", Value::Float(ent.fire_energy as f64, 1)),
+                ("last_hit_bearing", match ent.last_hit_bearing {
+                    Some(bearing) => Value::Float(bearing.to_degrees() as f64, 1),
+                    None => Value::Text(AppDefines::EMPTY_REPLY.to_string()),
+                }),
+                ("actuator_seq", match ent.actuator_seq {
+                    Some(seq) => Value::Int(seq as i64),
+                    None => Value::Text(AppDefines::EMPTY_REPLY.to_string()),
+                }),
+                ("armor", Value::Float(ent.armor as f64, 2)),
+            ],
+        }
+    }
+
+    /// Builds the state of every entity, or of those a ghost camera sees.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The code of the reply.
+    /// * `world` - The last world snapshot.
+    /// * `view` - The camera of an observing subscriber, `None` for every entity.
+    ///
+    /// # Returns
+    ///
+    /// One `id,x,y,orientation,health,score,name` record per entity.
+    ///
+    pub(crate) fn world_state(code: &'static str, world: &WorldSnapshot, view: Option<&GhostCamera>) -> Reply {
+        let seen = |entity: &&EntityStatus| view.map_or(true, |camera| camera.sees(entity.x, entity.y));
+        let records = world.entities.iter().filter(seen).map(|entity| {
+            vec![
+                ("id", Value::Int(entity.id as i64)),
+                ("x", Value::Float(entity.x as f64, 1)),
+                ("y", Value::Float(entity.y as f64, 1)),
+                ("orientation", Value::Float(entity.orientation, 3)),
+                ("health", Value::Int(entity.health as i64)),
+                ("score", Value::Int(entity.score as i64)),
+                ("name", Value::Text(entity.name.clone())),
+            ]
+        }).collect();
+        Reply::Records { cmd: code, records }
+    }
+
+    /// Builds the game events a client has not received yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The last world snapshot.
+    /// * `cursor` - The number of the last event sent to the client.
+    ///
+    /// # Returns
+    ///
+    /// One `seq,step,type,source,target,value` record per event, oldest first.
+    ///
+    fn events(world: &WorldSnapshot, cursor: u64) -> Reply {
+        let empty = || Value::Text(AppDefines::EMPTY_REPLY.to_string());
+        let id = |id: u32| Value::Int(id as i64);
+        let records = world.events.since(cursor).map(|queued| {
+            let (source, target, value) = match &queued.event {
+                GameEvent::Spawn { id: spawned, .. } => (id(*spawned), empty(), empty()),
+                GameEvent::BulletFired { shooter, .. } => (id(*shooter), empty(), empty()),
+                GameEvent::Hit { shooter, victim, damage, .. } => (id(*shooter), id(*victim), Value::Int(*damage as i64)),
+                GameEvent::Kill { shooter, victim, .. } => (id(*shooter), id(*victim), empty()),
+                GameEvent::Detonation { owner, .. } => (id(*owner), empty(), empty()),
+                GameEvent::Despawn { id: despawned } => (id(*despawned), empty(), empty()),
+                GameEvent::OutOfBounds { id: entity, .. } => (id(*entity), empty(), empty()),
+                GameEvent::PickUp { id: entity, power_up, .. } => (id(*entity), empty(), Value::Text(power_up.clone())),
+                GameEvent::Penalty { id: entity, points, .. } | GameEvent::Bonus { id: entity, points, .. } => {
+                    (id(*entity), empty(), Value::Int(*points as i64))
+                }
+                GameEvent::Camping { id: entity, .. } => (id(*entity), empty(), empty()),
+                GameEvent::Capture { point, owner } => (Value::Text(owner.code()), empty(), Value::Text(point.clone())),
+                GameEvent::Wave { wave, state, .. } => (Value::Int(*wave as i64), empty(), Value::Text(state.code().to_string())),
+                GameEvent::RuleChange { .. } | GameEvent::RoundStart { .. } | GameEvent::RoundEnd | GameEvent::MatchEnd { .. } => {
+                    (empty(), empty(), empty())
+                }
+            };
+            vec![
+                ("seq", Value::Int(queued.seq as i64)),
+                ("step", Value::Int(queued.step as i64)),
+                ("type", Value::Text(queued.event.kind().to_string())),
+                ("source", source),
+                ("target", target),
+                ("value", value),
+            ]
+        }).collect();
+        Reply::Records { cmd: AppDefines::QUERY_EVENTS, records }
+    }
+
+    /// Builds the position, size and shape of every obstacle.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic.
+    ///
+    /// # Returns
+    ///
+    /// One `x,y,half_width,half_height,shape,angle` record per obstacle.
+    ///
+    fn obstacles(logic: &GameLogic) -> Reply {
+        let records = logic.obstacles.iter().map(|obstacle| {
+            // La forme vient après la taille : les clients lisant 4 champs restent compatibles
+            let half_extents = obstacle.shape.half_extents();
+            vec![
+                ("x", Value::Float(obstacle.position.0, 1)),
+                ("y", Value::Float(obstacle.position.1, 1)),
+                ("half_width", Value::Float(half_extents.0 as f64, 1)),
+                ("half_height", Value::Float(half_extents.1 as f64, 1)),
+                ("shape", Value::Text(obstacle.shape.name().to_string())),
+                ("angle", Value::Float(obstacle.shape.angle() as f64, 3)),
+            ]
+        }).collect();
+        Reply::Records { cmd: AppDefines::QUERY_OBSTACLES, records }
+    }
+
+    /// Builds the position, size and state of every control point.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic.
+    ///
+    /// # Returns
+    ///
+    /// One `x,y,radius,owner,progress,name` record per control point.
+    ///
+    fn control_points(logic: &GameLogic) -> Reply {
+        let records = logic.control_points().iter().map(|point| {
+            let owner = point.owner.map_or_else(|| AppDefines::EMPTY_REPLY.to_string(), |owner| owner.code());
+            vec![
+                ("x", Value::Float(point.position.0 as f64, 1)),
+                ("y", Value::Float(point.position.1 as f64, 1)),
+                ("radius", Value::Float(point.radius as f64, 1)),
+                ("owner", Value::Text(owner)),
+                ("progress", Value::Float(point.progress as f64, 3)),
+                ("name", Value::Text(point.name.clone())),
+            ]
+        }).collect();
+        Reply::Records { cmd: AppDefines::QUERY_CONTROL_POINTS, records }
+    }
+
+    /// Builds the durations of the step phases.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic.
+    ///
+    /// # Returns
+    ///
+    /// One `phase,avg_ms,max_ms,samples,total_ms` record per phase, in the order they run in.
+    ///
+    fn step_timings(logic: &GameLogic) -> Reply {
+        let timings = &logic.step_timings;
+        let records = StepPhase::ALL.iter().map(|&phase| {
+            vec![
+                ("phase", Value::Text(phase.name().to_string())),
+                ("avg_ms", Value::Float(timings.average(phase).as_secs_f64() * 1000.0, 3)),
+                ("max_ms", Value::Float(timings.worst(phase).as_secs_f64() * 1000.0, 3)),
+                ("samples", Value::Int(timings.samples(phase) as i64)),
+                ("total_ms", Value::Float(timings.total(phase).as_secs_f64() * 1000.0, 3)),
+            ]
+        }).collect();
+        Reply::Records { cmd: AppDefines::TIMINGS, records }
+    }
+
+    /// Encodes an accuracy, `EMPTY` before the first shot.
+    ///
+    /// # Arguments
+    ///
+    /// * `accuracy` - The share of the shots that hit an opponent.
+    ///
+    fn accuracy(accuracy: Option<f32>) -> Value {
+        match accuracy {
+            Some(accuracy) => Value::Float(accuracy as f64, 3),
+            None => Value::Text(AppDefines::EMPTY_REPLY.to_string()),
+        }
+    }
+
+    /// Builds the state of an entity and of its closest threats.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic.
+    /// * `entity_id` - The id of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The `SENSORS` reply, or `None` if the entity does not exist.
+    ///
+    fn sensors(logic: &GameLogic, entity_id: u32) -> Option<Reply> {
+        let entity = logic.entities.iter().find(|e| e.id == entity_id)?;
+        let body = logic.physics_engine.bodies.get(entity.handle)?;
+        let cooldown_ms = entity.fire_cooldown_remaining(logic.sim_time()).as_micros().div_ceil(1000);
+        let enemy = logic.closest_entity(entity_id, true).map(|(enemy, distance)| {
+            (distance, GameLogic::bearing((entity.x, entity.y), entity.self_orientation as f32, (enemy.x, enemy.y)))
+        });
+        let bullet = logic.closest_hostile_bullet(entity_id);
+
+        let mut fields = vec![
+            ("x", Value::Float(entity.x as f64, 1)),
+            ("y", Value::Float(entity.y as f64, 1)),
+            ("orientation", Value::Float(entity.self_orientation, 3)),
+            ("gun_orientation", Value::Float(GameLogic::gun_angle(entity, body) as f64, 3)),
+            ("vx", Value::Float(entity.vx as f64, 1)),
+            ("vy", Value::Float(entity.vy as f64, 1)),
+            ("angular_velocity", Value::Float(body.angvel() as f64, 3)),
+            ("health", Value::Int(entity.health as i64)),
+            ("score", Value::Int(entity.score as i64)),
+            ("fire_cooldown_ms", Value::Int(cooldown_ms as i64)),
+        ];
+        // Des champs vides gardent les positions fixes pour les clients
+        for (distance_name, bearing_name, threat) in [
+            ("enemy_distance", "enemy_bearing", enemy),
+            ("bullet_distance", "bullet_bearing", bullet),
+        ] {
+            match threat {
+                Some((distance, bearing)) => {
+                    fields.push((distance_name, Value::Float(distance as f64, 1)));
+                    fields.push((bearing_name, Value::Float(bearing.to_degrees() as f64, 1)));
+                }
+                None => {
+                    fields.push((distance_name, Value::Text(AppDefines::EMPTY_REPLY.to_string())));
+                    fields.push((bearing_name, Value::Text(AppDefines::EMPTY_REPLY.to_string())));
+                }
+            }
+        }
+        Some(Reply::Query { cmd: AppDefines::QUERY_SENSORS, fields })
+    }
+
+    /// Refuses a new entity to the client while the lobby is locked, and closes its connection
+    /// once the error is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    ///
+    /// # Returns
+    ///
+    /// The error to answer the client with.
+    ///
+    fn refuse_locked(&mut self, client_id: ClientId) -> ProtocolError {
+        add_message(
+            &self.messages,
+            format!("[WARNING] Lobby locked, client {} refused.", client_id),
+            MessageType::Warning,
+            MessageOrigin::Network,
+        );
+        self.disconnect_pending = true;
+        ProtocolError::MatchLocked
+    }
+
+    /// Returns whether the client may get a new entity after its entity was destroyed:
+    /// a horde wave ended since.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The id of the entity of the client, live or destroyed.
+    ///
+    fn may_respawn(&self, entity_id: u32) -> bool {
+        self.destroyed_entity == Some(entity_id)
+            && self.world.latest().respawns_granted > self.respawns_seen
+    }
+
+    /// Creates the entity of the client and opens its reconnection session.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new entity.
+    ///
+    fn spawn_entity(&mut self, client_id: ClientId) -> u32 {
+        let entity_id = self.commands.ask(|reply| GameCommand::Spawn { name: "Player".to_string(), reply });
+        // L'ajout a republié le monde : l'instantané montre déjà la nouvelle entité
+        if is_logged(MessageType::Debug) {
+            let entities: Vec<String> = self.world.latest().entities.iter().map(|entity| format!("#{} {}", entity.id, entity.name)).collect();
+            add_message(&self.messages, format!("Current entities in game: {}", entities.join(", ")), MessageType::Debug, MessageOrigin::Game);
+        }
+        self.client_entity_map.lock().unwrap().insert(client_id, entity_id);
+        self.clients.lock().unwrap().set_entity(&client_id, Some((entity_id, "Player".to_string())));
+        self.sessions.lock().unwrap().open(entity_id);
+        entity_id
+    }
+
+    /// Tells the client where its new entity stands, with a `RESPAWN` line.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The id of the new entity.
+    ///
+    fn push_respawn(&mut self, entity_id: u32) {
+        let Some((x, y)) = self.world.latest().entity(entity_id).map(|entity| (entity.x, entity.y)) else { return };
+        let notice = Reply::Query {
+            cmd: AppDefines::PUSH_RESPAWN,
+            fields: vec![("x", Value::Float(x as f64, 1)), ("y", Value::Float(y as f64, 1))],
         };
+        let line = if self.json_mode { notice.to_json().to_string() } else { notice.to_legacy() };
+        self.send_line(&line);
+    }
 
-        let _ = writeln!(self.buf_writer, "{}", response);
-        let _ = self.buf_writer.flush();
+    /// Forgets the entity of the client after it was destroyed, and closes its session.
+    ///
+    /// The id stays known to the handler only, so that no other client or admin
+    /// command mistakes the client for the controller of a live entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The id of the client.
+    /// * `entity_id` - The id of the destroyed entity.
+    ///
+    fn forget_entity(&mut self, client_id: ClientId, entity_id: u32) {
+        self.client_entity_map.lock().unwrap().remove(&client_id);
+        self.sessions.lock().unwrap().close(entity_id);
+        self.clients.lock().unwrap().set_entity(&client_id, None);
+        self.destroyed_entity = Some(entity_id);
+        self.respawns_seen = self.world.latest().respawns_granted;
+        add_message(
+            &self.messages,
+            format!("[INFO] Entity {} of client {} was destroyed.", entity_id, client_id),
+            MessageType::Info,
+            MessageOrigin::Network,
+        );
     }
 
+    /// Closes the session of an entity removed by an admin, and the connection controlling it.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The id of the removed entity.
+    ///
+    fn release_entity(&mut self, entity_id: u32) {
+        self.sessions.lock().unwrap().close(entity_id);
+        let owner = self
+            .client_entity_map
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, id)| **id == entity_id)
+            .map(|(owner, _)| *owner);
+        if let Some(owner) = owner {
+            self.connections.lock().unwrap().shut_down(&owner);
+        }
+    }
+
+    /// Turns the connection into a spectator, releasing its entity if it had one.
+    fn become_spectator(&mut self, client_id: ClientId, entity_id: u32) {
+        if self.spectator {
+            return;
+        }
+        self.spectator = true;
+        self.clients.lock().unwrap().set_spectator(&client_id);
+        if entity_id != 0 {
+            self.client_entity_map.lock().unwrap().remove(&client_id);
+            self.sessions.lock().unwrap().close(entity_id);
+            self.commands.ask(|reply| GameCommand::Remove { entity_id, reply });
+        }
+        add_message(
+            &self.messages,
+            format!("[SPECTATOR] Client {} is now spectating.", client_id),
+            MessageType::Debug,
+            MessageOrigin::Network,
+        );
+    }
+
+    /// Returns the major part of a `<major>.<minor>` protocol version.
+    fn major_version(version: &str) -> &str {
+        version.split('.').next().unwrap_or("").trim()
+    }
 
-    fn handle_disconnection(&mut self) {
-        let peer_addr = match self.socket.peer_addr() {
-            Ok(addr) => addr,
-            Err(_) => {
+    /// Handles the disconnection of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `release_entity` - Whether the client left for good. Otherwise its entity is kept
+    ///   alive during the reconnection grace period.
+    ///
+    fn handle_disconnection(&mut self, release_entity: bool) {
+        if !self.connected {
+            return;
+        }
+        self.connected = false;
+
+        let client_id = self.client_id;
+
+        self.subscriptions.lock().unwrap().unsubscribe(&client_id);
+        if let Some(camera) = self.camera.take() {
+            self.subscriptions.lock().unwrap().set_camera(client_id, None);
+            self.commands.update(move |logic| logic.remove_ghost_camera(camera));
+        }
+        self.connections.lock().unwrap().unregister(&client_id);
+        self.flush_stats();
+        self.client_stats.lock().unwrap().disconnect(&client_id);
+        self.clients.lock().unwrap().disconnect(&client_id);
+        let entity_id = self.client_entity_map.lock().unwrap().remove(&client_id);
+        match entity_id {
+            Some(entity_id) if release_entity => {
+                self.sessions.lock().unwrap().close(entity_id);
+                self.commands.ask(|reply| GameCommand::Remove { entity_id, reply });
                 add_message(
                     &self.messages,
-                    "[WARN] Could not get peer address during disconnection.".to_string(),
-                    MessageType::Warning,
+                    format!("[INFO] Client {} disconnected, entity {} removed.", client_id, entity_id),
+                    MessageType::Info,
+                    MessageOrigin::Network,
                 );
-                return;
             }
-        };
+            Some(entity_id) => {
+                self.sessions.lock().unwrap().detach(entity_id);
+                add_message(
+                    &self.messages,
+                    format!("[INFO] Client {} disconnected, entity {} kept for reconnection.", client_id, entity_id),
+                    MessageType::Info,
+                    MessageOrigin::Network,
+                );
+            }
+            None if self.spectator => {
+                add_message(
+                    &self.messages,
+                    format!("[SPECTATOR] Spectator {} disconnected.", client_id),
+                    MessageType::Debug,
+                    MessageOrigin::Network,
+                );
+            }
+            None => {
+                add_message(
+                    &self.messages,
+                    format!("[INFO] Client {} disconnected, but had no associated entity.", client_id),
+                    MessageType::Info,
+                    MessageOrigin::Network,
+                );
+            }
+        }
 
-        if let Some(entity_id) = self.client_entity_map.lock().unwrap().remove(&peer_addr) {
-            let mut logic = self.game_logic.lock().unwrap();
-            logic.remove_entity_by_id(entity_id);
-            add_message(
-                &self.messages,
-                format!("[INFO] Client {} disconnected, entity {} removed.", peer_addr, entity_id),
-                MessageType::Info,
-            );
-        } else {
-            add_message(
-                &self.messages,
-                format!("[INFO] Client {} disconnected, but had no associated entity.", peer_addr),
-                MessageType::Info,
-            );
+        if self.web_socket_open {
+            self.send_raw(&web_socket::encode_close());
         }
 
-        // Shutdown la socket, mais on ignore les erreurs bénignes
-        if let Err(e) = self.socket.shutdown(Shutdown::Both) {
-            add_message(
+        // Shutdown la socket, mais on ignore les erreurs bénignes : une connexion déjà coupée n'a plus rien à fermer
+        let shutdown = self.buf_writer.lock().unwrap().get_ref().shutdown();
+        match shutdown {
+            Err(e) if e.kind() != ErrorKind::NotConnected => add_message(
                 &self.messages,
-                format!("[WARN] Failed to shutdown socket for {}: {:?}", peer_addr, e),
+                format!("[WARN] Failed to shutdown socket for {}: {:?}", client_id, e),
                 MessageType::Warning,
-            );
+                MessageOrigin::Network,
+            ),
+            _ => {}
         }
     }
 
-    /// Adds a message to the response string.
+    /// Appends the response of one command to the response of a whole line.
     ///
     /// # Arguments
     ///
-    /// * `response` - The existing response string.
-    /// * `message` - The message to add to the response.
-    ///
-    /// # Returns
-    ///
-    /// The updated response string with the new message appended.
+    /// * `response` - The response being built, modified in place.
+    /// * `part` - The response of the next command.
     ///
-    pub fn add_to_reponse(mut reponse: String, message: String) {
-        if reponse != "" {
-            reponse += AppDefines::COMMAND_SEP;
-            return reponse += &*message;
+    pub fn append_response(response: &mut String, part: &str) {
+        if !response.is_empty() {
+            response.push_str(AppDefines::COMMAND_SEP);
         }
+        response.push_str(part);
+    }
+}
+
+impl Drop for ClientHandler {
+    /// Runs the disconnection of a client that was not disconnected, e.g. after its handler panicked,
+    /// so that its entity and its registrations never outlive it.
+    fn drop(&mut self) {
+        // Un verrou empoisonné par la panique du handler ne doit pas arrêter le worker
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| self.handle_disconnection(false)));
     }
 }
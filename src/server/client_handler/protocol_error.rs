@@ -0,0 +1,176 @@
+use crate::app_defines::AppDefines;
+
+/// Enum representing the errors a command can be answered with.
+pub(crate) enum ProtocolError {
+    /// The command code is not known. Holds the received code.
+    UnknownCommand(String),
+    /// A required argument is missing. Holds a description of the argument.
+    MissingArgument(&'static str),
+    /// An argument could not be parsed. Holds a description of the problem.
+    BadValue(&'static str),
+    /// The client has no entity in the game.
+    NoEntity,
+    /// A command was received before the handshake.
+    HandshakeRequired,
+    /// The client speaks an unsupported protocol version. Holds the client version.
+    VersionMismatch(String),
+    /// The reconnection token is not known.
+    UnknownToken,
+    /// The grace period of the reconnection token expired.
+    TokenExpired,
+    /// The reconnection token belongs to a connected client.
+    TokenInUse,
+    /// A spectator sent a command controlling an entity.
+    SpectatorForbidden,
+    /// The server already serves the maximum number of clients.
+    ServerFull,
+    /// An admin command was sent before `ADMIN`.
+    AdminRequired,
+    /// The admin password is wrong, or admin commands are disabled.
+    BadPassword,
+    /// The address of the client is banned.
+    Banned,
+    /// The client was kicked by an admin.
+    Kicked,
+    /// The entity already has the maximum number of mines in the arena.
+    MineLimit,
+    /// The lobby is locked, the client cannot get a new entity.
+    MatchLocked,
+    /// The entity is driven with the keyboard from the game UI.
+    ControlledLocally,
+    /// The name asked for is held by another entity.
+    NameTaken,
+}
+
+impl ProtocolError {
+    /// Returns the numeric code of the error.
+    pub fn code(&self) -> u8 {
+        match self {
+            ProtocolError::UnknownCommand(_) => AppDefines::ERR_UNKNOWN_COMMAND,
+            ProtocolError::MissingArgument(_) => AppDefines::ERR_MISSING_ARG,
+            ProtocolError::BadValue(_) => AppDefines::ERR_BAD_VALUE,
+            ProtocolError::NoEntity => AppDefines::ERR_NO_ENTITY,
+            ProtocolError::HandshakeRequired => AppDefines::ERR_HANDSHAKE_REQUIRED,
+            ProtocolError::VersionMismatch(_) => AppDefines::ERR_VERSION_MISMATCH,
+            ProtocolError::UnknownToken => AppDefines::ERR_UNKNOWN_TOKEN,
+            ProtocolError::TokenExpired => AppDefines::ERR_TOKEN_EXPIRED,
+            ProtocolError::TokenInUse => AppDefines::ERR_TOKEN_IN_USE,
+            ProtocolError::SpectatorForbidden => AppDefines::ERR_SPECTATOR,
+            ProtocolError::ServerFull => AppDefines::ERR_SERVER_FULL,
+            ProtocolError::AdminRequired => AppDefines::ERR_ADMIN_REQUIRED,
+            ProtocolError::BadPassword => AppDefines::ERR_BAD_PASSWORD,
+            ProtocolError::Banned => AppDefines::ERR_BANNED,
+            ProtocolError::Kicked => AppDefines::ERR_KICKED,
+            ProtocolError::MineLimit => AppDefines::ERR_MINE_LIMIT,
+            ProtocolError::MatchLocked => AppDefines::ERR_MATCH_LOCKED,
+            ProtocolError::ControlledLocally => AppDefines::ERR_CONTROLLED_LOCALLY,
+            ProtocolError::NameTaken => AppDefines::ERR_NAME_TAKEN,
+        }
+    }
+
+    /// Returns the stable name of the error.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProtocolError::UnknownCommand(_) => "unknown_command",
+            ProtocolError::MissingArgument(_) => "missing_arg",
+            ProtocolError::BadValue(_) => "bad_value",
+            ProtocolError::NoEntity => "no_entity",
+            ProtocolError::HandshakeRequired => "handshake_required",
+            ProtocolError::VersionMismatch(_) => "version_mismatch",
+            ProtocolError::UnknownToken => "unknown_token",
+            ProtocolError::TokenExpired => "token_expired",
+            ProtocolError::TokenInUse => "token_in_use",
+            ProtocolError::SpectatorForbidden => "spectator",
+            ProtocolError::ServerFull => "server_full",
+            ProtocolError::AdminRequired => "admin_required",
+            ProtocolError::BadPassword => "bad_password",
+            ProtocolError::Banned => "banned",
+            ProtocolError::Kicked => "kicked",
+            ProtocolError::MineLimit => "mine_limit",
+            ProtocolError::MatchLocked => "match_locked",
+            ProtocolError::ControlledLocally => "controlled_locally",
+            ProtocolError::NameTaken => "name_taken",
+        }
+    }
+
+    /// Returns a human-readable description of the error.
+    pub fn text(&self) -> String {
+        match self {
+            ProtocolError::UnknownCommand(code) => format!("Unknown command: {}", code),
+            ProtocolError::MissingArgument(argument) => format!("Missing {}", argument),
+            ProtocolError::BadValue(problem) => problem.to_string(),
+            ProtocolError::NoEntity => "Entity not found".to_string(),
+            ProtocolError::HandshakeRequired => format!("Send {} first", AppDefines::HELLO),
+            ProtocolError::VersionMismatch(version) => format!(
+                "Protocol {} is not supported, this server speaks {}. Goodbye",
+                version,
+                AppDefines::PROTOCOL_VERSION
+            ),
+            ProtocolError::UnknownToken => "Unknown reconnection token".to_string(),
+            ProtocolError::TokenExpired => "Reconnection token expired, the entity was removed".to_string(),
+            ProtocolError::TokenInUse => "Reconnection token already in use by a connected client".to_string(),
+            ProtocolError::SpectatorForbidden => "Spectators cannot control an entity".to_string(),
+            ProtocolError::ServerFull => "Server full, try again later".to_string(),
+            ProtocolError::AdminRequired => format!("Send {} first", AppDefines::ADMIN),
+            ProtocolError::BadPassword => "Wrong admin password".to_string(),
+            ProtocolError::Banned => "This address is banned".to_string(),
+            ProtocolError::Kicked => "Kicked by an admin".to_string(),
+            ProtocolError::MineLimit => "Too many mines, wait for one to explode".to_string(),
+            ProtocolError::MatchLocked => format!("Match in progress, send {} or {} to get in", AppDefines::SPECTATE, AppDefines::RECONNECT),
+            ProtocolError::ControlledLocally => "The entity is driven from the server, actuator commands are ignored".to_string(),
+            ProtocolError::NameTaken => "Name already taken by another entity".to_string(),
+        }
+    }
+
+    /// Converts the error to its reply on the wire.
+    ///
+    /// # Returns
+    ///
+    /// The reply, formatted as `ERR=<code>=<name>=<text>`.
+    pub fn to_wire(&self) -> String {
+        format!(
+            "{}{sep}{}{sep}{}{sep}{}",
+            AppDefines::ERROR_REPLY,
+            self.code(),
+            self.name(),
+            self.text(),
+            sep = AppDefines::ARGUMENT_SEP
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_error_has_its_wire_form() {
+        let table = [
+            (ProtocolError::UnknownCommand("FLY".to_string()), "ERR=1=unknown_command=Unknown command: FLY"),
+            (ProtocolError::MissingArgument("value"), "ERR=2=missing_arg=Missing value"),
+            (ProtocolError::BadValue("Invalid float value"), "ERR=3=bad_value=Invalid float value"),
+            (ProtocolError::NoEntity, "ERR=4=no_entity=Entity not found"),
+            (ProtocolError::HandshakeRequired, "ERR=5=handshake_required=Send HELLO first"),
+            (ProtocolError::VersionMismatch("2.0".to_string()), "ERR=6=version_mismatch=Protocol 2.0 is not supported, this server speaks 1.0. Goodbye"),
+            (ProtocolError::UnknownToken, "ERR=7=unknown_token=Unknown reconnection token"),
+            (ProtocolError::TokenExpired, "ERR=8=token_expired=Reconnection token expired, the entity was removed"),
+            (ProtocolError::TokenInUse, "ERR=9=token_in_use=Reconnection token already in use by a connected client"),
+            (ProtocolError::SpectatorForbidden, "ERR=10=spectator=Spectators cannot control an entity"),
+            (ProtocolError::ServerFull, "ERR=11=server_full=Server full, try again later"),
+            (ProtocolError::AdminRequired, "ERR=12=admin_required=Send ADMIN first"),
+            (ProtocolError::BadPassword, "ERR=13=bad_password=Wrong admin password"),
+            (ProtocolError::Banned, "ERR=14=banned=This address is banned"),
+            (ProtocolError::Kicked, "ERR=15=kicked=Kicked by an admin"),
+            (ProtocolError::MineLimit, "ERR=16=mine_limit=Too many mines, wait for one to explode"),
+            (ProtocolError::MatchLocked, "ERR=17=match_locked=Match in progress, send SPECTATE or RECONNECT to get in"),
+            (ProtocolError::ControlledLocally, "ERR=18=controlled_locally=The entity is driven from the server, actuator commands are ignored"),
+            (ProtocolError::NameTaken, "ERR=19=name_taken=Name already taken by another entity"),
+        ];
+        for (error, wire) in &table {
+            assert_eq!(error.to_wire(), *wire);
+        }
+        // Un code par erreur, de 1 à 19 sans trou
+        let codes: Vec<u8> = table.iter().map(|(error, _)| error.code()).collect();
+        assert_eq!(codes, (1..=19).collect::<Vec<u8>>());
+    }
+}
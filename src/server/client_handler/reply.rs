@@ -0,0 +1,190 @@
+use serde_json::{json, Map, Number, Value as Json};
+
+use crate::app_defines::AppDefines;
+use crate::server::client_handler::protocol_error::ProtocolError;
+
+/// Enum representing a value of a reply.
+pub(crate) enum Value {
+    /// An integer.
+    Int(i64),
+    /// A float, with the number of decimals it is sent with.
+    Float(f64, usize),
+    /// A string.
+    Text(String),
+    /// A list of records, the last value of a reply. Legacy: one argument per record.
+    List(Vec<Fields>),
+}
+
+impl Value {
+    /// Converts the value to its legacy text encoding.
+    fn to_legacy(&self) -> String {
+        match self {
+            Value::Int(value) => value.to_string(),
+            Value::Float(value, decimals) => format!("{:.*}", decimals, value),
+            Value::Text(text) => text.clone(),
+            Value::List(records) => Value::legacy_records(records).join(AppDefines::ARGUMENT_SEP),
+        }
+    }
+
+    /// Converts the value to the arguments of a legacy reply: one per record for a list, one otherwise.
+    fn to_legacy_args(&self) -> Vec<String> {
+        match self {
+            Value::List(records) => Value::legacy_records(records),
+            _ => vec![self.to_legacy()],
+        }
+    }
+
+    /// Converts records to their legacy text encoding, the fields of a record joined by `FIELD_SEP`.
+    fn legacy_records(records: &[Fields]) -> Vec<String> {
+        records
+            .iter()
+            .map(|record| record.iter().map(|(_, value)| value.to_legacy()).collect::<Vec<_>>().join(AppDefines::FIELD_SEP))
+            .collect()
+    }
+
+    /// Converts the value to JSON.
+    fn to_json(&self) -> Json {
+        match self {
+            Value::Int(value) => json!(value),
+            Value::Float(value, decimals) => {
+                let factor = 10f64.powi(*decimals as i32);
+                Number::from_f64((value * factor).round() / factor).map_or(Json::Null, Json::Number)
+            }
+            Value::Text(text) => json!(text),
+            Value::List(records) => Json::Array(records.iter().map(|record| Json::Object(Reply::fields_object(record))).collect()),
+        }
+    }
+}
+
+/// A list of named values.
+pub(crate) type Fields = Vec<(&'static str, Value)>;
+
+/// Enum representing the reply to a command, encoded either in the legacy text format or in JSON.
+pub(crate) enum Reply {
+    /// A successful action. Legacy: `OK=<cmd>=<fields…>=<text>`.
+    Ack { cmd: String, fields: Fields, text: String },
+    /// The values answered by a query. Legacy: `<cmd>=<values…>`.
+    Query { cmd: &'static str, fields: Fields },
+    /// A list of records. Legacy: `<cmd>=<count>=<record>…`, with the fields of a record joined by `FIELD_SEP`.
+    Records { cmd: &'static str, records: Vec<Fields> },
+    /// A command without response. Legacy: `EMPTY`.
+    Empty,
+    /// An error. Legacy: `ERR=<code>=<name>=<text>`.
+    Error(ProtocolError),
+}
+
+impl Reply {
+    /// Creates the reply to a successful action without values.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - The code of the command.
+    /// * `text` - A human-readable description of the result.
+    ///
+    pub fn ack(cmd: &str, text: impl Into<String>) -> Self {
+        Reply::Ack { cmd: cmd.to_string(), fields: Vec::new(), text: text.into() }
+    }
+
+    /// Converts the reply to the legacy text format.
+    pub fn to_legacy(&self) -> String {
+        let parts: Vec<String> = match self {
+            Reply::Ack { cmd, fields, text } => {
+                let mut parts = vec![AppDefines::OK_REPLY.to_string(), cmd.clone()];
+                parts.extend(fields.iter().flat_map(|(_, value)| value.to_legacy_args()));
+                parts.push(text.clone());
+                parts
+            }
+            Reply::Query { cmd, fields } => {
+                let mut parts = vec![cmd.to_string()];
+                parts.extend(fields.iter().flat_map(|(_, value)| value.to_legacy_args()));
+                parts
+            }
+            Reply::Records { cmd, records } => {
+                let mut parts = vec![cmd.to_string(), records.len().to_string()];
+                parts.extend(Value::legacy_records(records));
+                parts
+            }
+            Reply::Empty => vec![AppDefines::EMPTY_REPLY.to_string()],
+            Reply::Error(error) => return error.to_wire(),
+        };
+        parts.join(AppDefines::ARGUMENT_SEP)
+    }
+
+    /// Converts the reply to a JSON object.
+    pub fn to_json(&self) -> Json {
+        let mut object = Map::new();
+        match self {
+            Reply::Ack { cmd, fields, text } => {
+                object.insert("cmd".to_string(), json!(cmd));
+                object.insert("ok".to_string(), json!(true));
+                Reply::insert_fields(&mut object, fields);
+                object.insert("text".to_string(), json!(text));
+            }
+            Reply::Query { cmd, fields } => {
+                object.insert("cmd".to_string(), json!(cmd));
+                Reply::insert_fields(&mut object, fields);
+            }
+            Reply::Records { cmd, records } => {
+                object.insert("cmd".to_string(), json!(cmd));
+                object.insert("count".to_string(), json!(records.len()));
+                let records: Vec<Json> = records.iter().map(|record| Json::Object(Reply::fields_object(record))).collect();
+                object.insert("records".to_string(), Json::Array(records));
+            }
+            Reply::Empty => {
+                object.insert("cmd".to_string(), json!(AppDefines::EMPTY_REPLY));
+            }
+            Reply::Error(error) => {
+                object.insert("cmd".to_string(), json!(AppDefines::ERROR_REPLY));
+                object.insert("code".to_string(), json!(error.code()));
+                object.insert("name".to_string(), json!(error.name()));
+                object.insert("text".to_string(), json!(error.text()));
+            }
+        }
+        Json::Object(object)
+    }
+
+    /// Adds named values to a JSON object.
+    fn insert_fields(object: &mut Map<String, Json>, fields: &Fields) {
+        for (name, value) in fields {
+            object.insert(name.to_string(), value.to_json());
+        }
+    }
+
+    /// Builds a JSON object of named values.
+    fn fields_object(fields: &Fields) -> Map<String, Json> {
+        let mut object = Map::new();
+        Reply::insert_fields(&mut object, fields);
+        object
+    }
+}
+
+impl From<ProtocolError> for Reply {
+    fn from(error: ProtocolError) -> Self {
+        Reply::Error(error)
+    }
+}
+
+/// Converts a JSON command `{"cmd": <code>, "args": [<values>…]}` to the legacy text format.
+///
+/// # Arguments
+///
+/// * `command` - The JSON command.
+///
+/// # Returns
+///
+/// The command as `<code>=<arg>…`, or `None` if it is not a valid JSON command.
+///
+pub(crate) fn legacy_command(command: &Json) -> Option<String> {
+    let mut parts = vec![command.get("cmd")?.as_str()?.to_string()];
+    if let Some(args) = command.get("args") {
+        for arg in args.as_array()? {
+            match arg {
+                Json::String(text) => parts.push(text.clone()),
+                Json::Number(number) => parts.push(number.to_string()),
+                Json::Bool(flag) => parts.push((*flag as u8).to_string()),
+                _ => return None,
+            }
+        }
+    }
+    Some(parts.join(AppDefines::ARGUMENT_SEP))
+}
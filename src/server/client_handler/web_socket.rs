@@ -0,0 +1,182 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+
+use crate::server::client_handler::protocol_error::ProtocolError;
+
+/// The GUID appended to the client key to compute the accept key (RFC 6455, section 1.3).
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The opcodes of the frames (RFC 6455, section 5.2).
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Enum representing a frame received from a WebSocket client.
+pub(crate) enum Frame {
+    /// A text message.
+    Text(String),
+    /// A ping, holding the payload to echo back.
+    Ping(Vec<u8>),
+    /// A pong.
+    Pong,
+    /// The client closes the connection.
+    Close,
+}
+
+/// Parses the HTTP upgrade request opening a WebSocket.
+///
+/// # Arguments
+///
+/// * `buffer` - The bytes received so far.
+///
+/// # Returns
+///
+/// `None` if the request is not complete yet. Otherwise the number of bytes consumed,
+/// with the HTTP response to send or the reason the request is refused.
+///
+pub(crate) fn accept(buffer: &[u8]) -> Option<(usize, Result<String, &'static str>)> {
+    let end = buffer.windows(4).position(|window| window == b"\r\n\r\n")? + 4;
+    let request = String::from_utf8_lossy(&buffer[..end]);
+    Some((end, response(&request)))
+}
+
+/// Builds the response to an HTTP upgrade request.
+fn response(request: &str) -> Result<String, &'static str> {
+    let mut lines = request.lines();
+    let request_line = lines.next().unwrap_or_default();
+    if !request_line.starts_with("GET ") {
+        return Err("Expected a GET request");
+    }
+
+    let mut key = None;
+    let mut upgrade = false;
+    let mut version = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "upgrade" => upgrade = value.eq_ignore_ascii_case("websocket"),
+            "sec-websocket-version" => version = value == "13",
+            "sec-websocket-key" => key = Some(value),
+            _ => {}
+        }
+    }
+    if !upgrade {
+        return Err("Missing WebSocket upgrade");
+    }
+    if !version {
+        return Err("Unsupported WebSocket version");
+    }
+    let key = key.ok_or("Missing WebSocket key")?;
+
+    let digest = Sha1::new().chain_update(key).chain_update(ACCEPT_GUID).finalize();
+    Ok(format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        STANDARD.encode(digest)
+    ))
+}
+
+/// Builds the response refusing an HTTP upgrade request.
+///
+/// # Arguments
+///
+/// * `status` - The HTTP status, e.g. `400 Bad Request`.
+/// * `reason` - The body of the response.
+///
+pub(crate) fn refuse(status: &str, reason: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        reason.len(),
+        reason
+    )
+}
+
+/// Decodes the first frame of a buffer.
+///
+/// Client frames must be masked, and fragmented messages are not supported.
+/// The decoder never panics on malformed input.
+///
+/// # Arguments
+///
+/// * `buffer` - The bytes received so far.
+///
+/// # Returns
+///
+/// `None` if the buffer does not hold a complete frame yet. Otherwise the number of
+/// bytes consumed, with the decoded frame or the reason it is invalid.
+///
+pub(crate) fn decode(buffer: &[u8]) -> Option<(usize, Result<Frame, ProtocolError>)> {
+    let (&first, rest) = buffer.split_first()?;
+    let (&second, rest) = rest.split_first()?;
+
+    let (length, header) = match second & 0x7F {
+        126 => (u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as u64, 4),
+        127 => (u64::from_be_bytes(rest.get(..8)?.try_into().ok()?), 10),
+        length => (length as u64, 2),
+    };
+    let masked = second & 0x80 != 0;
+    let header = if masked { header + 4 } else { header };
+    // Une longueur invalide ne sera jamais atteinte : la limite de ligne coupe la connexion
+    let total = usize::try_from(length).ok()?.checked_add(header)?;
+    let frame = buffer.get(..total)?;
+
+    if !masked {
+        return Some((total, Err(ProtocolError::BadValue("Client frames must be masked"))));
+    }
+    if first & 0x80 == 0 || first & 0x0F == 0 {
+        return Some((total, Err(ProtocolError::BadValue("Fragmented messages are not supported"))));
+    }
+
+    let mask = &frame[header - 4..header];
+    let payload: Vec<u8> = frame[header..].iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]).collect();
+
+    let decoded = match first & 0x0F {
+        OPCODE_TEXT => String::from_utf8(payload)
+            .map(Frame::Text)
+            .map_err(|_| ProtocolError::BadValue("Invalid UTF-8 text")),
+        OPCODE_BINARY => Err(ProtocolError::BadValue("Binary messages are not supported, send text")),
+        OPCODE_CLOSE => Ok(Frame::Close),
+        OPCODE_PING => Ok(Frame::Ping(payload)),
+        OPCODE_PONG => Ok(Frame::Pong),
+        _ => Err(ProtocolError::BadValue("Unknown WebSocket opcode")),
+    };
+    Some((total, decoded))
+}
+
+/// Encodes a text message into an unmasked frame.
+pub(crate) fn encode_text(text: &str) -> Vec<u8> {
+    encode(OPCODE_TEXT, text.as_bytes())
+}
+
+/// Encodes the pong answering a ping.
+pub(crate) fn encode_pong(payload: &[u8]) -> Vec<u8> {
+    encode(OPCODE_PONG, payload)
+}
+
+/// Encodes the frame closing the connection.
+pub(crate) fn encode_close() -> Vec<u8> {
+    encode(OPCODE_CLOSE, &[])
+}
+
+/// Encodes a final, unmasked frame.
+fn encode(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
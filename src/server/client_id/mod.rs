@@ -0,0 +1,34 @@
+use std::fmt;
+use std::net::SocketAddr;
+
+/// Enum identifying a connected client, whatever its transport.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum ClientId {
+    /// A client speaking the protocol over a raw TCP connection.
+    Tcp(SocketAddr),
+    /// A client speaking the protocol in the text frames of a WebSocket.
+    WebSocket(SocketAddr),
+}
+
+impl ClientId {
+    /// Returns the address of the client.
+    pub fn addr(&self) -> SocketAddr {
+        match self {
+            ClientId::Tcp(addr) | ClientId::WebSocket(addr) => *addr,
+        }
+    }
+
+    /// Returns whether the client is connected through a WebSocket.
+    pub fn is_web_socket(&self) -> bool {
+        matches!(self, ClientId::WebSocket(_))
+    }
+}
+
+impl fmt::Display for ClientId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientId::Tcp(addr) => write!(f, "{}", addr),
+            ClientId::WebSocket(addr) => write!(f, "{} (WebSocket)", addr),
+        }
+    }
+}
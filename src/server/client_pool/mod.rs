@@ -0,0 +1,237 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+use crate::server::client_handler::protocol_error::ProtocolError;
+use crate::server::client_handler::ClientHandler;
+use crate::server::server_thread::ServerThread;
+use crate::server::tls::TlsHandshake;
+use crate::types::{add_message, MessageOrigin, MessageType};
+
+/// A struct holding a place among the connected clients, released when dropped.
+pub(crate) struct ClientSlot(pub(crate) Arc<AtomicUsize>);
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A client handled by a worker, with its place among the connected clients.
+struct PooledClient {
+    handler: ClientHandler,
+    _slot: ClientSlot,
+}
+
+/// A TLS client whose handshake is not complete, with what the listener decided for it.
+struct PendingTls {
+    handshake: TlsHandshake,
+    /// The place of the client among the connected clients, or why it is refused once the handshake completes.
+    admission: Result<ClientSlot, ProtocolError>,
+}
+
+/// A client handed to a worker.
+enum Arrival {
+    Client(Box<PooledClient>),
+    Tls(Box<PendingTls>),
+}
+
+/// A worker thread of the pool, as seen by the listeners.
+struct Worker {
+    /// Hands new clients to the worker.
+    sender: Sender<Arrival>,
+    /// The number of clients the worker handles.
+    clients: Arc<AtomicUsize>,
+}
+
+/// A struct running the client handlers on a fixed number of worker threads.
+///
+/// Each worker polls its clients in turn: a handler reads what its non-blocking
+/// socket holds and answers it, then hands the worker back. The TLS handshakes are
+/// advanced the same way before the client gets a handler. A worker with nothing
+/// to read sleeps `CLIENT_POLL_MS`, a worker without clients waits for one. The workers
+/// end once the server stops and their clients saw it.
+#[derive(Clone)]
+pub(crate) struct ClientPool {
+    workers: Arc<Vec<Worker>>,
+}
+
+impl ClientPool {
+    /// Starts the worker threads, as threads of the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of worker threads, at least 1.
+    /// * `server` - The server, which joins the workers when it stops.
+    ///
+    /// # Returns
+    ///
+    /// The pool, shared by the listeners.
+    ///
+    pub fn new(size: usize, server: &ServerThread) -> Self {
+        let workers = (0..size.max(1)).map(|_| {
+            let (sender, receiver) = mpsc::channel();
+            let clients = Arc::new(AtomicUsize::new(0));
+            let count = Arc::clone(&clients);
+            let worker_server = server.clone();
+            server.spawn_thread(move || ClientPool::work(receiver, count, &worker_server));
+            Worker { sender, clients }
+        }).collect::<Vec<_>>();
+        add_message(
+            &server.messages,
+            format!("[START] {} worker thread(s) handle the clients.", workers.len()),
+            MessageType::Default,
+            MessageOrigin::Network,
+        );
+        ClientPool { workers: Arc::new(workers) }
+    }
+
+    /// Hands a new client to the worker with the fewest clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler of the client, greeted by the worker.
+    /// * `slot` - The place of the client among the connected clients, released when it disconnects.
+    ///
+    pub fn add(&self, handler: ClientHandler, slot: ClientSlot) {
+        self.send(Arrival::Client(Box::new(PooledClient { handler, _slot: slot })));
+    }
+
+    /// Hands a new TLS client to the worker with the fewest clients, which completes its handshake.
+    ///
+    /// # Arguments
+    ///
+    /// * `handshake` - The handshake of the client.
+    /// * `admission` - The place of the client among the connected clients, or why it is refused:
+    ///   the refusal is sent once the handshake completes, so that the client can read it.
+    ///
+    pub fn add_tls(&self, handshake: TlsHandshake, admission: Result<ClientSlot, ProtocolError>) {
+        self.send(Arrival::Tls(Box::new(PendingTls { handshake, admission })));
+    }
+
+    /// Hands a new client to the worker with the fewest clients.
+    fn send(&self, arrival: Arrival) {
+        // Le pool a toujours au moins un worker, voir `new`
+        let Some(worker) = self.workers.iter().min_by_key(|worker| worker.clients.load(Ordering::SeqCst)) else { return };
+        // Compté tout de suite : les clients suivants vont à un autre worker
+        worker.clients.fetch_add(1, Ordering::SeqCst);
+        let _ = worker.sender.send(arrival);
+    }
+
+    /// Polls the clients of a worker until the server stops or the pool is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `receiver` - The new clients of the worker.
+    /// * `count` - The number of clients of the worker, shared with `add`.
+    /// * `server` - The server, whose `stopping` flag tells the handlers to disconnect at their next poll.
+    ///
+    fn work(receiver: Receiver<Arrival>, count: Arc<AtomicUsize>, server: &ServerThread) {
+        let mut clients: Vec<PooledClient> = Vec::new();
+        let mut handshakes: Vec<PendingTls> = Vec::new();
+        loop {
+            // Sans client, le worker dort jusqu'au prochain ou jusqu'à l'arrêt du serveur
+            if clients.is_empty() && handshakes.is_empty() {
+                if server.stopping.load(Ordering::SeqCst) {
+                    return;
+                }
+                match receiver.recv_timeout(Duration::from_millis(AppDefines::ACCEPT_POLL_MS)) {
+                    Ok(arrival) => ClientPool::arrive(arrival, &mut clients, &mut handshakes),
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            while let Ok(arrival) = receiver.try_recv() {
+                ClientPool::arrive(arrival, &mut clients, &mut handshakes);
+            }
+
+            // Les handshakes en cours ne sont connus de personne d'autre : l'arrêt les coupe simplement
+            if server.stopping.load(Ordering::SeqCst) {
+                count.fetch_sub(handshakes.len(), Ordering::SeqCst);
+                handshakes.clear();
+            }
+            let mut active = false;
+            let mut index = 0;
+            while index < handshakes.len() {
+                let result = handshakes[index].handshake.poll();
+                if let Ok(false) = result {
+                    index += 1;
+                    continue;
+                }
+                active = true;
+                let PendingTls { handshake, admission } = handshakes.swap_remove(index);
+                match (result, admission) {
+                    (Ok(_), Ok(slot)) => {
+                        add_message(
+                            &server.messages,
+                            format!("[INFO] New client connected: {} (TLS)", handshake.client_id),
+                            MessageType::Info,
+                            MessageOrigin::Network,
+                        );
+                        let client_id = handshake.client_id;
+                        match ClientHandler::new(Box::new(handshake.into_stream()), client_id, server) {
+                            Ok(mut handler) => {
+                                handler.start();
+                                clients.push(PooledClient { handler, _slot: slot });
+                            }
+                            Err(e) => {
+                                add_message(
+                                    &server.messages,
+                                    format!("[WARNING] Client {} dropped, its connection cannot be handled: {}", client_id, e),
+                                    MessageType::Warning,
+                                    MessageOrigin::Network,
+                                );
+                                count.fetch_sub(1, Ordering::SeqCst);
+                            }
+                        }
+                    }
+                    (Ok(_), Err(error)) => {
+                        let web_socket = handshake.client_id.is_web_socket();
+                        ServerThread::refuse_client(Box::new(handshake.into_stream()), web_socket, error);
+                        count.fetch_sub(1, Ordering::SeqCst);
+                    }
+                    (Err(e), _) => {
+                        add_message(
+                            &server.messages,
+                            format!("[WARNING] TLS handshake with {} failed: {}", handshake.client_id, e),
+                            MessageType::Warning,
+                            MessageOrigin::Network,
+                        );
+                        count.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            }
+
+            clients.retain_mut(|client| {
+                // Un handler qui panique est abandonné, les autres clients du worker continuent ; sa déconnexion est faite en le libérant
+                let (connected, read) = panic::catch_unwind(AssertUnwindSafe(|| client.handler.poll())).unwrap_or_else(|_| {
+                    add_message(&server.messages, "[ERROR] A client handler panicked, its client is dropped.".to_string(), MessageType::Error, MessageOrigin::Network);
+                    (false, false)
+                });
+                active |= read;
+                if !connected {
+                    count.fetch_sub(1, Ordering::SeqCst);
+                }
+                connected
+            });
+            if !active {
+                thread::sleep(Duration::from_millis(AppDefines::CLIENT_POLL_MS));
+            }
+        }
+    }
+
+    /// Takes a client handed to a worker: a plain client is greeted, a TLS client waits for its handshake.
+    fn arrive(arrival: Arrival, clients: &mut Vec<PooledClient>, handshakes: &mut Vec<PendingTls>) {
+        match arrival {
+            Arrival::Client(mut client) => {
+                client.handler.start();
+                clients.push(*client);
+            }
+            Arrival::Tls(pending) => handshakes.push(*pending),
+        }
+    }
+}
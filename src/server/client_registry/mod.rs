@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::server::client_id::ClientId;
+
+/// A struct describing who a client is, shown in the clients tab of the server UI.
+#[derive(Clone, Debug)]
+pub(crate) struct ClientInfo {
+    /// The entity the client controls, with its name, `None` for a spectator or once it was destroyed.
+    pub entity: Option<(u32, String)>,
+    /// Whether the client watches the game without an entity.
+    pub spectator: bool,
+    /// When the client connected.
+    pub connected_at: Instant,
+    /// When the client last sent something.
+    pub last_activity: Instant,
+    /// When the client disconnected, `None` while it is connected.
+    pub disconnected_at: Option<Instant>,
+}
+
+/// A struct keeping the clients connected now, and those disconnected in the last moments.
+///
+/// The listeners add a client when they accept it, and its handler keeps its row up to date.
+/// Disconnected clients keep their row for a grace period.
+pub(crate) struct ClientRegistry {
+    clients: HashMap<ClientId, ClientInfo>,
+}
+
+impl ClientRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        ClientRegistry { clients: HashMap::new() }
+    }
+
+    /// Adds a new connection, replacing the row of an older connection with the same identity.
+    pub fn connect(&mut self, client_id: ClientId) {
+        let now = Instant::now();
+        let info = ClientInfo { entity: None, spectator: false, connected_at: now, last_activity: now, disconnected_at: None };
+        self.clients.insert(client_id, info);
+    }
+
+    /// Sets the entity of a client.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    /// * `entity` - The id and name of the entity, `None` if the client lost it.
+    ///
+    pub fn set_entity(&mut self, client_id: &ClientId, entity: Option<(u32, String)>) {
+        if let Some(info) = self.clients.get_mut(client_id) {
+            info.entity = entity;
+        }
+    }
+
+    /// Sets the name of the entity of a client, after a `NAME` command.
+    pub fn set_name(&mut self, client_id: &ClientId, name: &str) {
+        if let Some((_, entity_name)) = self.clients.get_mut(client_id).and_then(|info| info.entity.as_mut()) {
+            *entity_name = name.to_string();
+        }
+    }
+
+    /// Marks a client as a spectator, without an entity.
+    pub fn set_spectator(&mut self, client_id: &ClientId) {
+        if let Some(info) = self.clients.get_mut(client_id) {
+            info.spectator = true;
+            info.entity = None;
+        }
+    }
+
+    /// Notes that a client just sent something.
+    pub fn touch(&mut self, client_id: &ClientId) {
+        if let Some(info) = self.clients.get_mut(client_id) {
+            info.last_activity = Instant::now();
+        }
+    }
+
+    /// Marks a client as disconnected, starting the grace period of its row.
+    pub fn disconnect(&mut self, client_id: &ClientId) {
+        if let Some(info) = self.clients.get_mut(client_id) {
+            info.disconnected_at.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Drops the rows of the clients disconnected for longer than the grace period.
+    pub fn expire(&mut self, grace: Duration) {
+        self.clients.retain(|_, info| info.disconnected_at.map_or(true, |at| at.elapsed() < grace));
+    }
+
+    /// Returns every client, in no particular order.
+    pub fn rows(&self) -> Vec<(ClientId, ClientInfo)> {
+        self.clients.iter().map(|(client_id, info)| (*client_id, info.clone())).collect()
+    }
+}
+
+/// A struct holding the row of a client in the registry, marked as disconnected when dropped.
+///
+/// Owned by the handler of the client, so that the row is marked even if the handler panics.
+pub(crate) struct ClientRegistration {
+    client_id: ClientId,
+    clients: Arc<Mutex<ClientRegistry>>,
+}
+
+impl ClientRegistration {
+    /// Adds a new connection to the registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    /// * `clients` - The registry.
+    ///
+    /// # Returns
+    ///
+    /// The registration, to be kept as long as the client is handled.
+    ///
+    pub fn new(client_id: ClientId, clients: &Arc<Mutex<ClientRegistry>>) -> Self {
+        clients.lock().unwrap().connect(client_id);
+        ClientRegistration { client_id, clients: Arc::clone(clients) }
+    }
+}
+
+impl Drop for ClientRegistration {
+    fn drop(&mut self) {
+        // Un verrou empoisonné par la panique d'un autre thread ne doit pas en provoquer une seconde
+        let mut clients = match self.clients.lock() {
+            Ok(clients) => clients,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        clients.disconnect(&self.client_id);
+    }
+}
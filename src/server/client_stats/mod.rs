@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::client_id::ClientId;
+use crate::server::metrics::registry::MetricsRegistry;
+
+/// A struct representing the traffic of a client connection.
+///
+/// Client handlers count their traffic in a local `ClientStats` and add it to the
+/// registry once per processed read, so that the registry is not locked for every byte.
+#[derive(Clone, Default)]
+pub(crate) struct ClientStats {
+    /// The number of commands processed.
+    pub commands: u64,
+    /// The number of bytes read from the client.
+    pub bytes_read: u64,
+    /// The number of bytes written to the client, world state pushes included.
+    pub bytes_written: u64,
+    /// The number of error replies sent to the client.
+    pub errors: u64,
+    /// The code of the last command processed.
+    pub last_command: String,
+    /// When the client disconnected, `None` while it is connected.
+    pub disconnected_at: Option<Instant>,
+}
+
+impl ClientStats {
+    /// Adds the traffic counted since the last batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The traffic counted since the last batch.
+    ///
+    pub fn add(&mut self, batch: &ClientStats) {
+        self.commands += batch.commands;
+        self.bytes_read += batch.bytes_read;
+        self.bytes_written += batch.bytes_written;
+        self.errors += batch.errors;
+        if !batch.last_command.is_empty() {
+            self.last_command.clone_from(&batch.last_command);
+        }
+    }
+}
+
+/// A struct keeping the traffic statistics of the connections, shown in the server UI.
+///
+/// Disconnected clients keep their row for a grace period. The traffic is also added to the
+/// counters exported to Prometheus, which keep growing once the rows are gone.
+pub(crate) struct ClientStatsRegistry {
+    stats: HashMap<ClientId, ClientStats>,
+    counters: Arc<MetricsRegistry>,
+}
+
+impl ClientStatsRegistry {
+    /// Creates an empty registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `counters` - The counters of the server the traffic is added to.
+    ///
+    pub fn new(counters: Arc<MetricsRegistry>) -> Self {
+        ClientStatsRegistry { stats: HashMap::new(), counters }
+    }
+
+    /// Starts the statistics of a new connection, replacing those of an older connection with the same identity.
+    pub fn connect(&mut self, client_id: ClientId) {
+        self.stats.insert(client_id, ClientStats::default());
+    }
+
+    /// Adds a batch of traffic to the statistics of a client.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    /// * `batch` - The traffic counted since the last batch.
+    ///
+    pub fn record(&mut self, client_id: ClientId, batch: &ClientStats) {
+        self.counters.commands_processed.add(batch.commands);
+        self.counters.bytes_received.add(batch.bytes_read);
+        self.counters.bytes_sent.add(batch.bytes_written);
+        self.stats.entry(client_id).or_default().add(batch);
+    }
+
+    /// Adds bytes written outside the handler of a client, e.g. a world state push.
+    pub fn record_written(&mut self, client_id: ClientId, bytes: usize) {
+        self.counters.bytes_sent.add(bytes as u64);
+        self.stats.entry(client_id).or_default().bytes_written += bytes as u64;
+    }
+
+    /// Marks a client as disconnected, starting the grace period of its row.
+    pub fn disconnect(&mut self, client_id: &ClientId) {
+        if let Some(stats) = self.stats.get_mut(client_id) {
+            stats.disconnected_at = Some(Instant::now());
+        }
+    }
+
+    /// Drops the rows of the clients disconnected for longer than the grace period.
+    pub fn expire(&mut self, grace: Duration) {
+        self.stats.retain(|_, stats| stats.disconnected_at.map_or(true, |at| at.elapsed() < grace));
+    }
+
+    /// Returns the statistics of every client, sorted by address.
+    pub fn rows(&self) -> Vec<(ClientId, ClientStats)> {
+        let mut rows: Vec<(ClientId, ClientStats)> =
+            self.stats.iter().map(|(client_id, stats)| (*client_id, stats.clone())).collect();
+        rows.sort_by_key(|(client_id, _)| client_id.addr());
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Mutex;
+    use std::thread;
+
+    use super::*;
+    use crate::app_defines::AppDefines;
+    use crate::game_logic::GameLogic;
+    use crate::server::server_thread::{ServerSettings, ServerThread};
+    use crate::types::MessageHub;
+
+    fn client(port: u16) -> ClientId {
+        ClientId::Tcp(([127, 0, 0, 1], port).into())
+    }
+
+    fn batch(commands: u64, bytes_read: u64, bytes_written: u64, errors: u64, last_command: &str) -> ClientStats {
+        ClientStats { commands, bytes_read, bytes_written, errors, last_command: last_command.to_string(), disconnected_at: None }
+    }
+
+    #[test]
+    fn batches_add_up_per_client() {
+        let counters = Arc::new(MetricsRegistry::new());
+        let mut registry = ClientStatsRegistry::new(Arc::clone(&counters));
+        registry.connect(client(2000));
+        registry.record(client(2000), &batch(3, 40, 100, 1, "STATUS"));
+        // Un lot sans commande garde la dernière commande connue
+        registry.record(client(2000), &batch(0, 0, 20, 0, ""));
+        registry.record_written(client(2000), 7);
+        registry.record(client(1000), &batch(1, 10, 5, 0, "PING"));
+
+        let rows = registry.rows();
+        assert_eq!(rows.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [client(1000), client(2000)], "sorted by address");
+        let stats = &rows[1].1;
+        assert_eq!((stats.commands, stats.bytes_read, stats.bytes_written, stats.errors), (3, 40, 127, 1));
+        assert_eq!(stats.last_command, "STATUS");
+        assert_eq!((counters.commands_processed.get(), counters.bytes_received.get(), counters.bytes_sent.get()), (4, 50, 132));
+
+        // Une nouvelle connexion de la même adresse repart de zéro, pas les compteurs
+        registry.connect(client(2000));
+        assert_eq!(registry.rows()[1].1.commands, 0);
+        assert_eq!(counters.commands_processed.get(), 4);
+    }
+
+    #[test]
+    fn disconnected_rows_expire_after_the_grace_period() {
+        let mut registry = ClientStatsRegistry::new(Arc::new(MetricsRegistry::new()));
+        registry.connect(client(1000));
+        registry.connect(client(2000));
+        registry.disconnect(&client(2000));
+        registry.disconnect(&client(3000));
+        assert!(registry.rows()[1].1.disconnected_at.is_some());
+
+        registry.expire(Duration::from_secs(60));
+        assert_eq!(registry.rows().len(), 2, "within the grace period");
+        thread::sleep(Duration::from_millis(20));
+        registry.expire(Duration::from_millis(10));
+        assert_eq!(registry.rows().iter().map(|(id, _)| *id).collect::<Vec<_>>(), [client(1000)], "connected rows stay");
+    }
+
+    #[test]
+    fn sessions_are_counted_as_the_client_sees_them() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut settings = ServerSettings::new();
+        settings.log_path = String::new();
+        settings.web_socket_port = 0;
+        settings.admin_control_port = 0;
+        settings.udp_actuators = false;
+        let game_logic = Arc::new(Mutex::new(GameLogic::new()));
+        let server = ServerThread::new(vec!["127.0.0.1".to_string()], port, MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY), Arc::new(Mutex::new(settings)), game_logic);
+        server.start();
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut received = Vec::new();
+        let mut read_line = |reader: &mut BufReader<TcpStream>| {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            received.extend_from_slice(line.as_bytes());
+        };
+        read_line(&mut reader);
+        let sep = AppDefines::COMMAND_SEP;
+        let lines = [
+            format!("{}={}\n", AppDefines::HELLO, AppDefines::PROTOCOL_VERSION),
+            format!("{}{sep}{}=0.5{sep}{}\n", AppDefines::PING, AppDefines::ACTUATOR_MOTOR_LEFT, AppDefines::QUERY_STATUS),
+            "NOPE\n".to_string(),
+        ];
+        let mut sent = 0;
+        for line in &lines {
+            writer.write_all(line.as_bytes()).unwrap();
+            sent += line.len();
+            read_line(&mut reader);
+        }
+        let exit = format!("{}\n", AppDefines::QUIT);
+        writer.write_all(exit.as_bytes()).unwrap();
+        sent += exit.len();
+        reader.read_to_end(&mut received).unwrap();
+
+        let stats = || server.client_stats.lock().unwrap().rows().into_iter().next().map(|(_, stats)| stats).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while stats().disconnected_at.is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        let stats = stats();
+        assert_eq!((stats.commands, stats.errors), (6, 1));
+        assert_eq!((stats.bytes_read, stats.bytes_written), (sent as u64, received.len() as u64));
+        assert_eq!(stats.last_command, AppDefines::QUIT);
+        server.stop();
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app_defines::AppDefines;
+
+/// A trait for the connection of a client: a plain TCP socket, or a TLS session over one.
+pub(crate) trait ClientStream: Read + Write + Send {
+    /// Returns the TCP socket carrying the connection.
+    fn socket(&self) -> &TcpStream;
+
+    /// Closes the connection: the socket is shut down in both directions.
+    fn shutdown(&mut self) -> io::Result<()> {
+        self.socket().shutdown(Shutdown::Both)
+    }
+}
+
+impl ClientStream for TcpStream {
+    fn socket(&self) -> &TcpStream {
+        self
+    }
+}
+
+/// A connection shared by the reader of a client and its writer.
+///
+/// A TLS session cannot be split like a socket: both directions go through the same state.
+pub(crate) type SharedStream = Arc<Mutex<Box<dyn ClientStream>>>;
+
+/// A struct reading from a shared non-blocking connection.
+pub(crate) struct SocketReader(SharedStream);
+
+impl SocketReader {
+    /// Wraps a shared connection.
+    pub fn new(stream: SharedStream) -> Self {
+        SocketReader(stream)
+    }
+}
+
+impl Read for SocketReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+/// A struct writing to a shared non-blocking connection as if it were blocking, for at most `SOCKET_WRITE_TIMEOUT_MS`.
+///
+/// The client sockets are non-blocking so that a worker can poll many of them, which
+/// also makes their writes non-blocking: a full socket is retried until the deadline.
+pub(crate) struct SocketWriter(SharedStream);
+
+impl SocketWriter {
+    /// Wraps a shared connection.
+    pub fn new(stream: SharedStream) -> Self {
+        SocketWriter(stream)
+    }
+
+    /// Closes the connection, see `ClientStream::shutdown`.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.0.lock().unwrap().shutdown()
+    }
+
+    /// Retries an operation on the connection while the socket is full, until the deadline.
+    ///
+    /// The connection is unlocked between two attempts, so that the client can still be read.
+    fn retry<T>(&self, mut operation: impl FnMut(&mut dyn ClientStream) -> io::Result<T>) -> io::Result<T> {
+        let deadline = Instant::now() + Duration::from_millis(AppDefines::SOCKET_WRITE_TIMEOUT_MS);
+        loop {
+            match operation(self.0.lock().unwrap().as_mut()) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(io::Error::new(ErrorKind::TimedOut, "client socket full"));
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Write for SocketWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.retry(|stream| stream.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.retry(|stream| stream.flush())
+    }
+}
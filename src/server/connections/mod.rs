@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::app_defines::AppDefines;
+use crate::server::client_handler::reply::{Reply, Value};
+use crate::server::client_handler::ClientHandler;
+use crate::server::client_id::ClientId;
+use crate::server::subscriptions::{Encoding, SharedWriter};
+
+/// A struct representing a live connection, as seen from outside its handler.
+struct Connection {
+    /// The flag telling the handler to shut down.
+    shutdown: Arc<AtomicBool>,
+    /// The writer of the connection, shared with its handler.
+    writer: SharedWriter,
+    /// How the lines sent to the client are encoded, `None` while it cannot receive lines yet.
+    encoding: Option<Encoding>,
+}
+
+/// A struct keeping the live connections, so that they can be closed from outside
+/// their handler, and the banned addresses.
+pub(crate) struct ConnectionRegistry {
+    /// The live connections.
+    connections: HashMap<ClientId, Connection>,
+    /// The addresses refused by the accept loop.
+    banned: HashSet<IpAddr>,
+}
+
+impl ConnectionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        ConnectionRegistry { connections: HashMap::new(), banned: HashSet::new() }
+    }
+
+    /// Registers a new connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    /// * `writer` - The writer of the connection.
+    /// * `encoding` - How the lines sent to the client are encoded, `None` while it cannot receive lines yet.
+    ///
+    /// # Returns
+    ///
+    /// The flag the handler of the connection checks in its read loop.
+    ///
+    pub fn register(&mut self, client_id: ClientId, writer: SharedWriter, encoding: Option<Encoding>) -> Arc<AtomicBool> {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        self.connections.insert(client_id, Connection { shutdown: Arc::clone(&shutdown), writer, encoding });
+        shutdown
+    }
+
+    /// Changes how the lines sent to a client are encoded.
+    pub fn set_encoding(&mut self, client_id: &ClientId, encoding: Encoding) {
+        if let Some(connection) = self.connections.get_mut(client_id) {
+            connection.encoding = Some(encoding);
+        }
+    }
+
+    /// Returns the writer of a live connection and how the lines sent to it are encoded.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the connection is not live or cannot receive lines yet.
+    ///
+    pub fn writer(&self, client_id: &ClientId) -> Option<(SharedWriter, Encoding)> {
+        let connection = self.connections.get(client_id)?;
+        Some((Arc::clone(&connection.writer), connection.encoding?))
+    }
+
+    /// Returns the writer of every live connection able to receive lines, with its encoding.
+    pub fn writers(&self) -> Vec<(ClientId, SharedWriter, Encoding)> {
+        self.connections
+            .iter()
+            .filter_map(|(client_id, connection)| Some((*client_id, Arc::clone(&connection.writer), connection.encoding?)))
+            .collect()
+    }
+
+    /// Forgets a closed connection.
+    pub fn unregister(&mut self, client_id: &ClientId) {
+        self.connections.remove(client_id);
+    }
+
+    /// Returns whether a connection is live.
+    pub fn is_live(&self, client_id: &ClientId) -> bool {
+        self.connections.contains_key(client_id)
+    }
+
+    /// Tells the handler of a connection to shut down.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the connection is not live.
+    ///
+    pub fn shut_down(&self, client_id: &ClientId) -> bool {
+        match self.connections.get(client_id) {
+            Some(connection) => {
+                connection.shutdown.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sends `SHUTDOWN` to every client, then shuts their sockets down.
+    ///
+    /// The handlers see the connection closed on their next read.
+    ///
+    /// # Returns
+    ///
+    /// The number of connections closed.
+    ///
+    pub fn close_all(&self) -> usize {
+        let notice = Reply::Query {
+            cmd: AppDefines::SERVER_SHUTDOWN,
+            fields: vec![("text", Value::Text("Server stopping".to_string()))],
+        };
+        for connection in self.connections.values() {
+            let Ok(mut writer) = connection.writer.lock() else { continue };
+            if let Some(encoding) = connection.encoding {
+                let line = if encoding.json { notice.to_json().to_string() } else { notice.to_legacy() };
+                if let Some(bytes) = ClientHandler::encode_line(&line, encoding) {
+                    let _ = writer.write_all(&bytes).and_then(|_| writer.flush());
+                }
+            }
+            let _ = writer.get_ref().shutdown();
+        }
+        self.connections.len()
+    }
+
+    /// Bans an address and tells the handlers of its connections to shut down.
+    ///
+    /// IPv4 clients of a dual-stack listener show up as IPv4-mapped IPv6 addresses,
+    /// so addresses are compared in their canonical form.
+    ///
+    /// # Returns
+    ///
+    /// The connections of the address.
+    ///
+    pub fn ban(&mut self, ip: IpAddr) -> Vec<ClientId> {
+        let ip = ip.to_canonical();
+        self.banned.insert(ip);
+        let banned: Vec<ClientId> = self.connections.keys().filter(|id| id.addr().ip().to_canonical() == ip).copied().collect();
+        for client_id in &banned {
+            self.shut_down(client_id);
+        }
+        banned
+    }
+
+    /// Returns whether an address is banned.
+    pub fn is_banned(&self, ip: &IpAddr) -> bool {
+        self.banned.contains(&ip.to_canonical())
+    }
+}
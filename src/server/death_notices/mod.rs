@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::world_snapshot::WorldSnapshot;
+
+/// A struct representing the destruction of an entity, to be told to its client.
+pub(crate) struct DeathNotice {
+    /// The id of the entity destroyed.
+    pub victim: u32,
+    /// The name of the entity credited with the kill, `None` if it is unknown.
+    pub killer: Option<String>,
+    /// Where the killing bullet came from, see `HitRecord::bearing`; `None` for a mine, a ram or the zone.
+    pub bearing: Option<f32>,
+}
+
+/// A struct reading the kills from the game event queue, at its own pace.
+///
+/// The destroyed entities leave the arena in the step they are destroyed, so the
+/// names of the entities are remembered from one read to the next.
+pub(crate) struct DeathNotices {
+    /// The number of the last game event read.
+    cursor: u64,
+    /// The names of the entities seen in the arena, by id.
+    names: HashMap<u32, String>,
+}
+
+impl DeathNotices {
+    /// Creates a reader starting after the last event of the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The last world snapshot.
+    ///
+    pub fn new(world: &WorldSnapshot) -> Self {
+        let names = world.entities.iter().map(|entity| (entity.id, entity.name.clone())).collect();
+        DeathNotices { cursor: world.events.cursor(), names }
+    }
+
+    /// Reads the kills that happened since the previous call.
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The last world snapshot.
+    ///
+    /// # Returns
+    ///
+    /// The destroyed entities, oldest first.
+    ///
+    pub fn read(&mut self, world: &WorldSnapshot) -> Vec<DeathNotice> {
+        let mut notices = Vec::new();
+        // Le coup fatal précède la mort dans le même pas, donc dans la même lecture
+        let mut bearings: HashMap<u32, Option<f32>> = HashMap::new();
+        for queued in world.events.since(self.cursor) {
+            match &queued.event {
+                GameEvent::Spawn { id, name, .. } => {
+                    self.names.insert(*id, name.clone());
+                }
+                GameEvent::Hit { victim, bearing, .. } => {
+                    bearings.insert(*victim, *bearing);
+                }
+                GameEvent::Kill { shooter, victim, .. } => {
+                    let bearing = bearings.remove(victim).flatten();
+                    notices.push(DeathNotice { victim: *victim, killer: self.names.get(shooter).cloned(), bearing });
+                }
+                GameEvent::Despawn { id } => {
+                    self.names.remove(id);
+                }
+                _ => {}
+            }
+        }
+        self.cursor = world.events.cursor();
+        // Les noms changent sans événement : ceux des entités présentes sont repris à chaque lecture
+        self.names.extend(world.entities.iter().map(|entity| (entity.id, entity.name.clone())));
+        notices
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::game_logic::control_point::Side;
+use crate::game_logic::match_state::MatchState;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::GameLogic;
+use crate::types::{add_messages, is_logged, MessageHub, MessageOrigin, MessageType};
+
+/// A struct writing the game events to the server messages, as `MessageType::GameEvent` messages.
+///
+/// The events are read from the game event queue with a cursor of its own, on the thread of the
+/// game loop after each step. The names of the entities are resolved when the events are read,
+/// so a message stays right once the entity is renamed or removed. Hits and shots are left
+/// out, they would flood the messages during a fight.
+pub(crate) struct EventLogger {
+    /// The messages of the server.
+    messages: MessageHub,
+    /// The number of the last game event read.
+    cursor: u64,
+    /// The names of the entities seen in the arena, by id, kept until they leave it.
+    names: HashMap<u32, String>,
+}
+
+impl EventLogger {
+    /// Creates a logger starting at the first event still kept in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages of the server.
+    ///
+    pub fn new(messages: MessageHub) -> Self {
+        EventLogger { messages, cursor: 0, names: HashMap::new() }
+    }
+
+    /// Renders the events that happened since the previous call, to be written with `write`.
+    ///
+    /// Nothing is rendered while the game events are below the verbosity of the messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic, locked by the game loop.
+    ///
+    /// # Returns
+    ///
+    /// The texts of the messages, oldest first.
+    ///
+    pub fn read(&mut self, logic: &GameLogic) -> Vec<String> {
+        let logged = is_logged(MessageType::GameEvent);
+        let mut texts = Vec::new();
+        for queued in logic.events.since(self.cursor) {
+            if let GameEvent::Spawn { id, name, .. } = &queued.event {
+                self.names.insert(*id, name.clone());
+            }
+            if logged {
+                // Le nom courant d'abord, puis celui retenu pour une entité déjà partie
+                let name = |id: u32| {
+                    logic.entities.iter().find(|entity| entity.id == id).map(|entity| entity.name.clone())
+                        .or_else(|| self.names.get(&id).cloned())
+                        .unwrap_or_else(|| format!("#{} (removed)", id))
+                };
+                texts.extend(EventLogger::render(&queued.event, &name));
+            }
+            if let GameEvent::Despawn { id } = queued.event {
+                self.names.remove(&id);
+            }
+        }
+        self.cursor = logic.events.cursor();
+        texts
+    }
+
+    /// Adds the texts rendered by `read` to the messages, at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - The texts of the messages, oldest first.
+    ///
+    pub fn write(&self, texts: Vec<String>) {
+        if !texts.is_empty() {
+            add_messages(&self.messages, texts, MessageType::GameEvent, MessageOrigin::Game);
+        }
+    }
+
+    /// Renders an event as the text of a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event.
+    /// * `name` - Returns the name of an entity by id.
+    ///
+    /// # Returns
+    ///
+    /// The text of the message, `None` for the hits and the shots.
+    ///
+    pub fn render(event: &GameEvent, name: &dyn Fn(u32) -> String) -> Option<String> {
+        let text = match event {
+            GameEvent::Spawn { name, is_ai, .. } => format!("{}{} entered the arena.", name, if *is_ai { " (AI)" } else { "" }),
+            GameEvent::BulletFired { .. } | GameEvent::Hit { .. } => return None,
+            GameEvent::Kill { shooter, victim, .. } if shooter == victim => format!("{} was destroyed.", name(*victim)),
+            GameEvent::Kill { shooter, victim, .. } => format!("{} destroyed {}.", name(*shooter), name(*victim)),
+            GameEvent::Detonation { owner, x, y } => format!("A mine of {} exploded at ({:.0}, {:.0}).", name(*owner), x, y),
+            GameEvent::Despawn { id } => format!("{} left the arena.", name(*id)),
+            GameEvent::OutOfBounds { id, x, y } => format!("{} got out of the arena and was brought back to ({:.0}, {:.0}).", name(*id), x, y),
+            GameEvent::PickUp { id, power_up, .. } => format!("{} picked up {}.", name(*id), power_up),
+            GameEvent::RuleChange { rule, value } => format!("Rule {} set to {}.", rule, value),
+            GameEvent::RoundStart { round } => format!("Round {} started.", round),
+            GameEvent::RoundEnd => "The simulation was reset.".to_string(),
+            GameEvent::MatchEnd { round, reason, winners, score } => format!("{}.", MatchState::result_text(*round, reason, winners, *score)),
+            GameEvent::Penalty { id, points, reason } => format!("{} loses {} points for {}.", name(*id), points, reason.text()),
+            GameEvent::Bonus { id, points, reason } => format!("{} earns {} points for {}.", name(*id), points, reason.text()),
+            GameEvent::Camping { id, x, y } => format!("{} is camping at ({:.0}, {:.0}).", name(*id), x, y),
+            GameEvent::Capture { point, owner: Side::Team(team) } => format!("Team {} captured {}.", team, point),
+            GameEvent::Capture { point, owner: Side::Entity(id) } => format!("{} captured {}.", name(*id), point),
+            GameEvent::Wave { text, .. } => format!("{}.", text.trim_end_matches('!')),
+        };
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_defines::AppDefines;
+
+    #[test]
+    fn events_are_written_with_the_names_of_their_entities() {
+        let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+        let mut logger = EventLogger::new(messages.clone());
+        let mut logic = GameLogic::new();
+        logger.read(&logic);
+
+        let alpha = logic.add_entity("alpha".to_string());
+        let beta = logic.add_entity("beta".to_string());
+        assert_eq!(logger.read(&logic), ["alpha entered the arena.", "beta entered the arena."]);
+        assert!(logger.read(&logic).is_empty(), "the events are read once");
+
+        // Les tirs et les touches ne sont pas écrits
+        let tick = logic.tick;
+        logic.events.push(tick, GameEvent::BulletFired { shooter: alpha, x: 0.0, y: 0.0, angle: 0.0 }, String::new());
+        logic.events.push(tick, GameEvent::Hit { shooter: alpha, victim: beta, damage: 10, x: 0.0, y: 0.0, bearing: None }, String::new());
+        logic.events.push(tick, GameEvent::Kill { shooter: alpha, victim: beta, x: 0.0, y: 0.0 }, String::new());
+        // Une entité partie avant la lecture garde le nom retenu à son arrivée
+        logic.remove_entity_by_id(beta);
+        assert_eq!(logger.read(&logic), ["alpha destroyed beta.", "beta left the arena."]);
+
+        // Une entité partie n'est plus nommée, une entité renommée l'est sous son nom courant
+        logic.entities.iter_mut().find(|entity| entity.id == alpha).unwrap().name = "gamma".to_string();
+        logic.events.push(tick, GameEvent::Kill { shooter: beta, victim: alpha, x: 0.0, y: 0.0 }, String::new());
+        logic.events.push(tick, GameEvent::Kill { shooter: alpha, victim: alpha, x: 0.0, y: 0.0 }, String::new());
+        logic.events.push(tick, GameEvent::Detonation { owner: beta, x: 12.4, y: 80.6 }, String::new());
+        let texts = logger.read(&logic);
+        assert_eq!(texts, [
+            format!("#{} (removed) destroyed gamma.", beta),
+            "gamma was destroyed.".to_string(),
+            format!("A mine of #{} (removed) exploded at (12, 81).", beta),
+        ]);
+
+        logger.write(texts.clone());
+        logger.write(Vec::new());
+        messages.flush();
+        let log = messages.log().to_vec();
+        assert_eq!(log.iter().map(|message| &message.text).collect::<Vec<_>>(), texts.iter().collect::<Vec<_>>());
+        assert!(log.iter().all(|message| message.message_type == MessageType::GameEvent && message.origin == MessageOrigin::Game));
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app_defines::AppDefines;
+use crate::types::{add_message, format_log_line, set_log_sink, LogEntry, MessageHub, MessageOrigin, MessageType};
+
+/// A struct appending the server messages to a log file, one timestamped line per message.
+///
+/// `add_message` sends the messages through a channel and a thread of the logger writes them,
+/// so adding a message never waits for the disk. The file is flushed every `LOG_FLUSH_MS` and
+/// when the logger stops. Past `max_bytes` the file is renamed `<path>.1`, the older files are
+/// shifted to `<path>.2` and so on, and only `kept_files` of them are kept.
+pub(crate) struct FileLogger {
+    thread: thread::JoinHandle<()>,
+}
+
+impl FileLogger {
+    /// Opens the log file and starts sending the messages to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The log file, appended to if it exists.
+    /// * `max_bytes` - The size past which the file is rotated.
+    /// * `kept_files` - The number of rotated files kept.
+    /// * `messages` - The messages of the server, where the write errors are reported.
+    ///
+    /// # Returns
+    ///
+    /// The logger, or the error opening the file.
+    ///
+    pub fn start(path: &Path, max_bytes: u64, kept_files: usize, messages: MessageHub) -> io::Result<Self> {
+        let mut writer = LogWriter::open(path.to_path_buf(), max_bytes, kept_files, messages)?;
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || writer.run(receiver));
+        set_log_sink(Some(sender));
+        Ok(FileLogger { thread })
+    }
+
+    /// Stops sending the messages to the log file, and waits for the messages sent so far to be written.
+    pub fn stop(self) {
+        // Le fil se termine quand le canal est fermé, après avoir tout écrit
+        set_log_sink(None);
+        let _ = self.thread.join();
+    }
+}
+
+/// The log file, written by the thread of the logger.
+struct LogWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    kept_files: usize,
+    writer: BufWriter<File>,
+    /// The size of the file, including what is still buffered.
+    size: u64,
+    /// When the file was last flushed.
+    flushed_at: Instant,
+    /// Whether the last write failed, so that a full disk is reported once.
+    failing: bool,
+    /// The messages of the server, where the write errors are reported.
+    messages: MessageHub,
+}
+
+impl LogWriter {
+    /// Opens the log file for appending.
+    fn open(path: PathBuf, max_bytes: u64, kept_files: usize, messages: MessageHub) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(LogWriter { path, max_bytes, kept_files, writer: BufWriter::new(file), size, flushed_at: Instant::now(), failing: false, messages })
+    }
+
+    /// Writes the messages of the channel until it is closed.
+    fn run(&mut self, receiver: Receiver<LogEntry>) {
+        let interval = Duration::from_millis(AppDefines::LOG_FLUSH_MS);
+        loop {
+            match receiver.recv_timeout(interval.saturating_sub(self.flushed_at.elapsed())) {
+                Ok(entry) => {
+                    let result = self.write(&entry);
+                    self.report(result);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if self.flushed_at.elapsed() >= interval {
+                let result = self.writer.flush();
+                self.report(result);
+                self.flushed_at = Instant::now();
+            }
+        }
+        let result = self.writer.flush();
+        self.report(result);
+    }
+
+    /// Appends a message to the file, rotating it first if it is full.
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        let line = format!("{}\n", format_log_line(entry.at, entry.message_type, entry.origin, &entry.text));
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.writer.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shifts the rotated files, renames the log file `<path>.1` and starts a new one.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        let rotated = |index: usize| {
+            let mut path = self.path.clone().into_os_string();
+            path.push(format!(".{}", index));
+            PathBuf::from(path)
+        };
+        if self.kept_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            // Le plus ancien disparaît, les autres reculent d'un rang
+            let _ = fs::remove_file(rotated(self.kept_files));
+            for index in (1..self.kept_files).rev() {
+                let from = rotated(index);
+                if from.exists() {
+                    fs::rename(&from, rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(1))?;
+        }
+        self.writer = BufWriter::new(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Reports the first of a series of write errors to the messages of the server.
+    ///
+    /// The message comes back to the failing file too, its error is not reported again.
+    fn report(&mut self, result: io::Result<()>) {
+        match result {
+            Err(e) if !self.failing => {
+                let text = format!("[ERROR] Failed to write the log file {}: {}", self.path.display(), e);
+                add_message(&self.messages, text, MessageType::Error, MessageOrigin::Network);
+                self.failing = true;
+            }
+            Err(_) => {}
+            Ok(()) => self.failing = false,
+        }
+    }
+}
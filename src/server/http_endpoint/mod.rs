@@ -0,0 +1,251 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value as Json};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::game_config::GameMode;
+use crate::game_logic::match_state::MatchPhase;
+use crate::game_logic::GameLogic;
+use crate::server::server_thread::ServerThread;
+use crate::types::{add_message, MessageOrigin, MessageType};
+
+/// A struct answering the read-only HTTP requests of the dashboards.
+///
+/// Three paths are served as JSON: `/state` for the arena, `/leaderboard` for the cumulative
+/// scores and `/metrics-lite` for the health of the server. Unless turned off, `/metrics` gives
+/// the counters of the server in the text format scraped by Prometheus. As for the state push, the answer is
+/// built while the game logic is locked, then written once it is released, so that a slow browser
+/// never holds the game. The requests are answered one at a time, each connection closed after it.
+pub(crate) struct HttpEndpoint {
+    listener: TcpListener,
+    server: ServerThread,
+}
+
+/// A response of the endpoint: its status line and its body.
+struct HttpResponse {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl HttpResponse {
+    /// Creates a response with a JSON body.
+    fn json(status: &'static str, body: Json) -> Self {
+        HttpResponse { status, content_type: "application/json", body: body.to_string() }
+    }
+
+    /// Creates an error response, its body giving the reason.
+    fn error(status: &'static str, reason: &str) -> Self {
+        HttpResponse::json(status, json!({ "error": reason }))
+    }
+}
+
+impl HttpEndpoint {
+    /// Creates the HTTP endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The listener of the HTTP port.
+    /// * `server` - The server the state is read from.
+    ///
+    /// # Returns
+    ///
+    /// A new `HttpEndpoint`.
+    ///
+    pub fn new(listener: TcpListener, server: ServerThread) -> Self {
+        HttpEndpoint { listener, server }
+    }
+
+    /// Answers the requests until the server stops.
+    pub fn run(&self) {
+        // Une écoute bloquante ne verrait jamais l'arrêt du serveur
+        if let Err(e) = self.listener.set_nonblocking(true) {
+            add_message(&self.server.messages, format!("[ERROR] Could not poll the HTTP port: {}", e), MessageType::Error, MessageOrigin::Network);
+        }
+        while !self.server.stopping.load(Ordering::SeqCst) {
+            match self.listener.accept() {
+                Ok((stream, _)) => self.answer(stream),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(AppDefines::ACCEPT_POLL_MS));
+                }
+                Err(e) => {
+                    add_message(&self.server.messages, format!("[ERROR] HTTP connection failed: {}", e), MessageType::Error, MessageOrigin::Network);
+                }
+            }
+        }
+    }
+
+    /// Reads one request from a connection and writes its response, then closes the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The connection of the browser.
+    ///
+    fn answer(&self, mut stream: TcpStream) {
+        // Un navigateur muet ne bloque les requêtes suivantes que jusqu'à l'échéance
+        let timeout = Some(Duration::from_millis(AppDefines::HTTP_TIMEOUT_MS));
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(timeout);
+        let _ = stream.set_write_timeout(timeout);
+
+        let response = match HttpEndpoint::read_head(&mut stream) {
+            Some(head) => self.respond(&head),
+            None => HttpResponse::error("400 Bad Request", "Incomplete request"),
+        };
+        let head = format!(
+            "HTTP/1.1 {}\r\n\
+             Content-Type: {}\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: GET, OPTIONS\r\n\
+             Access-Control-Allow-Headers: *\r\n\
+             Cache-Control: no-store\r\n\
+             Connection: close\r\n\
+             \r\n",
+            response.status,
+            response.content_type,
+            response.body.len(),
+        );
+        let _ = stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(response.body.as_bytes())).and_then(|_| stream.flush());
+    }
+
+    /// Reads the head of a request, up to the empty line ending it.
+    ///
+    /// # Returns
+    ///
+    /// The head of the request, `None` if the connection closed, timed out or sent too much before it ended.
+    ///
+    fn read_head(stream: &mut TcpStream) -> Option<String> {
+        let mut head: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+            if head.len() > AppDefines::HTTP_MAX_REQUEST_BYTES {
+                return None;
+            }
+            match stream.read(&mut chunk) {
+                Ok(0) => return None,
+                Ok(read) => head.extend_from_slice(&chunk[..read]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return None,
+            }
+        }
+        Some(String::from_utf8_lossy(&head).into_owned())
+    }
+
+    /// Builds the response to a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `head` - The head of the request: its request line and its headers.
+    ///
+    /// # Returns
+    ///
+    /// The response, a 404 for an unknown path and a 405 for a method other than `GET` and `OPTIONS`.
+    ///
+    fn respond(&self, head: &str) -> HttpResponse {
+        let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+        let method = request_line.next().unwrap_or("");
+        let target = request_line.next().unwrap_or("");
+        // La requête et le fragment ne changent rien à la réponse
+        let path = target.split(['?', '#']).next().unwrap_or("");
+
+        match method {
+            // La requête préliminaire CORS du navigateur n'attend que les en-têtes
+            "OPTIONS" => HttpResponse { status: "204 No Content", content_type: "text/plain", body: String::new() },
+            "GET" => match path {
+                "/state" => HttpResponse::json("200 OK", self.server.commands.query(HttpEndpoint::state)),
+                "/leaderboard" => HttpResponse::json("200 OK", self.server.commands.query(HttpEndpoint::leaderboard)),
+                "/metrics-lite" => HttpResponse::json("200 OK", self.server.metrics.sample().to_json()),
+                "/metrics" if self.server.settings.lock().unwrap().http_prometheus_metrics => {
+                    let metrics = self.server.metrics.clone();
+                    let gauges = self.server.commands.query(move |logic| metrics.gauges(logic));
+                    HttpResponse {
+                        status: "200 OK",
+                        content_type: "text/plain; version=0.0.4",
+                        body: self.server.metrics.registry.render(&gauges),
+                    }
+                }
+                _ => HttpResponse::error("404 Not Found", "Unknown path"),
+            },
+            _ => HttpResponse::error("405 Method Not Allowed", "Only GET is served"),
+        }
+    }
+
+    /// Captures the arena: the entities, the number of bullets, the timer of the match, the zone
+    /// and, in the control point mode, the control points.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic, locked by the caller.
+    ///
+    fn state(logic: &GameLogic) -> Json {
+        let entities: Vec<Json> = logic.entities.iter().map(|entity| json!({
+            "id": entity.id,
+            "name": entity.name,
+            "team": entity.team,
+            "x": entity.x,
+            "y": entity.y,
+            "health": entity.health,
+            "score": entity.score,
+        })).collect();
+        let state = &logic.match_state;
+        let phase = match state.phase {
+            MatchPhase::Running => AppDefines::MATCH_RUNNING,
+            MatchPhase::Intermission => AppDefines::MATCH_INTERMISSION,
+        };
+        let remaining = state.remaining(logic.sim_time(), &logic.game_config).map(|remaining| remaining.as_secs_f64());
+        let zone = logic.zone().map(|zone| json!({
+            "x": zone.center.0,
+            "y": zone.center.1,
+            "radius": zone.radius,
+            "next_radius": zone.next_radius,
+            "shrink_starts_in_s": zone.shrink_starts_in.as_secs_f64(),
+            "shrink_ends_in_s": zone.shrink_ends_in.as_secs_f64(),
+        }));
+        let control_points: Vec<Json> = match logic.game_config.game_mode {
+            GameMode::ControlPoint => logic.control_points().iter().map(|point| json!({
+                "name": point.name,
+                "x": point.position.0,
+                "y": point.position.1,
+                "radius": point.radius,
+                "owner": point.owner.map(|owner| owner.code()),
+                "progress": point.progress,
+            })).collect(),
+            GameMode::Deathmatch => Vec::new(),
+        };
+        json!({
+            "tick": logic.tick,
+            "entities": entities,
+            "bullets": logic.bullets.len(),
+            "match": { "round": state.round, "phase": phase, "remaining_s": remaining },
+            "zone": zone,
+            "control_points": control_points,
+        })
+    }
+
+    /// Captures the best players of the cumulative leaderboard, as `LEADERBOARD` lists them.
+    ///
+    /// # Arguments
+    ///
+    /// * `logic` - The game logic, locked by the caller.
+    ///
+    fn leaderboard(logic: &GameLogic) -> Json {
+        let players: Vec<Json> = logic.leaderboard.top(AppDefines::LEADERBOARD_MAX_TOP).into_iter().enumerate().map(|(index, (name, totals))| json!({
+            "rank": index + 1,
+            "name": name,
+            "score": totals.score,
+            "kills": totals.kills,
+            "deaths": totals.deaths,
+            "matches": totals.matches_played,
+            "shots_fired": totals.shots_fired,
+            "hits": totals.hits,
+            "accuracy": totals.accuracy(),
+            "waves": totals.waves_survived,
+        })).collect();
+        json!({ "players": players })
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value as Json};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::GameLogic;
+
+pub(crate) mod registry;
+
+use registry::{Gauges, MetricsRegistry};
+
+/// The health of the server at some point, see `ServerMetrics::sample`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MetricsSample {
+    /// The number of connected clients, TCP and WebSocket together.
+    pub connected_clients: usize,
+    /// The number of entities controlled by a client.
+    pub players: usize,
+    /// The number of entities controlled by the AI.
+    pub ai_entities: usize,
+    /// The number of bullets flying.
+    pub bullets: usize,
+    /// The number of obstacles in the arena.
+    pub obstacles: usize,
+    /// The number of steps run in the last second.
+    pub tick_rate: f32,
+    /// The number of steps per second the game loop aims for.
+    pub target_tick_rate: f32,
+    /// The average time a step held the game logic in the last second, in milliseconds.
+    pub average_step_ms: f32,
+    /// The simulated time per second of real time in the last second: the speed applied by the game loop.
+    pub time_scale: f32,
+    /// The time since the server started.
+    pub uptime: Duration,
+    /// The frames drawn per second by the game UI, `None` while its status bar or its tab is hidden.
+    pub ui_frame_rate: Option<f32>,
+    /// The average time a frame of the game UI waited for the game loop, in milliseconds.
+    pub ui_lock_wait_ms: Option<f32>,
+}
+
+impl MetricsSample {
+    /// Returns the metrics as a JSON object, the durations in seconds and the missing UI metrics as `null`.
+    pub fn to_json(&self) -> Json {
+        json!({
+            "connected_clients": self.connected_clients,
+            "players": self.players,
+            "ai_entities": self.ai_entities,
+            "bullets": self.bullets,
+            "obstacles": self.obstacles,
+            "tick_rate": self.tick_rate,
+            "target_tick_rate": self.target_tick_rate,
+            "average_step_ms": self.average_step_ms,
+            "time_scale": self.time_scale,
+            "uptime_secs": self.uptime.as_secs_f64(),
+            "ui_frame_rate": self.ui_frame_rate,
+            "ui_lock_wait_ms": self.ui_lock_wait_ms,
+        })
+    }
+}
+
+/// One point of the history of the metrics, recorded every second.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MetricsPoint {
+    /// The uptime of the server at the point, in seconds.
+    pub uptime_secs: f64,
+    pub tick_rate: f32,
+    pub connected_clients: usize,
+}
+
+/// The metrics written by the game loop.
+#[derive(Debug, Default)]
+struct MetricsState {
+    players: usize,
+    ai_entities: usize,
+    bullets: usize,
+    obstacles: usize,
+    tick_rate: f32,
+    target_tick_rate: f32,
+    average_step_ms: f32,
+    time_scale: f32,
+    /// The frame rate of the game UI, its average wait for the game loop in milliseconds and when they were recorded.
+    ui_frames: Option<(f32, f32, Instant)>,
+    /// The last `METRICS_HISTORY_SECS` points, oldest first.
+    history: VecDeque<MetricsPoint>,
+}
+
+/// The health metrics of the server, computed once and shared by the server UI, the game UI and the admin queries.
+///
+/// The game loop records the simulation metrics once per second, while it holds the game logic,
+/// the game UI records its own frame rate, and the client count is the counter of the listeners:
+/// reading the metrics never locks the game logic.
+#[derive(Debug, Clone)]
+pub(crate) struct ServerMetrics {
+    /// The number of connected clients, kept up to date by the listeners.
+    connected_clients: Arc<AtomicUsize>,
+    /// When the server started.
+    started_at: Instant,
+    state: Arc<Mutex<MetricsState>>,
+    /// The counters exported to Prometheus.
+    pub registry: Arc<MetricsRegistry>,
+}
+
+impl ServerMetrics {
+    /// Creates the metrics of a server starting now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connected_clients` - The number of connected clients, shared with the listeners.
+    ///
+    /// # Returns
+    ///
+    /// The metrics, empty until the game loop records them.
+    ///
+    pub fn new(connected_clients: Arc<AtomicUsize>) -> Self {
+        ServerMetrics {
+            connected_clients,
+            started_at: Instant::now(),
+            state: Arc::new(Mutex::new(MetricsState::default())),
+            registry: Arc::new(MetricsRegistry::new()),
+        }
+    }
+
+    /// Records the simulation metrics of the last second and adds a point to the history.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_logic` - The game logic, locked by the caller.
+    /// * `target_tick_rate` - The number of steps per second the game loop aims for.
+    /// * `average_step` - The average time a step held the game logic in the last second.
+    /// * `time_scale` - The simulated time per second of real time in the last second.
+    ///
+    pub fn record(&self, game_logic: &GameLogic, target_tick_rate: f32, average_step: Duration, time_scale: f32) {
+        let ai_entities = game_logic.entities.iter().filter(|entity| entity.is_ai).count();
+        let point = MetricsPoint {
+            uptime_secs: self.started_at.elapsed().as_secs_f64(),
+            tick_rate: game_logic.achieved_tick_rate,
+            connected_clients: self.connected_clients.load(Ordering::SeqCst),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.players = game_logic.entities.len() - ai_entities;
+        state.ai_entities = ai_entities;
+        state.bullets = game_logic.bullets.len();
+        state.obstacles = game_logic.obstacles.len();
+        state.tick_rate = point.tick_rate;
+        state.target_tick_rate = target_tick_rate;
+        state.average_step_ms = average_step.as_secs_f32() * 1000.0;
+        state.time_scale = time_scale;
+        // Un point par seconde : l'historique couvre METRICS_HISTORY_SECS secondes
+        if state.history.len() == AppDefines::METRICS_HISTORY_SECS {
+            state.history.pop_front();
+        }
+        state.history.push_back(point);
+    }
+
+    /// Records the frame rate of the game UI, measured by its status bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames` - The frame rate and the average time a frame waited for the game logic,
+    ///   `None` once the status bar is hidden and no longer measures them.
+    ///
+    pub fn record_ui(&self, frames: Option<(f32, Duration)>) {
+        self.state.lock().unwrap().ui_frames =
+            frames.map(|(frame_rate, lock_wait)| (frame_rate, lock_wait.as_secs_f32() * 1000.0, Instant::now()));
+    }
+
+    /// Adds a step to the counters: its duration, and the kills among the events it pushed.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_logic` - The game logic, locked by the caller right after the step.
+    ///
+    pub fn record_step(&self, game_logic: &GameLogic) {
+        self.registry.step_duration.observe(game_logic.last_step_duration);
+        let cursor = self.registry.event_cursor.load(Ordering::Relaxed);
+        let kills = game_logic.events.since(cursor).filter(|queued| matches!(queued.event, GameEvent::Kill { .. })).count();
+        self.registry.kills.add(kills as u64);
+        self.registry.event_cursor.store(game_logic.events.cursor(), Ordering::Relaxed);
+    }
+
+    /// Reads the instantaneous values exported to Prometheus.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_logic` - The game logic, locked by the caller.
+    ///
+    pub fn gauges(&self, game_logic: &GameLogic) -> Gauges {
+        Gauges {
+            connected_clients: self.connected_clients.load(Ordering::SeqCst),
+            entities: game_logic.entities.len(),
+            ai_entities: game_logic.entities.iter().filter(|entity| entity.is_ai).count(),
+            live_bullets: game_logic.bullets.len(),
+            tick_rate: game_logic.achieved_tick_rate,
+        }
+    }
+
+    /// Returns the current metrics.
+    pub fn sample(&self) -> MetricsSample {
+        let state = self.state.lock().unwrap();
+        // Un onglet caché ne dessine plus : sa dernière mesure ne vaut plus rien
+        let ui_frames = state.ui_frames
+            .filter(|(_, _, recorded_at)| recorded_at.elapsed() < Duration::from_millis(AppDefines::UI_METRICS_STALE_MS));
+        MetricsSample {
+            connected_clients: self.connected_clients.load(Ordering::SeqCst),
+            players: state.players,
+            ai_entities: state.ai_entities,
+            bullets: state.bullets,
+            obstacles: state.obstacles,
+            tick_rate: state.tick_rate,
+            target_tick_rate: state.target_tick_rate,
+            average_step_ms: state.average_step_ms,
+            time_scale: state.time_scale,
+            uptime: self.started_at.elapsed(),
+            ui_frame_rate: ui_frames.map(|(frame_rate, _, _)| frame_rate),
+            ui_lock_wait_ms: ui_frames.map(|(_, lock_wait_ms, _)| lock_wait_ms),
+        }
+    }
+
+    /// Returns the metrics recorded in the last `METRICS_HISTORY_SECS` seconds, oldest first.
+    pub fn history(&self) -> Vec<MetricsPoint> {
+        self.state.lock().unwrap().history.iter().copied().collect()
+    }
+}
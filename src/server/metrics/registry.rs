@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::app_defines::AppDefines;
+
+/// A count that only grows, shared between threads.
+#[derive(Debug, Default)]
+pub(crate) struct Counter(AtomicU64);
+
+impl Counter {
+    /// Adds to the count.
+    pub fn add(&self, amount: u64) {
+        self.0.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    /// Returns the count.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts told apart by a label, e.g. the errors by kind.
+#[derive(Debug, Default)]
+pub(crate) struct LabeledCounter {
+    counts: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl LabeledCounter {
+    /// Adds one to the count of a label, starting it at 0 if it is new.
+    pub fn increment(&self, label: &'static str) {
+        *self.counts.lock().unwrap().entry(label).or_insert(0) += 1;
+    }
+
+    /// Returns the count of each label seen, sorted by label.
+    pub fn counts(&self) -> Vec<(&'static str, u64)> {
+        self.counts.lock().unwrap().iter().map(|(label, count)| (*label, *count)).collect()
+    }
+}
+
+/// Durations sorted into buckets, with their number and their sum.
+#[derive(Debug)]
+pub(crate) struct Histogram {
+    /// The upper bounds of the buckets in seconds, increasing.
+    bounds: &'static [f64],
+    /// The number of durations in each bucket, not cumulative, the last one above every bound.
+    buckets: Vec<AtomicU64>,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - The upper bounds of the buckets in seconds, increasing.
+    ///
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Histogram {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Adds a duration to the bucket of the smallest bound above it.
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = self.bounds.iter().position(|bound| seconds <= *bound).unwrap_or(self.bounds.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// The instantaneous values exported with the counters, read by the caller.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Gauges {
+    pub connected_clients: usize,
+    pub entities: usize,
+    pub ai_entities: usize,
+    pub live_bullets: usize,
+    /// The number of steps run in the last second.
+    pub tick_rate: f32,
+}
+
+/// The counters exported to Prometheus, see `MetricsRegistry::render`.
+///
+/// The counters are atomic, so the game loop and the client handlers add to them without a lock,
+/// and they only grow for the lifetime of the server: a reset of the match does not touch them.
+#[derive(Debug)]
+pub(crate) struct MetricsRegistry {
+    /// The commands processed, every client together.
+    pub commands_processed: Counter,
+    /// The bytes read from the clients.
+    pub bytes_received: Counter,
+    /// The bytes written to the clients, world state pushes included.
+    pub bytes_sent: Counter,
+    /// The entities destroyed, self-destructions included.
+    pub kills: Counter,
+    /// The error replies sent, by name of the error.
+    pub errors: LabeledCounter,
+    /// How long the simulation steps took to compute.
+    pub step_duration: Histogram,
+    /// The number of the last game event counted, see `ServerMetrics::record_step`.
+    pub event_cursor: AtomicU64,
+}
+
+impl MetricsRegistry {
+    /// Creates a registry with every counter at 0.
+    pub fn new() -> Self {
+        MetricsRegistry {
+            commands_processed: Counter::default(),
+            bytes_received: Counter::default(),
+            bytes_sent: Counter::default(),
+            kills: Counter::default(),
+            errors: LabeledCounter::default(),
+            step_duration: Histogram::new(&AppDefines::STEP_DURATION_BUCKETS),
+            event_cursor: AtomicU64::new(0),
+        }
+    }
+
+    /// Writes the gauges and the counters in the text exposition format of Prometheus.
+    ///
+    /// # Arguments
+    ///
+    /// * `gauges` - The instantaneous values.
+    ///
+    /// # Returns
+    ///
+    /// One family per metric, each with its `HELP` and `TYPE` lines.
+    ///
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let mut text = String::new();
+        let prefix = AppDefines::PROMETHEUS_PREFIX;
+        let mut family = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(text, "# HELP {}{} {}", prefix, name, help);
+            let _ = writeln!(text, "# TYPE {}{} {}", prefix, name, kind);
+            for (suffix, value) in samples {
+                let _ = writeln!(text, "{}{}{} {}", prefix, name, suffix, value);
+            }
+        };
+        let single = |value: String| vec![(String::new(), value)];
+
+        family("connected_clients", "gauge", "The connected clients, TCP and WebSocket together.", &single(gauges.connected_clients.to_string()));
+        family("entities", "gauge", "The entities in the arena, AI included.", &single(gauges.entities.to_string()));
+        family("ai_entities", "gauge", "The entities controlled by the AI.", &single(gauges.ai_entities.to_string()));
+        family("live_bullets", "gauge", "The bullets flying.", &single(gauges.live_bullets.to_string()));
+        family("tick_rate", "gauge", "The simulation steps run in the last second.", &single(gauges.tick_rate.to_string()));
+
+        // Les seaux de Prometheus sont cumulés, ceux du registre non
+        let mut cumulative = 0;
+        let mut buckets: Vec<(String, String)> = Vec::new();
+        for (index, bound) in self.step_duration.bounds.iter().enumerate() {
+            cumulative += self.step_duration.buckets[index].load(Ordering::Relaxed);
+            buckets.push((format!("_bucket{{le=\"{}\"}}", bound), cumulative.to_string()));
+        }
+        // Le dernier seau compté avec les autres : le total reste cohérent pendant une mesure
+        let count = cumulative + self.step_duration.buckets[self.step_duration.bounds.len()].load(Ordering::Relaxed);
+        buckets.push(("_bucket{le=\"+Inf\"}".to_string(), count.to_string()));
+        let sum = self.step_duration.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+        buckets.push(("_sum".to_string(), sum.to_string()));
+        buckets.push(("_count".to_string(), count.to_string()));
+        family("step_duration_seconds", "histogram", "How long the simulation steps took to compute.", &buckets);
+
+        family("commands_processed_total", "counter", "The commands processed.", &single(self.commands_processed.get().to_string()));
+        family("bytes_rx_total", "counter", "The bytes read from the clients.", &single(self.bytes_received.get().to_string()));
+        family("bytes_tx_total", "counter", "The bytes written to the clients.", &single(self.bytes_sent.get().to_string()));
+        family("kills_total", "counter", "The entities destroyed, self-destructions included.", &single(self.kills.get().to_string()));
+        let errors: Vec<(String, String)> = self.errors.counts().into_iter()
+            .map(|(kind, count)| (format!("{{kind=\"{}\"}}", kind), count.to_string()))
+            .collect();
+        family("errors_total", "counter", "The error replies sent to the clients, by kind.", &errors);
+        text
+    }
+}
@@ -1,2 +1,20 @@
+pub(crate) mod admin_port;
+pub mod chat;
 pub(crate) mod client_handler;
-pub(crate) mod server_thread;
\ No newline at end of file
+pub(crate) mod client_id;
+pub(crate) mod client_pool;
+pub(crate) mod client_registry;
+pub(crate) mod client_stats;
+pub(crate) mod client_stream;
+pub(crate) mod connections;
+pub(crate) mod death_notices;
+pub(crate) mod event_logger;
+pub(crate) mod file_logger;
+pub(crate) mod http_endpoint;
+pub(crate) mod metrics;
+pub mod server_thread;
+pub(crate) mod sessions;
+pub mod state_delta;
+pub(crate) mod subscriptions;
+pub(crate) mod tls;
+pub(crate) mod udp_channel;
\ No newline at end of file
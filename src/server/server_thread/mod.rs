@@ -1,22 +1,48 @@
 use std::collections::HashMap;
-use std::net::{SocketAddr, TcpListener};
-use std::sync::{Arc, Mutex};
+use std::io::Write;
+use std::path::Path;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use rustls::ServerConfig;
 
 use crate::app_defines::AppDefines;
 use crate::entities::entity::Entity;
+use crate::game_logic::command::{GameCommand, GameCommands};
+use crate::game_logic::game_loop::{GameLoop, GameLoopHandle};
+use crate::game_logic::observer::GameEvent;
 use crate::game_logic::GameLogic;
+use crate::server::admin_port::AdminPort;
+use crate::server::chat::{self, ChatMutes};
+use crate::server::client_handler::protocol_error::ProtocolError;
+use crate::server::client_handler::web_socket;
 use crate::server::client_handler::ClientHandler;
-use crate::types::{add_message, MessageType, StyledMessage};
+use crate::server::client_id::ClientId;
+use crate::server::client_pool::{ClientPool, ClientSlot};
+use crate::server::client_registry::ClientRegistry;
+use crate::server::client_stats::ClientStatsRegistry;
+use crate::server::client_stream::ClientStream;
+use crate::server::client_handler::reply::{Reply, Value};
+use crate::server::connections::ConnectionRegistry;
+use crate::server::death_notices::DeathNotices;
+use crate::server::event_logger::EventLogger;
+use crate::server::file_logger::FileLogger;
+use crate::server::http_endpoint::HttpEndpoint;
+use crate::server::metrics::ServerMetrics;
+use crate::server::sessions::SessionRegistry;
+use crate::server::state_delta::{DeltaFrame, WorldFrame};
+use crate::server::subscriptions::SubscriptionRegistry;
+use crate::server::tls::{self, TlsHandshake};
+use crate::server::udp_channel::UdpChannel;
+use crate::types::{add_chat_message, add_message, MessageHub, MessageOrigin, MessageType};
 
 /// A struct representing server settings.
 #[derive(Debug)]
-pub(crate) struct ServerSettings {
-    /// The width of the arena.
-    pub arena_width: f32,
-    /// The height of the arena.
-    pub arena_height: f32,
+pub struct ServerSettings {
     /// The probability of obstacles appearing in the arena.
     pub obstacle_probability: f64,
     /// The available game modes.
@@ -25,14 +51,65 @@ pub(crate) struct ServerSettings {
     pub bot_rate_of_fire: i32,
     /// The penalty time for infractions.
     pub penalty_time: i64,
-    /// The delay before a connection times out.
-    pub connection_timeout_delay: i32,
+    /// The delay before a silent connection times out in seconds, see `set_connection_timeout_delay`.
+    connection_timeout_delay: i32,
+    /// The port the server listens on for TCP clients, see `set_port`.
+    port: u16,
+    /// The number of simulation steps per second, `tick_hz` in the configuration file, see `set_tick_hz`.
+    tick_hz: f32,
     /// The duration messages are displayed.
     pub message_duration: i32,
     /// The maximum length of a message.
     pub message_length: i32,
     /// The score limit for the game.
     pub score_limit: i32,
+    /// The maximum number of actuator commands and queries per entity and per step (0 = unlimited).
+    pub command_budget_per_step: u32,
+    /// Whether clients may skip the `HELLO` handshake.
+    pub allow_legacy_clients: bool,
+    /// How long the entity of a dropped client is kept alive for it to reconnect, in seconds.
+    pub reconnect_grace_secs: u64,
+    /// The port of the WebSocket listener (0 = disabled).
+    pub web_socket_port: u16,
+    /// The address of the admin control listener, read when the server starts.
+    pub admin_control_address: String,
+    /// The port of the admin control listener (0 = disabled), read when the server starts.
+    pub admin_control_port: u16,
+    /// The port of the read-only HTTP endpoint (0 = disabled), read when the server starts.
+    pub http_port: u16,
+    /// Whether the HTTP endpoint also serves the Prometheus metrics on `/metrics`.
+    pub http_prometheus_metrics: bool,
+    /// Whether actuator commands are also accepted as UDP datagrams, on the port of the server.
+    pub udp_actuators: bool,
+    /// The maximum number of connected clients, TCP and WebSocket together (0 = unlimited).
+    pub max_clients: usize,
+    /// Whether the lobby is locked: the clients connecting get no entity, only spectators and
+    /// reconnection token holders get in. Read at each handshake, so unlocking applies at once.
+    pub lobby_locked: bool,
+    /// The number of worker threads handling the clients, read when the server starts.
+    pub client_workers: usize,
+    /// The password unlocking the admin commands (empty = admin commands disabled).
+    pub admin_password: String,
+    /// How long the traffic statistics of a disconnected client are kept, in seconds.
+    pub client_stats_grace_secs: u64,
+    /// The file the cumulative leaderboard is loaded from at startup and saved to after each match.
+    pub leaderboard_path: String,
+    /// The file the messages are appended to (empty = no log file), read when the server starts.
+    pub log_path: String,
+    /// The size past which the log file is rotated, in bytes.
+    pub log_max_bytes: u64,
+    /// The number of rotated log files kept.
+    pub log_kept_files: usize,
+    /// The number of messages the server keeps in memory, the oldest dropped past it. Read when the server starts.
+    pub message_log_capacity: usize,
+    /// The least severe messages the server keeps, see `set_log_verbosity`.
+    log_verbosity: MessageType,
+    /// Whether the TCP and WebSocket clients must speak TLS, read when the server starts.
+    pub enable_tls: bool,
+    /// The PEM file of the TLS certificate chain, read when the server starts.
+    pub tls_cert_path: String,
+    /// The PEM file of the private key of the TLS certificate, read when the server starts.
+    pub tls_key_path: String,
 }
 
 impl ServerSettings {
@@ -44,119 +121,1188 @@ impl ServerSettings {
     ///
     pub fn new() -> Self {
         ServerSettings {
-            arena_width: AppDefines::ARENA_WIDTH,
-            arena_height: AppDefines::ARENA_HEIGHT,
             obstacle_probability: AppDefines::OBSTACLE_PROBABILITY,
             game_modes: AppDefines::GAME_MODES.to_vec(),
             bot_rate_of_fire: AppDefines::BOT_RATE_OF_FIRE,
             penalty_time: AppDefines::PENALTY_TIME,
             connection_timeout_delay: AppDefines::CONNECTION_TIMEOUT_DELAY,
+            port: AppDefines::SERVER_PORT,
+            tick_hz: AppDefines::TARGET_FPS_2D_PHYSICS,
             message_duration: AppDefines::MESSAGE_DURATION,
             message_length: AppDefines::MESSAGE_LENGTH,
             score_limit: AppDefines::SCORE_LIMIT,
+            command_budget_per_step: AppDefines::COMMAND_BUDGET_PER_STEP,
+            allow_legacy_clients: AppDefines::ALLOW_LEGACY_CLIENTS,
+            reconnect_grace_secs: AppDefines::RECONNECT_GRACE_PERIOD_SECS,
+            web_socket_port: AppDefines::WEB_SOCKET_PORT,
+            admin_control_address: AppDefines::ADMIN_CONTROL_ADDRESS.to_string(),
+            admin_control_port: AppDefines::ADMIN_CONTROL_PORT,
+            http_port: AppDefines::HTTP_PORT,
+            http_prometheus_metrics: AppDefines::HTTP_PROMETHEUS_METRICS,
+            udp_actuators: AppDefines::UDP_ACTUATORS_ENABLED,
+            max_clients: AppDefines::MAX_CLIENTS,
+            lobby_locked: AppDefines::LOBBY_LOCKED,
+            client_workers: AppDefines::CLIENT_WORKERS,
+            admin_password: AppDefines::ADMIN_PASSWORD.to_string(),
+            client_stats_grace_secs: AppDefines::CLIENT_STATS_GRACE_PERIOD_SECS,
+            leaderboard_path: AppDefines::LEADERBOARD_FILE.to_string(),
+            log_path: AppDefines::LOG_FILE.to_string(),
+            log_max_bytes: AppDefines::LOG_MAX_BYTES,
+            log_kept_files: AppDefines::LOG_KEPT_FILES,
+            message_log_capacity: AppDefines::MESSAGE_LOG_CAPACITY,
+            log_verbosity: AppDefines::LOG_VERBOSITY,
+            enable_tls: AppDefines::ENABLE_TLS,
+            tls_cert_path: AppDefines::TLS_CERT_FILE.to_string(),
+            tls_key_path: AppDefines::TLS_KEY_FILE.to_string(),
         }
     }
+
+    /// Creates the settings of a server with the settings read from a file, the others from `AppDefines`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The settings of `[server]`, checked by `Config::parse`.
+    ///
+    /// # Returns
+    ///
+    /// A new `ServerSettings` instance.
+    ///
+    pub fn from_config(config: &crate::config::ServerConfig) -> Self {
+        ServerSettings {
+            port: config.port,
+            max_clients: config.max_clients,
+            connection_timeout_delay: config.timeout_secs,
+            tick_hz: config.tick_hz,
+            http_port: config.http_port,
+            http_prometheus_metrics: config.prometheus_metrics,
+            ..ServerSettings::new()
+        }
+    }
+
+    /// Returns the delay before a silent connection times out, in seconds.
+    pub fn connection_timeout_delay(&self) -> i32 {
+        self.connection_timeout_delay
+    }
+
+    /// Sets the delay before a silent connection times out, applied to the connected clients at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The delay in seconds, at least 1.
+    ///
+    /// # Returns
+    ///
+    /// Why the delay is refused, the previous one is kept.
+    ///
+    pub fn set_connection_timeout_delay(&mut self, delay: i32) -> Result<(), String> {
+        if delay <= 0 {
+            return Err(format!("The connection timeout must be at least 1 second, not {}", delay));
+        }
+        self.connection_timeout_delay = delay;
+        Ok(())
+    }
+
+    /// Returns the least severe messages the server keeps.
+    pub fn log_verbosity(&self) -> MessageType {
+        self.log_verbosity
+    }
+
+    /// Sets the least severe messages the server keeps, applied to the next messages at once.
+    ///
+    /// The messages less severe are dropped as they are added: they reach neither the server UI nor the log file.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_level` - The least severe type kept.
+    ///
+    pub fn set_log_verbosity(&mut self, min_level: MessageType) {
+        self.log_verbosity = min_level;
+        crate::types::set_log_verbosity(min_level);
+    }
+
+    /// Returns the port the server listens on for TCP clients.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Sets the port the server listens on for TCP clients.
+    ///
+    /// The running server binds the new port within a second and stops accepting clients on the
+    /// old one, the connected clients stay connected. The WebSocket and UDP ports do not change.
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - The new port, not 0.
+    ///
+    /// # Returns
+    ///
+    /// Why the port is refused, the previous one is kept.
+    ///
+    pub fn set_port(&mut self, port: u16) -> Result<(), String> {
+        if port == 0 {
+            return Err("The port must not be 0".to_string());
+        }
+        self.port = port;
+        Ok(())
+    }
+
+    /// Returns the number of simulation steps per second.
+    pub fn tick_hz(&self) -> f32 {
+        self.tick_hz
+    }
+
+    /// Sets the number of simulation steps per second, read by the game loop every second.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick_hz` - The new rate, above 0.
+    ///
+    /// # Returns
+    ///
+    /// Why the rate is refused, the previous one is kept.
+    ///
+    pub fn set_tick_hz(&mut self, tick_hz: f32) -> Result<(), String> {
+        if !tick_hz.is_finite() || tick_hz <= 0.0 {
+            return Err(format!("The tick rate must be above 0, not {}", tick_hz));
+        }
+        self.tick_hz = tick_hz;
+        Ok(())
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings::new()
+    }
 }
 
 /// A struct representing a server thread.
-pub(crate) struct ServerThread {
-    /// The address on which the server listens.
-    pub(crate) address: String,
-    /// The port on which the server listens.
+///
+/// The clones of a server share everything, so any clone can stop it. The fields are left to
+/// the server: a program embedding it only creates it, starts it and stops it.
+#[derive(Clone)]
+pub struct ServerThread {
+    /// The addresses on which the server listens: IPv4 or IPv6 addresses, or host names.
+    /// `"::"` also accepts IPv4 clients where the system allows dual-stack sockets.
+    pub(crate) addresses: Vec<String>,
+    /// The port on which the server starts listening, later changed through `ServerSettings::set_port`.
     pub(crate) port: u16,
-    /// A thread-safe, shared vector of styled messages.
-    pub(crate) messages: Arc<Mutex<Vec<StyledMessage>>>,
+    /// The port the TCP listeners currently accept clients on, the older listeners stop when it changes.
+    pub(crate) listening_port: Arc<AtomicU16>,
+    /// The messages of the server, see `MessageHub`.
+    pub(crate) messages: MessageHub,
     /// Thread-safe, shared server settings.
     pub(crate) settings: Arc<Mutex<ServerSettings>>,
-    /// Game logic shared with the simulation
-    pub(crate) game_logic: Arc<Mutex<GameLogic>>,
+    /// The way of the clients into the game logic, owned by the game loop while it runs:
+    /// commands to the loop and the snapshots it publishes
+    pub(crate) commands: GameCommands,
     /// Map client -> entity
-    pub(crate) client_entity_map: Arc<Mutex<HashMap<SocketAddr, u32>>>,
+    pub(crate) client_entity_map: Arc<Mutex<HashMap<ClientId, u32>>>,
+    /// Reconnection tokens of the client entities
+    pub(crate) sessions: Arc<Mutex<SessionRegistry>>,
+    /// Clients subscribed to the world state push
+    pub(crate) subscriptions: Arc<Mutex<SubscriptionRegistry>>,
+    /// Number of connected clients, shared with the server UI
+    pub(crate) connected_clients: Arc<AtomicUsize>,
+    /// Live connections and banned addresses
+    pub(crate) connections: Arc<Mutex<ConnectionRegistry>>,
+    /// When the server started, the origin of the time sent by `PING`
+    pub(crate) started_at: Instant,
+    /// Traffic statistics of the connections, shared with the server UI
+    pub(crate) client_stats: Arc<Mutex<ClientStatsRegistry>>,
+    /// The loop stepping the game logic, started by `start` and stopped by `stop`
+    pub(crate) game_loop: Arc<Mutex<Option<GameLoopHandle>>>,
+    /// Set by `stop`: the listeners, the workers and the other server threads end their loops
+    pub(crate) stopping: Arc<AtomicBool>,
+    /// The threads of the server, joined by `stop`
+    pub(crate) threads: Arc<Mutex<Vec<thread::JoinHandle<()>>>>,
+    /// Health metrics recorded by the game loop, shown by the server UI
+    pub(crate) metrics: ServerMetrics,
+    /// The log file of the messages, opened by `start` and flushed by `stop`
+    pub(crate) logger: Arc<Mutex<Option<FileLogger>>>,
+    /// Who the connected clients are, shown in the server UI
+    pub(crate) clients: Arc<Mutex<ClientRegistry>>,
+    /// The configuration of the TLS sessions of the TCP and WebSocket clients, loaded by `start` when TLS is enabled
+    pub(crate) tls: Arc<Mutex<Option<Arc<ServerConfig>>>>,
+    /// The players muted in the chat, shared with the server UI
+    pub(crate) chat_mutes: Arc<Mutex<ChatMutes>>,
 }
 
 impl ServerThread {
-    /// Creates a new server thread with the specified address, port, messages, and settings.
+    /// Creates a new server thread with the specified addresses, port, messages, settings and game logic.
     ///
     /// # Arguments
     ///
-    /// * `address` - The address on which the server listens.
+    /// * `addresses` - The addresses on which the server listens.
     /// * `port` - The port on which the server listens.
-    /// * `messages` - A thread-safe, shared vector of styled messages.
+    /// * `messages` - The messages of the server, see `MessageHub`.
     /// * `settings` - Thread-safe, shared server settings.
+    /// * `game_logic` - The game logic the clients play in, stepped by the server once started, reporting to `messages`.
     ///
     /// # Returns
     ///
     /// A new `ServerThread` instance.
     ///
-    pub fn new(address: String, port: u16, messages: Arc<Mutex<Vec<StyledMessage>>>, settings: Arc<Mutex<ServerSettings>>) -> Self {
+    pub fn new(addresses: Vec<String>, port: u16, messages: MessageHub, settings: Arc<Mutex<ServerSettings>>, game_logic: Arc<Mutex<GameLogic>>) -> Self {
+        // Le jeu écrit dans les messages du serveur ce qui n'est pas un événement de l'arène
+        game_logic.lock().unwrap().set_messages(messages.clone());
+        let connected_clients = Arc::new(AtomicUsize::new(0));
+        let metrics = ServerMetrics::new(Arc::clone(&connected_clients));
         ServerThread {
-            address,
+            addresses,
             port,
+            listening_port: Arc::new(AtomicU16::new(port)),
             messages,
             settings,
-            game_logic: Arc::new(Mutex::new(GameLogic::new())),
+            commands: GameCommands::new(game_logic),
             client_entity_map: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(Mutex::new(SessionRegistry::new())),
+            subscriptions: Arc::new(Mutex::new(SubscriptionRegistry::new())),
+            connected_clients: Arc::clone(&connected_clients),
+            connections: Arc::new(Mutex::new(ConnectionRegistry::new())),
+            started_at: Instant::now(),
+            client_stats: Arc::new(Mutex::new(ClientStatsRegistry::new(Arc::clone(&metrics.registry)))),
+            game_loop: Arc::new(Mutex::new(None)),
+            stopping: Arc::new(AtomicBool::new(false)),
+            threads: Arc::new(Mutex::new(Vec::new())),
+            metrics,
+            logger: Arc::new(Mutex::new(None)),
+            clients: Arc::new(Mutex::new(ClientRegistry::new())),
+            tls: Arc::new(Mutex::new(None)),
+            chat_mutes: Arc::new(Mutex::new(ChatMutes::new())),
+        }
+    }
+
+    /// Starts the server thread, listening for incoming connections on every address and spawning a new client handler for each connection.
+    ///
+    /// The game logic is stepped at `ServerSettings::tick_rate` on its own thread, even if no
+    /// address can be bound. An address that cannot be bound is logged and skipped, the others keep working.
+    pub fn start(&self) {
+        self.start_logger();
+        self.start_game_loop();
+        if let Err(e) = self.settings.lock().unwrap().set_port(self.port) {
+            add_message(&self.messages, format!("[ERROR] {}", e), MessageType::Error, MessageOrigin::Network);
         }
+        if !self.start_tls() {
+            return;
+        }
+        let listeners = self.bind_port(self.port);
+        if listeners.is_empty() {
+            add_message(
+                &self.messages,
+                "[ERROR] No address could be bound, no client can connect.".to_string(),
+                MessageType::Error,
+                MessageOrigin::Network,
+            );
+            return;
+        }
+        self.listening_port.store(self.port, Ordering::SeqCst);
+        let pool = ClientPool::new(self.settings.lock().unwrap().client_workers, self);
+        self.spawn_acceptors(listeners, &pool);
+        add_message(
+            &self.messages,
+            format!("[START] Listening on port: {}", self.port),
+            MessageType::Default,
+            MessageOrigin::Network,
+        );
+
+        self.start_port_watcher(&pool);
+        self.start_session_reaper();
+        self.start_state_broadcaster();
+        self.start_death_notifier();
+        self.start_reset_notifier();
+        self.start_wave_notifier();
+        self.start_web_socket_listener(&pool);
+        self.start_udp_channel();
+        self.start_admin_control();
+        self.start_http_endpoint();
     }
 
-    /// Starts the server thread, listening for incoming connections and spawning a new client handler for each connection.
-    pub(crate) fn start(&self) {
-        let listener = TcpListener::bind((self.address.to_string(), self.port)).expect("Could not bind to port");
+    /// Stops the server: tells every client that the server stops, closes their connections,
+    /// stops the game loop, writes the files of the game logic, waits for the server threads
+    /// and closes the log file.
+    ///
+    /// The threads still running after `SHUTDOWN_JOIN_TIMEOUT_MS` are logged and left behind.
+    ///
+    /// # Returns
+    ///
+    /// The number of clients notified.
+    ///
+    pub fn stop(&self) -> usize {
+        // Les clients reçoivent SHUTDOWN avant que leurs handlers ne s'arrêtent
+        let closed = self.connections.lock().unwrap().close_all();
+        self.stopping.store(true, Ordering::SeqCst);
+        // Le pas en cours et les commandes reçues se terminent avant que le thread ne s'arrête
+        if let Some(game_loop) = self.game_loop.lock().unwrap().take() {
+            game_loop.stop();
+        }
+        self.commands.update(GameLogic::shut_down);
 
+        let deadline = Instant::now() + Duration::from_millis(AppDefines::SHUTDOWN_JOIN_TIMEOUT_MS);
+        let mut threads = std::mem::take(&mut *self.threads.lock().unwrap());
+        while !threads.is_empty() && Instant::now() < deadline {
+            let (finished, running) = threads.into_iter().partition::<Vec<_>, _>(|handle| handle.is_finished());
+            for handle in finished {
+                let _ = handle.join();
+            }
+            threads = running;
+            thread::sleep(Duration::from_millis(AppDefines::CLIENT_POLL_MS));
+        }
+        if !threads.is_empty() {
+            add_message(
+                &self.messages,
+                format!("[WARNING] {} server thread(s) still running after {} ms, left behind.", threads.len(), AppDefines::SHUTDOWN_JOIN_TIMEOUT_MS),
+                MessageType::Warning,
+                MessageOrigin::Network,
+            );
+        }
         add_message(
             &self.messages,
-            format!("\n[START] Server address: {:?}", listener.local_addr().unwrap()),
+            format!("[STOP] Server stopped, {} client(s) notified.", closed),
             MessageType::Default,
+            MessageOrigin::Network,
         );
+        // Le dernier message est écrit avant que le fichier ne soit fermé
+        self.messages.flush();
+        if let Some(logger) = self.logger.lock().unwrap().take() {
+            logger.stop();
+        }
+        closed
+    }
+
+    /// Kicks a client from outside the player protocol, as the `KICK` admin command does: its entity
+    /// is removed, its reconnection session closed, and its handler told to close the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The client to kick.
+    /// * `by` - Who kicks the client, for the messages.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the client is no longer connected.
+    ///
+    pub(crate) fn kick_client(&self, client_id: &ClientId, by: &str) -> bool {
+        if !self.connections.lock().unwrap().shut_down(client_id) {
+            return false;
+        }
+        let entity_id = self.client_entity_map.lock().unwrap().get(client_id).copied();
+        if let Some(entity_id) = entity_id {
+            self.commands.ask(|reply| GameCommand::Remove { entity_id, reply });
+            self.sessions.lock().unwrap().close(entity_id);
+        }
         add_message(
             &self.messages,
-            format!("[START] Listening on port: {}", self.port),
+            format!("[ADMIN] Client {} kicked from {}.", client_id, by),
+            MessageType::Warning,
+            MessageOrigin::Admin,
+        );
+        true
+    }
+
+    /// Sends a chat message of the server UI to every connected client, as `MSG=SERVER=<text>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text of the message, at most `MESSAGE_LENGTH` characters.
+    ///
+    /// # Returns
+    ///
+    /// The number of clients the message was written to, or why the text is refused.
+    ///
+    pub fn broadcast_chat(&self, text: &str) -> Result<usize, String> {
+        let text = text.trim();
+        chat::check_text(text).map_err(str::to_string)?;
+        add_chat_message(&self.messages, AppDefines::CHAT_SERVER_SENDER, text);
+        Ok(chat::relay(&self.connections, &self.client_stats, AppDefines::CHAT_SERVER_SENDER, text, None))
+    }
+
+    /// Returns the players muted in the chat, shared by the client handlers and the server UI.
+    pub fn chat_mutes(&self) -> Arc<Mutex<ChatMutes>> {
+        Arc::clone(&self.chat_mutes)
+    }
+
+    /// Opens the log file of the settings, unless its path is empty.
+    ///
+    /// A log file that cannot be opened is reported, the server runs without it.
+    fn start_logger(&self) {
+        let (path, max_bytes, kept_files) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.log_path.clone(), settings.log_max_bytes, settings.log_kept_files)
+        };
+        if path.is_empty() {
+            return;
+        }
+        match FileLogger::start(Path::new(&path), max_bytes, kept_files, self.messages.clone()) {
+            Ok(logger) => {
+                *self.logger.lock().unwrap() = Some(logger);
+                add_message(&self.messages, format!("[START] Logging to {}", path), MessageType::Default, MessageOrigin::Network);
+            }
+            Err(e) => add_message(
+                &self.messages,
+                format!("[WARNING] Could not open the log file {}: {}", path, e),
+                MessageType::Warning,
+                MessageOrigin::Network,
+            ),
+        }
+    }
+
+    /// Loads the TLS certificate and key of the settings, when TLS is enabled.
+    ///
+    /// # Returns
+    ///
+    /// `false` if TLS is enabled but the certificate or the key cannot be used: the server
+    /// then accepts no client rather than falling back to plaintext.
+    ///
+    fn start_tls(&self) -> bool {
+        let (enabled, cert_path, key_path) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.enable_tls, settings.tls_cert_path.clone(), settings.tls_key_path.clone())
+        };
+        if !enabled {
+            return true;
+        }
+        match tls::load_config(Path::new(&cert_path), Path::new(&key_path)) {
+            Ok(config) => {
+                *self.tls.lock().unwrap() = Some(config);
+                add_message(&self.messages, format!("[START] TCP and WebSocket clients must speak TLS, certificate {}", cert_path), MessageType::Default, MessageOrigin::Network);
+                true
+            }
+            Err(e) => {
+                add_message(
+                    &self.messages,
+                    format!("[ERROR] TLS is enabled but {}, no client can connect.", e),
+                    MessageType::Error,
+                    MessageOrigin::Network,
+                );
+                false
+            }
+        }
+    }
+
+    /// Starts a server thread, joined by `stop`.
+    ///
+    /// # Arguments
+    ///
+    /// * `run` - The body of the thread, which must end once `stopping` is set.
+    ///
+    pub(crate) fn spawn_thread(&self, run: impl FnOnce() + Send + 'static) {
+        self.threads.lock().unwrap().push(thread::spawn(run));
+    }
+
+    /// Starts the thread stepping the game logic, at the rate of the settings.
+    ///
+    /// The loop owns the game logic from then on: the clients send their commands through `commands`.
+    fn start_game_loop(&self) {
+        let settings = Arc::clone(&self.settings);
+        let tick_rate = settings.lock().unwrap().tick_hz();
+        let event_logger = EventLogger::new(self.messages.clone());
+        let game_loop = GameLoop::spawn(self.commands.clone(), move || settings.lock().unwrap().tick_hz(), Some(self.metrics.clone()), Some(event_logger));
+        *self.game_loop.lock().unwrap() = Some(game_loop);
+        add_message(
+            &self.messages,
+            format!("[START] Game loop running at {} Hz.", tick_rate),
             MessageType::Default,
+            MessageOrigin::Network,
         );
+    }
 
-        for stream in listener.incoming() {
-            match stream {
-                Ok(stream) => {
-                    let peer_addr = stream.peer_addr().unwrap();
+    /// Binds a port on every address of the server.
+    ///
+    /// An address that cannot be bound is logged and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - The port to bind.
+    ///
+    /// # Returns
+    ///
+    /// The listeners of the addresses bound.
+    ///
+    fn bind_port(&self, port: u16) -> Vec<TcpListener> {
+        let mut listeners = Vec::new();
+        for address in self.resolve_addresses(port) {
+            match TcpListener::bind(address) {
+                Ok(listener) => {
+                    add_message(
+                        &self.messages,
+                        format!("\n[START] Server address: {:?}", listener.local_addr().unwrap_or(address)),
+                        MessageType::Default,
+                        MessageOrigin::Network,
+                    );
+                    listeners.push(listener);
+                }
+                Err(e) => {
+                    add_message(
+                        &self.messages,
+                        format!("[ERROR] Could not bind {}: {}", address, e),
+                        MessageType::Error,
+                        MessageOrigin::Network,
+                    );
+                }
+            }
+        }
+        listeners
+    }
 
+    /// Starts a thread per TCP listener accepting its clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `listeners` - The listeners of the port in `listening_port`.
+    /// * `pool` - The workers the accepted clients are handed to.
+    ///
+    fn spawn_acceptors(&self, listeners: Vec<TcpListener>, pool: &ClientPool) {
+        for listener in listeners {
+            let server = self.clone();
+            let pool = pool.clone();
+            self.spawn_thread(move || server.accept_clients(listener, false, pool));
+        }
+    }
+
+    /// Starts a thread moving the TCP listeners to the port of the settings when it changes.
+    ///
+    /// The new port is bound before the old listeners stop, the connected clients stay connected.
+    /// If no address can be bound on the new port, the server keeps its port and the setting is restored.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The workers the accepted clients are handed to.
+    ///
+    fn start_port_watcher(&self, pool: &ClientPool) {
+        let server = self.clone();
+        let pool = pool.clone();
+        self.spawn_thread(move || while !server.stopping.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_secs(1));
+            let port = server.settings.lock().unwrap().port();
+            let previous = server.listening_port.load(Ordering::SeqCst);
+            if port == previous {
+                continue;
+            }
+
+            let listeners = server.bind_port(port);
+            if listeners.is_empty() {
+                add_message(
+                    &server.messages,
+                    format!("[ERROR] Port {} could not be bound, still listening on port {}.", port, previous),
+                    MessageType::Error,
+                    MessageOrigin::Network,
+                );
+                // L'ancien port est valide : il a déjà été accepté par le setter
+                let _ = server.settings.lock().unwrap().set_port(previous);
+                continue;
+            }
+            // Les anciennes écoutes voient le nouveau port et s'arrêtent d'elles-mêmes
+            server.listening_port.store(port, Ordering::SeqCst);
+            server.spawn_acceptors(listeners, &pool);
+            add_message(
+                &server.messages,
+                format!("[START] Listening on port {} instead of {}, the connected clients stay connected.", port, previous),
+                MessageType::Default,
+                MessageOrigin::Network,
+            );
+        });
+    }
+
+    /// Resolves the addresses of the server, which may be IPv4 or IPv6 addresses or host names.
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - The port to bind on each address.
+    ///
+    /// # Returns
+    ///
+    /// The socket addresses to bind. Addresses that cannot be resolved are logged and skipped.
+    ///
+    fn resolve_addresses(&self, port: u16) -> Vec<SocketAddr> {
+        let mut resolved: Vec<SocketAddr> = Vec::new();
+        for address in &self.addresses {
+            match (address.as_str(), port).to_socket_addrs() {
+                Ok(addresses) => {
+                    // Un nom d'hôte peut se résoudre plusieurs fois vers la même adresse
+                    for address in addresses {
+                        if !resolved.contains(&address) {
+                            resolved.push(address);
+                        }
+                    }
+                }
+                Err(e) => {
                     add_message(
                         &self.messages,
-                        format!("[INFO] New client connected: {}", peer_addr),
-                        MessageType::Info,
+                        format!("[ERROR] Could not resolve {}: {}", address, e),
+                        MessageType::Error,
+                        MessageOrigin::Network,
                     );
+                }
+            }
+        }
+        resolved
+    }
 
-                    // Création de l'entité pour le client
-                    let entity_id = {
-                        let mut logic = self.game_logic.lock().unwrap();
-                        logic.add_entity("Player".to_string())
-                    };
+    /// Starts the thread accepting the admin tools on the admin control port, unless its port is 0.
+    ///
+    /// The port has no password: an address other than the loopback is logged as a warning.
+    fn start_admin_control(&self) {
+        let (address, port) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.admin_control_address.clone(), settings.admin_control_port)
+        };
+        if port == 0 {
+            return;
+        }
 
-                    // Sauvegarde de l'association client -> entity
-                    self.client_entity_map
-                        .lock()
-                        .unwrap()
-                        .insert(peer_addr, entity_id);
+        let address = match (address.as_str(), port).to_socket_addrs().map(|mut addresses| addresses.next()) {
+            Ok(Some(address)) => address,
+            _ => {
+                add_message(
+                    &self.messages,
+                    format!("[ERROR] Invalid admin control address: {}", address),
+                    MessageType::Error,
+                    MessageOrigin::Network,
+                );
+                return;
+            }
+        };
+        if !address.ip().is_loopback() {
+            add_message(
+                &self.messages,
+                format!("[WARNING] The admin control port {} is reachable from other hosts, without a password.", address),
+                MessageType::Warning,
+                MessageOrigin::Network,
+            );
+        }
+        match TcpListener::bind(address) {
+            Ok(listener) => {
+                add_message(
+                    &self.messages,
+                    format!("[START] Listening for admin tools on: {}", address),
+                    MessageType::Default,
+                    MessageOrigin::Network,
+                );
+                let admin_port = AdminPort::new(listener, self.clone());
+                self.spawn_thread(move || admin_port.run());
+            }
+            Err(e) => {
+                add_message(
+                    &self.messages,
+                    format!("[ERROR] Could not bind admin control address {}: {}", address, e),
+                    MessageType::Error,
+                    MessageOrigin::Network,
+                );
+            }
+        }
+    }
 
-                    let messages = Arc::clone(&self.messages);
-                    let settings = Arc::clone(&self.settings);
-                    let game_logic = Arc::clone(&self.game_logic);
-                    let client_map = Arc::clone(&self.client_entity_map);
+    /// Starts a thread per address answering the dashboards on the HTTP port, unless its port is 0.
+    fn start_http_endpoint(&self) {
+        let port = self.settings.lock().unwrap().http_port;
+        if port == 0 {
+            return;
+        }
 
-                    stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap(); // Set timeout
+        for address in self.resolve_addresses(port) {
+            match TcpListener::bind(address) {
+                Ok(listener) => {
+                    add_message(
+                        &self.messages,
+                        format!("[START] Serving the HTTP state endpoint on: {}", address),
+                        MessageType::Default,
+                        MessageOrigin::Network,
+                    );
+                    let endpoint = HttpEndpoint::new(listener, self.clone());
+                    self.spawn_thread(move || endpoint.run());
+                }
+                Err(e) => {
+                    add_message(
+                        &self.messages,
+                        format!("[ERROR] Could not bind HTTP address {}: {}", address, e),
+                        MessageType::Error,
+                        MessageOrigin::Network,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Starts a thread per address applying the actuator commands received as UDP datagrams, if enabled.
+    fn start_udp_channel(&self) {
+        if !self.settings.lock().unwrap().udp_actuators {
+            return;
+        }
+
+        for address in self.resolve_addresses(self.port) {
+            match UdpSocket::bind(address) {
+                Ok(socket) => {
+                    add_message(
+                        &self.messages,
+                        format!("[START] Listening for UDP actuator commands on: {}", address),
+                        MessageType::Default,
+                        MessageOrigin::Network,
+                    );
+                    let mut channel = UdpChannel::new(
+                        socket,
+                        Arc::clone(&self.settings),
+                        self.commands.clone(),
+                        Arc::clone(&self.sessions),
+                    );
+                    let stopping = Arc::clone(&self.stopping);
+                    self.spawn_thread(move || channel.run(&stopping));
+                }
+                Err(e) => {
+                    add_message(
+                        &self.messages,
+                        format!("[ERROR] Could not bind UDP address {}: {}", address, e),
+                        MessageType::Error,
+                        MessageOrigin::Network,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Starts a thread per address accepting the WebSocket clients, unless the WebSocket port is 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The workers the accepted clients are handed to.
+    ///
+    fn start_web_socket_listener(&self, pool: &ClientPool) {
+        let port = self.settings.lock().unwrap().web_socket_port;
+        if port == 0 {
+            return;
+        }
 
-                    thread::spawn(move || {
-                        ClientHandler::new(stream, messages, settings, game_logic, client_map).run();
-                    });
+        for address in self.resolve_addresses(port) {
+            match TcpListener::bind(address) {
+                Ok(listener) => {
+                    add_message(
+                        &self.messages,
+                        format!("[START] Listening for WebSocket clients on: {}", address),
+                        MessageType::Default,
+                        MessageOrigin::Network,
+                    );
+                    let server = self.clone();
+                    let pool = pool.clone();
+                    self.spawn_thread(move || server.accept_clients(listener, true, pool));
+                }
+                Err(e) => {
+                    add_message(
+                        &self.messages,
+                        format!("[ERROR] Could not bind WebSocket address {}: {}", address, e),
+                        MessageType::Error,
+                        MessageOrigin::Network,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Accepts the clients of a listener, handing a new client handler to the pool for each connection.
+    ///
+    /// A TCP listener stops once `listening_port` moved to another port, a WebSocket listener only
+    /// when the server stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The listener to accept the clients of.
+    /// * `web_socket` - Whether the clients connect through a WebSocket.
+    /// * `pool` - The workers the accepted clients are handed to.
+    ///
+    fn accept_clients(&self, listener: TcpListener, web_socket: bool, pool: ClientPool) {
+        let local_addr = listener.local_addr().ok();
+        let port = local_addr.map_or(0, |address| address.port());
+        // Une écoute bloquante ne verrait jamais le changement de port
+        if let Err(e) = listener.set_nonblocking(true) {
+            add_message(&self.messages, format!("[ERROR] Could not poll the listener: {}", e), MessageType::Error, MessageOrigin::Network);
+        }
+        let tls = self.tls.lock().unwrap().clone();
+        while !self.stopping.load(Ordering::SeqCst) {
+            if !web_socket && self.listening_port.load(Ordering::SeqCst) != port {
+                add_message(
+                    &self.messages,
+                    format!("[STOP] No longer accepting clients on {}.", local_addr.map_or(port.to_string(), |address| address.to_string())),
+                    MessageType::Default,
+                    MessageOrigin::Network,
+                );
+                return;
+            }
+            match listener.accept() {
+                // L'adresse du client est lue une fois, à l'acceptation : elle ne dépend plus de la connexion ensuite
+                Ok((stream, peer_addr)) => {
+                    // Selon le système, la connexion hérite du mode non bloquant de l'écoute
+                    if let Err(e) = stream.set_nonblocking(false) {
+                        add_message(&self.messages, format!("[ERROR] Connection failed: {}", e), MessageType::Error, MessageOrigin::Network);
+                        continue;
+                    }
+                    let client_id = if web_socket { ClientId::WebSocket(peer_addr) } else { ClientId::Tcp(peer_addr) };
+
+                    let admission = if self.connections.lock().unwrap().is_banned(&peer_addr.ip()) {
+                        add_message(
+                            &self.messages,
+                            format!("[ADMIN] Banned client {} refused.", client_id),
+                            MessageType::Warning,
+                            MessageOrigin::Admin,
+                        );
+                        Err(ProtocolError::Banned)
+                    } else {
+                        self.take_client_slot().ok_or_else(|| {
+                            add_message(
+                                &self.messages,
+                                format!("[WARNING] Server full, client {} refused.", client_id),
+                                MessageType::Warning,
+                                MessageOrigin::Network,
+                            );
+                            ProtocolError::ServerFull
+                        })
+                    };
+
+                    // Le handshake TLS est mené par un worker, rien n'est créé pour le client avant qu'il aboutisse
+                    if let Some(config) = &tls {
+                        match TlsHandshake::new(config, stream, client_id) {
+                            Ok(handshake) => pool.add_tls(handshake, admission),
+                            Err(e) => add_message(
+                                &self.messages,
+                                format!("[WARNING] TLS handshake with {} failed: {}", client_id, e),
+                                MessageType::Warning,
+                                MessageOrigin::Network,
+                            ),
+                        }
+                        continue;
+                    }
+                    let slot = match admission {
+                        Ok(slot) => slot,
+                        Err(error) => {
+                            ServerThread::refuse_client(Box::new(stream), web_socket, error);
+                            continue;
+                        }
+                    };
+
+                    add_message(
+                        &self.messages,
+                        format!("[INFO] New client connected: {}", client_id),
+                        MessageType::Info,
+                        MessageOrigin::Network,
+                    );
+
+                    // L'entité du client est créée par son ClientHandler, sauf s'il est spectateur
+                    match ClientHandler::new(Box::new(stream), client_id, self) {
+                        Ok(handler) => pool.add(handler, slot),
+                        Err(e) => add_message(
+                            &self.messages,
+                            format!("[WARNING] Client {} dropped, its connection cannot be handled: {}", client_id, e),
+                            MessageType::Warning,
+                            MessageOrigin::Network,
+                        ),
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(AppDefines::ACCEPT_POLL_MS));
                 }
                 Err(e) => {
                     add_message(
                         &self.messages,
                         format!("[ERROR] Connection failed: {}", e),
                         MessageType::Error,
+                        MessageOrigin::Network,
                     );
                 }
             }
         }
     }
-}
 
+    /// Takes a place among the connected clients, unless `max_clients` is reached.
+    ///
+    /// # Returns
+    ///
+    /// The place, released when dropped, or `None` if the server is full.
+    ///
+    fn take_client_slot(&self) -> Option<ClientSlot> {
+        let max_clients = self.settings.lock().unwrap().max_clients;
+        let previous = self.connected_clients.fetch_add(1, Ordering::SeqCst);
+        // Si le serveur est plein, la place est rendue quand `slot` est libéré
+        let slot = ClientSlot(Arc::clone(&self.connected_clients));
+        if max_clients > 0 && previous >= max_clients {
+            return None;
+        }
+        Some(slot)
+    }
+
+    /// Answers a refused client with a single error, then closes its connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The connection of the client.
+    /// * `web_socket` - Whether the client connects through a WebSocket.
+    /// * `error` - The reason the client is refused.
+    ///
+    pub(crate) fn refuse_client(mut stream: Box<dyn ClientStream>, web_socket: bool, error: ProtocolError) {
+        let response = if web_socket {
+            let status = match error {
+                ProtocolError::Banned => "403 Forbidden",
+                _ => "503 Service Unavailable",
+            };
+            web_socket::refuse(status, &error.to_wire())
+        } else {
+            format!("{}\n", error.to_wire())
+        };
+        // Une session TLS arrive non bloquante, le refus est écrit en mode bloquant avec un délai
+        let _ = stream.socket().set_nonblocking(false);
+        let _ = stream.socket().set_write_timeout(Some(Duration::from_millis(AppDefines::SOCKET_WRITE_TIMEOUT_MS)));
+        let _ = stream.write_all(response.as_bytes());
+        let _ = stream.shutdown();
+    }
+
+    /// Starts a thread removing the entities of dropped clients once their reconnection grace period expired,
+    /// the traffic statistics of disconnected clients once theirs expired, and the rows of the
+    /// disconnected clients after `CLIENT_LINGER_SECS`.
+    fn start_session_reaper(&self) {
+        let messages = self.messages.clone();
+        let settings = Arc::clone(&self.settings);
+        let commands = self.commands.clone();
+        let sessions = Arc::clone(&self.sessions);
+        let client_stats = Arc::clone(&self.client_stats);
+        let connections = Arc::clone(&self.connections);
+        let client_entity_map = Arc::clone(&self.client_entity_map);
+        let clients = Arc::clone(&self.clients);
+        let stopping = Arc::clone(&self.stopping);
+
+        self.spawn_thread(move || while !stopping.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_secs(1));
+            let (grace, stats_grace) = {
+                let settings = settings.lock().unwrap();
+                (Duration::from_secs(settings.reconnect_grace_secs), Duration::from_secs(settings.client_stats_grace_secs))
+            };
+            client_stats.lock().unwrap().expire(stats_grace);
+            clients.lock().unwrap().expire(Duration::from_secs(AppDefines::CLIENT_LINGER_SECS));
+            let expired = sessions.lock().unwrap().expire(grace);
+
+            for entity_id in expired {
+                commands.ask(|reply| GameCommand::Remove { entity_id, reply });
+                add_message(
+                    &messages,
+                    format!("[INFO] Reconnection grace period expired, entity {} removed.", entity_id),
+                    MessageType::Info,
+                    MessageOrigin::Network,
+                );
+            }
+
+            // Les entités détruites en jeu libèrent leur jeton ; le retrait ci-dessus a republié le monde
+            let alive_ids: Vec<u32> = commands.query(|logic| logic.entities.iter().map(|e| e.id).collect());
+            sessions.lock().unwrap().retain_alive(&alive_ids);
+
+            // Une connexion fermée sans passer par sa déconnexion ne garde pas son entité.
+            // Les clients connectés oublient eux-mêmes leur entité détruite, pour répondre DEAD.
+            let connections = connections.lock().unwrap();
+            client_entity_map.lock().unwrap().retain(|client_id, _| connections.is_live(client_id));
+        });
+    }
+
+    /// Starts a thread pushing the world state to the subscribed clients.
+    ///
+    /// A client still busy with a reply skips the frame, and a client whose socket
+    /// stays full past the write timeout loses its subscription. A delta subscriber
+    /// skipping a frame gets a keyframe next, to catch up in one frame.
+    ///
+    /// The full states and the delta frames are built from the world snapshot, so the
+    /// pushes never lock the game logic and show the world of the last step.
+    fn start_state_broadcaster(&self) {
+        let messages = self.messages.clone();
+        let world_slot = self.commands.world().clone();
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let client_stats = Arc::clone(&self.client_stats);
+        let stopping = Arc::clone(&self.stopping);
+
+        self.spawn_thread(move || while !stopping.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(5));
+            let due = subscriptions.lock().unwrap().take_due();
+            if due.is_empty() {
+                continue;
+            }
+
+            let (state, world, views) = {
+                let snapshot = world_slot.latest();
+                let state = due.iter().any(|(.., delta, _)| !delta).then(|| ClientHandler::world_state(AppDefines::PUSH_STATE, &snapshot, None));
+                let world = due.iter().any(|(.., delta, _)| *delta).then(|| WorldFrame::capture(&snapshot));
+                // Un observateur ne reçoit que le monde autour de sa caméra
+                let views: HashMap<ClientId, (Option<Reply>, Option<WorldFrame>)> = due.iter()
+                    .filter_map(|(client_id, _, _, delta, camera)| {
+                        let camera = snapshot.ghost_camera((*camera)?).filter(|camera| camera.view_radius > 0.0)?;
+                        let view = match &world {
+                            Some(world) if *delta => (None, Some(world.around(camera))),
+                            _ => (Some(ClientHandler::world_state(AppDefines::PUSH_STATE, &snapshot, Some(camera))), None),
+                        };
+                        Some((*client_id, view))
+                    })
+                    .collect();
+                (state, world, views)
+            };
+
+            for (client_id, writer, encoding, delta, _) in due {
+                let view = views.get(&client_id);
+                let state = view.map_or(state.as_ref(), |(state, _)| state.as_ref());
+                let world = view.map_or(world.as_ref(), |(_, world)| world.as_ref());
+                // Le delta part de la dernière trame écrite au client
+                let frame = match world {
+                    Some(world) if delta => subscriptions.lock().unwrap().delta_frame(&client_id, world),
+                    _ => None,
+                };
+                let delta_reply = frame.as_ref().map(DeltaFrame::to_reply);
+                let Some(reply) = delta_reply.as_ref().or(state.filter(|_| !delta)) else { continue };
+                let line = if encoding.json { reply.to_json().to_string() } else { reply.to_legacy() };
+                let Some(bytes) = ClientHandler::encode_line(&line, encoding) else { continue };
+                let mut writer = match writer.try_lock() {
+                    Ok(writer) => writer,
+                    Err(TryLockError::WouldBlock) => {
+                        if frame.is_some() {
+                            subscriptions.lock().unwrap().request_keyframe(&client_id);
+                        }
+                        continue;
+                    }
+                    Err(TryLockError::Poisoned(_)) => {
+                        subscriptions.lock().unwrap().unsubscribe(&client_id);
+                        continue;
+                    }
+                };
+                if writer.write_all(&bytes).and_then(|_| writer.flush()).is_err() {
+                    subscriptions.lock().unwrap().unsubscribe(&client_id);
+                    add_message(
+                        &messages,
+                        format!("[WARNING] Client {} too slow, state push stopped.", client_id),
+                        MessageType::Warning,
+                        MessageOrigin::Network,
+                    );
+                    continue;
+                }
+                if let Some(frame) = &frame {
+                    subscriptions.lock().unwrap().acknowledge(&client_id, frame);
+                }
+                client_stats.lock().unwrap().record_written(client_id, bytes.len());
+            }
+        });
+    }
+
+    /// Starts a thread telling the clients that their entity was destroyed, with a `DEAD` line.
+    ///
+    /// The handler of a client forgets its destroyed entity at its next command, so the owners
+    /// of the entities are also remembered from the previous read of the client map.
+    fn start_death_notifier(&self) {
+        let world_slot = self.commands.world().clone();
+        let client_entity_map = Arc::clone(&self.client_entity_map);
+        let connections = Arc::clone(&self.connections);
+        let client_stats = Arc::clone(&self.client_stats);
+        let stopping = Arc::clone(&self.stopping);
+
+        self.spawn_thread(move || {
+            let mut notices = DeathNotices::new(&world_slot.latest());
+            let mut owners: HashMap<u32, ClientId> = HashMap::new();
+            while !stopping.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(5));
+                let deaths = notices.read(&world_slot.latest());
+                let current: HashMap<u32, ClientId> = client_entity_map
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(client_id, &entity_id)| (entity_id, *client_id))
+                    .collect();
+                for death in deaths {
+                    let Some(&client_id) = current.get(&death.victim).or(owners.get(&death.victim)) else { continue };
+                    let Some((writer, encoding)) = connections.lock().unwrap().writer(&client_id) else { continue };
+
+                    let killer = death.killer.unwrap_or_else(|| AppDefines::EMPTY_REPLY.to_string());
+                    let bearing = match death.bearing {
+                        Some(bearing) => Value::Float(bearing.to_degrees() as f64, 1),
+                        None => Value::Text(AppDefines::EMPTY_REPLY.to_string()),
+                    };
+                    let notice = Reply::Query { cmd: AppDefines::PUSH_DEAD, fields: vec![("killer", Value::Text(killer)), ("bearing", bearing)] };
+                    let line = if encoding.json { notice.to_json().to_string() } else { notice.to_legacy() };
+                    let Some(bytes) = ClientHandler::encode_line(&line, encoding) else { continue };
+                    // Contrairement à l'état poussé, la mort n'est dite qu'une fois : on attend la réponse en cours
+                    let Ok(mut writer) = writer.lock() else { continue };
+                    if writer.write_all(&bytes).and_then(|_| writer.flush()).is_ok() {
+                        client_stats.lock().unwrap().record_written(client_id, bytes.len());
+                    }
+                }
+                owners = current;
+            }
+        });
+    }
+
+    /// Starts a thread telling the subscribed clients that the simulation was reset, with a `RESET` line.
+    fn start_reset_notifier(&self) {
+        let world_slot = self.commands.world().clone();
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let client_stats = Arc::clone(&self.client_stats);
+        let stopping = Arc::clone(&self.stopping);
+
+        self.spawn_thread(move || {
+            let mut resets = world_slot.latest().reset_count;
+            while !stopping.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(5));
+                let reset_count = world_slot.latest().reset_count;
+                if reset_count == resets {
+                    continue;
+                }
+                resets = reset_count;
+
+                let notice = Reply::Query { cmd: AppDefines::PUSH_RESET, fields: Vec::new() };
+                let subscribers = subscriptions.lock().unwrap().all();
+                for (client_id, writer, encoding) in subscribers {
+                    let line = if encoding.json { notice.to_json().to_string() } else { notice.to_legacy() };
+                    let Some(bytes) = ClientHandler::encode_line(&line, encoding) else { continue };
+                    // Comme la mort, la remise à zéro n'est dite qu'une fois : on attend la réponse en cours
+                    let Ok(mut writer) = writer.lock() else { continue };
+                    if writer.write_all(&bytes).and_then(|_| writer.flush()).is_ok() {
+                        client_stats.lock().unwrap().record_written(client_id, bytes.len());
+                    }
+                }
+            }
+        });
+    }
+
+    /// Starts a thread telling every client that a horde wave started, was cleared or was lost, with a `WAVE` line.
+    fn start_wave_notifier(&self) {
+        let world_slot = self.commands.world().clone();
+        let connections = Arc::clone(&self.connections);
+        let client_stats = Arc::clone(&self.client_stats);
+        let stopping = Arc::clone(&self.stopping);
+
+        self.spawn_thread(move || {
+            let mut cursor = world_slot.latest().events.cursor();
+            while !stopping.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(5));
+                let notices: Vec<Reply> = {
+                    let world = world_slot.latest();
+                    let notices = world.events.since(cursor).filter_map(|queued| match &queued.event {
+                        GameEvent::Wave { wave, state, text } => Some(Reply::Query {
+                            cmd: AppDefines::PUSH_WAVE,
+                            fields: vec![
+                                ("wave", Value::Int(*wave as i64)),
+                                ("state", Value::Text(state.code().to_string())),
+                                ("text", Value::Text(text.clone())),
+                            ],
+                        }),
+                        _ => None,
+                    }).collect();
+                    cursor = world.events.cursor();
+                    notices
+                };
+                if notices.is_empty() {
+                    continue;
+                }
+
+                let writers = connections.lock().unwrap().writers();
+                for notice in &notices {
+                    for (client_id, writer, encoding) in &writers {
+                        let line = if encoding.json { notice.to_json().to_string() } else { notice.to_legacy() };
+                        let Some(bytes) = ClientHandler::encode_line(&line, *encoding) else { continue };
+                        // Comme la mort, l'annonce n'est dite qu'une fois : on attend la réponse en cours
+                        let Ok(mut writer) = writer.lock() else { continue };
+                        if writer.write_all(&bytes).and_then(|_| writer.flush()).is_ok() {
+                            client_stats.lock().unwrap().record_written(*client_id, bytes.len());
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::server::client_handler::protocol_error::ProtocolError;
+
+/// A struct representing the session of a client entity.
+pub(crate) struct Session {
+    /// The id of the entity owned by the session.
+    pub entity_id: u32,
+    /// When the client disconnected, `None` while it is connected.
+    pub disconnected_at: Option<Instant>,
+}
+
+/// A struct keeping the reconnection tokens of the client entities.
+///
+/// A client whose connection drops can reclaim its entity with its token,
+/// as long as the grace period has not expired.
+pub(crate) struct SessionRegistry {
+    sessions: HashMap<String, Session>,
+    /// The tokens of the sessions closed while disconnected, to tell expired tokens from unknown ones.
+    expired_tokens: HashSet<String>,
+}
+
+impl SessionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        SessionRegistry { sessions: HashMap::new(), expired_tokens: HashSet::new() }
+    }
+
+    /// Opens the session of a newly spawned entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The id of the entity.
+    ///
+    /// # Returns
+    ///
+    /// The reconnection token of the session.
+    ///
+    pub fn open(&mut self, entity_id: u32) -> String {
+        let mut rng = rand::rng();
+        // Un nouveau jeton est tiré tant qu'il entre en collision avec un jeton existant
+        let token = loop {
+            let token = format!("{:016x}", rng.random::<u64>());
+            if !self.sessions.contains_key(&token) && !self.expired_tokens.contains(&token) {
+                break token;
+            }
+        };
+        self.sessions.insert(token.clone(), Session { entity_id, disconnected_at: None });
+        token
+    }
+
+    /// Returns the reconnection token of an entity.
+    pub fn token_of(&self, entity_id: u32) -> Option<&str> {
+        self.sessions
+            .iter()
+            .find(|(_, session)| session.entity_id == entity_id)
+            .map(|(token, _)| token.as_str())
+    }
+
+    /// Returns the entity of a session, whether its client is connected or not.
+    pub fn entity_of(&self, token: &str) -> Option<u32> {
+        self.sessions.get(token).map(|session| session.entity_id)
+    }
+
+    /// Marks the session of an entity as disconnected, starting its grace period.
+    pub fn detach(&mut self, entity_id: u32) {
+        for session in self.sessions.values_mut().filter(|s| s.entity_id == entity_id) {
+            session.disconnected_at = Some(Instant::now());
+        }
+    }
+
+    /// Closes the session of an entity. Its token can no longer be used.
+    pub fn close(&mut self, entity_id: u32) {
+        self.sessions.retain(|_, session| session.entity_id != entity_id);
+    }
+
+    /// Binds a new connection to the entity of a disconnected session.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The reconnection token sent by the client.
+    /// * `grace` - How long a disconnected session can be reclaimed.
+    ///
+    /// # Returns
+    ///
+    /// The id of the reclaimed entity, or the reason the token was refused.
+    ///
+    pub fn reclaim(&mut self, token: &str, grace: Duration) -> Result<u32, ProtocolError> {
+        let Some(session) = self.sessions.get_mut(token) else {
+            return if self.expired_tokens.contains(token) {
+                Err(ProtocolError::TokenExpired)
+            } else {
+                Err(ProtocolError::UnknownToken)
+            };
+        };
+        match session.disconnected_at {
+            None => Err(ProtocolError::TokenInUse),
+            Some(at) if at.elapsed() > grace => Err(ProtocolError::TokenExpired),
+            Some(_) => {
+                session.disconnected_at = None;
+                Ok(session.entity_id)
+            }
+        }
+    }
+
+    /// Closes the sessions whose grace period expired.
+    ///
+    /// # Returns
+    ///
+    /// The ids of the entities to remove from the game.
+    ///
+    pub fn expire(&mut self, grace: Duration) -> Vec<u32> {
+        let mut expired = Vec::new();
+        let expired_tokens = &mut self.expired_tokens;
+        self.sessions.retain(|token, session| match session.disconnected_at {
+            Some(at) if at.elapsed() > grace => {
+                expired.push(session.entity_id);
+                expired_tokens.insert(token.clone());
+                false
+            }
+            _ => true,
+        });
+        expired
+    }
+
+    /// Closes the sessions whose entity no longer exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `alive_ids` - The ids of the entities in the game.
+    ///
+    pub fn retain_alive(&mut self, alive_ids: &[u32]) {
+        let expired_tokens = &mut self.expired_tokens;
+        self.sessions.retain(|token, session| {
+            let alive = alive_ids.contains(&session.entity_id);
+            if !alive && session.disconnected_at.is_some() {
+                expired_tokens.insert(token.clone());
+            }
+            alive
+        });
+    }
+}
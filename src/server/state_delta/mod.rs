@@ -0,0 +1,376 @@
+use std::collections::BTreeMap;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::control_point::Side;
+use crate::game_logic::ghost_camera::GhostCamera;
+use crate::game_logic::world_snapshot::WorldSnapshot;
+use crate::server::client_handler::reply::{Fields, Reply, Value};
+
+/// The kind of a record upserting an entity. Record: `E,id,x,y,orientation,health,score,name`.
+const ENTITY_RECORD: &str = "E";
+/// The kind of a record upserting a bullet. Record: `B,id,x,y`.
+const BULLET_RECORD: &str = "B";
+/// The kind of a record removing an entity. Record: `-E,id`.
+const REMOVED_ENTITY_RECORD: &str = "-E";
+/// The kind of a record removing a bullet. Record: `-B,id`.
+const REMOVED_BULLET_RECORD: &str = "-B";
+/// The kind of a record upserting a control point. Record: `P,index,x,y,radius,owner,progress,name`,
+/// the owner as in the `POINTS` reply.
+const CONTROL_POINT_RECORD: &str = "P";
+/// The kind of a record removing a control point. Record: `-P,index`.
+const REMOVED_CONTROL_POINT_RECORD: &str = "-P";
+
+/// A struct representing the state of an entity in a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityState {
+    pub x: f32,
+    pub y: f32,
+    pub orientation: f64,
+    pub health: i32,
+    pub score: i32,
+    pub name: String,
+}
+
+/// A struct representing the state of a bullet in a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BulletState {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A struct representing the state of a control point in a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlPointState {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub owner: Option<Side>,
+    pub progress: f32,
+    pub name: String,
+}
+
+/// A struct representing the world as seen by a subscriber: the entities by id, the bullets by id
+/// and, in the control point mode, the control points by index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldFrame {
+    pub entities: BTreeMap<u32, EntityState>,
+    pub bullets: BTreeMap<u64, BulletState>,
+    pub control_points: BTreeMap<u32, ControlPointState>,
+}
+
+impl WorldFrame {
+    /// Captures the world of a snapshot published by the game logic, see `GameLogic::publish_world`.
+    ///
+    /// The frame is taken from the snapshot rather than from the game logic, so that the state
+    /// pushes never lock it: they show the world of the last step.
+    ///
+    pub fn capture(snapshot: &WorldSnapshot) -> Self {
+        let entities = snapshot.entities.iter().map(|entity| {
+            let state = EntityState {
+                x: entity.x,
+                y: entity.y,
+                orientation: entity.orientation,
+                health: entity.health,
+                score: entity.score,
+                name: entity.name.clone(),
+            };
+            (entity.id, state)
+        }).collect();
+        let bullets = snapshot.bullets.iter().map(|bullet| (bullet.id, BulletState { x: bullet.x, y: bullet.y })).collect();
+        let control_points = snapshot.control_points.iter().enumerate().map(|(index, point)| {
+            let state = ControlPointState {
+                x: point.x,
+                y: point.y,
+                radius: point.radius,
+                owner: point.owner,
+                progress: point.progress,
+                name: point.name.clone(),
+            };
+            (index as u32, state)
+        }).collect();
+        WorldFrame { entities, bullets, control_points }
+    }
+
+    /// Returns the part of the world a ghost camera sees: its entities and bullets, and every control point.
+    ///
+    /// An entity or a bullet leaving the view is removed from the frames of the subscriber.
+    ///
+    pub fn around(&self, camera: &GhostCamera) -> Self {
+        WorldFrame {
+            entities: self.entities.iter()
+                .filter(|(_, entity)| camera.sees(entity.x, entity.y))
+                .map(|(id, entity)| (*id, entity.clone()))
+                .collect(),
+            bullets: self.bullets.iter()
+                .filter(|(_, bullet)| camera.sees(bullet.x, bullet.y))
+                .map(|(id, bullet)| (*id, *bullet))
+                .collect(),
+            control_points: self.control_points.clone(),
+        }
+    }
+
+    /// Applies a frame of the delta stream: a keyframe replaces the world, a delta updates it.
+    ///
+    /// This is how a subscriber rebuilds the world, and how the encoder keeps what a subscriber has.
+    ///
+    pub fn apply(&mut self, frame: &DeltaFrame) {
+        if frame.keyframe {
+            self.entities.clear();
+            self.bullets.clear();
+            self.control_points.clear();
+        }
+        for id in &frame.removed_entities {
+            self.entities.remove(id);
+        }
+        for id in &frame.removed_bullets {
+            self.bullets.remove(id);
+        }
+        for index in &frame.removed_control_points {
+            self.control_points.remove(index);
+        }
+        self.entities.extend(frame.entities.iter().cloned());
+        self.bullets.extend(frame.bullets.iter().copied());
+        self.control_points.extend(frame.control_points.iter().cloned());
+    }
+}
+
+/// A struct representing one frame of the delta stream.
+///
+/// Legacy: `KEYFRAME=<count>=<record>…` or `DELTA=<count>=<record>…`, each record starting
+/// with its kind: `E`, `B` and `P` for an entity, a bullet or a control point added or changed,
+/// `-E`, `-B` and `-P` for one removed. A keyframe holds the whole world.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeltaFrame {
+    /// Whether the frame replaces the world instead of updating it.
+    pub keyframe: bool,
+    /// The entities added or changed.
+    pub entities: Vec<(u32, EntityState)>,
+    /// The bullets added or moved.
+    pub bullets: Vec<(u64, BulletState)>,
+    /// The ids of the entities removed.
+    pub removed_entities: Vec<u32>,
+    /// The ids of the bullets removed.
+    pub removed_bullets: Vec<u64>,
+    /// The control points added or changed, by index.
+    pub control_points: Vec<(u32, ControlPointState)>,
+    /// The indices of the control points removed.
+    pub removed_control_points: Vec<u32>,
+}
+
+impl DeltaFrame {
+    /// Returns the number of records of the frame.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+            + self.bullets.len()
+            + self.control_points.len()
+            + self.removed_entities.len()
+            + self.removed_bullets.len()
+            + self.removed_control_points.len()
+    }
+
+    /// Returns whether the frame changes nothing. An empty keyframe still clears the world.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Builds the push of the frame.
+    pub(crate) fn to_reply(&self) -> Reply {
+        let kind = |kind: &str| ("kind", Value::Text(kind.to_string()));
+        let mut records: Vec<Fields> = Vec::with_capacity(self.len());
+        records.extend(self.entities.iter().map(|(id, entity)| {
+            vec![
+                kind(ENTITY_RECORD),
+                ("id", Value::Int(*id as i64)),
+                ("x", Value::Float(entity.x as f64, 1)),
+                ("y", Value::Float(entity.y as f64, 1)),
+                ("orientation", Value::Float(entity.orientation, 3)),
+                ("health", Value::Int(entity.health as i64)),
+                ("score", Value::Int(entity.score as i64)),
+                ("name", Value::Text(entity.name.clone())),
+            ]
+        }));
+        records.extend(self.bullets.iter().map(|(id, bullet)| {
+            vec![
+                kind(BULLET_RECORD),
+                ("id", Value::Int(*id as i64)),
+                ("x", Value::Float(bullet.x as f64, 1)),
+                ("y", Value::Float(bullet.y as f64, 1)),
+            ]
+        }));
+        records.extend(self.control_points.iter().map(|(index, point)| {
+            let owner = point.owner.map_or_else(|| AppDefines::EMPTY_REPLY.to_string(), |owner| owner.code());
+            vec![
+                kind(CONTROL_POINT_RECORD),
+                ("index", Value::Int(*index as i64)),
+                ("x", Value::Float(point.x as f64, 1)),
+                ("y", Value::Float(point.y as f64, 1)),
+                ("radius", Value::Float(point.radius as f64, 1)),
+                ("owner", Value::Text(owner)),
+                ("progress", Value::Float(point.progress as f64, 3)),
+                ("name", Value::Text(point.name.clone())),
+            ]
+        }));
+        records.extend(self.removed_entities.iter().map(|id| vec![kind(REMOVED_ENTITY_RECORD), ("id", Value::Int(*id as i64))]));
+        records.extend(self.removed_bullets.iter().map(|id| vec![kind(REMOVED_BULLET_RECORD), ("id", Value::Int(*id as i64))]));
+        records.extend(self.removed_control_points.iter().map(|index| vec![kind(REMOVED_CONTROL_POINT_RECORD), ("index", Value::Int(*index as i64))]));
+        let cmd = if self.keyframe { AppDefines::PUSH_KEYFRAME } else { AppDefines::PUSH_DELTA };
+        Reply::Records { cmd, records }
+    }
+
+    /// Converts the frame to the legacy text format.
+    pub fn to_legacy(&self) -> String {
+        self.to_reply().to_legacy()
+    }
+
+    /// Reads a frame in the legacy text format.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - A `KEYFRAME` or `DELTA` line.
+    ///
+    /// # Returns
+    ///
+    /// The frame, or `None` if the line is not a well-formed frame.
+    ///
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.trim_end().split(AppDefines::ARGUMENT_SEP);
+        let keyframe = match parts.next()? {
+            AppDefines::PUSH_KEYFRAME => true,
+            AppDefines::PUSH_DELTA => false,
+            _ => return None,
+        };
+        let count: usize = parts.next()?.parse().ok()?;
+        let mut frame = DeltaFrame { keyframe, ..DeltaFrame::default() };
+        for record in parts.by_ref().take(count) {
+            // Le nom vient en dernier et peut contenir le séparateur des champs
+            let fields: Vec<&str> = record.splitn(8, AppDefines::FIELD_SEP).collect();
+            match fields.as_slice() {
+                [ENTITY_RECORD, id, x, y, orientation, health, score, name] => frame.entities.push((id.parse().ok()?, EntityState {
+                    x: x.parse().ok()?,
+                    y: y.parse().ok()?,
+                    orientation: orientation.parse().ok()?,
+                    health: health.parse().ok()?,
+                    score: score.parse().ok()?,
+                    name: name.to_string(),
+                })),
+                [BULLET_RECORD, id, x, y] => frame.bullets.push((id.parse().ok()?, BulletState { x: x.parse().ok()?, y: y.parse().ok()? })),
+                [CONTROL_POINT_RECORD, index, x, y, radius, owner, progress, name] => frame.control_points.push((index.parse().ok()?, ControlPointState {
+                    x: x.parse().ok()?,
+                    y: y.parse().ok()?,
+                    radius: radius.parse().ok()?,
+                    owner: match *owner {
+                        AppDefines::EMPTY_REPLY => None,
+                        code => Some(Side::parse(code)?),
+                    },
+                    progress: progress.parse().ok()?,
+                    name: name.to_string(),
+                })),
+                [REMOVED_ENTITY_RECORD, id] => frame.removed_entities.push(id.parse().ok()?),
+                [REMOVED_BULLET_RECORD, id] => frame.removed_bullets.push(id.parse().ok()?),
+                [REMOVED_CONTROL_POINT_RECORD, index] => frame.removed_control_points.push(index.parse().ok()?),
+                _ => return None,
+            }
+        }
+        (frame.len() == count && parts.next().is_none()).then_some(frame)
+    }
+}
+
+/// A struct encoding the world pushed to one subscriber as deltas from what it last received.
+///
+/// The encoder only moves its baseline forward when a frame is acknowledged as written, so a frame
+/// that is dropped does not leave the subscriber behind: the next delta covers both changes.
+pub struct DeltaEncoder {
+    /// The world the subscriber has, `None` before the first keyframe.
+    acknowledged: Option<WorldFrame>,
+    /// The number of deltas written since the last keyframe.
+    since_keyframe: u32,
+    /// The number of frames between two keyframes.
+    keyframe_interval: u32,
+    /// Whether the next frame must be a keyframe.
+    keyframe_requested: bool,
+}
+
+impl DeltaEncoder {
+    /// Creates an encoder whose first frame is a keyframe.
+    ///
+    /// # Arguments
+    ///
+    /// * `keyframe_interval` - The number of frames between two keyframes, every frame if 0 or 1.
+    ///
+    pub fn new(keyframe_interval: u32) -> Self {
+        DeltaEncoder { acknowledged: None, since_keyframe: 0, keyframe_interval, keyframe_requested: false }
+    }
+
+    /// Makes the next frame a keyframe.
+    pub fn request_keyframe(&mut self) {
+        self.keyframe_requested = true;
+    }
+
+    /// Encodes the world as a frame, without moving the baseline.
+    ///
+    /// An entity is sent when it moved or turned by more than `DELTA_POSITION_EPSILON` or
+    /// `DELTA_ORIENTATION_EPSILON`, or when its health, score or name changed. A bullet is sent
+    /// when it moved by more than `DELTA_POSITION_EPSILON`. A control point is sent when anything of it changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - The current world.
+    ///
+    /// # Returns
+    ///
+    /// A keyframe if one is due or requested, a delta from the acknowledged world otherwise.
+    ///
+    pub fn encode(&self, world: &WorldFrame) -> DeltaFrame {
+        let baseline = match &self.acknowledged {
+            Some(baseline) if !self.keyframe_requested && self.since_keyframe + 1 < self.keyframe_interval => baseline,
+            _ => {
+                return DeltaFrame {
+                    keyframe: true,
+                    entities: world.entities.iter().map(|(id, entity)| (*id, entity.clone())).collect(),
+                    bullets: world.bullets.iter().map(|(id, bullet)| (*id, *bullet)).collect(),
+                    control_points: world.control_points.iter().map(|(index, point)| (*index, point.clone())).collect(),
+                    ..DeltaFrame::default()
+                };
+            }
+        };
+
+        let moved = |x: f32, y: f32, old_x: f32, old_y: f32| (x - old_x).hypot(y - old_y) > AppDefines::DELTA_POSITION_EPSILON;
+        let entities = world.entities.iter().filter(|(id, entity)| match baseline.entities.get(id) {
+            Some(old) => {
+                moved(entity.x, entity.y, old.x, old.y)
+                    || (entity.orientation - old.orientation).abs() > AppDefines::DELTA_ORIENTATION_EPSILON
+                    || entity.health != old.health
+                    || entity.score != old.score
+                    || entity.name != old.name
+            }
+            None => true,
+        });
+        let bullets = world.bullets.iter().filter(|(id, bullet)| match baseline.bullets.get(id) {
+            Some(old) => moved(bullet.x, bullet.y, old.x, old.y),
+            None => true,
+        });
+        DeltaFrame {
+            keyframe: false,
+            entities: entities.map(|(id, entity)| (*id, entity.clone())).collect(),
+            bullets: bullets.map(|(id, bullet)| (*id, *bullet)).collect(),
+            removed_entities: baseline.entities.keys().filter(|id| !world.entities.contains_key(id)).copied().collect(),
+            removed_bullets: baseline.bullets.keys().filter(|id| !world.bullets.contains_key(id)).copied().collect(),
+            control_points: world.control_points.iter()
+                .filter(|(index, point)| baseline.control_points.get(index) != Some(point))
+                .map(|(index, point)| (*index, point.clone()))
+                .collect(),
+            removed_control_points: baseline.control_points.keys().filter(|index| !world.control_points.contains_key(index)).copied().collect(),
+        }
+    }
+
+    /// Moves the baseline to a frame written to the subscriber.
+    pub fn acknowledge(&mut self, frame: &DeltaFrame) {
+        if frame.keyframe {
+            self.since_keyframe = 0;
+            self.keyframe_requested = false;
+        } else {
+            self.since_keyframe += 1;
+        }
+        self.acknowledged.get_or_insert_with(WorldFrame::default).apply(frame);
+    }
+}
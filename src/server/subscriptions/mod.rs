@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::app_defines::AppDefines;
+use crate::server::client_id::ClientId;
+use crate::server::client_stream::SocketWriter;
+use crate::server::state_delta::{DeltaEncoder, DeltaFrame, WorldFrame};
+
+/// A writer shared between a client handler and the state broadcaster.
+pub(crate) type SharedWriter = Arc<Mutex<BufWriter<SocketWriter>>>;
+
+/// A struct representing how the pushes to a client are encoded.
+#[derive(Clone, Copy)]
+pub(crate) struct Encoding {
+    /// Whether the client reads JSON instead of the legacy text format.
+    pub json: bool,
+    /// Whether the client reads binary frames instead of lines.
+    pub binary: bool,
+    /// Whether the client reads WebSocket text frames instead of lines.
+    pub web_socket: bool,
+}
+
+/// A struct representing a client receiving the world state at a fixed rate.
+struct Subscriber {
+    /// The writer of the client connection.
+    writer: SharedWriter,
+    /// The delay between two pushes.
+    interval: Duration,
+    /// When the state was last pushed.
+    last_push: Instant,
+    /// How the pushes are encoded.
+    encoding: Encoding,
+    /// The encoder of the delta frames, `None` for `STATE` pushes.
+    delta: Option<DeltaEncoder>,
+}
+
+/// A struct keeping the clients subscribed to the world state push.
+pub(crate) struct SubscriptionRegistry {
+    subscribers: HashMap<ClientId, Subscriber>,
+    /// The ghost camera of each observing client, kept across its subscriptions.
+    cameras: HashMap<ClientId, u32>,
+}
+
+impl SubscriptionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        SubscriptionRegistry { subscribers: HashMap::new(), cameras: HashMap::new() }
+    }
+
+    /// Subscribes a client, or changes the rate of an existing subscription.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    /// * `writer` - The writer of the client connection.
+    /// * `hz` - The requested number of pushes per second.
+    /// * `encoding` - How the pushes are encoded.
+    /// * `delta` - Whether the client receives delta frames, starting with a keyframe.
+    ///
+    /// # Returns
+    ///
+    /// The rate actually used, clamped between `SUBSCRIBE_MIN_HZ` and `SUBSCRIBE_MAX_HZ`.
+    ///
+    pub fn subscribe(&mut self, client_id: ClientId, writer: SharedWriter, hz: u32, encoding: Encoding, delta: bool) -> u32 {
+        let hz = hz.clamp(AppDefines::SUBSCRIBE_MIN_HZ, AppDefines::SUBSCRIBE_MAX_HZ);
+        let interval = Duration::from_secs_f64(1.0 / hz as f64);
+        let delta = delta.then(|| DeltaEncoder::new(AppDefines::DELTA_KEYFRAME_INTERVAL));
+        self.subscribers.insert(client_id, Subscriber { writer, interval, last_push: Instant::now(), encoding, delta });
+        hz
+    }
+
+    /// Removes the subscription of a client.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the client was subscribed.
+    ///
+    pub fn unsubscribe(&mut self, client_id: &ClientId) -> bool {
+        self.subscribers.remove(client_id).is_some()
+    }
+
+    /// Centers the pushes to a client on a ghost camera, or on nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The identity of the client.
+    /// * `camera` - The id of its camera, `None` once it stops observing.
+    ///
+    pub fn set_camera(&mut self, client_id: ClientId, camera: Option<u32>) {
+        match camera {
+            Some(camera) => self.cameras.insert(client_id, camera),
+            None => self.cameras.remove(&client_id),
+        };
+    }
+
+    /// Changes the encoding of the pushes to a client, if it is subscribed.
+    pub fn set_encoding(&mut self, client_id: &ClientId, encoding: Encoding) {
+        if let Some(subscriber) = self.subscribers.get_mut(client_id) {
+            subscriber.encoding = encoding;
+        }
+    }
+
+    /// Makes the next frame pushed to a delta subscriber a keyframe.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the client receives delta frames.
+    ///
+    pub fn request_keyframe(&mut self, client_id: &ClientId) -> bool {
+        match self.subscribers.get_mut(client_id).and_then(|subscriber| subscriber.delta.as_mut()) {
+            Some(encoder) => {
+                encoder.request_keyframe();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Encodes the world for a delta subscriber, from the last frame written to it.
+    ///
+    /// # Returns
+    ///
+    /// The frame, or `None` if the client does not receive delta frames.
+    ///
+    pub fn delta_frame(&self, client_id: &ClientId, world: &WorldFrame) -> Option<DeltaFrame> {
+        self.subscribers.get(client_id)?.delta.as_ref().map(|encoder| encoder.encode(world))
+    }
+
+    /// Records that a delta frame was written to a subscriber, the base of its next delta.
+    pub fn acknowledge(&mut self, client_id: &ClientId, frame: &DeltaFrame) {
+        if let Some(encoder) = self.subscribers.get_mut(client_id).and_then(|subscriber| subscriber.delta.as_mut()) {
+            encoder.acknowledge(frame);
+        }
+    }
+
+    /// Returns every subscriber, with its encoding, without marking it as pushed.
+    pub fn all(&self) -> Vec<(ClientId, SharedWriter, Encoding)> {
+        self.subscribers
+            .iter()
+            .map(|(client_id, subscriber)| (*client_id, Arc::clone(&subscriber.writer), subscriber.encoding))
+            .collect()
+    }
+
+    /// Returns the subscribers due for a push, with their encoding, whether they receive
+    /// delta frames and their ghost camera, and marks them as pushed.
+    pub fn take_due(&mut self) -> Vec<(ClientId, SharedWriter, Encoding, bool, Option<u32>)> {
+        let now = Instant::now();
+        self.subscribers
+            .iter_mut()
+            .filter(|(_, subscriber)| now.duration_since(subscriber.last_push) >= subscriber.interval)
+            .map(|(client_id, subscriber)| {
+                subscriber.last_push = now;
+                let camera = self.cameras.get(client_id).copied();
+                (*client_id, Arc::clone(&subscriber.writer), subscriber.encoding, subscriber.delta.is_some(), camera)
+            })
+            .collect()
+    }
+}
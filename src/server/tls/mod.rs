@@ -0,0 +1,137 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+use crate::app_defines::AppDefines;
+use crate::server::client_id::ClientId;
+use crate::server::client_stream::ClientStream;
+
+/// Loads the certificate chain and the private key of the server.
+///
+/// # Arguments
+///
+/// * `cert_path` - The PEM file of the certificate chain, server certificate first.
+/// * `key_path` - The PEM file of the private key.
+///
+/// # Returns
+///
+/// The configuration of the TLS sessions, or why the files cannot be used.
+///
+pub(crate) fn load_config(cert_path: &Path, key_path: &Path) -> Result<Arc<ServerConfig>, String> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("the certificate {} cannot be read: {}", cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("the file {} holds no certificate", cert_path.display()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("the key {} cannot be read: {}", key_path.display(), e))?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("the certificate and the key cannot be used: {}", e))?;
+    Ok(Arc::new(config))
+}
+
+/// A struct representing the TLS session of a client, once its handshake completed.
+pub(crate) struct TlsStream(StreamOwned<ServerConnection, TcpStream>);
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    /// Sends every record still held by the session, unlike `StreamOwned::flush` which stops at the first partial write.
+    fn flush(&mut self) -> io::Result<()> {
+        while self.0.conn.wants_write() {
+            self.0.conn.write_tls(&mut self.0.sock)?;
+        }
+        self.0.sock.flush()
+    }
+}
+
+impl ClientStream for TlsStream {
+    fn socket(&self) -> &TcpStream {
+        self.0.get_ref()
+    }
+
+    /// Sends the `close_notify` alert, then shuts the socket down.
+    fn shutdown(&mut self) -> io::Result<()> {
+        self.0.conn.send_close_notify();
+        // Le client peut être parti : l'alerte est envoyée si possible, et l'avoir envoyée
+        // à un client parti a pu réinitialiser la connexion
+        let _ = self.flush();
+        match self.0.sock.shutdown(Shutdown::Both) {
+            Err(e) if e.kind() == ErrorKind::NotConnected => Ok(()),
+            result => result,
+        }
+    }
+}
+
+/// A struct representing the TLS handshake of a client, advanced by a worker of the pool
+/// without waiting, so that a slow client holds neither a listener nor a worker.
+pub(crate) struct TlsHandshake {
+    stream: StreamOwned<ServerConnection, TcpStream>,
+    /// The identity of the client.
+    pub client_id: ClientId,
+    /// When the client has taken too long, see `TLS_HANDSHAKE_TIMEOUT_MS`.
+    deadline: Instant,
+}
+
+impl TlsHandshake {
+    /// Starts the handshake of an accepted client.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the TLS sessions.
+    /// * `socket` - The socket of the client, made non-blocking.
+    /// * `client_id` - The identity of the client.
+    ///
+    /// # Returns
+    ///
+    /// The handshake, or the error preparing the socket or the session.
+    ///
+    pub fn new(config: &Arc<ServerConfig>, socket: TcpStream, client_id: ClientId) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        let conn = ServerConnection::new(Arc::clone(config)).map_err(io::Error::other)?;
+        let deadline = Instant::now() + Duration::from_millis(AppDefines::TLS_HANDSHAKE_TIMEOUT_MS);
+        Ok(TlsHandshake { stream: StreamOwned::new(conn, socket), client_id, deadline })
+    }
+
+    /// Advances the handshake with what the client sent so far.
+    ///
+    /// # Returns
+    ///
+    /// Whether the handshake completed, or why it failed: a bad message, a closed connection or the timeout.
+    ///
+    pub fn poll(&mut self) -> io::Result<bool> {
+        let StreamOwned { conn, sock } = &mut self.stream;
+        match conn.complete_io(sock) {
+            Ok(_) if !conn.is_handshaking() => return Ok(true),
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+        if Instant::now() >= self.deadline {
+            return Err(io::Error::new(ErrorKind::TimedOut, "handshake timed out"));
+        }
+        Ok(false)
+    }
+
+    /// Returns the session of the completed handshake.
+    pub fn into_stream(self) -> TlsStream {
+        TlsStream(self.stream)
+    }
+}
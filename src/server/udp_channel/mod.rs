@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::app_defines::AppDefines;
+use crate::entities::entity::Entity;
+use crate::game_logic::command::{GameCommand, GameCommands};
+use crate::server::client_handler::ClientHandler;
+use crate::server::server_thread::ServerSettings;
+use crate::server::sessions::SessionRegistry;
+
+/// A struct receiving actuator commands as UDP datagrams, alongside the TCP connections.
+///
+/// A datagram holds the reconnection token of a client followed by actuator commands,
+/// joined with `COMMAND_SEP`, e.g. `<token>#MotL=0.5#MotR=0.5`. The commands are applied
+/// to the entity of the token and never answered. Datagrams with an unknown token and
+/// commands other than actuators are dropped silently: a datagram never creates an entity.
+///
+/// Datagrams are applied in their order of arrival, so the last datagram received wins,
+/// even when the network delivers an older datagram after a newer one. Clients needing
+/// strict ordering send their actuator commands over TCP.
+pub(crate) struct UdpChannel {
+    /// The socket receiving the datagrams.
+    socket: UdpSocket,
+    /// Thread-safe, shared server settings.
+    settings: Arc<Mutex<ServerSettings>>,
+    /// The way into the game logic, owned by the game loop.
+    commands: GameCommands,
+    sessions: Arc<Mutex<SessionRegistry>>,
+    /// The number of datagrams received from each source address in the current second.
+    received: HashMap<SocketAddr, u32>,
+    /// When the current second started.
+    window_start: Instant,
+}
+
+impl UdpChannel {
+    /// Creates a new UDP channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The socket receiving the datagrams.
+    /// * `settings` - Thread-safe, shared server settings.
+    /// * `commands` - The way into the game logic, owned by the game loop.
+    /// * `sessions` - The reconnection tokens of the client entities.
+    ///
+    /// # Returns
+    ///
+    /// A new `UdpChannel`.
+    ///
+    pub fn new(socket: UdpSocket,
+               settings: Arc<Mutex<ServerSettings>>,
+               commands: GameCommands,
+               sessions: Arc<Mutex<SessionRegistry>>,
+        ) -> Self {
+        UdpChannel { socket, settings, commands, sessions, received: HashMap::new(), window_start: Instant::now() }
+    }
+
+    /// Receives and applies datagrams until the server stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `stopping` - Set when the server stops, checked every `ACCEPT_POLL_MS` at most.
+    ///
+    pub fn run(&mut self, stopping: &AtomicBool) {
+        let mut buffer = [0u8; 1024];
+        // Une lecture bloquante ne verrait jamais l'arrêt du serveur
+        let _ = self.socket.set_read_timeout(Some(Duration::from_millis(AppDefines::ACCEPT_POLL_MS)));
+        while !stopping.load(Ordering::SeqCst) {
+            // Les erreurs (ICMP port unreachable, etc.) ne concernent qu'un datagramme
+            let Ok((length, source)) = self.socket.recv_from(&mut buffer) else { continue };
+            if self.within_rate_limit(source) {
+                self.apply(&buffer[..length]);
+            }
+        }
+    }
+
+    /// Counts a datagram of a source address against the rate limit.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the source already sent `UDP_MAX_DATAGRAMS_PER_SEC` datagrams this second.
+    ///
+    fn within_rate_limit(&mut self, source: SocketAddr) -> bool {
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            self.received.clear();
+            self.window_start = Instant::now();
+        }
+        let count = self.received.entry(source).or_insert(0);
+        *count += 1;
+        *count <= AppDefines::UDP_MAX_DATAGRAMS_PER_SEC
+    }
+
+    /// Applies the actuator commands of a datagram to the entity of its token.
+    ///
+    /// The token is checked before any command is sent to the game loop, so that datagrams
+    /// without a valid token never slow the simulation down. As on TCP, every command of the
+    /// datagram is charged to the budget of the entity before it runs, valid or not, and the
+    /// commands beyond the budget are dropped. The commands are not answered: the loop applies
+    /// them between two steps and drops the refused ones.
+    fn apply(&self, datagram: &[u8]) {
+        let Ok(text) = std::str::from_utf8(datagram) else { return };
+        let mut commands = text.trim().split(AppDefines::COMMAND_SEP);
+        let token = commands.next().unwrap_or("").trim();
+        let Some(entity_id) = self.sessions.lock().unwrap().entity_of(token) else { return };
+        let command_budget = self.settings.lock().unwrap().command_budget_per_step;
+        let commands: Vec<&str> = commands.collect();
+        let granted = match command_budget {
+            0 => commands.len(),
+            budget => self.commands.ask(|reply| GameCommand::ChargeBudget {
+                entity_id,
+                count: commands.len() as u32,
+                budget,
+                reply,
+            }) as usize,
+        };
+
+        for command in commands.into_iter().take(granted) {
+            let Some((code, value)) = command.trim().split_once(AppDefines::ARGUMENT_SEP) else { continue };
+            let code = ClientHandler::canonical_code(code.trim());
+            let args: Vec<&str> = value.split(AppDefines::ARGUMENT_SEP).collect();
+            let Ok((args, seq)) = ClientHandler::split_sequence(&args) else { continue };
+            // Comme sur TCP, `GunTrig=1=0.9` vise la tourelle 1 et `MotL=0.8=@12` numérote la commande
+            let (turret, value) = match *args {
+                [turret, value] => match turret.trim().parse::<usize>() {
+                    Ok(turret) => (turret, value),
+                    Err(_) => continue,
+                },
+                [value] => (0, value),
+                _ => continue,
+            };
+            let Some(value) = value.trim().parse::<f32>().ok().and_then(Entity::clamp_actuator) else { continue };
+            if !ClientHandler::is_actuator(code) {
+                continue;
+            }
+            // Une commande périmée est perdue sans réponse, comme un datagramme perdu
+            self.commands.send(GameCommand::SetActuator {
+                entity_id,
+                code: ClientHandler::static_code(code),
+                turret,
+                value,
+                seq,
+                reply: None,
+            });
+        }
+    }
+}
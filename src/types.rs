@@ -1,12 +1,36 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use eframe::egui;
+use serde::{Deserialize, Serialize};
+use crate::app_defines::AppDefines;
+
+/// Where the thread of the `MessageHub` also sends the messages, set while a `FileLogger` runs.
+static LOG_SINK: Mutex<Option<Sender<LogEntry>>> = Mutex::new(None);
+/// The severity under which `add_message` drops the messages, see `set_log_verbosity`.
+static LOG_VERBOSITY: AtomicU8 = AtomicU8::new(AppDefines::LOG_VERBOSITY.severity());
 
 /// Struct representing a styled message with text and color.
+#[derive(Clone)]
 pub struct StyledMessage {
     /// The text of the message.
     pub text: String,
     /// The color of the message.
     pub color: egui::Color32,
+    /// The type of the message, which the server UI filters on.
+    pub message_type: MessageType,
+    /// When the message was added, never before the message added just before it.
+    pub timestamp: SystemTime,
+    /// The part of the server the message comes from.
+    pub origin: MessageOrigin,
+    /// Who sent a chat message, see `add_chat_message`, `None` for the other messages.
+    pub sender: Option<String>,
 }
 
 impl StyledMessage {
@@ -16,28 +40,210 @@ impl StyledMessage {
     ///
     /// * `text` - The text of the message.
     /// * `message_type` - The type of the message which determines its color.
+    /// * `origin` - The part of the server the message comes from.
+    /// * `timestamp` - When the message was added.
     ///
     /// # Returns
     ///
     /// A new `StyledMessage` with the specified text and color.
-    pub fn new(text: String, message_type: MessageType) -> Self {
+    pub fn new(text: String, message_type: MessageType, origin: MessageOrigin, timestamp: SystemTime) -> Self {
         StyledMessage {
             text,
             color: message_type.to_color(),
+            message_type,
+            timestamp,
+            origin,
+            sender: None,
+        }
+    }
+
+    /// Returns the sender of a chat message and its text without the name, `None` for the other messages.
+    pub fn chat(&self) -> Option<(&str, &str)> {
+        let sender = self.sender.as_deref()?;
+        let text = self.text.strip_prefix(sender).and_then(|text| text.strip_prefix(": ")).unwrap_or(&self.text);
+        Some((sender, text))
+    }
+}
+
+/// The messages of the server, the oldest dropped once `capacity` are kept.
+///
+/// Each message gets a sequence number, counted from the start or the last `clear`, the dropped
+/// messages included. A reader keeps the number of the next message it expects and reads the new
+/// ones with `from_sequence`, whatever was dropped in between.
+///
+/// The messages are shared between the copies of the log, so the `MessageHub` publishes a copy
+/// without copying their texts.
+#[derive(Clone)]
+pub struct MessageLog {
+    /// The messages kept, oldest first.
+    messages: VecDeque<Arc<StyledMessage>>,
+    /// The number of messages kept at most.
+    capacity: usize,
+    /// The number of messages added since the start or the last clear, the dropped ones included.
+    added: u64,
+}
+
+impl MessageLog {
+    /// Creates an empty log.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of messages kept at most, at least 1.
+    ///
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        MessageLog { messages: VecDeque::with_capacity(capacity), capacity, added: 0 }
+    }
+
+    /// Adds a message, dropping the oldest one if the log is full.
+    pub fn push(&mut self, message: StyledMessage) {
+        if self.messages.len() == self.capacity {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(Arc::new(message));
+        self.added += 1;
+    }
+
+    /// Returns the number of messages kept at most.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of messages added since the start or the last clear, which is the sequence number of the next one.
+    pub fn added(&self) -> u64 {
+        self.added
+    }
+
+    /// Returns the number of messages dropped to make room, which is the sequence number of the oldest one kept.
+    pub fn dropped(&self) -> u64 {
+        self.added - self.messages.len() as u64
+    }
+
+    /// Returns the last message added, if it is still kept.
+    pub fn last(&self) -> Option<&StyledMessage> {
+        self.messages.back().map(Arc::as_ref)
+    }
+
+    /// Returns the messages kept from a sequence number on, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - The sequence number of the first message wanted. The messages dropped since are skipped.
+    ///
+    pub fn from_sequence(&self, sequence: u64) -> impl Iterator<Item = &StyledMessage> {
+        self.messages.iter().skip(sequence.saturating_sub(self.dropped()) as usize).map(Arc::as_ref)
+    }
+
+    /// Returns a copy of every message kept, oldest first.
+    pub fn to_vec(&self) -> Vec<StyledMessage> {
+        self.messages.iter().map(|message| StyledMessage::clone(message)).collect()
+    }
+
+    /// Returns the messages kept of a severity at least that of a level, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_level` - The least severe type returned, see `MessageType::severity`.
+    ///
+    pub fn filtered(&self, min_level: MessageType) -> Vec<StyledMessage> {
+        self.messages
+            .iter()
+            .filter(|message| message.message_type.is_at_least(min_level))
+            .map(|message| StyledMessage::clone(message))
+            .collect()
+    }
+
+    /// Returns the most recent messages kept containing a text, ignoring the case, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `substring` - The text searched, an empty one matching every message.
+    /// * `limit` - The number of messages returned at most, the most recent ones.
+    ///
+    pub fn search(&self, substring: &str, limit: usize) -> Vec<StyledMessage> {
+        let substring = substring.to_lowercase();
+        let mut found: Vec<StyledMessage> = self.messages
+            .iter()
+            .rev()
+            .filter(|message| message.text.to_lowercase().contains(&substring))
+            .take(limit)
+            .map(|message| StyledMessage::clone(message))
+            .collect();
+        found.reverse();
+        found
+    }
+
+    /// Returns the messages kept added after a time, oldest first.
+    ///
+    /// The times follow the order of the log, so the first message after the time is found by bisection.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - The time, the messages stamped with it exactly are not returned.
+    ///
+    pub fn since(&self, timestamp: SystemTime) -> Vec<StyledMessage> {
+        let start = self.messages.partition_point(|message| message.timestamp <= timestamp);
+        self.messages.range(start..).map(|message| StyledMessage::clone(message)).collect()
+    }
+
+    /// Removes every message and starts the sequence numbers over.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.added = 0;
+    }
+}
+
+/// The part of the server a message comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageOrigin {
+    /// The connections of the clients and the server threads.
+    Network,
+    /// The arena: its events, the match and the leaderboard.
+    Game,
+    /// An administrator, from a client or the admin port.
+    Admin,
+    /// The user of the server UI.
+    Ui,
+    /// The chat of the clients and of the server UI, see `add_chat_message`.
+    Chat,
+}
+
+impl MessageOrigin {
+    /// Returns the tag of the origin, as written in the log file and shown in the server UI.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            MessageOrigin::Network => "NET",
+            MessageOrigin::Game => "GAME",
+            MessageOrigin::Admin => "ADMIN",
+            MessageOrigin::Ui => "UI",
+            MessageOrigin::Chat => "CHAT",
         }
     }
 }
 
 /// Enum representing different types of messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MessageType {
     Info,
     Error,
     Warning,
     Debug,
+    /// An event of the arena, see `EventLogger`.
+    GameEvent,
     Default,
 }
 
 impl MessageType {
+    /// All the message types, in the order of the filters of the server UI.
+    pub const ALL: [MessageType; 6] = [
+        MessageType::Info,
+        MessageType::Warning,
+        MessageType::Error,
+        MessageType::Debug,
+        MessageType::GameEvent,
+        MessageType::Default,
+    ];
+
     /// Converts a message type to a corresponding color.
     ///
     /// # Returns
@@ -49,22 +255,409 @@ impl MessageType {
             MessageType::Error => egui::Color32::RED,       // Red for error messages
             MessageType::Warning => egui::Color32::YELLOW,  // Yellow for warning messages
             MessageType::Debug => egui::Color32::BLUE,      // Blue for debug messages
+            MessageType::GameEvent => egui::Color32::LIGHT_BLUE, // Light blue for game events
             MessageType::Default => egui::Color32::GRAY,    // Gray for default messages
         }
     }
+
+    /// Returns how severe a message type is, from 0 for `Debug` to 3 for `Error`.
+    ///
+    /// `Default` and `GameEvent` are as severe as `Info`.
+    pub const fn severity(&self) -> u8 {
+        match self {
+            MessageType::Debug => 0,
+            MessageType::Info | MessageType::GameEvent | MessageType::Default => 1,
+            MessageType::Warning => 2,
+            MessageType::Error => 3,
+        }
+    }
+
+    /// Returns whether a message type is at least as severe as another.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The type compared to.
+    ///
+    pub fn is_at_least(&self, level: MessageType) -> bool {
+        self.severity() >= level.severity()
+    }
+
+    /// Converts a message type to the level written in the log file.
+    ///
+    /// # Returns
+    ///
+    /// The level of the message type.
+    pub fn to_level(&self) -> &'static str {
+        match self {
+            MessageType::Info | MessageType::Default => "INFO",
+            MessageType::Error => "ERROR",
+            MessageType::Warning => "WARN",
+            MessageType::Debug => "DEBUG",
+            MessageType::GameEvent => "EVENT",
+        }
+    }
 }
 
-/// Adds a new message to the list of messages.
+/// A message on its way to the log file.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// When the message was added.
+    pub at: SystemTime,
+    pub message_type: MessageType,
+    pub origin: MessageOrigin,
+    pub text: String,
+}
+
+/// Sets where `add_message` also sends the messages.
 ///
 /// # Arguments
 ///
-/// * `messages` - A thread-safe reference to the list of messages.
+/// * `sink` - The channel of the log file, or `None` to stop sending the messages.
+pub fn set_log_sink(sink: Option<Sender<LogEntry>>) {
+    *LOG_SINK.lock().unwrap() = sink;
+}
+
+/// The formats the messages can be exported in, see `export_messages`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One line per message, as in the log file: time, level, origin and text.
+    Text,
+    /// One JSON object per line, with the `timestamp`, `level`, `origin` and `text` fields.
+    JsonLines,
+}
+
+impl ExportFormat {
+    /// Returns the format named `text` or `json`, whatever the case.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the format.
+    ///
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Some(ExportFormat::Text),
+            "json" | "jsonl" => Some(ExportFormat::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// A message as written by `ExportFormat::JsonLines`.
+#[derive(Serialize)]
+struct ExportedMessage<'a> {
+    timestamp: String,
+    level: &'static str,
+    origin: &'static str,
+    text: &'a str,
+}
+
+/// Formats a message as a line of the log file, without the line break.
+///
+/// # Arguments
+///
+/// * `at` - When the message was added.
+/// * `message_type` - The type of the message, written as its level.
+/// * `origin` - The part of the server the message comes from.
+/// * `text` - The text of the message, flattened to one line.
+///
+pub fn format_log_line(at: SystemTime, message_type: MessageType, origin: MessageOrigin, text: &str) -> String {
+    // Une ligne par message : les messages sur plusieurs lignes sont aplatis
+    let text = text.trim().replace('\n', " ");
+    format!("{} [{}] [{}] {}", format_timestamp(at), message_type.to_level(), origin.tag(), text)
+}
+
+/// Writes messages to a file, replacing it.
+///
+/// The messages are copied out of the log beforehand, see `MessageLog::to_vec`, so that no
+/// lock is held while the file is written.
+///
+/// # Arguments
+///
+/// * `messages` - The messages written, in order.
+/// * `path` - The file written.
+/// * `format` - The format of the lines.
+///
+/// # Returns
+///
+/// The number of messages written, or why the file could not be written.
+///
+pub fn export_messages(messages: &[StyledMessage], path: &Path, format: ExportFormat) -> Result<usize, String> {
+    let error = |e: std::io::Error| format!("Could not write {}: {}", path.display(), e);
+    let mut writer = BufWriter::new(File::create(path).map_err(error)?);
+    for message in messages {
+        let line = match format {
+            ExportFormat::Text => format_log_line(message.timestamp, message.message_type, message.origin, &message.text),
+            ExportFormat::JsonLines => {
+                let exported = ExportedMessage {
+                    timestamp: format_timestamp(message.timestamp),
+                    level: message.message_type.to_level(),
+                    origin: message.origin.tag(),
+                    text: &message.text,
+                };
+                serde_json::to_string(&exported).map_err(|e| e.to_string())?
+            }
+        };
+        writeln!(writer, "{}", line).map_err(error)?;
+    }
+    writer.flush().map_err(error)?;
+    Ok(messages.len())
+}
+
+/// Sets the least severe messages `add_message` keeps, the others are dropped before being stored.
+///
+/// # Arguments
+///
+/// * `min_level` - The least severe type kept.
+pub fn set_log_verbosity(min_level: MessageType) {
+    LOG_VERBOSITY.store(min_level.severity(), Ordering::Relaxed);
+}
+
+/// Returns whether `add_message` keeps the messages of a type, to skip building a message it would drop.
+///
+/// # Arguments
+///
+/// * `message_type` - The type of the message.
+pub fn is_logged(message_type: MessageType) -> bool {
+    message_type.severity() >= LOG_VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// A message on its way from `add_message` to the thread of the `MessageHub`.
+struct LogRecord {
+    text: String,
+    message_type: MessageType,
+    origin: MessageOrigin,
+    /// When the message was sent.
+    at: SystemTime,
+    /// Who sent a chat message.
+    sender: Option<String>,
+}
+
+/// What the thread of a `MessageHub` is asked to do.
+enum HubCommand {
+    /// Adds a message.
+    Add(LogRecord),
+    /// Adds several messages, in order.
+    AddAll(Vec<LogRecord>),
+    /// Removes every message.
+    Clear,
+    /// Answers once the commands sent before are applied and published.
+    Flush(Sender<()>),
+}
+
+/// The messages of the server, added by every thread and read by the UI.
+///
+/// `add_message` only sends the message through a channel: a thread of the hub owns the
+/// `MessageLog`, stamps the messages, feeds the log file and, after each batch of messages,
+/// publishes a read-only copy of the log. The readers take that copy with `log`, so the threads
+/// adding messages never wait for a reader drawing them. The messages of a thread keep their
+/// order, those of different threads are in the order they reach the hub.
+///
+/// The clones of a hub share the same messages, the thread stops once they are all dropped.
+#[derive(Clone)]
+pub struct MessageHub {
+    sender: Sender<HubCommand>,
+    /// The last copy of the log published by the thread.
+    published: Arc<Mutex<Arc<MessageLog>>>,
+}
+
+impl MessageHub {
+    /// Creates an empty hub and starts its thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of messages kept at most, see `MessageLog::new`.
+    ///
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let published = Arc::new(Mutex::new(Arc::new(MessageLog::new(capacity))));
+        let thread_published = Arc::clone(&published);
+        thread::spawn(move || MessageHub::run(MessageLog::new(capacity), receiver, thread_published));
+        MessageHub { sender, published }
+    }
+
+    /// Returns the last copy of the log published, which the messages sent since may not have reached yet.
+    pub fn log(&self) -> Arc<MessageLog> {
+        Arc::clone(&self.published.lock().unwrap())
+    }
+
+    /// Removes every message and starts the sequence numbers over, once the messages sent before are added.
+    pub fn clear(&self) {
+        let _ = self.sender.send(HubCommand::Clear);
+    }
+
+    /// Waits for the messages sent so far to be added, published and sent to the log file.
+    pub fn flush(&self) {
+        let (done, finished) = mpsc::channel();
+        if self.sender.send(HubCommand::Flush(done)).is_ok() {
+            let _ = finished.recv();
+        }
+    }
+
+    /// Applies the commands until every hub is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `log` - The messages, owned by the thread.
+    /// * `receiver` - The commands of the hubs.
+    /// * `published` - Where the copy of the log is published.
+    ///
+    fn run(mut log: MessageLog, receiver: Receiver<HubCommand>, published: Arc<Mutex<Arc<MessageLog>>>) {
+        while let Ok(first) = receiver.recv() {
+            let mut changed = false;
+            let mut flushes = Vec::new();
+            // Tout ce qui attend est appliqué avant de publier une seule copie
+            for command in std::iter::once(first).chain(receiver.try_iter()) {
+                match command {
+                    HubCommand::Add(record) => MessageHub::store(&mut log, record),
+                    HubCommand::AddAll(records) => records.into_iter().for_each(|record| MessageHub::store(&mut log, record)),
+                    HubCommand::Clear => log.clear(),
+                    HubCommand::Flush(done) => {
+                        flushes.push(done);
+                        continue;
+                    }
+                }
+                changed = true;
+            }
+            if changed {
+                *published.lock().unwrap() = Arc::new(log.clone());
+            }
+            for done in flushes {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    /// Stamps a message, never before the last one, sends it to the log file if one is open, and adds it to the log.
+    fn store(log: &mut MessageLog, record: LogRecord) {
+        let LogRecord { text, message_type, origin, at, sender } = record;
+        let timestamp = log.last().map_or(at, |last| last.timestamp.max(at));
+        if let Some(sink) = LOG_SINK.lock().unwrap().as_ref() {
+            let _ = sink.send(LogEntry { at: timestamp, message_type, origin, text: text.clone() });
+        }
+        log.push(StyledMessage { sender, ..StyledMessage::new(text, message_type, origin, timestamp) });
+    }
+}
+
+/// Adds a new message to the messages of the server.
+///
+/// # Arguments
+///
+/// * `messages` - The messages of the server.
 /// * `text` - The text of the message.
 /// * `message_type` - The type of the message which determines its color.
+/// * `origin` - The part of the server the message comes from.
+///
+/// A message less severe than the verbosity set with `set_log_verbosity` is dropped at once.
+/// Otherwise, the message is sent to the thread of the hub, which adds it to the log, dropping
+/// the oldest message if the log is full, and to the log file if one is open. Nothing is locked:
+/// the message reaches the readers of the log shortly after.
+/// The message is stamped when it is sent, and the hub keeps the times in the order of the log
+/// even if the system clock goes back.
+pub fn add_message(messages: &MessageHub, text: String, message_type: MessageType, origin: MessageOrigin) {
+    if !is_logged(message_type) {
+        return;
+    }
+    let _ = messages.sender.send(HubCommand::Add(LogRecord { text, message_type, origin, at: SystemTime::now(), sender: None }));
+}
+
+/// Adds a chat message to the messages of the server, written `<sender>: <text>` with the `Chat` origin.
+///
+/// # Arguments
+///
+/// * `messages` - The messages of the server.
+/// * `sender` - The name of the entity that sent the message, or `CHAT_SERVER_SENDER` for the server UI.
+/// * `text` - The text of the message.
+///
+/// The message is dropped, stamped and stored as by `add_message`, keeping its sender apart
+/// for the chat tab of the server UI, see `StyledMessage::chat`.
+pub fn add_chat_message(messages: &MessageHub, sender: &str, text: &str) {
+    if !is_logged(MessageType::Default) {
+        return;
+    }
+    let record = LogRecord {
+        text: format!("{}: {}", sender, text),
+        message_type: MessageType::Default,
+        origin: MessageOrigin::Chat,
+        at: SystemTime::now(),
+        sender: Some(sender.to_string()),
+    };
+    let _ = messages.sender.send(HubCommand::Add(record));
+}
+
+/// Adds several messages of the same type and origin to the messages of the server, sent at once.
+///
+/// # Arguments
+///
+/// * `messages` - The messages of the server.
+/// * `texts` - The texts of the messages, in order.
+/// * `message_type` - The type of the messages which determines their color.
+/// * `origin` - The part of the server the messages come from.
+///
+/// The messages are dropped, stamped and stored as by `add_message`.
+pub fn add_messages(messages: &MessageHub, texts: Vec<String>, message_type: MessageType, origin: MessageOrigin) {
+    if !is_logged(message_type) {
+        return;
+    }
+    let at = SystemTime::now();
+    let records = texts.into_iter().map(|text| LogRecord { text, message_type, origin, at, sender: None }).collect();
+    let _ = messages.sender.send(HubCommand::AddAll(records));
+}
+
+/// Formats a time as an ISO 8601 UTC time, to the millisecond, e.g. `2024-05-01T13:45:12.345Z`.
+///
+/// # Arguments
+///
+/// * `at` - The time.
+///
+pub fn format_timestamp(at: SystemTime) -> String {
+    let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+
+    // Jours depuis 1970 vers la date civile (algorithme de H. Hinnant)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, time / 3600, time / 60 % 60, time % 60, since_epoch.subsec_millis()
+    )
+}
+
+/// Formats the UTC time of day of a time, to the millisecond: always 12 characters, e.g. `13:45:12.345`.
+///
+/// # Arguments
+///
+/// * `at` - The time.
+///
+pub fn format_clock(at: SystemTime) -> String {
+    let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let time = since_epoch.as_secs() % 86_400;
+    format!("{:02}:{:02}:{:02}.{:03}", time / 3600, time / 60 % 60, time % 60, since_epoch.subsec_millis())
+}
+
+/// Formats how long ago a time was, in its largest whole unit: `now`, `12s ago`, `3m ago`, `2h ago` or `5d ago`.
+///
+/// # Arguments
+///
+/// * `at` - The time.
+/// * `now` - The current time. A time after it counts as now.
 ///
-/// This function locks the list of messages, creates a new styled message, and adds it to the list.
-pub fn add_message(messages: &Arc<Mutex<Vec<StyledMessage>>>, text: String, message_type: MessageType) {
-    let message = StyledMessage::new(text, message_type);
-    messages.lock().unwrap().push(message);
+pub fn format_age(at: SystemTime, now: SystemTime) -> String {
+    let age = now.duration_since(at).unwrap_or(Duration::ZERO).as_secs();
+    match age {
+        0 => "now".to_string(),
+        1..=59 => format!("{}s ago", age),
+        60..=3599 => format!("{}m ago", age / 60),
+        3600..=86_399 => format!("{}h ago", age / 3600),
+        _ => format!("{}d ago", age / 86_400),
+    }
 }
 
@@ -1,24 +1,75 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use eframe::egui;
-use crate::game_logic::GameLogic;
-use crate::types::StyledMessage;
-use crate::server::server_thread::ServerSettings;
+use crate::server::server_thread::ServerThread;
 
 use crate::ui::game_ui::GameUI;
+use crate::ui::preferences::{Theme, UiPreferences};
 use crate::ui::server_ui::ServerUi;
 
+/// The window of the server: the server UI and the game UI, one at a time.
 pub struct CombinedUI {
     server_ui: ServerUi,
     game_ui: GameUI,
     show_server_ui: bool,
+    /// The theme of the window.
+    theme: Theme,
+    /// The accent color of `Theme::Accent`, in sRGB.
+    accent: [u8; 3],
 }
 
 impl CombinedUI {
-    pub fn new(messages: Arc<Mutex<Vec<StyledMessage>>>, settings: Arc<Mutex<ServerSettings>>, game_logic: Arc<Mutex<GameLogic>>) -> Self {
+    /// Creates the window of a server, restoring the preferences kept by eframe.
+    ///
+    /// # Arguments
+    ///
+    /// * `cc` - The context eframe creates the window with.
+    /// * `server` - The server shown, whose messages, settings, game logic and statistics the window reads.
+    ///
+    pub fn new(cc: &eframe::CreationContext, server: ServerThread) -> Self {
+        let preferences = UiPreferences::load(cc.storage);
+        cc.egui_ctx.set_visuals(preferences.visuals());
+        let mut game_ui = GameUI::new(server.commands.clone(), server.metrics.clone(), server.messages.clone());
+        game_ui.apply_settings(&preferences.game);
+        let (messages, settings, commands, client_stats) =
+            (server.messages.clone(), Arc::clone(&server.settings), server.commands.clone(), Arc::clone(&server.client_stats));
+        let mut server_ui = ServerUi::new(messages, settings, commands, server, client_stats);
+        server_ui.set_message_filters(&preferences.message_filters);
         CombinedUI {
-            server_ui: ServerUi::new(messages.clone(), settings.clone()),
-            game_ui: GameUI::new(game_logic), // 💡 à implémenter si besoin
-            show_server_ui: true,
+            game_ui,
+            server_ui,
+            show_server_ui: preferences.show_server_ui,
+            theme: preferences.theme,
+            accent: preferences.accent,
+        }
+    }
+
+    /// Returns the preferences of the window, to be kept for the next run.
+    fn preferences(&self) -> UiPreferences {
+        UiPreferences {
+            game: self.game_ui.settings(),
+            theme: self.theme,
+            accent: self.accent,
+            message_filters: self.server_ui.message_filters(),
+            show_server_ui: self.show_server_ui,
+            ..UiPreferences::default()
+        }
+    }
+
+    /// Shows the theme switcher, applying the theme chosen at once.
+    fn show_theme_switcher(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        egui::ComboBox::from_label("Theme")
+            .selected_text(format!("{:?}", self.theme))
+            .show_ui(ui, |ui| {
+                for theme in Theme::ALL {
+                    changed |= ui.selectable_value(&mut self.theme, theme, format!("{:?}", theme)).changed();
+                }
+            });
+        if self.theme == Theme::Accent {
+            changed |= ui.color_edit_button_srgb(&mut self.accent).changed();
+        }
+        if changed {
+            ui.ctx().set_visuals(self.preferences().visuals());
         }
     }
 }
@@ -33,6 +84,8 @@ impl eframe::App for CombinedUI {
                 if ui.selectable_label(!self.show_server_ui, "Game UI").clicked() {
                     self.show_server_ui = false;
                 }
+                ui.separator();
+                self.show_theme_switcher(ui);
             });
         });
 
@@ -42,4 +95,14 @@ impl eframe::App for CombinedUI {
             self.game_ui.update(ctx, frame);
         }
     }
+
+    // eframe l'appelle régulièrement et à la fermeture
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.preferences().save(storage);
+    }
+
+    // Fermer la fenêtre arrête le serveur comme le bouton "Stop Server"
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.server_ui.shut_down();
+    }
 }
\ No newline at end of file
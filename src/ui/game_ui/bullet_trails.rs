@@ -0,0 +1,144 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use eframe::egui::{self, Color32, Shape, Stroke, Ui};
+use egui_plot::{PlotBounds, PlotGeometry, PlotItem, PlotPoint, PlotTransform};
+use rapier2d::prelude::RigidBodyHandle;
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::render_snapshot::RenderSnapshot;
+
+/// The trails of the bullets, laid out for drawing: the points of every trail one after the other.
+#[derive(Default)]
+struct TrailBuffer {
+    points: Vec<PlotPoint>,
+    /// The number of points of each trail.
+    lengths: Vec<usize>,
+}
+
+/// The last positions of the bullets of the live game, drawn behind them as fading tracers.
+///
+/// The history is kept per bullet body and sampled once per simulation step, at most
+/// `MAX_BULLET_TRAIL` positions each; the bullets gone from the snapshot lose theirs.
+#[derive(Default)]
+pub struct BulletTrails {
+    /// The positions of each bullet, newest last, with the step they were last seen at.
+    history: HashMap<RigidBodyHandle, (u64, VecDeque<[f64; 2]>)>,
+    /// The step of the last snapshot recorded.
+    tick: Option<u64>,
+    /// Handed to the plot each frame, refilled in place once the plot dropped it.
+    buffer: Arc<TrailBuffer>,
+}
+
+impl BulletTrails {
+    /// Adds the positions of a new step to the history, and forgets the bullets that are gone.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The last snapshot of the game loop; the same step is recorded once.
+    pub fn record(&mut self, snapshot: &RenderSnapshot) {
+        let tick = snapshot.frame.tick;
+        if self.tick == Some(tick) {
+            return;
+        }
+        self.tick = Some(tick);
+        for (handle, &(x, y)) in snapshot.bullet_handles.iter().zip(&snapshot.frame.bullets) {
+            let (seen, positions) = self.history
+                .entry(*handle)
+                .or_insert_with(|| (tick, VecDeque::with_capacity(AppDefines::MAX_BULLET_TRAIL)));
+            *seen = tick;
+            if positions.len() == AppDefines::MAX_BULLET_TRAIL {
+                positions.pop_front();
+            }
+            positions.push_back([x as f64, y as f64]);
+        }
+        self.history.retain(|_, (seen, _)| *seen == tick);
+    }
+
+    /// Forgets every trail, when the replay takes the place of the live game.
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.tick = None;
+    }
+
+    /// Builds the plot item drawing the trails, each ending at the drawn position of its bullet.
+    ///
+    /// # Parameters
+    /// - `handles`: The body of each bullet drawn.
+    /// - `heads`: The drawn position of each bullet, between two steps.
+    /// - `length`: The number of past positions drawn per bullet, at most `MAX_BULLET_TRAIL`.
+    /// - `color`, `width`: The color of the newest segment, and the width of the trails in points.
+    pub fn plot_item(&mut self, handles: &[RigidBodyHandle], heads: &[(f32, f32)], length: usize, color: Color32, width: f32) -> TrailsItem {
+        // Le tampon de l'image précédente est réutilisé, sauf si le graphique le tient encore
+        if Arc::get_mut(&mut self.buffer).is_none() {
+            self.buffer = Arc::default();
+        }
+        let buffer = Arc::get_mut(&mut self.buffer).unwrap();
+        buffer.points.clear();
+        buffer.lengths.clear();
+        for (handle, &(x, y)) in handles.iter().zip(heads) {
+            let Some((_, positions)) = self.history.get(handle) else { continue };
+            let skipped = positions.len().saturating_sub(length);
+            let start = buffer.points.len();
+            buffer.points.extend(positions.iter().skip(skipped).map(|&[x, y]| PlotPoint::new(x, y)));
+            buffer.points.push(PlotPoint::new(x, y));
+            buffer.lengths.push(buffer.points.len() - start);
+        }
+        TrailsItem { buffer: Arc::clone(&self.buffer), color, width }
+    }
+}
+
+/// The plot item drawing every trail, from faint at the oldest position to `color` at the bullet.
+///
+/// A single item draws them all: a `Line` per bullet would allocate its points and its shape every frame.
+pub struct TrailsItem {
+    buffer: Arc<TrailBuffer>,
+    color: Color32,
+    width: f32,
+}
+
+impl PlotItem for TrailsItem {
+    fn shapes(&self, _ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let mut start = 0;
+        for &length in &self.buffer.lengths {
+            let trail = &self.buffer.points[start..start + length];
+            start += length;
+            let segments = trail.len().saturating_sub(1);
+            for (index, pair) in trail.windows(2).enumerate() {
+                let fade = (index + 1) as f32 / segments as f32;
+                shapes.push(Shape::line_segment(
+                    [transform.position_from_point(&pair[0]), transform.position_from_point(&pair[1])],
+                    Stroke::new(self.width, self.color.gamma_multiply(fade)),
+                ));
+            }
+        }
+    }
+
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        "Bullet Trails"
+    }
+
+    fn color(&self) -> Color32 {
+        self.color
+    }
+
+    fn highlight(&mut self) {}
+
+    fn highlighted(&self) -> bool {
+        false
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::None
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        PlotBounds::NOTHING
+    }
+
+    fn id(&self) -> Option<egui::Id> {
+        None
+    }
+}
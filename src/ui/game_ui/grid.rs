@@ -0,0 +1,61 @@
+use eframe::egui::Color32;
+use egui_plot::{Line, LineStyle, PlotPoints, PlotUi};
+
+/// The lines of the background grid of the arena.
+///
+/// The ends of the lines are only computed again when the spacing or the size of the
+/// arena changes, not on every frame.
+#[derive(Default)]
+pub struct Grid {
+    /// The spacing, width and height the lines were computed for.
+    computed_for: Option<(f64, f64, f64)>,
+    /// The two ends of each line, vertical lines first.
+    lines: Vec<[[f64; 2]; 2]>,
+}
+
+impl Grid {
+    /// Computes the lines again if the spacing or the size of the arena changed.
+    ///
+    /// # Parameters
+    /// - `spacing`: The distance between two lines, in world units.
+    /// - `width`: The width of the arena.
+    /// - `height`: The height of the arena.
+    pub fn update(&mut self, spacing: f64, width: f64, height: f64) {
+        if self.computed_for == Some((spacing, width, height)) {
+            return;
+        }
+        self.computed_for = Some((spacing, width, height));
+        self.lines.clear();
+        // Un espacement nul ou négatif tracerait une infinité de lignes
+        if spacing <= 0.0 {
+            return;
+        }
+        let columns = (width / spacing).floor() as usize;
+        let rows = (height / spacing).floor() as usize;
+        self.lines.extend((0..=columns).map(|i| {
+            let x = i as f64 * spacing;
+            [[x, 0.0], [x, height]]
+        }));
+        self.lines.extend((0..=rows).map(|i| {
+            let y = i as f64 * spacing;
+            [[0.0, y], [width, y]]
+        }));
+    }
+
+    /// Draws the lines computed by the last `update`.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `color`: The color of the lines.
+    /// - `width`: The width of the lines, in points.
+    pub fn draw(&self, plot_ui: &mut PlotUi, color: Color32, width: f32) {
+        for ends in &self.lines {
+            plot_ui.line(
+                Line::new(PlotPoints::new(ends.to_vec()))
+                    .color(color)
+                    .width(width)
+                    .style(LineStyle::Solid),
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use eframe::egui::{Align2, Color32};
+use egui_plot::{Line, PlotPoint, PlotPoints, PlotUi, Text};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::event_queue::EventQueue;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::replay::EntityFrame;
+
+/// The color of the ring of an impact.
+const RING_COLOR: Color32 = Color32::from_rgb(255, 230, 150);
+/// The color of the points won.
+const SCORE_COLOR: Color32 = Color32::from_rgb(120, 255, 120);
+
+/// What an effect draws.
+#[derive(Debug, Clone, Copy)]
+enum EffectKind {
+    /// A ring growing around the point a bullet hit.
+    Ring { x: f32, y: f32 },
+    /// The entity struck, drawn lighter.
+    Flash { id: u32 },
+    /// The points won by an entity, rising from where it was.
+    Score { x: f32, y: f32, points: i32 },
+}
+
+/// A short-lived effect of the arena.
+#[derive(Debug, Clone, Copy)]
+struct Effect {
+    kind: EffectKind,
+    /// When the effect started.
+    started_at: Instant,
+    /// How long the effect lasts.
+    duration: Duration,
+}
+
+impl Effect {
+    /// Returns how far the effect went, from 0 when it starts to 1 when it ends.
+    fn progress(&self, now: Instant) -> f32 {
+        (now.saturating_duration_since(self.started_at).as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+}
+
+/// The feedback of the hits in the arena: impact rings, flashes of the entities struck
+/// and the points won floating above the shooters.
+///
+/// The effects come from the event queue, read with their own cursor like the kill feed,
+/// and from the scores of the frames drawn: nothing of the game logic is changed. At most
+/// `MAX_HIT_EFFECTS` effects are kept, the oldest giving way to the new ones.
+pub struct HitEffects {
+    /// Whether the effects are drawn and read the events.
+    pub enabled: bool,
+    /// The number of the last event read, `None` until the effects are enabled.
+    cursor: Option<u64>,
+    /// The score of each entity in the last frame, to see the points won.
+    scores: HashMap<u32, i32>,
+    /// The effects being drawn, oldest first.
+    effects: Vec<Effect>,
+}
+
+impl HitEffects {
+    /// Creates enabled effects, none of them running.
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            cursor: None,
+            scores: HashMap::new(),
+            effects: Vec::with_capacity(AppDefines::MAX_HIT_EFFECTS),
+        }
+    }
+
+    /// Adds an effect, dropping the oldest one when `MAX_HIT_EFFECTS` are running.
+    fn push(&mut self, kind: EffectKind, now: Instant, duration_ms: u64) {
+        if self.effects.len() == AppDefines::MAX_HIT_EFFECTS {
+            self.effects.remove(0);
+        }
+        self.effects.push(Effect { kind, started_at: now, duration: Duration::from_millis(duration_ms) });
+    }
+
+    /// Starts the effects of the events not read yet and of the points won, and drops the effects over.
+    ///
+    /// # Parameters
+    /// - `events`: The event queue of the game logic, left as is.
+    /// - `entities`: The entities of the frame drawn.
+    /// - `now`: The current time.
+    pub fn update(&mut self, events: &EventQueue, entities: &[EntityFrame], now: Instant) {
+        self.effects.retain(|effect| effect.progress(now) < 1.0);
+
+        // Activé pour la première fois : les coups d'avant ne sont pas rejoués
+        let cursor = *self.cursor.get_or_insert(events.cursor());
+        for queued in events.since(cursor) {
+            if let GameEvent::Hit { victim, x, y, .. } = queued.event {
+                self.push(EffectKind::Ring { x, y }, now, AppDefines::HIT_RING_MS);
+                self.push(EffectKind::Flash { id: victim }, now, AppDefines::HIT_FLASH_MS);
+            }
+        }
+        self.cursor = Some(events.cursor());
+
+        for entity in entities {
+            // Une entité vue pour la première fois, ou un score remis à zéro, ne rapporte rien
+            if let Some(previous) = self.scores.insert(entity.id, entity.score) {
+                if entity.score > previous {
+                    let kind = EffectKind::Score { x: entity.x, y: entity.y, points: entity.score - previous };
+                    self.push(kind, now, AppDefines::SCORE_POPUP_MS);
+                }
+            }
+        }
+        self.scores.retain(|id, _| entities.iter().any(|entity| entity.id == *id));
+    }
+
+    /// Stops the effects and forgets the scores, the hits happening while disabled are not shown later.
+    pub fn clear(&mut self) {
+        self.cursor = None;
+        self.scores.clear();
+        self.effects.clear();
+    }
+
+    /// Returns whether an entity was just struck and is drawn lighter.
+    pub fn is_flashing(&self, id: u32) -> bool {
+        self.effects.iter().any(|effect| matches!(effect.kind, EffectKind::Flash { id: flashed } if flashed == id))
+    }
+
+    /// Returns a color lightened for the flash of an entity struck.
+    pub fn flash_color(color: Color32) -> Color32 {
+        let lighten = |channel: u8| channel + ((255 - channel) as f32 * 0.6) as u8;
+        Color32::from_rgb(lighten(color.r()), lighten(color.g()), lighten(color.b()))
+    }
+
+    /// Draws the rings of the impacts and the points won.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `now`: The current time.
+    pub fn draw(&self, plot_ui: &mut PlotUi, now: Instant) {
+        for effect in &self.effects {
+            let progress = effect.progress(now);
+            match effect.kind {
+                EffectKind::Ring { x, y } => {
+                    let radius = AppDefines::HIT_RING_RADIUS * (0.3 + 0.7 * progress as f64);
+                    let ring: Vec<[f64; 2]> = (0..=16)
+                        .map(|i| {
+                            let angle = i as f64 * std::f64::consts::TAU / 16.0;
+                            [x as f64 + radius * angle.cos(), y as f64 + radius * angle.sin()]
+                        })
+                        .collect();
+                    plot_ui.line(Line::new(PlotPoints::new(ring)).color(RING_COLOR.gamma_multiply(1.0 - progress)).width(2.0));
+                }
+                EffectKind::Score { x, y, points } => {
+                    let rise = AppDefines::SCORE_POPUP_RISE * progress as f64;
+                    plot_ui.text(
+                        Text::new(PlotPoint::new(x as f64, y as f64 + AppDefines::HEALTH_BAR_OFFSET + rise), format!("+{}", points))
+                            .color(SCORE_COLOR.gamma_multiply(1.0 - progress))
+                            .anchor(Align2::CENTER_BOTTOM),
+                    );
+                }
+                EffectKind::Flash { .. } => {}
+            }
+        }
+    }
+}
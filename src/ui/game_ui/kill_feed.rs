@@ -0,0 +1,163 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+use eframe::egui::{self, Color32, FontId, Rect, Ui};
+use egui::text::{LayoutJob, TextFormat};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::event_queue::EventQueue;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::replay::EntityFrame;
+
+/// The color of the lines without entity, and of the words between the names.
+const TEXT_COLOR: Color32 = Color32::from_rgb(220, 220, 220);
+/// The color of the end of a round.
+const ROUND_COLOR: Color32 = Color32::from_rgb(255, 215, 90);
+/// The color of the points lost to a rule of the match.
+const PENALTY_COLOR: Color32 = Color32::from_rgb(240, 110, 100);
+/// The color of the points earned from a rule of the match.
+const BONUS_COLOR: Color32 = Color32::from_rgb(120, 220, 120);
+
+/// One line of the kill feed.
+struct FeedLine {
+    /// The parts of the line and their colors, the names in the colors of their entities.
+    parts: Vec<(String, Color32)>,
+    /// When the line was added.
+    added_at: Instant,
+}
+
+/// The last kills, pick-ups, score changes and rounds, in the top-right corner of the arena.
+///
+/// The feed reads the event queue with its own cursor, like the server and the clients, so
+/// the other readers still get every event. The entities are often gone when their kill is
+/// read: their names and colors are kept from the frames drawn until they leave the arena.
+pub struct KillFeed {
+    /// Whether the feed is shown and reads the events.
+    pub visible: bool,
+    /// The number of the last event read, `None` until the feed is shown.
+    cursor: Option<u64>,
+    /// The names and colors of the entities, by id.
+    entities: HashMap<u32, (String, Color32)>,
+    /// The lines shown, oldest first.
+    lines: VecDeque<FeedLine>,
+}
+
+impl KillFeed {
+    /// Creates a visible, empty feed.
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            cursor: None,
+            entities: HashMap::new(),
+            lines: VecDeque::with_capacity(AppDefines::KILL_FEED_LINES + 1),
+        }
+    }
+
+    /// Adds the events not read yet and drops the lines shown for `KILL_FEED_SECS`.
+    ///
+    /// # Parameters
+    /// - `events`: The event queue of the game logic, left as is.
+    /// - `entities`: The entities of the frame drawn.
+    /// - `now`: The current time.
+    pub fn update(&mut self, events: &EventQueue, entities: &[EntityFrame], now: Instant) {
+        for entity in entities {
+            let color = Color32::from_rgb(entity.color[0], entity.color[1], entity.color[2]);
+            match self.entities.get_mut(&entity.id) {
+                // Un nom inchangé n'est pas recopié : rien n'est alloué d'une image à l'autre
+                Some(known) if known.0 == entity.name => known.1 = color,
+                Some(known) => *known = (entity.name.clone(), color),
+                None => {
+                    self.entities.insert(entity.id, (entity.name.clone(), color));
+                }
+            }
+        }
+
+        // Affiché pour la première fois : les événements d'avant ne sont pas repris
+        let cursor = *self.cursor.get_or_insert(events.cursor());
+        for queued in events.since(cursor) {
+            let parts = match &queued.event {
+                GameEvent::Kill { shooter, victim, .. } if shooter == victim => {
+                    vec![self.name(*victim), ("was destroyed".to_string(), TEXT_COLOR)]
+                }
+                GameEvent::Kill { shooter, victim, .. } => {
+                    vec![self.name(*shooter), ("destroyed".to_string(), TEXT_COLOR), self.name(*victim)]
+                }
+                GameEvent::PickUp { id, power_up, .. } => {
+                    vec![self.name(*id), (format!("picked up {}", power_up), TEXT_COLOR)]
+                }
+                GameEvent::Penalty { id, points, reason } => {
+                    vec![self.name(*id), (format!("-{} for {}", points, reason.text()), PENALTY_COLOR)]
+                }
+                GameEvent::Bonus { id, points, reason } => {
+                    vec![self.name(*id), (format!("+{} for {}", points, reason.text()), BONUS_COLOR)]
+                }
+                GameEvent::RoundStart { round } => vec![(format!("Round {} started", round), TEXT_COLOR)],
+                GameEvent::MatchEnd { .. } => vec![(queued.text.clone(), ROUND_COLOR)],
+                GameEvent::Despawn { id } => {
+                    self.entities.remove(id);
+                    continue;
+                }
+                _ => continue,
+            };
+            self.lines.push_back(FeedLine { parts, added_at: now });
+            if self.lines.len() > AppDefines::KILL_FEED_LINES {
+                self.lines.pop_front();
+            }
+        }
+        self.cursor = Some(events.cursor());
+
+        while self.lines.front().is_some_and(|line| now.saturating_duration_since(line.added_at).as_secs_f32() >= AppDefines::KILL_FEED_SECS) {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Forgets the lines and the entities, the events happening while the feed is hidden are not shown later.
+    pub fn clear(&mut self) {
+        self.cursor = None;
+        self.entities.clear();
+        self.lines.clear();
+    }
+
+    /// Returns the name of an entity, shortened to `KILL_FEED_NAME_CHARS` characters, and its color.
+    fn name(&self, id: u32) -> (String, Color32) {
+        let Some((name, color)) = self.entities.get(&id) else {
+            return (format!("#{}", id), TEXT_COLOR);
+        };
+        if name.chars().count() <= AppDefines::KILL_FEED_NAME_CHARS {
+            return (name.clone(), *color);
+        }
+        let mut short: String = name.chars().take(AppDefines::KILL_FEED_NAME_CHARS - 1).collect();
+        short.push('…');
+        (short, *color)
+    }
+
+    /// Draws the lines in the top-right corner of the arena, newest at the bottom, fading out during their last second.
+    ///
+    /// # Parameters
+    /// - `ui`: The panel of the arena.
+    /// - `rect`: The rectangle of the plot of the arena.
+    /// - `now`: The current time.
+    pub fn show(&self, ui: &Ui, rect: Rect, now: Instant) {
+        if self.lines.is_empty() {
+            return;
+        }
+        let painter = ui.painter_at(rect);
+        let font = FontId::proportional(14.0);
+        let mut y = rect.top() + 10.0;
+        for line in &self.lines {
+            let left = AppDefines::KILL_FEED_SECS - now.saturating_duration_since(line.added_at).as_secs_f32();
+            let opacity = left.clamp(0.0, 1.0);
+            let mut job = LayoutJob::default();
+            for (index, (text, color)) in line.parts.iter().enumerate() {
+                let leading_space = if index == 0 { 0.0 } else { 4.0 };
+                job.append(text, leading_space, TextFormat::simple(font.clone(), color.gamma_multiply(opacity)));
+            }
+            let galley = painter.layout_job(job);
+            let position = egui::pos2(rect.right() - 10.0 - galley.size().x, y);
+            let background = Rect::from_min_size(position, galley.size()).expand(3.0);
+            painter.rect_filled(background, 3.0, Color32::from_black_alpha((160.0 * opacity) as u8));
+            painter.galley(position, galley, TEXT_COLOR);
+            y += background.height() + 2.0;
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use eframe::egui::{self, PointerButton};
+use egui_plot::{Line, LineStyle, PlotPoints, PlotUi};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::command::GameCommands;
+use crate::game_logic::map_file::MapObstacle;
+use crate::obstacles::ObstacleShape;
+use crate::types::{add_message, MessageHub, MessageOrigin, MessageType};
+
+use super::status_bar::StatusBar;
+
+/// The shapes of the palette of the map editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaletteShape {
+    Square,
+    Circle,
+    Wall,
+}
+
+/// An edit of the obstacles, with what it takes to undo it.
+#[derive(Debug, Clone)]
+enum MapEdit {
+    /// An obstacle was placed at this index.
+    Added(usize),
+    /// The obstacle at this index was removed.
+    Removed(usize, MapObstacle),
+    /// The obstacle at this index was dragged from this position.
+    Moved(usize, (f32, f32)),
+}
+
+/// The mode of the game UI editing the obstacles of the map with the mouse.
+///
+/// A left click places an obstacle of the shape of the palette, a right click removes the
+/// obstacle under the pointer and a drag moves it. The simulation is paused while editing,
+/// and the last `MAP_EDITOR_UNDO_LIMIT` edits can be undone.
+pub struct MapEditor {
+    /// Whether the map is being edited.
+    pub active: bool,
+    shape: PaletteShape,
+    /// The half size of a square, the radius of a disc or the half length of a wall, in world units.
+    size: f32,
+    /// The rotation of a wall, in degrees.
+    angle_degrees: f32,
+    /// Whether the simulation ran before the editing started, and runs again once it ends.
+    resume_on_exit: bool,
+    /// The index of the obstacle being dragged.
+    dragged: Option<usize>,
+    /// The edits that can be undone, oldest first.
+    history: VecDeque<MapEdit>,
+}
+
+impl MapEditor {
+    /// Creates an inactive editor placing squares of the default size.
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            shape: PaletteShape::Square,
+            size: AppDefines::OBSTACLE_HALF_SIZE,
+            angle_degrees: 0.0,
+            resume_on_exit: false,
+            dragged: None,
+            history: VecDeque::with_capacity(AppDefines::MAP_EDITOR_UNDO_LIMIT),
+        }
+    }
+
+    /// Starts editing, pausing the simulation.
+    pub fn start(&mut self, commands: &GameCommands) {
+        self.active = true;
+        self.resume_on_exit = commands.update(|game_logic| {
+            let running = !game_logic.is_paused();
+            game_logic.pause();
+            running
+        });
+        self.history.clear();
+    }
+
+    /// Stops editing, resuming the simulation unless it was paused before.
+    pub fn stop(&mut self, commands: &GameCommands) {
+        self.active = false;
+        self.dragged = None;
+        self.history.clear();
+        if self.resume_on_exit {
+            commands.post(|game_logic| game_logic.resume());
+        }
+    }
+
+    /// Drops the edits to undo, once the obstacles were replaced by another map.
+    pub fn forget_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Returns the obstacle shape of the palette.
+    fn shape(&self) -> ObstacleShape {
+        match self.shape {
+            PaletteShape::Square => ObstacleShape::Square { half: self.size },
+            PaletteShape::Circle => ObstacleShape::Circle { radius: self.size },
+            PaletteShape::Wall => ObstacleShape::Wall {
+                half_length: self.size,
+                half_thickness: AppDefines::OBSTACLE_WALL_HALF_THICKNESS,
+                angle: self.angle_degrees.to_radians(),
+            },
+        }
+    }
+
+    /// Keeps an edit to undo, forgetting the oldest past `MAP_EDITOR_UNDO_LIMIT`.
+    fn record(&mut self, edit: MapEdit) {
+        if self.history.len() == AppDefines::MAP_EDITOR_UNDO_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(edit);
+    }
+
+    /// Undoes the last edit.
+    ///
+    /// The edits are undone in reverse order, so the indices they kept still point to the same obstacles.
+    /// If the map changed otherwise in between, the edit cannot be undone and the history is dropped.
+    fn undo(&mut self, status_bar: &StatusBar, commands: &GameCommands, messages: &MessageHub) {
+        let Some(edit) = self.history.pop_back() else {
+            return;
+        };
+        let undone = status_bar.wait(|| commands.update(move |game_logic| match edit {
+            MapEdit::Added(index) => game_logic.remove_obstacle(index).is_some(),
+            MapEdit::Removed(index, obstacle) => game_logic.insert_obstacle(index, obstacle),
+            MapEdit::Moved(index, (x, y)) => game_logic.move_obstacle(index, x, y),
+        }));
+        if !undone {
+            add_message(messages, "[WARNING] The map changed outside the editor, its edits can no longer be undone.".to_string(), MessageType::Warning, MessageOrigin::Ui);
+            self.history.clear();
+        }
+    }
+
+    /// Places, removes or moves the obstacles with the mouse.
+    ///
+    /// A command is only sent to the game loop when a button is pressed or an obstacle dragged.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in, which must not pan while editing.
+    /// - `status_bar`: Times the wait for the game loop, see `StatusBar::wait`.
+    /// - `commands`: The way into the game logic edited.
+    pub fn handle_input(&mut self, plot_ui: &PlotUi, status_bar: &StatusBar, commands: &GameCommands) {
+        let response = plot_ui.response();
+        let to_world = |pointer: egui::Pos2| {
+            let point = plot_ui.plot_from_screen(pointer);
+            (point.x as f32, point.y as f32)
+        };
+
+        if response.drag_started_by(PointerButton::Primary) {
+            // Le glissement commence après quelques points : l'obstacle est pris là où le bouton a été enfoncé
+            let Some(origin) = response.ctx.input(|input| input.pointer.press_origin()) else {
+                return;
+            };
+            let (x, y) = to_world(origin);
+            let grabbed = status_bar.wait(|| commands.query(move |game_logic| {
+                game_logic.obstacle_at(x, y).map(|index| (index, game_logic.obstacles[index].position))
+            }));
+            self.dragged = grabbed.map(|(index, _)| index);
+            if let Some((index, position)) = grabbed {
+                self.record(MapEdit::Moved(index, (position.0 as f32, position.1 as f32)));
+            }
+        } else if response.drag_stopped() {
+            self.dragged = None;
+        } else if let Some(index) = self.dragged {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let (x, y) = to_world(pointer);
+                // Sans attendre : l'obstacle suit la souris à l'image suivante
+                commands.post(move |game_logic| {
+                    game_logic.move_obstacle(index, x, y);
+                });
+            }
+        } else if let Some(pointer) = response.interact_pointer_pos() {
+            let (x, y) = to_world(pointer);
+            if response.clicked() {
+                let shape = self.shape();
+                if let Some(index) = status_bar.wait(|| commands.update(move |game_logic| game_logic.add_obstacle_at(x, y, shape))) {
+                    self.record(MapEdit::Added(index));
+                }
+            } else if response.secondary_clicked() {
+                let removed = status_bar.wait(|| commands.update(move |game_logic| {
+                    game_logic.obstacle_at(x, y).and_then(|index| Some((index, game_logic.remove_obstacle(index)?)))
+                }));
+                if let Some((index, obstacle)) = removed {
+                    self.record(MapEdit::Removed(index, obstacle));
+                }
+            }
+        }
+    }
+
+    /// Draws the outline of the obstacle a click would place, under the pointer.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    pub fn draw_preview(&self, plot_ui: &mut PlotUi) {
+        if self.dragged.is_some() {
+            return;
+        }
+        let Some(pointer) = plot_ui.response().hover_pos() else {
+            return;
+        };
+        let center = plot_ui.plot_from_screen(pointer);
+        let outline = self.shape().outline((center.x, center.y));
+        plot_ui.line(
+            Line::new(PlotPoints::new(outline))
+                .color(egui::Color32::from_gray(180))
+                .style(LineStyle::dashed_dense()),
+        );
+    }
+
+    /// Shows the palette, the undo button and the map file buttons.
+    ///
+    /// # Parameters
+    /// - `ui`: The side panel of the editor.
+    /// - `status_bar`: Times the wait for the game loop, see `StatusBar::wait`.
+    /// - `commands`: The way into the game logic edited.
+    /// - `messages`: The messages of the server, where the map files saved and loaded are reported.
+    /// - `map_path`: The map file the map is saved to and loaded from.
+    pub fn show_panel(&mut self, ui: &mut egui::Ui, status_bar: &StatusBar, commands: &GameCommands, messages: &MessageHub, map_path: &mut String) {
+        ui.heading("Map Editor");
+        ui.label("Left click: place. Right click: remove. Drag: move.");
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.shape, PaletteShape::Square, "Square");
+            ui.radio_value(&mut self.shape, PaletteShape::Circle, "Circle");
+            ui.radio_value(&mut self.shape, PaletteShape::Wall, "Wall");
+        });
+        let size_label = match self.shape {
+            PaletteShape::Square => "Half size",
+            PaletteShape::Circle => "Radius",
+            PaletteShape::Wall => "Half length",
+        };
+        ui.add(egui::Slider::new(&mut self.size, AppDefines::OBSTACLE_WALL_HALF_THICKNESS..=AppDefines::OBSTACLE_WALL_MAX_HALF_LENGTH * 2.0).text(size_label));
+        ui.add_enabled(
+            self.shape == PaletteShape::Wall,
+            egui::Slider::new(&mut self.angle_degrees, 0.0..=180.0).suffix("°").text("Angle"),
+        );
+        ui.separator();
+
+        let undo_shortcut = ui.input_mut(|input| input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z));
+        let undo_button = ui.add_enabled(!self.history.is_empty(), egui::Button::new(format!("Undo ({})", self.history.len())));
+        if undo_button.on_hover_text("Ctrl+Z").clicked() || undo_shortcut {
+            self.undo(status_bar, commands, messages);
+        }
+        ui.separator();
+
+        ui.add(egui::TextEdit::singleline(map_path).hint_text("Map file"));
+        ui.horizontal(|ui| {
+            if ui.button("Save Map").clicked() {
+                let path = map_path.clone();
+                match status_bar.wait(|| commands.query(move |game_logic| game_logic.save_map(Path::new(&path)))) {
+                    Ok(()) => add_message(messages, format!("[INFO] Map saved to {}", map_path), MessageType::Info, MessageOrigin::Ui),
+                    Err(e) => add_message(messages, format!("[WARNING] Could not save the map to {}: {}", map_path, e), MessageType::Warning, MessageOrigin::Ui),
+                }
+            }
+            if ui.button("Load Map").clicked() {
+                let path = map_path.clone();
+                match status_bar.wait(|| commands.update(move |game_logic| game_logic.load_map(Path::new(&path)))) {
+                    // Les indices gardés ne désignent plus les mêmes obstacles
+                    Ok(()) => self.forget_history(),
+                    Err(e) => add_message(messages, format!("[WARNING] Could not load the map from {}: {}", map_path, e), MessageType::Warning, MessageOrigin::Ui),
+                }
+            }
+        });
+        ui.separator();
+        if ui.button("Done").clicked() {
+            self.stop(commands);
+        }
+    }
+}
@@ -1,56 +1,506 @@
+use std::path::Path;
+use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use eframe::egui;
 use egui::{Align2, Context, Stroke, TopBottomPanel};
 use egui_extras::*;
 use egui_plot::*;
 
+use crate::app_defines::AppDefines;
+use crate::bullet::bullet::BulletKind;
+use crate::game_logic::ai_behavior::AiDifficulty;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::command::GameCommands;
+use crate::game_logic::control_point::Side;
+use crate::game_logic::game_config::GameMode;
+use crate::game_logic::ghost_camera::GhostCamera;
+use crate::game_logic::hazard::HazardEffect;
+use crate::game_logic::map_file::MapHazard;
+use crate::game_logic::match_state::MatchPhase;
+use crate::game_logic::render_snapshot::{AiIntent, GameControls, InspectedEntity, MineFrame, PowerUpFrame, RenderSnapshot};
+use crate::game_logic::replay::{ControlPointFrame, EntityFrame, ObstacleFrame, Replay, ReplayPlayer};
 use crate::game_logic::GameLogic;
+use crate::game_logic::game_loop::GameLoop;
+use crate::power_ups::PowerUpKind;
+use crate::server::metrics::ServerMetrics;
+use crate::types::{add_message, MessageHub, MessageOrigin, MessageType};
+
+mod bullet_trails;
+mod grid;
+mod hit_effects;
+mod kill_feed;
+mod map_editor;
+mod movement_trails;
+mod score_history;
+mod status_bar;
+pub(crate) mod ui_settings;
+
+use bullet_trails::BulletTrails;
+use grid::Grid;
+use hit_effects::HitEffects;
+use kill_feed::KillFeed;
+use map_editor::MapEditor;
+use movement_trails::MovementTrails;
+use score_history::ScoreHistory;
+use status_bar::StatusBar;
+use ui_settings::{LabelConfig, UiSettings, ViewSettings};
+
+/// The totals of the members of a team in the arena, shown above the scoreboard.
+struct TeamTotals {
+    team: u8,
+    members: usize,
+    score: i32,
+    kills: u32,
+}
+
+impl TeamTotals {
+    /// Sums the entities of each team, live or replayed.
+    ///
+    /// # Returns
+    /// The totals by increasing team. Entities without a team are left out.
+    fn of(entities: &[EntityFrame]) -> Vec<TeamTotals> {
+        let mut teams: Vec<TeamTotals> = Vec::new();
+        for entity in entities {
+            let Some(team) = entity.team else { continue };
+            let index = match teams.binary_search_by_key(&team, |totals| totals.team) {
+                Ok(index) => index,
+                Err(index) => {
+                    teams.insert(index, TeamTotals { team, members: 0, score: 0, kills: 0 });
+                    index
+                }
+            };
+            let totals = &mut teams[index];
+            totals.members += 1;
+            totals.score += entity.score;
+            totals.kills += entity.breakdown.kills;
+        }
+        teams
+    }
+}
+
+/// An action of the menu that throws away the match in progress, run once confirmed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SimulationAction {
+    /// Resets the simulation, see `GameLogic::reset_simulation`.
+    Reset,
+    /// Generates a new map, from the seed typed or from a random one.
+    GenerateMap(Option<u64>),
+}
+
+/// The entity the next click in the arena places, see `GameUI::handle_placement`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placement {
+    /// An entity driven by a player.
+    Entity,
+    /// An entity driven by the default AI behavior, at this difficulty.
+    Ai(AiDifficulty),
+}
 
 /// Represents the user interface for the game.
 pub struct GameUI {
-    game_logic: Arc<Mutex<GameLogic>>,
-    line_thickness: f32,
-    show_names: bool,
-    show_background: bool,
+    /// The way into the game logic: the changes are sent to the game loop, the live game is drawn
+    /// from its render snapshots, so the UI never locks the game logic.
+    commands: GameCommands,
+    /// The state of the game the menus show and edit, copied from the last render snapshot.
+    controls: GameControls,
+    /// When the menus last sent a change of `controls`, kept shown until a snapshot taken after it.
+    controls_edited_at: Option<Instant>,
+    /// The grid, the boundary and the thickness of the lines of the arena.
+    view: ViewSettings,
+    /// Whether the 'View Settings' window is open.
+    show_view_settings: bool,
+    /// The lines of the background grid, computed when its spacing or the arena changes.
+    grid: Grid,
+    /// What the label above each entity shows.
+    labels: LabelConfig,
+    /// Whether the mines are drawn, a debug view: they are hidden from the players.
+    show_mines: bool,
+    /// Whether the destination and the aim of each AI entity are drawn, a debug view of the AI behaviors.
+    show_ai_intents: bool,
+    /// Whether the ghost cameras of the spectators are drawn, a debug view: no entity sees them.
+    show_ghost_cameras: bool,
+    /// Whether the bullets of the live game leave a fading trail.
+    show_trails: bool,
+    /// The number of past positions in each trail, at most `MAX_BULLET_TRAIL`.
+    trail_length: usize,
+    /// The last positions of the bullets, for their trails.
+    trails: BulletTrails,
+    /// Whether the path each entity took is drawn behind it, see `TrailStore`.
+    show_movement_trails: bool,
+    movement_trails: MovementTrails,
+    /// Whether the gun of each entity is drawn, pointing where its bullets go.
+    show_barrels: bool,
+    /// Whether a health bar is drawn above each entity.
+    show_health_bars: bool,
+    /// Whether the health bars of the entities at full health are left out.
+    hide_full_health_bars: bool,
+    /// The seed typed for the next map, a random seed is drawn when empty.
+    seed_input: String,
+    /// The file the state of the match is saved to and loaded from.
+    snapshot_path: String,
+    /// The map file loaded and saved by the map buttons.
+    map_path: String,
+    /// The file replays are saved to and opened from.
+    replay_path: String,
+    /// The replay being played back, shown instead of the live game.
+    player: Option<ReplayPlayer>,
+    /// The id of the entity the camera follows, `None` for the free camera.
+    followed: Option<u32>,
+    /// How many times the arena is wider than the view while following, see `DEFAULT_FOLLOW_ZOOM`.
+    follow_zoom: f32,
+    /// Whether dragging the plot broke the follow, until it is resumed.
+    follow_paused: bool,
+    /// Whether the next frame applies `follow_zoom`, the view otherwise keeps the zoom of the mouse wheel.
+    follow_zoom_changed: bool,
+    /// Whether the next frame shows the whole arena again, once the follow ended.
+    reset_camera: bool,
+    /// The id of the entity shown in the inspector, chosen by a click in the arena.
+    selected: Option<u32>,
+    /// Whether the next click in the arena moves the selected entity there.
+    teleport_mode: bool,
+    /// The new name typed in the inspector.
+    rename_input: String,
+    /// The entity driven with the keyboard, see `drive_controlled`.
+    controlled: Option<u32>,
+    /// The entity the next click in the arena places, `None` when no placement is armed.
+    placement: Option<Placement>,
+    /// Where the drag aiming the entity being placed started, in world units.
+    placement_origin: Option<PlotPoint>,
+    /// A short message shown over the arena, and when it was shown.
+    toast: Option<(String, Instant)>,
+    /// The metrics recorded by the game loop, with the speed it actually reaches.
+    metrics: ServerMetrics,
+    /// The frame rate, the simulation metrics and the time spent waiting for the game logic.
+    status_bar: StatusBar,
+    /// The last kills, pick-ups, score changes and rounds, read from the event queue of the game logic.
+    kill_feed: KillFeed,
+    /// The scores of the entities over the simulated time, and the changes the events tell.
+    score_history: ScoreHistory,
+    /// The impact rings, flashes and points won, read from the event queue of the game logic.
+    hit_effects: HitEffects,
+    /// The mode editing the obstacles of the map with the mouse.
+    map_editor: MapEditor,
+    /// The action waiting for the confirmation of the user.
+    pending_action: Option<SimulationAction>,
+    /// The messages of the server, where the files saved and loaded are reported.
+    messages: MessageHub,
 }
 
 impl GameUI {
-    pub fn new(game_logic: Arc<Mutex<GameLogic>>) -> Self {
+    /// Creates the game UI.
+    ///
+    /// # Parameters
+    /// - `commands`: The way into the game logic stepped by the game loop.
+    /// - `metrics`: The metrics recorded by the game loop, shown in the status bar.
+    /// - `messages`: The messages of the server, where the files saved and loaded are reported.
+    pub fn new(commands: GameCommands, metrics: ServerMetrics, messages: MessageHub) -> Self {
+        let controls = commands.render().latest().controls.clone();
         Self {
-            game_logic,
-            line_thickness: 4.0,
-            show_names: true,
-            show_background: true,
+            commands,
+            controls,
+            controls_edited_at: None,
+            view: ViewSettings::default(),
+            show_view_settings: false,
+            grid: Grid::default(),
+            labels: LabelConfig::default(),
+            show_mines: false,
+            show_ai_intents: false,
+            show_ghost_cameras: false,
+            show_trails: true,
+            trail_length: AppDefines::DEFAULT_BULLET_TRAIL,
+            trails: BulletTrails::default(),
+            show_movement_trails: false,
+            movement_trails: MovementTrails::default(),
+            show_barrels: true,
+            show_health_bars: true,
+            hide_full_health_bars: false,
+            seed_input: String::new(),
+            snapshot_path: AppDefines::DEFAULT_SNAPSHOT_FILE.to_string(),
+            map_path: AppDefines::DEFAULT_MAP_FILE.to_string(),
+            replay_path: AppDefines::DEFAULT_REPLAY_FILE.to_string(),
+            player: None,
+            followed: None,
+            follow_zoom: AppDefines::DEFAULT_FOLLOW_ZOOM,
+            follow_paused: false,
+            follow_zoom_changed: false,
+            reset_camera: false,
+            selected: None,
+            teleport_mode: false,
+            rename_input: String::new(),
+            controlled: None,
+            placement: None,
+            placement_origin: None,
+            toast: None,
+            status_bar: StatusBar::new(metrics.clone()),
+            kill_feed: KillFeed::new(),
+            score_history: ScoreHistory::new(),
+            hit_effects: HitEffects::new(),
+            map_editor: MapEditor::new(),
+            metrics,
+            pending_action: None,
+            messages,
         }
     }
 
-    fn draw_obstacles(&self, plot_ui: &mut PlotUi) {
-        // On verrouille game_logic ici
-        let game_logic = self.game_logic.lock().unwrap();
+    /// Applies display options kept from a previous run.
+    ///
+    /// # Parameters
+    /// - `settings`: The display options, see `settings`.
+    pub fn apply_settings(&mut self, settings: &UiSettings) {
+        self.labels = settings.labels;
+        self.view = settings.view;
+        self.view.line_thickness = self.view.line_thickness.clamp(1.0, 20.0);
+        self.view.grid_spacing = self.view.grid_spacing.clamp(AppDefines::MIN_GRID_SPACING, AppDefines::MAX_GRID_SPACING);
+        self.show_trails = settings.show_trails;
+        self.trail_length = settings.trail_length.clamp(1, AppDefines::MAX_BULLET_TRAIL);
+        self.show_movement_trails = settings.show_movement_trails;
+        self.show_barrels = settings.show_barrels;
+        self.show_health_bars = settings.show_health_bars;
+        self.hide_full_health_bars = settings.hide_full_health_bars;
+        self.status_bar.visible = settings.show_status_bar;
+        self.kill_feed.visible = settings.show_kill_feed;
+        self.hit_effects.enabled = settings.show_hit_effects;
+    }
 
-        for obstacle in &game_logic.obstacles {
-            let position = obstacle.position;
-            let line_thickness = self.line_thickness / 2.0;
+    /// Returns the current display options, to be kept for the next run.
+    pub fn settings(&self) -> UiSettings {
+        UiSettings {
+            labels: self.labels,
+            view: self.view,
+            show_trails: self.show_trails,
+            trail_length: self.trail_length,
+            show_movement_trails: self.show_movement_trails,
+            show_barrels: self.show_barrels,
+            show_health_bars: self.show_health_bars,
+            hide_full_health_bars: self.hide_full_health_bars,
+            show_status_bar: self.status_bar.visible,
+            show_kill_feed: self.kill_feed.visible,
+            show_hit_effects: self.hit_effects.enabled,
+        }
+    }
 
-            let diamond_points = vec![
-                [position.0, position.1 - 10.0],
-                [position.0 - 10.0, position.1],
-                [position.0, position.1 + 10.0],
-                [position.0 + 10.0, position.1],
-                [position.0, position.1 - 10.0], // close the diamond shape
-            ];
+    /// Draws the obstacles of the arena.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `obstacles`: The obstacles, with their shape.
+    fn draw_obstacles(&self, plot_ui: &mut PlotUi, obstacles: &[ObstacleFrame]) {
+        for obstacle in obstacles {
+            let line_thickness = self.view.line_thickness / 2.0;
 
             plot_ui.line(
-                Line::new(PlotPoints::new(diamond_points))
+                Line::new(PlotPoints::new(obstacle.shape.outline((obstacle.x, obstacle.y))))
                     .color(egui::Color32::GREEN)
                     .width(line_thickness),
             );
         }
     }
 
-    fn display_entities(&self, plot_ui: &mut PlotUi) {
-        let game_logic = self.game_logic.lock().unwrap();
+    /// Draws the bullets, each kind with its own point size.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `bullets`: The positions of the bullets.
+    /// - `kinds`: The kind of each bullet, empty when they are all standard bullets.
+    /// - `radius`: The size of the point of a standard bullet.
+    fn draw_bullets(&self, plot_ui: &mut PlotUi, bullets: &[(f32, f32)], kinds: &[BulletKind], radius: f32) {
+        for kind in BulletKind::ALL {
+            let points: Vec<[f64; 2]> = bullets.iter().enumerate()
+                .filter(|(index, _)| kinds.get(*index).copied().unwrap_or_default() == kind)
+                .map(|(_, &(x, y))| [x as f64, y as f64])
+                .collect();
+            if points.is_empty() {
+                continue;
+            }
+            // Les balles de sniper, plus grosses, se distinguent des plombs de la dispersion
+            let (scale, name) = match kind {
+                BulletKind::Standard => (1.0, "Bullets"),
+                BulletKind::Sniper => (1.6, "Sniper bullets"),
+                BulletKind::Scatter => (0.6, "Scatter bullets"),
+            };
+            plot_ui.points(Points::new(points).radius(radius * scale).name(name));
+        }
+    }
+
+    /// Draws the border of the safe zone.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `zone`: The center and the radius of the zone.
+    /// - `arena`: The arena the border is cut to.
+    fn draw_zone(&self, plot_ui: &mut PlotUi, zone: (f32, f32, f32), arena: &ArenaConfig) {
+        let (x, y, radius) = zone;
+        let border: Vec<[f64; 2]> = (0..=64)
+            .map(|i| {
+                let angle = i as f64 * std::f64::consts::TAU / 64.0;
+                // Rabattue sur les murs : la partie sûre de l'arène, sans agrandir la vue
+                let (x, y) = arena.clamp(x + radius * angle.cos() as f32, y + radius * angle.sin() as f32, 0.0);
+                [x as f64, y as f64]
+            })
+            .collect();
+        plot_ui.line(
+            Line::new(PlotPoints::new(border))
+                .color(egui::Color32::from_rgb(200, 60, 60))
+                .name("Zone")
+                .width(self.view.line_thickness / 2.0)
+                .style(LineStyle::dashed_loose()),
+        );
+    }
+
+    /// Draws the hazards as translucent regions, red for the damage zones and blue for the slow fields.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `hazards`: The hazards of the frame.
+    fn draw_hazards(&self, plot_ui: &mut PlotUi, hazards: &[MapHazard]) {
+        for hazard in hazards {
+            let (color, name) = match hazard.effect {
+                HazardEffect::Damage { .. } => (egui::Color32::from_rgb(220, 50, 50), "Damage zones"),
+                HazardEffect::Slow { .. } => (egui::Color32::from_rgb(60, 120, 230), "Slow fields"),
+            };
+            plot_ui.polygon(
+                Polygon::new(hazard.shape.outline((hazard.x as f64, hazard.y as f64)))
+                    .fill_color(color.gamma_multiply(0.2))
+                    .stroke(Stroke::new(self.view.line_thickness / 3.0, color.gamma_multiply(0.6)))
+                    .name(name),
+            );
+        }
+    }
+
+    /// Draws the control points as circles in the color of their owner, gray before a side
+    /// enters them, each with an arc growing with the capture.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `points`: The control points of the frame.
+    /// - `entities`: The entities of the frame, for the color of an owner without a team.
+    fn draw_control_points(&self, plot_ui: &mut PlotUi, points: &[ControlPointFrame], entities: &[EntityFrame]) {
+        let circle = |x: f32, y: f32, radius: f32, turns: f32| -> Vec<[f64; 2]> {
+            let segments = (64.0 * turns).ceil().max(1.0) as usize;
+            (0..=segments)
+                .map(|i| {
+                    // Depuis le haut, dans le sens des aiguilles d'une montre
+                    let angle = std::f64::consts::FRAC_PI_2 - i as f64 / segments as f64 * turns as f64 * std::f64::consts::TAU;
+                    [x as f64 + radius as f64 * angle.cos(), y as f64 + radius as f64 * angle.sin()]
+                })
+                .collect()
+        };
+        for point in points {
+            let color = match point.owner {
+                Some(Side::Team(team)) => GameUI::team_color(team),
+                Some(Side::Entity(id)) => entities.iter()
+                    .find(|entity| entity.id == id)
+                    .map_or(egui::Color32::GRAY, |entity| egui::Color32::from_rgb(entity.color[0], entity.color[1], entity.color[2])),
+                None => egui::Color32::GRAY,
+            };
+            // Un point en cours de prise reste pâle, l'arc montre où en est la prise
+            let border = if point.progress >= 1.0 { color } else { color.gamma_multiply(0.5) };
+            plot_ui.line(
+                Line::new(PlotPoints::new(circle(point.x, point.y, point.radius, 1.0)))
+                    .color(border)
+                    .name("Control points")
+                    .width(self.view.line_thickness / 2.0),
+            );
+            if point.owner.is_some() && point.progress > 0.0 {
+                plot_ui.line(
+                    Line::new(PlotPoints::new(circle(point.x, point.y, point.radius * 0.85, point.progress.min(1.0))))
+                        .color(color)
+                        .width(self.view.line_thickness),
+                );
+            }
+            plot_ui.text(
+                Text::new(PlotPoint::new(point.x, point.y), &point.name)
+                    .color(border)
+                    .anchor(Align2::CENTER_CENTER),
+            );
+        }
+    }
+
+    fn draw_power_ups(&self, plot_ui: &mut PlotUi, power_ups: &[PowerUpFrame]) {
+        for kind in PowerUpKind::ALL {
+            let (shape, color) = match kind {
+                PowerUpKind::Heal => (MarkerShape::Plus, egui::Color32::RED),
+                PowerUpKind::SpeedBoost => (MarkerShape::Up, egui::Color32::LIGHT_BLUE),
+                PowerUpKind::RapidFire => (MarkerShape::Asterisk, egui::Color32::GOLD),
+                PowerUpKind::ArmorUp => (MarkerShape::Square, egui::Color32::GRAY),
+            };
+            let positions: Vec<[f64; 2]> = power_ups
+                .iter()
+                .filter(|power_up| power_up.kind == kind)
+                .map(|power_up| [power_up.position.0, power_up.position.1])
+                .collect();
+            plot_ui.points(
+                Points::new(positions)
+                    .shape(shape)
+                    .color(color)
+                    .radius(self.view.line_thickness * 2.0)
+                    .name(kind.name()),
+            );
+        }
+    }
+
+    /// Draws the mines in the color of their owner, filled once armed.
+    fn draw_mines(&self, plot_ui: &mut PlotUi, mines: &[MineFrame]) {
+        for mine in mines {
+            let color = mine.color.map_or(egui::Color32::LIGHT_GRAY, |[r, g, b]| egui::Color32::from_rgb(r, g, b));
+            plot_ui.points(
+                Points::new(vec![[mine.position.0, mine.position.1]])
+                    .shape(MarkerShape::Circle)
+                    .filled(mine.armed)
+                    .color(color)
+                    .radius(self.view.line_thickness * 1.5),
+            );
+        }
+    }
+
+    /// Draws each ghost camera as a small camera, its lens pointing right, with a dashed circle
+    /// around it when its view is cut to a radius.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `cameras`: The cameras published by the game.
+    fn draw_ghost_cameras(&self, plot_ui: &mut PlotUi, cameras: &[GhostCamera]) {
+        let color = egui::Color32::from_rgb(120, 200, 255);
+        for camera in cameras {
+            let (x, y) = (camera.x as f64, camera.y as f64);
+            // Le boîtier, puis l'objectif en trapèze sur sa droite
+            plot_ui.line(
+                Line::new(PlotPoints::new(vec![[x - 8.0, y - 5.0], [x + 4.0, y - 5.0], [x + 4.0, y + 5.0], [x - 8.0, y + 5.0], [x - 8.0, y - 5.0]]))
+                    .color(color)
+                    .width(self.view.line_thickness),
+            );
+            plot_ui.line(
+                Line::new(PlotPoints::new(vec![[x + 4.0, y - 2.0], [x + 9.0, y - 5.0], [x + 9.0, y + 5.0], [x + 4.0, y + 2.0]]))
+                    .color(color)
+                    .width(self.view.line_thickness),
+            );
+            plot_ui.text(
+                Text::new(PlotPoint::new(x, y + 12.0), format!("Cam {}", camera.id))
+                    .color(color)
+                    .anchor(Align2::CENTER_BOTTOM),
+            );
+            if camera.view_radius > 0.0 {
+                let radius = camera.view_radius as f64;
+                let circle: Vec<[f64; 2]> = (0..=64)
+                    .map(|i| {
+                        let angle = i as f64 / 64.0 * std::f64::consts::TAU;
+                        [x + radius * angle.cos(), y + radius * angle.sin()]
+                    })
+                    .collect();
+                plot_ui.line(Line::new(PlotPoints::new(circle)).color(color.gamma_multiply(0.5)).width(1.0).style(LineStyle::dashed_loose()));
+            }
+        }
+    }
 
+    /// Draws the entities, from the live game or from a replay.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `entities`: The entities to draw.
+    /// - `max_health`: The health of a fresh entity, a full health bar.
+    /// - `bullet_range`: How far the bullets fly, drawn from the gun of the selected entity; `None` in a replay.
+    fn display_entities(&self, plot_ui: &mut PlotUi, entities: &[EntityFrame], max_health: i32, bullet_range: Option<f32>) {
         fn offset_point(origin: [f64; 2], angle: f64, distance: f64) -> [f64; 2] {
             [
                 origin[0] + distance * angle.cos(),
@@ -58,12 +508,20 @@ impl GameUI {
             ]
         }
 
-        for entity in &game_logic.entities {
-            let body = &game_logic.physics_engine.bodies[entity.handle];
-            let pos = [body.translation().x as f64, body.translation().y as f64];
-            let angle = body.rotation().angle() as f64;
+        // Le texte garde sa taille à l'écran : au dézoom, la barre de vie s'allonge et s'écarte du nom en unités du monde
+        let units_per_point = plot_ui.transform().dvalue_dpos()[0].abs();
+        let units_per_point = if units_per_point.is_finite() { units_per_point } else { 0.0 };
+        let bar_length = AppDefines::HEALTH_BAR_LENGTH.max(AppDefines::HEALTH_BAR_MIN_POINTS * units_per_point);
+        let labelled_bar_offset = AppDefines::HEALTH_BAR_OFFSET + AppDefines::NAME_LABEL_POINTS * units_per_point.max(1.0);
+        // Trop dézoomé, les étiquettes se chevaucheraient : elles sont laissées de côté
+        let show_labels = plot_ui.plot_bounds().width() <= AppDefines::LABELS_MAX_VIEW_WIDTH;
+
+        for entity in entities {
+            let pos = [entity.x as f64, entity.y as f64];
+            let angle = entity.rotation as f64;
+            let color = egui::Color32::from_rgb(entity.color[0], entity.color[1], entity.color[2]);
 
-            let length = self.line_thickness as f64 * 4.0;
+            let length = self.view.line_thickness as f64 * 4.0;
             let half_base = length * 0.4;
 
             let nose = offset_point(pos, angle, length);
@@ -71,17 +529,87 @@ impl GameUI {
             let left  = offset_point(base_center, angle + std::f64::consts::FRAC_PI_2, half_base);
             let right = offset_point(base_center, angle - std::f64::consts::FRAC_PI_2, half_base);
 
+            // Une entité qui vient d'être touchée s'éclaircit, une entité protégée après son apparition est translucide
+            let fill = if self.hit_effects.is_flashing(entity.id) { HitEffects::flash_color(color) } else { color };
+            let fill = if entity.protected { fill.gamma_multiply(0.4) } else { fill };
             plot_ui.polygon(
                 Polygon::new(vec![nose, left, right])
-                    .fill_color(entity.color)
+                    .fill_color(fill)
                     .stroke(Stroke::NONE), // pas de contour => pointe parfaite
             );
+            // Le contour aux couleurs de l'équipe est tracé à part, la pointe du triangle reste nette
+            if let Some(team) = entity.team {
+                plot_ui.line(
+                    Line::new(PlotPoints::new(vec![nose, left, right, nose]))
+                        .color(GameUI::team_color(team))
+                        .width((self.view.line_thickness / 3.0).max(1.0)),
+                );
+            }
+
+            if self.show_barrels {
+                for ((x, y), gun_angle) in entity.barrels() {
+                    let (base, gun_angle) = ([x as f64, y as f64], gun_angle as f64);
+                    let muzzle = offset_point(base, gun_angle, length * 1.2);
+                    plot_ui.line(
+                        Line::new(PlotPoints::new(vec![base, muzzle]))
+                            .color(egui::Color32::WHITE)
+                            .width((self.view.line_thickness / 2.0).max(1.0)),
+                    );
+                    // L'entité inspectée montre aussi jusqu'où portent ses balles
+                    if let Some(range) = bullet_range.filter(|_| self.selected == Some(entity.id)) {
+                        plot_ui.line(
+                            Line::new(PlotPoints::new(vec![muzzle, offset_point(base, gun_angle, range as f64)]))
+                                .color(egui::Color32::WHITE.gamma_multiply(0.3))
+                                .width(1.0)
+                                .style(LineStyle::dotted_dense()),
+                        );
+                    }
+                }
+            }
+
+            let ring = |radius: f64| -> Vec<[f64; 2]> {
+                (0..=32)
+                    .map(|i| offset_point(pos, i as f64 * std::f64::consts::TAU / 32.0, radius))
+                    .collect()
+            };
+            if entity.shield {
+                plot_ui.line(
+                    Line::new(PlotPoints::new(ring(length * 1.3)))
+                        .color(egui::Color32::LIGHT_BLUE)
+                        .width(self.view.line_thickness / 2.0),
+                );
+            }
+            if entity.protected {
+                plot_ui.line(
+                    Line::new(PlotPoints::new(ring(length * 1.6)))
+                        .color(egui::Color32::GOLD)
+                        .width(self.view.line_thickness / 3.0),
+                );
+            }
+            if self.selected == Some(entity.id) {
+                plot_ui.line(
+                    Line::new(PlotPoints::new(ring(length * 1.9)))
+                        .color(egui::Color32::WHITE)
+                        .width(self.view.line_thickness / 3.0)
+                        .style(LineStyle::dashed_dense()),
+                );
+            }
 
-            if self.show_names {
-                let pos_with_offset = [pos[0], pos[1] + 20.0];
+            let label = if show_labels { self.labels.label(entity) } else { String::new() };
+            if self.show_health_bars {
+                let bar_offset = if label.is_empty() { AppDefines::HEALTH_BAR_OFFSET } else { labelled_bar_offset };
+                self.draw_health_bar(plot_ui, [pos[0], pos[1] + bar_offset], bar_length, entity.health, max_health);
+                if let Some(fraction) = entity.fire_energy {
+                    let energy_offset = bar_offset - AppDefines::ENERGY_BAR_GAP_POINTS * units_per_point.max(f64::EPSILON);
+                    self.draw_energy_bar(plot_ui, [pos[0], pos[1] + energy_offset], bar_length, fraction);
+                }
+            }
+
+            if !label.is_empty() {
+                let pos_with_offset = [pos[0], pos[1] + AppDefines::HEALTH_BAR_OFFSET];
                 plot_ui.text(
-                    Text::new(PlotPoint::from(pos_with_offset), &entity.name)
-                        .color(entity.color)
+                    Text::new(PlotPoint::from(pos_with_offset), label)
+                        .color(color)
                         .anchor(Align2::CENTER_CENTER),
                 );
             }
@@ -90,47 +618,1185 @@ impl GameUI {
 
 
 
+    /// Draws what the AI entities are doing: a dashed line to the cross marking their destination
+    /// and, when their behavior picked an entity to shoot at, a line to it with the angle aimed.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `intents`: The intentions published by the game, see `GameLogic::ai_debug_enabled`.
+    /// - `entities`: The entities drawn, the lines start and end at their interpolated positions.
+    fn draw_ai_intents(&self, plot_ui: &mut PlotUi, intents: &[AiIntent], entities: &[EntityFrame]) {
+        let color = egui::Color32::from_rgb(230, 120, 255);
+        let position = |id: u32| {
+            entities.iter().find(|entity| entity.id == id).map(|entity| [entity.x as f64, entity.y as f64])
+        };
+        for intent in intents {
+            let Some(bot) = position(intent.id) else { continue };
+            let destination = [intent.destination.0 as f64, intent.destination.1 as f64];
+            plot_ui.line(
+                Line::new(PlotPoints::new(vec![bot, destination]))
+                    .color(color.gamma_multiply(0.6))
+                    .width(1.0)
+                    .style(LineStyle::dashed_loose()),
+            );
+            plot_ui.points(
+                Points::new(vec![destination])
+                    .shape(MarkerShape::Cross)
+                    .radius(self.view.line_thickness * 1.5)
+                    .color(color),
+            );
+            let Some((enemy, angle)) = intent.aim else { continue };
+            if let Some(enemy) = position(enemy) {
+                plot_ui.line(Line::new(PlotPoints::new(vec![bot, enemy])).color(egui::Color32::from_rgb(255, 150, 60)).width(1.0));
+            }
+            let angle = angle.rem_euclid(std::f32::consts::TAU);
+            plot_ui.text(
+                Text::new(PlotPoint::new(bot[0], bot[1] - AppDefines::HEALTH_BAR_OFFSET), format!("{:.0}°", angle.to_degrees()))
+                    .color(egui::Color32::from_rgb(255, 150, 60))
+                    .anchor(Align2::CENTER_CENTER),
+            );
+        }
+    }
+
+    /// Draws the health bar of an entity: a dark background of fixed length, covered in proportion
+    /// to the health left by a bar going from green to yellow to red.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `center`: The middle of the bar.
+    /// - `length`: The length of the bar, in world units.
+    /// - `health`, `max_health`: The health of the entity, and the health filling the bar.
+    fn draw_health_bar(&self, plot_ui: &mut PlotUi, center: [f64; 2], length: f64, health: i32, max_health: i32) {
+        let fraction = (health as f32 / max_health.max(1) as f32).clamp(0.0, 1.0);
+        if self.hide_full_health_bars && fraction >= 1.0 {
+            return;
+        }
+        let left = center[0] - length / 2.0;
+        let width = self.view.line_thickness.max(2.0);
+        plot_ui.line(
+            Line::new(PlotPoints::new(vec![[left, center[1]], [left + length, center[1]]]))
+                .color(egui::Color32::from_gray(60))
+                .width(width),
+        );
+        if fraction > 0.0 {
+            // Vert à pleine santé, jaune à la moitié, rouge à la fin
+            let red = (2.0 * (1.0 - fraction)).min(1.0);
+            let green = (2.0 * fraction).min(1.0);
+            let color = egui::Color32::from_rgb((red * 255.0) as u8, (green * 255.0) as u8, 0);
+            plot_ui.line(
+                Line::new(PlotPoints::new(vec![[left, center[1]], [left + length * fraction as f64, center[1]]]))
+                    .color(color)
+                    .width(width),
+            );
+        }
+    }
+
+    /// Draws the fire energy bar of an entity under its health bar: thinner, in a single color.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `center`: The middle of the bar.
+    /// - `length`: The length of the bar, in world units.
+    /// - `fraction`: The share of the fire energy left.
+    fn draw_energy_bar(&self, plot_ui: &mut PlotUi, center: [f64; 2], length: f64, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        if self.hide_full_health_bars && fraction >= 1.0 {
+            return;
+        }
+        let left = center[0] - length / 2.0;
+        let width = (self.view.line_thickness / 2.0).max(1.0);
+        plot_ui.line(
+            Line::new(PlotPoints::new(vec![[left, center[1]], [left + length, center[1]]]))
+                .color(egui::Color32::from_gray(60))
+                .width(width),
+        );
+        if fraction > 0.0 {
+            plot_ui.line(
+                Line::new(PlotPoints::new(vec![[left, center[1]], [left + length * fraction as f64, center[1]]]))
+                    .color(egui::Color32::from_rgb(80, 170, 255))
+                    .width(width),
+            );
+        }
+    }
+
+    /// Returns the color of a team, from `TEAM_COLORS` in turn by team id.
+    fn team_color(team: u8) -> egui::Color32 {
+        let [r, g, b] = AppDefines::TEAM_COLORS[team as usize % AppDefines::TEAM_COLORS.len()];
+        egui::Color32::from_rgb(r, g, b)
+    }
+
+    /// Shows the totals of each team, in a section folded by the user.
+    ///
+    /// # Parameters
+    /// - `ui`: The panel of the scoreboard.
+    /// - `teams`: The totals of the teams present, see `TeamTotals::of`.
+    fn show_team_totals(ui: &mut egui::Ui, teams: &[TeamTotals]) {
+        egui::CollapsingHeader::new("Teams").default_open(true).show(ui, |ui| {
+            egui::Grid::new("team_totals").striped(true).show(ui, |ui| {
+                for title in ["Team", "Members", "Score", "Kills"] {
+                    ui.strong(title);
+                }
+                ui.end_row();
+                for totals in teams {
+                    ui.colored_label(GameUI::team_color(totals.team), format!("Team {}", totals.team));
+                    ui.label(totals.members.to_string());
+                    ui.label(totals.score.to_string());
+                    ui.label(totals.kills.to_string());
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// Draws the color of each team in the bottom right corner of the arena, below the kill feed.
+    ///
+    /// # Parameters
+    /// - `ui`: The panel of the arena.
+    /// - `rect`: Where the plot of the arena is on screen.
+    /// - `teams`: The teams present.
+    fn draw_team_legend(ui: &egui::Ui, rect: egui::Rect, teams: &[TeamTotals]) {
+        let painter = ui.painter_at(rect);
+        let font = egui::FontId::proportional(13.0);
+        let mut y = rect.bottom() - 12.0 - 18.0 * (teams.len() - 1) as f32;
+        for totals in teams {
+            let color = GameUI::team_color(totals.team);
+            painter.circle_filled(egui::pos2(rect.right() - 70.0, y), 5.0, color);
+            painter.text(egui::pos2(rect.right() - 60.0, y), Align2::LEFT_CENTER, format!("Team {}", totals.team), font.clone(), color);
+            y += 18.0;
+        }
+    }
+
+    /// Places the armed entity where the arena is clicked, facing right, or where a drag starts,
+    /// facing where it ends. A point outside the arena or in an obstacle is refused with a toast,
+    /// and the placement stays armed.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in, which must not pan while placing.
+    fn handle_placement(&mut self, plot_ui: &mut PlotUi) {
+        let Some(placement) = self.placement else {
+            return;
+        };
+        let response = plot_ui.response();
+        let latest_pointer = response.ctx.input(|input| input.pointer.latest_pos());
+        let mut aim = None;
+        let pose = if response.drag_started_by(egui::PointerButton::Primary) {
+            // Le glissement commence après quelques points : l'entité apparaît là où le bouton a été enfoncé
+            let origin = response.ctx.input(|input| input.pointer.press_origin());
+            self.placement_origin = origin.map(|origin| plot_ui.plot_from_screen(origin));
+            None
+        } else if response.drag_stopped() {
+            self.placement_origin.take().zip(latest_pointer).map(|(origin, pointer)| {
+                let tip = plot_ui.plot_from_screen(pointer);
+                (origin, (tip.y - origin.y).atan2(tip.x - origin.x) as f32)
+            })
+        } else if let Some(origin) = self.placement_origin {
+            aim = latest_pointer.map(|pointer| (origin, plot_ui.plot_from_screen(pointer)));
+            None
+        } else if response.clicked() {
+            response.interact_pointer_pos().map(|pointer| (plot_ui.plot_from_screen(pointer), 0.0))
+        } else {
+            None
+        };
+        if let Some((origin, tip)) = aim {
+            plot_ui.arrows(Arrows::new(vec![[origin.x, origin.y]], vec![[tip.x, tip.y]]).color(egui::Color32::WHITE));
+        }
+
+        let Some((point, angle)) = pose else {
+            return;
+        };
+        let (x, y) = (point.x as f32, point.y as f32);
+        let placed = self.status_bar.wait(|| self.commands.update(move |game_logic| match placement {
+            Placement::Entity => game_logic.add_entity_at("Player".to_string(), x, y, angle, false),
+            Placement::Ai(difficulty) => game_logic.add_ai_at("AI Bot".to_string(), x, y, angle, difficulty),
+        }));
+        match placed {
+            Ok(_) => self.placement = None,
+            Err(reason) => self.toast = Some((format!("Cannot place the entity: {}", reason), Instant::now())),
+        }
+    }
+
+    /// Arms the placement of an entity, or disarms it if it was already armed.
+    fn arm_placement(&mut self, placement: Placement) {
+        self.placement = if self.placement == Some(placement) { None } else { Some(placement) };
+        self.placement_origin = None;
+        self.teleport_mode = false;
+    }
+
+    /// Draws the hint of the armed placement and the toast, at the top of the arena.
+    ///
+    /// # Parameters
+    /// - `ui`: The panel of the arena.
+    /// - `rect`: The rectangle of the plot of the arena.
+    fn show_notices(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let painter = ui.painter_at(rect);
+        let font = egui::FontId::proportional(14.0);
+        let mut y = rect.top() + 10.0;
+        let hint = self.placement.map(|_| ("Click to place, drag to aim, Esc to cancel", egui::Color32::WHITE));
+        let toast = self.toast.as_ref().map(|(text, _)| (text.as_str(), egui::Color32::from_rgb(255, 150, 120)));
+        for (text, color) in hint.into_iter().chain(toast) {
+            let galley = painter.layout_no_wrap(text.to_string(), font.clone(), color);
+            let position = egui::pos2(rect.center().x - galley.size().x / 2.0, y);
+            let background = egui::Rect::from_min_size(position, galley.size()).expand(4.0);
+            painter.rect_filled(background, 3.0, egui::Color32::from_black_alpha(180));
+            painter.galley(position, galley, color);
+            y += background.height() + 4.0;
+        }
+    }
+
+    /// Starts following an entity, at the zoom of the settings.
+    fn follow(&mut self, entity_id: u32) {
+        self.followed = Some(entity_id);
+        self.follow_paused = false;
+        self.follow_zoom_changed = true;
+    }
+
+    /// Goes back to the free camera, showing the whole arena.
+    fn stop_follow(&mut self) {
+        self.followed = None;
+        self.follow_paused = false;
+        self.reset_camera = true;
+    }
+
+    /// Shows the choice of the entity the camera follows, and its zoom.
+    ///
+    /// # Parameters
+    /// - `ui`: The panel of the arena.
+    /// - `entities`: The entities of the frame drawn, live or replayed.
+    fn show_camera_controls(&mut self, ui: &mut egui::Ui, entities: &[EntityFrame]) {
+        ui.horizontal(|ui| {
+            let selected = self.followed
+                .and_then(|id| entities.iter().find(|entity| entity.id == id))
+                .map_or("Free camera".to_string(), |entity| entity.name.clone());
+            egui::ComboBox::from_label("Follow")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(self.followed.is_none(), "Free camera").clicked() {
+                        self.stop_follow();
+                    }
+                    for entity in entities {
+                        if ui.selectable_label(self.followed == Some(entity.id), format!("{} (#{})", entity.name, entity.id)).clicked() {
+                            self.follow(entity.id);
+                        }
+                    }
+                });
+            let zoom = egui::Slider::new(&mut self.follow_zoom, 1.0..=AppDefines::MAX_FOLLOW_ZOOM).text("Zoom");
+            if ui.add_enabled(self.followed.is_some(), zoom).changed() {
+                self.follow_zoom_changed = true;
+            }
+            if self.followed.is_some() && self.follow_paused {
+                ui.label("Follow paused by the drag.");
+                if ui.button("Resume Follow").clicked() {
+                    self.follow_paused = false;
+                }
+            }
+        });
+    }
+
+    /// Centers the view on the followed entity, or shows the whole arena again once the follow ended.
+    ///
+    /// The view keeps the zoom of the mouse wheel between two changes of `follow_zoom`,
+    /// and dragging it pauses the follow.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `target`: The interpolated position of the followed entity, `None` without one.
+    /// - `arena_width`: The width of the arena, which `follow_zoom` divides.
+    fn follow_camera(&mut self, plot_ui: &mut PlotUi, target: Option<[f64; 2]>, arena_width: f64) {
+        if std::mem::take(&mut self.reset_camera) {
+            plot_ui.set_auto_bounds(true.into());
+            return;
+        }
+        let Some([x, y]) = target else {
+            return;
+        };
+        if self.follow_paused {
+            return;
+        }
+        if plot_ui.response().dragged() {
+            self.follow_paused = true;
+            return;
+        }
+
+        let bounds = plot_ui.plot_bounds();
+        let half_width = if std::mem::take(&mut self.follow_zoom_changed) || !bounds.is_valid() {
+            arena_width / self.follow_zoom as f64 / 2.0
+        } else {
+            bounds.width() / 2.0
+        };
+        // Les proportions de la vue sont gardées, la hauteur suit la largeur
+        let half_height = if bounds.is_valid() { half_width * bounds.height() / bounds.width() } else { half_width };
+        plot_ui.set_plot_bounds(PlotBounds::from_min_max([x - half_width, y - half_height], [x + half_width, y + half_height]));
+    }
+
+    /// Adds a message of the game UI to the messages of the server.
+    ///
+    /// # Parameters
+    /// - `text`: The text of the message.
+    /// - `message_type`: The type of the message.
+    fn report(&self, text: String, message_type: MessageType) {
+        add_message(&self.messages, text, message_type, MessageOrigin::Ui);
+    }
+
+    /// Selects an entity for the inspector, or clears the selection.
+    fn select(&mut self, entity: Option<&EntityFrame>) {
+        let selected = entity.map(|entity| entity.id);
+        // Le jeu ne publie que l'entité inspectée, à partir du pas suivant
+        if selected != self.selected {
+            self.commands.post(move |game_logic| game_logic.inspected = selected);
+        }
+        self.selected = selected;
+        self.rename_input = entity.map_or(String::new(), |entity| entity.name.clone());
+        self.teleport_mode = false;
+    }
+
+    /// Selects the entity clicked in the arena, or moves the selected one there in teleport mode.
+    ///
+    /// Dragging the view is not a click: egui only reports one when the pointer barely moved.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    /// - `entities`: The entities drawn, at their interpolated positions.
+    fn handle_click(&mut self, plot_ui: &PlotUi, entities: &[EntityFrame]) {
+        let response = plot_ui.response();
+        let Some(pointer) = response.interact_pointer_pos().filter(|_| response.clicked()) else {
+            return;
+        };
+        if self.teleport_mode {
+            self.teleport_mode = false;
+            let target = plot_ui.plot_from_screen(pointer);
+            if let Some(entity_id) = self.selected {
+                let (x, y) = (target.x as f32, target.y as f32);
+                self.commands.post(move |game_logic| {
+                    game_logic.teleport_entity(entity_id, x, y);
+                });
+            }
+            return;
+        }
+        // Comparées à l'écran : le rayon de sélection ne dépend pas du zoom
+        let clicked = entities
+            .iter()
+            .map(|entity| (entity, plot_ui.screen_from_plot(PlotPoint::new(entity.x, entity.y)).distance(pointer)))
+            .filter(|(_, distance)| *distance <= AppDefines::SELECT_RADIUS_POINTS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(entity, _)| entity);
+        self.select(clicked);
+    }
+
+    /// Shows the live state of the selected entity, and the actions on it.
+    ///
+    /// # Parameters
+    /// - `ui`: The side panel of the inspector.
+    /// - `inspected`: The selected entity, as the last render snapshot shows it.
+    fn show_inspector(&mut self, ui: &mut egui::Ui, inspected: Option<&InspectedEntity>) {
+        let Some(entity_id) = self.selected else {
+            return;
+        };
+        // Pas encore publiée, ou retirée depuis l'image dessinée : la sélection tombe à l'image suivante
+        let Some(entity) = inspected.filter(|entity| entity.id == entity_id) else {
+            return;
+        };
+        let driver = entity.ai_driver
+            .as_ref()
+            .map_or("Player".to_string(), |(behavior, difficulty)| format!("AI {} ({:?})", behavior, difficulty));
+        let driver = if self.controlled == Some(entity_id) { format!("Keyboard, instead of {}", driver) } else { driver };
+
+        ui.heading("Inspector");
+        let breakdown = &entity.breakdown;
+        let accuracy = breakdown.accuracy().map_or("-".to_string(), |accuracy| format!("{:.0}%", accuracy * 100.0));
+        let chassis_angle = entity.orientation.rem_euclid(std::f32::consts::TAU);
+        let gun_angle = entity.gun_angle.rem_euclid(std::f32::consts::TAU);
+        let fields = [
+            ("Id", entity.id.to_string()),
+            ("Name", entity.name.clone()),
+            ("Team", entity.team.map_or("-".to_string(), |team| team.to_string())),
+            ("Health", entity.health.to_string()),
+            ("Armor", format!("{:.0}%, {} mitigated", entity.armor * 100.0, breakdown.damage_mitigated)),
+            ("Score", entity.score.to_string()),
+            ("Shots / Hits", format!("{} / {} ({})", breakdown.shots_fired, breakdown.hits, accuracy)),
+            ("Kills / Deaths", format!("{} / {}", breakdown.kills, breakdown.deaths)),
+            ("Position", format!("{:.1}, {:.1}", entity.x, entity.y)),
+            ("Velocity", format!("{:.1}, {:.1}", entity.vx, entity.vy)),
+            ("Chassis", format!("{:.1}°", chassis_angle.to_degrees())),
+            ("Gun", format!("{:.1}°", gun_angle.to_degrees())),
+            ("Motors", format!("{:.2} / {:.2}", entity.motor_left, entity.motor_right)),
+            ("Trigger", format!("{:.2}", entity.trigger)),
+            ("Traverse", format!("{:.2}", entity.traverse)),
+            ("Shield", format!("{:.2}, energy {:.0}", entity.shield, entity.energy)),
+            ("Fire energy", format!("{:.0}", entity.fire_energy)),
+            ("Cooldown", format!("{} ms", entity.fire_cooldown.as_millis())),
+            ("Driver", driver),
+        ];
+        egui::Grid::new("inspector_fields").num_columns(2).striped(true).show(ui, |ui| {
+            for (label, value) in fields {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        let mut rename = None;
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.rename_input);
+            let name = self.rename_input.trim();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Rename")).clicked() {
+                rename = Some(name.to_string());
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut color = entity.color;
+            if ui.color_edit_button_srgb(&mut color).changed() {
+                let [r, g, b] = color;
+                self.commands.post(move |game_logic| {
+                    if let Some(entity) = game_logic.get_entity_mut(entity_id) {
+                        entity.set_color(r, g, b);
+                    }
+                });
+            }
+            ui.label("Color");
+        });
+        let mut kill = false;
+        let mut take_control = false;
+        ui.horizontal(|ui| {
+            kill = ui.button("Kill").clicked();
+            if ui.selectable_label(self.teleport_mode, "Teleport").clicked() {
+                self.teleport_mode = !self.teleport_mode;
+            }
+            let controlled = self.controlled == Some(entity_id);
+            take_control = ui.selectable_label(controlled, "Take Control").on_hover_text("W/S: drive, A/D: turn, Q/E: gun, Space: fire, Esc: release").clicked();
+        });
+        if self.teleport_mode {
+            ui.label("Click in the arena to move the entity there.");
+        }
+        if self.controlled == Some(entity_id) {
+            ui.label("Driven with the keyboard: W/S, A/D, Q/E and Space. Esc gives it back.");
+        }
+        if let Some(name) = rename {
+            match self.status_bar.wait(|| self.commands.update(move |game_logic| game_logic.rename_entity(entity_id, &name))) {
+                Ok(name) => self.rename_input = name,
+                Err(reason) => self.toast = Some((format!("Cannot rename the entity: {}", reason), Instant::now())),
+            }
+        }
+        if kill {
+            self.commands.post(move |game_logic| {
+                game_logic.kill_entity(entity_id);
+            });
+        }
+        if take_control {
+            // Une seule entité au clavier : la précédente est rendue
+            let previous = self.controlled.take();
+            let taken = self.status_bar.wait(|| self.commands.update(move |game_logic| {
+                if let Some(previous) = previous {
+                    game_logic.set_keyboard_control(previous, false);
+                }
+                previous != Some(entity_id) && game_logic.set_keyboard_control(entity_id, true)
+            }));
+            if taken {
+                self.controlled = Some(entity_id);
+            }
+        }
+    }
+
+    /// Drives the entity taken control of with the keyboard, through the same actuators as the protocol.
+    ///
+    /// W and S drive both motors forward and back, A and D turn, Q and E turn the gun traverse,
+    /// and Space holds the trigger. The keys only count while the window has the focus and no
+    /// text field has it; otherwise the entity stops. Esc gives the entity back, its actuators
+    /// set to neutral.
+    ///
+    /// # Parameters
+    /// - `ctx`: The context of the game UI.
+    /// - `entities`: The entities of the last render snapshot.
+    fn drive_controlled(&mut self, ctx: &Context, entities: &[EntityFrame]) {
+        let Some(entity_id) = self.controlled else {
+            return;
+        };
+        let typing = ctx.memory(|memory| memory.focused().is_some());
+        let (release, dt, keys) = ctx.input(|input| {
+            let key = |key: egui::Key| if input.focused && !typing && input.key_down(key) { 1.0_f32 } else { 0.0 };
+            let keys = [egui::Key::W, egui::Key::S, egui::Key::A, egui::Key::D, egui::Key::Q, egui::Key::E, egui::Key::Space].map(key);
+            (!typing && input.key_pressed(egui::Key::Escape), input.stable_dt, keys)
+        });
+        let [forward, back, left, right, gun_left, gun_right, fire] = keys;
+        if release {
+            self.commands.post(move |game_logic| {
+                game_logic.set_keyboard_control(entity_id, false);
+            });
+            self.controlled = None;
+            return;
+        }
+        // L'entité détruite ne revient pas : plus rien à conduire
+        if !entities.iter().any(|entity| entity.id == entity_id) {
+            self.controlled = None;
+            return;
+        }
+        let drive = forward - back;
+        let turn = right - left;
+        // Moteur gauche plus rapide que le droit : l'entité tourne vers la droite
+        let motor_left = 0.5 + 0.5 * (drive + turn).clamp(-1.0, 1.0);
+        let motor_right = 0.5 + 0.5 * (drive - turn).clamp(-1.0, 1.0);
+        let turned = (gun_left - gun_right) * AppDefines::KEYBOARD_GUN_TRAVERSE_RATE * dt;
+        // La tourelle tourne depuis sa position du moment, lue par la boucle
+        self.commands.post(move |game_logic| {
+            let Some(entity) = game_logic.get_entity_mut(entity_id) else { return };
+            let traverse = (entity.gun().traverse + turned).rem_euclid(1.0);
+            entity.set_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, motor_left);
+            entity.set_actuator(AppDefines::ACTUATOR_MOTOR_RIGHT, motor_right);
+            entity.set_actuator(AppDefines::ACTUATOR_GUN_TRAVERSE, traverse);
+            entity.set_actuator(AppDefines::ACTUATOR_GUN_TRIGGER, fire);
+        });
+    }
+
+    /// Pauses a running simulation, or resumes a paused one.
+    fn toggle_pause(game_logic: &mut GameLogic) {
+        if game_logic.is_paused() {
+            game_logic.resume();
+        } else {
+            game_logic.pause();
+        }
+    }
+
+    /// Applies the keyboard shortcuts of the simulation: Space pauses or resumes, unless it fires
+    /// with the entity driven with the keyboard, N steps once while paused.
+    ///
+    /// They only apply over the arena and while no text field has the keyboard focus,
+    /// so that typing a seed or a name does not pause the game.
+    ///
+    /// # Parameters
+    /// - `plot_ui`: The plot the arena is drawn in.
+    fn handle_shortcuts(&self, plot_ui: &PlotUi) {
+        let ctx = &plot_ui.response().ctx;
+        if !plot_ui.response().hovered() || ctx.memory(|memory| memory.focused().is_some()) {
+            return;
+        }
+        // Espace tire avec l'entité conduite au clavier : il ne met plus en pause
+        let (pause, step) = ctx.input(|input| (self.controlled.is_none() && input.key_pressed(egui::Key::Space), input.key_pressed(egui::Key::N)));
+        if !pause && !step {
+            return;
+        }
+        self.commands.post(move |game_logic| {
+            if pause {
+                GameUI::toggle_pause(game_logic);
+            }
+            if step {
+                game_logic.step_once();
+            }
+        });
+    }
+
+    /// Shows the 'View Settings' window, changing how the arena is drawn.
+    fn show_view_settings(&mut self, ctx: &Context) {
+        let mut open = self.show_view_settings;
+        egui::Window::new("View Settings")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("view_settings").num_columns(2).show(ui, |ui| {
+                    ui.label("Background grid");
+                    ui.checkbox(&mut self.view.show_background, "");
+                    ui.end_row();
+                    ui.label("Grid spacing");
+                    ui.add_enabled(
+                        self.view.show_background,
+                        egui::Slider::new(&mut self.view.grid_spacing, AppDefines::MIN_GRID_SPACING..=AppDefines::MAX_GRID_SPACING),
+                    );
+                    ui.end_row();
+                    ui.label("Grid color");
+                    ui.color_edit_button_srgb(&mut self.view.grid_color);
+                    ui.end_row();
+                    ui.label("Boundary color");
+                    ui.color_edit_button_srgb(&mut self.view.boundary_color);
+                    ui.end_row();
+                    ui.label("Line thickness");
+                    ui.add(egui::Slider::new(&mut self.view.line_thickness, 1.0..=20.0).step_by(1.0));
+                    ui.end_row();
+                });
+                if ui.button("Reset").clicked() {
+                    self.view = ViewSettings::default();
+                }
+            });
+        self.show_view_settings = open;
+    }
+
+    /// Asks to confirm the pending action, which would throw away the match in progress, and runs it.
+    fn show_confirmation(&mut self, ctx: &Context) {
+        let Some(action) = self.pending_action else {
+            return;
+        };
+        let question = match action {
+            SimulationAction::Reset => "Reset the simulation? The scores of the round are lost.".to_string(),
+            SimulationAction::GenerateMap(Some(seed)) => format!("Generate the map {}? The arena of the match in progress is replaced.", seed),
+            SimulationAction::GenerateMap(None) => "Generate a random map? The arena of the match in progress is replaced.".to_string(),
+        };
+        let mut answer = None;
+        egui::Window::new("Confirm")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(question);
+                ui.horizontal(|ui| {
+                    if ui.button("Confirm").clicked() {
+                        answer = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() || ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+                        answer = Some(false);
+                    }
+                });
+            });
+        let Some(confirmed) = answer else {
+            return;
+        };
+        self.pending_action = None;
+        if !confirmed {
+            return;
+        }
+        match action {
+            SimulationAction::Reset => self.commands.post(GameLogic::reset_simulation),
+            SimulationAction::GenerateMap(seed) => {
+                self.commands.post(move |game_logic| game_logic.generate_map(seed));
+                self.map_editor.forget_history();
+            }
+        }
+    }
+
+    /// Copies the state of the game the menus show from a render snapshot, unless a change
+    /// sent by the menus is still on its way to the game loop.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The last render snapshot.
+    fn sync_controls(&mut self, snapshot: &RenderSnapshot) {
+        // La boucle applique les commandes avant son pas : un instantané pris après l'envoi montre la modification
+        if self.controls_edited_at.map_or(true, |edited_at| snapshot.stepped_at >= edited_at) {
+            self.controls.clone_from(&snapshot.controls);
+            self.controls_edited_at = None;
+        }
+    }
+
+    /// Sends a change made in the menus to the game loop, without waiting for it.
+    ///
+    /// # Parameters
+    /// - `change`: Applies the change to the game logic, already made to `controls`.
+    fn apply_control(&mut self, change: impl FnOnce(&mut GameLogic) + Send + 'static) {
+        self.commands.post(change);
+        self.controls_edited_at = Some(Instant::now());
+    }
+
+    /// Describes the current round and the time left in it.
+    fn match_status(controls: &GameControls) -> String {
+        match (controls.phase, controls.round_remaining) {
+            (MatchPhase::Running, Some(remaining)) => {
+                let seconds = remaining.as_secs_f32().ceil() as u32;
+                format!("Round {} - {}:{:02} left", controls.round, seconds / 60, seconds % 60)
+            }
+            (MatchPhase::Running, None) => format!("Round {}", controls.round),
+            (MatchPhase::Intermission, remaining) => format!(
+                "{} | Next round in {:.0} s",
+                controls.last_result.as_deref().unwrap_or_default(),
+                remaining.unwrap_or_default().as_secs_f32().ceil()
+            ),
+        }
+    }
+
+    /// Displays the play, pause and scrub controls of the replay being played back.
+    fn show_replay_controls(&mut self, ctx: &Context) {
+        let Some(player) = &mut self.player else { return };
+        let mut close = false;
+        TopBottomPanel::bottom("replay_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let label = if player.playing { "Pause" } else { "Play" };
+                if ui.button(label).clicked() {
+                    // Relancer un replay terminé le reprend du début
+                    if !player.playing && player.position == player.last_index() {
+                        player.position = 0;
+                    }
+                    player.playing = !player.playing;
+                }
+                let last_index = player.last_index();
+                ui.add(egui::Slider::new(&mut player.position, 0..=last_index).text("Frame"));
+                if let Some(frame) = player.frame() {
+                    ui.label(format!("Tick {}", frame.tick));
+                }
+                if ui.button("Close Replay").clicked() {
+                    close = true;
+                }
+            });
+        });
+        if close {
+            self.player = None;
+        }
+    }
+
     fn show_menu(&mut self, ctx: &Context) {
         TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if ui.button("Toggle Names").clicked() {
-                    self.show_names = !self.show_names;
+                ui.menu_button("Labels", |ui| {
+                    ui.checkbox(&mut self.labels.name, "Name");
+                    ui.checkbox(&mut self.labels.health, "Health");
+                    ui.checkbox(&mut self.labels.score, "Score");
+                    ui.checkbox(&mut self.labels.id, "Id");
+                    ui.checkbox(&mut self.labels.ai_suffix, "[AI] suffix");
+                });
+                if ui.selectable_label(self.show_view_settings, "View Settings").clicked() {
+                    self.show_view_settings = !self.show_view_settings;
                 }
-                if ui.button("T+").clicked() {
-                    if self.line_thickness < 20.0 {
-                        self.line_thickness += 1.0;
+
+                ui.group(|ui| {
+                    // Relu à chaque image : une pause venue du port d'administration s'affiche au pas suivant
+                    let paused = self.controls.paused;
+                    if ui.button(if paused { "Resume" } else { "Pause" }).on_hover_text("Space").clicked() {
+                        self.controls.paused = !paused;
+                        self.apply_control(GameUI::toggle_pause);
+                    }
+                    if ui.add_enabled(paused, egui::Button::new("Step")).on_hover_text("N, while paused").clicked() {
+                        self.commands.post(|game_logic| {
+                            game_logic.step_once();
+                        });
+                    }
+                    // La boucle baisse d'elle-même une vitesse qu'elle ne tient pas : le curseur la suit.
+                    // Il est figé pendant un enregistrement, qui se fait ainsi à une seule vitesse
+                    let speed = ui.add_enabled(
+                        !self.controls.recording,
+                        egui::Slider::new(&mut self.controls.time_scale, AppDefines::MIN_TIME_SCALE..=AppDefines::MAX_TIME_SCALE)
+                            .logarithmic(true)
+                            .suffix("x")
+                            .text("Speed"),
+                    )
+                    .on_disabled_hover_text("The speed cannot change while a replay is recorded");
+                    if speed.changed() {
+                        let time_scale = self.controls.time_scale;
+                        self.apply_control(move |game_logic| game_logic.time_scale = time_scale);
+                    }
+                    ui.label(format!("{:.2}x applied", self.metrics.sample().time_scale));
+                    if ui.button("Reset Simulation...").clicked() {
+                        self.pending_action = Some(SimulationAction::Reset);
+                    }
+                });
+                if ui.button("Generate Map...").clicked() {
+                    let seed = self.seed_input.trim();
+                    match seed.parse::<u64>() {
+                        Ok(seed) => self.pending_action = Some(SimulationAction::GenerateMap(Some(seed))),
+                        Err(_) if seed.is_empty() => self.pending_action = Some(SimulationAction::GenerateMap(None)),
+                        Err(_) => self.toast = Some((format!("Invalid map seed: {}", seed), Instant::now())),
                     }
                 }
-                if ui.button("T-").clicked() {
-                    if self.line_thickness > 1.0 {
-                        self.line_thickness -= 1.0;
+                ui.add(egui::TextEdit::singleline(&mut self.seed_input).hint_text("Seed").desired_width(80.0));
+                if let Some(seed) = self.controls.map_seed {
+                    ui.label(format!("Seed: {}", seed));
+                } else if let Some(name) = &self.controls.map_name {
+                    ui.label(format!("Map: {}", name));
+                }
+                if ui.button("Load Map").clicked() {
+                    let path = self.map_path.clone();
+                    match self.status_bar.wait(|| self.commands.update(move |game_logic| game_logic.load_map(Path::new(&path)))) {
+                        Ok(()) => self.map_editor.forget_history(),
+                        Err(e) => self.report(format!("[WARNING] Could not load the map from {}: {}", self.map_path, e), MessageType::Warning),
                     }
                 }
-
-                // Ici on doit aussi verrouiller avant d’appeler les méthodes
-                if ui.button("Reset Simulation").clicked() {
-                    if let Ok(mut game_logic) = self.game_logic.lock() {
-                        game_logic.reset_simulation();
+                if ui.button("Save Map").clicked() {
+                    let path = self.map_path.clone();
+                    match self.status_bar.wait(|| self.commands.query(move |game_logic| game_logic.save_map(Path::new(&path)))) {
+                        Ok(()) => self.report(format!("[INFO] Map saved to {}", self.map_path), MessageType::Info),
+                        Err(e) => self.report(format!("[WARNING] Could not save the map to {}: {}", self.map_path, e), MessageType::Warning),
                     }
                 }
-                if ui.button("Generate Map").clicked() {
-                    if let Ok(mut game_logic) = self.game_logic.lock() {
-                        game_logic.generate_map();
+                ui.add(egui::TextEdit::singleline(&mut self.map_path).hint_text("Map file").desired_width(120.0));
+                let edit_map = egui::SelectableLabel::new(self.map_editor.active, "Edit Map");
+                if ui.add_enabled(self.player.is_none(), edit_map).clicked() {
+                    if self.map_editor.active {
+                        self.map_editor.stop(&self.commands);
+                    } else {
+                        self.status_bar.wait(|| self.map_editor.start(&self.commands));
+                        self.select(None);
                     }
                 }
-                if ui.button("Show Background").clicked() {
-                    self.show_background = !self.show_background;
+                if ui.button("Save State").clicked() {
+                    let path = self.snapshot_path.clone();
+                    match self.status_bar.wait(|| self.commands.query(move |game_logic| game_logic.save_to_file(Path::new(&path)))) {
+                        Ok(()) => self.report(format!("[INFO] State saved to {}", self.snapshot_path), MessageType::Info),
+                        Err(e) => self.report(format!("[WARNING] Could not save the state to {}: {}", self.snapshot_path, e), MessageType::Warning),
+                    }
                 }
-                if ui.button("Add Entity").clicked() {
-                    if let Ok(mut game_logic) = self.game_logic.lock() {
-                        game_logic.add_entity("Player".to_string());
+                if ui.button("Load State").clicked() {
+                    let path = self.snapshot_path.clone();
+                    match self.status_bar.wait(|| self.commands.update(move |game_logic| game_logic.load_from_file(Path::new(&path)))) {
+                        Ok(()) => self.report(format!("[INFO] State loaded from {}", self.snapshot_path), MessageType::Info),
+                        Err(e) => self.report(format!("[WARNING] Could not load the state from {}: {}", self.snapshot_path, e), MessageType::Warning),
                     }
                 }
-                if ui.button("Add AI").clicked() {
-                    if let Ok(mut game_logic) = self.game_logic.lock() {
-                        game_logic.add_ai("AI Bot".to_string());
+                ui.add(egui::TextEdit::singleline(&mut self.snapshot_path).hint_text("Snapshot file").desired_width(120.0));
+                if !self.controls.recording {
+                    if ui.button("Record").clicked() {
+                        self.controls.recording = true;
+                        self.apply_control(GameLogic::start_recording);
+                    }
+                } else if ui.button("Stop Recording").clicked() {
+                    self.controls.recording = false;
+                    // Le replay est écrit par l'UI, une fois rendu par la boucle
+                    if let Some(recorder) = self.status_bar.wait(|| self.commands.update(GameLogic::stop_recording)) {
+                        match recorder.save(Path::new(&self.replay_path)) {
+                            Ok(()) => self.report(format!("[INFO] Replay of {} frames saved to {}", recorder.frame_count(), self.replay_path), MessageType::Info),
+                            Err(e) => self.report(format!("[WARNING] Could not save the replay to {}: {}", self.replay_path, e), MessageType::Warning),
+                        }
+                    }
+                }
+                if ui.button("Open Replay").clicked() {
+                    match Replay::load(Path::new(&self.replay_path)) {
+                        Ok(replay) => {
+                            // L'éditeur modifie la partie en cours, pas le replay
+                            if self.map_editor.active {
+                                self.map_editor.stop(&self.commands);
+                            }
+                            if let Some(entity_id) = self.controlled.take() {
+                                self.commands.post(move |game_logic| {
+                                    game_logic.set_keyboard_control(entity_id, false);
+                                });
+                            }
+                            self.player = Some(ReplayPlayer::new(replay));
+                            self.placement = None;
+                            self.placement_origin = None;
+                        }
+                        Err(e) => self.report(format!("[WARNING] Could not open the replay {}: {}", self.replay_path, e), MessageType::Warning),
+                    }
+                }
+                ui.add(egui::TextEdit::singleline(&mut self.replay_path).hint_text("Replay file").desired_width(120.0));
+                ui.checkbox(&mut self.show_mines, "Show Mines");
+                // Le jeu ne publie les intentions des IA que si la vue les demande
+                if ui.checkbox(&mut self.show_ai_intents, "AI Intentions").changed() {
+                    let enabled = self.show_ai_intents;
+                    self.commands.post(move |game_logic| game_logic.ai_debug_enabled = enabled);
+                }
+                ui.checkbox(&mut self.show_ghost_cameras, "Ghost Cameras");
+                ui.checkbox(&mut self.show_trails, "Bullet Trails");
+                ui.add_enabled(
+                    self.show_trails,
+                    egui::Slider::new(&mut self.trail_length, 1..=AppDefines::MAX_BULLET_TRAIL).text("Trail"),
+                );
+                ui.checkbox(&mut self.show_movement_trails, "Show Trails");
+                ui.checkbox(&mut self.show_barrels, "Gun Barrels");
+                ui.checkbox(&mut self.show_health_bars, "Health Bars");
+                ui.add_enabled(self.show_health_bars, egui::Checkbox::new(&mut self.hide_full_health_bars, "Hide Full Health"));
+                ui.checkbox(&mut self.status_bar.visible, "Status Bar");
+                ui.checkbox(&mut self.kill_feed.visible, "Kill Feed");
+                ui.checkbox(&mut self.score_history.visible, "Score History");
+                ui.checkbox(&mut self.hit_effects.enabled, "Hit Effects");
+                // L'entité apparaît au prochain clic dans l'arène
+                if ui.selectable_label(self.placement == Some(Placement::Entity), "Add Entity…").clicked() {
+                    self.arm_placement(Placement::Entity);
+                }
+                let armed_ai = matches!(self.placement, Some(Placement::Ai(_)));
+                ui.menu_button(if armed_ai { "Add AI… (armed)" } else { "Add AI…" }, |ui| {
+                    for difficulty in AiDifficulty::ALL {
+                        if ui.selectable_label(self.placement == Some(Placement::Ai(difficulty)), format!("{:?}", difficulty)).clicked() {
+                            self.arm_placement(Placement::Ai(difficulty));
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.menu_button("Scoring", |ui| {
+                    let before = self.controls.score_rules.clone();
+                    {
+                        let rules = &mut self.controls.score_rules;
+                        ui.checkbox(&mut rules.decay_enabled, "Score decay");
+                        ui.horizontal(|ui| {
+                            ui.label("Decay per minute:");
+                            ui.add(egui::DragValue::new(&mut rules.decay_per_minute).speed(0.01).clamp_range(0.0..=1.0));
+                        });
+                        ui.checkbox(&mut rules.comeback_enabled, "Comeback bonus");
+                        ui.horizontal(|ui| {
+                            ui.label("Bonus ratio of score gap:");
+                            ui.add(egui::DragValue::new(&mut rules.comeback_ratio).speed(0.01).clamp_range(0.0..=1.0));
+                        });
+                    }
+                    if self.controls.score_rules != before {
+                        let rules = self.controls.score_rules.clone();
+                        self.apply_control(move |game_logic| game_logic.score_rules = rules);
+                    }
+                });
+
+                ui.menu_button("Rules", |ui| {
+                    let before = self.controls.game_config.clone();
+                    {
+                        let config = &mut self.controls.game_config;
+                        ui.label("Applied to new entities and bullets");
+                        ui.horizontal(|ui| {
+                            ui.label("Starting health:");
+                            ui.add(egui::DragValue::new(&mut config.starting_health).clamp_range(1..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bullet damage:");
+                            ui.add(egui::DragValue::new(&mut config.bullet_damage).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bullet speed:");
+                            ui.add(egui::DragValue::new(&mut config.bullet_speed).speed(10.0).clamp_range(50.0..=2000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bullet radius:");
+                            ui.add(egui::DragValue::new(&mut config.bullet_radius).speed(0.5).clamp_range(1.0..=20.0));
+                        });
+                        ui.checkbox(&mut config.bullet_ccd, "Continuous collision detection for bullets");
+                        ui.horizontal(|ui| {
+                            ui.label("Bullet lifetime (ms):");
+                            ui.add(egui::DragValue::new(&mut config.bullet_lifetime_ms).speed(10.0).clamp_range(100..=10000));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bullet bounces:");
+                            ui.add(egui::DragValue::new(&mut config.bullet_bounces).clamp_range(0..=10));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Damage falloff range (0 = none):");
+                            ui.add(egui::DragValue::new(&mut config.damage_falloff_range).speed(10.0).clamp_range(0.0..=5000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Damage kept past the range:");
+                            ui.add(egui::DragValue::new(&mut config.damage_falloff_min).speed(0.01).clamp_range(0.0..=1.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Shooter grace (ms):");
+                            ui.add(egui::DragValue::new(&mut config.shooter_grace_ms).speed(10.0).clamp_range(0..=2000));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Fire energy:");
+                            ui.add(egui::DragValue::new(&mut config.fire_energy_max).speed(1.0).clamp_range(1.0..=1000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Fire energy regen per second:");
+                            ui.add(egui::DragValue::new(&mut config.fire_energy_regen_per_sec).speed(0.5).clamp_range(0.0..=1000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Shot energy cost:");
+                            ui.add(egui::DragValue::new(&mut config.shot_energy_cost).speed(0.5).clamp_range(0.0..=1000.0));
+                        });
+                        ui.checkbox(&mut config.motors_drain_energy, "Motors drain fire energy");
+                        ui.horizontal(|ui| {
+                            ui.label("Self hit grace (ms):");
+                            ui.add(egui::DragValue::new(&mut config.self_hit_grace_ms).speed(10.0).clamp_range(0..=2000));
+                        });
+                        ui.checkbox(&mut config.bullets_ignore_shooter, "Bullets never hit their shooter");
+                        ui.horizontal(|ui| {
+                            ui.label("Bullets per entity (0 = no cap):");
+                            ui.add(egui::DragValue::new(&mut config.max_bullets_per_entity).clamp_range(0..=100));
+                        });
+                        ui.checkbox(&mut config.replace_oldest_bullet, "Replace the oldest bullet at the cap");
+                        ui.horizontal(|ui| {
+                            ui.label("Fire cooldown (ms):");
+                            ui.add(egui::DragValue::new(&mut config.fire_cooldown_ms).speed(10.0).clamp_range(50..=5000));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Spawn protection (ms):");
+                            ui.add(egui::DragValue::new(&mut config.spawn_protection_ms).speed(10.0).clamp_range(0..=10000));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Gun traverse rate (rad/s):");
+                            ui.add(egui::DragValue::new(&mut config.gun_traverse_rate).speed(0.1).clamp_range(0.1..=100.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Base spread (rad):");
+                            ui.add(egui::DragValue::new(&mut config.base_spread).speed(0.01).clamp_range(0.0..=1.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Spread per speed:");
+                            ui.add(egui::DragValue::new(&mut config.spread_per_speed).speed(0.0001).clamp_range(0.0..=0.1));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Spread per angular speed:");
+                            ui.add(egui::DragValue::new(&mut config.spread_per_angular_speed).speed(0.01).clamp_range(0.0..=1.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Points per hit:");
+                            ui.add(egui::DragValue::new(&mut config.points_per_hit).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Points per kill:");
+                            ui.add(egui::DragValue::new(&mut config.points_per_kill).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Death penalty:");
+                            ui.add(egui::DragValue::new(&mut config.death_penalty).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Friendly hit penalty:");
+                            ui.add(egui::DragValue::new(&mut config.friendly_hit_penalty).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Self hit penalty:");
+                            ui.add(egui::DragValue::new(&mut config.self_hit_penalty).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Mine damage:");
+                            ui.add(egui::DragValue::new(&mut config.mine_damage).clamp_range(0..=100));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Mine blast radius:");
+                            ui.add(egui::DragValue::new(&mut config.mine_blast_radius).speed(1.0).clamp_range(10.0..=500.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Mines per entity:");
+                            ui.add(egui::DragValue::new(&mut config.max_mines).clamp_range(0..=20));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Starting armor, deathmatch:");
+                            ui.add(egui::DragValue::new(&mut config.deathmatch_starting_armor).speed(0.01).clamp_range(0.0..=AppDefines::ARMOR_MAX));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Starting armor, control point:");
+                            ui.add(egui::DragValue::new(&mut config.control_point_starting_armor).speed(0.01).clamp_range(0.0..=AppDefines::ARMOR_MAX));
+                        });
+                        ui.checkbox(&mut config.armor_blocks_ramming_and_hazards, "Armor blocks ramming and hazards");
+                        ui.checkbox(&mut config.armor_power_ups, "Armor power-ups");
+                        ui.checkbox(&mut config.ramming_enabled, "Ramming damage");
+                        ui.horizontal(|ui| {
+                            ui.label("Ramming damage per speed:");
+                            ui.add(egui::DragValue::new(&mut config.ramming_damage_per_speed).speed(0.001).clamp_range(0.0..=1.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Contact force threshold:");
+                            ui.add(egui::DragValue::new(&mut config.contact_force_threshold).speed(1000.0).clamp_range(0.0..=10_000_000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bot aim error (deg):");
+                            ui.add(egui::DragValue::new(&mut config.bot_aim_error_deg).speed(0.5).clamp_range(0.0..=90.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bot fire range:");
+                            ui.add(egui::DragValue::new(&mut config.bot_fire_range).speed(10.0).clamp_range(0.0..=2000.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bot look-ahead:");
+                            ui.add(egui::DragValue::new(&mut config.bot_ray_length).speed(1.0).clamp_range(0.0..=500.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Bot avoidance radius:");
+                            ui.add(egui::DragValue::new(&mut config.bot_avoidance_radius).speed(1.0).clamp_range(0.0..=500.0));
+                        });
+                        ui.separator();
+                        ui.checkbox(&mut config.zone_enabled, "Shrinking zone");
+                        ui.horizontal(|ui| {
+                            ui.label("Zone delay (s):");
+                            ui.add(egui::DragValue::new(&mut config.zone_delay_secs).speed(1.0).clamp_range(0.0..=3600.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Zone shrink time (s):");
+                            ui.add(egui::DragValue::new(&mut config.zone_shrink_secs).speed(1.0).clamp_range(0.0..=3600.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Zone final radius:");
+                            ui.add(egui::DragValue::new(&mut config.zone_final_radius).speed(5.0).clamp_range(0.0..=800.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Zone damage per second:");
+                            ui.add(egui::DragValue::new(&mut config.zone_damage_per_sec).speed(0.1).clamp_range(0.0..=100.0));
+                        });
+                        ui.checkbox(&mut config.hazards_ignore_spawn_protection, "Hazards hurt protected entities");
+                        ui.separator();
+                        ui.checkbox(&mut config.camping_enabled, "Anti-camping");
+                        ui.horizontal(|ui| {
+                            ui.label("Camping radius:");
+                            ui.add(egui::DragValue::new(&mut config.camping_radius).speed(1.0).clamp_range(1.0..=500.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Camping delay (s):");
+                            ui.add(egui::DragValue::new(&mut config.camping_secs).speed(0.5).clamp_range(0.0..=600.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Camping points per second:");
+                            ui.add(egui::DragValue::new(&mut config.camping_points_per_sec).speed(0.1).clamp_range(0.0..=100.0));
+                        });
+                        ui.checkbox(&mut config.camping_reveal, "Reveal campers");
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label("Game mode:");
+                            for mode in [GameMode::Deathmatch, GameMode::ControlPoint] {
+                                ui.radio_value(&mut config.game_mode, mode, mode.name());
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Capture time (s):");
+                            ui.add(egui::DragValue::new(&mut config.control_capture_secs).speed(0.5).clamp_range(0.0..=600.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Control points per second:");
+                            ui.add(egui::DragValue::new(&mut config.control_points_per_sec).speed(0.1).clamp_range(0.0..=100.0));
+                        });
+                        ui.separator();
+                        ui.label("Round end, 0 = none");
+                        ui.horizontal(|ui| {
+                            ui.label("Round duration (s):");
+                            ui.add(egui::DragValue::new(&mut config.match_duration_secs).clamp_range(0..=3600));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Score target:");
+                            ui.add(egui::DragValue::new(&mut config.score_target).clamp_range(0..=1000));
+                        });
                     }
+                    if self.controls.game_config != before {
+                        let config = self.controls.game_config.clone();
+                        self.apply_control(move |game_logic| game_logic.game_config = config);
+                    }
+                });
+
+                ui.menu_button("Teams", |ui| {
+                    let config = &mut self.controls.game_config;
+                    let friendly_fire = ui.checkbox(&mut config.friendly_fire, "Friendly fire").changed();
+                    let pass_through = ui.checkbox(&mut config.teammates_pass_through, "Teammates pass through each other").changed();
+                    if friendly_fire || pass_through {
+                        let (friendly_fire, pass_through) = (config.friendly_fire, config.teammates_pass_through);
+                        self.apply_control(move |game_logic| {
+                            game_logic.game_config.friendly_fire = friendly_fire;
+                            game_logic.game_config.teammates_pass_through = pass_through;
+                        });
+                    }
+                });
+
+                ui.menu_button("Profiling", |ui| {
+                    if ui.checkbox(&mut self.controls.profiling_enabled, "Time the step phases").changed() {
+                        let enabled = self.controls.profiling_enabled;
+                        self.apply_control(move |game_logic| game_logic.profiling_enabled = enabled);
+                    }
+                    egui::Grid::new("step_timings").striped(true).show(ui, |ui| {
+                        ui.label("Phase");
+                        ui.label("Average");
+                        ui.label("Worst");
+                        ui.end_row();
+                        for (phase, average, worst) in &self.controls.step_timings {
+                            ui.label(phase.name());
+                            ui.label(format!("{:.3} ms", average.as_secs_f64() * 1000.0));
+                            ui.label(format!("{:.3} ms", worst.as_secs_f64() * 1000.0));
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                let mut selected = self.controls.default_behavior.clone();
+                egui::ComboBox::from_label("AI")
+                    .selected_text(selected.clone())
+                    .show_ui(ui, |ui| {
+                        for name in &self.controls.behaviors {
+                            ui.selectable_value(&mut selected, name.clone(), name);
+                        }
+                    });
+                if selected != self.controls.default_behavior {
+                    self.controls.default_behavior = selected.clone();
+                    self.apply_control(move |game_logic| {
+                        if let Err(error) = game_logic.set_default_behavior(&selected) {
+                            game_logic.report(format!("[WARNING] {}", error), MessageType::Warning);
+                        }
+                    });
                 }
+
+                let label = if self.controls.horde_active { "Stop Horde" } else { "Start Horde" };
+                if ui.button(label).clicked() {
+                    let active = self.controls.horde_active;
+                    self.controls.horde_active = !active;
+                    self.apply_control(move |game_logic| if active { game_logic.stop_horde() } else { game_logic.start_horde() });
+                }
+                if let Some(status) = &self.controls.horde_status {
+                    ui.label(status);
+                }
+                ui.label(format!("Tick rate: {:.1} Hz", self.controls.achieved_tick_rate));
+                ui.label(GameUI::match_status(&self.controls));
             });
         });
     }
@@ -139,34 +1805,175 @@ impl GameUI {
 impl Default for GameUI {
     fn default() -> Self {
         let mut game_logic = GameLogic::new();
-        game_logic.generate_map();
+        game_logic.generate_map(None);
+        let commands = GameCommands::new(Arc::new(Mutex::new(game_logic)));
+        let metrics = ServerMetrics::new(Arc::new(AtomicUsize::new(0)));
+        GameLoop::spawn(commands.clone(), || AppDefines::TARGET_FPS_2D_PHYSICS, Some(metrics.clone()), None);
+        let controls = commands.render().latest().controls.clone();
+        let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
 
         Self {
-            game_logic: Arc::new(Mutex::new(game_logic)),
-            line_thickness: 4.0,
-            show_names: true,
-            show_background: true,
+            commands,
+            controls,
+            controls_edited_at: None,
+            view: ViewSettings::default(),
+            show_view_settings: false,
+            grid: Grid::default(),
+            labels: LabelConfig::default(),
+            show_mines: false,
+            show_ai_intents: false,
+            show_ghost_cameras: false,
+            show_trails: true,
+            trail_length: AppDefines::DEFAULT_BULLET_TRAIL,
+            trails: BulletTrails::default(),
+            show_movement_trails: false,
+            movement_trails: MovementTrails::default(),
+            show_barrels: true,
+            show_health_bars: true,
+            hide_full_health_bars: false,
+            seed_input: String::new(),
+            snapshot_path: AppDefines::DEFAULT_SNAPSHOT_FILE.to_string(),
+            map_path: AppDefines::DEFAULT_MAP_FILE.to_string(),
+            replay_path: AppDefines::DEFAULT_REPLAY_FILE.to_string(),
+            player: None,
+            followed: None,
+            follow_zoom: AppDefines::DEFAULT_FOLLOW_ZOOM,
+            follow_paused: false,
+            follow_zoom_changed: false,
+            reset_camera: false,
+            selected: None,
+            teleport_mode: false,
+            rename_input: String::new(),
+            controlled: None,
+            placement: None,
+            placement_origin: None,
+            toast: None,
+            status_bar: StatusBar::new(metrics.clone()),
+            kill_feed: KillFeed::new(),
+            score_history: ScoreHistory::new(),
+            hit_effects: HitEffects::new(),
+            map_editor: MapEditor::new(),
+            metrics,
+            pending_action: None,
+            messages,
         }
     }
 }
 
 impl eframe::App for GameUI {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Le rendu lit une image figée, de la partie en cours ou du replay
+        let snapshot = self.commands.render().latest();
+        self.sync_controls(&snapshot);
         self.show_menu(ctx);
+        self.show_confirmation(ctx);
+        self.show_view_settings(ctx);
+        self.score_history.show(ctx);
+        self.show_replay_controls(ctx);
+        self.status_bar.show(ctx);
+        if let Some(player) = &mut self.player {
+            player.advance(ctx.input(|input| input.stable_dt));
+        }
 
-        // Verrouille et appelle les fonctions update
-        if let Ok(mut game_logic) = self.game_logic.lock() {
-            game_logic.update_ai();
-            game_logic.step();
+        let (frame, obstacles, arena, max_health) = match &self.player {
+            Some(player) => {
+                let frame = player.frame().cloned().unwrap_or_default();
+                // Un replay ne garde pas les règles du match : la santé de départ par défaut, ou plus si une entité la dépasse
+                let max_health = frame.entities.iter().map(|entity| entity.health).fold(AppDefines::DEFAULT_STARTING_HEALTH, i32::max);
+                (
+                    frame,
+                    player.replay.obstacles_at(player.position).to_vec(),
+                    player.replay.header.arena(),
+                    max_health,
+                )
+            }
+            None => {
+                // Les corps sont dessinés entre les deux derniers pas, selon le temps écoulé depuis
+                let mut frame = snapshot.interpolated(snapshot.alpha(Instant::now()));
+                let obstacles = frame.obstacles.take().unwrap_or_default();
+                (frame, obstacles, snapshot.arena, snapshot.max_health)
+            }
+        };
+        let (width, height) = (arena.width as f64, arena.height as f64);
+        // La grille suit les dimensions de l'arène, de la partie en cours ou du replay
+        self.grid.update(self.view.grid_spacing, width, height);
+        let teams = TeamTotals::of(&frame.entities);
+        let bullet_range = self.player.is_none().then_some(snapshot.bullet_range);
+        // Le fil ne suit que la partie en cours : un replay ne garde pas les événements
+        let now = Instant::now();
+        let live = self.player.is_none();
+        if live {
+            if self.kill_feed.visible {
+                self.kill_feed.update(&snapshot.events, &frame.entities, now);
+            }
+            if self.score_history.visible {
+                self.score_history.update(&snapshot.events, &frame.entities, snapshot.sim_time.as_secs_f64());
+            }
+            if self.hit_effects.enabled {
+                self.hit_effects.update(&snapshot.events, &frame.entities, now);
+            }
+        }
+        if !live || !self.kill_feed.visible {
+            self.kill_feed.clear();
+        }
+        if !live || !self.score_history.visible {
+            self.score_history.clear();
+        }
+        if !live || !self.hit_effects.enabled {
+            self.hit_effects.clear();
+        }
+        if self.toast.as_ref().is_some_and(|(_, shown_at)| now.saturating_duration_since(*shown_at).as_secs_f32() >= AppDefines::TOAST_SECS) {
+            self.toast = None;
+        }
+        // Échap annule d'abord le placement, avant de relâcher l'entité conduite au clavier
+        if self.placement.is_some() && ctx.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.placement = None;
+            self.placement_origin = None;
+        }
+        if self.player.is_none() {
+            self.drive_controlled(ctx, &frame.entities);
+        }
+        // Les traînées ne suivent que la partie en cours : un replay ne garde pas les corps des balles
+        let trails = if self.show_trails && self.player.is_none() {
+            self.trails.record(&snapshot);
+            let width = (self.view.line_thickness / 2.0).max(1.0);
+            Some(self.trails.plot_item(&snapshot.bullet_handles, &frame.bullets, self.trail_length, egui::Color32::from_rgb(255, 190, 80), width))
+        } else {
+            self.trails.clear();
+            None
+        };
+        let movement_trails = self.show_movement_trails.then(|| {
+            let width = (self.view.line_thickness / 3.0).max(1.0);
+            match &mut self.player {
+                Some(player) => self.movement_trails.plot_item(player.trails(), &frame.entities, width),
+                None => self.movement_trails.plot_item(&snapshot.trails, &frame.entities, width),
+            }
+        });
+        // La tête d'une traînée est un peu plus grosse que la balle seule
+        let bullet_radius = if trails.is_some() { self.view.line_thickness * 0.75 } else { self.view.line_thickness / 2.0 };
+        // L'entité suivie a disparu (détruite, déconnectée, absente du replay) : retour à la caméra libre
+        let followed_position = self.followed
+            .and_then(|id| frame.entities.iter().find(|entity| entity.id == id))
+            .map(|entity| [entity.x as f64, entity.y as f64]);
+        if self.followed.is_some() && followed_position.is_none() {
+            self.stop_follow();
+        }
+        // L'entité inspectée a été retirée : une remise à zéro de la manche la garde, avec son id
+        if self.player.is_none() && self.selected.is_some_and(|id| !frame.entities.iter().any(|entity| entity.id == id)) {
+            self.select(None);
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::SidePanel::left("entity_list").show_inside(ui, |ui| {
-                let game_logic = self.game_logic.lock().unwrap();
-
+                if !teams.is_empty() {
+                    GameUI::show_team_totals(ui, &teams);
+                    ui.separator();
+                }
                 TableBuilder::new(ui)
                     .column(Column::exact(200.0).resizable(false))
                     .column(Column::exact(100.0).resizable(false))
+                    .column(Column::exact(60.0).resizable(false))
+                    .columns(Column::exact(60.0).resizable(false), 4)
                     .header(20.0, |mut header| {
                         header.col(|ui| {
                             ui.heading("Player Name");
@@ -174,11 +1981,19 @@ impl eframe::App for GameUI {
                         header.col(|ui| {
                             ui.heading("Score");
                         });
+                        header.col(|ui| {
+                            ui.heading("Team");
+                        });
+                        for title in ["Shots", "Hits", "Kills", "Acc."] {
+                            header.col(|ui| {
+                                ui.heading(title);
+                            });
+                        }
                     })
                     .body(|mut body| {
                         let padding = 10.0;
 
-                        for (index, entity) in game_logic.entities.iter().enumerate() {
+                        for (index, entity) in frame.entities.iter().enumerate() {
                             body.row(30.0, |mut row| {
                                 let bg_color = if index % 2 == 0 {
                                     egui::Color32::from_gray(20)
@@ -199,77 +2014,122 @@ impl eframe::App for GameUI {
                                         ui.colored_label(egui::Color32::from_rgb(255, 255, 255), &entity.score.to_string());
                                     });
                                 });
+                                row.col(|ui| {
+                                    ui.painter().rect_filled(ui.max_rect(), 0.0, bg_color);
+                                    ui.horizontal_centered(|ui| {
+                                        ui.add_space(padding);
+                                        match entity.team {
+                                            Some(team) => ui.colored_label(GameUI::team_color(team), team.to_string()),
+                                            None => ui.colored_label(egui::Color32::from_rgb(255, 255, 255), "-"),
+                                        };
+                                    });
+                                });
+                                let breakdown = &entity.breakdown;
+                                let accuracy = breakdown.accuracy().map_or("-".to_string(), |accuracy| format!("{:.0}%", accuracy * 100.0));
+                                // Une entité détruite quitte l'arène : ses morts n'apparaissent qu'au classement
+                                for value in [breakdown.shots_fired.to_string(), breakdown.hits.to_string(), breakdown.kills.to_string(), accuracy] {
+                                    row.col(|ui| {
+                                        ui.painter().rect_filled(ui.max_rect(), 0.0, bg_color);
+                                        ui.horizontal_centered(|ui| {
+                                            ui.add_space(padding);
+                                            ui.colored_label(egui::Color32::from_rgb(255, 255, 255), &value);
+                                        });
+                                    });
+                                }
                             });
                         }
                     });
             });
 
+            // L'inspecteur lit la partie en cours : il n'a rien à montrer d'un replay
+            if self.map_editor.active {
+                egui::SidePanel::right("map_editor").show_inside(ui, |ui| {
+                    self.map_editor.show_panel(ui, &self.status_bar, &self.commands, &self.messages, &mut self.map_path);
+                });
+            } else if self.selected.is_some() && self.player.is_none() {
+                egui::SidePanel::right("inspector").show_inside(ui, |ui| self.show_inspector(ui, snapshot.inspected.as_ref()));
+            }
+
             egui::CentralPanel::default().show_inside(ui, |ui| {
-                Plot::new("dynamic_plot")
+                self.show_camera_controls(ui, &frame.entities);
+                let plot = Plot::new("dynamic_plot")
                     .show_axes([false, false])
                     .allow_boxed_zoom(false)
                     .show_grid(false)
                     .show_x(false)
                     .show_y(false)
                     .data_aspect(1.0)
+                    // Glisser déplace un obstacle, ou oriente l'entité placée, pas la vue
+                    .allow_drag(!self.map_editor.active && self.placement.is_none())
                     .show(ui, |plot_ui| {
-                        if self.show_background {
-                            let x_lines: Vec<f64> = (0..=1200).step_by(50).map(|x| x as f64).collect();
-                            let y_lines: Vec<f64> = (0..=1000).step_by(50).map(|y| y as f64).collect();
-
-                            for &x in &x_lines {
-                                let vertical_line = Line::new(PlotPoints::new(vec![[x, 0.0], [x, 1000.0]]))
-                                    .color(egui::Color32::from_rgb(0, 40, 0))
-                                    .width(self.line_thickness / 3.0)
-                                    .style(LineStyle::Solid);
-                                plot_ui.line(vertical_line);
-                            }
-
-                            for &y in &y_lines {
-                                let horizontal_line = Line::new(PlotPoints::new(vec![[0.0, y], [1200.0, y]]))
-                                    .color(egui::Color32::from_rgb(0, 40, 0))
-                                    .width(self.line_thickness / 3.0)
-                                    .style(LineStyle::Solid);
-                                plot_ui.line(horizontal_line);
-                            }
+                        self.follow_camera(plot_ui, followed_position, width);
+                        if self.map_editor.active {
+                            self.map_editor.handle_input(plot_ui, &self.status_bar, &self.commands);
+                            self.map_editor.draw_preview(plot_ui);
+                        } else if self.placement.is_some() && self.player.is_none() {
+                            self.handle_placement(plot_ui);
+                        } else if self.player.is_none() {
+                            self.handle_click(plot_ui, &frame.entities);
+                            self.handle_shortcuts(plot_ui);
+                        }
+                        if self.view.show_background {
+                            self.grid.draw(plot_ui, self.view.grid_color(), self.view.line_thickness / 3.0);
                         }
 
-                        let game_logic = self.game_logic.lock().unwrap();
-
-                        let plot_points = Points::new(
-                            game_logic.bullets
-                                .iter()
-                                .map(|bullet| {
-                                    let pos = game_logic.physics_engine.bodies[bullet.handle].translation();
-                                    [pos.x as f64, pos.y as f64]
-                                })
-                                .collect::<Vec<_>>(),
-                        )
-                            .radius(self.line_thickness / 2.0)
-                            .name("Bullets");
-                        plot_ui.points(plot_points);
-
-                        drop(game_logic); // On drop explicitement le lock avant d’appeler ces méthodes
+                        // Sous tout le reste, les entités restent visibles dans les zones
+                        self.draw_hazards(plot_ui, &frame.hazards);
+                        if let Some(movement_trails) = movement_trails {
+                            plot_ui.add(movement_trails);
+                        }
+                        if let Some(trails) = trails {
+                            plot_ui.add(trails);
+                        }
+                        self.draw_bullets(plot_ui, &frame.bullets, &frame.bullet_kinds, bullet_radius);
 
-                        self.display_entities(plot_ui);
-                        self.draw_obstacles(plot_ui);
+                        // Sous les entités, pour ne pas les cacher
+                        if self.show_ai_intents && self.player.is_none() {
+                            self.draw_ai_intents(plot_ui, &snapshot.ai_intents, &frame.entities);
+                        }
+                        self.display_entities(plot_ui, &frame.entities, max_health, bullet_range);
+                        self.hit_effects.draw(plot_ui, now);
+                        self.draw_obstacles(plot_ui, &obstacles);
+                        if let Some(zone) = frame.zone {
+                            self.draw_zone(plot_ui, zone, &arena);
+                        }
+                        self.draw_control_points(plot_ui, &frame.control_points, &frame.entities);
+                        if self.player.is_none() {
+                            self.draw_power_ups(plot_ui, &snapshot.power_ups);
+                            if self.show_mines {
+                                self.draw_mines(plot_ui, &snapshot.mines);
+                            }
+                            if self.show_ghost_cameras {
+                                self.draw_ghost_cameras(plot_ui, &snapshot.ghost_cameras);
+                            }
+                        }
 
                         let world_boundary = Line::new(PlotPoints::new(vec![
                             [0.0, 0.0],
-                            [1200.0, 0.0],
-                            [1200.0, 1000.0],
-                            [0.0, 1000.0],
+                            [width, 0.0],
+                            [width, height],
+                            [0.0, height],
                             [0.0, 0.0],
                         ]))
-                            .color(egui::Color32::GREEN)
+                            .color(self.view.boundary_color())
                             .name("World Boundary")
-                            .width(self.line_thickness)
+                            .width(self.view.line_thickness)
                             .style(LineStyle::Solid);
                         plot_ui.line(world_boundary);
                     });
+                if teams.len() > 1 {
+                    GameUI::draw_team_legend(ui, plot.response.rect, &teams);
+                }
+                self.kill_feed.show(ui, plot.response.rect, now);
+                self.show_notices(ui, plot.response.rect);
             });
 
             ctx.request_repaint();
         });
+
+        self.status_bar.end_frame();
     }
 }
@@ -0,0 +1,90 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use eframe::egui::{self, Color32, Shape, Stroke, Ui};
+use egui_plot::{PlotBounds, PlotGeometry, PlotItem, PlotPoint, PlotTransform};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::replay::EntityFrame;
+use crate::game_logic::trail_store::{TrailPolylines, TrailStore};
+
+/// The paths the entities took, drawn as faint lines in their color behind them.
+///
+/// The trails are laid out in the same buffers every frame, see `TrailStore::assemble`.
+#[derive(Default)]
+pub struct MovementTrails {
+    /// Handed to the plot each frame, refilled in place once the plot dropped it.
+    polylines: Arc<TrailPolylines>,
+}
+
+impl MovementTrails {
+    /// Builds the plot item drawing the trails, each ending at the drawn position of its entity.
+    ///
+    /// # Parameters
+    /// - `store`: The trails of the live game or of the replay.
+    /// - `heads`: The entities drawn, at their drawn position.
+    /// - `width`: The width of the trails in points.
+    pub fn plot_item(&mut self, store: &TrailStore, heads: &[EntityFrame], width: f32) -> MovementTrailsItem {
+        // Les tampons de l'image précédente sont réutilisés, sauf si le graphique les tient encore
+        if Arc::get_mut(&mut self.polylines).is_none() {
+            self.polylines = Arc::default();
+        }
+        store.assemble(heads, Arc::get_mut(&mut self.polylines).unwrap());
+        MovementTrailsItem { polylines: Arc::clone(&self.polylines), width }
+    }
+}
+
+/// The plot item drawing every movement trail, the older segments more transparent.
+pub struct MovementTrailsItem {
+    polylines: Arc<TrailPolylines>,
+    width: f32,
+}
+
+impl PlotItem for MovementTrailsItem {
+    fn shapes(&self, _ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        for (run, points) in self.polylines.trails() {
+            let color = Color32::from_rgb(run.color[0], run.color[1], run.color[2]);
+            let segments = points.len().saturating_sub(1);
+            let mut previous = None;
+            for (index, &(x, y)) in points.iter().enumerate() {
+                let position = transform.position_from_point(&PlotPoint::new(x, y));
+                if let Some(start) = previous {
+                    let fade = index as f32 / segments as f32;
+                    shapes.push(Shape::line_segment(
+                        [start, position],
+                        Stroke::new(self.width, color.gamma_multiply(AppDefines::MOVEMENT_TRAIL_OPACITY * fade)),
+                    ));
+                }
+                previous = Some(position);
+            }
+        }
+    }
+
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        "Movement Trails"
+    }
+
+    fn color(&self) -> Color32 {
+        Color32::GRAY
+    }
+
+    fn highlight(&mut self) {}
+
+    fn highlighted(&self) -> bool {
+        false
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::None
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        PlotBounds::NOTHING
+    }
+
+    fn id(&self) -> Option<egui::Id> {
+        None
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use eframe::egui::{self, Color32, Context};
+use egui_plot::{Legend, Line, MarkerShape, Plot, PlotPoints, Points};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::event_queue::EventQueue;
+use crate::game_logic::observer::GameEvent;
+use crate::game_logic::replay::EntityFrame;
+
+/// The color of the markers of the points lost to a rule of the match.
+const PENALTY_COLOR: Color32 = Color32::from_rgb(240, 110, 100);
+/// The color of the markers of the points earned from a rule of the match.
+const BONUS_COLOR: Color32 = Color32::from_rgb(120, 220, 120);
+
+/// The scores of one entity over the simulated time.
+struct Series {
+    name: String,
+    color: Color32,
+    /// The simulated time in seconds and the score, oldest first.
+    points: VecDeque<[f64; 2]>,
+}
+
+/// A score change told by a `Penalty` or `Bonus` event, drawn over the line of its entity.
+struct Marker {
+    /// The simulated time in seconds and the score right after the change.
+    point: [f64; 2],
+    /// Whether the entity earned the points.
+    bonus: bool,
+}
+
+/// The 'Score History' window: the score of each entity over the simulated time, with a
+/// marker wherever a rule of the match, such as the score decay or the comeback bonus,
+/// changed it.
+///
+/// The scores are sampled from the frames drawn every `SCORE_HISTORY_SAMPLE_SECS` of
+/// simulated time, and the events are read with their own cursor like the kill feed.
+/// At most `SCORE_HISTORY_SAMPLES` samples are kept per entity, and as many markers.
+pub struct ScoreHistory {
+    /// Whether the window is shown and the scores sampled.
+    pub visible: bool,
+    /// The number of the last event read, `None` until the window is shown.
+    cursor: Option<u64>,
+    /// The scores of each entity, by id.
+    series: BTreeMap<u32, Series>,
+    /// The score changes told by the events, oldest first.
+    markers: VecDeque<Marker>,
+    /// The simulated time of the last sample, in seconds.
+    last_sample: Option<f64>,
+}
+
+impl ScoreHistory {
+    /// Creates a hidden, empty history.
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            cursor: None,
+            series: BTreeMap::new(),
+            markers: VecDeque::new(),
+            last_sample: None,
+        }
+    }
+
+    /// Samples the scores when it is time, and adds the score changes not read yet.
+    ///
+    /// # Parameters
+    /// - `events`: The event queue of the game logic, left as is.
+    /// - `entities`: The entities of the frame drawn.
+    /// - `time`: The simulated time of the frame, in seconds.
+    pub fn update(&mut self, events: &EventQueue, entities: &[EntityFrame], time: f64) {
+        // Le temps simulé revient en arrière après une remise à zéro : l'historique repart de rien
+        if self.last_sample.is_some_and(|last| time < last) {
+            self.clear();
+        }
+        if self.last_sample.map_or(true, |last| time - last >= AppDefines::SCORE_HISTORY_SAMPLE_SECS) {
+            self.last_sample = Some(time);
+            for entity in entities {
+                let series = self.series.entry(entity.id).or_insert_with(|| Series {
+                    name: entity.name.clone(),
+                    color: Color32::from_rgb(entity.color[0], entity.color[1], entity.color[2]),
+                    points: VecDeque::new(),
+                });
+                series.name.clone_from(&entity.name);
+                series.points.push_back([time, entity.score as f64]);
+                if series.points.len() > AppDefines::SCORE_HISTORY_SAMPLES {
+                    series.points.pop_front();
+                }
+            }
+        }
+
+        let cursor = *self.cursor.get_or_insert(events.cursor());
+        for queued in events.since(cursor) {
+            let (id, bonus) = match &queued.event {
+                GameEvent::Penalty { id, .. } => (*id, false),
+                GameEvent::Bonus { id, .. } => (*id, true),
+                _ => continue,
+            };
+            let Some(entity) = entities.iter().find(|entity| entity.id == id) else { continue };
+            // Le changement est aussi échantillonné, pour que la ligne passe par le marqueur
+            if let Some(series) = self.series.get_mut(&id) {
+                series.points.push_back([time, entity.score as f64]);
+            }
+            self.markers.push_back(Marker { point: [time, entity.score as f64], bonus });
+            if self.markers.len() > AppDefines::SCORE_HISTORY_SAMPLES {
+                self.markers.pop_front();
+            }
+        }
+        self.cursor = Some(events.cursor());
+    }
+
+    /// Forgets the scores and the markers, the events happening while the window is hidden are not shown later.
+    pub fn clear(&mut self) {
+        self.cursor = None;
+        self.series.clear();
+        self.markers.clear();
+        self.last_sample = None;
+    }
+
+    /// Shows the window, if visible.
+    ///
+    /// # Parameters
+    /// - `ctx`: The Egui context.
+    pub fn show(&mut self, ctx: &Context) {
+        let mut open = self.visible;
+        egui::Window::new("Score History")
+            .open(&mut open)
+            .default_size([420.0, 260.0])
+            .show(ctx, |ui| {
+                Plot::new("score_history")
+                    .legend(Legend::default())
+                    .x_axis_label("Simulated time (s)")
+                    .y_axis_label("Score")
+                    .show(ui, |plot_ui| {
+                        for series in self.series.values() {
+                            let points: Vec<[f64; 2]> = series.points.iter().copied().collect();
+                            plot_ui.line(Line::new(PlotPoints::from(points)).color(series.color).name(&series.name));
+                        }
+                        for (bonus, name, shape, color) in [
+                            (false, "Points lost", MarkerShape::Down, PENALTY_COLOR),
+                            (true, "Points earned", MarkerShape::Up, BONUS_COLOR),
+                        ] {
+                            let points: Vec<[f64; 2]> = self.markers.iter().filter(|marker| marker.bonus == bonus).map(|marker| marker.point).collect();
+                            plot_ui.points(Points::new(points).shape(shape).radius(4.0).filled(true).color(color).name(name));
+                        }
+                    });
+            });
+        self.visible = open;
+    }
+}
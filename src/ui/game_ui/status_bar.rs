@@ -0,0 +1,124 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use eframe::egui::{Context, TopBottomPanel};
+
+use crate::app_defines::AppDefines;
+use crate::server::metrics::{MetricsSample, ServerMetrics};
+
+/// The thin bar at the bottom of the game UI, telling a slow rendering from a slow simulation.
+///
+/// It measures the frame rate of the UI and the time the UI waits for the game loop, and
+/// shows them with the simulation metrics recorded by the game loop, refreshed every
+/// `STATUS_BAR_REFRESH_MS`. The measures go to the shared `ServerMetrics`, so the server UI
+/// shows the same values. Once hidden, the bar no longer reads the clock.
+pub struct StatusBar {
+    /// Whether the bar is shown and measures the frames.
+    pub visible: bool,
+    metrics: ServerMetrics,
+    /// The time the UI waited for the answers of the game loop since `window_start`.
+    lock_wait: Cell<Duration>,
+    /// The number of frames drawn since `window_start`.
+    frames: u32,
+    /// When the current measure started, `None` until the first frame shown.
+    window_start: Option<Instant>,
+    /// The frame rate and the average wait for the game loop per frame of the last measure.
+    measured: Option<(f32, Duration)>,
+    /// The simulation metrics shown, sampled with the last measure.
+    sample: MetricsSample,
+}
+
+impl StatusBar {
+    /// Creates a visible status bar.
+    ///
+    /// # Parameters
+    /// - `metrics`: The metrics recorded by the game loop, where the measures of the UI go too.
+    pub fn new(metrics: ServerMetrics) -> Self {
+        let sample = metrics.sample();
+        Self {
+            visible: true,
+            metrics,
+            lock_wait: Cell::new(Duration::ZERO),
+            frames: 0,
+            window_start: None,
+            measured: None,
+            sample,
+        }
+    }
+
+    /// Waits for the answer of a command sent to the game loop, adding the time waited to the
+    /// measure while the bar is visible. The drawing never waits: it reads the render snapshots.
+    ///
+    /// # Parameters
+    /// - `command`: Sends the command and waits for its answer, see `GameCommands`.
+    ///
+    /// # Returns
+    /// The answer of the command.
+    pub fn wait<R>(&self, command: impl FnOnce() -> R) -> R {
+        if !self.visible {
+            return command();
+        }
+        let started = Instant::now();
+        let answer = command();
+        self.lock_wait.set(self.lock_wait.get() + started.elapsed());
+        answer
+    }
+
+    /// Counts a frame drawn, and ends the measure once `STATUS_BAR_REFRESH_MS` passed.
+    pub fn end_frame(&mut self) {
+        if !self.visible {
+            // Mesures effacées une seule fois : la barre cachée ne coûte plus rien
+            if self.window_start.take().is_some() {
+                self.measured = None;
+                self.metrics.record_ui(None);
+            }
+            return;
+        }
+        let now = Instant::now();
+        let Some(window_start) = self.window_start else {
+            self.start_window(now);
+            return;
+        };
+        self.frames += 1;
+        let window = now.saturating_duration_since(window_start);
+        if window >= Duration::from_millis(AppDefines::STATUS_BAR_REFRESH_MS) {
+            let frame_rate = self.frames as f32 / window.as_secs_f32();
+            let lock_wait = self.lock_wait.get() / self.frames;
+            self.measured = Some((frame_rate, lock_wait));
+            self.metrics.record_ui(self.measured);
+            self.sample = self.metrics.sample();
+            self.start_window(now);
+        }
+    }
+
+    /// Starts a new measure.
+    fn start_window(&mut self, now: Instant) {
+        self.window_start = Some(now);
+        self.frames = 0;
+        self.lock_wait.set(Duration::ZERO);
+    }
+
+    /// Shows the bar, unless it is hidden.
+    pub fn show(&self, ctx: &Context) {
+        if !self.visible {
+            return;
+        }
+        let sample = &self.sample;
+        TopBottomPanel::bottom("status_bar").exact_height(20.0).show(ctx, |ui| {
+            ui.horizontal_centered(|ui| {
+                let ui_text = self.measured.map_or("UI: -".to_string(), |(frame_rate, lock_wait)| {
+                    format!("UI: {:.0} FPS, game wait {:.2} ms", frame_rate, lock_wait.as_secs_f64() * 1000.0)
+                });
+                ui.label(ui_text);
+                ui.separator();
+                ui.label(format!("Simulation: {:.1} / {:.1} Hz", sample.tick_rate, sample.target_tick_rate));
+                ui.separator();
+                ui.label(format!("Entities: {} ({} AI)", sample.players + sample.ai_entities, sample.ai_entities));
+                ui.separator();
+                ui.label(format!("Bullets: {}", sample.bullets));
+                ui.separator();
+                ui.label(format!("Obstacles: {}", sample.obstacles));
+            });
+        });
+    }
+}
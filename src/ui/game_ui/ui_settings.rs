@@ -0,0 +1,136 @@
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::replay::EntityFrame;
+
+/// What the label drawn above each entity shows, on one line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelConfig {
+    /// The name of the entity, after its team.
+    pub name: bool,
+    /// The health of the entity.
+    pub health: bool,
+    /// The score of the entity.
+    pub score: bool,
+    /// The id of the entity.
+    pub id: bool,
+    /// An "[AI]" suffix on the entities driven by the AI.
+    pub ai_suffix: bool,
+}
+
+impl Default for LabelConfig {
+    fn default() -> Self {
+        Self { name: true, health: false, score: false, id: false, ai_suffix: false }
+    }
+}
+
+impl LabelConfig {
+    /// Returns the label of an entity, empty when nothing is shown for it.
+    ///
+    /// # Parameters
+    /// - `entity`: The entity labelled.
+    ///
+    /// # Returns
+    /// The parts shown, separated by spaces, e.g. "#3 [T1] Bob 80 HP 12 pts [AI]".
+    pub fn label(&self, entity: &EntityFrame) -> String {
+        let mut parts = Vec::with_capacity(5);
+        if self.id {
+            parts.push(format!("#{}", entity.id));
+        }
+        if self.name {
+            parts.push(match entity.team {
+                Some(team) => format!("[T{}] {}", team, entity.name),
+                None => entity.name.clone(),
+            });
+        }
+        if self.health {
+            parts.push(format!("{} HP", entity.health));
+        }
+        if self.score {
+            parts.push(format!("{} pts", entity.score));
+        }
+        if self.ai_suffix && entity.is_ai {
+            parts.push("[AI]".to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+/// How the arena itself is drawn: its background grid, its boundary and the thickness of the lines.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewSettings {
+    /// Whether the background grid is drawn.
+    pub show_background: bool,
+    /// The distance between two lines of the grid, in world units.
+    pub grid_spacing: f64,
+    /// The color of the grid, in sRGB.
+    pub grid_color: [u8; 3],
+    /// The color of the boundary of the arena, in sRGB.
+    pub boundary_color: [u8; 3],
+    /// The thickness the lines of the arena are drawn with, from 1 to 20.
+    pub line_thickness: f32,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            show_background: true,
+            grid_spacing: AppDefines::DEFAULT_GRID_SPACING,
+            grid_color: [0, 40, 0],
+            boundary_color: [0, 255, 0],
+            line_thickness: 4.0,
+        }
+    }
+}
+
+impl ViewSettings {
+    /// Returns the color of the grid.
+    pub fn grid_color(&self) -> Color32 {
+        Color32::from_rgb(self.grid_color[0], self.grid_color[1], self.grid_color[2])
+    }
+
+    /// Returns the color of the boundary of the arena.
+    pub fn boundary_color(&self) -> Color32 {
+        Color32::from_rgb(self.boundary_color[0], self.boundary_color[1], self.boundary_color[2])
+    }
+}
+
+/// The display options of the game UI kept from one run to the next.
+///
+/// The fields missing from older preferences take their default value, see `UiPreferences`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    pub labels: LabelConfig,
+    pub view: ViewSettings,
+    pub show_trails: bool,
+    pub trail_length: usize,
+    pub show_movement_trails: bool,
+    pub show_barrels: bool,
+    pub show_health_bars: bool,
+    pub hide_full_health_bars: bool,
+    pub show_status_bar: bool,
+    pub show_kill_feed: bool,
+    pub show_hit_effects: bool,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            labels: LabelConfig::default(),
+            view: ViewSettings::default(),
+            show_trails: true,
+            trail_length: AppDefines::DEFAULT_BULLET_TRAIL,
+            show_movement_trails: false,
+            show_barrels: true,
+            show_health_bars: true,
+            hide_full_health_bars: false,
+            show_status_bar: true,
+            show_kill_feed: true,
+            show_hit_effects: true,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub(crate) mod game_ui;
 pub(crate) mod server_ui;
 pub(crate) mod combined_ui;
+pub(crate) mod preferences;
 
 pub use combined_ui::CombinedUI;
\ No newline at end of file
@@ -0,0 +1,174 @@
+use eframe::egui::{self, Color32, Visuals};
+use serde::{Deserialize, Serialize};
+
+use crate::app_defines::AppDefines;
+use crate::ui::game_ui::ui_settings::UiSettings;
+use crate::ui::server_ui::message_view::MessageFilters;
+
+/// The look of the whole window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// The dark theme, with the selections and links in the accent color.
+    Accent,
+}
+
+impl Theme {
+    /// All the themes, in the order of the theme switcher.
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::Accent];
+}
+
+/// The preferences of the window kept by eframe from one run to the next.
+///
+/// Stored data that is missing, cannot be read or comes from a newer version gives the
+/// defaults, without a word. The fields added after a version take their default value
+/// when older preferences are read; `version` is raised when a field changes meaning.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiPreferences {
+    /// The version of the preferences, `UI_PREFERENCES_VERSION` when they were saved.
+    pub version: u32,
+    /// The display options of the game UI.
+    pub game: UiSettings,
+    pub theme: Theme,
+    /// The accent color of `Theme::Accent`, in sRGB.
+    pub accent: [u8; 3],
+    /// The filters of the message log of the server UI.
+    pub message_filters: MessageFilters,
+    /// Whether the server UI was the tab shown, rather than the game UI.
+    pub show_server_ui: bool,
+}
+
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self {
+            version: AppDefines::UI_PREFERENCES_VERSION,
+            game: UiSettings::default(),
+            theme: Theme::default(),
+            accent: [255, 140, 0],
+            message_filters: MessageFilters::default(),
+            show_server_ui: true,
+        }
+    }
+}
+
+impl UiPreferences {
+    /// Reads the preferences kept by eframe.
+    ///
+    /// # Parameters
+    /// - `storage`: The storage of eframe, `None` when it keeps nothing.
+    ///
+    /// # Returns
+    /// The preferences kept, or the defaults.
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let Some(preferences) = storage.and_then(|storage| eframe::get_value::<UiPreferences>(storage, AppDefines::UI_PREFERENCES_KEY)) else {
+            return Self::default();
+        };
+        // Des préférences plus récentes pourraient donner un autre sens aux mêmes champs
+        if preferences.version > AppDefines::UI_PREFERENCES_VERSION {
+            return Self::default();
+        }
+        Self { version: AppDefines::UI_PREFERENCES_VERSION, ..preferences }
+    }
+
+    /// Hands the preferences to eframe, which writes them.
+    ///
+    /// # Parameters
+    /// - `storage`: The storage of eframe.
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, AppDefines::UI_PREFERENCES_KEY, self);
+    }
+
+    /// Returns the look of the window for the theme chosen.
+    pub fn visuals(&self) -> Visuals {
+        match self.theme {
+            Theme::Dark => Visuals::dark(),
+            Theme::Light => Visuals::light(),
+            Theme::Accent => {
+                let accent = Color32::from_rgb(self.accent[0], self.accent[1], self.accent[2]);
+                let mut visuals = Visuals::dark();
+                visuals.selection.bg_fill = accent.gamma_multiply(0.6);
+                visuals.selection.stroke = egui::Stroke::new(1.0, accent);
+                visuals.hyperlink_color = accent;
+                visuals.widgets.hovered.bg_stroke = egui::Stroke::new(1.0, accent);
+                visuals
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::MessageType;
+
+    /// A storage of eframe kept in memory.
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    /// Returns a storage holding `record` as the preferences.
+    fn storage_with(record: &str) -> MemoryStorage {
+        let mut storage = MemoryStorage::default();
+        eframe::Storage::set_string(&mut storage, AppDefines::UI_PREFERENCES_KEY, record.to_string());
+        storage
+    }
+
+    #[test]
+    fn preferences_round_trip_through_the_storage() {
+        let mut preferences = UiPreferences { theme: Theme::Accent, accent: [10, 200, 30], show_server_ui: false, ..UiPreferences::default() };
+        preferences.game.show_trails = false;
+        preferences.game.trail_length = 7;
+        preferences.game.labels.id = true;
+        preferences.game.view.line_thickness = 4.5;
+        preferences.game.view.grid_color = [1, 2, 3];
+        preferences.message_filters.hidden_types = vec![MessageType::Debug, MessageType::GameEvent];
+        preferences.message_filters.relative_times = true;
+        assert_ne!(preferences, UiPreferences::default());
+
+        let mut storage = MemoryStorage::default();
+        preferences.save(&mut storage);
+        assert_eq!(UiPreferences::load(Some(&storage)), preferences);
+    }
+
+    #[test]
+    fn old_records_take_the_defaults_of_the_newer_fields() {
+        // Des préférences écrites avant le thème, les filtres et la plupart des options d'affichage
+        let storage = storage_with("(version: 1, game: (show_trails: false, view: (line_thickness: 3.0)), show_server_ui: false)");
+        let preferences = UiPreferences::load(Some(&storage));
+
+        let mut expected = UiPreferences { show_server_ui: false, ..UiPreferences::default() };
+        expected.game.show_trails = false;
+        expected.game.view.line_thickness = 3.0;
+        assert_eq!(preferences, expected);
+
+        // Sans version, les préférences datent d'avant elle
+        let storage = storage_with("(theme: Light)");
+        assert_eq!(UiPreferences::load(Some(&storage)), UiPreferences { theme: Theme::Light, ..UiPreferences::default() });
+    }
+
+    #[test]
+    fn missing_corrupt_or_newer_records_give_the_defaults() {
+        assert_eq!(UiPreferences::load(None), UiPreferences::default());
+        assert_eq!(UiPreferences::load(Some(&MemoryStorage::default())), UiPreferences::default());
+        assert_eq!(UiPreferences::load(Some(&storage_with("(theme: Neon"))), UiPreferences::default());
+        assert_eq!(UiPreferences::load(Some(&storage_with("(theme: Neon)"))), UiPreferences::default());
+        let newer = format!("(version: {}, theme: Light)", AppDefines::UI_PREFERENCES_VERSION + 1);
+        assert_eq!(UiPreferences::load(Some(&storage_with(&newer))), UiPreferences::default());
+    }
+}
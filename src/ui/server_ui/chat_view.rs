@@ -0,0 +1,153 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use eframe::egui::{self, Color32, RichText, ScrollArea, Ui};
+
+use crate::app_defines::AppDefines;
+use crate::game_logic::command::GameCommands;
+use crate::server::server_thread::ServerThread;
+use crate::types::{add_message, format_clock, MessageHub, MessageOrigin, MessageType};
+use crate::StyledMessage;
+
+/// The chat of the clients, as shown in the chat tab of the server UI.
+///
+/// The chat messages of the copy of the log published by the hub are copied as they arrive,
+/// the other messages are skipped. Each line shows the color of the entity of its sender, and
+/// the operator can mute a sender, or answer every client from the box at the bottom.
+pub struct ChatView {
+    /// The messages of the server, shared with the threads adding them.
+    messages: MessageHub,
+    /// The server, relaying the messages of the operator and dropping those of the muted players.
+    server: ServerThread,
+    /// The game logic, giving the colors of the senders.
+    commands: GameCommands,
+    /// The chat messages copied from the shared log, oldest first.
+    lines: VecDeque<StyledMessage>,
+    /// The sequence number in the shared log of the next message to read.
+    next: u64,
+    /// Whether the chat follows the new messages while it is scrolled to the bottom.
+    auto_scroll: bool,
+    /// The message the operator is typing.
+    draft: String,
+}
+
+impl ChatView {
+    /// Creates an empty chat, following the new messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages of the server, shared with the threads adding them.
+    /// * `server` - The server, relaying the messages of the operator.
+    /// * `commands` - The way into the game logic, whose render snapshots give the colors of the senders.
+    ///
+    pub fn new(messages: MessageHub, server: ServerThread, commands: GameCommands) -> Self {
+        ChatView { messages, server, commands, lines: VecDeque::new(), next: 0, auto_scroll: true, draft: String::new() }
+    }
+
+    /// Copies the chat messages added since the last call, and drops the oldest beyond the capacity of the shared log.
+    ///
+    /// A shared log with fewer messages added than already read was cleared: the chat starts over.
+    fn sync(&mut self) {
+        let log = self.messages.log();
+        if log.added() < self.next {
+            self.lines.clear();
+            self.next = 0;
+        }
+        let start = self.next.max(log.dropped());
+        self.lines.extend(log.from_sequence(start).filter(|message| message.origin == MessageOrigin::Chat).cloned());
+        self.next = log.added();
+        while self.lines.len() > log.capacity() {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Shows the muted players, the chat and the box to answer every client.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The panel of the chat tab.
+    ///
+    pub fn show(&mut self, ui: &mut Ui) {
+        self.sync();
+        let mutes = self.server.chat_mutes();
+
+        egui::TopBottomPanel::bottom("chat_input").show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                let input = ui.add(
+                    egui::TextEdit::singleline(&mut self.draft)
+                        .hint_text("Message to every client")
+                        .char_limit(AppDefines::MESSAGE_LENGTH as usize)
+                        .desired_width(320.0),
+                );
+                let entered = input.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if (ui.button("Send").clicked() || entered) && !self.draft.trim().is_empty() {
+                    match self.server.broadcast_chat(&self.draft) {
+                        Ok(_) => self.draft.clear(),
+                        Err(e) => add_message(&self.messages, format!("[WARNING] Message not sent: {}", e), MessageType::Warning, MessageOrigin::Ui),
+                    }
+                    input.request_focus();
+                }
+                ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
+            });
+        });
+
+        // Les joueurs muets restent listés, pour pouvoir leur rendre la parole
+        let senders: BTreeSet<&str> = self.lines
+            .iter()
+            .filter_map(|message| message.chat().map(|(sender, _)| sender))
+            .filter(|sender| *sender != AppDefines::CHAT_SERVER_SENDER)
+            .collect();
+        egui::CollapsingHeader::new(format!("Players ({})", senders.len())).show(ui, |ui| {
+            let mut mutes = mutes.lock().unwrap();
+            egui::Grid::new("chat_senders").striped(true).show(ui, |ui| {
+                for sender in &senders {
+                    let mut muted = mutes.is_muted(sender);
+                    let mut dropped = mutes.drops(sender);
+                    ui.label(*sender);
+                    let muted_changed = ui.checkbox(&mut muted, "Muted").changed();
+                    let dropped_changed = ui.add_enabled(muted, egui::Checkbox::new(&mut dropped, "Dropped on the server")).changed();
+                    if muted_changed || dropped_changed {
+                        if muted {
+                            mutes.mute(sender, dropped);
+                        } else {
+                            mutes.unmute(sender);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        ui.separator();
+
+        let colors: HashMap<String, Color32> = self.commands
+            .render()
+            .latest()
+            .frame
+            .entities
+            .iter()
+            .map(|entity| (entity.name.clone(), Color32::from_rgb(entity.color[0], entity.color[1], entity.color[2])))
+            .collect();
+        let mutes = mutes.lock().unwrap();
+        ScrollArea::vertical()
+            .id_source("chat_log")
+            .auto_shrink([false, false])
+            .stick_to_bottom(self.auto_scroll)
+            .show(ui, |ui| {
+                for message in self.lines.iter().filter(|message| mutes.shows(message)) {
+                    let Some((sender, text)) = message.chat() else { continue };
+                    // L'opérateur a sa propre couleur, un joueur parti la couleur des messages
+                    let color = match colors.get(sender) {
+                        Some(color) => *color,
+                        None if sender == AppDefines::CHAT_SERVER_SENDER => Color32::GOLD,
+                        None => message.color,
+                    };
+                    ui.horizontal_top(|ui| {
+                        ui.label(RichText::new(format_clock(message.timestamp)).monospace().color(Color32::GRAY));
+                        let (swatch, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                        ui.painter().rect_filled(swatch.translate(egui::vec2(0.0, 3.0)), 2.0, color);
+                        ui.label(RichText::new(format!("{}:", sender)).strong().color(color));
+                        ui.add(egui::Label::new(text).wrap(true));
+                    });
+                }
+            });
+    }
+}
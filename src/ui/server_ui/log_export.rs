@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, Align2, Context, RichText};
+
+use crate::app_defines::AppDefines;
+use crate::types::{export_messages, ExportFormat};
+use crate::StyledMessage;
+
+/// The window exporting the messages to a file, and the toast telling how it went.
+///
+/// The file is written by a thread of its own, so that a large export does not freeze the window.
+pub struct LogExport {
+    /// Whether the window is shown.
+    open: bool,
+    /// The file written.
+    path: String,
+    format: ExportFormat,
+    /// Whether every message kept is exported, rather than the ones the filters show.
+    all_messages: bool,
+    /// The outcome of the export running, sent by its thread.
+    running: Option<Receiver<Result<usize, String>>>,
+    /// The outcome of the last export, whether it failed, and when it ended.
+    toast: Option<(String, bool, Instant)>,
+}
+
+impl LogExport {
+    /// Creates a closed export window, writing plain text to the default file.
+    pub fn new() -> Self {
+        LogExport {
+            open: false,
+            path: AppDefines::DEFAULT_LOG_EXPORT_FILE.to_string(),
+            format: ExportFormat::Text,
+            all_messages: false,
+            running: None,
+            toast: None,
+        }
+    }
+
+    /// Shows the window.
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Shows the window, if open.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The Egui context.
+    ///
+    /// # Returns
+    ///
+    /// Whether every message kept is to be exported, once the 'Export' button is clicked.
+    ///
+    pub fn show_window(&mut self, ctx: &Context) -> Option<bool> {
+        let mut open = self.open;
+        let mut requested = None;
+        egui::Window::new("Export Log")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Format:");
+                    ui.radio_value(&mut self.format, ExportFormat::Text, "Plain text");
+                    ui.radio_value(&mut self.format, ExportFormat::JsonLines, "JSON lines");
+                });
+                ui.checkbox(&mut self.all_messages, "All messages, not only the filtered ones");
+                let idle = self.running.is_none();
+                if ui.add_enabled(idle && !self.path.trim().is_empty(), egui::Button::new("Export")).clicked() {
+                    requested = Some(self.all_messages);
+                }
+                if !idle {
+                    ui.label("Exporting...");
+                }
+            });
+        self.open = open && requested.is_none();
+        requested
+    }
+
+    /// Writes messages to the file chosen, on a thread of its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages exported, copied out of the log.
+    ///
+    pub fn start(&mut self, messages: Vec<StyledMessage>) {
+        let (sender, receiver) = mpsc::channel();
+        let path = PathBuf::from(self.path.trim());
+        let format = self.format;
+        thread::spawn(move || {
+            let _ = sender.send(export_messages(&messages, &path, format));
+        });
+        self.running = Some(receiver);
+    }
+
+    /// Collects the outcome of the export running, and shows it for a few seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The Egui context.
+    ///
+    pub fn show_toast(&mut self, ctx: &Context) {
+        if let Some(receiver) = &self.running {
+            let outcome = match receiver.try_recv() {
+                Ok(outcome) => Some(outcome),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err("The export stopped unexpectedly".to_string())),
+            };
+            match outcome {
+                Some(Ok(count)) => self.toast = Some((format!("{} messages exported to {}.", count, self.path.trim()), false, Instant::now())),
+                Some(Err(e)) => self.toast = Some((e, true, Instant::now())),
+                // Le fil d'écriture ne réveille pas la fenêtre
+                None => {
+                    ctx.request_repaint_after(Duration::from_millis(100));
+                    return;
+                }
+            }
+            self.running = None;
+        }
+
+        let Some((text, failed, ended_at)) = &self.toast else {
+            return;
+        };
+        if ended_at.elapsed().as_secs_f32() > AppDefines::TOAST_SECS {
+            self.toast = None;
+            return;
+        }
+        let color = if *failed { egui::Color32::from_rgb(255, 150, 120) } else { egui::Color32::LIGHT_GREEN };
+        egui::Area::new(egui::Id::new("log_export_toast"))
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(RichText::new(text.as_str()).color(color)));
+            });
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+use eframe::egui::{self, Align2, Context, RichText, ScrollArea, TextStyle, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{format_age, format_clock, format_timestamp, MessageHub, MessageType};
+use crate::StyledMessage;
+use super::log_export::LogExport;
+
+/// The filters of the message log kept from one run to the next, the search apart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageFilters {
+    /// The message types left out of the log.
+    pub hidden_types: Vec<MessageType>,
+    /// Whether the log follows the new messages.
+    pub auto_scroll: bool,
+    /// Whether the messages show how long ago they were added, rather than when.
+    pub relative_times: bool,
+}
+
+impl Default for MessageFilters {
+    fn default() -> Self {
+        MessageFilters { hidden_types: Vec::new(), auto_scroll: true, relative_times: false }
+    }
+}
+
+/// The messages of the server, filtered by type and searched, as shown in the server UI.
+///
+/// The new messages of the copy of the log published by the hub are copied as they arrive:
+/// filtering and drawing work on the copy. The copy drops its oldest messages like the
+/// shared log, so both keep the same messages. Only the rows on screen are laid out, so the
+/// log stays smooth at the capacity of the shared log.
+pub struct MessageView {
+    /// The messages of the server, shared with the threads adding them.
+    messages: MessageHub,
+    /// The copy of the shared messages, filtered and drawn.
+    copy: VecDeque<StyledMessage>,
+    /// The sequence number in the shared log of the first message of `copy`.
+    copy_start: u64,
+    /// The sequence numbers of the messages shown, in order.
+    visible: VecDeque<u64>,
+    /// The number of messages the shared log dropped to make room.
+    dropped: u64,
+    /// Whether each message type of `MessageType::ALL` is shown.
+    shown_types: [bool; MessageType::ALL.len()],
+    /// The text searched, case-insensitively.
+    search: String,
+    /// Whether the log follows the new messages while it is scrolled to the bottom.
+    auto_scroll: bool,
+    /// Whether the messages show how long ago they were added, rather than when.
+    relative_times: bool,
+    /// Whether the confirmation of the 'Clear' button is shown.
+    confirm_clear: bool,
+    /// The export of the messages to a file.
+    export: LogExport,
+}
+
+impl MessageView {
+    /// Creates a log showing every message, following the new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages of the server, shared with the threads adding them.
+    ///
+    pub fn new(messages: MessageHub) -> Self {
+        MessageView {
+            messages,
+            copy: VecDeque::new(),
+            copy_start: 0,
+            visible: VecDeque::new(),
+            dropped: 0,
+            shown_types: [true; MessageType::ALL.len()],
+            search: String::new(),
+            auto_scroll: true,
+            relative_times: false,
+            confirm_clear: false,
+            export: LogExport::new(),
+        }
+    }
+
+    /// Returns the type filters and the auto-scroll, to be kept for the next run.
+    pub fn filters(&self) -> MessageFilters {
+        let hidden_types = MessageType::ALL.iter().zip(self.shown_types).filter(|(_, shown)| !shown).map(|(kind, _)| *kind).collect();
+        MessageFilters { hidden_types, auto_scroll: self.auto_scroll, relative_times: self.relative_times }
+    }
+
+    /// Applies type filters and an auto-scroll kept from a previous run.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - The filters, see `filters`.
+    ///
+    pub fn set_filters(&mut self, filters: &MessageFilters) {
+        for (kind, shown) in MessageType::ALL.iter().zip(self.shown_types.iter_mut()) {
+            *shown = !filters.hidden_types.contains(kind);
+        }
+        self.auto_scroll = filters.auto_scroll;
+        self.relative_times = filters.relative_times;
+        self.refilter();
+    }
+
+    /// Returns whether a message passes the type filters and the search.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message.
+    /// * `search` - The text searched, in lowercase, empty to match every message.
+    ///
+    fn matches(&self, message: &StyledMessage, search: &str) -> bool {
+        let type_shown = MessageType::ALL.iter().zip(self.shown_types).any(|(kind, shown)| shown && *kind == message.message_type);
+        type_shown && (search.is_empty() || message.text.to_lowercase().contains(search))
+    }
+
+    /// Returns the message of the copy with a sequence number, which must still be kept.
+    fn message(&self, sequence: u64) -> &StyledMessage {
+        &self.copy[(sequence - self.copy_start) as usize]
+    }
+
+    /// Empties the copy, which starts again at a sequence number.
+    fn restart(&mut self, sequence: u64) {
+        self.copy.clear();
+        self.visible.clear();
+        self.copy_start = sequence;
+    }
+
+    /// Copies the messages added since the last call, filtering them, and drops those the shared log dropped.
+    ///
+    /// A shared log with fewer messages added than the copy was cleared: the copy starts over.
+    fn sync(&mut self) {
+        let end = self.copy_start + self.copy.len() as u64;
+        let (cleared, dropped, start, new_messages, capacity) = {
+            let messages = self.messages.log();
+            let cleared = messages.added() < end;
+            let read = if cleared { 0 } else { end };
+            // Tout ce que la copie n'a pas encore lu a pu être écarté entre deux images
+            let start = read.max(messages.dropped());
+            (cleared, messages.dropped(), start, messages.from_sequence(start).cloned().collect::<Vec<_>>(), messages.capacity())
+        };
+        self.dropped = dropped;
+        if cleared || start > end {
+            self.restart(start);
+        }
+        if new_messages.is_empty() {
+            return;
+        }
+        let search = self.search.to_lowercase();
+        for (offset, message) in new_messages.iter().enumerate() {
+            if self.matches(message, &search) {
+                self.visible.push_back(start + offset as u64);
+            }
+        }
+        self.copy.extend(new_messages);
+        while self.copy.len() > capacity {
+            self.copy.pop_front();
+            self.copy_start += 1;
+        }
+        while self.visible.front().is_some_and(|&sequence| sequence < self.copy_start) {
+            self.visible.pop_front();
+        }
+    }
+
+    /// Filters the whole copy again, after a filter or the search changed.
+    fn refilter(&mut self) {
+        let search = self.search.to_lowercase();
+        let visible = self.copy
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| self.matches(message, &search))
+            .map(|(index, _)| self.copy_start + index as u64)
+            .collect();
+        self.visible = visible;
+    }
+
+    /// Shows the filters, the search box, the buttons and the messages shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The panel of the log, filled by the messages.
+    ///
+    pub fn show(&mut self, ui: &mut Ui) {
+        self.sync();
+
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            for (kind, shown) in MessageType::ALL.iter().zip(self.shown_types.iter_mut()) {
+                changed |= ui.checkbox(shown, RichText::new(format!("{:?}", kind)).color(kind.to_color())).changed();
+            }
+            ui.separator();
+            changed |= ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Search").desired_width(160.0)).changed();
+            ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
+            ui.checkbox(&mut self.relative_times, "Relative times");
+            if ui.button("Copy Visible").clicked() {
+                // Comme dans le fichier de log : l'heure complète et l'origine de chaque message
+                let text = self.visible
+                    .iter()
+                    .map(|&sequence| {
+                        let message = self.message(sequence);
+                        format!("{} [{}] {}", format_timestamp(message.timestamp), message.origin.tag(), message.text)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.output_mut(|output| output.copied_text = text);
+            }
+            if ui.button("Export log...").clicked() {
+                self.export.open();
+            }
+            if ui.button("Clear...").clicked() {
+                self.confirm_clear = true;
+            }
+            ui.label(format!("{} / {} messages", self.visible.len(), self.copy.len()));
+            if self.dropped > 0 {
+                ui.label(RichText::new(format!("{} older messages discarded", self.dropped)).weak());
+            }
+        });
+        if changed {
+            self.refilter();
+        }
+
+        let row_height = ui.text_style_height(&TextStyle::Body).max(ui.text_style_height(&TextStyle::Monospace));
+        let now = SystemTime::now();
+        // Seules les lignes à l'écran sont dessinées ; coller au bas ne tient que si la vue y était déjà
+        ScrollArea::both()
+            .id_source("message_log")
+            .auto_shrink([false, false])
+            .stick_to_bottom(self.auto_scroll)
+            .show_rows(ui, row_height, self.visible.len(), |ui, rows| {
+                for &sequence in self.visible.range(rows) {
+                    let message = self.message(sequence);
+                    // Un préfixe de largeur fixe, pour que les textes restent alignés
+                    let time = if self.relative_times { format!("{:>12}", format_age(message.timestamp, now)) } else { format_clock(message.timestamp) };
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(format!("{} {:<5}", time, message.origin.tag())).monospace().color(egui::Color32::GRAY));
+                        ui.add(egui::Label::new(RichText::new(&message.text).color(message.color)).wrap(false));
+                    });
+                }
+            });
+    }
+
+    /// Shows the export window, starts the export asked for, and tells how the last one went.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The Egui context.
+    ///
+    pub fn show_export(&mut self, ctx: &Context) {
+        if let Some(all_messages) = self.export.show_window(ctx) {
+            let messages = if all_messages {
+                self.messages.log().to_vec()
+            } else {
+                self.visible.iter().map(|&sequence| self.message(sequence).clone()).collect()
+            };
+            self.export.start(messages);
+        }
+        self.export.show_toast(ctx);
+    }
+
+    /// Asks to confirm the 'Clear' button, and empties the shared messages once confirmed.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The Egui context.
+    ///
+    pub fn show_clear_confirmation(&mut self, ctx: &Context) {
+        if !self.confirm_clear {
+            return;
+        }
+        let mut answer = None;
+        egui::Window::new("Clear Messages")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Clear all the server messages? A log file, if one is written, keeps them.");
+                ui.horizontal(|ui| {
+                    if ui.button("Clear").clicked() {
+                        answer = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() || ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+                        answer = Some(false);
+                    }
+                });
+            });
+        let Some(confirmed) = answer else {
+            return;
+        };
+        self.confirm_clear = false;
+        if confirmed {
+            // Le journal vidé est attendu, pour ne pas recopier ses anciens messages
+            self.messages.clear();
+            self.messages.flush();
+            self.restart(0);
+            self.dropped = 0;
+        }
+    }
+}
@@ -1,15 +1,102 @@
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use eframe::egui;
+use egui_plot::{Line, Plot, PlotPoints};
 
 use eframe::egui::{CentralPanel, Context, RichText, TopBottomPanel, Window};
 use crate::app_defines::AppDefines;
-use crate::server::server_thread::ServerSettings;
-use crate::StyledMessage;
+use crate::game_logic::arena_config::ArenaConfig;
+use crate::game_logic::command::GameCommands;
+use crate::game_logic::leaderboard::PlayerTotals;
+use crate::server::client_id::ClientId;
+use crate::server::client_registry::ClientInfo;
+use crate::server::client_stats::ClientStatsRegistry;
+use crate::server::server_thread::{ServerSettings, ServerThread};
+use crate::types::{add_message, MessageHub, MessageOrigin, MessageType};
+
+mod chat_view;
+mod log_export;
+pub(crate) mod message_view;
+
+use chat_view::ChatView;
+use message_view::{MessageFilters, MessageView};
+
+/// The tabs of the server UI.
+#[derive(Clone, Copy, PartialEq)]
+enum ServerTab {
+    /// The status of the server, the traffic, the leaderboard and the messages.
+    Messages,
+    /// The clients connected now.
+    Clients,
+    /// The chat of the clients.
+    Chat,
+}
+
+/// The columns the clients table can be sorted by.
+#[derive(Clone, Copy, PartialEq)]
+enum ClientColumn {
+    Address,
+    Entity,
+    Name,
+    Connected,
+    LastActivity,
+    Spectator,
+}
+
+impl ClientColumn {
+    const ALL: [ClientColumn; 6] = [
+        ClientColumn::Address,
+        ClientColumn::Entity,
+        ClientColumn::Name,
+        ClientColumn::Connected,
+        ClientColumn::LastActivity,
+        ClientColumn::Spectator,
+    ];
+
+    /// Returns the heading of the column.
+    fn title(&self) -> &'static str {
+        match self {
+            ClientColumn::Address => "Address",
+            ClientColumn::Entity => "Entity",
+            ClientColumn::Name => "Name",
+            ClientColumn::Connected => "Connected For",
+            ClientColumn::LastActivity => "Last Activity",
+            ClientColumn::Spectator => "Spectator",
+        }
+    }
+
+    /// Sorts the clients by the column, in ascending order.
+    fn sort(&self, rows: &mut [(ClientId, ClientInfo)]) {
+        match self {
+            ClientColumn::Address => rows.sort_by_key(|(client_id, _)| client_id.addr()),
+            ClientColumn::Entity => rows.sort_by_key(|(_, info)| info.entity.as_ref().map(|(id, _)| *id)),
+            ClientColumn::Name => rows.sort_by(|(_, a), (_, b)| {
+                a.entity.as_ref().map(|(_, name)| name).cmp(&b.entity.as_ref().map(|(_, name)| name))
+            }),
+            // Les durées croissent quand les instants décroissent
+            ClientColumn::Connected => rows.sort_by_key(|(_, info)| std::cmp::Reverse(info.connected_at)),
+            ClientColumn::LastActivity => rows.sort_by_key(|(_, info)| std::cmp::Reverse(info.last_activity)),
+            ClientColumn::Spectator => rows.sort_by_key(|(_, info)| info.spectator),
+        }
+    }
+}
 
 /// A struct representing the server's user interface.
 pub struct ServerUi {
-    /// A thread-safe, shared vector of styled messages.
-    messages: Arc<Mutex<Vec<StyledMessage>>>,
+    /// The messages of the server, see `MessageHub`.
+    messages: MessageHub,
+    /// Thread-safe, shared server settings.
+    settings: Arc<Mutex<ServerSettings>>,
+    /// The way into the game logic, resized by the options and read for the leaderboard.
+    commands: GameCommands,
+    /// The best players of the leaderboard, read from the game loop at most once per second.
+    leaderboard: Vec<(String, PlayerTotals)>,
+    /// When `leaderboard` was read, `None` before the first time it is shown.
+    leaderboard_read_at: Option<Instant>,
+    /// The server, stopped by the 'Stop Server' button and when the window closes.
+    server: ServerThread,
+    /// The traffic statistics of the connections.
+    client_stats: Arc<Mutex<ClientStatsRegistry>>,
     /// Whether the 'About' dialog is currently shown.
     show_about: bool,
     /// Whether the 'Options' dialog is currently shown.
@@ -28,12 +115,30 @@ pub struct ServerUi {
     penalty_time: i64,
     /// The delay before a connection times out.
     connection_timeout_delay: i32,
+    /// The port the server listens on for TCP clients.
+    port: u16,
+    /// The number of simulation steps per second.
+    tick_rate: f32,
     /// The duration messages are displayed.
     message_duration: i32,
     /// The maximum length of a message.
     message_length: i32,
     /// The score limit for the game.
     score_limit: i32,
+    /// The maximum number of connected clients (0 = unlimited).
+    max_clients: usize,
+    /// How long the traffic statistics of a disconnected client are kept, in seconds.
+    client_stats_grace_secs: u64,
+    /// The least severe messages kept.
+    log_verbosity: MessageType,
+    /// The tab shown.
+    tab: ServerTab,
+    /// The column the clients table is sorted by, and whether in ascending order.
+    client_sort: (ClientColumn, bool),
+    /// The messages shown, filtered and searched.
+    message_view: MessageView,
+    /// The chat of the clients.
+    chat_view: ChatView,
 }
 
 impl ServerUi {
@@ -41,25 +146,65 @@ impl ServerUi {
     ///
     /// # Arguments
     ///
-    /// * `messages` - A thread-safe, shared vector of styled messages.
+    /// * `messages` - The messages of the server, see `MessageHub`.
     /// * `settings` - Thread-safe, shared server settings.
+    /// * `commands` - The way into the game logic, resized by the options and read for the leaderboard.
+    /// * `server` - The server, stopped by the 'Stop Server' button and when the window closes.
+    /// * `client_stats` - The traffic statistics of the connections.
     ///
     /// # Returns
     ///
     /// A new `ServerUi` instance.
     ///
-    pub fn new(messages: Arc<Mutex<Vec<StyledMessage>>>, settings: Arc<Mutex<ServerSettings>>) -> Self {
-        ServerUi { messages, show_about: false, show_options: false,
-            arena_width: AppDefines::ARENA_WIDTH,
-            arena_height: AppDefines::ARENA_HEIGHT,
+    pub fn new(messages: MessageHub,
+               settings: Arc<Mutex<ServerSettings>>,
+               commands: GameCommands,
+               server: ServerThread,
+               client_stats: Arc<Mutex<ClientStatsRegistry>>,
+        ) -> Self {
+        let (max_clients, client_stats_grace_secs, connection_timeout_delay, port, tick_rate, log_verbosity) = {
+            let settings = settings.lock().unwrap();
+            (settings.max_clients, settings.client_stats_grace_secs, settings.connection_timeout_delay(), settings.port(), settings.tick_hz(), settings.log_verbosity())
+        };
+        let arena = commands.render().latest().arena;
+        let message_view = MessageView::new(messages.clone());
+        let chat_view = ChatView::new(messages.clone(), server.clone(), commands.clone());
+        ServerUi { messages, settings, commands, leaderboard: Vec::new(), leaderboard_read_at: None, server, client_stats, show_about: false, show_options: false,
+            arena_width: arena.width,
+            arena_height: arena.height,
             obstacle_probability: AppDefines::OBSTACLE_PROBABILITY,
             game_modes: AppDefines::GAME_MODES,
             bot_rate_of_fire: AppDefines::BOT_RATE_OF_FIRE,
             penalty_time: AppDefines::PENALTY_TIME,
-            connection_timeout_delay: AppDefines::CONNECTION_TIMEOUT_DELAY,
+            connection_timeout_delay,
+            port,
+            tick_rate,
             message_duration: AppDefines::MESSAGE_DURATION,
             message_length: AppDefines::MESSAGE_LENGTH,
-            score_limit: AppDefines::SCORE_LIMIT, }
+            score_limit: AppDefines::SCORE_LIMIT,
+            max_clients,
+            client_stats_grace_secs,
+            log_verbosity,
+            tab: ServerTab::Messages,
+            client_sort: (ClientColumn::Connected, false),
+            message_view,
+            chat_view,
+        }
+    }
+
+    /// Returns the filters of the message log, kept for the next run.
+    pub fn message_filters(&self) -> MessageFilters {
+        self.message_view.filters()
+    }
+
+    /// Applies the filters of the message log kept from a previous run.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - The filters, see `message_filters`.
+    ///
+    pub fn set_message_filters(&mut self, filters: &MessageFilters) {
+        self.message_view.set_filters(filters);
     }
 
     /// Displays the main menu bar with options for general settings and help.
@@ -76,9 +221,11 @@ impl ServerUi {
                         self.show_options = true;
                         ui.close_menu();
                     }
+                    if ui.button("Stop Server").clicked() {
+                        self.stop_server();
+                    }
                     if ui.button("Exit").clicked() {
-                        // Handle exit click
-                        std::process::exit(0);
+                        self.stop_server();
                     }
                 });
 
@@ -93,6 +240,229 @@ impl ServerUi {
         });
     }
 
+    /// Stops the server and exits, see `shut_down`.
+    fn stop_server(&self) {
+        self.shut_down();
+        std::process::exit(0);
+    }
+
+    /// Tells every client that the server stops, closes their connections and waits for the server threads.
+    pub fn shut_down(&self) {
+        self.server.stop();
+    }
+
+    /// Displays the health of the server, with the history of the tick rate and of the client count.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The Egui UI.
+    ///
+    fn show_metrics(&self, ui: &mut egui::Ui) {
+        let metrics = self.server.metrics.sample();
+        let (max_clients, mut lobby_locked) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.max_clients, settings.lobby_locked)
+        };
+        let limit = if max_clients == 0 { "unlimited".to_string() } else { max_clients.to_string() };
+        ui.horizontal(|ui| {
+            ui.label(format!("Connected clients: {} / {}", metrics.connected_clients, limit));
+            // Le verrou s'applique à la prochaine poignée de main, sans redémarrer le serveur
+            if ui.checkbox(&mut lobby_locked, "Lobby locked").changed() {
+                self.settings.lock().unwrap().lobby_locked = lobby_locked;
+                let text = if lobby_locked { "locked, only spectators and reconnecting players get in" } else { "unlocked" };
+                add_message(&self.messages, format!("[INFO] Lobby {}.", text), MessageType::Info, MessageOrigin::Ui);
+            }
+        });
+        egui::CollapsingHeader::new("Server Status")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.label(format!("Uptime: {}", ServerUi::format_duration(metrics.uptime)));
+                ui.label(format!("Entities: {} player(s), {} AI", metrics.players, metrics.ai_entities));
+                ui.label(format!("Bullets: {}, obstacles: {}", metrics.bullets, metrics.obstacles));
+                ui.label(format!("Tick rate: {:.1} Hz / {:.1} Hz", metrics.tick_rate, metrics.target_tick_rate));
+                ui.label(format!("Speed: {:.2}x", metrics.time_scale));
+                ui.label(format!("Average step: {:.2} ms", metrics.average_step_ms));
+                match (metrics.ui_frame_rate, metrics.ui_lock_wait_ms) {
+                    (Some(frame_rate), Some(lock_wait_ms)) => {
+                        ui.label(format!("Game UI: {:.1} FPS, {:.2} ms waiting for the game loop per frame", frame_rate, lock_wait_ms))
+                    }
+                    _ => ui.label("Game UI: not measured"),
+                };
+
+                // Abscisses en secondes avant maintenant, l'historique couvre METRICS_HISTORY_SECS secondes
+                let history = self.server.metrics.history();
+                let now = metrics.uptime.as_secs_f64();
+                let tick_rates: Vec<[f64; 2]> = history.iter().map(|point| [point.uptime_secs - now, point.tick_rate as f64]).collect();
+                let clients: Vec<[f64; 2]> = history.iter().map(|point| [point.uptime_secs - now, point.connected_clients as f64]).collect();
+                ui.horizontal(|ui| {
+                    ServerUi::sparkline(ui, "Tick rate (Hz)", tick_rates);
+                    ServerUi::sparkline(ui, "Clients", clients);
+                });
+            });
+    }
+
+    /// Draws a small plot of the last `METRICS_HISTORY_SECS` seconds of a metric.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The Egui UI.
+    /// * `name` - The name of the metric, shown above the plot.
+    /// * `points` - The values of the metric, in seconds before now.
+    ///
+    fn sparkline(ui: &mut egui::Ui, name: &str, points: Vec<[f64; 2]>) {
+        ui.vertical(|ui| {
+            ui.label(name);
+            Plot::new(name)
+                .width(240.0)
+                .height(60.0)
+                .show_axes([false, true])
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .allow_boxed_zoom(false)
+                .include_x(-(AppDefines::METRICS_HISTORY_SECS as f64))
+                .include_x(0.0)
+                .include_y(0.0)
+                .show(ui, |plot_ui| plot_ui.line(Line::new(PlotPoints::new(points))));
+        });
+    }
+
+    /// Displays the clients connected now, and those disconnected in the last `CLIENT_LINGER_SECS` greyed out.
+    ///
+    /// Clicking a heading sorts the table by its column, clicking it again reverses the order.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The Egui UI.
+    ///
+    fn show_clients(&mut self, ui: &mut egui::Ui) {
+        let mut rows = self.server.clients.lock().unwrap().rows();
+        let (column, ascending) = self.client_sort;
+        column.sort(&mut rows);
+        if !ascending {
+            rows.reverse();
+        }
+
+        egui::Grid::new("clients").striped(true).show(ui, |ui| {
+            for heading in ClientColumn::ALL {
+                let arrow = match self.client_sort {
+                    (sorted, true) if sorted == heading => " ⏶",
+                    (sorted, false) if sorted == heading => " ⏷",
+                    _ => "",
+                };
+                if ui.button(format!("{}{}", heading.title(), arrow)).clicked() {
+                    self.client_sort = (heading, !(ascending && column == heading));
+                }
+            }
+            ui.end_row();
+
+            for (client_id, info) in rows {
+                let connected = info.disconnected_at.is_none();
+                let color = if connected { ui.visuals().text_color() } else { egui::Color32::GRAY };
+                let (entity, name) = match &info.entity {
+                    Some((entity_id, name)) => (entity_id.to_string(), name.clone()),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                let connected_for = info.disconnected_at.unwrap_or_else(Instant::now) - info.connected_at;
+                ui.label(RichText::new(client_id.to_string()).color(color));
+                ui.label(RichText::new(entity).color(color));
+                ui.label(RichText::new(name).color(color));
+                ui.label(RichText::new(ServerUi::format_duration(connected_for)).color(color));
+                ui.label(RichText::new(format!("{} ago", ServerUi::format_duration(info.last_activity.elapsed()))).color(color));
+                ui.label(RichText::new(if info.spectator { "yes" } else { "no" }).color(color));
+                if ui.add_enabled(connected, egui::Button::new("Kick")).clicked() {
+                    self.server.kick_client(&client_id, "the server UI");
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Formats a duration as `h:mm:ss`, or `m:ss` under an hour.
+    fn format_duration(duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        if seconds >= 3600 {
+            format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+        } else {
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        }
+    }
+
+    /// Displays the traffic statistics of the clients, one row per client.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The Egui UI.
+    ///
+    fn show_client_stats(&self, ui: &mut egui::Ui) {
+        let rows = self.client_stats.lock().unwrap().rows();
+        egui::CollapsingHeader::new(format!("Client Traffic ({})", rows.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("client_stats").striped(true).show(ui, |ui| {
+                    for heading in ["Client", "Status", "Commands", "Bytes Read", "Bytes Written", "Errors", "Last Command"] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+
+                    for (client_id, stats) in rows {
+                        ui.label(client_id.to_string());
+                        match stats.disconnected_at {
+                            Some(_) => ui.colored_label(egui::Color32::GRAY, "disconnected"),
+                            None => ui.colored_label(egui::Color32::GREEN, "connected"),
+                        };
+                        ui.label(stats.commands.to_string());
+                        ui.label(stats.bytes_read.to_string());
+                        ui.label(stats.bytes_written.to_string());
+                        ui.label(stats.errors.to_string());
+                        ui.label(stats.last_command);
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    /// Displays the best players of the cumulative leaderboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - The Egui UI.
+    ///
+    fn show_leaderboard(&mut self, ui: &mut egui::Ui) {
+        // Le classement ne change qu'en fin de manche : relu une fois par seconde, pas à chaque image
+        if self.leaderboard_read_at.map_or(true, |read_at| read_at.elapsed() >= Duration::from_secs(1)) {
+            self.leaderboard = self.commands.query(|logic| {
+                logic.leaderboard.top(AppDefines::LEADERBOARD_DEFAULT_TOP)
+                    .into_iter()
+                    .map(|(name, totals)| (name.to_string(), totals.clone()))
+                    .collect()
+            });
+            self.leaderboard_read_at = Some(Instant::now());
+        }
+        let players = &self.leaderboard;
+        egui::CollapsingHeader::new("Leaderboard")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("leaderboard").striped(true).show(ui, |ui| {
+                    for heading in ["Rank", "Player", "Score", "Kills", "Deaths", "Matches", "Waves"] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+
+                    for (index, (name, totals)) in players.iter().enumerate() {
+                        ui.label((index + 1).to_string());
+                        ui.label(name);
+                        ui.label(totals.score.to_string());
+                        ui.label(totals.kills.to_string());
+                        ui.label(totals.deaths.to_string());
+                        ui.label(totals.matches_played.to_string());
+                        ui.label(totals.waves_survived.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
     /// Displays the 'About' dialog with information about the application.
     ///
     /// # Arguments
@@ -130,10 +500,20 @@ impl ServerUi {
             .show(ctx, |ui| {
 
                 ui.horizontal(|ui| {
-                    ui.label("Connection Timeout Delay:");
+                    ui.label("Port:");
+                    ui.add(egui::DragValue::new(&mut self.port));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Connection Timeout Delay (s):");
                     ui.add(egui::DragValue::new(&mut self.connection_timeout_delay));
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("Tick Rate (Hz):");
+                    ui.add(egui::DragValue::new(&mut self.tick_rate).speed(1.0));
+                });
+
                 ui.horizontal(|ui| {
                     ui.label("Bot Rate of Fire:");
                     ui.add(egui::DragValue::new(&mut self.bot_rate_of_fire));
@@ -175,8 +555,56 @@ impl ServerUi {
                     ui.add(egui::DragValue::new(&mut self.obstacle_probability));
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("Max Clients (0 = unlimited):");
+                    ui.add(egui::DragValue::new(&mut self.max_clients));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Client Stats Grace Period (s):");
+                    ui.add(egui::DragValue::new(&mut self.client_stats_grace_secs));
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Log Verbosity:");
+                    egui::ComboBox::from_id_source("log_verbosity")
+                        .selected_text(format!("{:?}", self.log_verbosity))
+                        .show_ui(ui, |ui| {
+                            for level in [MessageType::Debug, MessageType::Info, MessageType::Warning, MessageType::Error] {
+                                ui.selectable_value(&mut self.log_verbosity, level, format!("{:?}", level));
+                            }
+                        });
+                });
+
                 if ui.button("Apply").clicked() {
                     // Apply changes to server settings here
+                    // La limite de clients s'applique aux prochaines connexions
+                    let mut settings = self.settings.lock().unwrap();
+                    settings.max_clients = self.max_clients;
+                    settings.client_stats_grace_secs = self.client_stats_grace_secs;
+                    settings.set_log_verbosity(self.log_verbosity);
+                    // Un réglage refusé garde l'ancienne valeur, remise dans le champ
+                    let results = [
+                        settings.set_port(self.port),
+                        settings.set_connection_timeout_delay(self.connection_timeout_delay),
+                        settings.set_tick_hz(self.tick_rate),
+                    ];
+                    self.port = settings.port();
+                    self.connection_timeout_delay = settings.connection_timeout_delay();
+                    self.tick_rate = settings.tick_hz();
+                    drop(settings);
+                    for error in results.into_iter().filter_map(Result::err) {
+                        add_message(&self.messages, format!("[WARNING] Setting refused: {}", error), MessageType::Warning, MessageOrigin::Ui);
+                    }
+                    // Les murs sont reconstruits tout de suite, la partie continue
+                    match ArenaConfig::new(self.arena_width, self.arena_height) {
+                        Ok(arena) => self.commands.post(move |logic| {
+                            if logic.arena() != arena {
+                                logic.set_arena(arena);
+                            }
+                        }),
+                        Err(e) => add_message(&self.messages, format!("[WARNING] Arena not resized: {}", e), MessageType::Warning, MessageOrigin::Ui),
+                    }
                     self.show_options = false;
                 }
             });
@@ -193,16 +621,32 @@ impl eframe::App for ServerUi {
     /// * `_frame` - The Eframe frame.
     ///
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        // Les métriques changent même sans interaction avec la fenêtre
+        ctx.request_repaint_after(Duration::from_secs(1));
         self.show_menu(ctx);
         self.show_about_dialog(ctx);
         self.show_options_dialog(ctx);
+        self.message_view.show_clear_confirmation(ctx);
+        self.message_view.show_export(ctx);
 
         CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Server Messages");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tab, ServerTab::Messages, "Server Messages");
+                ui.selectable_value(&mut self.tab, ServerTab::Clients, "Clients");
+                ui.selectable_value(&mut self.tab, ServerTab::Chat, "Chat");
+            });
+            ui.separator();
 
-            let messages = self.messages.lock().unwrap();
-            for message in messages.iter() {
-                ui.label(RichText::new(&message.text).color(message.color));
+            match self.tab {
+                ServerTab::Messages => {
+                    self.show_metrics(ui);
+                    self.show_client_stats(ui);
+                    self.show_leaderboard(ui);
+                    ui.separator();
+                    self.message_view.show(ui);
+                }
+                ServerTab::Clients => self.show_clients(ui),
+                ServerTab::Chat => self.chat_view.show(ui),
             }
         });
     }
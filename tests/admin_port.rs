@@ -0,0 +1,141 @@
+mod harness;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+use harness::{wait_for, TestServer};
+use serde_json::Value;
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::game_logic::ai_behavior::AiDifficulty;
+use universal_rust_server_software::game_logic::observer::GameEvent;
+use universal_rust_server_software::game_logic::GameLogic;
+
+/// Sends a command to the admin port and reads its one-line reply.
+fn command(reader: &mut BufReader<TcpStream>, line: &str) -> String {
+    writeln!(reader.get_mut(), "{}", line).unwrap();
+    let mut reply = String::new();
+    reader.read_line(&mut reply).unwrap();
+    reply.trim_end().to_string()
+}
+
+#[test]
+fn last_report_gives_the_round_that_ended() {
+    let admin_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let server = TestServer::start_with(|settings, _| settings.admin_control_port = admin_port);
+    let mut admin = BufReader::new(TcpStream::connect(("127.0.0.1", admin_port)).unwrap());
+    assert_eq!(command(&mut admin, "LAST_REPORT"), "ERR No round has ended yet");
+
+    let mut bot = server.connect();
+    bot.set_name("Champion").unwrap();
+    assert!(wait_for(|| server.game_logic.lock().unwrap().entities.iter().any(|entity| entity.name == "Champion")));
+    {
+        // Le score visé atteint, la boucle de jeu clôt la manche
+        let mut logic = server.game_logic.lock().unwrap();
+        logic.game_config.score_target = 3;
+        logic.entities[0].score = 3;
+    }
+    assert!(wait_for(|| server.game_logic.lock().unwrap().last_report().is_some()), "the round did not end");
+
+    let reply = command(&mut admin, "last_report");
+    let json = reply.strip_prefix("OK ").unwrap_or_else(|| panic!("unexpected reply: {}", reply));
+    let report: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(report["round"], 1);
+    assert_eq!(report["reason"], "score target reached");
+    assert_eq!(report["winners"], serde_json::json!(["Champion"]));
+    assert_eq!(report["players"][0]["name"], "Champion");
+    assert_eq!(report["players"][0]["score"], 3);
+    assert!(report["kills"].as_array().is_some_and(Vec::is_empty));
+}
+
+#[test]
+fn ai_behavior_switches_the_bots_already_in_the_arena() {
+    let admin_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let server = TestServer::start_with(|settings, _| settings.admin_control_port = admin_port);
+    let mut admin = BufReader::new(TcpStream::connect(("127.0.0.1", admin_port)).unwrap());
+    let bots = {
+        let mut logic = server.game_logic.lock().unwrap();
+        [
+            logic.add_ai("Rookie".to_string(), None, AiDifficulty::Easy),
+            logic.add_ai("Veteran".to_string(), Some("camper"), AiDifficulty::Hard),
+        ]
+    };
+
+    assert_eq!(command(&mut admin, "AI_BEHAVIOR"), "ERR Usage: AI_BEHAVIOR <wanderer|hunter|camper|defensive>");
+    assert_eq!(command(&mut admin, "ai_behavior sniper"), "ERR Unknown AI behavior: sniper");
+    assert_eq!(command(&mut admin, "AI_BEHAVIOR hunter"), "OK 2 AI entities switched to hunter");
+
+    let mut logic = server.game_logic.lock().unwrap();
+    let drivers: Vec<_> = bots.iter().map(|id| logic.ai_driver_of(*id).unwrap()).collect();
+    assert_eq!(drivers, [("hunter", AiDifficulty::Easy), ("hunter", AiDifficulty::Hard)], "the bots keep their difficulty");
+    let added = logic.add_ai("Recruit".to_string(), None, AiDifficulty::Normal);
+    assert_eq!(logic.ai_driver_of(added).unwrap().0, "hunter", "the behavior is the default from now on");
+}
+
+/// Steps the game for ten simulated seconds and counts the shots of an entity.
+fn shots_in_ten_seconds(logic: &mut GameLogic, shooter: u32) -> usize {
+    let cursor = logic.events.cursor();
+    for _ in 0..(10.0 * AppDefines::TARGET_FPS_2D_PHYSICS) as usize {
+        logic.update_ai();
+        logic.step();
+    }
+    logic.events.since(cursor).filter(|queued| matches!(queued.event, GameEvent::BulletFired { shooter: id, .. } if id == shooter)).count()
+}
+
+#[test]
+fn ai_behavior_switches_change_how_the_bots_fight() {
+    let admin_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let server = TestServer::start_with(|settings, logic| {
+        settings.admin_control_port = admin_port;
+        logic.game_config.starting_health = 1_000_000;
+    });
+    let mut admin = BufReader::new(TcpStream::connect(("127.0.0.1", admin_port)).unwrap());
+    let bot = {
+        let mut logic = server.game_logic.lock().unwrap();
+        // Une cible immobile, à portée de tir des deux comportements
+        logic.add_entity_at("Target".to_string(), 400.0, 300.0, 0.0, false).unwrap();
+        let bot = logic.add_ai("Bot".to_string(), None, AiDifficulty::Normal);
+        logic.teleport_entity(bot, 650.0, 300.0);
+        bot
+    };
+
+    // Les noms de la demande désignent les comportements intégrés
+    assert_eq!(command(&mut admin, "AI_BEHAVIOR aggressive"), "OK 1 AI entities switched to hunter");
+    let aggressive = shots_in_ten_seconds(&mut server.game_logic.lock().unwrap(), bot);
+    assert_eq!(command(&mut admin, "AI_BEHAVIOR defensive"), "OK 1 AI entities switched to defensive");
+    let defensive = shots_in_ten_seconds(&mut server.game_logic.lock().unwrap(), bot);
+    // Le défensif tire au plus une fois toutes les 1,5 s, le chasseur dès que son canon le permet
+    assert!((1..=7).contains(&defensive) && aggressive >= defensive + 5, "{} shots once defensive, {} before", defensive, aggressive);
+    assert_eq!(command(&mut admin, "AI_BEHAVIOR random-walk"), "OK 1 AI entities switched to wanderer");
+    assert_eq!(server.game_logic.lock().unwrap().ai_driver_of(bot).unwrap().0, "wanderer");
+}
+
+#[test]
+fn reset_and_set_config_change_the_game() {
+    let admin_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let server = TestServer::start_with(|settings, _| settings.admin_control_port = admin_port);
+    let mut admin = BufReader::new(TcpStream::connect(("127.0.0.1", admin_port)).unwrap());
+    let _bot = server.connect();
+    let starting_health = {
+        let mut logic = server.game_logic.lock().unwrap();
+        let entity = &mut logic.entities[0];
+        entity.score = 5;
+        entity.health = 10;
+        logic.game_config.starting_health
+    };
+
+    assert_eq!(command(&mut admin, "RESET"), "OK Simulation reset");
+    {
+        let logic = server.game_logic.lock().unwrap();
+        assert_eq!(logic.reset_count(), 1);
+        assert_eq!((logic.entities[0].score, logic.entities[0].health), (0, starting_health), "the entity starts over");
+    }
+
+    assert_eq!(command(&mut admin, "SET_CONFIG bullet_speed 777"), "OK bullet_speed=777");
+    assert_eq!(command(&mut admin, "set_config ramming_enabled false"), "OK ramming_enabled=false");
+    assert_eq!(command(&mut admin, "SET_CONFIG bullet_speed"), "ERR Usage: SET_CONFIG <key> <value>");
+    assert_eq!(command(&mut admin, "SET_CONFIG warp_drive 1"), "ERR Unknown setting warp_drive");
+    assert_eq!(command(&mut admin, "SET_CONFIG bullet_speed fast"), "ERR Invalid value fast for bullet_speed");
+    let logic = server.game_logic.lock().unwrap();
+    assert_eq!(logic.game_config.bullet_speed, 777.0);
+    assert!(!logic.game_config.ramming_enabled);
+}
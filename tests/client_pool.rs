@@ -0,0 +1,48 @@
+//! Load test of the pool of worker threads handling the clients, in a test binary of its own:
+//! the threads of the process are counted, and no other test may start any meanwhile.
+
+mod harness;
+
+use harness::{wait_for, TestServer};
+use universal_rust_server_software::app_defines::AppDefines;
+
+/// Returns the number of threads of the test process, as the kernel counts them.
+fn thread_count() -> usize {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    status.lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap()
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn two_hundred_clients_share_the_worker_threads() {
+    const CLIENTS: usize = 200;
+    const WORKERS: usize = 4;
+    let server = TestServer::start_with(|settings, logic| {
+        settings.max_clients = 0;
+        settings.client_workers = WORKERS;
+        // Sans pas de simulation, aucune entité ne meurt en se heurtant aux autres
+        logic.pause();
+    });
+    // Un premier client réveille le pool : les ouvriers existent avant la mesure
+    let first = server.connect();
+    let before = thread_count();
+
+    let mut bots = vec![first];
+    for _ in 1..CLIENTS {
+        bots.push(server.connect());
+    }
+    assert!(wait_for(|| server.entity_count() == CLIENTS), "{} entities", server.entity_count());
+    for bot in &mut bots {
+        bot.command(AppDefines::PING).unwrap();
+    }
+    let during = thread_count();
+    assert!(during <= before + 2, "{} threads with 1 client, {} with {}", before, during, CLIENTS);
+    assert!(during < WORKERS * 10, "{} threads for {} workers", during, WORKERS);
+
+    drop(bots);
+    assert!(wait_for(|| server.entity_count() == 0));
+    assert!(thread_count() <= before, "the disconnections left threads behind");
+}
@@ -0,0 +1,115 @@
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::cli::CliArgs;
+use universal_rust_server_software::config::Config;
+use universal_rust_server_software::game_logic::ai_behavior::AiDifficulty;
+use universal_rust_server_software::game_logic::game_config::GameConfig;
+use universal_rust_server_software::server::server_thread::ServerSettings;
+
+#[test]
+fn defaults_match_the_constants() {
+    let config = Config::default();
+    assert_eq!(config.server.bind, vec![AppDefines::SERVER_ADDRESS.to_string()]);
+    assert_eq!(config.server.port, AppDefines::SERVER_PORT);
+    assert_eq!(config.server.max_clients, AppDefines::MAX_CLIENTS);
+    assert_eq!(config.server.timeout_secs, AppDefines::CONNECTION_TIMEOUT_DELAY);
+    assert_eq!(config.server.tick_hz, AppDefines::TARGET_FPS_2D_PHYSICS);
+    assert_eq!(config.server.http_port, 0, "the HTTP endpoint must be off by default");
+    assert_eq!(config.server.prometheus_metrics, AppDefines::HTTP_PROMETHEUS_METRICS);
+    assert_eq!(config.server.report_dir, "", "no report is written by default");
+    assert_eq!(config.game, GameConfig::default());
+    assert_eq!(config.ai.count, 0);
+
+    let settings = ServerSettings::from_config(&config.server);
+    assert_eq!(settings.port(), AppDefines::SERVER_PORT);
+    assert_eq!(settings.connection_timeout_delay(), AppDefines::CONNECTION_TIMEOUT_DELAY);
+    assert_eq!(settings.tick_hz(), AppDefines::TARGET_FPS_2D_PHYSICS);
+    assert_eq!(settings.http_port, 0);
+}
+
+#[test]
+fn empty_and_default_files_give_the_defaults() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    assert_eq!(Config::parse(&Config::default_template()).unwrap(), Config::default());
+}
+
+#[test]
+fn sections_are_read() {
+    let config = Config::parse(
+        "[server]\n\
+         bind = [\"127.0.0.1\", \"::1\"]\n\
+         port = 7000\n\
+         max_clients = 8\n\
+         timeout_secs = 30\n\
+         tick_hz = 30\n\
+         http_port = 8080\n\
+         prometheus_metrics = false\n\
+         report_dir = \"reports\"\n\
+         \n\
+         [game]\n\
+         arena_width = 800\n\
+         arena_height = 600.5\n\
+         starting_health = 50\n\
+         bullet_speed = 900.0\n\
+         friendly_fire = true\n\
+         \n\
+         [ai]\n\
+         count = 3\n\
+         difficulty = \"Hard\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.server.bind, vec!["127.0.0.1".to_string(), "::1".to_string()]);
+    assert_eq!(config.server.port, 7000);
+    assert_eq!(config.server.max_clients, 8);
+    assert_eq!(config.server.timeout_secs, 30);
+    assert_eq!(config.server.tick_hz, 30.0);
+    assert_eq!(config.server.http_port, 8080);
+    assert!(!config.server.prometheus_metrics);
+    assert_eq!(config.server.report_dir, "reports");
+    let settings = ServerSettings::from_config(&config.server);
+    assert_eq!((settings.http_port, settings.http_prometheus_metrics), (8080, false));
+    assert_eq!((config.arena.width, config.arena.height), (800.0, 600.5));
+    assert_eq!(config.game.starting_health, 50);
+    assert_eq!(config.game.bullet_speed, 900.0);
+    assert!(config.game.friendly_fire);
+    assert_eq!(config.game.bullet_damage, GameConfig::default().bullet_damage);
+    assert_eq!(config.ai.count, 3);
+    assert_eq!(config.ai.difficulty, AiDifficulty::Hard);
+}
+
+#[test]
+fn files_without_sections_hold_the_rules() {
+    let config = Config::parse("bullet_damage = 12\nramming_enabled = false\n").unwrap();
+    assert_eq!(config.game.bullet_damage, 12);
+    assert!(!config.game.ramming_enabled);
+    assert_eq!(config.server, Config::default().server);
+}
+
+#[test]
+fn errors_give_the_line_and_the_key() {
+    let error = |text: &str| Config::parse(text).unwrap_err();
+    assert_eq!(error("[server]\nport = 0\n"), "line 2: server.port: expected a port from 1 to 65535");
+    assert!(error("[server]\nport = 70000\n").starts_with("line 2: server.port: "));
+    assert!(error("[server]\n\ntick_hz = -1\n").starts_with("line 3: server.tick_hz: "));
+    assert_eq!(error("[server]\ntimeout = 5\n"), "line 2: server.timeout: unknown key");
+    assert!(error("[game]\nstarting_health = 0\n").starts_with("line 2: game.starting_health: "));
+    assert!(error("[game]\nbullet_damage = \"high\"\n").starts_with("line 2: game.bullet_damage: "));
+    assert!(error("[game]\narena_width = 10\n").starts_with("line 2: game.arena_width: "));
+    assert_eq!(error("[ai]\ndifficulty = \"insane\"\n"), "line 2: ai.difficulty: invalid difficulty 'insane', expected easy, normal or hard");
+    assert_eq!(error("[server]\nport = 7000\n\n[sever]\nport = 7000\n"), "line 4: unknown section [sever]");
+    assert_eq!(error("bullet_speed = 0\n"), "line 1: bullet_speed: expected a speed above 0");
+    // Un fichier qui n'est pas du TOML donne la ligne de l'erreur
+    assert!(error("[server]\nport = 7000\nbind = \n").starts_with("line 3: "));
+}
+
+#[test]
+fn command_line_overrides_the_file() {
+    let mut config = Config::parse("[server]\nport = 7000\ntick_hz = 30\n[ai]\ncount = 2\n").unwrap();
+    let args: Vec<String> = ["--port", "7100", "--ai", "5"].iter().map(|arg| arg.to_string()).collect();
+    CliArgs::parse(&args).unwrap().apply(&mut config);
+    assert_eq!(config.server.port, 7100);
+    assert_eq!(config.server.tick_hz, 30.0);
+    assert_eq!(config.ai.count, 5);
+
+    let print = CliArgs::parse(&["config".to_string(), "--print-default".to_string()]).unwrap();
+    assert!(print.print_default_config);
+}
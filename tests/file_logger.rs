@@ -0,0 +1,87 @@
+mod harness;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use harness::TestServer;
+use universal_rust_server_software::types::{add_message, MessageOrigin, MessageType};
+
+/// The number of messages written, enough to rotate the file many times.
+const MESSAGES: usize = 3000;
+/// The size past which the log file is rotated in the test.
+const MAX_BYTES: u64 = 8 * 1024;
+/// The number of rotated files kept in the test.
+const KEPT_FILES: usize = 3;
+
+/// Returns the path of a rotated log file, `<path>.<index>`.
+fn rotated(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+/// Whether a text is an ISO 8601 UTC time to the millisecond, e.g. `2024-05-01T13:45:12.345Z`.
+fn is_utc_time(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 24
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            4 | 7 => *byte == b'-',
+            10 => *byte == b'T',
+            13 | 16 => *byte == b':',
+            19 => *byte == b'.',
+            23 => *byte == b'Z',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+#[test]
+fn the_log_file_is_rotated_and_the_oldest_files_dropped() {
+    let dir = std::env::temp_dir().join(format!("log_rotation_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("server.log");
+
+    let server = TestServer::start_with(|settings, _| {
+        settings.log_path = path.to_string_lossy().into_owned();
+        settings.log_max_bytes = MAX_BYTES;
+        settings.log_kept_files = KEPT_FILES;
+    });
+    for index in 0..MESSAGES {
+        add_message(&server.messages, format!("rotation message {}", index), MessageType::Info, MessageOrigin::Game);
+    }
+    // Arrêter le serveur ferme le journal après avoir tout écrit
+    drop(server);
+
+    // Les fichiers tournés vont de <path>.1, le plus récent, à <path>.KEPT_FILES
+    for index in 1..=KEPT_FILES {
+        assert!(rotated(&path, index).exists(), "{} is missing", rotated(&path, index).display());
+    }
+    assert!(!rotated(&path, KEPT_FILES + 1).exists(), "more than {} rotated files were kept", KEPT_FILES);
+
+    let mut files: Vec<PathBuf> = (1..=KEPT_FILES).rev().map(|index| rotated(&path, index)).collect();
+    files.push(path.clone());
+    let mut numbers = Vec::new();
+    let mut last_line = String::new();
+    for file in &files {
+        let contents = fs::read_to_string(file).unwrap();
+        assert!(contents.len() as u64 <= MAX_BYTES, "{} holds {} bytes, past the limit", file.display(), contents.len());
+        for line in contents.lines() {
+            let (time, rest) = line.split_once(' ').unwrap();
+            assert!(is_utc_time(time), "{:?} does not start with a UTC time", line);
+            let (level, _) = rest.split_once(' ').unwrap();
+            assert!(["[INFO]", "[WARN]", "[ERROR]", "[DEBUG]", "[EVENT]"].contains(&level), "{:?} has no level", line);
+            if let Some((_, number)) = line.split_once("[INFO] [GAME] rotation message ") {
+                numbers.push(number.parse::<usize>().unwrap());
+            }
+            last_line = line.to_string();
+        }
+    }
+
+    // Les fichiers gardés se suivent sans trou et finissent par les derniers messages
+    assert!(numbers.len() > 1 && numbers.len() < MESSAGES, "{} messages kept", numbers.len());
+    assert!(numbers.windows(2).all(|pair| pair[1] == pair[0] + 1), "the kept messages are not in order or have a gap");
+    assert_eq!(numbers.last(), Some(&(MESSAGES - 1)));
+    assert!(last_line.contains("[STOP] Server stopped"), "the log ends with {:?}", last_line);
+
+    let _ = fs::remove_dir_all(&dir);
+}
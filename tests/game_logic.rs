@@ -0,0 +1,3639 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::bullet::bullet::BulletKind;
+use universal_rust_server_software::entities::entity::{Entity, HitRecord};
+use rapier2d::prelude::*;
+use universal_rust_server_software::game_logic::ai_behavior::{AiDifficulty, ObstacleView};
+use universal_rust_server_software::game_logic::arena_config::ArenaConfig;
+use universal_rust_server_software::game_logic::control_point::Side;
+use universal_rust_server_software::game_logic::game_config::{GameConfig, GameMode};
+use universal_rust_server_software::game_logic::game_loop::GameLoop;
+use universal_rust_server_software::game_logic::leaderboard::{Leaderboard, PlayerTotals};
+use universal_rust_server_software::game_logic::match_report::{MatchReport, PlayerRecord, PlayerReport, RoundSummary};
+use universal_rust_server_software::game_logic::match_state::MatchPhase;
+use universal_rust_server_software::game_logic::name_index::NameError;
+use universal_rust_server_software::game_logic::observer::{BonusReason, GameEvent, GameObserver, PenaltyReason};
+use universal_rust_server_software::game_logic::render_snapshot::RenderSnapshot;
+use universal_rust_server_software::game_logic::replay::{EntityFrame, Replay, ReplayPlayer};
+use universal_rust_server_software::game_logic::scoring::{ScoreBreakdown, ScoreRules};
+use universal_rust_server_software::game_logic::step_timings::StepPhase;
+use universal_rust_server_software::game_logic::timeline::{analyze_timeline, TimelineMode, TimelineWriter};
+use universal_rust_server_software::game_logic::trail_store::TrailSettings;
+use universal_rust_server_software::game_logic::wave_director::{WaveDirector, WaveState};
+use universal_rust_server_software::game_logic::GameLogic;
+use universal_rust_server_software::obstacles::ObstacleShape;
+use universal_rust_server_software::physics::body_identity::BodyIdentity;
+use universal_rust_server_software::power_ups::{PowerUp, PowerUpKind};
+use universal_rust_server_software::types::{MessageHub, MessageOrigin, MessageType};
+
+#[test]
+fn added_entities_are_stepped() {
+    let mut logic = GameLogic::new();
+    let first = logic.add_entity("Player1".to_string());
+    let second = logic.add_entity("Player2".to_string());
+    assert_ne!(first, second);
+    assert_eq!(logic.entities.len(), 2);
+
+    // Les deux moteurs à fond, l'entité avance tout droit
+    let driven = logic.entities.iter_mut().find(|entity| entity.id == first).unwrap();
+    driven.motor_left = 1.0;
+    driven.motor_right = 1.0;
+    let start = (driven.x, driven.y);
+    for _ in 0..30 {
+        logic.step();
+    }
+
+    assert!(logic.sim_time() > Duration::ZERO);
+    assert_eq!(logic.entities.len(), 2);
+    let driven = logic.entities.iter().find(|entity| entity.id == first).unwrap();
+    assert_ne!((driven.x, driven.y), start, "the driven entity did not move in 30 steps");
+}
+
+#[test]
+fn entity_spawns_are_queued() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity("Player1".to_string());
+    logic.step();
+
+    let spawned = logic.events.since(0).any(|queued| matches!(&queued.event, GameEvent::Spawn { id: spawned, name, .. } if *spawned == id && name == "Player1"));
+    assert!(spawned);
+}
+
+#[test]
+fn ai_entities_are_driven() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_ai("AI Bot 1".to_string(), None, AiDifficulty::default());
+    logic.add_entity("Player1".to_string());
+    for _ in 0..60 {
+        logic.update_ai();
+        logic.step();
+    }
+
+    let bot = logic.entities.iter().find(|entity| entity.id == id).expect("the AI entity is still in the arena");
+    assert!(bot.is_ai);
+}
+
+#[test]
+fn paused_game_does_not_step() {
+    let mut logic = GameLogic::new();
+    logic.add_entity("Player1".to_string());
+    logic.pause();
+    let time = logic.sim_time();
+    logic.step();
+    assert_eq!(logic.sim_time(), time);
+}
+
+#[test]
+fn paused_game_steps_once_at_a_time() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity_at("Runner".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+    let dt = Duration::from_secs_f64(logic.physics_engine.integration_parameters.dt as f64);
+
+    // Hors pause, le pas unique ne fait rien
+    let tick = logic.tick;
+    assert!(!logic.step_once());
+    assert_eq!(logic.tick, tick);
+
+    logic.pause();
+    for steps in 1..=3 {
+        let (time, position) = (logic.sim_time(), position_of(&logic, id));
+        assert!(logic.step_once());
+        assert_eq!(logic.tick, tick + steps, "exactly one step");
+        assert!((logic.sim_time().as_secs_f64() - (time + dt).as_secs_f64()).abs() < 1e-6);
+        assert_ne!(position_of(&logic, id), position, "the entity moved");
+        assert!(logic.is_paused(), "the game stays paused");
+        // Le pas suivant attend le prochain pas unique
+        logic.step();
+        assert_eq!(logic.tick, tick + steps);
+    }
+}
+
+#[test]
+fn entity_names_are_unique() {
+    let mut logic = GameLogic::new();
+    let first = logic.add_entity("Player".to_string());
+    let second = logic.add_entity("Player".to_string());
+    let bot = logic.add_ai("Player".to_string(), None, AiDifficulty::Normal);
+    let names: Vec<&str> = logic.entities.iter().map(|entity| entity.name.as_str()).collect();
+    assert_eq!(names, vec!["Player", "Player-2", "Player-3"]);
+    assert_eq!(logic.entity_by_name("Player-3").map(|entity| entity.id), Some(bot));
+
+    assert_eq!(logic.rename_entity(second, "Player"), Err(NameError::Taken));
+    assert_eq!(logic.rename_entity(first, "Player"), Ok("Player".to_string()), "an entity keeps its own name");
+    assert_eq!(logic.rename_entity(second, "Rusty"), Ok("Rusty".to_string()));
+    assert_eq!(logic.entity_by_name("Rusty").map(|entity| entity.id), Some(second));
+    assert!(logic.entity_by_name("Player-2").is_none(), "a renamed entity frees its previous name");
+
+    // Le nom d'une entité retirée est libre à nouveau
+    logic.remove_entity_by_id(first);
+    assert!(logic.entity_by_name("Player").is_none());
+    assert_eq!(logic.rename_entity(second, "Player"), Ok("Player".to_string()));
+    assert_eq!(logic.rename_entity(first, "Ghost"), Err(NameError::NoEntity));
+}
+
+#[test]
+fn taken_names_get_a_suffix_when_asked() {
+    let mut logic = GameLogic::new();
+    logic.game_config.auto_suffix_names = true;
+    logic.add_entity("Player".to_string());
+    logic.add_entity("Player-2".to_string());
+    let renamed = logic.add_entity("Rusty".to_string());
+    assert_eq!(logic.rename_entity(renamed, "Player"), Ok("Player-3".to_string()));
+
+    // Le suffixe raccourcit un nom trop long pour tenir avec lui
+    let long = "A".repeat(24);
+    let other = logic.add_entity("Other".to_string());
+    assert_eq!(logic.rename_entity(renamed, &long), Ok(long.clone()));
+    let suffixed = logic.rename_entity(other, &long).unwrap();
+    assert_eq!(suffixed, format!("{}-2", "A".repeat(22)));
+}
+
+#[test]
+fn invalid_names_are_refused() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity("Player".to_string());
+    for name in ["", &"x".repeat(25), "a,b", "a#b", "a=b", "tab\tname"] {
+        assert!(logic.rename_entity(id, name).is_err(), "{:?}", name);
+    }
+    assert_eq!(logic.rename_entity(id, "\u{1}"), Err(NameError::Character));
+    assert_eq!(logic.rename_entity(id, &"é".repeat(24)), Ok("é".repeat(24)), "the length counts characters");
+    assert_eq!(logic.entities[0].name, "é".repeat(24));
+}
+
+/// Returns the penalties and the camping positions told since the game started.
+fn penalties(logic: &GameLogic) -> (Vec<(u32, i32, PenaltyReason)>, Vec<u32>) {
+    let mut penalties = Vec::new();
+    let mut camping = Vec::new();
+    for queued in logic.events.since(0) {
+        match queued.event {
+            GameEvent::Penalty { id, points, reason } => penalties.push((id, points, reason)),
+            GameEvent::Camping { id, .. } => camping.push(id),
+            _ => {}
+        }
+    }
+    (penalties, camping)
+}
+
+#[test]
+fn own_mines_cost_points() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 100;
+    config.spawn_protection_ms = 0;
+    config.ramming_enabled = false;
+    config.points_per_hit = 5;
+    config.self_hit_penalty = 3;
+    config.mine_damage = 10;
+    let owner = logic.add_entity("Owner".to_string());
+    let other = logic.add_entity("Other".to_string());
+    logic.teleport_entity(owner, 300.0, 300.0);
+    logic.teleport_entity(other, 700.0, 700.0);
+    logic.step();
+    logic.deploy_mine(owner).unwrap();
+    for _ in 0..70 {
+        logic.step();
+    }
+
+    // L'autre entité entre dans la mine armée : le souffle touche aussi le poseur
+    logic.teleport_entity(other, 300.0 + AppDefines::ENTITY_HALF_SIZE + AppDefines::MINE_RADIUS / 2.0, 300.0);
+    for _ in 0..5 {
+        logic.step();
+    }
+    let health = |id: u32| logic.entities.iter().find(|entity| entity.id == id).unwrap().health;
+    assert!(health(other) < 100 && health(owner) < 100, "both entities stand in the blast");
+    let owner_entity = logic.entities.iter().find(|entity| entity.id == owner).unwrap();
+    assert_eq!(owner_entity.score, 5 - 3, "the hit on the other entity earns 5, the self hit costs 3");
+    assert_eq!(owner_entity.breakdown.hits, 0);
+    assert_eq!(penalties(&logic).0, vec![(owner, 3, PenaltyReason::SelfHit)]);
+}
+
+#[test]
+fn destroying_oneself_is_no_kill() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 100;
+    config.spawn_protection_ms = 0;
+    config.ramming_enabled = false;
+    config.self_hit_penalty = 2;
+    config.mine_damage = 1000;
+    let owner = logic.add_entity("Owner".to_string());
+    let other = logic.add_entity("Other".to_string());
+    logic.teleport_entity(owner, 300.0, 300.0);
+    logic.teleport_entity(other, 700.0, 700.0);
+    logic.step();
+    logic.deploy_mine(owner).unwrap();
+    for _ in 0..70 {
+        logic.step();
+    }
+    logic.teleport_entity(other, 300.0 + AppDefines::ENTITY_HALF_SIZE + AppDefines::MINE_RADIUS / 2.0, 300.0);
+    for _ in 0..5 {
+        logic.step();
+    }
+
+    assert!(logic.entities.is_empty(), "the blast destroys both entities");
+    let kills: Vec<(u32, u32)> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Kill { shooter, victim, .. } => Some((shooter, victim)),
+        _ => None,
+    }).collect();
+    assert!(kills.contains(&(owner, owner)), "the owner is its own shooter: {:?}", kills);
+    let totals: Vec<(String, u32, u32)> = logic.leaderboard.top(10).into_iter()
+        .map(|(name, totals)| (name.to_string(), totals.kills, totals.deaths))
+        .collect();
+    assert!(totals.contains(&("Owner".to_string(), 1, 1)), "only the other entity counts as a kill: {:?}", totals);
+    assert!(penalties(&logic).0.contains(&(owner, 2, PenaltyReason::SelfHit)));
+}
+
+#[test]
+fn campers_lose_points_until_they_move() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.camping_enabled = true;
+    config.camping_secs = 1.0;
+    config.camping_points_per_sec = 2.0;
+    let camper = logic.add_entity("Camper".to_string());
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+
+    for _ in 0..steps_per_second {
+        logic.step();
+    }
+    assert_eq!(logic.entities[0].score, 0, "no penalty before the delay");
+    assert_eq!(penalties(&logic), (Vec::new(), Vec::new()));
+
+    for _ in 0..2 * steps_per_second {
+        logic.step();
+    }
+    let (lost, camping) = penalties(&logic);
+    assert_eq!(logic.entities[0].score, -4, "2 points per second for 2 seconds");
+    assert_eq!(lost, vec![(camper, 1, PenaltyReason::Camping); 4]);
+    assert_eq!(camping, vec![camper], "the position is told once without the reveal rule");
+
+    // Sortie du rayon : la pénalité s'arrête et l'attente recommence.
+    // Le saut se fait vers le centre, pour que le bord de l'arène ne le raccourcisse pas
+    let (x, y) = (logic.entities[0].x, logic.entities[0].y);
+    let jump = if x < AppDefines::DEFAULT_ARENA_WIDTH / 2.0 { 100.0 } else { -100.0 };
+    logic.teleport_entity(camper, x + jump, y);
+    for _ in 0..steps_per_second / 2 {
+        logic.step();
+    }
+    assert_eq!(logic.entities[0].score, -4);
+}
+
+#[test]
+fn campers_are_revealed_when_asked() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.camping_enabled = true;
+    config.camping_secs = 1.0;
+    config.camping_points_per_sec = 0.0;
+    config.camping_reveal = true;
+    let camper = logic.add_entity("Camper".to_string());
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    for _ in 0..12 * steps_per_second {
+        logic.step();
+    }
+    let (lost, camping) = penalties(&logic);
+    assert!(lost.is_empty());
+    assert_eq!(camping, vec![camper; 3], "told at 1, 6 and 11 seconds");
+}
+
+/// Puts an entity against the left wall, or `distance` away from it, with its gun pointing at the wall.
+fn face_left_wall(logic: &mut GameLogic, id: u32, distance: f32) {
+    logic.teleport_entity(id, AppDefines::ENTITY_HALF_SIZE + distance, 300.0);
+    let entity = logic.entities.iter_mut().find(|entity| entity.id == id).unwrap();
+    let rotation = logic.physics_engine.bodies[entity.handle].rotation().angle();
+    // Le canon tire vers l'arrière du corps : une orientation nulle vise l'angle du corps plus un demi-tour
+    let orientation = (-rotation / std::f32::consts::TAU).rem_euclid(1.0);
+    entity.gun_mut().orientation = orientation as f64;
+    entity.gun_mut().traverse = orientation;
+}
+
+/// Fires one bullet from an entity, once its fire cooldown allows it.
+fn fire_once(logic: &mut GameLogic, id: u32) {
+    logic.entities.iter_mut().find(|entity| entity.id == id).unwrap().gun_mut().trigger = 1.0;
+    while logic.bullets.is_empty() {
+        logic.step();
+    }
+    logic.entities.iter_mut().find(|entity| entity.id == id).unwrap().gun_mut().trigger = 0.0;
+}
+
+#[test]
+fn point_blank_shots_spare_the_shooter() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 10;
+    config.spawn_protection_ms = 0;
+    config.bullet_bounces = 1;
+    config.shooter_grace_ms = 0;
+    config.self_hit_penalty = 3;
+    let shooter = logic.add_entity("Shooter".to_string());
+    face_left_wall(&mut logic, shooter, 20.0);
+    logic.step();
+
+    fire_once(&mut logic, shooter);
+    for _ in 0..60 {
+        logic.step();
+    }
+    assert!(logic.bullets.is_empty(), "the bullet is spent on the wall");
+    assert_eq!((logic.entities[0].health, logic.entities[0].score), (10, 0), "the bullet comes back within the grace period");
+    assert_eq!(penalties(&logic).0, Vec::new());
+}
+
+#[test]
+fn ricochets_hurt_the_shooter_after_the_grace_period() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 10;
+    config.spawn_protection_ms = 0;
+    config.bullet_bounces = 1;
+    config.bullet_damage = 2;
+    config.self_hit_penalty = 3;
+    let shooter = logic.add_entity("Shooter".to_string());
+    face_left_wall(&mut logic, shooter, 200.0);
+    logic.step();
+
+    fire_once(&mut logic, shooter);
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    for _ in 0..2 * steps_per_second {
+        logic.step();
+    }
+    assert!(logic.bullets.is_empty(), "the bullet came back into its shooter");
+    assert_eq!((logic.entities[0].health, logic.entities[0].score), (8, -3));
+    assert_eq!(penalties(&logic).0, vec![(shooter, 3, PenaltyReason::SelfHit)]);
+    let hits: Vec<(u32, u32)> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { shooter, victim, .. } => Some((shooter, victim)),
+        _ => None,
+    }).collect();
+    assert_eq!(hits, vec![(shooter, shooter)]);
+}
+
+/// Returns where the bullets were fired from, oldest first.
+fn shots(logic: &GameLogic) -> Vec<(f32, f32)> {
+    logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::BulletFired { x, y, .. } => Some((x, y)),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn turrets_cool_down_on_their_own() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.fire_cooldown_ms = 500;
+    let id = logic.add_entity("Heavy".to_string());
+    logic.get_entity_mut(id).unwrap().mount_turrets(&[(0.0, -10.0), (0.0, 10.0)]);
+    let entity = logic.get_entity_mut(id).unwrap();
+    assert_eq!(entity.set_turret_actuator(0, "GunTrig", 1.0), Some(1.0));
+    assert_eq!(entity.set_turret_actuator(2, "GunTrig", 1.0), None, "there is no third turret");
+    while logic.bullets.is_empty() {
+        logic.step();
+    }
+
+    // La première tourelle recharge, la seconde n'a pas encore tiré
+    let now = logic.sim_time();
+    let entity = logic.get_entity_mut(id).unwrap();
+    let cooldown = entity.fire_cooldown;
+    assert!(!entity.turrets[0].cooldown_remaining(cooldown, now).is_zero());
+    assert!(entity.turrets[1].cooldown_remaining(cooldown, now).is_zero());
+    entity.set_turret_actuator(1, "GunTrig", 1.0);
+    logic.step();
+    assert_eq!(logic.bullets.len(), 2, "the second turret fires at once");
+
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    for _ in 0..steps_per_second / 4 {
+        logic.step();
+    }
+    assert_eq!(shots(&logic).len(), 2, "neither turret fires again before its cooldown");
+    for _ in 0..steps_per_second / 2 {
+        logic.step();
+    }
+    assert_eq!(shots(&logic).len(), 4);
+}
+
+#[test]
+fn the_gun_fires_again_exactly_when_the_cooldown_is_over() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.fire_cooldown_ms = 500;
+    let id = logic.add_entity("Gunner".to_string());
+    let entity = logic.get_entity_mut(id).unwrap();
+    let shot = Duration::from_secs(10);
+    assert!(entity.try_fire(0, shot));
+
+    let early = shot + Duration::from_millis(499);
+    assert_eq!(entity.fire_cooldown_remaining(early), Duration::from_millis(1), "the STATUS reply counts down to the shot");
+    assert!(!entity.try_fire(0, early), "1 ms too early");
+    let ready = shot + Duration::from_millis(500);
+    assert_eq!(entity.fire_cooldown_remaining(ready), Duration::ZERO);
+    assert!(entity.try_fire(0, ready), "the refused shot did not restart the cooldown");
+    assert!(!entity.try_fire(0, ready), "the shot restarts it");
+}
+
+/// Returns the obstacles of a map generated from a seed, as bytes.
+fn map_bytes(logic: &mut GameLogic, seed: u64) -> Vec<u8> {
+    logic.generate_map(Some(seed));
+    let obstacles: Vec<_> = logic.obstacles.iter().map(|obstacle| (obstacle.position, obstacle.shape)).collect();
+    serde_json::to_vec(&obstacles).unwrap()
+}
+
+#[test]
+fn a_seed_always_gives_the_same_obstacles() {
+    let mut logic = GameLogic::new();
+    let first = map_bytes(&mut logic, 1793);
+    assert!(!first.is_empty());
+
+    // Une autre partie, avec des entités et un générateur qui a déjà servi
+    let mut other = GameLogic::new();
+    other.add_entity("Player".to_string());
+    other.generate_map(None);
+    for _ in 0..10 {
+        other.step();
+    }
+    assert_eq!(map_bytes(&mut other, 1793), first);
+    assert_eq!(map_bytes(&mut logic, 1793), first, "the map can be generated again");
+    assert_eq!(logic.map_seed(), Some(1793));
+
+    for seed in [0, 1792, 1794, u64::MAX] {
+        assert_ne!(map_bytes(&mut logic, seed), first, "seed {}", seed);
+    }
+}
+
+/// Runs 300 steps of a match through a game loop, with the wall clock advancing 20 ms at a time.
+///
+/// # Returns
+/// The position, health and score of each entity, as bits.
+fn run_scaled(time_scale: f32) -> Vec<(u32, u32, i32, i32)> {
+    let mut logic = GameLogic::new();
+    logic.generate_map(Some(1796));
+    logic.game_config.spawn_protection_ms = 0;
+    let player = logic.add_entity("Player".to_string());
+    logic.add_ai("Hunter".to_string(), Some("hunter"), AiDifficulty::Hard);
+    logic.add_ai("Wanderer".to_string(), None, AiDifficulty::Normal);
+    let entity = logic.get_entity_mut(player).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 0.6;
+    entity.gun_mut().trigger = 1.0;
+    logic.time_scale = time_scale;
+
+    let game_logic = Arc::new(Mutex::new(logic));
+    let start = Instant::now();
+    let mut game_loop = GameLoop::headless(Arc::clone(&game_logic), 50.0, start);
+    let mut now = start;
+    while game_logic.lock().unwrap().tick < 300 {
+        // Le dernier pas peut manquer d'une nanoseconde après la mise à l'échelle : il est rattrapé par petits bouts
+        let remaining = 300 - game_logic.lock().unwrap().tick;
+        now += if (remaining as f32) < time_scale { Duration::from_millis(1) } else { Duration::from_millis(20) };
+        game_loop.advance(now);
+    }
+    let logic = game_logic.lock().unwrap();
+    assert_eq!(logic.tick, 300);
+    logic.entities.iter().map(|entity| (entity.x.to_bits(), entity.y.to_bits(), entity.health, entity.score)).collect()
+}
+
+#[test]
+fn the_time_scale_does_not_change_the_match() {
+    assert_eq!(run_scaled(2.0), run_scaled(1.0));
+}
+
+#[test]
+fn bullets_leave_from_their_turret() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    let id = logic.add_entity("Heavy".to_string());
+    logic.teleport_entity(id, 400.0, 300.0);
+    let mounts = [(5.0, -12.0), (5.0, 12.0)];
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.mount_turrets(&mounts);
+    entity.turrets[1].orientation = 0.25;
+    entity.turrets[1].traverse = 0.25;
+    for turret in 0..2 {
+        entity.set_turret_actuator(turret, "GunTrig", 1.0);
+    }
+    while logic.bullets.len() < 2 {
+        logic.step();
+    }
+
+    let entity = logic.get_entity_mut(id).unwrap();
+    let rotation = entity.self_orientation as f32;
+    let (sin, cos) = rotation.sin_cos();
+    let fired = shots(&logic);
+    for ((x, y), (turret, mount)) in fired.into_iter().zip(mounts.into_iter().enumerate()) {
+        // Une orientation nulle vise l'arrière du corps, un demi-tour l'avant
+        let angle = rotation + std::f32::consts::PI * (1.0 + 2.0 * [0.5, 0.25][turret]);
+        let expected_x = 400.0 + mount.0 * cos - mount.1 * sin + AppDefines::ENTITY_HALF_SIZE * angle.cos();
+        let expected_y = 300.0 + mount.0 * sin + mount.1 * cos + AppDefines::ENTITY_HALF_SIZE * angle.sin();
+        assert!((x - expected_x).abs() < 0.01 && (y - expected_y).abs() < 0.01,
+            "turret {} fired from ({}, {}) instead of ({}, {})", turret, x, y, expected_x, expected_y);
+    }
+}
+
+#[test]
+fn fire_energy_regenerates_over_time() {
+    let mut logic = GameLogic::new();
+    logic.game_config.fire_energy_max = 100.0;
+    logic.game_config.fire_energy_regen_per_sec = 20.0;
+    let id = logic.add_entity("Gunner".to_string());
+    logic.get_entity_mut(id).unwrap().fire_energy = 10.0;
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    for _ in 0..2 * steps_per_second {
+        logic.step();
+    }
+    let energy = logic.entities[0].fire_energy;
+    assert!((energy - 50.0).abs() < 0.01, "20 per second for 2 seconds, got {}", energy);
+
+    for _ in 0..5 * steps_per_second {
+        logic.step();
+    }
+    assert_eq!(logic.entities[0].fire_energy, 100.0, "the energy stops at the maximum");
+}
+
+#[test]
+fn shots_need_fire_energy() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.fire_cooldown_ms = 0;
+    config.fire_energy_regen_per_sec = 0.0;
+    config.fire_energy_max = 100.0;
+    config.shot_energy_cost = 30.0;
+    let id = logic.add_entity("Gunner".to_string());
+    logic.get_entity_mut(id).unwrap().gun_mut().trigger = 1.0;
+    for _ in 0..30 {
+        logic.step();
+    }
+    assert_eq!(shots(&logic).len(), 3, "three shots of 30 out of 100");
+    assert!((logic.entities[0].fire_energy - 10.0).abs() < 0.01);
+
+    // Le rechargement de la simulation rend toute l'énergie
+    logic.reset_simulation();
+    assert_eq!(logic.entities[0].fire_energy, 100.0);
+}
+
+#[test]
+fn free_shots_play_as_before() {
+    let run = |cost: Option<f32>| {
+        let mut logic = GameLogic::new();
+        logic.game_config.spawn_protection_ms = 0;
+        if let Some(cost) = cost {
+            logic.game_config.shot_energy_cost = cost;
+            logic.game_config.motors_drain_energy = false;
+        }
+        logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+        logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+        let gun = logic.entities[0].gun_mut();
+        gun.traverse = 0.0;
+        gun.orientation = 0.0;
+        gun.trigger = 1.0;
+        for _ in 0..300 {
+            logic.step();
+        }
+        (shots(&logic), logic.entities.iter().map(|entity| (entity.x, entity.y, entity.health)).collect::<Vec<_>>())
+    };
+    assert_eq!(run(Some(0.0)), run(None), "a zero cost changes nothing");
+}
+
+/// Returns the directions the bullets were fired in, oldest first.
+fn shot_angles(logic: &GameLogic) -> Vec<f32> {
+    logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::BulletFired { angle, .. } => Some(angle),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn scatter_shots_fan_out_around_the_aim() {
+    let fan = BulletKind::Scatter.fan(1.0);
+    let spacing = AppDefines::SCATTER_FAN_ANGLE;
+    assert_eq!(fan.len(), AppDefines::SCATTER_PELLETS);
+    for (angle, expected) in fan.iter().zip([1.0 - spacing, 1.0, 1.0 + spacing]) {
+        assert!((angle - expected).abs() < 1e-6, "{:?}", fan);
+    }
+    assert_eq!(BulletKind::Sniper.fan(1.0), vec![1.0]);
+
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    let id = logic.add_entity_at("Gunner".to_string(), 400.0, 300.0, 0.0, false).unwrap();
+    logic.get_entity_mut(id).unwrap().weapon = BulletKind::Scatter;
+    fire_once(&mut logic, id);
+    let angles = shot_angles(&logic);
+    assert_eq!(angles.len(), 3, "one trigger pull, three bullets");
+    assert!((angles[1] - angles[0] - spacing).abs() < 1e-5 && (angles[2] - angles[1] - spacing).abs() < 1e-5, "{:?}", angles);
+    assert_eq!(logic.entities[0].breakdown.shots_fired, 3);
+    for _ in 0..10 {
+        logic.step();
+    }
+    assert_eq!(logic.bullets.len(), 3, "the bullets of a shot do not destroy each other");
+}
+
+#[test]
+fn damage_falls_off_with_the_distance() {
+    let mut config = GameConfig { bullet_damage: 40, ..GameConfig::default() };
+    assert_eq!(BulletKind::Standard.damage(800.0, &config), 40, "no falloff by default");
+    config.damage_falloff_range = 400.0;
+    config.damage_falloff_min = 0.25;
+    let curve: Vec<i32> = [0.0, 100.0, 200.0, 400.0, 800.0].iter().map(|&distance| BulletKind::Standard.damage(distance, &config)).collect();
+    assert_eq!(curve, [40, 33, 25, 10, 10], "linear down to a quarter at 400");
+    assert_eq!(BulletKind::Scatter.damage(200.0, &config), 13, "half of 25, rounded");
+    config.bullet_damage = 1;
+    assert_eq!(BulletKind::Scatter.damage(400.0, &config), 1, "a hit removes at least 1");
+
+    // La distance parcourue est suivie pas à pas
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 200;
+    config.bullet_damage = 100;
+    config.damage_falloff_range = 600.0;
+    config.damage_falloff_min = 0.0;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    let damage: Vec<i32> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { damage, .. } => Some(damage),
+        _ => None,
+    }).collect();
+    // Du bord du tireur au bord de la cible, moins le rayon de la balle
+    let traveled = 300.0 - 2.0 * AppDefines::ENTITY_HALF_SIZE - AppDefines::DEFAULT_BULLET_RADIUS;
+    let expected = 100.0 * (1.0 - traveled / 600.0);
+    assert!(damage.len() == 1 && (damage[0] as f32 - expected).abs() <= 3.0, "{:?} instead of about {}", damage, expected);
+}
+
+#[test]
+fn armor_shrugs_off_part_of_the_damage() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity("Tank".to_string());
+    let entity = logic.get_entity_mut(id).unwrap();
+    let mut tiers = Vec::new();
+    for _ in 0..5 {
+        tiers.push((entity.armor, entity.mitigate(10), entity.mitigate(4), entity.mitigate(1), entity.mitigate(0)));
+        entity.pick_up(PowerUpKind::ArmorUp, 0, 0);
+    }
+    // 7,5 et 2,5 s'arrondissent vers le haut, 0,25 remonte à 1
+    assert_eq!(tiers, [
+        (0.0, 10, 4, 1, 0),
+        (0.25, 8, 3, 1, 0),
+        (0.5, 5, 2, 1, 0),
+        (0.75, 3, 1, 1, 0),
+        (0.75, 3, 1, 1, 0),
+    ], "no armor by default, a hit removes at least 1 and the armor stops at 0.75");
+}
+
+#[test]
+fn armored_targets_take_less_from_each_bullet() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 100;
+    config.bullet_damage = 10;
+    config.game_mode = GameMode::ControlPoint;
+    config.control_point_starting_armor = 0.5;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    assert_eq!(logic.get_entity_mut(target).unwrap().armor, 0.5, "the starting armor of the game mode");
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    let damage: Vec<i32> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { damage, .. } => Some(damage),
+        _ => None,
+    }).collect();
+    assert_eq!(damage, [5]);
+    let entity = logic.get_entity_mut(target).unwrap();
+    assert_eq!((entity.health, entity.breakdown.damage_mitigated), (95, 5));
+    let summary = RoundSummary {
+        round: 1,
+        reason: "time is up".to_string(),
+        winners: Vec::new(),
+        seed: None,
+        map: None,
+        start_tick: 0,
+        end_tick: 0,
+        step: Duration::from_millis(20),
+        ended_at: std::time::UNIX_EPOCH,
+    };
+    let report = MatchReport::build(&summary, &[PlayerRecord::of(entity)], []);
+    assert_eq!(report.players[0].damage_mitigated, 5);
+
+    // L'armure gagnée survit à une sauvegarde, pas à une nouvelle manche
+    entity.armor = 0.75;
+    let snapshot = logic.save_state();
+    logic.load_state(snapshot);
+    let entity = logic.get_entity_mut(target).unwrap();
+    assert_eq!((entity.armor, entity.breakdown.damage_mitigated), (0.75, 5));
+    logic.reset_simulation();
+    assert_eq!(logic.get_entity_mut(target).unwrap().armor, 0.5);
+    logic.game_config.game_mode = GameMode::Deathmatch;
+    logic.reset_simulation();
+    assert_eq!(logic.get_entity_mut(target).unwrap().armor, 0.0);
+}
+
+/// Shoots once at a target whose shield is set as given, and lets the bullet land.
+///
+/// Returns the target and the energy it had when the bullet left the gun.
+fn shoot_at_shield(shield: f32, energy: f32) -> (GameLogic, u32, f32) {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 100;
+    config.bullet_damage = 10;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    let entity = logic.get_entity_mut(target).unwrap();
+    (entity.shield, entity.energy) = (shield, energy);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    (logic, target, energy)
+}
+
+#[test]
+fn raised_shields_block_the_bullets() {
+    let (mut logic, target, energy) = shoot_at_shield(1.0, AppDefines::SHIELD_MAX_ENERGY);
+    let entity = logic.get_entity_mut(target).unwrap();
+    assert_eq!(entity.health, 100, "the bullet did no damage");
+    assert!(entity.shield_active());
+    // Le coup plus l'usure du bouclier levé pendant le vol de la balle, moins d'une seconde
+    let drained = energy - entity.energy;
+    let per_hit = AppDefines::SHIELD_DRAIN_PER_HIT;
+    assert!((per_hit..per_hit + AppDefines::SHIELD_DRAIN_PER_SECOND).contains(&drained), "drained {}", drained);
+    assert!(!logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Hit { .. })), "a blocked bullet is no hit");
+}
+
+#[test]
+fn hits_drain_the_shield_down_to_zero() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity("Turtle".to_string());
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.shield = 1.0;
+    entity.energy = AppDefines::SHIELD_DRAIN_PER_HIT * 1.5;
+    entity.block_hit();
+    assert!(entity.shield_active(), "half a hit of energy is left");
+    entity.block_hit();
+    assert_eq!((entity.energy, entity.shield), (0.0, 0.0), "the energy stops at zero and the shield drops");
+    assert!(!entity.shield_active());
+
+    // Le bouclier vidé ne protège plus : la balle suivante touche
+    let (mut logic, target, _) = shoot_at_shield(0.0, 0.0);
+    assert_eq!(logic.get_entity_mut(target).unwrap().health, 90);
+}
+
+#[test]
+fn shields_without_energy_block_nothing() {
+    let (mut logic, target, _) = shoot_at_shield(1.0, 0.0);
+    let entity = logic.get_entity_mut(target).unwrap();
+    assert_eq!(entity.health, 90, "the raised shield had no energy");
+    assert_eq!(entity.shield, 0.0, "the shield dropped on its own");
+    assert!(logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Hit { victim, damage: 10, .. } if victim == target)));
+}
+
+#[test]
+fn scores_decay_once_per_simulated_minute() {
+    let mut logic = GameLogic::new();
+    logic.score_rules.decay_enabled = true;
+    let id = logic.add_entity("Idle".to_string());
+    logic.get_entity_mut(id).unwrap().score = 100;
+    while penalties(&logic).0.is_empty() {
+        logic.step();
+    }
+    assert!((logic.sim_time().as_secs_f64() - 60.0).abs() < 0.1, "decayed after {:?}", logic.sim_time());
+    assert_eq!(penalties(&logic).0, vec![(id, 5, PenaltyReason::Decay)], "5 % of 100 points");
+    assert_eq!(logic.get_entity_mut(id).unwrap().score, 95);
+}
+
+#[test]
+fn comeback_bonus_follows_the_score_gap() {
+    let rules = ScoreRules { comeback_enabled: true, ..Default::default() };
+    let bonuses: Vec<i32> = [(0, 100), (0, 10), (20, 24), (50, 50), (100, 10), (-20, 20)]
+        .into_iter()
+        .map(|(shooter, victim)| rules.comeback_bonus(shooter, victim))
+        .collect();
+    // 2,5 s'arrondit vers le haut, aucun bonus contre un adversaire moins bien classé
+    assert_eq!(bonuses, [25, 3, 1, 0, 0, 10]);
+    assert_eq!(ScoreRules::default().comeback_bonus(0, 100), 0, "the bonus is off by default");
+}
+
+#[test]
+fn comeback_kills_are_told_as_bonus_events() {
+    let mut logic = GameLogic::new();
+    logic.score_rules.comeback_enabled = true;
+    logic.game_config.spawn_protection_ms = 0;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let leader = logic.add_entity_at("Leader".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    let target = logic.get_entity_mut(leader).unwrap();
+    target.score = 40;
+    target.health = 1;
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    let bonuses: Vec<(u32, i32)> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Bonus { id, points, reason: BonusReason::Comeback } => Some((id, points)),
+        _ => None,
+    }).collect();
+    assert_eq!(bonuses, [(gunner, 10)], "a quarter of the 40 points gap");
+}
+
+#[test]
+fn snipers_fire_faster_bullets_less_often() {
+    let run = |weapon: BulletKind| {
+        let mut logic = GameLogic::new();
+        let config = &mut logic.game_config;
+        config.spawn_protection_ms = 0;
+        config.fire_cooldown_ms = 500;
+        config.fire_energy_regen_per_sec = 0.0;
+        config.shot_energy_cost = 5.0;
+        let id = logic.add_entity_at("Gunner".to_string(), 400.0, 300.0, 0.0, false).unwrap();
+        let entity = logic.get_entity_mut(id).unwrap();
+        entity.weapon = weapon;
+        entity.gun_mut().trigger = 1.0;
+        // 2,83 secondes : les tirs tombent toutes les 30 ou 60 étapes
+        let mut speed = None;
+        for _ in 0..170 {
+            logic.step();
+            speed = speed.or_else(|| logic.bullets.first().map(|bullet| logic.physics_engine.bodies[bullet.handle].linvel().norm()));
+        }
+        let entity = &logic.entities[0];
+        (entity.shot_cooldown(), shots(&logic).len(), entity.fire_energy, speed.unwrap())
+    };
+    let (cooldown, fired, energy, speed) = run(BulletKind::Standard);
+    assert_eq!((cooldown, fired), (Duration::from_millis(500), 5));
+    assert!((energy - 75.0).abs() < 0.01 && (speed - AppDefines::DEFAULT_BULLET_SPEED).abs() < 1.0, "{} {}", energy, speed);
+    let (cooldown, fired, energy, speed) = run(BulletKind::Sniper);
+    assert_eq!((cooldown, fired), (Duration::from_millis(1000), 2));
+    assert!((energy - 70.0).abs() < 0.01, "two shots of 15: {}", energy);
+    assert!((speed - 2.0 * AppDefines::DEFAULT_BULLET_SPEED).abs() < 1.0, "{}", speed);
+}
+
+#[test]
+fn trails_are_sampled_and_capped() {
+    let mut logic = GameLogic::new();
+    logic.trails.settings = TrailSettings { interval_ticks: 5, max_samples: 4, linger_ticks: 0 };
+    let id = logic.add_entity("Walker".to_string());
+    let mut sampled = Vec::new();
+    for _ in 0..40 {
+        logic.step();
+        if logic.tick % 5 == 0 {
+            sampled.push((logic.entities[0].x, logic.entities[0].y));
+        }
+    }
+    let trail = logic.trails.get(id).unwrap();
+    assert_eq!(trail.points.len(), 4, "only the last samples are kept");
+    assert_eq!(trail.points.iter().copied().collect::<Vec<_>>(), sampled[sampled.len() - 4..]);
+}
+
+#[test]
+fn trails_of_removed_entities_linger() {
+    let mut logic = GameLogic::new();
+    logic.trails.settings = TrailSettings { interval_ticks: 1, max_samples: 10, linger_ticks: 20 };
+    let id = logic.add_entity("Ghost".to_string());
+    for _ in 0..5 {
+        logic.step();
+    }
+    logic.remove_entity_by_id(id);
+    for _ in 0..19 {
+        logic.step();
+    }
+    assert_eq!(logic.trails.get(id).map(|trail| trail.points.len()), Some(5), "kept for the post-mortem");
+    logic.step();
+    assert!(logic.trails.get(id).is_none(), "dropped once the linger is over");
+
+    logic.trails.settings.linger_ticks = 0;
+    let id = logic.add_entity("Gone".to_string());
+    logic.step();
+    logic.remove_entity_by_id(id);
+    assert!(logic.trails.get(id).is_none(), "dropped at once without linger");
+}
+
+#[test]
+fn trails_start_over_on_reset() {
+    let mut logic = GameLogic::new();
+    logic.trails.settings.interval_ticks = 1;
+    logic.add_entity("Walker".to_string());
+    for _ in 0..10 {
+        logic.step();
+    }
+    assert_eq!(logic.trails.len(), 1);
+    logic.reset_simulation();
+    assert!(logic.trails.is_empty());
+}
+
+#[test]
+fn replays_rebuild_the_trails() {
+    let mut logic = GameLogic::new();
+    logic.trails.settings = TrailSettings { interval_ticks: 3, max_samples: 8, linger_ticks: 10 };
+    let first = logic.add_entity("Walker".to_string());
+    logic.add_entity("Runner".to_string());
+    logic.entities[0].motor_left = 1.0;
+    for _ in 0..20 {
+        logic.step();
+    }
+    // L'enregistrement commence avec des traînées déjà tracées
+    logic.start_recording();
+    let mut expected = Vec::new();
+    for step in 0..60 {
+        if step == 25 {
+            logic.remove_entity_by_id(first);
+        }
+        if step == 45 {
+            logic.reset_simulation();
+        }
+        logic.step();
+        expected.push(logic.trails.clone());
+    }
+    let path = std::env::temp_dir().join(format!("trails_replay_{}.jsonl", std::process::id()));
+    logic.stop_recording().unwrap().save(&path).unwrap();
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut player = ReplayPlayer::new(replay);
+    // En avant image par image, puis en arrière, par sauts
+    for position in (0..60).chain([10, 50, 30, 59, 0]) {
+        player.position = position;
+        let trails: Vec<_> = player.trails().iter().map(|(id, trail)| (id, trail.points.clone())).collect();
+        let live: Vec<_> = expected[position].iter().map(|(id, trail)| (id, trail.points.clone())).collect();
+        assert_eq!(trails, live, "frame {}", position);
+    }
+}
+
+fn obstacle_view(x: f32, y: f32, shape: ObstacleShape) -> ObstacleView {
+    ObstacleView { position: vector![x, y], radius: shape.bounding_radius(), shape }
+}
+
+fn assert_near(point: Vector<f32>, expected: (f32, f32)) {
+    assert!((point.x - expected.0).abs() < 1e-3 && (point.y - expected.1).abs() < 1e-3, "{:?} instead of {:?}", point, expected);
+}
+
+#[test]
+fn closest_surfaces_follow_the_shapes() {
+    let position = vector![100.0, 100.0];
+    // Un carré à droite : le bord, pas le centre
+    let square = [obstacle_view(150.0, 110.0, ObstacleShape::Square { half: 20.0 })];
+    let closest = GameLogic::closest_surface(position, &square, None).unwrap();
+    assert_near(closest.point, (130.0, 100.0));
+    assert!((closest.distance - 30.0).abs() < 1e-3);
+
+    // Dans la boîte englobante d'un disque, hors du disque : vers le coin, au bord du disque
+    let circle = [obstacle_view(120.0, 120.0, ObstacleShape::Circle { radius: 25.0 })];
+    let closest = GameLogic::closest_surface(position, &circle, None).unwrap();
+    let corner = 20.0 - 25.0 / std::f32::consts::SQRT_2;
+    assert_near(closest.point, (100.0 + corner, 100.0 + corner));
+    assert!((closest.distance - (20.0 * std::f32::consts::SQRT_2 - 25.0)).abs() < 1e-3);
+
+    // Un mur en diagonale dont la boîte englobante contient la position
+    let wall = [obstacle_view(130.0, 130.0, ObstacleShape::Wall { half_length: 60.0, half_thickness: 4.0, angle: -std::f32::consts::FRAC_PI_4 })];
+    let closest = GameLogic::closest_surface(position, &wall, None).unwrap();
+    let step = (30.0 * std::f32::consts::SQRT_2 - 4.0) / std::f32::consts::SQRT_2;
+    assert_near(closest.point, (100.0 + step, 100.0 + step));
+
+    // Le plus proche l'emporte, avec son indice
+    let both = [circle[0], square[0]];
+    assert_eq!(GameLogic::closest_surface(position, &both, None).unwrap().obstacle, Some(0));
+    assert!(GameLogic::closest_surface(position, &[], None).is_none());
+}
+
+#[test]
+fn arena_walls_count_when_asked() {
+    let arena = ArenaConfig { width: 400.0, height: 300.0 };
+    let square = [obstacle_view(200.0, 150.0, ObstacleShape::Square { half: 10.0 })];
+    let closest = GameLogic::closest_surface(vector![20.0, 150.0], &square, Some(&arena)).unwrap();
+    assert_eq!(closest.obstacle, None);
+    assert_near(closest.point, (0.0, 150.0));
+    let closest = GameLogic::closest_surface(vector![20.0, 150.0], &square, None).unwrap();
+    assert_eq!(closest.obstacle, Some(0));
+    assert_near(GameLogic::closest_surface(vector![200.0, 290.0], &[], Some(&arena)).unwrap().point, (200.0, 300.0));
+}
+
+#[test]
+fn entities_find_the_closest_obstacle() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity_at("Scout".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    assert!(logic.closest_obstacle(id, false).is_none());
+    logic.add_obstacle_at(300.0, 360.0, ObstacleShape::Circle { radius: 20.0 }).unwrap();
+    logic.add_obstacle_at(255.0, 300.0, ObstacleShape::Square { half: 10.0 }).unwrap();
+    let (distance, bearing, obstacle) = logic.closest_obstacle(id, false).unwrap();
+    assert!((distance - 35.0).abs() < 1e-3);
+    assert!((bearing.abs() - std::f32::consts::PI).abs() < 1e-3, "behind the entity facing right");
+    assert_eq!(obstacle.unwrap().shape.name(), "SQUARE");
+    assert!(logic.closest_obstacle(999, false).is_none());
+}
+
+/// Writes a script in the temporary directory, under a name of its own.
+fn script_file(name: &str, text: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("{}_{}.ai", name, std::process::id()));
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+/// Returns the position of an entity.
+fn position_of(logic: &GameLogic, id: u32) -> (f32, f32) {
+    let entity = logic.entities.iter().find(|entity| entity.id == id).expect("the entity left the arena");
+    (entity.x, entity.y)
+}
+
+#[test]
+fn scripted_patrols_visit_their_waypoints_in_order() {
+    let mut logic = GameLogic::new();
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts/patroller.ai");
+    let id = logic.add_ai_scripted("Patroller".to_string(), &path).unwrap();
+    let corners = [(250.0, 250.0), (950.0, 250.0), (950.0, 750.0), (250.0, 750.0)];
+
+    let mut visits: Vec<usize> = Vec::new();
+    for _ in 0..6000 {
+        logic.update_ai();
+        logic.step();
+        let (x, y) = position_of(&logic, id);
+        let reached = corners.iter().position(|(cx, cy)| (x - cx).hypot(y - cy) < 40.0);
+        if let Some(corner) = reached.filter(|corner| visits.last() != Some(corner)) {
+            visits.push(corner);
+        }
+        if visits.len() >= 5 {
+            break;
+        }
+    }
+
+    assert!(visits.len() >= 5, "only visited {:?}", visits);
+    // Chaque coin mène au suivant, le dernier au premier
+    for pair in visits.windows(2) {
+        assert_eq!(pair[1], (pair[0] + 1) % corners.len(), "visited {:?}", visits);
+    }
+}
+
+#[test]
+fn scripted_ambushers_hold_their_post_and_fire() {
+    let mut logic = GameLogic::new();
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts/ambusher.ai");
+    let id = logic.add_ai_scripted("Ambusher".to_string(), &path).unwrap();
+
+    let mut held = 0;
+    for _ in 0..4000 {
+        logic.update_ai();
+        logic.step();
+        let (x, y) = position_of(&logic, id);
+        held = if (x - 600.0).hypot(y - 250.0) < 40.0 { held + 1 } else { 0 };
+        if held >= 120 {
+            break;
+        }
+    }
+    assert!(held >= 120, "the ambusher did not hold its post");
+
+    // Une proie à portée : l'embuscade se déclenche
+    logic.add_entity_at("Prey".to_string(), 600.0, 500.0, 0.0, false).unwrap();
+    let mut fired = false;
+    for _ in 0..600 {
+        logic.update_ai();
+        logic.step();
+        fired |= logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::BulletFired { shooter, .. } if shooter == id));
+        if fired {
+            break;
+        }
+    }
+    assert!(fired, "the ambusher did not fire at the prey");
+}
+
+#[test]
+fn script_errors_give_their_line() {
+    let mut logic = GameLogic::new();
+    let unknown = script_file("unknown_state", "state idle\nfire\nwhen enemy_within 100 -> fight\n");
+    let error = logic.add_ai_scripted("Broken".to_string(), &unknown).unwrap_err();
+    assert!(error.contains("line 3") && error.contains("fight"), "{}", error);
+
+    let typo = script_file("typo", "waypoint a 10 10\n\nstate idle\ngoto a\njump\n");
+    let error = logic.add_ai_scripted("Broken".to_string(), &typo).unwrap_err();
+    assert!(error.contains("line 5") && error.contains("jump"), "{}", error);
+
+    let two_moves = script_file("two_moves", "state idle\ngoto 10,10\nflee\n");
+    assert!(logic.add_ai_scripted("Broken".to_string(), &two_moves).unwrap_err().contains("line 3"));
+
+    assert!(logic.add_ai_scripted("Broken".to_string(), &script_file("empty", "# nothing\n")).is_err());
+    assert!(logic.add_ai_scripted("Broken".to_string(), std::path::Path::new("missing.ai")).is_err());
+    assert!(logic.entities.is_empty(), "a broken script added an entity");
+}
+
+#[test]
+fn failing_scripts_stop_their_entity() {
+    let mut logic = GameLogic::new();
+    let path = script_file("lost", "state wander\ngoto nowhere\n");
+    let lost = logic.add_ai_scripted("Lost".to_string(), &path).unwrap();
+    let wanderer = logic.add_ai("Wanderer".to_string(), None, AiDifficulty::Normal);
+
+    for _ in 0..120 {
+        logic.update_ai();
+        logic.step();
+    }
+
+    let entity = logic.entities.iter().find(|entity| entity.id == lost).expect("the entity of the failed script was removed");
+    assert_eq!((entity.motor_left, entity.motor_right), (0.5, 0.5));
+    assert!(logic.entities.iter().any(|entity| entity.id == wanderer));
+}
+
+#[test]
+fn round_reports_tell_the_scripted_match() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 2;
+    config.bullet_damage = 1;
+    config.points_per_hit = 1;
+    config.points_per_kill = 5;
+    config.death_penalty = 2;
+    config.score_target = 7;
+    config.spawn_protection_ms = 0;
+    config.base_spread = 0.0;
+    let dir = std::env::temp_dir().join(format!("round_reports_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    logic.report_dir = Some(dir.clone());
+    // Le canon tire vers l'arrière : Alpha, tourné vers la gauche, vise Bravo à sa droite
+    let alpha = logic.add_entity_at("Alpha".to_string(), 200.0, 300.0, std::f32::consts::PI, false).unwrap();
+    let bravo = logic.add_entity_at("Bravo".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(alpha).unwrap().gun_mut();
+    gun.orientation = 0.0;
+    gun.traverse = 0.0;
+    logic.step();
+
+    // Deux balles : une touche, puis la touche fatale
+    for _ in 0..2 {
+        fire_once(&mut logic, alpha);
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+    }
+    for _ in 0..5 {
+        logic.step();
+    }
+
+    let end_tick = logic.events.since(0).find(|queued| matches!(queued.event, GameEvent::MatchEnd { .. })).expect("the round did not end").step;
+    let kill_tick = logic.events.since(0).find(|queued| matches!(queued.event, GameEvent::Kill { .. })).unwrap().step;
+    let report = logic.last_report().expect("no report").clone();
+    assert_eq!(report.round, 1);
+    assert_eq!(report.reason, "score target reached");
+    assert_eq!(report.winners, vec!["Alpha".to_string()]);
+    assert_eq!((report.start_tick, report.end_tick, report.duration_ticks), (0, end_tick, end_tick));
+    assert!(report.match_id.starts_with("match_") && report.match_id.ends_with("_round1"), "{}", report.match_id);
+
+    assert_eq!(report.players, vec![
+        PlayerReport {
+            id: alpha, name: "Alpha".to_string(), team: None, score: 7, shots_fired: 2, hits: 2, kills: 1, deaths: 0,
+            accuracy: Some(1.0), damage_dealt: 2, damage_taken: 0, damage_mitigated: 0,
+        },
+        PlayerReport {
+            id: bravo, name: "Bravo".to_string(), team: None, score: -2, shots_fired: 0, hits: 0, kills: 0, deaths: 1,
+            accuracy: None, damage_dealt: 0, damage_taken: 2, damage_mitigated: 0,
+        },
+    ]);
+    assert_eq!(report.kills.len(), 1);
+    let kill = &report.kills[0];
+    assert_eq!((kill.tick, kill.shooter, kill.victim), (kill_tick, alpha, bravo));
+    assert_eq!((kill.shooter_name.as_deref(), kill.victim_name.as_deref()), (Some("Alpha"), Some("Bravo")));
+    let step = logic.physics_engine.integration_parameters.dt as f64;
+    assert!((kill.time_secs - kill_tick as f64 * step).abs() < 1e-6);
+
+    // Les deux fichiers portent le nom du rapport
+    let json = std::fs::read_to_string(dir.join(format!("{}.json", report.match_id))).unwrap();
+    let written: MatchReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(written, report);
+    let csv = std::fs::read_to_string(dir.join(format!("{}.csv", report.match_id))).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "match_id,round,name,team,score,shots_fired,hits,kills,deaths,accuracy,damage_dealt,damage_taken,damage_mitigated");
+    assert_eq!(lines[1], format!("{},1,Alpha,,7,2,2,1,0,1.0000,2,0,0", report.match_id));
+    assert_eq!(lines[2], format!("{},1,Bravo,,-2,0,0,0,1,,0,2,0", report.match_id));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn reports_keep_to_their_round() {
+    let players = [
+        PlayerRecord { id: 1, name: "Red, One".to_string(), team: Some(1), score: 3, breakdown: ScoreBreakdown { shots_fired: 4, hits: 1, kills: 0, deaths: 0, damage_mitigated: 0, waves_survived: 0 } },
+        PlayerRecord { id: 2, name: "Red Two".to_string(), team: Some(1), score: 3, breakdown: ScoreBreakdown::default() },
+        PlayerRecord { id: 3, name: "Blue".to_string(), team: Some(2), score: 5, breakdown: ScoreBreakdown::default() },
+    ];
+    let events = [
+        (5, GameEvent::Hit { shooter: 1, victim: 3, damage: 4, x: 0.0, y: 0.0, bearing: None }),
+        (20, GameEvent::Hit { shooter: 1, victim: 3, damage: 2, x: 0.0, y: 0.0, bearing: None }),
+        (21, GameEvent::Hit { shooter: 1, victim: 2, damage: 3, x: 0.0, y: 0.0, bearing: None }),
+        (22, GameEvent::Hit { shooter: 1, victim: 1, damage: 1, x: 0.0, y: 0.0, bearing: None }),
+        (30, GameEvent::Kill { shooter: 3, victim: 9, x: 1.0, y: 2.0 }),
+        (200, GameEvent::Kill { shooter: 3, victim: 1, x: 0.0, y: 0.0 }),
+    ];
+    let summary = RoundSummary {
+        round: 4,
+        reason: "time is up".to_string(),
+        winners: vec!["Blue".to_string()],
+        seed: Some(42),
+        map: None,
+        start_tick: 10,
+        end_tick: 110,
+        step: Duration::from_millis(20),
+        ended_at: std::time::UNIX_EPOCH + Duration::from_secs(1_714_571_112),
+    };
+    let report = MatchReport::build(&summary, &players, events.iter().map(|(tick, event)| (*tick, event)));
+
+    assert_eq!(report.match_id, "match_20240501T134512Z_round4");
+    assert_eq!(report.ended_at, "2024-05-01T13:45:12.000Z");
+    assert_eq!((report.duration_ticks, report.duration_secs), (100, 2.0));
+    // Le meilleur score d'abord, puis le nom à égalité
+    let names: Vec<&str> = report.players.iter().map(|player| player.name.as_str()).collect();
+    assert_eq!(names, ["Blue", "Red Two", "Red, One"]);
+    // Ni le coéquipier ni soi-même ne comptent dans les dégâts infligés
+    let damage: Vec<(i32, i32)> = report.players.iter().map(|player| (player.damage_dealt, player.damage_taken)).collect();
+    assert_eq!(damage, [(0, 2), (0, 3), (2, 1)]);
+    assert_eq!(report.kills.len(), 1, "only the kill of the round is listed");
+    assert_eq!((report.kills[0].time_secs, report.kills[0].victim_name.as_deref()), (0.4, None));
+    assert!(report.to_csv().contains(",\"Red, One\",1,3,4,1,0,0,0.2500,2,1,0\n"), "{}", report.to_csv());
+}
+
+/// Writes a map without obstacles holding control points, each as its name, position and radius.
+fn control_map(name: &str, points: &[(&str, f32, f32, f32)]) -> std::path::PathBuf {
+    let points: Vec<serde_json::Value> = points.iter()
+        .map(|(name, x, y, radius)| serde_json::json!({ "name": name, "x": x, "y": y, "radius": radius }))
+        .collect();
+    let map = serde_json::json!({ "format_version": 1, "name": name, "obstacles": [], "control_points": points });
+    let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+    std::fs::write(&path, map.to_string()).unwrap();
+    path
+}
+
+/// Creates a game in the control point mode on a map with one point, `hill`, captured in a second
+/// and giving a point per second.
+fn control_game(map_name: &str) -> GameLogic {
+    let mut logic = GameLogic::new();
+    logic.load_map(&control_map(map_name, &[("hill", 600.0, 500.0, 60.0)])).unwrap();
+    logic.game_config.game_mode = GameMode::ControlPoint;
+    logic.game_config.control_capture_secs = 1.0;
+    logic.game_config.control_points_per_sec = 1.0;
+    logic
+}
+
+/// Runs the game for whole simulated seconds.
+fn run_secs(logic: &mut GameLogic, secs: u32) {
+    for _ in 0..secs * logic.physics_engine.tick_rate() as u32 {
+        logic.step();
+    }
+}
+
+fn score_of(logic: &GameLogic, id: u32) -> i32 {
+    logic.entities.iter().find(|entity| entity.id == id).unwrap().score
+}
+
+#[test]
+fn control_points_score_for_the_side_inside() {
+    let mut logic = control_game("control_timeline");
+    let alpha = logic.add_entity_at("Alpha".to_string(), 200.0, 200.0, 0.0, false).unwrap();
+    let bravo = logic.add_entity_at("Bravo".to_string(), 1000.0, 800.0, 0.0, false).unwrap();
+    let hill = |logic: &GameLogic| (logic.control_points()[0].owner, logic.control_points()[0].progress);
+    run_secs(&mut logic, 1);
+    assert_eq!(hill(&logic), (None, 0.0), "no one entered the point");
+
+    // Déplacée par le jeu, l'entité compte avant que le capteur ne la voie
+    assert!(logic.teleport_entity(alpha, 600.0, 500.0));
+    logic.step();
+    assert_eq!(hill(&logic).0, Some(Side::Entity(alpha)));
+    run_secs(&mut logic, 1);
+    assert_eq!(hill(&logic), (Some(Side::Entity(alpha)), 1.0), "captured in a second");
+    assert_eq!(score_of(&logic, alpha), 0, "the capture itself earns nothing");
+    assert!(logic.events.since(0).any(|queued| matches!(&queued.event, GameEvent::Capture { point, owner } if point == "hill" && *owner == Side::Entity(alpha))));
+    run_secs(&mut logic, 2);
+    assert_eq!(score_of(&logic, alpha), 2, "a point per second while held");
+
+    // Contesté, le point ne bouge plus et ne rapporte rien
+    assert!(logic.teleport_entity(bravo, 620.0, 520.0));
+    run_secs(&mut logic, 2);
+    assert_eq!(hill(&logic), (Some(Side::Entity(alpha)), 1.0));
+    assert_eq!((score_of(&logic, alpha), score_of(&logic, bravo)), (2, 0));
+
+    // Seule, Bravo défait la prise d'Alpha en une seconde puis prend le point en une autre
+    assert!(logic.teleport_entity(alpha, 200.0, 200.0));
+    run_secs(&mut logic, 1);
+    assert_eq!(hill(&logic).0, Some(Side::Entity(alpha)), "the capture of Alpha is undone first");
+    assert!(hill(&logic).1 < 0.05);
+    run_secs(&mut logic, 1);
+    assert_eq!(hill(&logic), (Some(Side::Entity(bravo)), 1.0));
+    run_secs(&mut logic, 2);
+    assert_eq!((score_of(&logic, alpha), score_of(&logic, bravo)), (2, 2), "Alpha left the point");
+
+    // Une entité retirée de l'arène ne tient plus le point
+    logic.remove_entity_by_id(bravo);
+    run_secs(&mut logic, 1);
+    assert_eq!(hill(&logic), (Some(Side::Entity(bravo)), 1.0), "an empty point keeps its owner");
+    assert_eq!(score_of(&logic, alpha), 2);
+}
+
+#[test]
+fn the_larger_team_takes_the_point_and_the_round() {
+    let mut logic = control_game("control_teams");
+    logic.game_config.score_target = 3;
+    let red_one = logic.add_entity_at("Red1".to_string(), 590.0, 480.0, 0.0, false).unwrap();
+    let red_two = logic.add_entity_at("Red2".to_string(), 590.0, 520.0, 0.0, false).unwrap();
+    let blue = logic.add_entity_at("Blue".to_string(), 630.0, 500.0, 0.0, false).unwrap();
+    for (id, team) in [(red_one, 1), (red_two, 1), (blue, 2)] {
+        logic.get_entity_mut(id).unwrap().team = Some(team);
+    }
+
+    run_secs(&mut logic, 3);
+    let point = &logic.control_points()[0];
+    assert_eq!((point.owner, point.is_held()), (Some(Side::Team(1)), true));
+    // Chaque membre de l'équipe dans le point marque
+    assert_eq!([red_one, red_two, blue].map(|id| score_of(&logic, id)), [2, 2, 0]);
+
+    run_secs(&mut logic, 1);
+    assert_eq!(logic.match_state.phase, MatchPhase::Intermission, "the score target ends the round");
+    let reset_at = logic.tick;
+    while logic.match_state.phase == MatchPhase::Intermission && logic.tick < reset_at + 100_000 {
+        logic.step();
+    }
+    assert_eq!(logic.control_points()[0].owner, None, "a new round starts with a free point");
+}
+
+#[test]
+fn control_points_are_idle_in_deathmatch() {
+    let mut logic = control_game("control_idle");
+    logic.game_config.game_mode = GameMode::Deathmatch;
+    let alpha = logic.add_entity_at("Alpha".to_string(), 600.0, 500.0, 0.0, false).unwrap();
+    run_secs(&mut logic, 3);
+    assert_eq!(logic.control_points()[0].owner, None);
+    assert_eq!(score_of(&logic, alpha), 0);
+    assert!(logic.replay_frame().control_points.is_empty(), "the points are only drawn in their mode");
+}
+
+#[test]
+fn map_control_points_are_checked() {
+    let mut logic = GameLogic::new();
+    let duplicate = control_map("control_duplicate", &[("hill", 300.0, 300.0, 50.0), ("hill", 900.0, 300.0, 50.0)]);
+    let error = logic.load_map(&duplicate).unwrap_err().to_string();
+    assert!(error.contains("control point 2 reuses the name 'hill'"), "{}", error);
+    let flat = control_map("control_flat", &[("hill", 300.0, 300.0, 0.0)]);
+    assert!(logic.load_map(&flat).unwrap_err().to_string().contains("invalid radius"));
+    assert!(logic.control_points().is_empty(), "a wrong map leaves the arena untouched");
+}
+
+#[test]
+fn ghost_cameras_fly_through_the_arena_unseen() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    let bot = logic.add_entity("Player".to_string());
+    let id = logic.add_ghost_camera();
+    assert_ne!(id, bot, "a camera never takes the id of an entity");
+    let (x, y) = logic.arena().center();
+    logic.teleport_entity(bot, x + 100.0, y - 100.0);
+
+    // Le moteur gauche pousse selon x, le droit selon y, 0.5 immobile
+    let camera = logic.ghost_camera_mut(id).unwrap();
+    camera.motor_left = 1.0;
+    camera.motor_right = 0.0;
+    logic.step();
+    let dt = logic.physics_engine.integration_parameters.dt;
+    let camera = *logic.ghost_camera(id).unwrap();
+    assert!((camera.x - (x + AppDefines::GHOST_CAMERA_SPEED * dt)).abs() < 1e-3, "{:?}", camera);
+    assert!((camera.y - (y - AppDefines::GHOST_CAMERA_SPEED * dt)).abs() < 1e-3, "{:?}", camera);
+
+    // Elle traverse l'entité sans la pousser, puis s'arrête au bord de l'arène
+    for _ in 0..600 {
+        logic.step();
+    }
+    let camera = *logic.ghost_camera(id).unwrap();
+    assert_eq!((camera.x, camera.y), (logic.arena().width, 0.0));
+    let entity = logic.entities.iter().find(|entity| entity.id == bot).unwrap();
+    assert!((entity.x - (x + 100.0)).abs() < 1.0 && (entity.y - (y - 100.0)).abs() < 1.0, "the entity was pushed");
+    assert_eq!(logic.entities.len(), 1);
+    assert!(logic.events.since(0).all(|queued| !matches!(queued.event, GameEvent::Hit { .. })));
+
+    assert!(logic.remove_ghost_camera(id));
+    assert!(logic.ghost_cameras().is_empty());
+    assert!(!logic.remove_ghost_camera(id));
+}
+
+#[test]
+fn hits_remember_where_the_bullets_came_from() {
+    // Le canon tire vers l'arrière du corps : 0.5 tire devant le tireur
+    let bearing = |victim_angle: f32, gunner: (f32, f32), gun: f32| {
+        let mut logic = GameLogic::new();
+        logic.game_config.spawn_protection_ms = 0;
+        logic.game_config.starting_health = 1000;
+        let victim = logic.add_entity_at("Victim".to_string(), 500.0, 500.0, victim_angle, false).unwrap();
+        let id = logic.add_entity_at("Gunner".to_string(), gunner.0, gunner.1, 0.0, false).unwrap();
+        let turret = logic.get_entity_mut(id).unwrap().gun_mut();
+        turret.orientation = gun as f64;
+        turret.traverse = gun;
+        fire_once(&mut logic, id);
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+        let hits = &logic.get_entity_mut(victim).unwrap().recent_hits;
+        assert_eq!(hits.len(), 1, "{:?}", hits);
+        hits[0].bearing.to_degrees()
+    };
+    let close = |bearing: f32, expected: f32| ((bearing - expected + 540.0).rem_euclid(360.0) - 180.0).abs() < 1.0;
+
+    let front = bearing(0.0, (800.0, 500.0), 0.0);
+    assert!(close(front, 0.0), "{}", front);
+    let left = bearing(0.0, (500.0, 800.0), 0.25);
+    assert!(close(left, 90.0), "{}", left);
+    let behind = bearing(0.0, (200.0, 500.0), 0.5);
+    assert!(close(behind, 180.0), "{}", behind);
+    // Le relèvement suit le châssis de la victime
+    let turned = bearing(std::f32::consts::FRAC_PI_2, (500.0, 800.0), 0.25);
+    assert!(close(turned, 0.0), "{}", turned);
+
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity("Target".to_string());
+    let entity = logic.get_entity_mut(id).unwrap();
+    for tick in 1..=6 {
+        entity.record_hit(tick, 0.0, 1);
+    }
+    let kept: Vec<(u64, u64)> = entity.recent_hits.iter().map(|hit| (hit.seq, hit.tick)).collect();
+    assert_eq!(kept, [(3, 3), (4, 4), (5, 5), (6, 6)], "the last RECENT_HITS are kept");
+}
+
+/// Writes a map without obstacles holding hazards, each as the JSON of a `MapHazard`.
+fn hazard_map(name: &str, hazards: serde_json::Value) -> std::path::PathBuf {
+    let map = serde_json::json!({ "format_version": 1, "name": name, "obstacles": [], "hazards": hazards });
+    let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+    std::fs::write(&path, map.to_string()).unwrap();
+    path
+}
+
+fn health_of(logic: &GameLogic, id: u32) -> i32 {
+    logic.entities.iter().find(|entity| entity.id == id).unwrap().health
+}
+
+#[test]
+fn damage_zones_hurt_the_entities_inside() {
+    let mut logic = GameLogic::new();
+    let hazards = serde_json::json!([{ "x": 600, "y": 500, "shape": "circle", "radius": 60, "effect": "damage", "per_sec": 10 }]);
+    logic.load_map(&hazard_map("hazard_damage", hazards)).unwrap();
+    logic.game_config.starting_health = 100;
+    logic.game_config.spawn_protection_ms = 3000;
+    let inside = logic.add_entity_at("Inside".to_string(), 600.0, 500.0, 0.0, false).unwrap();
+    let outside = logic.add_entity_at("Outside".to_string(), 200.0, 200.0, 0.0, false).unwrap();
+
+    run_secs(&mut logic, 1);
+    assert_eq!(health_of(&logic, inside), 100, "the spawn protection blocks the hazards by default");
+    logic.game_config.hazards_ignore_spawn_protection = true;
+    run_secs(&mut logic, 2);
+    // Le capteur voit l'entité un pas après son arrivée
+    let health = health_of(&logic, inside);
+    assert!((80..=81).contains(&health), "10 per second for 2 seconds: {}", health);
+    assert_eq!(health_of(&logic, outside), 100);
+
+    assert!(logic.teleport_entity(inside, 200.0, 800.0));
+    run_secs(&mut logic, 1);
+    assert_eq!(health_of(&logic, inside), health, "leaving the zone stops the damage");
+
+    // Détruite par la zone, l'entité se détruit elle-même
+    assert!(logic.teleport_entity(outside, 600.0, 500.0));
+    logic.get_entity_mut(outside).unwrap().health = 1;
+    run_secs(&mut logic, 1);
+    assert!(logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Kill { shooter, victim, .. } if shooter == outside && victim == outside)));
+}
+
+#[test]
+fn armor_spares_hazard_damage_only_when_told_to() {
+    let mut logic = GameLogic::new();
+    let hazards = serde_json::json!([{ "x": 600, "y": 500, "shape": "circle", "radius": 60, "effect": "damage", "per_sec": 10 }]);
+    logic.load_map(&hazard_map("hazard_armor", hazards)).unwrap();
+    logic.game_config.starting_health = 100;
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.deathmatch_starting_armor = 0.5;
+    let inside = logic.add_entity_at("Inside".to_string(), 600.0, 500.0, 0.0, false).unwrap();
+
+    run_secs(&mut logic, 2);
+    let health = health_of(&logic, inside);
+    assert!((80..=81).contains(&health), "the armor ignores the hazards by default: {}", health);
+    let mitigated = |logic: &GameLogic| logic.entities.iter().find(|entity| entity.id == inside).unwrap().breakdown.damage_mitigated;
+    assert_eq!(mitigated(&logic), 0);
+    logic.game_config.armor_blocks_ramming_and_hazards = true;
+    run_secs(&mut logic, 2);
+    // Le taux est réduit de moitié, sans minimum d'un point par pas
+    let lost = health - health_of(&logic, inside);
+    assert!((9..=11).contains(&lost), "5 per second for 2 seconds: {}", lost);
+    assert!((9..=11).contains(&mitigated(&logic)), "the other half is counted as mitigated: {}", mitigated(&logic));
+}
+
+#[test]
+fn slow_fields_shorten_the_travel() {
+    let mut logic = GameLogic::new();
+    let hazards = serde_json::json!([{ "x": 400, "y": 300, "shape": "rect", "half_width": 300, "half_height": 60, "effect": "slow", "factor": 0.5 }]);
+    logic.load_map(&hazard_map("hazard_slow", hazards)).unwrap();
+    let slowed = logic.add_entity_at("Slowed".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let free = logic.add_entity_at("Free".to_string(), 200.0, 700.0, 0.0, false).unwrap();
+    for id in [slowed, free] {
+        let entity = logic.get_entity_mut(id).unwrap();
+        entity.motor_left = 1.0;
+        entity.motor_right = 1.0;
+    }
+
+    run_secs(&mut logic, 1);
+    let traveled = |logic: &GameLogic, id: u32| position_of(logic, id).0 - 200.0;
+    let (slowed_travel, free_travel) = (traveled(&logic, slowed), traveled(&logic, free));
+    assert!(free_travel > 90.0, "full speed outside: {}", free_travel);
+    assert!((0.45..0.55).contains(&(slowed_travel / free_travel)), "half speed inside: {} against {}", slowed_travel, free_travel);
+    assert_eq!(logic.replay_frame().hazards.len(), 1, "the fields are drawn");
+}
+
+#[test]
+fn map_hazards_are_checked_and_saved() {
+    let mut logic = GameLogic::new();
+    let faster = serde_json::json!([{ "x": 400, "y": 300, "shape": "circle", "radius": 50, "effect": "slow", "factor": 1.5 }]);
+    let error = logic.load_map(&hazard_map("hazard_faster", faster)).unwrap_err().to_string();
+    assert!(error.contains("hazard 1 has an invalid effect"), "{}", error);
+    let outside = serde_json::json!([{ "x": -40, "y": 300, "shape": "rect", "half_width": 50, "half_height": 50, "effect": "damage", "per_sec": 5 }]);
+    assert!(logic.load_map(&hazard_map("hazard_outside", outside)).unwrap_err().to_string().contains("outside the arena"));
+    assert!(logic.hazards().is_empty(), "a wrong map leaves the arena untouched");
+
+    let hazards = serde_json::json!([
+        { "x": 400, "y": 300, "shape": "circle", "radius": 50, "effect": "slow", "factor": 0.25 },
+        { "x": 800, "y": 600, "shape": "rect", "half_width": 80, "half_height": 40, "effect": "damage", "per_sec": 5 },
+    ]);
+    logic.load_map(&hazard_map("hazard_saved", hazards)).unwrap();
+    let saved = std::env::temp_dir().join(format!("hazard_resaved_{}.json", std::process::id()));
+    logic.save_map(&saved).unwrap();
+    let mut reloaded = GameLogic::new();
+    reloaded.load_map(&saved).unwrap();
+    let shapes: Vec<(&str, &str, f32)> = reloaded.hazards().iter().map(|hazard| (hazard.shape.name(), hazard.effect.name(), hazard.effect.strength())).collect();
+    assert_eq!(shapes, [("CIRCLE", "SLOW", 0.25), ("RECT", "DAMAGE", 5.0)]);
+    reloaded.generate_map(Some(7));
+    assert!(reloaded.hazards().is_empty(), "a generated map has no hazard");
+}
+
+#[test]
+fn teammates_pass_through_each_other_when_asked() {
+    // Une entité fonce vers une autre à l'arrêt devant elle
+    let race = |pass_through: bool, teams: (Option<u8>, Option<u8>)| {
+        let mut logic = GameLogic::new();
+        logic.game_config.teammates_pass_through = pass_through;
+        let runner = logic.add_entity_at("Runner".to_string(), 300.0, 500.0, 0.0, false).unwrap();
+        let other = logic.add_entity_at("Other".to_string(), 360.0, 500.0, 0.0, false).unwrap();
+        assert!(logic.set_team(runner, teams.0) && logic.set_team(other, teams.1));
+        let entity = logic.get_entity_mut(runner).unwrap();
+        entity.motor_left = 1.0;
+        entity.motor_right = 1.0;
+        run_secs(&mut logic, 1);
+        (position_of(&logic, runner).0, position_of(&logic, other).0)
+    };
+
+    let (runner, teammate) = race(true, (Some(1), Some(1)));
+    assert!(runner > teammate + 2.0 * AppDefines::ENTITY_HALF_SIZE, "the runner went through its teammate: {} and {}", runner, teammate);
+    let (runner, enemy) = race(true, (Some(1), Some(2)));
+    assert!(runner < enemy, "an enemy blocks: {} and {}", runner, enemy);
+    let (runner, loner) = race(true, (Some(1), None));
+    assert!(runner < loner, "an entity without a team blocks: {} and {}", runner, loner);
+    let (runner, teammate) = race(false, (Some(1), Some(1)));
+    assert!(runner < teammate, "teammates are solid by default: {} and {}", runner, teammate);
+}
+
+#[test]
+fn bullets_hit_the_teammates_they_pass_through() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.teammates_pass_through = true;
+    config.friendly_fire = true;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 10;
+    let shooter = logic.add_entity("Shooter".to_string());
+    face_left_wall(&mut logic, shooter, 300.0);
+    let teammate = logic.add_entity_at("Teammate".to_string(), AppDefines::ENTITY_HALF_SIZE + 150.0, 300.0, 0.0, false).unwrap();
+    logic.set_team(shooter, Some(1));
+    logic.set_team(teammate, Some(1));
+
+    // Le changement d'équipe s'applique au collider sans attendre le pas suivant
+    let entity = logic.entities.iter().find(|entity| entity.id == teammate).unwrap();
+    let collider = logic.physics_engine.bodies[entity.handle].colliders()[0];
+    assert_eq!(logic.physics_engine.colliders[collider].collision_groups(), entity.collision_groups(&logic.game_config));
+    assert_ne!(entity.collision_groups(&logic.game_config), InteractionGroups::all());
+
+    logic.step();
+    fire_once(&mut logic, shooter);
+    for _ in 0..60 {
+        logic.step();
+    }
+    assert!(logic.bullets.is_empty(), "the bullet is spent");
+    let hits: Vec<u32> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { victim, .. } => Some(victim),
+        _ => None,
+    }).collect();
+    assert_eq!(hits, [teammate], "the bullet hit the teammate in its way");
+}
+
+#[test]
+fn sequenced_actuators_apply_in_order_at_the_next_step() {
+    let mut logic = GameLogic::new();
+    let id = logic.add_entity("Bot".to_string());
+    let entity = logic.get_entity_mut(id).unwrap();
+    assert_eq!(entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, 0.9, Some(5)), Ok(()));
+    // Un paquet en retard ne défait pas une commande plus récente
+    assert_eq!(entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, 0.2, Some(3)), Err(5));
+    assert_eq!(entity.command_actuator(AppDefines::ACTUATOR_MOTOR_RIGHT, 0, 0.7, Some(4)), Ok(()));
+    assert_eq!(entity.motor_left, 0.5, "the command waits for the next step");
+    logic.step();
+    let entity = logic.get_entity_mut(id).unwrap();
+    assert_eq!((entity.motor_left, entity.motor_right), (0.9, 0.7));
+    assert_eq!(entity.pending_actuators.applied(), Some(5));
+
+    // Arrivées dans le désordre au cours d'un pas, les commandes s'appliquent dans l'ordre des numéros
+    entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, 0.3, Some(7)).unwrap();
+    assert_eq!(entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, 0.6, Some(6)), Err(7));
+    entity.command_actuator(AppDefines::ACTUATOR_GUN_TRAVERSE, 0, 0.25, Some(9)).unwrap();
+    entity.command_actuator(AppDefines::ACTUATOR_GUN_TRAVERSE, 0, 0.75, Some(8)).unwrap_err();
+    logic.step();
+    let entity = logic.get_entity_mut(id).unwrap();
+    assert_eq!((entity.motor_left, entity.gun().traverse), (0.3, 0.25));
+    assert_eq!(entity.pending_actuators.applied(), Some(9));
+
+    // Une commande sans numéro s'applique aussitôt et remplace celles en attente
+    entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, 0.1, Some(10)).unwrap();
+    entity.command_actuator(AppDefines::ACTUATOR_MOTOR_LEFT, 0, 0.8, None).unwrap();
+    assert_eq!(entity.motor_left, 0.8);
+    logic.step();
+    assert_eq!(logic.get_entity_mut(id).unwrap().motor_left, 0.8);
+}
+
+#[test]
+fn world_snapshots_follow_the_entities() {
+    let mut logic = GameLogic::new();
+    let world = logic.world_slot();
+    let id = logic.add_entity_at("Bot".to_string(), 300.0, 500.0, 0.0, false).unwrap();
+    // Publié dès l'ajout, sans attendre un pas
+    assert_eq!(world.latest().entity(id).map(|entity| (entity.x, entity.y)), Some((300.0, 500.0)));
+
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+    logic.step();
+    assert_eq!(world.latest().entity(id).unwrap().x, 300.0, "only the game loop publishes the steps");
+    logic.publish_world();
+    let snapshot = world.latest();
+    assert_eq!((snapshot.tick, snapshot.entity(id).unwrap().x), (logic.tick, position_of(&logic, id).0));
+
+    logic.remove_entity_by_id(id);
+    assert!(world.latest().entity(id).is_none());
+}
+
+/// Fires two bullets into a target with the bullet pool holding `pool_capacity` bodies at most.
+///
+/// Returns the hits, as shooter, victim and damage, the lifetime of each bullet in steps,
+/// and whether the second bullet took back the body of the first one.
+fn volley(pool_capacity: usize) -> (Vec<(u32, u32, i32)>, Vec<u64>, bool) {
+    let mut logic = GameLogic::new();
+    logic.physics_engine.set_bullet_pool_capacity(pool_capacity);
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 10;
+    let shooter = logic.add_entity("Shooter".to_string());
+    face_left_wall(&mut logic, shooter, 300.0);
+    logic.add_entity_at("Target".to_string(), AppDefines::ENTITY_HALF_SIZE + 150.0, 300.0, 0.0, false).unwrap();
+    logic.step();
+
+    let mut lifetimes = Vec::new();
+    let mut handles = Vec::new();
+    for _ in 0..2 {
+        fire_once(&mut logic, shooter);
+        let bullet = &logic.bullets[0];
+        lifetimes.push(bullet.expires_at - bullet.fired_at);
+        handles.push(bullet.handle);
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+        assert_eq!(logic.physics_engine.pooled_bullets(), pool_capacity.min(1));
+    }
+    if pool_capacity > 0 {
+        // Le corps attend désactivé, loin de l'arène
+        let body = &logic.physics_engine.bodies[handles[1]];
+        assert!(!body.is_enabled() && body.translation().x < -1000.0);
+    }
+    let hits = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { shooter, victim, damage, .. } => Some((shooter, victim, damage)),
+        _ => None,
+    }).collect();
+    (hits, lifetimes, handles[0] == handles[1])
+}
+
+#[test]
+fn pooled_bullets_fly_as_fresh_ones() {
+    let (pooled_hits, pooled_lifetimes, reused) = volley(AppDefines::BULLET_POOL_CAPACITY);
+    assert!(reused, "the second bullet takes the body of the first one");
+    let (fresh_hits, fresh_lifetimes, reused) = volley(0);
+    assert!(!reused);
+    assert_eq!(pooled_hits.len(), 2, "both bullets hit the target");
+    assert_eq!((pooled_hits, pooled_lifetimes), (fresh_hits, fresh_lifetimes));
+}
+
+/// Steps the horde until every bot of the current wave is in the arena, and returns their ids.
+fn spawn_wave(logic: &mut GameLogic) -> Vec<u32> {
+    let size = WaveDirector::wave_size(logic.wave_director.as_ref().unwrap().wave + 1) as usize;
+    // L'entracte et l'apparition échelonnée tiennent en quelques secondes simulées
+    for _ in 0..1000 {
+        let bots: Vec<u32> = logic.entities.iter().filter(|entity| entity.is_ai).map(|entity| entity.id).collect();
+        if bots.len() == size {
+            return bots;
+        }
+        logic.step();
+    }
+    panic!("the wave did not spawn");
+}
+
+fn wave_events(logic: &GameLogic) -> Vec<(u32, WaveState)> {
+    logic.events.since(0).filter_map(|queued| match &queued.event {
+        GameEvent::Wave { wave, state, .. } => Some((*wave, *state)),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn players_clearing_a_wave_win_the_round() {
+    let mut logic = GameLogic::new();
+    let player = logic.add_entity("Survivor".to_string());
+    logic.start_horde();
+    let bots = spawn_wave(&mut logic);
+    let teams: Vec<Option<u8>> = logic.entities.iter().map(|entity| entity.team).collect();
+    assert!(teams.iter().all(|team| team.is_some()));
+    assert_eq!(logic.entities.iter().find(|entity| entity.id == player).unwrap().team, Some(AppDefines::HORDE_PLAYER_TEAM));
+
+    // Le joueur scripté abat les robots un à un
+    for bot in bots {
+        assert!(logic.kill_entity(bot));
+        logic.step();
+    }
+
+    assert_eq!(wave_events(&logic), vec![(1, WaveState::InProgress), (1, WaveState::Intermission)]);
+    assert_eq!(logic.respawns_granted(), 1, "the players destroyed during the wave stay out");
+    assert_eq!(logic.match_state.phase, MatchPhase::Intermission);
+    let won = logic.events.since(0).any(|queued| matches!(&queued.event,
+        GameEvent::MatchEnd { reason, winners, .. } if reason == "wave 1 cleared" && winners == &["Survivor".to_string()]));
+    assert!(won, "the round was not won by the survivor");
+    let (_, totals) = logic.leaderboard.top(10).into_iter().find(|(name, _)| *name == "Survivor").unwrap();
+    assert_eq!(totals.waves_survived, 1);
+}
+
+#[test]
+fn the_horde_starts_over_when_every_player_dies() {
+    let mut logic = GameLogic::new();
+    let player = logic.add_entity("Victim".to_string());
+    logic.start_horde();
+    let bots = spawn_wave(&mut logic);
+    assert!(logic.kill_entity(player));
+    logic.step();
+
+    assert_eq!(wave_events(&logic), vec![(1, WaveState::InProgress), (1, WaveState::Lost)]);
+    let lost = logic.events.since(0).any(|queued| matches!(&queued.event,
+        GameEvent::MatchEnd { winners, score: None, .. } if winners.is_empty()));
+    assert!(lost, "the round did not end without winner");
+
+    // La manche suivante repart de la première vague, sans les robots restants
+    while logic.match_state.phase == MatchPhase::Intermission {
+        logic.step();
+    }
+    assert!(logic.entities.iter().all(|entity| !bots.contains(&entity.id)));
+    let director = logic.wave_director.as_ref().unwrap();
+    assert_eq!((director.wave, director.state), (0, WaveState::Intermission));
+}
+
+#[test]
+fn a_loaded_state_saves_back_to_the_same_file() {
+    let mut logic = GameLogic::new();
+    logic.generate_map(Some(1799));
+    logic.game_config.spawn_protection_ms = 0;
+    let player = logic.add_entity("Player".to_string());
+    logic.add_ai("Hunter".to_string(), Some("hunter"), AiDifficulty::Hard);
+    logic.add_ai("Camper".to_string(), Some("camper"), AiDifficulty::Easy);
+    let entity = logic.get_entity_mut(player).unwrap();
+    entity.motor_left = 0.8;
+    entity.motor_right = -0.3;
+    entity.score = 7;
+    for _ in 0..100 {
+        logic.update_ai();
+        logic.step();
+    }
+    fire_once(&mut logic, player);
+
+    let dir = std::env::temp_dir().join(format!("state_round_trip_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (first, second) = (dir.join("first.json"), dir.join("second.json"));
+    logic.save_to_file(&first).unwrap();
+    let mut loaded = GameLogic::new();
+    loaded.load_from_file(&first).unwrap();
+    loaded.save_to_file(&second).unwrap();
+    let (first, second) = (std::fs::read_to_string(&first).unwrap(), std::fs::read_to_string(&second).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(first.contains("\"Hunter\"") && first.contains("\"bullets\": [\n"), "the snapshot holds the bots and the bullet");
+    assert_eq!(second, first);
+}
+
+#[test]
+fn removed_entities_leave_their_id_behind() {
+    let mut logic = GameLogic::new();
+    let a = logic.add_entity("A".to_string());
+    let b = logic.add_entity("B".to_string());
+    logic.remove_entity_by_id(a);
+    let c = logic.add_entity("C".to_string());
+    assert!(c != a && c != b, "A={} B={} C={}", a, b, c);
+    logic.remove_entity_by_id(b);
+    logic.remove_entity_by_id(c);
+    let d = logic.add_entity("D".to_string());
+    assert!(![a, b, c].contains(&d), "the arena was empty, D={}", d);
+
+    // Une partie restaurée reprend après les identifiants qu'elle contient
+    let snapshot = logic.save_state();
+    let mut restored = GameLogic::new();
+    restored.load_state(snapshot);
+    assert!(restored.add_entity("E".to_string()) > d);
+}
+
+/// Adds an entity standing at a point of the arena with its gun aimed along the x axis.
+///
+/// # Returns
+/// The id of the entity.
+fn gunner(logic: &mut GameLogic, name: &str, x: f32, toward_positive_x: bool) -> u32 {
+    let id = logic.add_entity_at(name.to_string(), x, 300.0, 0.0, false).unwrap();
+    // Le canon vise l'angle du corps plus un demi-tour
+    let aim = if toward_positive_x { 0.5 } else { 0.0 };
+    let gun = logic.get_entity_mut(id).unwrap().gun_mut();
+    gun.traverse = aim;
+    gun.orientation = aim as f64;
+    id
+}
+
+/// The shooter and the victim of each hit or kill.
+type Pairs = Vec<(u32, u32)>;
+
+/// Returns the shooter and the victim of each hit, and of each kill.
+fn hits_and_kills(logic: &GameLogic) -> (Pairs, Pairs) {
+    let mut hits = Vec::new();
+    let mut kills = Vec::new();
+    for queued in logic.events.since(0) {
+        match queued.event {
+            GameEvent::Hit { shooter, victim, .. } => hits.push((shooter, victim)),
+            GameEvent::Kill { shooter, victim, .. } => kills.push((shooter, victim)),
+            _ => {}
+        }
+    }
+    (hits, kills)
+}
+
+#[test]
+fn hits_do_not_depend_on_the_order_of_the_entities() {
+    let duel = |victim_first: bool| {
+        let mut logic = GameLogic::new();
+        logic.game_config.spawn_protection_ms = 0;
+        logic.game_config.starting_health = 5;
+        logic.game_config.fire_cooldown_ms = 100;
+        let (shooter, victim) = if victim_first {
+            let victim = gunner(&mut logic, "Victim", 500.0, false);
+            (gunner(&mut logic, "Shooter", 200.0, true), victim)
+        } else {
+            let shooter = gunner(&mut logic, "Shooter", 200.0, true);
+            (shooter, gunner(&mut logic, "Victim", 500.0, false))
+        };
+        logic.get_entity_mut(shooter).unwrap().gun_mut().trigger = 1.0;
+        while shots(&logic).len() < 2 {
+            logic.step();
+        }
+        logic.get_entity_mut(shooter).unwrap().gun_mut().trigger = 0.0;
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+        let (hits, _) = hits_and_kills(&logic);
+        let health = logic.get_entity_mut(victim).unwrap().health;
+        let score = logic.get_entity_mut(shooter).unwrap().score;
+        (hits == [(shooter, victim); 2], health, score)
+    };
+    assert_eq!(duel(true), (true, 3, 2));
+    assert_eq!(duel(false), duel(true));
+}
+
+#[test]
+fn an_entity_destroyed_in_a_step_is_not_hit_again() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 1;
+    let left = gunner(&mut logic, "Left", 300.0, true);
+    let victim = gunner(&mut logic, "Victim", 600.0, true);
+    let right = gunner(&mut logic, "Right", 900.0, false);
+    // Les deux balles partent au même pas, à la même distance de la cible
+    for id in [left, right] {
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 1.0;
+    }
+    while shots(&logic).len() < 2 {
+        logic.step();
+    }
+    for id in [left, right] {
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 0.0;
+    }
+    while logic.entities.iter().any(|entity| entity.id == victim) {
+        logic.step();
+    }
+    let (hits, kills) = hits_and_kills(&logic);
+    assert_eq!((hits.len(), kills.len()), (1, 1), "hits {:?}, kills {:?}", hits, kills);
+    let points: i32 = logic.entities.iter().map(|entity| entity.score).sum();
+    assert_eq!(points, 1, "only the hit that destroyed it scores");
+}
+
+#[test]
+fn bullets_meeting_head_on_both_vanish() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    let left = gunner(&mut logic, "Left", 200.0, true);
+    let right = gunner(&mut logic, "Right", 1000.0, false);
+    for id in [left, right] {
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 1.0;
+    }
+    while shots(&logic).len() < 2 {
+        logic.step();
+    }
+    for id in [left, right] {
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 0.0;
+    }
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    for _ in 0..steps_per_second * 6 / 5 {
+        logic.step();
+    }
+    assert!(logic.bullets.is_empty(), "the bullets would still fly for 0.8 s");
+    assert_eq!(hits_and_kills(&logic), (Vec::new(), Vec::new()));
+}
+
+#[test]
+fn a_bullet_only_scores_for_the_entity_that_fired_it() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 5;
+    let shooter = gunner(&mut logic, "Shooter", 200.0, true);
+    let victim = gunner(&mut logic, "Victim", 600.0, false);
+    let heir = logic.add_entity_at("Heir".to_string(), 200.0, 800.0, 0.0, false).unwrap();
+    fire_once(&mut logic, shooter);
+
+    // Le tireur parti, son corps passe à une autre entité alors que la balle vole encore
+    let handle = logic.get_entity_mut(shooter).unwrap().handle;
+    logic.entities.retain(|entity| entity.id != shooter);
+    logic.get_entity_mut(heir).unwrap().handle = handle;
+    assert_eq!(logic.bullets[0].shooter, handle);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    assert_eq!(hits_and_kills(&logic), (Vec::new(), Vec::new()));
+    assert_eq!(logic.get_entity_mut(heir).unwrap().score, 0);
+    assert_eq!(logic.get_entity_mut(victim).unwrap().health, 5);
+}
+
+#[test]
+fn timelines_tell_the_first_blood_the_longest_life_and_the_damage() {
+    let path = std::env::temp_dir().join(format!("timeline_{}.jsonl", std::process::id()));
+    let mut writer = TimelineWriter::create(&path, TimelineMode::OnDemand, &ArenaConfig::default(), 50.0).unwrap();
+    let spawn = |id, name: &str| GameEvent::Spawn { id, name: name.to_string(), is_ai: false, x: 0.0, y: 0.0 };
+    let hit = |shooter, victim, damage| GameEvent::Hit { shooter, victim, damage, x: 0.0, y: 0.0, bearing: Some(0.0) };
+    let kill = |shooter, victim| GameEvent::Kill { shooter, victim, x: 0.0, y: 0.0 };
+    // Alice abat Bob au pas 40, puis Bob revenu sous l'id 3 abat Carol au pas 90
+    let script = [
+        (0, spawn(1, "Alice")),
+        (5, spawn(2, "Bob")),
+        (10, spawn(4, "Carol")),
+        (20, hit(1, 2, 30)),
+        (30, GameEvent::BulletFired { shooter: 2, x: 0.0, y: 0.0, angle: 0.0 }),
+        (40, hit(1, 2, 70)),
+        (40, kill(1, 2)),
+        (40, GameEvent::Despawn { id: 2 }),
+        (60, spawn(3, "Bob")),
+        (80, hit(3, 4, 100)),
+        (90, kill(3, 4)),
+        (90, GameEvent::Despawn { id: 4 }),
+        (150, GameEvent::RoundEnd),
+    ];
+    for (step, event) in &script {
+        writer.on_event(*step, event);
+    }
+    drop(writer);
+
+    let summary = analyze_timeline(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((summary.last_step, summary.kills), (150, 2));
+    let first_blood = summary.first_blood.as_ref().unwrap();
+    assert_eq!((first_blood.step, first_blood.shooter, first_blood.victim), (40, 1, 2));
+    // Alice vit du pas 0 à la fin ; Bob 35 pas, Carol 80, Bob revenu 90
+    let longest = summary.longest_life.as_ref().unwrap();
+    assert_eq!((longest.id, longest.name.as_str(), longest.steps), (1, "Alice", 150));
+    assert_eq!(summary.damage[&1], [(20, 30), (40, 100)], "the running total of Alice");
+    assert_eq!(summary.damage[&3], [(80, 100)]);
+    assert!(!summary.damage.contains_key(&2), "a shot that missed is no damage");
+    assert!(summary.to_string().contains("First blood: Alice on Bob at step 40"), "{}", summary);
+}
+
+#[test]
+fn cones_keep_the_points_inside_closest_first() {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+    let origin = (100.0, 100.0);
+    let points = [
+        (200.0, 100.0), // 0 : sur l'axe, à 100
+        (150.0, 150.0), // 1 : à 45°, à 70.7
+        (150.0, 140.0), // 2 : à 38.7°, à 64.0
+        (130.0, 70.0),  // 3 : à -45°, à 42.4
+        (100.0, 200.0), // 4 : à 90°
+        (50.0, 100.0),  // 5 : derrière
+        (400.0, 100.0), // 6 : sur l'axe, hors de portée
+    ];
+
+    let inside = GameLogic::in_cone(origin, 0.0, FRAC_PI_4 + 1e-4, 150.0, &points);
+    let indices: Vec<usize> = inside.iter().map(|&(index, _, _)| index).collect();
+    assert_eq!(indices, [3, 2, 1, 0], "closest first, bounds included");
+    let (_, distance, bearing) = inside[2];
+    assert!((distance - 50.0 * 2f32.sqrt()).abs() < 1e-3 && (bearing - FRAC_PI_4).abs() < 1e-4);
+    assert!((inside[0].2 + FRAC_PI_4).abs() < 1e-4, "clockwise bearings are negative");
+
+    // Portée exacte incluse, un rien au-delà exclue
+    assert_eq!(GameLogic::in_cone(origin, 0.0, 0.1, 100.0, &points[..1]).len(), 1);
+    assert!(GameLogic::in_cone(origin, 0.0, 0.1, 99.9, &points[..1]).is_empty());
+    assert!(GameLogic::in_cone(origin, 0.0, FRAC_PI_4 - 1e-3, 150.0, &points[1..2]).is_empty());
+    assert_eq!(GameLogic::in_cone(origin, FRAC_PI_2, 0.1, 150.0, &points).iter().map(|p| p.0).collect::<Vec<_>>(), [4]);
+}
+
+#[test]
+fn cones_pointing_at_pi_wrap_around() {
+    use std::f32::consts::PI;
+    let origin = (0.0, 0.0);
+    // Juste au-dessus et juste au-dessous de l'axe -x : des angles bruts de près de π et de près de -π
+    let points = [(-100.0, 5.0), (-100.0, -5.0), (100.0, 0.0), (-100.0, 60.0)];
+    for direction in [PI, -PI, 3.0 * PI] {
+        let inside = GameLogic::in_cone(origin, direction, 0.2, 200.0, &points);
+        let mut indices: Vec<usize> = inside.iter().map(|&(index, _, _)| index).collect();
+        indices.sort();
+        assert_eq!(indices, [0, 1], "cone at {}", direction);
+        for &(index, _, bearing) in &inside {
+            assert!((-PI..=PI).contains(&bearing), "bearing {} out of range", bearing);
+            // Vu depuis l'axe -x, le point du dessus est à droite : angle négatif
+            let expected = if index == 0 { -(5f32.atan2(100.0)) } else { 5f32.atan2(100.0) };
+            assert!((bearing - expected).abs() < 1e-4, "bearing {} for point {}", bearing, index);
+        }
+    }
+    // Un cône étroit autour de 179° garde le point vu à -179°
+    let inside = GameLogic::in_cone(origin, 179f32.to_radians(), 3f32.to_radians(), 200.0, &[(-100.0, -1.75)]);
+    assert_eq!(inside.len(), 1);
+}
+
+/// Shoots once from a gunner at a target of the given teams, and lets the bullet land.
+///
+/// Returns the game, the gunner and the target.
+fn shoot_between_teams(teams: (Option<u8>, Option<u8>), friendly_fire: bool, starting_health: i32) -> (GameLogic, u32, u32) {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = starting_health;
+    config.bullet_damage = 10;
+    config.points_per_hit = 2;
+    config.points_per_kill = 5;
+    config.friendly_hit_penalty = 3;
+    config.friendly_fire = friendly_fire;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    assert!(logic.set_team(gunner, teams.0) && logic.set_team(target, teams.1));
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    (logic, gunner, target)
+}
+
+#[test]
+fn teammates_are_hit_only_with_friendly_fire() {
+    let (mut logic, _, target) = shoot_between_teams((Some(1), Some(1)), false, 100);
+    assert_eq!(logic.get_entity_mut(target).unwrap().health, 100, "a teammate was hit without friendly fire");
+    assert!(!logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Hit { .. })));
+
+    let (mut logic, _, target) = shoot_between_teams((Some(1), Some(1)), true, 100);
+    assert_eq!(logic.get_entity_mut(target).unwrap().health, 90, "friendly fire spared a teammate");
+}
+
+#[test]
+fn team_hits_cost_the_shooter_points_with_friendly_fire() {
+    // (équipes, tir ami) -> (santé de la cible, score du tireur)
+    let cases = [
+        ((Some(1), Some(2)), false, (90, 2)),
+        ((Some(1), Some(2)), true, (90, 2)),
+        ((None, None), false, (90, 2)),
+        ((Some(1), Some(1)), false, (100, 0)),
+        ((Some(1), Some(1)), true, (90, -3)),
+    ];
+    for (teams, friendly_fire, expected) in cases {
+        let (mut logic, gunner, target) = shoot_between_teams(teams, friendly_fire, 100);
+        let health = logic.get_entity_mut(target).unwrap().health;
+        let score = logic.get_entity_mut(gunner).unwrap().score;
+        assert_eq!((health, score), expected, "teams {:?}, friendly fire {}", teams, friendly_fire);
+    }
+
+    // Détruire un coéquipier ne rapporte pas les points d'une destruction
+    let (mut logic, gunner, _) = shoot_between_teams((Some(1), Some(1)), true, 10);
+    assert!(logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Kill { .. })), "the teammate survived");
+    assert_eq!(logic.get_entity_mut(gunner).unwrap().score, -3);
+    let (mut logic, gunner, _) = shoot_between_teams((Some(1), Some(2)), true, 10);
+    assert_eq!(logic.get_entity_mut(gunner).unwrap().score, 2 + 5);
+}
+
+#[test]
+fn actuator_values_are_clamped_and_non_finite_ones_refused() {
+    // Les valeurs sont lues comme le protocole les lit
+    let cases = [
+        ("0", Some(0.0)),
+        ("1", Some(1.0)),
+        ("1.0001", Some(1.0)),
+        ("9999", Some(1.0)),
+        ("-0.25", Some(0.0)),
+        ("5e-1", Some(0.5)),
+        ("1e3", Some(1.0)),
+        ("0.3", Some(0.3)),
+        ("NaN", None),
+        ("inf", None),
+        ("-infinity", None),
+    ];
+    for (text, expected) in cases {
+        let value: f32 = text.parse().unwrap();
+        assert_eq!(Entity::clamp_actuator(value), expected, "for {}", text);
+    }
+}
+
+#[test]
+fn configured_health_takes_the_configured_hits() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 5;
+    config.bullet_damage = 1;
+    config.points_per_hit = 2;
+    config.points_per_kill = 10;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    let killed = |logic: &GameLogic| logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Kill { victim, .. } if victim == target));
+
+    for hit in 1..=4 {
+        fire_once(&mut logic, gunner);
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+        assert_eq!(logic.get_entity_mut(target).unwrap().health, 5 - hit, "after hit {}", hit);
+        assert_eq!(logic.get_entity_mut(gunner).unwrap().score, 2 * hit);
+        assert!(!killed(&logic), "the target died on hit {}", hit);
+    }
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    assert!(killed(&logic), "the target survived its fifth hit");
+    assert_eq!(logic.get_entity_mut(gunner).unwrap().score, 5 * 2 + 10);
+    let damage: Vec<i32> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { damage, .. } => Some(damage),
+        _ => None,
+    }).collect();
+    assert_eq!(damage, [1; 5]);
+}
+
+#[test]
+fn held_triggers_fire_at_the_cooldown_rate() {
+    for cooldown_ms in [750, 250] {
+        let mut logic = GameLogic::new();
+        logic.game_config.spawn_protection_ms = 0;
+        logic.game_config.fire_cooldown_ms = cooldown_ms;
+        let id = logic.add_entity("Gunner".to_string());
+        // Contre un mur, les balles disparaissent aussitôt et ne remplissent pas le chargeur
+        face_left_wall(&mut logic, id, 20.0);
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 1.0;
+        let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+        for _ in 0..10 * steps_per_second {
+            logic.step();
+        }
+
+        let expected = 10_000.0 / cooldown_ms as f32;
+        let fired = shots(&logic).len() as f32;
+        assert!((fired - expected).abs() <= 1.0, "{} shots in 10 s with a {} ms cooldown, {} expected", fired, cooldown_ms, expected);
+    }
+}
+
+#[test]
+fn obstacles_stop_the_bullets() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let index = logic.add_obstacle_at(400.0, 300.0, ObstacleShape::Square { half: 20.0 }).unwrap();
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+
+    let mut farthest = 0.0f32;
+    for _ in 0..120 {
+        if let Some(bullet) = logic.bullets.first() {
+            farthest = farthest.max(logic.physics_engine.bodies[bullet.handle].translation().x);
+        }
+        logic.step();
+    }
+    assert!(logic.bullets.is_empty(), "the bullet went through the obstacle");
+    assert!(farthest < 380.0, "the bullet was seen at {}, inside the obstacle", farthest);
+    assert_eq!(logic.get_entity_mut(gunner).unwrap().score, 0);
+    assert!(logic.remove_obstacle(index).is_some());
+
+    // Une balle qui apparaît dans un obstacle disparaît au pas suivant
+    fire_once(&mut logic, gunner);
+    let position = *logic.physics_engine.bodies[logic.bullets[0].handle].translation();
+    logic.add_obstacle_at(position.x, position.y, ObstacleShape::Circle { radius: 15.0 }).unwrap();
+    logic.step();
+    assert!(logic.bullets.is_empty(), "the bullet inside the obstacle was not removed");
+}
+
+/// Drops a power-up on an entity and lets it be picked up.
+fn drop_power_up(logic: &mut GameLogic, id: u32, kind: PowerUpKind) {
+    let entity = logic.get_entity_mut(id).unwrap();
+    let position = (entity.x as f64, entity.y as f64);
+    let power_up = PowerUp::new(kind, position, &mut logic.physics_engine);
+    logic.power_ups.push(power_up);
+    logic.step();
+}
+
+#[test]
+fn power_ups_are_consumed_and_their_effects_expire() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 10;
+    logic.game_config.fire_cooldown_ms = 800;
+    let id = logic.add_entity_at("Picker".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    logic.step();
+
+    // Le soin ne dépasse pas la santé de départ
+    logic.get_entity_mut(id).unwrap().health = 9;
+    drop_power_up(&mut logic, id, PowerUpKind::Heal);
+    assert!(logic.power_ups.is_empty(), "the heal was not consumed");
+    assert_eq!(logic.get_entity_mut(id).unwrap().health, 10);
+    let picked: Vec<String> = logic.events.since(0).filter_map(|queued| match &queued.event {
+        GameEvent::PickUp { id: picker, power_up, .. } if *picker == id => Some(power_up.clone()),
+        _ => None,
+    }).collect();
+    assert_eq!(picked.len(), 1, "one pickup per power-up: {:?}", picked);
+
+    drop_power_up(&mut logic, id, PowerUpKind::RapidFire);
+    let picked_at = logic.tick;
+    let entity = logic.get_entity_mut(id).unwrap();
+    assert_eq!(entity.fire_cooldown, Duration::from_millis(400));
+    let until = entity.rapid_fire_until.expect("rapid fire did not start");
+    let duration = (AppDefines::POWER_UP_DURATION_SECS / logic.physics_engine.integration_parameters.dt).round() as u64;
+    assert!(until.abs_diff(picked_at + duration) <= 1, "rapid fire ends at {} instead of about {}", until, picked_at + duration);
+
+    while logic.tick + 1 < until {
+        logic.step();
+    }
+    assert!(logic.get_entity_mut(id).unwrap().rapid_fire_until.is_some(), "rapid fire ended early");
+    logic.step();
+    logic.step();
+    let entity = logic.get_entity_mut(id).unwrap();
+    assert_eq!((entity.rapid_fire_until, entity.fire_cooldown), (None, Duration::from_millis(800)));
+}
+
+#[test]
+fn headless_loops_step_sixty_times_a_second() {
+    let game_logic = Arc::new(Mutex::new(GameLogic::new()));
+    let start = Instant::now();
+    let mut game_loop = GameLoop::headless(Arc::clone(&game_logic), 60.0, start);
+    // Des réveils irréguliers, de 3 à 23 ms, sur une seconde
+    let jitter = [3, 17, 9, 23, 5, 14, 21, 8, 11, 19];
+    let (mut now, mut steps, mut elapsed) = (start, 0, 0);
+    for wait in jitter.iter().cycle() {
+        if elapsed + wait > 1000 {
+            break;
+        }
+        elapsed += wait;
+        now += Duration::from_millis(*wait);
+        steps += game_loop.advance(now);
+    }
+    now += Duration::from_millis(1000 - elapsed);
+    steps += game_loop.advance(now);
+
+    assert!((59..=61).contains(&steps), "{} steps in one second", steps);
+    let logic = game_logic.lock().unwrap();
+    assert_eq!(logic.tick, steps as u64);
+    assert!((logic.achieved_tick_rate - 60.0).abs() <= 1.0, "measured {} Hz", logic.achieved_tick_rate);
+}
+
+#[test]
+fn bullets_survive_a_pause() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.bullet_speed = 100.0;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 100.0, 500.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    let fired_at = logic.tick;
+    let game_logic = Arc::new(Mutex::new(logic));
+    let start = Instant::now();
+    let mut game_loop = GameLoop::headless(Arc::clone(&game_logic), 60.0, start);
+    let mut now = start;
+    let mut run = |game_loop: &mut GameLoop, millis: u64| {
+        for _ in 0..millis / 10 {
+            now += Duration::from_millis(10);
+            game_loop.advance(now);
+        }
+    };
+
+    run(&mut game_loop, 1000);
+    game_logic.lock().unwrap().pause();
+    let paused_at = game_logic.lock().unwrap().tick;
+    run(&mut game_loop, 5000);
+    assert_eq!(game_logic.lock().unwrap().tick, paused_at, "the simulation stepped while paused");
+    game_logic.lock().unwrap().resume();
+    run(&mut game_loop, 800);
+    assert_eq!(game_logic.lock().unwrap().bullets.len(), 1, "the bullet did not survive the pause");
+
+    // Elle disparaît au bout de sa durée de vie simulée, la pause exclue
+    while !game_logic.lock().unwrap().bullets.is_empty() {
+        run(&mut game_loop, 10);
+    }
+    let lived = game_logic.lock().unwrap().tick - fired_at;
+    let lifetime = AppDefines::DEFAULT_BULLET_LIFETIME_MS * 60 / 1000;
+    assert!(lived.abs_diff(lifetime) <= 1, "the bullet lived {} steps instead of {}", lived, lifetime);
+}
+
+#[test]
+fn timed_rounds_go_through_the_intermission_to_the_next_round() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.match_duration_secs = 5;
+    let leader = logic.add_entity("Leader".to_string());
+    let other = logic.add_entity("Other".to_string());
+    logic.get_entity_mut(leader).unwrap().score = 3;
+    logic.get_entity_mut(other).unwrap().score = 1;
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as u64;
+
+    while logic.match_state.phase == MatchPhase::Running {
+        logic.step();
+    }
+    assert!(logic.tick.abs_diff(5 * steps_per_second) <= 1, "the 5 s round ended at step {}", logic.tick);
+    let ended_at = logic.tick;
+    let ends: Vec<(u32, Vec<String>, Option<i32>)> = logic.events.since(0).filter_map(|queued| match &queued.event {
+        GameEvent::MatchEnd { round, winners, score, .. } => Some((*round, winners.clone(), *score)),
+        _ => None,
+    }).collect();
+    assert_eq!(ends, vec![(1, vec!["Leader".to_string()], Some(3))]);
+
+    // Pendant l'entracte, les moteurs ne font rien
+    let entity = logic.get_entity_mut(other).unwrap();
+    (entity.motor_left, entity.motor_right) = (1.0, 1.0);
+    let position = (entity.x, entity.y);
+    for _ in 0..steps_per_second {
+        logic.step();
+    }
+    let entity = logic.get_entity_mut(other).unwrap();
+    assert_eq!((entity.x, entity.y), position, "an entity moved during the intermission");
+
+    while logic.match_state.phase == MatchPhase::Intermission {
+        logic.step();
+    }
+    let intermission = (AppDefines::MATCH_INTERMISSION_SECS * steps_per_second as f32) as u64;
+    assert!((logic.tick - ended_at).abs_diff(intermission) <= 1, "the intermission lasted {} steps", logic.tick - ended_at);
+    assert_eq!((logic.match_state.round, logic.match_state.phase), (2, MatchPhase::Running));
+    assert!(logic.entities.iter().all(|entity| entity.score == 0), "the scores were not reset for the next round");
+}
+
+#[test]
+fn a_fatal_shot_is_told_in_order() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 1;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    for _ in 0..10 {
+        logic.step();
+    }
+
+    let events: Vec<(&str, u32)> = logic.events.since(0).filter_map(|queued| {
+        let id = match queued.event {
+            GameEvent::Spawn { id, .. } | GameEvent::Despawn { id } => id,
+            GameEvent::BulletFired { shooter, .. } => shooter,
+            GameEvent::Hit { victim, .. } | GameEvent::Kill { victim, .. } => victim,
+            _ => return None,
+        };
+        Some((queued.event.kind(), id))
+    }).collect();
+    assert_eq!(events, [("spawn", gunner), ("spawn", target), ("bullet_fired", gunner), ("hit", target), ("kill", target), ("despawn", target)]);
+}
+
+#[test]
+fn recorded_replays_load_back_frame_for_frame() {
+    let mut logic = GameLogic::new();
+    logic.generate_map(Some(1800));
+    logic.game_config.spawn_protection_ms = 0;
+    let walker = logic.add_entity("Walker".to_string());
+    let entity = logic.get_entity_mut(walker).unwrap();
+    (entity.motor_left, entity.motor_right) = (1.0, 0.7);
+    logic.start_recording();
+    // Les positions en direct, échantillonnées toutes les 10 images
+    let mut sampled = Vec::new();
+    for step in 0..100 {
+        if step == 30 {
+            logic.add_entity("Joiner".to_string());
+        }
+        logic.step();
+        if step % 10 == 0 {
+            let positions: Vec<(u32, f32, f32)> = logic.entities.iter().map(|entity| (entity.id, entity.x, entity.y)).collect();
+            sampled.push((step, logic.tick, positions));
+        }
+    }
+    let path = std::env::temp_dir().join(format!("hundred_ticks_{}.jsonl", std::process::id()));
+    logic.stop_recording().unwrap().save(&path).unwrap();
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(replay.frames.len(), 100);
+    assert!(replay.frames.windows(2).all(|pair| pair[1].tick == pair[0].tick + 1), "the frames are not consecutive steps");
+    for (step, tick, positions) in sampled {
+        let frame = &replay.frames[step];
+        assert_eq!(frame.tick, tick);
+        let recorded: Vec<(u32, f32, f32)> = frame.entities.iter().map(|entity| (entity.id, entity.x, entity.y)).collect();
+        assert_eq!(recorded, positions, "frame {}", step);
+    }
+    assert!(replay.frames[0].obstacles.is_some(), "the first frame has no obstacles");
+    assert!(replay.frames[1..].iter().all(|frame| frame.obstacles.is_none()), "unchanged obstacles were stored again");
+}
+
+#[test]
+fn leaderboards_add_up_across_saves() {
+    let path = std::env::temp_dir().join(format!("leaderboard_{}.json", std::process::id()));
+    let temporary = path.with_extension("json.tmp");
+    let _ = std::fs::remove_file(&path);
+    // Un fichier temporaire resté d'un arrêt brutal est simplement remplacé
+    std::fs::write(&temporary, "not json").unwrap();
+
+    let mut leaderboard = Leaderboard::load(&path).unwrap();
+    assert!(leaderboard.top(10).is_empty(), "a missing file gives an empty leaderboard");
+    leaderboard.record_breakdown("Ace", &ScoreBreakdown { shots_fired: 4, hits: 2, kills: 1, ..ScoreBreakdown::default() });
+    leaderboard.record_match(&[("Ace".to_string(), 7), ("  ".to_string(), 3)]);
+    leaderboard.save().unwrap();
+    assert!(!temporary.exists(), "the temporary file is renamed over the leaderboard");
+
+    // Chargement, nouvelle partie, nouvelle sauvegarde
+    let mut reloaded = Leaderboard::load(&path).unwrap();
+    reloaded.record_breakdown("Ace", &ScoreBreakdown { shots_fired: 1, deaths: 1, ..ScoreBreakdown::default() });
+    reloaded.record_match(&[("Rookie".to_string(), 2)]);
+    reloaded.save().unwrap();
+    assert!(!temporary.exists());
+
+    let totals: Vec<(String, PlayerTotals)> = Leaderboard::load(&path).unwrap().top(10).into_iter()
+        .map(|(name, totals)| (name.to_string(), totals.clone()))
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(totals, vec![
+        ("Ace".to_string(), PlayerTotals { kills: 1, deaths: 1, matches_played: 2, score: 7, shots_fired: 5, hits: 2, waves_survived: 0 }),
+        ("Rookie".to_string(), PlayerTotals { matches_played: 1, score: 2, ..PlayerTotals::default() }),
+    ]);
+}
+
+#[test]
+fn head_on_rams_cost_each_entity_health_once() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 100;
+    let left = logic.add_entity_at("Left".to_string(), 400.0, 300.0, 0.0, false).unwrap();
+    let right = logic.add_entity_at("Right".to_string(), 520.0, 300.0, std::f32::consts::PI, false).unwrap();
+    for id in [left, right] {
+        let entity = logic.get_entity_mut(id).unwrap();
+        entity.motor_left = 1.0;
+        entity.motor_right = 1.0;
+    }
+    let starting_health = health_of(&logic, left);
+
+    // Les deux entités continuent de pousser bien après le choc
+    run_secs(&mut logic, 3);
+    let hits: Vec<(u32, u32, i32)> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { shooter, victim, damage, .. } => Some((shooter, victim, damage)),
+        _ => None,
+    }).collect();
+    assert_eq!(hits.len(), 2, "one hit each: {:?}", hits);
+    assert!(hits.iter().any(|&(shooter, victim, _)| (shooter, victim) == (right, left)), "{:?}", hits);
+    assert!(hits.iter().any(|&(shooter, victim, _)| (shooter, victim) == (left, right)), "{:?}", hits);
+    // Vitesses égales et opposées : le choc est partagé en deux parts égales
+    assert_eq!(hits[0].2, hits[1].2);
+    for (_, victim, damage) in hits {
+        assert_eq!(health_of(&logic, victim), starting_health - damage);
+    }
+}
+
+#[test]
+fn guns_swing_at_the_traverse_rate_the_short_way() {
+    let mut logic = GameLogic::new();
+    logic.game_config.gun_traverse_rate = std::f32::consts::PI;
+    let id = logic.add_entity_at("Gunner".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    let dt = logic.physics_engine.integration_parameters.dt as f64;
+    let turns_per_step = std::f64::consts::PI * dt / std::f64::consts::TAU;
+    // Écart le plus court entre deux orientations, en tours
+    let apart = |a: f64, b: f64| ((a - b + 0.5).rem_euclid(1.0) - 0.5).abs();
+    let orientation = |logic: &GameLogic| logic.entities.iter().find(|entity| entity.id == id).unwrap().gun().orientation;
+
+    // Un demi-tour prend une seconde, puis le canon reste sur sa consigne
+    let gun = logic.get_entity_mut(id).unwrap().gun_mut();
+    gun.orientation = 0.0;
+    gun.traverse = 0.5;
+    for step in 1..=70 {
+        logic.step();
+        let expected = (step as f64 * turns_per_step).min(0.5);
+        assert!((apart(orientation(&logic), 0.0) - expected).abs() < 1e-6, "step {}: {}", step, orientation(&logic));
+    }
+
+    // De 0.9 à 0.1, le canon passe par 0 au lieu de faire les quatre cinquièmes du tour
+    let gun = logic.get_entity_mut(id).unwrap().gun_mut();
+    gun.orientation = 0.9;
+    gun.traverse = 0.1;
+    for step in 1..=30 {
+        logic.step();
+        let expected = (0.9 + (step as f64 * turns_per_step).min(0.2)).rem_euclid(1.0);
+        assert!(apart(orientation(&logic), expected) < 1e-6, "step {}: {}", step, orientation(&logic));
+        assert!((0.0..1.0).contains(&orientation(&logic)));
+    }
+    assert!(apart(orientation(&logic), 0.1) < 1e-6);
+}
+
+#[test]
+fn moving_shooters_spread_their_shots_uniformly() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.base_spread = 0.02;
+    config.spread_per_speed = 0.001;
+    config.spread_per_angular_speed = 0.0;
+    config.shot_energy_cost = 0.0;
+    config.max_bullets_per_entity = 0;
+    let id = logic.add_entity_at("Runner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.fire_cooldown = Duration::ZERO;
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+    let gun = entity.gun_mut();
+    gun.orientation = 0.5;
+    gun.traverse = 0.5;
+    gun.trigger = 1.0;
+
+    // À pleine vitesse, 100 unités par seconde élargissent la dispersion de 0.1 radian
+    let spread = 0.02 + 0.001 * 100.0;
+    let mut deviations = Vec::new();
+    while deviations.len() < 1000 {
+        // Ramené en arrière avant d'atteindre le mur d'en face
+        if position_of(&logic, id).0 > 900.0 {
+            logic.teleport_entity(id, 200.0, 300.0);
+        }
+        let cursor = logic.events.cursor();
+        logic.step();
+        deviations.extend(logic.events.since(cursor).filter_map(|queued| match queued.event {
+            // La visée est l'angle du corps, nul, à un tour près
+            GameEvent::BulletFired { angle, .. } => Some((angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI),
+            _ => None,
+        }));
+    }
+
+    let deviations = &deviations[..1000];
+    let mean = deviations.iter().sum::<f32>() / 1000.0;
+    let variance = deviations.iter().map(|deviation| (deviation - mean).powi(2)).sum::<f32>() / 1000.0;
+    let (low, high) = deviations.iter().fold((f32::MAX, f32::MIN), |(low, high), &deviation| (low.min(deviation), high.max(deviation)));
+    assert!(low >= -spread - 1e-4 && high <= spread + 1e-4, "within the spread: {} to {}", low, high);
+    assert!(low < -0.9 * spread && high > 0.9 * spread, "the whole spread is used: {} to {}", low, high);
+    assert!(mean.abs() < 0.01, "centered on the aim: {}", mean);
+    // Loi uniforme sur [-s, s] : variance de s² / 3
+    let expected = spread * spread / 3.0;
+    assert!((variance - expected).abs() < 0.1 * expected, "variance {} against {}", variance, expected);
+}
+
+#[test]
+fn ricochets_mirror_the_direction_off_the_wall() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.bullet_bounces = 1;
+    config.base_spread = 0.0;
+    let id = logic.add_entity_at("Gunner".to_string(), 300.0, 500.0, 0.0, false).unwrap();
+    // Une orientation nulle vise l'arrière du corps : 0.875 tour vise à 135°, vers le mur de gauche
+    let gun = logic.get_entity_mut(id).unwrap().gun_mut();
+    gun.orientation = 0.875;
+    gun.traverse = 0.875;
+    fire_once(&mut logic, id);
+
+    let velocity = |logic: &GameLogic| *logic.physics_engine.bodies[logic.bullets[0].handle].linvel();
+    let incoming = velocity(&logic);
+    assert!((incoming.y.atan2(incoming.x) - 3.0 * std::f32::consts::FRAC_PI_4).abs() < 1e-3, "fired at 135°: {:?}", incoming);
+    let mut steps = 0;
+    while velocity(&logic).x < 0.0 {
+        logic.step();
+        steps += 1;
+        assert!(steps < 120, "the bullet never reached the wall");
+    }
+
+    // Le mur renvoie la composante horizontale, la balle repart à 45° sans perdre de vitesse
+    let outgoing = velocity(&logic);
+    assert_eq!(logic.bullets[0].bounces_remaining, 0);
+    assert!((outgoing.y.atan2(outgoing.x) - std::f32::consts::FRAC_PI_4).abs() < 1e-2, "leaves at 45°: {:?}", outgoing);
+    assert!((outgoing.norm() - incoming.norm()).abs() < 1e-2 * incoming.norm(), "{} against {}", outgoing.norm(), incoming.norm());
+}
+
+#[test]
+fn mines_arm_late_spare_their_owner_and_fade_with_distance() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 100;
+    config.spawn_protection_ms = 0;
+    config.ramming_enabled = false;
+    config.mine_damage = 20;
+    config.mine_blast_radius = 80.0;
+    let owner = logic.add_entity_at("Owner".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    let other = logic.add_entity_at("Other".to_string(), 700.0, 700.0, 0.0, false).unwrap();
+    let bystander = logic.add_entity_at("Bystander".to_string(), 300.0, 360.0, 0.0, false).unwrap();
+    let far = logic.add_entity_at("Far".to_string(), 300.0, 400.0, 0.0, false).unwrap();
+    logic.step();
+    logic.deploy_mine(owner).unwrap();
+    let detonated = |logic: &GameLogic| logic.events.since(0).any(|queued| matches!(queued.event, GameEvent::Detonation { .. }));
+
+    // Avant l'armement, une autre entité traverse la mine sans la déclencher
+    let entering = 300.0 + AppDefines::ENTITY_HALF_SIZE + AppDefines::MINE_RADIUS / 2.0;
+    logic.teleport_entity(other, entering, 300.0);
+    for _ in 0..10 {
+        logic.step();
+    }
+    assert!(!detonated(&logic), "the mine is not armed yet");
+    logic.teleport_entity(other, 700.0, 700.0);
+
+    // Armée, la mine ignore son poseur resté dessus
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    for _ in 0..steps_per_second {
+        logic.step();
+    }
+    assert!(logic.mines[0].is_armed(logic.sim_time()));
+    assert!(!detonated(&logic), "the owner never sets off its own mine");
+
+    logic.teleport_entity(other, entering, 300.0);
+    for _ in 0..5 {
+        logic.step();
+    }
+    assert!(detonated(&logic));
+    assert!(logic.mines.is_empty());
+    // Pleins dégâts au centre, rien au bord du souffle
+    let hits: Vec<(u32, i32, f32)> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Hit { victim, damage, x, y, .. } => Some((victim, damage, (x - 300.0).hypot(y - 300.0))),
+        _ => None,
+    }).collect();
+    for &(victim, damage, distance) in &hits {
+        assert_eq!(damage, (20.0 * (1.0 - distance / 80.0)).ceil() as i32, "entity {} at {}", victim, distance);
+        assert_eq!(health_of(&logic, victim), 100 - damage);
+    }
+    let damage_of = |id: u32| hits.iter().find(|hit| hit.0 == id).map(|hit| hit.1);
+    assert_eq!(damage_of(owner), Some(20), "the owner stands on the mine");
+    assert_eq!(damage_of(bystander), Some(5), "a quarter of the damage three quarters out");
+    assert!(damage_of(other).unwrap() > damage_of(bystander).unwrap());
+    assert_eq!(damage_of(far), None, "out of the blast");
+    assert_eq!(health_of(&logic, far), 100);
+}
+
+#[test]
+fn spawn_protection_ignores_hits_until_it_expires() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 100;
+    config.spawn_protection_ms = 2000;
+    config.bullet_damage = 10;
+    config.points_per_hit = 2;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    // Seule la cible est protégée, le tireur peut tirer tout de suite
+    let entity = logic.get_entity_mut(gunner).unwrap();
+    entity.spawn_protected_until = Duration::ZERO;
+    let gun = entity.gun_mut();
+    gun.traverse = 0.5;
+    gun.orientation = 0.5;
+    let protected = |logic: &GameLogic| logic.entities.iter().find(|entity| entity.id == target).unwrap().is_spawn_protected(logic.sim_time());
+    let hits = |logic: &GameLogic| logic.events.since(0).filter(|queued| matches!(queued.event, GameEvent::Hit { .. })).count();
+
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    assert!(protected(&logic), "the bullet arrived during the protection");
+    assert_eq!(hits(&logic), 0);
+    assert_eq!(health_of(&logic, target), 100);
+    assert_eq!(score_of(&logic, gunner), 0);
+
+    while protected(&logic) {
+        logic.step();
+    }
+    fire_once(&mut logic, gunner);
+    while !logic.bullets.is_empty() {
+        logic.step();
+    }
+    assert_eq!(hits(&logic), 1, "the first hit after the protection counts");
+    assert_eq!(health_of(&logic, target), 90);
+    assert_eq!(score_of(&logic, gunner), 2);
+}
+
+#[test]
+fn enormous_velocities_stay_within_the_arena() {
+    let mut logic = GameLogic::new();
+    logic.game_config.ramming_enabled = false;
+    let launches = [(1e6, 0.0), (-1e6, 0.0), (0.0, 1e6), (7e5, -7e5)];
+    let ids: Vec<u32> = (0..launches.len())
+        .map(|i| logic.add_entity_at(format!("Runner{}", i), 300.0 + 200.0 * i as f32, 500.0, 0.0, false).unwrap())
+        .collect();
+    let ArenaConfig { width, height } = logic.arena();
+
+    // Les moteurs réécrivent la vitesse à chaque pas : le moteur physique est lancé directement
+    for step in 0..600 {
+        for (&id, &(vx, vy)) in ids.iter().zip(&launches) {
+            let handle = logic.get_entity_mut(id).unwrap().handle;
+            logic.physics_engine.bodies[handle].set_linvel(vector![vx, vy], true);
+        }
+        logic.physics_engine.step(&());
+        // Ce qui a traversé un coin est ramené par le pas de jeu
+        logic.step();
+        for &id in &ids {
+            let (x, y) = position_of(&logic, id);
+            assert!((0.0..=width).contains(&x) && (0.0..=height).contains(&y), "step {}: entity {} at ({}, {})", step, id, x, y);
+        }
+    }
+
+    // Sortie de l'arène malgré tout, l'entité est ramenée au bord et arrêtée
+    let cursor = logic.events.cursor();
+    let handle = logic.get_entity_mut(ids[0]).unwrap().handle;
+    logic.physics_engine.bodies[handle].set_translation(vector![-500.0, 2000.0], true);
+    logic.step();
+    let margin = AppDefines::ENTITY_HALF_SIZE;
+    let (x, y) = position_of(&logic, ids[0]);
+    assert!((x - margin).abs() < 1.0 && (y - (height - margin)).abs() < 1.0, "brought back to ({}, {})", x, y);
+    let brought_back: Vec<u32> = logic.events.since(cursor).filter_map(|queued| match queued.event {
+        GameEvent::OutOfBounds { id, .. } => Some(id),
+        _ => None,
+    }).collect();
+    assert_eq!(brought_back, vec![ids[0]]);
+}
+
+/// Returns what makes a bullet fly and bounce: its radius, restitution, friction, speed and collision detection.
+fn bullet_physics(logic: &GameLogic, handle: RigidBodyHandle) -> (f32, f32, f32, f32, bool) {
+    let body = &logic.physics_engine.bodies[handle];
+    let collider = &logic.physics_engine.colliders[body.colliders()[0]];
+    let radius = collider.shape().as_ball().expect("bullets are balls").radius;
+    (radius, collider.restitution(), collider.friction(), body.linvel().norm(), body.is_ccd_enabled())
+}
+
+#[test]
+fn bots_fire_player_bullets_at_the_cooldown_rate() {
+    for difficulty in [AiDifficulty::Normal, AiDifficulty::Hard] {
+        let mut logic = GameLogic::new();
+        let config = &mut logic.game_config;
+        config.starting_health = 1000;
+        config.spawn_protection_ms = 0;
+        config.ramming_enabled = false;
+        config.bot_aim_error_deg = 0.0;
+        logic.set_default_behavior("hunter").unwrap();
+        let player = logic.add_entity_at("Player".to_string(), 700.0, 500.0, 0.0, false).unwrap();
+        let bot = logic.add_ai_at("Bot".to_string(), 400.0, 500.0, 0.0, difficulty).unwrap();
+
+        // Le joueur tire une balle vers le haut, loin du bot, pour comparer
+        let gun = logic.get_entity_mut(player).unwrap().gun_mut();
+        gun.orientation = 0.25;
+        gun.traverse = 0.25;
+        fire_once(&mut logic, player);
+        let player_bullet = bullet_physics(&logic, logic.bullets[0].handle);
+
+        let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as u64;
+        let mut bot_bullets = Vec::new();
+        for _ in 0..5 * steps_per_second {
+            logic.update_ai();
+            logic.step();
+            // Les corps des balles sont recyclés : seule compte la balle tirée pendant ce pas
+            let fired = logic.bullets.iter().filter(|bullet| bullet.shooter_id == Some(bot) && bullet.fired_at + 1 == logic.tick);
+            bot_bullets.extend(fired.map(|bullet| bullet_physics(&logic, bullet.handle)));
+        }
+        assert!(bot_bullets.len() > 3, "{:?}: the bot fired {} bullets", difficulty, bot_bullets.len());
+        for physics in &bot_bullets {
+            assert_eq!(*physics, player_bullet, "{:?}: the bot bullets are player bullets", difficulty);
+        }
+
+        // Tant que la cible reste en vue, les tirs se suivent à la cadence de la recharge
+        let cooldown = logic.entities.iter().find(|entity| entity.id == bot).unwrap().fire_cooldown;
+        assert_eq!(cooldown, Duration::from_millis(logic.game_config.fire_cooldown_ms).mul_f32(difficulty.params().fire_cooldown_factor));
+        let cooldown_steps = (cooldown.as_secs_f64() * steps_per_second as f64 - 1e-6).ceil() as u64;
+        let steps: Vec<u64> = logic.events.since(0).filter_map(|queued| match queued.event {
+            GameEvent::BulletFired { shooter, .. } if shooter == bot => Some(queued.step),
+            _ => None,
+        }).collect();
+        let intervals: Vec<u64> = steps.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(intervals.iter().all(|&interval| interval >= cooldown_steps), "{:?}: {:?}", difficulty, intervals);
+        assert_eq!(intervals.iter().min(), Some(&cooldown_steps), "{:?}: {:?}", difficulty, intervals);
+    }
+}
+
+#[test]
+fn bots_find_their_way_around_a_wall() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    // La cible survit aux tirs du bot une fois le mur contourné
+    config.starting_health = 10000;
+    config.spawn_protection_ms = 0;
+    config.ramming_enabled = false;
+    logic.set_default_behavior("hunter").unwrap();
+    // Un mur vertical de 400 unités entre le bot et sa cible
+    logic.add_obstacle_at(600.0, 500.0, ObstacleShape::Wall { half_length: 200.0, half_thickness: 10.0, angle: std::f32::consts::FRAC_PI_2 }).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 900.0, 500.0, 0.0, false).unwrap();
+    let bot = logic.add_ai_at("Bot".to_string(), 300.0, 500.0, 0.0, AiDifficulty::Normal).unwrap();
+    let distance = |logic: &GameLogic| {
+        let ((bot_x, bot_y), (target_x, target_y)) = (position_of(logic, bot), position_of(logic, target));
+        (target_x - bot_x).hypot(target_y - bot_y)
+    };
+
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    let mut steps = 0;
+    while position_of(&logic, bot).0 < 620.0 {
+        logic.update_ai();
+        logic.step();
+        steps += 1;
+        assert!(steps < 30 * steps_per_second, "the bot is stuck at {:?}", position_of(&logic, bot));
+    }
+    // Passé le mur, le bot va droit sur sa cible
+    for _ in 0..5 * steps_per_second {
+        logic.update_ai();
+        logic.step();
+    }
+    assert!(distance(&logic) < 300.0, "the bot closes in on its target: {}", distance(&logic));
+}
+
+/// Lets the bots decide and the game step for a number of seconds.
+fn drive_bots(logic: &mut GameLogic, secs: u32) {
+    for _ in 0..secs * logic.physics_engine.tick_rate() as u32 {
+        logic.update_ai();
+        logic.step();
+    }
+}
+
+/// Returns the steps at which an entity fired.
+fn fired_steps(logic: &GameLogic, id: u32) -> Vec<u64> {
+    logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::BulletFired { shooter, .. } if shooter == id => Some(queued.step),
+        _ => None,
+    }).collect()
+}
+
+/// Creates a game where nothing dies, with an AI entity driven by a behavior.
+fn behavior_arena(behavior: &str, x: f32, y: f32) -> (GameLogic, u32) {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.starting_health = 10000;
+    config.spawn_protection_ms = 0;
+    config.ramming_enabled = false;
+    logic.set_default_behavior(behavior).unwrap();
+    let bot = logic.add_ai_at("Bot".to_string(), x, y, 0.0, AiDifficulty::Normal).unwrap();
+    (logic, bot)
+}
+
+#[test]
+fn wanderers_roam_between_destinations() {
+    let (mut logic, bot) = behavior_arena("wanderer", 600.0, 500.0);
+    let mut destinations: Vec<(f32, f32)> = Vec::new();
+    let mut traveled = 0.0;
+    for _ in 0..8 {
+        let before = position_of(&logic, bot);
+        drive_bots(&mut logic, 1);
+        let after = position_of(&logic, bot);
+        traveled += (after.0 - before.0).hypot(after.1 - before.1);
+        let entity = logic.entities.iter().find(|entity| entity.id == bot).unwrap();
+        if destinations.last() != Some(&(entity.target_x, entity.target_y)) {
+            destinations.push((entity.target_x, entity.target_y));
+        }
+    }
+    assert!(destinations.len() >= 3, "a new destination every few seconds: {:?}", destinations);
+    assert!(traveled > 200.0, "the wanderer keeps moving: {}", traveled);
+    assert!(fired_steps(&logic, bot).is_empty(), "nothing to fire at");
+}
+
+#[test]
+fn hunters_close_in_and_hold_at_a_standoff() {
+    let (mut logic, bot) = behavior_arena("hunter", 200.0, 500.0);
+    let target = logic.add_entity_at("Target".to_string(), 800.0, 500.0, 0.0, false).unwrap();
+    drive_bots(&mut logic, 10);
+    let ((bot_x, bot_y), (target_x, target_y)) = (position_of(&logic, bot), position_of(&logic, target));
+    let distance = (target_x - bot_x).hypot(target_y - bot_y);
+    assert!((100.0..250.0).contains(&distance), "the hunter holds near its 150 units standoff: {}", distance);
+    assert!(fired_steps(&logic, bot).len() > 5, "and fires at its target");
+}
+
+#[test]
+fn campers_hold_beside_an_obstacle_sweeping_their_gun() {
+    let (mut logic, bot) = behavior_arena("camper", 300.0, 500.0);
+    logic.add_obstacle_at(500.0, 500.0, ObstacleShape::Square { half: 30.0 }).unwrap();
+    drive_bots(&mut logic, 6);
+    // Le poste est à deux demi-tailles d'entité du bord gauche de l'obstacle
+    let entity = logic.entities.iter().find(|entity| entity.id == bot).unwrap();
+    assert_eq!((entity.target_x, entity.target_y), (470.0 - 2.0 * AppDefines::ENTITY_HALF_SIZE, 500.0));
+    let post = position_of(&logic, bot);
+    assert!((post.0 - entity.target_x).hypot(post.1 - entity.target_y) < 25.0, "at its post: {:?}", post);
+
+    let mut sweep = Vec::new();
+    for _ in 0..4 {
+        drive_bots(&mut logic, 1);
+        sweep.push(logic.entities.iter().find(|entity| entity.id == bot).unwrap().gun().traverse);
+    }
+    let moved = position_of(&logic, bot);
+    assert!((moved.0 - post.0).hypot(moved.1 - post.1) < 5.0, "the camper holds its post: {:?} then {:?}", post, moved);
+    // Un dixième de tour par seconde
+    for pair in sweep.windows(2) {
+        assert!(((pair[1] - pair[0]).rem_euclid(1.0) - 0.1).abs() < 0.01, "{:?}", sweep);
+    }
+    assert!(fired_steps(&logic, bot).is_empty(), "nothing in sight");
+}
+
+#[test]
+fn defensive_bots_back_away_and_fire_sparingly() {
+    let (mut logic, bot) = behavior_arena("defensive", 500.0, 500.0);
+    let target = logic.add_entity_at("Target".to_string(), 650.0, 500.0, 0.0, false).unwrap();
+    drive_bots(&mut logic, 10);
+    let ((bot_x, bot_y), (target_x, target_y)) = (position_of(&logic, bot), position_of(&logic, target));
+    let distance = (target_x - bot_x).hypot(target_y - bot_y);
+    assert!(distance > 250.0, "the bot backs away toward 300 units: {}", distance);
+
+    let shots = fired_steps(&logic, bot);
+    assert!(!shots.is_empty(), "it fires while the target is within 400 units");
+    let steps_per_second = logic.physics_engine.tick_rate() as u64;
+    for pair in shots.windows(2) {
+        assert!(pair[1] - pair[0] >= steps_per_second * 3 / 2, "at most one shot every 1.5 s: {:?}", shots);
+    }
+}
+
+#[test]
+fn the_zone_shrinks_on_schedule_and_kills_on_a_known_step() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.zone_enabled = true;
+    config.zone_delay_secs = 1.0;
+    config.zone_shrink_secs = 2.0;
+    config.zone_final_radius = 100.0;
+    config.zone_damage_per_sec = 6.0;
+    config.starting_health = 10;
+    config.spawn_protection_ms = 0;
+    // Le match continue sans l'entité sacrifiée
+    logic.add_entity_at("Center".to_string(), 600.0, 500.0, 0.0, false).unwrap();
+    let corner = logic.add_entity_at("Corner".to_string(), 50.0, 50.0, 0.0, false).unwrap();
+
+    // Au départ, le cercle passe par les coins de l'arène de 1200 par 1000
+    let start = 600.0f32.hypot(500.0);
+    let schedule = [(0, start, 1.0, 3.0), (60, start, 0.0, 2.0), (120, (start + 100.0) / 2.0, 0.0, 1.0), (180, 100.0, 0.0, 0.0), (240, 100.0, 0.0, 0.0)];
+    let mut deaths = Vec::new();
+    for (tick, radius, starts_in, ends_in) in schedule {
+        while logic.tick < tick {
+            let cursor = logic.events.cursor();
+            logic.step();
+            deaths.extend(logic.events.since(cursor).filter(|queued| matches!(queued.event, GameEvent::Kill { victim, .. } if victim == corner)).map(|queued| queued.step));
+        }
+        let zone = logic.zone().expect("the zone is enabled");
+        assert!((zone.radius - radius).abs() < 0.01, "tick {}: radius {} instead of {}", tick, zone.radius, radius);
+        assert!((zone.shrink_starts_in.as_secs_f32() - starts_in).abs() < 1e-3, "tick {}: {:?}", tick, zone.shrink_starts_in);
+        assert!((zone.shrink_ends_in.as_secs_f32() - ends_in).abs() < 1e-3, "tick {}: {:?}", tick, zone.shrink_ends_in);
+    }
+
+    // Le coin, à 710.6 du centre, sort de la zone au pas 73 : 1.2 s après le départ le rayon vaut encore 712.9.
+    // À 6 points par seconde, il perd un point tous les 10 pas et son dixième point au 100e pas dehors.
+    assert_eq!(deaths, vec![73 + 99]);
+    assert!(logic.entities.iter().all(|entity| entity.id != corner));
+}
+
+#[test]
+fn scripted_shots_add_up_to_the_score_rules() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.starting_health = 30;
+    config.bullet_damage = 10;
+    config.points_per_hit = 2;
+    config.points_per_kill = 5;
+    config.death_penalty = 4;
+    logic.score_rules.comeback_enabled = true;
+    logic.score_rules.comeback_ratio = 0.5;
+    let gunner = logic.add_entity_at("Gunner".to_string(), 200.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 500.0, 300.0, 0.0, false).unwrap();
+    logic.get_entity_mut(target).unwrap().score = 20;
+    let shoot = |logic: &mut GameLogic, orientation: f64| {
+        let gun = logic.get_entity_mut(gunner).unwrap().gun_mut();
+        gun.orientation = orientation;
+        gun.traverse = orientation as f32;
+        fire_once(logic, gunner);
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+    };
+
+    // Deux tirs dans le mur de gauche, puis trois sur la cible
+    shoot(&mut logic, 0.0);
+    shoot(&mut logic, 0.0);
+    shoot(&mut logic, 0.5);
+    shoot(&mut logic, 0.5);
+    assert_eq!(score_of(&logic, gunner), 2 * 2);
+    assert_eq!(health_of(&logic, target), 10);
+    shoot(&mut logic, 0.5);
+
+    // Le coup fatal : 2 par touche, 5 par destruction et la moitié des 16 points d'écart avant ce coup
+    assert_eq!(score_of(&logic, gunner), 2 * 2 + 2 + 5 + (20 - 4) / 2);
+    let entity = logic.entities.iter().find(|entity| entity.id == gunner).unwrap();
+    assert_eq!(entity.breakdown, ScoreBreakdown { shots_fired: 5, hits: 3, kills: 1, ..ScoreBreakdown::default() });
+    assert_eq!(entity.breakdown.accuracy(), Some(0.6));
+    let bonuses: Vec<(u32, i32)> = logic.events.since(0).filter_map(|queued| match queued.event {
+        GameEvent::Bonus { id, points, reason: BonusReason::Comeback } => Some((id, points)),
+        _ => None,
+    }).collect();
+    assert_eq!(bonuses, vec![(gunner, 8)]);
+
+    // La cible partie, sa mort est comptée au classement ; son score, pénalité de mort comprise, part avec elle
+    logic.leaderboard.record_match(&[]);
+    let (_, totals) = logic.leaderboard.top(10).into_iter().find(|(name, _)| *name == "Target").unwrap();
+    assert_eq!((totals.deaths, totals.score), (1, 0));
+}
+
+#[test]
+fn obstacle_colliders_and_outlines_follow_their_shape() {
+    let mut logic = GameLogic::new();
+    let mut names = std::collections::HashSet::new();
+    for seed in 0..8 {
+        logic.generate_map(Some(seed));
+        for obstacle in &logic.obstacles {
+            names.insert(obstacle.shape.name());
+            let body = &logic.physics_engine.bodies[obstacle.body_handle];
+            assert_eq!(body.colliders().len(), 1);
+            let collider = &logic.physics_engine.colliders[body.colliders()[0]];
+            let center = collider.translation();
+            assert!((center.x as f64 - obstacle.position.0).abs() < 1e-3 && (center.y as f64 - obstacle.position.1).abs() < 1e-3);
+            assert!((collider.rotation().angle() - obstacle.shape.angle()).abs() < 1e-5, "{:?}", obstacle.shape);
+            match obstacle.shape {
+                ObstacleShape::Circle { radius } => assert_eq!(collider.shape().as_ball().map(|ball| ball.radius), Some(radius)),
+                ObstacleShape::Square { .. } | ObstacleShape::Wall { .. } => {
+                    let (half_width, half_height) = obstacle.shape.half_extents();
+                    assert_eq!(collider.shape().as_cuboid().map(|cuboid| cuboid.half_extents), Some(vector![half_width, half_height]));
+                }
+            }
+
+            // Le contour est fermé et chacun de ses points est sur le bord de la forme
+            let outline = obstacle.shape.outline(obstacle.position);
+            let expected = if matches!(obstacle.shape, ObstacleShape::Circle { .. }) { 33 } else { 5 };
+            assert_eq!(outline.len(), expected, "{:?}", obstacle.shape);
+            assert_eq!(outline.first(), outline.last());
+            for point in &outline {
+                let point = vector![point[0] as f32, point[1] as f32];
+                let distance = collider.shape().distance_to_local_point(&collider.position().inverse_transform_point(&point.into()), false);
+                assert!(distance.abs() < 1e-2, "{:?} at {:?}: {}", obstacle.shape, point, distance);
+            }
+        }
+    }
+    assert_eq!(names.len(), 3, "every shape is drawn: {:?}", names);
+}
+
+#[test]
+fn held_triggers_never_exceed_the_bullet_cap() {
+    for replace_oldest in [false, true] {
+        let mut logic = GameLogic::new();
+        let config = &mut logic.game_config;
+        config.spawn_protection_ms = 0;
+        config.shot_energy_cost = 0.0;
+        config.max_bullets_per_entity = 3;
+        config.replace_oldest_bullet = replace_oldest;
+        let id = logic.add_entity_at("Gunner".to_string(), 100.0, 500.0, 0.0, false).unwrap();
+        let entity = logic.get_entity_mut(id).unwrap();
+        // Assez espacées pour que les balles ne se percutent pas entre elles
+        entity.fire_cooldown = Duration::from_millis(100);
+        // Vers le mur d'en face, à plus de 1000 unités
+        let gun = entity.gun_mut();
+        gun.orientation = 0.5;
+        gun.traverse = 0.5;
+        gun.trigger = 1.0;
+        let live = |logic: &GameLogic| {
+            let counted = logic.entities.iter().find(|entity| entity.id == id).unwrap().live_bullets;
+            assert_eq!(counted as usize, logic.bullets.iter().filter(|bullet| bullet.shooter_id == Some(id)).count());
+            counted
+        };
+
+        let mut peak = 0;
+        for step in 0..60 {
+            logic.step();
+            assert!(live(&logic) <= 3, "replace {}: step {}: {} bullets", replace_oldest, step, live(&logic));
+            peak = peak.max(live(&logic));
+        }
+        assert_eq!(peak, 3);
+        let fired = shots(&logic).len();
+        if replace_oldest {
+            assert!(fired > 6, "the oldest bullet makes room for each new one: {} shots", fired);
+        } else {
+            assert_eq!(fired, 3, "refused at the cap");
+        }
+
+        // Trigger relâché, les balles finissent dans le mur et la réserve se reconstitue
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 0.0;
+        while !logic.bullets.is_empty() {
+            logic.step();
+        }
+        assert_eq!(live(&logic), 0);
+        logic.get_entity_mut(id).unwrap().gun_mut().trigger = 1.0;
+        let mut steps = 0;
+        while shots(&logic).len() < fired + 3 {
+            logic.step();
+            steps += 1;
+            assert!(steps < 30, "replace {}: the gun fires again", replace_oldest);
+        }
+        assert_eq!(live(&logic), 3, "replace {}", replace_oldest);
+    }
+}
+
+#[test]
+fn bullet_lifetimes_count_steps_not_wall_clock_time() {
+    let mut logic = GameLogic::new();
+    logic.game_config.bullet_lifetime_ms = 500;
+    logic.game_config.spawn_protection_ms = 0;
+    let id = logic.add_entity_at("Gunner".to_string(), 100.0, 500.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(id).unwrap().gun_mut();
+    gun.orientation = 0.5;
+    gun.traverse = 0.5;
+    fire_once(&mut logic, id);
+    let position = |logic: &GameLogic| *logic.physics_engine.bodies[logic.bullets[0].handle].translation();
+    let start = position(&logic);
+
+    // Chaque pas attend 40 ms : la balle vit 1.2 s d'horloge mais 500 ms de simulation
+    let steps_per_second = (1.0 / logic.physics_engine.integration_parameters.dt).round() as usize;
+    let lifetime_steps = steps_per_second / 2;
+    let wall_clock = Instant::now();
+    for step in 1..lifetime_steps {
+        std::thread::sleep(Duration::from_millis(40));
+        logic.step();
+        assert_eq!(logic.bullets.len(), 1, "step {}: the bullet expired early", step);
+    }
+    let end = position(&logic);
+    logic.step();
+    assert!(logic.bullets.is_empty(), "the bullet expires after 500 ms of simulation");
+    assert!(wall_clock.elapsed() > Duration::from_millis(1000));
+    // À 500 unités par seconde, un pas de moins que la durée de vie
+    let traveled = (end - start).norm();
+    let expected = logic.game_config.bullet_speed * (lifetime_steps - 1) as f32 / steps_per_second as f32;
+    assert!((traveled - expected).abs() < 1.0, "{} against {}", traveled, expected);
+}
+
+#[test]
+fn entity_mirrors_follow_their_bodies_every_step() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 10000;
+    let driver = logic.add_entity_at("Driver".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    logic.add_ai_at("Wanderer".to_string(), 800.0, 600.0, 1.0, AiDifficulty::Normal).unwrap();
+    let entity = logic.get_entity_mut(driver).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 0.7;
+    let check = |logic: &GameLogic, tick: u64| {
+        for entity in &logic.entities {
+            let body = &logic.physics_engine.bodies[entity.handle];
+            assert_eq!((entity.x, entity.y), (body.translation().x, body.translation().y), "tick {}: {}", tick, entity.name);
+            assert_eq!(entity.self_orientation, body.rotation().angle() as f64, "tick {}: {}", tick, entity.name);
+            assert_eq!((entity.vx, entity.vy), (body.linvel().x, body.linvel().y), "tick {}: {}", tick, entity.name);
+        }
+    };
+
+    for tick in 0..300 {
+        logic.update_ai();
+        logic.step();
+        check(&logic, tick);
+        // Un déplacement à la main met le miroir à jour sans attendre le pas
+        if tick == 150 {
+            logic.teleport_entity(driver, 600.0, 200.0);
+            check(&logic, tick);
+            assert_eq!(position_of(&logic, driver), (600.0, 200.0));
+        }
+    }
+    let entity = logic.entities.iter().find(|entity| entity.id == driver).unwrap();
+    assert!(entity.vx.hypot(entity.vy) > 50.0, "the driver is moving");
+}
+
+/// Returns every field of an entity a new entity starts with, the id, name, color and body left out.
+fn fresh_fields(entity: &Entity) -> String {
+    format!(
+        "score {} breakdown {:?} ai {} live {} cooldown {:?} weapon {:?} protected {:?} at ({}, {}) {} moving ({}, {}) turrets {:?} \
+         target ({}, {}) motors ({}, {}) shield {} energy {} fire energy {} health {} armor {} recent {:?} hits {} team {:?} \
+         boost {:?} rapid {:?} commands {} pending {:?} keyboard {}",
+        entity.score, entity.breakdown, entity.is_ai, entity.live_bullets, entity.fire_cooldown, entity.weapon, entity.spawn_protected_until,
+        entity.x, entity.y, entity.self_orientation, entity.vx, entity.vy, entity.turrets, entity.target_x, entity.target_y,
+        entity.motor_left, entity.motor_right, entity.shield, entity.energy, entity.fire_energy, entity.health, entity.armor,
+        entity.recent_hits, entity.hits_taken, entity.team, entity.speed_boost_until, entity.rapid_fire_until,
+        entity.commands_this_step, entity.pending_actuators, entity.keyboard_controlled,
+    )
+}
+
+#[test]
+fn reset_entities_match_new_ones_but_their_identity() {
+    let mut logic = GameLogic::new();
+    logic.game_config.starting_health = 50;
+    logic.game_config.spawn_protection_ms = 1500;
+    let id = logic.add_entity_at("Messy".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    run_secs(&mut logic, 2);
+    drop_power_up(&mut logic, id, PowerUpKind::RapidFire);
+    drop_power_up(&mut logic, id, PowerUpKind::SpeedBoost);
+    drop_power_up(&mut logic, id, PowerUpKind::ArmorUp);
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.score = 17;
+    entity.breakdown.shots_fired = 4;
+    entity.health = 3;
+    entity.motor_left = 1.0;
+    entity.motor_right = 0.0;
+    entity.shield = 1.0;
+    entity.energy = 2.0;
+    entity.fire_energy = 0.0;
+    entity.target_x = 900.0;
+    entity.recent_hits.push_back(HitRecord { seq: 1, tick: 5, bearing: 0.5, damage: 3 });
+    let gun = entity.gun_mut();
+    gun.trigger = 1.0;
+    gun.traverse = 0.1;
+    gun.orientation = 0.2;
+    logic.step();
+    assert!(logic.entities[0].rapid_fire_until.is_some() && logic.entities[0].speed_boost_until.is_some());
+
+    logic.reset_simulation();
+    let (x, y) = position_of(&logic, id);
+    // Une entité neuve au même endroit, au même instant
+    let fresh = logic.add_entity_at("Fresh".to_string(), x, y, 0.0, false).unwrap();
+    let messy = logic.entities.iter().find(|entity| entity.id == id).unwrap();
+    let fresh = logic.entities.iter().find(|entity| entity.id == fresh).unwrap();
+    assert_eq!(fresh_fields(messy), fresh_fields(fresh));
+}
+
+#[test]
+fn small_arenas_keep_spawns_bullets_and_entities_inside() {
+    let mut logic = GameLogic::with_arena(ArenaConfig::new(300.0, 200.0).unwrap());
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.ramming_enabled = false;
+    logic.generate_map(Some(1826));
+    let inside = |x: f32, y: f32, margin: f32| (margin..=300.0 - margin).contains(&x) && (margin..=200.0 - margin).contains(&y);
+    for obstacle in &logic.obstacles {
+        assert!(inside(obstacle.position.0 as f32, obstacle.position.1 as f32, 0.0), "obstacle at {:?}", obstacle.position);
+    }
+    let ids: Vec<u32> = (0..6).map(|i| logic.add_entity(format!("Player{}", i))).collect();
+    for &id in &ids {
+        let (x, y) = position_of(&logic, id);
+        assert!(inside(x, y, AppDefines::ENTITY_HALF_SIZE), "spawned at ({}, {})", x, y);
+    }
+
+    // Les murs arrêtent une entité lancée vers la droite, seule dans l'arène vidée
+    let runner = ids[0];
+    for &id in &ids[1..] {
+        logic.remove_entity_by_id(id);
+    }
+    while !logic.obstacles.is_empty() {
+        logic.remove_obstacle(0);
+    }
+    logic.teleport_entity(runner, 150.0, 100.0);
+    let entity = logic.get_entity_mut(runner).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+    for _ in 0..180 {
+        logic.step();
+        let (x, y) = position_of(&logic, runner);
+        assert!(inside(x, y, 0.0), "at ({}, {})", x, y);
+    }
+    let (x, _) = position_of(&logic, runner);
+    assert!((x - (300.0 - AppDefines::ENTITY_HALF_SIZE)).abs() < 1.0, "the runner stops against the right wall: {}", x);
+
+    // Une balle poussée au-delà des murs disparaît, une balle restée dedans vole encore
+    let gun = logic.get_entity_mut(runner).unwrap().gun_mut();
+    gun.orientation = 0.0;
+    gun.traverse = 0.0;
+    for (y, kept) in [(260.0, false), (60.0, true)] {
+        fire_once(&mut logic, runner);
+        let handle = logic.bullets[0].handle;
+        logic.physics_engine.bodies[handle].set_translation(vector![150.0, y], true);
+        logic.step();
+        assert_eq!(!logic.bullets.is_empty(), kept, "bullet at y = {}", y);
+    }
+}
+
+#[test]
+fn bullets_fired_at_full_speed_outlive_their_first_steps() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.shot_energy_cost = 0.0;
+    config.base_spread = 0.0;
+    config.spread_per_speed = 0.0;
+    let id = logic.add_entity_at("Runner".to_string(), 100.0, 500.0, 0.0, false).unwrap();
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.fire_cooldown = Duration::from_millis(100);
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+    // Le canon tire droit devant, dans le sens de la course
+    let gun = entity.gun_mut();
+    gun.orientation = 0.5;
+    gun.traverse = 0.5;
+    gun.trigger = 1.0;
+
+    let mut fired: Vec<u64> = Vec::new();
+    for _ in 0..400 {
+        if position_of(&logic, id).0 > 700.0 {
+            logic.teleport_entity(id, 100.0, 500.0);
+        }
+        let cursor = logic.events.cursor();
+        logic.step();
+        fired.extend(logic.events.since(cursor).filter(|queued| matches!(queued.event, GameEvent::BulletFired { .. })).map(|queued| queued.step));
+        // Chaque balle tirée vit encore trois pas après son tir
+        let flying: Vec<u64> = logic.bullets.iter().map(|bullet| bullet.fired_at).collect();
+        for &shot in fired.iter().filter(|&&shot| logic.tick <= shot + 4) {
+            assert!(flying.contains(&shot), "the bullet fired at step {} vanished by step {}", shot, logic.tick);
+        }
+    }
+    assert!(fired.len() > 30, "{} shots", fired.len());
+    assert!(logic.events.since(0).all(|queued| !matches!(queued.event, GameEvent::Hit { .. })), "no bullet hit its shooter");
+}
+
+#[test]
+fn ccd_bullets_bounce_off_the_wall_at_speed_5000() {
+    let mut logic = GameLogic::new();
+    let config = &mut logic.game_config;
+    config.spawn_protection_ms = 0;
+    config.bullet_speed = 5000.0;
+    config.bullet_bounces = 1;
+    config.base_spread = 0.0;
+    // La balle renvoyée repasse par son tireur
+    config.bullets_ignore_shooter = true;
+    assert!(config.bullet_ccd, "on by default");
+    // À 5000 unités par seconde, une balle avance de 83 unités par pas, bien plus que l'épaisseur du mur
+    let id = logic.add_entity_at("Gunner".to_string(), 1100.0, 500.0, 0.0, false).unwrap();
+    let gun = logic.get_entity_mut(id).unwrap().gun_mut();
+    gun.orientation = 0.5;
+    gun.traverse = 0.5;
+    let width = logic.arena().width;
+
+    for shot in 0..20 {
+        fire_once(&mut logic, id);
+        let mut bounced = false;
+        while let Some(bullet) = logic.bullets.first() {
+            let body = &logic.physics_engine.bodies[bullet.handle];
+            assert!(body.translation().x <= width, "shot {} went through the wall: {:?}", shot, body.translation());
+            bounced |= body.linvel().x < 0.0;
+            logic.step();
+        }
+        assert!(bounced, "shot {} bounced back", shot);
+    }
+}
+
+#[test]
+fn walls_block_the_line_of_sight_up_to_the_point_looked_at() {
+    let mut logic = GameLogic::new();
+    // Un mur vertical de 200 unités, sa face gauche en x = 590
+    let index = logic.add_obstacle_at(600.0, 500.0, ObstacleShape::Wall { half_length: 100.0, half_thickness: 10.0, angle: std::f32::consts::FRAC_PI_2 }).unwrap();
+    let wall = logic.physics_engine.bodies[logic.obstacles[index].body_handle].colliders()[0];
+    logic.step();
+
+    assert!(!logic.line_of_sight(vector![400.0, 500.0], vector![800.0, 500.0], &[]), "the wall stands between the points");
+    assert!(logic.line_of_sight(vector![400.0, 500.0], vector![800.0, 500.0], &[wall]), "an ignored wall does not block the view");
+    assert!(logic.line_of_sight(vector![400.0, 300.0], vector![800.0, 300.0], &[]), "the ray passes beside the wall");
+    // Le point visé touche la face du mur : il est vu, pas ce qu'il y a derrière
+    assert!(logic.line_of_sight(vector![400.0, 500.0], vector![590.0, 500.0], &[]), "a wall touching the end of the ray does not block it");
+    assert!(!logic.line_of_sight(vector![400.0, 500.0], vector![595.0, 500.0], &[]), "a point inside the wall is hidden");
+
+    let hit = logic.physics_engine.raycast(vector![400.0, 500.0], vector![1.0, 0.0], 1000.0, QueryFilter::default().exclude_sensors());
+    let (collider, distance) = hit.expect("the ray hits the wall");
+    assert_eq!(collider, wall);
+    assert!((distance - 190.0).abs() < 0.01, "the wall is 190 units away: {}", distance);
+    assert_eq!(logic.physics_engine.raycast(vector![400.0, 500.0], vector![1.0, 0.0], 150.0, QueryFilter::default()), None, "the wall is beyond the reach");
+}
+
+#[test]
+fn rams_report_contact_forces_above_the_threshold() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    logic.game_config.starting_health = 10000;
+    let left = logic.add_entity_at("Left".to_string(), 400.0, 300.0, 0.0, false).unwrap();
+    let right = logic.add_entity_at("Right".to_string(), 520.0, 300.0, std::f32::consts::PI, false).unwrap();
+    for id in [left, right] {
+        let entity = logic.get_entity_mut(id).unwrap();
+        entity.motor_left = 1.0;
+        entity.motor_right = 1.0;
+    }
+    let collider_of = |logic: &GameLogic, id: u32| {
+        let entity = logic.entities.iter().find(|entity| entity.id == id).unwrap();
+        logic.physics_engine.bodies[entity.handle].colliders()[0]
+    };
+    let pair = [collider_of(&logic, left), collider_of(&logic, right)];
+    let threshold = logic.game_config.contact_force_threshold;
+
+    let mut forces = Vec::new();
+    for _ in 0..180 {
+        logic.step();
+        // Les événements d'un pas restent lisibles jusqu'au pas suivant
+        for event in &logic.physics_engine.contact_force_events {
+            assert!(pair.contains(&event.collider1) && pair.contains(&event.collider2), "only the rammed entities push: {:?}", event);
+            forces.push(event.total_force_magnitude);
+        }
+    }
+    assert!(forces.iter().any(|&force| force > threshold), "the ram reports a force above {}: {:?}", threshold, forces);
+}
+
+#[test]
+fn bodies_of_the_arena_carry_their_identity() {
+    let mut logic = GameLogic::new();
+    logic.game_config.spawn_protection_ms = 0;
+    let index = logic.add_obstacle_at(600.0, 300.0, ObstacleShape::Circle { radius: 20.0 }).unwrap();
+    let gunner = logic.add_entity_at("Gunner".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    let target = logic.add_entity_at("Target".to_string(), 300.0, 600.0, 0.0, false).unwrap();
+    face_left_wall(&mut logic, gunner, 200.0);
+    fire_once(&mut logic, gunner);
+
+    let physics_engine = &logic.physics_engine;
+    for entity in &logic.entities {
+        assert_eq!(physics_engine.identify(entity.handle), BodyIdentity::Entity(entity.id));
+    }
+    assert_eq!(physics_engine.identify(logic.bullets[0].handle), BodyIdentity::Bullet(Some(gunner)));
+    assert_eq!(physics_engine.identify(logic.obstacles[index].body_handle), BodyIdentity::Obstacle);
+    assert!(!physics_engine.boundary_colliders.is_empty());
+    for &wall in &physics_engine.boundary_colliders {
+        assert_eq!(physics_engine.identify_collider(wall), BodyIdentity::Boundary);
+    }
+
+    // Une balle partie d'une entité retirée n'appartient plus à personne
+    logic.remove_entity_by_id(gunner);
+    assert_eq!(logic.physics_engine.identify(logic.bullets[0].handle), BodyIdentity::Bullet(None));
+    assert!(logic.entities.iter().all(|entity| entity.id == target));
+}
+
+#[test]
+fn regenerated_maps_leave_no_orphan_bodies_or_colliders() {
+    let mut logic = GameLogic::new();
+    for name in ["First", "Second", "Third"] {
+        logic.add_entity(name.to_string());
+    }
+    logic.generate_map(Some(0));
+    let sizes = |logic: &GameLogic| (logic.physics_engine.bodies.len(), logic.physics_engine.colliders.len());
+    let first = sizes(&logic);
+
+    for seed in 1..20 {
+        logic.generate_map(Some(seed));
+        // Un corps fixe par obstacle et un par entité, chacun avec son collider, en plus des murs et des capteurs
+        assert_eq!(logic.physics_engine.bodies.len(), logic.entities.len() + logic.obstacles.len(), "seed {}", seed);
+        let parentless = logic.physics_engine.colliders.iter().filter(|(_, collider)| collider.parent().is_none()).count();
+        assert_eq!(logic.physics_engine.colliders.len() - parentless, logic.physics_engine.bodies.len(), "seed {}", seed);
+        for (handle, collider) in logic.physics_engine.colliders.iter().filter(|(_, collider)| collider.parent().is_none()) {
+            assert!(collider.is_sensor() || logic.physics_engine.boundary_colliders.contains(&handle), "seed {}: a stray {}", seed, logic.physics_engine.identify_collider(handle));
+        }
+    }
+    logic.generate_map(Some(0));
+    assert_eq!(sizes(&logic), first, "the same map as at first");
+}
+
+#[test]
+fn obstacles_stop_the_entities() {
+    let mut logic = GameLogic::new();
+    logic.add_obstacle_at(600.0, 300.0, ObstacleShape::Wall { half_length: 100.0, half_thickness: 10.0, angle: std::f32::consts::FRAC_PI_2 }).unwrap();
+    let id = logic.add_entity_at("Runner".to_string(), 400.0, 300.0, 0.0, false).unwrap();
+    let entity = logic.get_entity_mut(id).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+
+    run_secs(&mut logic, 3);
+    let (x, _) = position_of(&logic, id);
+    assert!(x < 590.0 - AppDefines::ENTITY_HALF_SIZE + 1.0, "the entity pushes against the wall at {}", x);
+    assert!(x > 500.0, "the entity drove up to the wall: {}", x);
+}
+
+#[test]
+fn render_snapshots_interpolate_between_steps_and_snap_teleports() {
+    let mut logic = GameLogic::new();
+    let runner = logic.add_entity_at("Runner".to_string(), 300.0, 300.0, 0.0, false).unwrap();
+    let jumper = logic.add_entity_at("Jumper".to_string(), 300.0, 600.0, 0.0, false).unwrap();
+    let entity = logic.get_entity_mut(runner).unwrap();
+    entity.motor_left = 1.0;
+    entity.motor_right = 1.0;
+    let interval = Duration::from_millis(16);
+    let start = Instant::now();
+    let position = |snapshot: &RenderSnapshot, alpha: f32, id: u32| {
+        let entity = snapshot.interpolated(alpha).entities.into_iter().find(|entity| entity.id == id).unwrap();
+        (entity.x, entity.y)
+    };
+
+    // Une entité tout juste apparue est dessinée là où elle est
+    logic.step();
+    logic.publish_render(start, interval);
+    let snapshot = logic.render_slot().latest();
+    assert!(snapshot.previous_entities.iter().all(Option::is_none));
+    assert_eq!(position(&snapshot, 0.0, runner), position_of(&logic, runner));
+
+    for _ in 0..10 {
+        logic.step();
+    }
+    logic.publish_render(start + interval, interval);
+    let before = snapshot.frame.entities.iter().find(|entity| entity.id == runner).map(|entity| (entity.x, entity.y)).unwrap();
+    let after = position_of(&logic, runner);
+    assert!(after.0 > before.0 + 1.0, "the runner moved: {:?} to {:?}", before, after);
+    let snapshot = logic.render_slot().latest();
+    assert_eq!(position(&snapshot, 0.0, runner), before);
+    assert_eq!(position(&snapshot, 1.0, runner), after);
+    let (x, y) = position(&snapshot, 0.25, runner);
+    assert!((x - (before.0 + 0.25 * (after.0 - before.0))).abs() < 1e-3 && (y - (before.1 + 0.25 * (after.1 - before.1))).abs() < 1e-3, "a quarter of the way: {:?}", (x, y));
+
+    // La fraction du pas écoulée, bornée à 1, et 1 quand la simulation n'avance pas
+    assert_eq!(snapshot.alpha(start + interval), 0.0);
+    assert!((snapshot.alpha(start + interval + interval / 2) - 0.5).abs() < 1e-3);
+    assert_eq!(snapshot.alpha(start + interval * 10), 1.0);
+    assert_eq!(RenderSnapshot { interval: Duration::ZERO, ..(*snapshot).clone() }.alpha(start), 1.0);
+
+    // Une entité téléportée saute à sa nouvelle place, les autres continuent de glisser
+    assert!(logic.teleport_entity(jumper, 900.0, 200.0));
+    logic.step();
+    logic.publish_render(start + interval * 2, interval);
+    let snapshot = logic.render_slot().latest();
+    let jumper_index = snapshot.frame.entities.iter().position(|entity| entity.id == jumper).unwrap();
+    let runner_index = snapshot.frame.entities.iter().position(|entity| entity.id == runner).unwrap();
+    assert_eq!(snapshot.previous_entities[jumper_index], None, "the teleport is flagged");
+    assert!(snapshot.previous_entities[runner_index].is_some());
+    assert_eq!(position(&snapshot, 0.0, jumper), position_of(&logic, jumper));
+    // Le drapeau ne vaut que pour un pas
+    logic.step();
+    logic.publish_render(start + interval * 3, interval);
+    assert!(logic.render_slot().latest().previous_entities.iter().all(Option::is_some));
+
+    // Les angles tournent par le plus court chemin, à travers le demi-tour
+    let mut snapshot = RenderSnapshot::default();
+    snapshot.frame.entities.push(EntityFrame { rotation: -3.0, ..EntityFrame::default() });
+    snapshot.previous_entities.push(Some((0.0, 0.0, 3.0)));
+    let rotation = snapshot.interpolated(0.5).entities[0].rotation;
+    assert!((rotation.abs() - std::f32::consts::PI).abs() < 1e-3, "half way from 3 to -3 is a half turn: {}", rotation);
+}
+
+#[test]
+fn profiled_steps_time_every_phase_with_growing_counters() {
+    let mut logic = GameLogic::new();
+    logic.game_config.starting_health = 10000;
+    for index in 0..4 {
+        logic.add_ai_at(format!("Bot{}", index), 200.0 + 200.0 * index as f32, 400.0, 0.0, AiDifficulty::Normal).unwrap();
+    }
+    logic.add_entity_at("Player".to_string(), 600.0, 700.0, 0.0, false).unwrap();
+
+    // Sans profilage, les pas ne lisent même pas l'horloge
+    for _ in 0..10 {
+        logic.update_ai();
+        logic.step();
+    }
+    for phase in StepPhase::ALL {
+        assert_eq!(logic.step_timings.samples(phase), 0, "{}", phase.name());
+    }
+
+    logic.profiling_enabled = true;
+    let mut totals = [Duration::ZERO; 5];
+    for tick in 1..=200 {
+        logic.update_ai();
+        logic.step();
+        for (index, phase) in StepPhase::ALL.into_iter().enumerate() {
+            assert_eq!(logic.step_timings.samples(phase), tick, "{} is measured once a step", phase.name());
+            let total = logic.step_timings.total(phase);
+            assert!(total >= totals[index], "the time spent in {} only grows", phase.name());
+            totals[index] = total;
+        }
+    }
+    for phase in StepPhase::ALL {
+        assert!(logic.step_timings.total(phase) > Duration::ZERO, "{} took some time", phase.name());
+        assert!(logic.step_timings.average(phase) > Duration::ZERO, "{}", phase.name());
+        assert!(logic.step_timings.worst(phase) >= logic.step_timings.average(phase), "{}", phase.name());
+    }
+}
+
+#[test]
+fn the_game_reports_what_is_not_an_event_to_the_server_messages() {
+    let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+    let mut game_logic = GameLogic::new();
+    // Sans messages, ce que le jeu signale est perdu
+    game_logic.add_ai("lost".to_string(), Some("no such behavior"), AiDifficulty::Normal);
+
+    game_logic.set_messages(messages.clone());
+    game_logic.set_arena(ArenaConfig::new(800.0, 600.0).unwrap());
+    game_logic.add_ai("bot".to_string(), Some("no such behavior"), AiDifficulty::Normal);
+    // Les événements de l'arène passent par la file d'événements, pas par les messages
+    game_logic.add_entity("player".to_string());
+    messages.flush();
+
+    let log = messages.log().to_vec();
+    let texts: Vec<&str> = log.iter().map(|message| message.text.as_str()).collect();
+    let fallback = game_logic.default_behavior().to_string();
+    assert_eq!(texts, ["[INFO] Arena resized to 800x600.".to_string(), format!("[WARNING] Unknown AI behavior no such behavior for bot, using {}.", fallback)]);
+    assert_eq!(log.iter().map(|message| message.message_type).collect::<Vec<_>>(), [MessageType::Info, MessageType::Warning]);
+    assert!(log.iter().all(|message| message.origin == MessageOrigin::Game));
+}
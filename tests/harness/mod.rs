@@ -0,0 +1,154 @@
+//! Runs a real server in the test process and drives it through `BotClient`s.
+//!
+//! Each `TestServer` has its own game, tick loop and port, so the tests run in parallel.
+
+// Chaque fichier de tests n'utilise qu'une partie du harnais
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::client::BotClient;
+use universal_rust_server_software::game_logic::GameLogic;
+use universal_rust_server_software::server::server_thread::{ServerSettings, ServerThread};
+use universal_rust_server_software::types::MessageHub;
+
+/// How long a condition is waited for.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waits until a condition holds.
+///
+/// # Returns
+/// Whether it held before `TIMEOUT`.
+pub fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + TIMEOUT;
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+/// A server listening on a free port of the loopback, stopped when dropped.
+///
+/// The game has no obstacle, no spawn protection and no file: no log, no leaderboard,
+/// and the entities of dropped clients are removed at once.
+pub struct TestServer {
+    pub server: ServerThread,
+    pub game_logic: Arc<Mutex<GameLogic>>,
+    /// The settings the server reads while it runs, to change them under it.
+    pub settings: Arc<Mutex<ServerSettings>>,
+    pub messages: MessageHub,
+    pub port: u16,
+}
+
+impl TestServer {
+    /// Starts a server with the settings of the tests.
+    pub fn start() -> Self {
+        TestServer::start_with(|_, _| {})
+    }
+
+    /// Starts a server, once its settings and its game are changed.
+    ///
+    /// # Parameters
+    /// - `configure`: Changes the settings and the game before the server starts.
+    pub fn start_with(configure: impl FnOnce(&mut ServerSettings, &mut GameLogic)) -> Self {
+        // Le système donne un port libre, rendu aussitôt au serveur
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut settings = ServerSettings::new();
+        settings.log_path = String::new();
+        settings.web_socket_port = 0;
+        settings.admin_control_port = 0;
+        settings.udp_actuators = false;
+        settings.reconnect_grace_secs = 0;
+        let mut logic = GameLogic::new();
+        logic.game_config.spawn_protection_ms = 0;
+        configure(&mut settings, &mut logic);
+
+        let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+        let game_logic = Arc::new(Mutex::new(logic));
+        let settings = Arc::new(Mutex::new(settings));
+        let server = ServerThread::new(vec!["127.0.0.1".to_string()], port, messages.clone(), Arc::clone(&settings), Arc::clone(&game_logic));
+        server.start();
+        TestServer { server, game_logic, settings, messages, port }
+    }
+
+    /// Connects a bot and completes its handshake, which gives it an entity.
+    pub fn connect(&self) -> BotClient {
+        let mut bot = BotClient::connect("127.0.0.1", self.port).unwrap();
+        bot.hello().unwrap();
+        bot
+    }
+
+    /// Returns the number of entities in the game.
+    pub fn entity_count(&self) -> usize {
+        self.game_logic.lock().unwrap().entities.len()
+    }
+
+    /// Returns the texts of the server messages.
+    pub fn message_texts(&self) -> Vec<String> {
+        self.messages.flush();
+        self.messages.log().to_vec().iter().map(|message| message.text.clone()).collect()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.server.stop();
+    }
+}
+
+/// Asks a WebSocket server to upgrade a connection, with the key of RFC 6455.
+///
+/// # Returns
+/// The HTTP response, up to its blank line; the frames follow it.
+pub fn web_socket_upgrade(stream: &mut (impl Read + Write)) -> String {
+    write!(
+        stream,
+        "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+    )
+    .unwrap();
+    // La réponse HTTP se lit octet par octet, la trame de salut la suit
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        response.push(byte[0]);
+    }
+    String::from_utf8(response).unwrap()
+}
+
+/// Sends a text frame, masked as a client must.
+pub fn send_text_frame(stream: &mut impl Write, text: &str) {
+    let mask = [0x12, 0x34, 0x56, 0x78];
+    // Les commandes des tests tiennent dans une longueur sur 7 bits
+    let mut frame = vec![0x81, 0x80 | text.len() as u8];
+    frame.extend_from_slice(&mask);
+    frame.extend(text.bytes().enumerate().map(|(index, byte)| byte ^ mask[index % 4]));
+    stream.write_all(&frame).unwrap();
+}
+
+/// Reads the next text frame sent by the server, without its line ending.
+pub fn read_text_frame(stream: &mut impl Read) -> String {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).unwrap();
+    assert_eq!(header[0], 0x81, "not a final text frame");
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut extended = [0u8; 2];
+            stream.read_exact(&mut extended).unwrap();
+            u16::from_be_bytes(extended) as usize
+        }
+        len => len as usize,
+    };
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).unwrap();
+    String::from_utf8(payload).unwrap().trim_end().to_string()
+}
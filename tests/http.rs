@@ -0,0 +1,209 @@
+mod harness;
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use harness::{wait_for, TestServer};
+use serde_json::Value;
+use universal_rust_server_software::server::server_thread::ServerSettings;
+
+/// A response of the HTTP endpoint.
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Response {
+    /// Returns the value of a header, its name in any case.
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the body read as JSON.
+    fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or_else(|e| panic!("invalid JSON ({}): {}", e, self.body))
+    }
+}
+
+/// Sends a request to the endpoint and reads the response until the server closes the connection.
+fn request(port: u16, method: &str, path: &str) -> Response {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nOrigin: http://dashboard.local\r\n\r\n", method, path).unwrap();
+    let mut text = String::new();
+    stream.read_to_string(&mut text).unwrap();
+    let (head, body) = text.split_once("\r\n\r\n").expect("no end of head");
+    let mut lines = head.lines();
+    let status = lines.next().unwrap().split_whitespace().nth(1).unwrap().parse().unwrap();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Response { status, headers, body: body.to_string() }
+}
+
+/// Starts a server with the HTTP endpoint on a free port of the loopback.
+fn start() -> (TestServer, u16) {
+    // Un port libre, rendu aussitôt au serveur
+    let http_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let server = TestServer::start_with(|settings, logic| {
+        settings.http_port = http_port;
+        logic.leaderboard.record_match(&[("Ace".to_string(), 12), ("Rookie".to_string(), 3)]);
+    });
+    (server, http_port)
+}
+
+#[test]
+fn state_lists_the_arena() {
+    let (server, http_port) = start();
+    let mut bot = server.connect();
+    bot.set_name("Watched").unwrap();
+    assert!(wait_for(|| server.game_logic.lock().unwrap().entities.iter().any(|entity| entity.name == "Watched")));
+
+    let response = request(http_port, "GET", "/state");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    assert_eq!(response.header("Access-Control-Allow-Origin"), Some("*"));
+    let state = response.json();
+    assert!(state["tick"].is_u64());
+    assert!(state["bullets"].is_u64());
+    let entities = state["entities"].as_array().expect("no entity list");
+    assert_eq!(entities.len(), 1);
+    let entity = &entities[0];
+    assert_eq!(entity["name"], "Watched");
+    assert!(entity["id"].is_u64());
+    assert!(entity["team"].is_null(), "no team was set: {}", entity);
+    for field in ["x", "y"] {
+        assert!(entity[field].is_f64(), "{} is not a number: {}", field, entity);
+    }
+    assert!(entity["health"].is_number() && entity["score"].is_number());
+    assert!(state["match"]["round"].is_u64());
+    assert!(state["match"]["phase"].is_string());
+    assert!(state["match"].get("remaining_s").is_some());
+    assert!(state.get("zone").is_some());
+}
+
+#[test]
+fn leaderboard_and_metrics_are_served() {
+    let (_server, http_port) = start();
+
+    let leaderboard = request(http_port, "GET", "/leaderboard?top=5").json();
+    let players = leaderboard["players"].as_array().expect("no player list");
+    let names: Vec<&str> = players.iter().map(|player| player["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Ace", "Rookie"]);
+    assert_eq!(players[0]["rank"], 1);
+    assert_eq!(players[0]["score"], 12);
+    assert!(players[0]["accuracy"].is_null(), "no shot was fired");
+
+    let metrics = request(http_port, "GET", "/metrics-lite").json();
+    for field in ["connected_clients", "players", "ai_entities", "bullets", "obstacles"] {
+        assert!(metrics[field].is_u64(), "{} is not a count: {}", field, metrics);
+    }
+    for field in ["tick_rate", "target_tick_rate", "average_step_ms", "time_scale", "uptime_secs"] {
+        assert!(metrics[field].is_number(), "{} is not a number: {}", field, metrics);
+    }
+}
+
+#[test]
+fn other_requests_are_refused_with_cors_headers() {
+    let (_server, http_port) = start();
+
+    let missing = request(http_port, "GET", "/nothing");
+    assert_eq!(missing.status, 404);
+    assert_eq!(missing.header("Access-Control-Allow-Origin"), Some("*"));
+    assert!(missing.json()["error"].is_string());
+
+    assert_eq!(request(http_port, "POST", "/state").status, 405);
+
+    let preflight = request(http_port, "OPTIONS", "/state");
+    assert_eq!(preflight.status, 204);
+    assert_eq!(preflight.header("Access-Control-Allow-Origin"), Some("*"));
+    assert!(preflight.header("Access-Control-Allow-Methods").is_some_and(|methods| methods.contains("GET")));
+}
+
+#[test]
+fn the_endpoint_is_off_by_default() {
+    assert_eq!(ServerSettings::new().http_port, 0);
+    let server = TestServer::start();
+    assert!(!server.message_texts().iter().any(|text| text.contains("HTTP")));
+}
+
+/// Reads the exposition format of Prometheus.
+///
+/// # Returns
+/// The type of each family, and the value of each sample by its name and labels.
+fn parse_exposition(text: &str) -> (HashMap<String, String>, HashMap<String, f64>) {
+    let mut types = HashMap::new();
+    let mut samples = HashMap::new();
+    for line in text.lines() {
+        if let Some(declaration) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = declaration.split_once(' ').expect("no type");
+            types.insert(name.to_string(), kind.to_string());
+        } else if !line.starts_with('#') && !line.is_empty() {
+            let (name, value) = line.rsplit_once(' ').expect("no value");
+            samples.insert(name.to_string(), value.parse().unwrap_or_else(|_| panic!("invalid value: {}", line)));
+        }
+    }
+    (types, samples)
+}
+
+#[test]
+fn prometheus_scrapes_the_counters() {
+    let (server, http_port) = start();
+    let mut bot = server.connect();
+    bot.command("NOPE").unwrap();
+    let scrape = || {
+        let response = request(http_port, "GET", "/metrics");
+        assert_eq!(response.status, 200);
+        assert!(response.header("Content-Type").is_some_and(|kind| kind.starts_with("text/plain")));
+        parse_exposition(&response.body)
+    };
+
+    // Le trafic est compté une fois la réponse envoyée
+    assert!(wait_for(|| scrape().1["starnet_commands_processed_total"] >= 2.0), "HELLO and NOPE were not counted");
+    let (types, samples) = scrape();
+    for (family, kind) in [
+        ("connected_clients", "gauge"),
+        ("entities", "gauge"),
+        ("ai_entities", "gauge"),
+        ("live_bullets", "gauge"),
+        ("tick_rate", "gauge"),
+        ("step_duration_seconds", "histogram"),
+        ("commands_processed_total", "counter"),
+        ("bytes_rx_total", "counter"),
+        ("bytes_tx_total", "counter"),
+        ("kills_total", "counter"),
+        ("errors_total", "counter"),
+    ] {
+        assert_eq!(types.get(&format!("starnet_{}", family)).map(String::as_str), Some(kind), "family {} missing", family);
+    }
+    assert_eq!(samples["starnet_connected_clients"], 1.0);
+    assert_eq!(samples["starnet_entities"], 1.0);
+    assert!(samples["starnet_bytes_rx_total"] > 0.0 && samples["starnet_bytes_tx_total"] > 0.0);
+    assert_eq!(samples["starnet_errors_total{kind=\"unknown_command\"}"], 1.0);
+    let steps = samples["starnet_step_duration_seconds_count"];
+    assert_eq!(samples["starnet_step_duration_seconds_bucket{le=\"+Inf\"}"], steps);
+
+    // Les compteurs survivent à la remise à zéro de la partie
+    let entity_id = server.game_logic.lock().unwrap().entities[0].id;
+    server.game_logic.lock().unwrap().kill_entity(entity_id);
+    assert!(wait_for(|| scrape().1["starnet_kills_total"] == 1.0), "the kill was not counted");
+    server.game_logic.lock().unwrap().reset_simulation();
+    let (_, after) = scrape();
+    assert_eq!(after["starnet_kills_total"], 1.0);
+    assert_eq!(after["starnet_errors_total{kind=\"unknown_command\"}"], 1.0);
+    assert!(after["starnet_step_duration_seconds_count"] >= steps);
+    assert!(after["starnet_commands_processed_total"] >= samples["starnet_commands_processed_total"]);
+}
+
+#[test]
+fn prometheus_can_be_turned_off() {
+    let http_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let _server = TestServer::start_with(|settings, _| {
+        settings.http_port = http_port;
+        settings.http_prometheus_metrics = false;
+    });
+    assert_eq!(request(http_port, "GET", "/metrics").status, 404);
+    assert_eq!(request(http_port, "GET", "/metrics-lite").status, 200);
+}
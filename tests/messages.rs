@@ -0,0 +1,291 @@
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::types::{add_chat_message, add_message, add_messages, export_messages, format_age, format_clock, format_log_line, format_timestamp, is_logged, set_log_verbosity, ExportFormat, MessageHub, MessageLog, MessageOrigin, MessageType, StyledMessage};
+
+/// Held by the tests adding messages: the verbosity is shared by every hub of the process.
+static VERBOSITY: Mutex<()> = Mutex::new(());
+
+/// Returns a new hub keeping `capacity` messages, with the tests changing the verbosity kept out until the guard is dropped.
+fn hub(capacity: usize) -> (MutexGuard<'static, ()>, MessageHub) {
+    let guard = VERBOSITY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Un test qui a échoué a pu laisser une autre verbosité
+    set_log_verbosity(AppDefines::LOG_VERBOSITY);
+    (guard, MessageHub::new(capacity))
+}
+
+/// Returns a message of the game, stamped at a number of seconds after the epoch.
+fn message(text: &str, message_type: MessageType, seconds: u64) -> StyledMessage {
+    StyledMessage::new(text.to_string(), message_type, MessageOrigin::Game, UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Returns the texts of messages, in order.
+fn texts<'a>(messages: impl IntoIterator<Item = &'a StyledMessage>) -> Vec<&'a str> {
+    messages.into_iter().map(|message| message.text.as_str()).collect()
+}
+
+/// Returns the time a number of milliseconds after the epoch.
+fn at_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+#[test]
+fn times_are_formatted_in_utc_to_the_millisecond() {
+    assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    assert_eq!(format_timestamp(at_millis(1_714_571_112_345)), "2024-05-01T13:45:12.345Z");
+    assert_eq!(format_timestamp(at_millis(1_709_251_199_999)), "2024-02-29T23:59:59.999Z", "a leap day");
+    assert_eq!(format_timestamp(at_millis(951_868_800_000)), "2000-03-01T00:00:00.000Z", "the day after a leap day of a leap century");
+    assert_eq!(format_timestamp(at_millis(946_684_799_007)), "1999-12-31T23:59:59.007Z");
+    assert_eq!(format_timestamp(UNIX_EPOCH - Duration::from_secs(10)), "1970-01-01T00:00:00.000Z", "a time before the epoch");
+
+    // L'heure du jour a toujours la même largeur
+    assert_eq!(format_clock(at_millis(1_714_571_112_345)), "13:45:12.345");
+    assert_eq!(format_clock(at_millis(1_714_521_600_001)), "00:00:00.001");
+
+    let now = at_millis(1_714_571_112_345);
+    let ages = [(0, "now"), (999, "now"), (1_000, "1s ago"), (59_999, "59s ago"), (60_000, "1m ago"), (3_599_000, "59m ago"), (3_600_000, "1h ago"), (86_399_000, "23h ago"), (86_400_000, "1d ago"), (10 * 86_400_000, "10d ago")];
+    for (age, text) in ages {
+        assert_eq!(format_age(now - Duration::from_millis(age), now), text, "{} ms", age);
+    }
+    assert_eq!(format_age(now + Duration::from_secs(5), now), "now", "a time after now");
+
+    let line = format_log_line(at_millis(1_714_571_112_345), MessageType::Warning, MessageOrigin::Network, "  Connection timeout:\n127.0.0.1:5000 ");
+    assert_eq!(line, "2024-05-01T13:45:12.345Z [WARN] [NET] Connection timeout: 127.0.0.1:5000");
+    let origins = [(MessageOrigin::Network, "NET"), (MessageOrigin::Game, "GAME"), (MessageOrigin::Admin, "ADMIN"), (MessageOrigin::Ui, "UI"), (MessageOrigin::Chat, "CHAT")];
+    for (origin, tag) in origins {
+        assert_eq!(origin.tag(), tag);
+    }
+}
+
+#[test]
+fn messages_are_stamped_in_the_order_of_the_log() {
+    let (_guard, messages) = hub(10_000);
+    let before = SystemTime::now();
+    let threads: Vec<_> = (0..4).map(|thread| {
+        let messages = messages.clone();
+        thread::spawn(move || {
+            for index in 0..250 {
+                add_message(&messages, format!("{} {}", thread, index), MessageType::Info, MessageOrigin::Game);
+            }
+        })
+    }).collect();
+    for handle in threads {
+        handle.join().unwrap();
+    }
+    messages.flush();
+    let after = SystemTime::now();
+
+    let log = messages.log().to_vec();
+    assert_eq!(log.len(), 1000);
+    assert!(log.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp), "the times follow the log");
+    assert!(log.iter().all(|message| before <= message.timestamp && message.timestamp <= after));
+    // Les messages d'un même fil gardent leur ordre
+    for thread in 0..4 {
+        let indices: Vec<usize> = log.iter()
+            .filter_map(|message| message.text.strip_prefix(&format!("{} ", thread)))
+            .map(|index| index.parse().unwrap())
+            .collect();
+        assert_eq!(indices, (0..250).collect::<Vec<usize>>());
+    }
+    assert!(log.iter().all(|message| message.origin == MessageOrigin::Game && message.message_type == MessageType::Info));
+}
+
+#[test]
+fn a_full_log_drops_its_oldest_messages() {
+    let mut log = MessageLog::new(3);
+    assert_eq!(MessageLog::new(0).capacity(), 1, "a log keeps one message at least");
+    assert!(log.last().is_none());
+    for index in 0..3 {
+        log.push(message(&index.to_string(), MessageType::Info, index));
+    }
+    assert_eq!(texts(&log.to_vec()), ["0", "1", "2"]);
+    assert_eq!((log.added(), log.dropped()), (3, 0));
+
+    for index in 3..8 {
+        log.push(message(&index.to_string(), MessageType::Info, index));
+    }
+    assert_eq!(texts(&log.to_vec()), ["5", "6", "7"], "the oldest messages are dropped first");
+    assert_eq!(log.capacity(), 3);
+    assert_eq!((log.added(), log.dropped()), (8, 5));
+    assert_eq!(log.last().map(|last| last.text.as_str()), Some("7"));
+
+    // Un lecteur reprend au numéro attendu, en sautant les messages perdus entre-temps
+    assert_eq!(texts(log.from_sequence(6)), ["6", "7"]);
+    assert_eq!(texts(log.from_sequence(2)), ["5", "6", "7"]);
+    assert_eq!(texts(log.from_sequence(8)), Vec::<&str>::new());
+    assert_eq!(texts(log.from_sequence(100)), Vec::<&str>::new());
+
+    log.clear();
+    assert!(log.to_vec().is_empty());
+    assert_eq!((log.added(), log.dropped()), (0, 0), "a cleared log starts the sequence numbers over");
+    log.push(message("after", MessageType::Info, 9));
+    assert_eq!(texts(log.from_sequence(0)), ["after"]);
+
+    // Le hub garde le même nombre de messages que son journal
+    let (_guard, messages) = hub(100);
+    for index in 0..250 {
+        add_message(&messages, index.to_string(), MessageType::Info, MessageOrigin::Game);
+    }
+    messages.flush();
+    let log = messages.log();
+    assert_eq!((log.to_vec().len(), log.added(), log.dropped()), (100, 250, 150));
+    assert_eq!(log.to_vec().first().map(|first| first.text.as_str()), Some("150"));
+    assert_eq!(log.last().map(|last| last.text.as_str()), Some("249"));
+}
+
+#[test]
+fn messages_are_filtered_by_severity_and_searched_by_text() {
+    use MessageType::*;
+    let by_severity = [Debug, Info, GameEvent, Default, Warning, Error];
+    let severities: Vec<u8> = by_severity.iter().map(MessageType::severity).collect();
+    assert_eq!(severities, [0, 1, 1, 1, 2, 3]);
+    assert!(Error.is_at_least(Warning) && Warning.is_at_least(Info) && Info.is_at_least(Debug));
+    assert!(!Debug.is_at_least(Info) && !Info.is_at_least(Warning) && !Warning.is_at_least(Error));
+    assert!(GameEvent.is_at_least(Info) && Info.is_at_least(GameEvent) && Default.is_at_least(Info), "events and chat are as severe as information");
+
+    let mut log = MessageLog::new(10);
+    for (index, message_type) in by_severity.into_iter().enumerate() {
+        log.push(message(&format!("{} {}", message_type.to_level(), index), message_type, index as u64));
+    }
+    assert_eq!(log.filtered(Debug).len(), 6);
+    assert_eq!(texts(&log.filtered(Info)), ["INFO 1", "EVENT 2", "INFO 3", "WARN 4", "ERROR 5"]);
+    assert_eq!(texts(&log.filtered(Warning)), ["WARN 4", "ERROR 5"]);
+    assert_eq!(texts(&log.filtered(Error)), ["ERROR 5"]);
+
+    // La recherche ignore la casse et garde les plus récents, dans l'ordre du journal
+    assert_eq!(texts(&log.search("info", 10)), ["INFO 1", "INFO 3"]);
+    assert_eq!(texts(&log.search("o", 2)), ["INFO 3", "ERROR 5"]);
+    assert_eq!(log.search("", 10).len(), 6, "an empty text matches every message");
+    assert_eq!(texts(&log.search("", 1)), ["ERROR 5"]);
+    assert!(log.search("missing", 10).is_empty());
+    assert!(log.search("info", 0).is_empty());
+    // Les messages horodatés exactement au moment donné sont exclus
+    assert_eq!(texts(&log.since(UNIX_EPOCH + Duration::from_secs(3))), ["WARN 4", "ERROR 5"]);
+
+    // Les messages sous la verbosité ne vont pas jusqu'au hub
+    let (_guard, messages) = hub(100);
+    assert!(!is_logged(Debug) && is_logged(Info), "the default verbosity drops the debug messages only");
+    set_log_verbosity(Warning);
+    assert!(!is_logged(Info) && !is_logged(GameEvent) && is_logged(Warning) && is_logged(Error));
+    for message_type in by_severity {
+        add_message(&messages, message_type.to_level().to_string(), message_type, MessageOrigin::Game);
+    }
+    add_messages(&messages, vec!["batch".to_string(); 3], Info, MessageOrigin::Game);
+    messages.flush();
+    set_log_verbosity(AppDefines::LOG_VERBOSITY);
+    let log = messages.log();
+    assert_eq!(texts(&log.to_vec()), ["WARN", "ERROR"]);
+    assert_eq!(log.added(), 2, "the dropped messages take no sequence number");
+}
+
+#[test]
+fn messages_are_exported_as_text_or_json_lines() {
+    let exported = vec![
+        message("Server started", MessageType::Info, 1_714_571_112),
+        StyledMessage::new("Client \"bob\"\nleft".to_string(), MessageType::Warning, MessageOrigin::Network, at_millis(1_714_571_113_250)),
+        message("Round won", MessageType::GameEvent, 1_714_571_114),
+    ];
+    let dir = std::env::temp_dir().join(format!("message_export_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let text = dir.join("messages.txt");
+    assert_eq!(export_messages(&exported, &text, ExportFormat::Text), Ok(3));
+    let lines: Vec<String> = std::fs::read_to_string(&text).unwrap().lines().map(str::to_string).collect();
+    assert_eq!(lines, [
+        "2024-05-01T13:45:12.000Z [INFO] [GAME] Server started",
+        "2024-05-01T13:45:13.250Z [WARN] [NET] Client \"bob\" left",
+        "2024-05-01T13:45:14.000Z [EVENT] [GAME] Round won",
+    ]);
+
+    let json = dir.join("messages.jsonl");
+    assert_eq!(export_messages(&exported, &json, ExportFormat::JsonLines), Ok(3));
+    let records: Vec<serde_json::Value> = std::fs::read_to_string(&json).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[1], serde_json::json!({
+        "timestamp": "2024-05-01T13:45:13.250Z",
+        "level": "WARN",
+        "origin": "NET",
+        "text": "Client \"bob\"\nleft",
+    }), "the JSON keeps the text as is");
+    assert_eq!(records[2]["level"], "EVENT");
+
+    // Un nouvel export remplace le fichier
+    assert_eq!(export_messages(&exported[..1], &text, ExportFormat::Text), Ok(1));
+    assert_eq!(std::fs::read_to_string(&text).unwrap().lines().count(), 1);
+    assert_eq!(export_messages(&[], &text, ExportFormat::Text), Ok(0));
+    assert_eq!(std::fs::read_to_string(&text).unwrap(), "");
+
+    for unwritable in [dir.join("missing").join("messages.txt"), dir.clone()] {
+        let error = export_messages(&exported, &unwritable, ExportFormat::JsonLines).unwrap_err();
+        assert!(error.starts_with(&format!("Could not write {}: ", unwritable.display())), "{}", error);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(ExportFormat::from_name("TEXT"), Some(ExportFormat::Text));
+    assert_eq!(ExportFormat::from_name("txt"), Some(ExportFormat::Text));
+    assert_eq!(ExportFormat::from_name("Json"), Some(ExportFormat::JsonLines));
+    assert_eq!(ExportFormat::from_name("jsonl"), Some(ExportFormat::JsonLines));
+    assert_eq!(ExportFormat::from_name("csv"), None);
+}
+
+#[test]
+fn no_message_sent_below_the_capacity_is_lost() {
+    let (_guard, messages) = hub(AppDefines::MESSAGE_LOG_CAPACITY);
+    let (threads, sends) = (8, 100);
+    // Chaque fil envoie un message seul, un lot de deux et un message de chat par tour
+    let per_thread = sends * 4;
+    assert!(threads * per_thread <= AppDefines::MESSAGE_LOG_CAPACITY);
+
+    // Un lecteur prend des copies pendant les envois, sans les ralentir ni rien perdre
+    let reader = {
+        let messages = messages.clone();
+        thread::spawn(move || {
+            let mut last = 0;
+            for _ in 0..1000 {
+                let added = messages.log().added();
+                assert!(added >= last, "a copy never goes back");
+                last = added;
+            }
+        })
+    };
+    let producers: Vec<_> = (0..threads).map(|thread| {
+        let messages = messages.clone();
+        thread::spawn(move || {
+            for index in 0..sends {
+                add_message(&messages, format!("{} single {}", thread, index), MessageType::Warning, MessageOrigin::Network);
+                add_messages(&messages, vec![format!("{} batch {} a", thread, index), format!("{} batch {} b", thread, index)], MessageType::Info, MessageOrigin::Game);
+                add_chat_message(&messages, &format!("client{}", thread), &format!("chat {}", index));
+            }
+        })
+    }).collect();
+    for handle in producers {
+        handle.join().unwrap();
+    }
+    reader.join().unwrap();
+    messages.flush();
+
+    let log = messages.log();
+    assert_eq!((log.added(), log.dropped()), ((threads * per_thread) as u64, 0));
+    let mut received: Vec<String> = log.to_vec().into_iter().map(|message| message.text).collect();
+    let mut sent = Vec::new();
+    for thread in 0..threads {
+        for index in 0..sends {
+            sent.push(format!("{} single {}", thread, index));
+            sent.push(format!("{} batch {} a", thread, index));
+            sent.push(format!("{} batch {} b", thread, index));
+            sent.push(format!("client{}: chat {}", thread, index));
+        }
+    }
+    received.sort();
+    sent.sort();
+    assert_eq!(received, sent, "the log holds every message sent, once");
+
+    // Les deux messages d'un lot se suivent dans le journal
+    let log = log.to_vec();
+    for (index, message) in log.iter().enumerate().filter(|(_, message)| message.text.ends_with(" a")) {
+        assert_eq!(log[index + 1].text, format!("{}b", message.text.trim_end_matches('a')));
+    }
+    assert_eq!(log.iter().filter(|message| message.chat().is_some()).count(), threads * sends);
+}
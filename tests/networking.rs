@@ -0,0 +1,520 @@
+mod harness;
+
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use harness::{read_text_frame, send_text_frame, wait_for, web_socket_upgrade, TestServer};
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::client::BotClient;
+use universal_rust_server_software::game_logic::GameLogic;
+use universal_rust_server_software::server::server_thread::{ServerSettings, ServerThread};
+use universal_rust_server_software::types::MessageHub;
+
+/// Opens a raw connection to a test server and reads its greeting.
+fn connect_raw(server: &TestServer) -> (TcpStream, BufReader<TcpStream>) {
+    let client = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    client.set_nodelay(true).unwrap();
+    let mut reader = BufReader::new(client.try_clone().unwrap());
+    let mut greeting = String::new();
+    reader.read_line(&mut greeting).unwrap();
+    assert!(greeting.starts_with(AppDefines::HELLO), "no greeting: {}", greeting);
+    (client, reader)
+}
+
+/// Reads the next line sent by the server, without its line ending.
+fn read_reply(reader: &mut BufReader<TcpStream>) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line.trim_end().to_string()
+}
+
+#[test]
+fn reset_connection_leaks_no_entity() {
+    // Un port libre, rendu aussitôt au serveur
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let mut settings = ServerSettings::new();
+    settings.reconnect_grace_secs = 0;
+    settings.log_path = String::new();
+    settings.web_socket_port = 0;
+    settings.admin_control_port = 0;
+    settings.udp_actuators = false;
+    let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+    let game_logic = Arc::new(Mutex::new(GameLogic::new()));
+    let server = ServerThread::new(vec!["127.0.0.1".to_string()], port, messages.clone(), Arc::new(Mutex::new(settings)), Arc::clone(&game_logic));
+    server.start();
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    writeln!(client, "{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION).unwrap();
+    assert!(wait_for(|| game_logic.lock().unwrap().entities.len() == 1), "the client got no entity");
+
+    // Le salut et la réponse au HELLO ne sont jamais lus : fermer la connexion envoie un RST
+    drop(client);
+    assert!(wait_for(|| game_logic.lock().unwrap().entities.is_empty()), "the entity of the reset client was not removed");
+
+    server.stop();
+    messages.flush();
+    let log = messages.log();
+    let texts: Vec<String> = log.to_vec().iter().map(|message| message.text.clone()).collect();
+    assert!(texts.iter().any(|text| text.contains("reset by the client")), "the reset was not logged: {:?}", texts);
+    assert!(texts.iter().any(|text| text.contains("disconnected, entity")), "the disconnection was not logged: {:?}", texts);
+    assert!(!texts.iter().any(|text| text.contains("panicked")), "a handler panicked: {:?}", texts);
+}
+
+#[test]
+fn commands_sent_byte_by_byte_are_reassembled() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+
+    // Chaque octet part dans son propre segment
+    let command = format!("{}{}{}\n", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION);
+    for byte in command.as_bytes() {
+        client.write_all(&[*byte]).unwrap();
+        client.flush().unwrap();
+        thread::sleep(Duration::from_millis(2));
+    }
+
+    let reply = read_reply(&mut reader);
+    assert!(reply.starts_with(&format!("{}{}{}", AppDefines::OK_REPLY, AppDefines::ARGUMENT_SEP, AppDefines::HELLO)), "{}", reply);
+    assert!(wait_for(|| server.entity_count() == 1), "the fragmented handshake gave no entity");
+}
+
+#[test]
+fn commands_read_together_get_one_reply_each() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+
+    let ping = |stamp: &str| format!("{}{}{}\n", AppDefines::PING, AppDefines::ARGUMENT_SEP, stamp);
+    client.write_all(format!("{}{}", ping("first"), ping("second")).as_bytes()).unwrap();
+
+    for stamp in ["first", "second"] {
+        let reply = read_reply(&mut reader);
+        assert_eq!(reply.split(AppDefines::ARGUMENT_SEP).nth(2), Some(stamp), "{}", reply);
+    }
+}
+
+#[test]
+fn lines_longer_than_the_cap_drop_the_client() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+
+    // Une ligne de la taille maximale est lue, et refusée comme une commande inconnue
+    let longest = "X".repeat(AppDefines::MAX_LINE_LENGTH - 1);
+    client.write_all(format!("{}\n", longest).as_bytes()).unwrap();
+    let reply = read_reply(&mut reader);
+    assert!(reply.starts_with(AppDefines::ERROR_REPLY), "the longest line was not answered: {:.40}", reply);
+
+    // Un octet de plus sans fin de ligne, et le client est coupé
+    client.write_all("X".repeat(AppDefines::MAX_LINE_LENGTH + 1).as_bytes()).unwrap();
+    let mut rest = Vec::new();
+    match reader.read_to_end(&mut rest) {
+        Ok(_) => {}
+        Err(e) => assert!(!matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut), "the client was not dropped: {}", e),
+    }
+    let texts = server.message_texts();
+    assert!(texts.iter().any(|text| text.contains("longer than")), "the overflow was not logged: {:?}", texts);
+}
+
+#[test]
+fn pipelined_requests_get_one_reply_line_each_in_order() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+    let sep = AppDefines::ARGUMENT_SEP;
+
+    for count in [1, 2, 5] {
+        // Chaque ligne porte deux commandes, répondues ensemble sur une seule ligne
+        let requests: String = (0..count)
+            .map(|index| format!("{ping}{sep}{count}-{index}a{}{ping}{sep}{count}-{index}b\n", AppDefines::COMMAND_SEP, ping = AppDefines::PING))
+            .collect();
+        client.write_all(requests.as_bytes()).unwrap();
+
+        for index in 0..count {
+            let reply = read_reply(&mut reader);
+            let stamps: Vec<&str> = reply.split(AppDefines::COMMAND_SEP).map(|part| part.split(sep).nth(2).unwrap_or_default()).collect();
+            let expected = [format!("{}-{}a", count, index), format!("{}-{}b", count, index)];
+            assert_eq!(stamps, expected, "reply {} of {}: {}", index, count, reply);
+        }
+    }
+}
+
+#[test]
+fn the_server_steps_the_game_at_its_tick_rate() {
+    let server = TestServer::start_with(|settings, _| settings.set_tick_hz(50.0).unwrap());
+    let mut bot = server.connect();
+    let before = bot.status().unwrap().unwrap();
+    bot.set_motors(1.0, 1.0).unwrap();
+    thread::sleep(Duration::from_millis(500));
+    let after = bot.status().unwrap().unwrap();
+    let moved = (after.x - before.x).hypot(after.y - before.y);
+    assert!(moved > 1.0, "the entity moved {} from ({}, {})", moved, before.x, before.y);
+    assert_eq!(server.game_logic.lock().unwrap().physics_engine.tick_rate(), 50.0);
+}
+
+#[test]
+fn stopping_the_server_closes_the_connections_and_joins_its_threads() {
+    let server = TestServer::start();
+    let (_client, mut reader) = connect_raw(&server);
+    let stopped_at = Instant::now();
+    let client = thread::spawn(move || {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return (lines, stopped_at.elapsed()),
+                Ok(_) => lines.push(line.trim_end().to_string()),
+            }
+        }
+    });
+    server.server.stop();
+
+    let (lines, closed_after) = client.join().unwrap();
+    assert!(lines.iter().any(|line| line.starts_with(AppDefines::SERVER_SHUTDOWN)), "no shutdown notice: {:?}", lines);
+    assert!(closed_after < Duration::from_secs(1), "the connection closed after {:?}", closed_after);
+    let texts = server.message_texts();
+    assert!(texts.iter().any(|text| text.contains("Server stopped")));
+    assert!(!texts.iter().any(|text| text.contains("still running")), "threads were left behind: {:?}", texts);
+}
+
+#[test]
+fn five_commands_on_a_line_get_five_parts_on_one_line() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+
+    let line = [AppDefines::PING; 5].join(AppDefines::COMMAND_SEP);
+    writeln!(client, "{}", line).unwrap();
+    let reply = read_reply(&mut reader);
+    let parts: Vec<&str> = reply.split(AppDefines::COMMAND_SEP).collect();
+    assert_eq!(parts.len(), 5, "{}", reply);
+    assert!(parts.iter().all(|part| part.starts_with(AppDefines::PING)), "{}", reply);
+
+    // Une seule ligne de réponse : la suivante répond à la requête suivante
+    writeln!(client, "{}{}next", AppDefines::PING, AppDefines::ARGUMENT_SEP).unwrap();
+    let next = read_reply(&mut reader);
+    assert_eq!(next.split(AppDefines::ARGUMENT_SEP).nth(2), Some("next"), "{}", next);
+}
+
+#[test]
+fn empty_replies_keep_the_parts_aligned() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+    writeln!(client, "{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION).unwrap();
+    read_reply(&mut reader);
+
+    // Sans zone, ZONE répond EMPTY, tout comme la commande vide entre deux séparateurs
+    let (sep, ping) = (AppDefines::ARGUMENT_SEP, AppDefines::PING);
+    writeln!(client, "{ping}{sep}first{c}{zone}{c}{c}{ping}{sep}last", c = AppDefines::COMMAND_SEP, zone = AppDefines::QUERY_ZONE).unwrap();
+    let reply = read_reply(&mut reader);
+    let parts: Vec<&str> = reply.split(AppDefines::COMMAND_SEP).collect();
+    assert_eq!(parts.len(), 4, "{}", reply);
+    let stamp = |part: &str| part.split(sep).nth(2).map(str::to_string);
+    assert_eq!(stamp(parts[0]).as_deref(), Some("first"), "{}", reply);
+    assert_eq!(&parts[1..3], [AppDefines::EMPTY_REPLY, AppDefines::EMPTY_REPLY]);
+    assert_eq!(stamp(parts[3]).as_deref(), Some("last"), "{}", reply);
+}
+
+/// Reads from a connection until the server closes it.
+///
+/// # Returns
+/// Whether it was closed before the read timeout of the connection.
+fn closed_by_server(reader: &mut BufReader<TcpStream>) -> bool {
+    let mut rest = Vec::new();
+    match reader.read_to_end(&mut rest) {
+        Ok(_) => true,
+        Err(error) => error.kind() == ErrorKind::ConnectionReset,
+    }
+}
+
+#[test]
+fn silent_clients_time_out() {
+    let server = TestServer::start_with(|settings, _| settings.set_connection_timeout_delay(1).unwrap());
+    let (_client, mut reader) = connect_raw(&server);
+    let connected = Instant::now();
+
+    assert!(closed_by_server(&mut reader), "the silent client was not dropped");
+    assert!(connected.elapsed() >= Duration::from_secs(1), "dropped after {:?}, before the delay", connected.elapsed());
+    assert!(wait_for(|| server.message_texts().iter().any(|text| text.starts_with("[WARNING] Connection timeout"))), "the timeout was not logged");
+}
+
+#[test]
+fn timeouts_changed_at_runtime_drop_silent_clients_on_the_new_schedule() {
+    let server = TestServer::start_with(|settings, _| settings.set_connection_timeout_delay(60).unwrap());
+    let (_client, mut reader) = connect_raw(&server);
+    let connected = Instant::now();
+
+    // Le client reste sous l'ancien délai, puis le nouveau, plus court, le rattrape
+    thread::sleep(Duration::from_millis(1500));
+    server.settings.lock().unwrap().set_connection_timeout_delay(2).unwrap();
+    assert!(closed_by_server(&mut reader), "the silent client was not dropped");
+    let dropped = connected.elapsed();
+    assert!(dropped >= Duration::from_secs(2), "dropped after {:?}, before the new delay", dropped);
+    assert!(dropped < Duration::from_secs(4), "dropped after {:?}, long after the new delay", dropped);
+    assert!(wait_for(|| server.message_texts().iter().any(|text| text.starts_with("[WARNING] Connection timeout"))), "the timeout was not logged");
+
+    // Un délai nul est refusé et laisse le précédent en place
+    assert!(server.settings.lock().unwrap().set_connection_timeout_delay(0).is_err());
+    assert_eq!(server.settings.lock().unwrap().connection_timeout_delay(), 2);
+}
+
+#[test]
+fn json_and_text_commands_get_the_same_answers() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+    let mut exchange = |line: &str| {
+        writeln!(client, "{}", line).unwrap();
+        read_reply(&mut reader)
+    };
+    exchange(&format!("{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION));
+    let motors = || {
+        let logic = server.game_logic.lock().unwrap();
+        (logic.entities[0].motor_left, logic.entities[0].motor_right)
+    };
+
+    // Réponses JSON, aux commandes JSON comme aux commandes texte
+    exchange(&format!("{}{}{}", AppDefines::FORMAT, AppDefines::ARGUMENT_SEP, AppDefines::FORMAT_JSON));
+    let parse = |reply: String| serde_json::from_str::<serde_json::Value>(&reply).unwrap();
+    let json_actuator = parse(exchange(r#"{"cmd":"MotL","args":[0.8]}"#));
+    assert_eq!(json_actuator, serde_json::json!({"cmd": "MotL", "ok": true, "text": "MotL set to 0.8"}));
+    assert_eq!(motors().0, 0.8);
+    let text_actuator = parse(exchange("MotL=0.3"));
+    assert_eq!(text_actuator, serde_json::json!({"cmd": "MotL", "ok": true, "text": "MotL set to 0.3"}));
+    assert_eq!(motors().0, 0.3);
+    let json_query = parse(exchange(r#"{"cmd":"STATUS"}"#));
+    assert_eq!(json_query["cmd"], AppDefines::QUERY_STATUS);
+    assert_eq!(json_query["state"], AppDefines::STATUS_ALIVE);
+    assert_eq!(parse(exchange(AppDefines::QUERY_STATUS)), json_query);
+
+    // Réponses texte, aux commandes JSON comme aux commandes texte
+    exchange(&format!(r#"{{"cmd":"{}","args":["{}"]}}"#, AppDefines::FORMAT, AppDefines::FORMAT_TEXT));
+    assert_eq!(exchange(r#"{"cmd":"MotR","args":[0.6]}"#), "OK=MotR=MotR set to 0.6");
+    assert_eq!(exchange("MotR=0.4"), "OK=MotR=MotR set to 0.4");
+    assert_eq!(motors().1, 0.4);
+    let text_query = exchange(AppDefines::QUERY_STATUS);
+    assert!(text_query.starts_with(&format!("{}={}", AppDefines::QUERY_STATUS, AppDefines::STATUS_ALIVE)), "{}", text_query);
+    assert_eq!(exchange(r#"{"cmd":"STATUS"}"#), text_query);
+}
+
+#[test]
+fn web_socket_clients_set_actuators_and_query() {
+    let web_socket_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let server = TestServer::start_with(|settings, _| settings.web_socket_port = web_socket_port);
+    let mut stream = TcpStream::connect(("127.0.0.1", web_socket_port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let response = web_socket_upgrade(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 101"), "the upgrade was refused: {}", response);
+    let greeting = read_text_frame(&mut stream);
+    assert!(greeting.starts_with(AppDefines::HELLO), "no greeting: {}", greeting);
+
+    let sep = AppDefines::ARGUMENT_SEP;
+    send_text_frame(&mut stream, &format!("{}{sep}{}", AppDefines::HELLO, AppDefines::PROTOCOL_VERSION));
+    let hello = read_text_frame(&mut stream);
+    assert!(hello.starts_with(&format!("{}{sep}{}", AppDefines::OK_REPLY, AppDefines::HELLO)), "the handshake failed: {}", hello);
+
+    send_text_frame(&mut stream, &format!("{}{sep}0.7", AppDefines::ACTUATOR_MOTOR_LEFT));
+    assert_eq!(read_text_frame(&mut stream), "OK=MotL=MotL set to 0.7");
+    assert_eq!(server.game_logic.lock().unwrap().entities[0].motor_left, 0.7);
+    send_text_frame(&mut stream, AppDefines::QUERY_STATUS);
+    let status = read_text_frame(&mut stream);
+    assert!(status.starts_with(&format!("{}{sep}{}", AppDefines::QUERY_STATUS, AppDefines::STATUS_ALIVE)), "no status: {}", status);
+}
+
+#[test]
+fn the_last_udp_datagram_sets_the_motors() {
+    let server = TestServer::start_with(|settings, _| {
+        settings.udp_actuators = true;
+        // Sans pas de jeu, le budget ne serait jamais rendu
+        settings.command_budget_per_step = 0;
+    });
+    let mut client = BotClient::connect("127.0.0.1", server.port).unwrap();
+    let token = client.hello().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let sep = AppDefines::COMMAND_SEP;
+    for index in 1..=100 {
+        let value = index as f32 / 100.0;
+        let datagram = format!("{token}{sep}MotL={value}{sep}MotR={}", value / 2.0);
+        socket.send_to(datagram.as_bytes(), ("127.0.0.1", server.port)).unwrap();
+    }
+
+    let motors = || {
+        let logic = server.game_logic.lock().unwrap();
+        (logic.entities[0].motor_left, logic.entities[0].motor_right)
+    };
+    assert!(wait_for(|| motors() == (1.0, 0.5)), "the last datagram was not applied: {:?}", motors());
+    // Les datagrammes sont appliqués dans l'ordre d'arrivée : aucun plus ancien ne repasse après le dernier
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(motors(), (1.0, 0.5));
+}
+
+#[test]
+fn clients_over_the_limit_are_refused() {
+    let limit = 3;
+    let server = TestServer::start_with(|settings, _| settings.max_clients = limit);
+    let _players: Vec<_> = (0..limit).map(|_| server.connect()).collect();
+    assert_eq!(server.entity_count(), limit);
+
+    for _ in 0..2 {
+        let client = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut reader = BufReader::new(client);
+        assert_eq!(read_reply(&mut reader), "ERR=11=server_full=Server full, try again later");
+        // Une seule ligne, puis la connexion est fermée
+        let mut rest = String::new();
+        assert_eq!(reader.read_line(&mut rest).unwrap(), 0, "the refused client got more: {}", rest);
+    }
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(server.entity_count(), limit, "a refused client got an entity");
+}
+
+#[test]
+fn full_and_locked_lobbies_refuse_with_their_own_error() {
+    let server = TestServer::start_with(|settings, _| settings.max_clients = 1);
+    let hello = format!("{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION);
+
+    // Partie pleine, non verrouillée : refusé dès la connexion
+    let player = server.connect();
+    assert_eq!(server.entity_count(), 1);
+    let client = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut reader = BufReader::new(client);
+    assert_eq!(read_reply(&mut reader), "ERR=11=server_full=Server full, try again later");
+    assert!(closed_by_server(&mut reader), "the refused client stays connected");
+    drop(player);
+    assert!(wait_for(|| server.entity_count() == 0), "the player left its entity behind");
+
+    // Place libre, partie verrouillée : accueilli sans entité, refusé à la première commande de jeu
+    server.settings.lock().unwrap().lobby_locked = true;
+    let (mut client, mut reader) = connect_raw(&server);
+    writeln!(client, "{}", hello).unwrap();
+    let welcome = read_reply(&mut reader);
+    assert!(welcome.ends_with("Match in progress, send SPECTATE or RECONNECT to get in"), "{}", welcome);
+    writeln!(client, "{}{}1", AppDefines::ACTUATOR_MOTOR_LEFT, AppDefines::ARGUMENT_SEP).unwrap();
+    assert_eq!(read_reply(&mut reader), "ERR=17=match_locked=Match in progress, send SPECTATE or RECONNECT to get in");
+    assert!(closed_by_server(&mut reader), "the refused client stays connected");
+    assert!(wait_for(|| server.message_texts().iter().any(|text| text.starts_with("[WARNING] Lobby locked, client"))), "the refusal was not logged");
+    assert_eq!(server.entity_count(), 0, "a refused client got an entity");
+
+    // Place libre, partie déverrouillée : la connexion suivante a son entité, sans redémarrage
+    // La place du client refusé se libère juste après la fermeture de sa connexion
+    server.settings.lock().unwrap().lobby_locked = false;
+    let mut player = None;
+    assert!(wait_for(|| {
+        if let Ok(mut bot) = BotClient::connect("127.0.0.1", server.port) {
+            if bot.hello().is_ok() {
+                player = Some(bot);
+            }
+        }
+        player.is_some()
+    }), "the unlocked lobby refused the next client");
+    assert_eq!(server.entity_count(), 1);
+}
+
+#[test]
+fn pings_echo_the_timestamp_unchanged() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+    let mut exchange = |line: &str| {
+        writeln!(client, "{}", line).unwrap();
+        read_reply(&mut reader)
+    };
+    let sep = AppDefines::ARGUMENT_SEP;
+    // Ni lu comme un nombre ni reformaté : un entier trop grand, des zéros en tête, des décimales
+    let stamps = ["1697040000123", "99999999999999999999999", "000042", "1697040000.1230", "-0.0", "t+5ms"];
+
+    // Avant le handshake comme après
+    for stamp in stamps {
+        let reply = exchange(&format!("{}{sep}{stamp}", AppDefines::PING));
+        assert_eq!(reply.split(sep).nth(2), Some(stamp), "{}", reply);
+    }
+    exchange(&format!("{}{sep}{}", AppDefines::HELLO, AppDefines::PROTOCOL_VERSION));
+    for stamp in stamps {
+        let reply = exchange(&format!("{}{sep}{stamp}", AppDefines::PING));
+        assert_eq!(reply.split(sep).nth(2), Some(stamp), "{}", reply);
+    }
+
+    exchange(&format!("{}{sep}{}", AppDefines::FORMAT, AppDefines::FORMAT_JSON));
+    for stamp in stamps {
+        let reply: serde_json::Value = serde_json::from_str(&exchange(&format!("{}{sep}{stamp}", AppDefines::PING))).unwrap();
+        assert_eq!(reply["client_timestamp"], stamp, "{}", reply);
+    }
+}
+
+#[test]
+fn ipv6_clients_play_beside_ipv4_clients() {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let mut settings = ServerSettings::new();
+    settings.reconnect_grace_secs = 0;
+    settings.log_path = String::new();
+    settings.web_socket_port = 0;
+    settings.admin_control_port = 0;
+    settings.udp_actuators = false;
+    let messages = MessageHub::new(AppDefines::MESSAGE_LOG_CAPACITY);
+    let game_logic = Arc::new(Mutex::new(GameLogic::new()));
+    let addresses = vec!["::1".to_string(), "127.0.0.1".to_string()];
+    let server = ServerThread::new(addresses, port, messages.clone(), Arc::new(Mutex::new(settings)), Arc::clone(&game_logic));
+    server.start();
+
+    let mut ipv6 = BotClient::connect("::1", port).unwrap();
+    let mut ipv4 = BotClient::connect("127.0.0.1", port).unwrap();
+    ipv6.hello().unwrap();
+    ipv4.hello().unwrap();
+    ipv6.set_name("Six").unwrap();
+    ipv4.set_name("Four").unwrap();
+    // Chaque connexion retrouve sa propre entité par son adresse
+    ipv6.set_motors(0.25, 0.5).unwrap();
+    ipv4.set_motors(0.75, 1.0).unwrap();
+    let motors = |name: &str| {
+        let logic = game_logic.lock().unwrap();
+        let entity = logic.entity_by_name(name).unwrap();
+        (entity.motor_left, entity.motor_right)
+    };
+    assert_eq!((motors("Six"), motors("Four")), ((0.25, 0.5), (0.75, 1.0)));
+    assert!(ipv6.status().unwrap().is_some());
+
+    ipv6.quit().unwrap();
+    assert!(wait_for(|| game_logic.lock().unwrap().entities.len() == 1), "the entity of the IPv6 client was not removed");
+    assert!(game_logic.lock().unwrap().entity_by_name("Four").is_some(), "the IPv4 client lost its entity");
+    assert!(ipv4.status().unwrap().is_some());
+    server.stop();
+}
+
+#[test]
+fn messy_commands_are_read_as_the_clean_ones() {
+    let server = TestServer::start();
+    let (mut client, mut reader) = connect_raw(&server);
+    writeln!(client, "{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION).unwrap();
+    read_reply(&mut reader);
+    let motor_left = || server.game_logic.lock().unwrap().entities[0].motor_left;
+
+    // (ligne envoyée, début de la réponse, moteur gauche ensuite)
+    let cases = [
+        ("motl=0.7\n", "OK=MotL=MotL set to 0.7", 0.7),
+        ("MOTL = 0.2\n", "OK=MotL=MotL set to 0.2", 0.2),
+        ("  MotL=.7  \n", "OK=MotL=MotL set to 0.7", 0.7),
+        ("MotL=0.3\r\n", "OK=MotL=MotL set to 0.3", 0.3),
+        ("mOtL\t=\t5e-1\n", "OK=MotL=MotL set to 0.5", 0.5),
+        // Les refus laissent le moteur tel quel
+        ("motl=0,7\n", "ERR=3=bad_value=", 0.5),
+        ("motl=abc\n", "ERR=3=bad_value=", 0.5),
+        ("motl=\n", "ERR=3=bad_value=", 0.5),
+        ("mmotl=0.7\n", "ERR=1=unknown_command=Unknown command: mmotl", 0.5),
+        ("=0.7\n", "ERR=1=unknown_command=", 0.5),
+        ("MOTL=1e3\n", "CLAMPED=MotL=1.000=1000.000000", 1.0),
+        ("Status\r\n", "STATUS=ALIVE=", 1.0),
+        ("ping = 42\n", "PING=", 1.0),
+    ];
+    for (line, reply, motor) in cases {
+        client.write_all(line.as_bytes()).unwrap();
+        let answer = read_reply(&mut reader);
+        assert!(answer.starts_with(reply), "{:?} was answered {:?}", line, answer);
+        assert_eq!(motor_left(), motor, "after {:?}", line);
+    }
+    // Les espaces autour des commandes d'une même ligne sont ignorés aussi
+    writeln!(client, "energy # status").unwrap();
+    let answer = read_reply(&mut reader);
+    let parts: Vec<&str> = answer.split(AppDefines::COMMAND_SEP).collect();
+    assert!(parts.len() == 2 && parts[0].starts_with("ENERGY=") && parts[1].starts_with("STATUS=ALIVE="), "{}", answer);
+    writeln!(client, "exit").unwrap();
+    assert!(closed_by_server(&mut reader), "exit in lower case did not close the connection");
+}
@@ -0,0 +1,158 @@
+use rapier2d::prelude::*;
+use universal_rust_server_software::game_logic::arena_config::ArenaConfig;
+use universal_rust_server_software::physics::body_identity::BodyIdentity;
+use universal_rust_server_software::physics::physics::PhysicsEngine;
+
+/// Adds a free ball at `position`, moving at `velocity`.
+fn insert_ball(physics_engine: &mut PhysicsEngine, position: Vector<f32>, velocity: Vector<f32>) -> RigidBodyHandle {
+    let body = RigidBodyBuilder::dynamic().translation(position).linvel(velocity).build();
+    let handle = physics_engine.bodies.insert(body);
+    let collider = ColliderBuilder::ball(5.0).build();
+    physics_engine.colliders.insert_with_parent(collider, handle, &mut physics_engine.bodies);
+    handle
+}
+
+#[test]
+fn two_seconds_at_60_hz_move_a_body_by_twice_its_speed() {
+    let mut physics_engine = PhysicsEngine::default();
+    physics_engine.set_tick_rate(60.0);
+    assert_eq!(physics_engine.tick_rate(), 60.0);
+    assert!((physics_engine.integration_parameters.dt - 1.0 / 60.0).abs() < 1e-6);
+    let ball = insert_ball(&mut physics_engine, vector![0.0, 0.0], vector![100.0, 0.0]);
+
+    for _ in 0..120 {
+        physics_engine.step(&());
+    }
+    let traveled = physics_engine.bodies[ball].translation().x;
+    assert!((traveled - 200.0).abs() < 0.5, "120 steps of 1/60 s at 100 units per second: {}", traveled);
+}
+
+#[test]
+fn identities_round_trip_through_user_data() {
+    let identities = [
+        BodyIdentity::Unknown,
+        BodyIdentity::Entity(1),
+        BodyIdentity::Entity(u32::MAX),
+        BodyIdentity::Bullet(Some(7)),
+        BodyIdentity::Bullet(Some(u32::MAX)),
+        BodyIdentity::Bullet(None),
+        BodyIdentity::Obstacle,
+        BodyIdentity::Boundary,
+        BodyIdentity::PowerUp,
+        BodyIdentity::Mine(3),
+        BodyIdentity::ControlPoint,
+        BodyIdentity::Hazard,
+    ];
+    for identity in identities {
+        assert_eq!(BodyIdentity::decode(identity.encode()), identity);
+    }
+    // Chaque identité a son propre code
+    for (index, identity) in identities.iter().enumerate() {
+        assert!(identities[index + 1..].iter().all(|other| other.encode() != identity.encode()), "{} is encoded apart", identity);
+    }
+    // Des données écrites par un autre que `encode` se lisent comme inconnues
+    assert_eq!(BodyIdentity::decode(0), BodyIdentity::Unknown);
+    assert_eq!(BodyIdentity::decode(42), BodyIdentity::Unknown);
+    assert_eq!(BodyIdentity::decode(u128::MAX), BodyIdentity::Unknown);
+
+    let mut physics_engine = PhysicsEngine::default();
+    let ball = insert_ball(&mut physics_engine, vector![0.0, 0.0], vector![0.0, 0.0]);
+    assert_eq!(physics_engine.identify(ball), BodyIdentity::Unknown, "a body no one tagged");
+    physics_engine.bodies[ball].user_data = BodyIdentity::Entity(12).encode();
+    let collider = physics_engine.bodies[ball].colliders()[0];
+    assert_eq!(physics_engine.identify(ball), BodyIdentity::Entity(12));
+    assert_eq!(physics_engine.identify_collider(collider), BodyIdentity::Entity(12), "a collider reads the identity of its body");
+}
+
+#[test]
+fn a_body_through_a_sensor_starts_and_stops_one_intersection() {
+    let mut physics_engine = PhysicsEngine::default();
+    let sensor = physics_engine.insert_sensor(SharedShape::ball(20.0), vector![150.0, 0.0], BodyIdentity::PowerUp);
+    let ball = insert_ball(&mut physics_engine, vector![0.0, 0.0], vector![100.0, 0.0]);
+    let collider = physics_engine.bodies[ball].colliders()[0];
+
+    let (mut started, mut stopped) = (0, 0);
+    // Trois secondes à 100 unités par seconde : la balle entre dans le capteur, le traverse et en sort
+    for _ in 0..180 {
+        physics_engine.step(&());
+        assert!(physics_engine.collision_events.is_empty(), "a sensor makes no contact: {:?}", physics_engine.collision_events);
+        for event in &physics_engine.sensor_events {
+            assert!(event.sensor());
+            let pair = [event.collider1(), event.collider2()];
+            assert!(pair.contains(&sensor) && pair.contains(&collider), "{:?}", event);
+            match event {
+                CollisionEvent::Started(..) => started += 1,
+                CollisionEvent::Stopped(..) => stopped += 1,
+            }
+        }
+    }
+    assert_eq!((started, stopped), (1, 1));
+    assert!(physics_engine.bodies[ball].translation().x > 300.0, "the body kept its speed through the sensor");
+
+    // Un capteur retiré pendant l'intersection n'en signale pas la fin
+    let sensor = physics_engine.insert_sensor(SharedShape::ball(20.0), vector![physics_engine.bodies[ball].translation().x + 50.0, 0.0], BodyIdentity::Mine(1));
+    let mut started = 0;
+    while started == 0 {
+        physics_engine.step(&());
+        started = physics_engine.sensor_events.len();
+    }
+    physics_engine.remove_sensor(sensor);
+    assert!(physics_engine.sensor_events.is_empty(), "the events of the last step are purged");
+    for _ in 0..60 {
+        physics_engine.step(&());
+        assert!(physics_engine.sensor_events.is_empty(), "no end of intersection for a removed sensor: {:?}", physics_engine.sensor_events);
+    }
+}
+
+#[test]
+fn bodies_swept_along_the_edges_and_into_the_corners_stay_inside() {
+    let arena = ArenaConfig::new(400.0, 300.0).unwrap();
+    let (width, height) = (arena.width, arena.height);
+    let mut physics_engine = PhysicsEngine::default();
+    physics_engine.setup_boundaries(&arena);
+    assert_eq!(physics_engine.boundary_colliders.len(), 4);
+
+    // Les murs restent hors de l'arène et couvrent ses coins
+    let half_thickness = physics_engine.wall_thickness / 2.0;
+    for &wall in &physics_engine.boundary_colliders {
+        let collider = &physics_engine.colliders[wall];
+        let aabb = collider.compute_aabb();
+        let outside = aabb.maxs.x <= 0.0 || aabb.mins.x >= width || aabb.maxs.y <= 0.0 || aabb.mins.y >= height;
+        assert!(outside, "the wall {:?} eats into the arena", aabb);
+    }
+    for (x, y) in [(-half_thickness, -half_thickness), (width + half_thickness, -half_thickness), (-half_thickness, height + half_thickness), (width + half_thickness, height + half_thickness)] {
+        let covered = physics_engine.boundary_colliders.iter().any(|&wall| {
+            let collider = &physics_engine.colliders[wall];
+            collider.shape().contains_point(collider.position(), &point![x, y])
+        });
+        assert!(covered, "the corner ({}, {}) is closed", x, y);
+    }
+
+    // Des balles lancées vite contre chaque bord, en le longeant presque, et droit dans chaque coin
+    let speed = 3000.0;
+    let mut launches = Vec::new();
+    for step in 1..10 {
+        let along = step as f32 / 10.0;
+        launches.push((vector![width * along, height / 2.0], vector![0.3, 1.0]));
+        launches.push((vector![width * along, height / 2.0], vector![-0.3, -1.0]));
+        launches.push((vector![width / 2.0, height * along], vector![1.0, 0.3]));
+        launches.push((vector![width / 2.0, height * along], vector![-1.0, -0.3]));
+    }
+    for (x, y) in [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)] {
+        launches.push((vector![width / 2.0, height / 2.0], vector![x - width / 2.0, y - height / 2.0]));
+    }
+
+    for (start, direction) in launches {
+        let body = RigidBodyBuilder::dynamic().translation(start).linvel(direction.normalize() * speed).ccd_enabled(true).build();
+        let ball = physics_engine.bodies.insert(body);
+        let collider = ColliderBuilder::ball(5.0).restitution(1.0).friction(0.0).build();
+        physics_engine.colliders.insert_with_parent(collider, ball, &mut physics_engine.bodies);
+        // Le solveur laisse une balle rapide s'enfoncer dans le mur le temps d'un pas, jamais le traverser
+        for _ in 0..120 {
+            physics_engine.step(&());
+            let position = physics_engine.bodies[ball].translation();
+            assert!(arena.contains_with_margin(position.x, position.y, physics_engine.wall_thickness), "a ball launched from {:?} towards {:?} escaped to {:?}", start, direction, position);
+        }
+        physics_engine.bodies.remove(ball, &mut physics_engine.islands, &mut physics_engine.colliders, &mut physics_engine.impulse_joints, &mut physics_engine.multibody_joints, true);
+    }
+}
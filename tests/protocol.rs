@@ -0,0 +1,701 @@
+mod harness;
+
+use std::thread;
+
+use harness::{wait_for, TestServer};
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::bullet::bullet::BulletKind;
+use universal_rust_server_software::client::{BotClient, ClientError};
+use universal_rust_server_software::game_logic::control_point::Side;
+use universal_rust_server_software::game_logic::game_config::GameMode;
+use universal_rust_server_software::game_logic::wave_director::WaveDirector;
+use universal_rust_server_software::game_logic::GameLogic;
+use universal_rust_server_software::obstacles::ObstacleShape;
+use universal_rust_server_software::server::chat::ChatMutes;
+use universal_rust_server_software::server::state_delta::{DeltaFrame, WorldFrame};
+
+#[test]
+fn bots_play_a_round() {
+    let server = TestServer::start();
+    let mut shooter = server.connect();
+    let mut target = server.connect();
+    assert!(shooter.greeting.starts_with(AppDefines::HELLO), "no greeting: {}", shooter.greeting);
+    assert!(wait_for(|| server.entity_count() == 2), "the bots got no entity");
+
+    shooter.set_name("Shooter").unwrap();
+    shooter.set_color(255, 0, 0).unwrap();
+    target.set_name("Target").unwrap();
+    {
+        let logic = server.game_logic.lock().unwrap();
+        let named = logic.entities.iter().find(|entity| entity.name == "Shooter").expect("the name was not applied");
+        assert_eq!((named.color.r(), named.color.g(), named.color.b()), (255, 0, 0));
+    }
+
+    // Les deux moteurs à fond, l'entité avance
+    let position = |bot: &mut BotClient| {
+        let status = bot.status().unwrap().expect("the entity is destroyed");
+        (status.x, status.y)
+    };
+    let start = position(&mut shooter);
+    shooter.set_motors(1.0, 1.0).unwrap();
+    assert!(wait_for(|| position(&mut shooter) != start), "the bot did not move");
+    shooter.set_motors(0.5, 0.5).unwrap();
+
+    // La cible est placée dans l'axe du canon du tireur
+    {
+        let mut logic = server.game_logic.lock().unwrap();
+        let shooter_id = logic.entities.iter().find(|entity| entity.name == "Shooter").unwrap().id;
+        let target_id = logic.entities.iter().find(|entity| entity.name == "Target").unwrap().id;
+        logic.teleport_entity(shooter_id, 600.0, 500.0);
+        let entity = logic.entities.iter().find(|entity| entity.id == shooter_id).unwrap();
+        let angle = GameLogic::gun_angle(entity, &logic.physics_engine.bodies[entity.handle]);
+        let (x, y) = (entity.x + 150.0 * angle.cos(), entity.y + 150.0 * angle.sin());
+        logic.teleport_entity(target_id, x, y);
+    }
+    shooter.set_trigger(true).unwrap();
+    let dead = target.wait_push(AppDefines::PUSH_DEAD).unwrap();
+    assert_eq!(dead.args[0], "Shooter");
+    assert!(dead.arg::<f32>(1).is_some_and(|bearing| bearing.abs() <= 180.0), "no bearing: {}", dead);
+    assert_eq!(target.status().unwrap(), None);
+    shooter.set_trigger(false).unwrap();
+    assert!(shooter.status().unwrap().unwrap().score >= 1, "the hit was not scored");
+
+    shooter.quit().unwrap();
+    assert!(wait_for(|| server.entity_count() <= 1), "the entity of the bot that left was not removed");
+    let texts = server.message_texts();
+    assert!(!texts.iter().any(|text| text.contains("panicked")), "a handler panicked: {:?}", texts);
+}
+
+#[test]
+fn destroyed_entities_report_their_death() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    assert!(wait_for(|| server.entity_count() == 1));
+    assert!(bot.status().unwrap().is_some());
+
+    let mut logic = server.game_logic.lock().unwrap();
+    let id = logic.entities[0].id;
+    logic.remove_entity_by_id(id);
+    drop(logic);
+    assert_eq!(bot.status().unwrap(), None);
+    assert!(matches!(bot.sensors(), Err(ClientError::Refused { code: AppDefines::ERR_NO_ENTITY, .. })));
+}
+
+#[test]
+fn hits_report_where_the_bullets_came_from() {
+    let server = TestServer::start_with(|_, logic| logic.game_config.starting_health = 10_000);
+    let mut shooter = server.connect();
+    let mut target = server.connect();
+    assert!(wait_for(|| server.entity_count() == 2));
+    assert!(target.damage().unwrap().is_empty());
+
+    // Le tireur vise la cible depuis sa droite, quelle que soit l'orientation des corps
+    let expected = {
+        let mut guard = server.game_logic.lock().unwrap();
+        let logic = &mut *guard;
+        let (shooter_id, target_id) = (logic.entities[0].id, logic.entities[1].id);
+        logic.teleport_entity(shooter_id, 600.0, 500.0);
+        logic.teleport_entity(target_id, 750.0, 500.0);
+        let entity = logic.entities.iter_mut().find(|entity| entity.id == shooter_id).unwrap();
+        let rotation = logic.physics_engine.bodies[entity.handle].rotation().angle();
+        // Le canon tire vers l'arrière du corps : une orientation nulle vise l'angle du corps plus un demi-tour
+        let orientation = (0.5 - rotation / std::f32::consts::TAU).rem_euclid(1.0);
+        entity.gun_mut().orientation = orientation as f64;
+        entity.gun_mut().traverse = orientation;
+        let target = logic.entities.iter().find(|entity| entity.id == target_id).unwrap();
+        let rotation = logic.physics_engine.bodies[target.handle].rotation().angle();
+        GameLogic::bearing((750.0, 500.0), rotation, (600.0, 500.0)).to_degrees()
+    };
+    let close = |bearing: f32| ((bearing - expected + 540.0).rem_euclid(360.0) - 180.0).abs() < 5.0;
+
+    shooter.set_trigger(true).unwrap();
+    let mut hits = Vec::new();
+    assert!(wait_for(|| {
+        hits.extend(target.damage().unwrap());
+        !hits.is_empty()
+    }), "the target was not hit");
+    shooter.set_trigger(false).unwrap();
+    assert!(hits.iter().all(|hit| close(hit.bearing) && hit.damage > 0), "{:?} instead of {}", hits, expected);
+    let status = target.command(AppDefines::QUERY_STATUS).unwrap();
+    assert!(status.arg::<f32>(14).is_some_and(close), "{}", status);
+
+    // Chaque coup n'est rapporté qu'une fois
+    assert!(wait_for(|| server.game_logic.lock().unwrap().bullets.is_empty()));
+    target.damage().unwrap();
+    assert!(target.damage().unwrap().is_empty(), "the hits were sent again");
+}
+
+#[test]
+fn names_are_held_by_one_entity() {
+    let server = TestServer::start();
+    let mut first = server.connect();
+    let mut second = server.connect();
+    first.set_name("Rusty").unwrap();
+    assert!(matches!(second.set_name("Rusty"), Err(ClientError::Refused { code: AppDefines::ERR_NAME_TAKEN, .. })));
+    assert!(matches!(second.set_name("Rus,ty"), Err(ClientError::Refused { code: AppDefines::ERR_BAD_VALUE, .. })));
+
+    let found = second.command(&format!("{}=Rusty", AppDefines::QUERY_BY_NAME)).unwrap();
+    assert_eq!(found.code, AppDefines::QUERY_BY_NAME);
+    let id = server.game_logic.lock().unwrap().entity_by_name("Rusty").unwrap().id;
+    assert_eq!(found.arg::<u32>(0), Some(id));
+
+    first.quit().unwrap();
+    assert!(wait_for(|| server.entity_count() == 1));
+    second.set_name("Rusty").unwrap();
+    assert_eq!(second.command(&format!("{}=Nobody", AppDefines::QUERY_BY_NAME)).unwrap().code, AppDefines::EMPTY_REPLY);
+}
+
+#[test]
+fn weapons_are_chosen_by_name() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    assert!(wait_for(|| server.entity_count() == 1));
+
+    let reply = bot.command(&format!("{}=sniper", AppDefines::SET_WEAPON)).unwrap();
+    assert_eq!(reply.code, AppDefines::OK_REPLY);
+    let refused = bot.command(&format!("{}=LASER", AppDefines::SET_WEAPON)).unwrap();
+    assert_eq!((refused.code.as_str(), refused.arg::<u8>(0)), (AppDefines::ERROR_REPLY, Some(AppDefines::ERR_BAD_VALUE)));
+    assert_eq!(server.game_logic.lock().unwrap().entities[0].weapon, BulletKind::Sniper, "the refused weapon changes nothing");
+}
+
+#[test]
+fn gun_commands_take_a_turret_index() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    assert!(wait_for(|| server.entity_count() == 1));
+    server.game_logic.lock().unwrap().entities[0].mount_turrets(&[(0.0, -10.0), (0.0, 10.0)]);
+
+    bot.command(&format!("{}=1=0.9", AppDefines::ACTUATOR_GUN_TRIGGER)).unwrap();
+    bot.command(&format!("{}=0.25", AppDefines::ACTUATOR_GUN_TRAVERSE)).unwrap();
+    let refused = bot.command(&format!("{}=2=1", AppDefines::ACTUATOR_GUN_TRIGGER)).unwrap();
+    assert_eq!((refused.code.as_str(), refused.arg::<u8>(0)), (AppDefines::ERROR_REPLY, Some(AppDefines::ERR_BAD_VALUE)));
+    let logic = server.game_logic.lock().unwrap();
+    let turrets = &logic.entities[0].turrets;
+    assert_eq!((turrets[0].trigger, turrets[1].trigger), (0.0, 0.9));
+    assert_eq!((turrets[0].traverse, turrets[1].traverse), (0.25, 0.5), "without an index, the first turret");
+}
+
+#[test]
+fn closest_obstacle_reports_the_nearest_edge() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    assert!(wait_for(|| server.entity_count() == 1));
+    let mut logic = server.game_logic.lock().unwrap();
+    while logic.remove_obstacle(0).is_some() {}
+    let (x, y) = (logic.entities[0].x, logic.entities[0].y);
+    drop(logic);
+    let query = |bot: &mut BotClient, args: &str| bot.command(&format!("{}{}", AppDefines::QUERY_CLOSEST_OBSTACLE, args)).unwrap();
+    assert_eq!(query(&mut bot, "").code, AppDefines::EMPTY_REPLY);
+
+    let walls = query(&mut bot, &format!("{}{}", AppDefines::ARGUMENT_SEP, AppDefines::CLOSEST_WALLS));
+    assert_eq!(walls.arg::<String>(2).as_deref(), Some(AppDefines::BOUNDARY_SHAPE));
+
+    // Le bord du disque, pas son centre, même quand le monde a bougé depuis
+    let offset = if y < 300.0 { 85.0 } else { -85.0 };
+    server.game_logic.lock().unwrap().add_obstacle_at(x, y + offset, ObstacleShape::Circle { radius: 50.0 }).unwrap();
+    let reply = query(&mut bot, "");
+    assert_eq!(reply.code, AppDefines::QUERY_CLOSEST_OBSTACLE);
+    assert_eq!(reply.arg::<String>(2).as_deref(), Some("CIRCLE"));
+    assert!(reply.arg::<f32>(0).is_some_and(|distance| distance <= 35.0 + 1.0));
+
+    let refused = query(&mut bot, "=ALL");
+    assert_eq!((refused.code.as_str(), refused.arg::<u8>(0)), (AppDefines::ERROR_REPLY, Some(AppDefines::ERR_BAD_VALUE)));
+}
+
+#[test]
+fn delta_subscribers_receive_keyframes() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    bot.set_name("Watcher").unwrap();
+    let resync = |bot: &mut BotClient| bot.command(AppDefines::RESYNC).unwrap();
+    assert_eq!(resync(&mut bot).code, AppDefines::ERROR_REPLY, "RESYNC needs a delta subscription");
+
+    let reply = bot.command(&format!("{}=20={}", AppDefines::SUBSCRIBE, AppDefines::SUBSCRIBE_DELTA)).unwrap();
+    assert_eq!(reply.code, AppDefines::OK_REPLY);
+    let keyframe = DeltaFrame::parse(&bot.wait_push(AppDefines::PUSH_KEYFRAME).unwrap().to_string()).unwrap();
+    let mut world = WorldFrame::default();
+    world.apply(&keyframe);
+    assert_eq!(world.entities.values().map(|entity| entity.name.as_str()).collect::<Vec<_>>(), vec!["Watcher"]);
+
+    let delta = DeltaFrame::parse(&bot.wait_push(AppDefines::PUSH_DELTA).unwrap().to_string()).unwrap();
+    world.apply(&delta);
+    assert_eq!(resync(&mut bot).code, AppDefines::OK_REPLY);
+    bot.take_pushes();
+    assert!(bot.wait_push(AppDefines::PUSH_KEYFRAME).is_ok());
+}
+
+#[test]
+fn control_points_are_queried_and_pushed() {
+    let map = std::env::temp_dir().join(format!("protocol_points_{}.json", std::process::id()));
+    let points = r#"[{ "name": "hill, north", "x": 600, "y": 300, "radius": 60 }]"#;
+    std::fs::write(&map, format!(r#"{{ "format_version": 1, "obstacles": [], "control_points": {} }}"#, points)).unwrap();
+    let server = TestServer::start_with(|_, logic| logic.load_map(&map).unwrap());
+    let mut bot = server.connect();
+    bot.set_name("Holder").unwrap();
+    assert!(wait_for(|| server.entity_count() == 1));
+    let query = |bot: &mut BotClient| bot.command(AppDefines::QUERY_CONTROL_POINTS).unwrap();
+    assert_eq!(query(&mut bot).code, AppDefines::EMPTY_REPLY, "not in the control point mode");
+
+    let id = {
+        let mut logic = server.game_logic.lock().unwrap();
+        logic.game_config.game_mode = GameMode::ControlPoint;
+        let id = logic.entities[0].id;
+        logic.teleport_entity(id, 600.0, 300.0);
+        id
+    };
+    let owner = format!("E{}", id);
+    assert!(wait_for(|| query(&mut bot).args.get(1).is_some_and(|record| record.split(',').nth(3) == Some(owner.as_str()))));
+    let reply = query(&mut bot);
+    assert_eq!((reply.code.as_str(), reply.arg::<usize>(0)), (AppDefines::QUERY_CONTROL_POINTS, Some(1)));
+    assert!(reply.args[1].starts_with("600.0,300.0,60.0,") && reply.args[1].ends_with(",hill, north"), "{}", reply.args[1]);
+
+    bot.command(&format!("{}=20={}", AppDefines::SUBSCRIBE, AppDefines::SUBSCRIBE_DELTA)).unwrap();
+    let keyframe = DeltaFrame::parse(&bot.wait_push(AppDefines::PUSH_KEYFRAME).unwrap().to_string()).unwrap();
+    let mut world = WorldFrame::default();
+    world.apply(&keyframe);
+    let point = &world.control_points[&0];
+    assert_eq!((point.name.as_str(), point.owner), ("hill, north", Some(Side::Entity(id))));
+}
+
+#[test]
+fn the_arena_lists_its_hazards() {
+    let map = std::env::temp_dir().join(format!("protocol_hazards_{}.json", std::process::id()));
+    let hazards = r#"[{ "x": 600, "y": 300, "shape": "circle", "radius": 60, "effect": "damage", "per_sec": 12.5 },
+        { "x": 300, "y": 700, "shape": "rect", "half_width": 80, "half_height": 40, "effect": "slow", "factor": 0.5 }]"#;
+    std::fs::write(&map, format!(r#"{{ "format_version": 1, "obstacles": [], "hazards": {} }}"#, hazards)).unwrap();
+    let server = TestServer::start_with(|_, logic| logic.load_map(&map).unwrap());
+    let mut bot = server.connect();
+
+    let reply = bot.command(AppDefines::QUERY_ARENA).unwrap();
+    assert_eq!((reply.code.as_str(), reply.arg::<usize>(2), reply.arg::<usize>(3)), (AppDefines::QUERY_ARENA, Some(0), Some(2)));
+    assert_eq!(&reply.args[4..], ["CIRCLE,600.0,300.0,60.0,60.0,DAMAGE,12.50", "RECT,300.0,700.0,80.0,40.0,SLOW,0.50"]);
+}
+
+#[test]
+fn observers_steer_a_camera_no_bot_sees() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    bot.set_name("Player").unwrap();
+    let mut observer = server.connect();
+    let refused = observer.command(AppDefines::OBSERVE).unwrap();
+    assert_eq!((refused.code.as_str(), refused.arg::<u8>(0)), (AppDefines::ERROR_REPLY, Some(AppDefines::ERR_BAD_VALUE)));
+    observer.command(AppDefines::SPECTATE).unwrap();
+    assert!(wait_for(|| server.entity_count() == 1));
+
+    let reply = observer.command(&format!("{}=150", AppDefines::OBSERVE)).unwrap();
+    assert_eq!(reply.code, AppDefines::OBSERVE);
+    assert_eq!((reply.arg::<f32>(1), reply.arg::<f32>(2), reply.arg::<f32>(3)), (Some(600.0), Some(500.0), Some(150.0)));
+    let camera_x = |observer: &mut BotClient| observer.command(AppDefines::OBSERVE).unwrap().arg::<f32>(1).unwrap();
+    assert_eq!(observer.command(&format!("{}=1", AppDefines::ACTUATOR_MOTOR_LEFT)).unwrap().code, AppDefines::OK_REPLY);
+    assert!(wait_for(|| camera_x(&mut observer) > 700.0), "the camera did not move");
+    observer.command(&format!("{}=0.5", AppDefines::ACTUATOR_MOTOR_LEFT)).unwrap();
+    let refused = observer.command(&format!("{}=1", AppDefines::ACTUATOR_GUN_TRIGGER)).unwrap();
+    assert_eq!(refused.arg::<u8>(0), Some(AppDefines::ERR_SPECTATOR));
+
+    // Aucune requête des bots ne voit la caméra
+    assert_eq!(server.entity_count(), 1);
+    assert_eq!(bot.command(AppDefines::QUERY_CLOSEST_BOT).unwrap().code, AppDefines::EMPTY_REPLY);
+    assert_eq!(bot.command(AppDefines::QUERY_WORLD).unwrap().arg::<usize>(0), Some(1));
+
+    // Le flux de l'observateur ne garde que les entités autour de sa caméra
+    let (id, camera) = {
+        let mut logic = server.game_logic.lock().unwrap();
+        let id = logic.entities[0].id;
+        logic.teleport_entity(id, 100.0, 100.0);
+        let camera = *logic.ghost_cameras().first().unwrap();
+        (id, camera)
+    };
+    observer.command(&format!("{}=20", AppDefines::SUBSCRIBE)).unwrap();
+    let pushed = |observer: &mut BotClient| observer.wait_push(AppDefines::PUSH_STATE).unwrap().arg::<usize>(0);
+    assert!(wait_for(|| pushed(&mut observer) == Some(0)), "the distant bot was pushed");
+    server.game_logic.lock().unwrap().teleport_entity(id, camera.x, camera.y + 50.0);
+    assert!(wait_for(|| pushed(&mut observer) == Some(1)), "the bot in view was not pushed");
+
+    observer.quit().unwrap();
+    assert!(wait_for(|| server.game_logic.lock().unwrap().ghost_cameras().is_empty()));
+}
+
+#[test]
+fn servers_run_side_by_side() {
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            thread::spawn(|| {
+                let server = TestServer::start();
+                let mut bots: Vec<_> = (0..2).map(|_| server.connect()).collect();
+                assert!(wait_for(|| server.entity_count() == 2));
+                for bot in &mut bots {
+                    assert!(bot.status().unwrap().is_some());
+                }
+                server.port
+            })
+        })
+        .collect();
+    let mut ports: Vec<u16> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    ports.sort_unstable();
+    ports.dedup();
+    assert_eq!(ports.len(), 3);
+}
+
+#[test]
+fn sequenced_actuators_drop_the_stale_commands() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    assert!(wait_for(|| server.entity_count() == 1));
+    let motor = |value: f32, seq: &str| format!("{}={}={}", AppDefines::ACTUATOR_MOTOR_LEFT, value, seq);
+
+    assert_eq!(bot.command(&motor(0.9, "@5")).unwrap().code, AppDefines::OK_REPLY);
+    let stale = bot.command(&motor(0.2, "@3")).unwrap();
+    assert_eq!(stale.code, AppDefines::ACTUATOR_STALE);
+    assert_eq!((stale.arg::<u64>(1), stale.arg::<u64>(2)), (Some(3), Some(5)), "{}", stale);
+    assert_eq!(bot.command(&motor(0.2, "@x")).unwrap().code, AppDefines::ERROR_REPLY);
+
+    // Le numéro appliqué n'est rapporté qu'après le pas suivant
+    assert!(wait_for(|| bot.command(AppDefines::QUERY_STATUS).unwrap().arg::<u64>(15) == Some(5)));
+    assert_eq!(server.game_logic.lock().unwrap().entities[0].motor_left, 0.9);
+}
+
+#[test]
+fn chat_messages_reach_the_other_clients() {
+    let server = TestServer::start();
+    let mut alice = server.connect();
+    let mut bob = server.connect();
+    alice.set_name("Alice").unwrap();
+
+    let sent = alice.command(&format!("{}=hello=there", AppDefines::MESSAGE)).unwrap();
+    assert_eq!(sent.code, AppDefines::OK_REPLY);
+    assert_eq!(sent.args, [AppDefines::MESSAGE, "hello", "there"]);
+    let pushed = bob.wait_push(AppDefines::MESSAGE).unwrap();
+    assert_eq!(pushed.args, ["Alice", "hello", "there"]);
+    let too_long = "x".repeat(AppDefines::MESSAGE_LENGTH as usize + 1);
+    assert_eq!(alice.command(&format!("{}={}", AppDefines::MESSAGE, too_long)).unwrap().code, AppDefines::ERROR_REPLY);
+
+    // Le message de l'opérateur part vers tous les clients, sans entité
+    assert_eq!(server.server.broadcast_chat("Welcome"), Ok(2));
+    for bot in [&mut alice, &mut bob] {
+        assert_eq!(bot.wait_push(AppDefines::MESSAGE).unwrap().args, [AppDefines::CHAT_SERVER_SENDER, "Welcome"]);
+    }
+    assert!(server.server.broadcast_chat(" ").is_err());
+    assert!(alice.take_pushes().is_empty(), "a client is not pushed its own message");
+
+    server.messages.flush();
+    let chat: Vec<(String, String)> = server.messages.log().to_vec().iter()
+        .filter_map(|message| message.chat().map(|(sender, text)| (sender.to_string(), text.to_string())))
+        .collect();
+    assert_eq!(chat, [("Alice".to_string(), "hello=there".to_string()), ("SERVER".to_string(), "Welcome".to_string())]);
+}
+
+#[test]
+fn muted_players_are_hidden_and_dropped() {
+    let server = TestServer::start();
+    let mut alice = server.connect();
+    let mut bob = server.connect();
+    alice.set_name("Alice").unwrap();
+    bob.set_name("Bob").unwrap();
+    let mutes = server.server.chat_mutes();
+    let say = |bot: &mut BotClient, text: &str| bot.command(&format!("{}={}", AppDefines::MESSAGE, text)).unwrap().code;
+
+    // Muet dans l'interface seulement : le message part encore
+    mutes.lock().unwrap().mute("Alice", false);
+    assert_eq!(say(&mut alice, "first"), AppDefines::OK_REPLY);
+    assert_eq!(bob.wait_push(AppDefines::MESSAGE).unwrap().args, ["Alice", "first"]);
+
+    // Coupé côté serveur : la réponse ne change pas, mais personne ne lit le message
+    mutes.lock().unwrap().mute("Alice", true);
+    assert_eq!(say(&mut alice, "second"), AppDefines::OK_REPLY);
+    assert_eq!(say(&mut bob, "third"), AppDefines::OK_REPLY);
+    bob.command(AppDefines::PING).unwrap();
+    assert!(bob.take_pushes().is_empty(), "the dropped message reached a client");
+    assert_eq!(alice.wait_push(AppDefines::MESSAGE).unwrap().args, ["Bob", "third"]);
+
+    server.messages.flush();
+    let log = server.messages.log().to_vec();
+    let shown = |mutes: &ChatMutes| -> Vec<String> {
+        log.iter().filter(|message| mutes.shows(message)).map(|message| message.text.clone()).collect()
+    };
+    assert_eq!(shown(&mutes.lock().unwrap()), ["Bob: third"], "the server keeps the messages of the muted players");
+    mutes.lock().unwrap().unmute("Alice");
+    assert!(!mutes.lock().unwrap().drops("Alice"));
+    assert_eq!(shown(&mutes.lock().unwrap()), ["Alice: first", "Alice: second", "Bob: third"]);
+}
+
+#[test]
+fn horde_waves_are_announced_to_every_client() {
+    let server = TestServer::start();
+    let mut first = server.connect();
+    let mut second = server.connect();
+    first.set_name("Fallen").unwrap();
+    assert!(wait_for(|| server.entity_count() == 2));
+    {
+        let mut logic = server.game_logic.lock().unwrap();
+        logic.start_horde();
+        // L'entracte est simulé d'un coup, sous le verrou
+        while logic.wave_director.as_ref().unwrap().wave == 0 {
+            logic.step();
+        }
+        let fallen = logic.entities.iter().find(|entity| entity.name == "Fallen").unwrap().id;
+        logic.kill_entity(fallen);
+    }
+
+    for bot in [&mut first, &mut second] {
+        let wave = bot.wait_push(AppDefines::PUSH_WAVE).unwrap();
+        assert_eq!((wave.arg::<u32>(0), wave.args[1].as_str()), (Some(1), AppDefines::WAVE_STARTED));
+    }
+    // Le joueur abattu attend la fin de la vague pour revenir
+    assert!(matches!(first.set_motors(1.0, 1.0), Err(ClientError::Refused { code: AppDefines::ERR_NO_ENTITY, .. })));
+    {
+        let mut logic = server.game_logic.lock().unwrap();
+        while logic.entities.iter().filter(|entity| entity.is_ai).count() < WaveDirector::wave_size(1) as usize {
+            logic.step();
+        }
+        let bots: Vec<u32> = logic.entities.iter().filter(|entity| entity.is_ai).map(|entity| entity.id).collect();
+        for bot in bots {
+            logic.kill_entity(bot);
+        }
+        logic.step();
+    }
+    let cleared = second.wait_push(AppDefines::PUSH_WAVE).unwrap();
+    assert_eq!((cleared.arg::<u32>(0), cleared.args[1].as_str()), (Some(1), AppDefines::WAVE_CLEARED));
+    first.wait_push(AppDefines::PUSH_WAVE).unwrap();
+    // Moteurs à l'arrêt : l'entité reste là où elle est apparue
+    first.set_motors(0.0, 0.0).unwrap();
+    let respawn = first.wait_push(AppDefines::PUSH_RESPAWN).unwrap();
+    let (x, y) = (respawn.arg::<f32>(0).unwrap(), respawn.arg::<f32>(1).unwrap());
+    let logic = server.game_logic.lock().unwrap();
+    let players: Vec<(f32, f32)> = logic.entities.iter().filter(|entity| !entity.is_ai).map(|entity| (entity.x, entity.y)).collect();
+    assert_eq!(players.len(), 2);
+    assert!(players.iter().any(|&(px, py)| (px - x).abs() < 1.0 && (py - y).abs() < 1.0), "{:?} is not at {:?}", players, (x, y));
+    drop(logic);
+    assert!(second.take_pushes().iter().all(|push| !push.starts_with(AppDefines::PUSH_RESPAWN)), "only the fallen player is told");
+}
+
+#[test]
+fn commands_beyond_the_step_budget_are_ignored() {
+    let server = TestServer::start_with(|settings, logic| {
+        settings.command_budget_per_step = 5;
+        // Sans pas de simulation, les 50 commandes tombent dans le même pas
+        logic.pause();
+    });
+    let mut bot = server.connect();
+    assert_eq!(bot.command_budget, 5, "the budget was not advertised");
+
+    // Actionneurs et requêtes mêlés : chacun compte une fois, quel qu'il soit
+    let mixed: Vec<String> = (0..50).map(|i| {
+        let value = (i + 1) as f32 / 100.0;
+        match i % 5 {
+            0 => format!("{}{}{}", AppDefines::ACTUATOR_MOTOR_LEFT, AppDefines::ARGUMENT_SEP, value),
+            1 => format!("{}{}{}", AppDefines::ACTUATOR_MOTOR_RIGHT, AppDefines::ARGUMENT_SEP, value),
+            2 => AppDefines::QUERY_STATUS.to_string(),
+            3 => AppDefines::QUERY_ORIENTATION.to_string(),
+            _ => AppDefines::QUERY_ENERGY.to_string(),
+        }
+    }).collect();
+    let replies = bot.send(&mixed.join(AppDefines::COMMAND_SEP)).unwrap();
+    assert_eq!(replies.len(), 50);
+    let took_effect: Vec<&str> = replies.iter().map(|reply| reply.code.as_str()).filter(|&code| code != AppDefines::BUDGET_EXCEEDED).collect();
+    assert_eq!(
+        took_effect,
+        [AppDefines::OK_REPLY, AppDefines::OK_REPLY, AppDefines::QUERY_STATUS, AppDefines::QUERY_ORIENTATION, AppDefines::QUERY_ENERGY],
+        "only the first five commands run"
+    );
+    assert!(replies[5..].iter().zip(&mixed[5..]).all(|(reply, command)| {
+        reply.code == AppDefines::BUDGET_EXCEEDED && command.starts_with(reply.args[0].as_str())
+    }));
+    {
+        // Les moteurs gardent les valeurs des deux premières commandes
+        let logic = server.game_logic.lock().unwrap();
+        let entity = logic.entities.iter().find(|entity| !entity.is_ai).unwrap();
+        assert_eq!((entity.motor_left, entity.motor_right), (0.01, 0.02));
+    }
+
+    let line = vec![format!("{}{}0.9", AppDefines::ACTUATOR_MOTOR_LEFT, AppDefines::ARGUMENT_SEP); 50].join(AppDefines::COMMAND_SEP);
+
+    // Le pas suivant ouvre un nouveau budget
+    server.game_logic.lock().unwrap().resume();
+    assert!(wait_for(|| bot.send(&line).unwrap().iter().any(|reply| reply.code == AppDefines::OK_REPLY)));
+}
+
+#[test]
+fn entities_are_reclaimed_within_the_grace_period_only() {
+    let server = TestServer::start_with(|settings, _| settings.reconnect_grace_secs = 1);
+    let mut first = BotClient::connect("127.0.0.1", server.port).unwrap();
+    let token = first.hello().unwrap();
+    first.set_name("Phoenix").unwrap();
+    let reconnect = format!("{}{}{}", AppDefines::RECONNECT, AppDefines::ARGUMENT_SEP, token);
+    drop(first);
+
+    // Dans le délai : la nouvelle connexion reprend l'entité, la sienne disparaît
+    let mut second = server.connect();
+    assert_eq!(server.entity_count(), 2);
+    // Le jeton reste pris tant que le serveur n'a pas vu la déconnexion
+    assert!(wait_for(|| second.command(&reconnect).unwrap().code == AppDefines::OK_REPLY), "the entity was not reclaimed");
+    let names: Vec<String> = server.game_logic.lock().unwrap().entities.iter().map(|entity| entity.name.clone()).collect();
+    assert_eq!(names, ["Phoenix"]);
+    assert!(second.status().unwrap().is_some(), "the reclaimed entity answers");
+
+    // Après le délai : l'entité est retirée et le jeton refusé
+    drop(second);
+    thread::sleep(std::time::Duration::from_millis(300));
+    assert_eq!(server.entity_count(), 1, "the entity waits for its client during the grace period");
+    assert!(wait_for(|| server.entity_count() == 0), "the entity outlived the grace period");
+    let mut third = server.connect();
+    match third.command(&reconnect).unwrap().expect(AppDefines::OK_REPLY) {
+        Err(ClientError::Refused { code, .. }) => assert_eq!(code, AppDefines::ERR_TOKEN_EXPIRED),
+        other => panic!("the expired token was accepted: {:?}", other),
+    }
+}
+
+#[test]
+fn subscriptions_push_at_their_rate() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    let reply = bot.command(&format!("{}{}10", AppDefines::SUBSCRIBE, AppDefines::ARGUMENT_SEP)).unwrap();
+    assert_eq!(reply.code, AppDefines::OK_REPLY, "{}", reply);
+    bot.take_pushes();
+
+    // Les poussées partent à l'horloge, que la simulation avance ou non
+    let started = std::time::Instant::now();
+    let mut frames = 0;
+    while started.elapsed() < std::time::Duration::from_secs(1) {
+        if bot.read_line().unwrap().starts_with(AppDefines::PUSH_STATE) {
+            frames += 1;
+        }
+    }
+    assert!((8..=12).contains(&frames), "{} frames in one second at 10 Hz", frames);
+}
+
+#[test]
+fn admins_kick_the_entity_of_another_connection() {
+    let server = TestServer::start_with(|settings, _| settings.admin_password = "secret".to_string());
+    let mut admin = server.connect();
+    let mut victim = server.connect();
+    victim.set_name("Victim").unwrap();
+    admin.command("ADMIN=secret").unwrap().expect(AppDefines::OK_REPLY).unwrap();
+
+    admin.command("KICK=Victim").unwrap().expect(AppDefines::OK_REPLY).unwrap();
+    assert_eq!(server.entity_count(), 1);
+    assert!(server.game_logic.lock().unwrap().entity_by_name("Victim").is_none());
+    // La connexion du joueur exclu reçoit l'erreur, puis est fermée
+    assert_eq!(victim.read_line().unwrap(), "ERR=15=kicked=Kicked by an admin");
+    assert!(matches!(victim.read_line(), Err(ClientError::Closed)));
+    assert!(admin.status().unwrap().is_some(), "the admin lost its own entity");
+}
+
+#[test]
+fn bans_of_ipv4_mapped_addresses_refuse_the_ipv4_clients() {
+    let server = TestServer::start_with(|settings, _| settings.admin_password = "secret".to_string());
+    let mut admin = server.connect();
+    admin.command("ADMIN=secret").unwrap().expect(AppDefines::OK_REPLY).unwrap();
+
+    // L'adresse IPv6 qui porte 127.0.0.1 bannit les clients IPv4 de cette adresse, l'admin compris
+    admin.command("BAN=::ffff:127.0.0.1").unwrap().expect(AppDefines::OK_REPLY).unwrap();
+    assert!(wait_for(|| server.entity_count() == 0), "the banned client kept its entity");
+    let refused = BotClient::connect("127.0.0.1", server.port).unwrap();
+    assert_eq!(refused.greeting, "ERR=14=banned=This address is banned");
+    thread::sleep(std::time::Duration::from_millis(100));
+    assert_eq!(server.entity_count(), 0, "a banned client got an entity");
+}
+
+#[test]
+fn the_arena_reports_the_obstacles_of_a_regenerated_map() {
+    let server = TestServer::start_with(|_, logic| logic.generate_map(Some(1)));
+    let mut bot = server.connect();
+    let positions = || -> Vec<String> {
+        let logic = server.game_logic.lock().unwrap();
+        logic.obstacles.iter().map(|obstacle| format!("{:.1},{:.1}", obstacle.position.0, obstacle.position.1)).collect()
+    };
+    let reported = |bot: &mut BotClient| -> Vec<String> {
+        let arena = bot.command(AppDefines::QUERY_ARENA).unwrap();
+        let obstacles = bot.command(AppDefines::QUERY_OBSTACLES).unwrap();
+        assert_eq!(arena.arg::<usize>(2), obstacles.arg::<usize>(0), "ARENA and OBSTACLES disagree");
+        assert_eq!(obstacles.arg::<usize>(0), Some(obstacles.args.len() - 1));
+        // Les deux premiers champs de chaque obstacle sont sa position
+        obstacles.args[1..].iter().map(|record| record.splitn(3, ',').take(2).collect::<Vec<_>>().join(",")).collect()
+    };
+
+    let first_map = positions();
+    assert_eq!(reported(&mut bot), first_map);
+    server.game_logic.lock().unwrap().generate_map(Some(2));
+    let second_map = positions();
+    assert_ne!(second_map, first_map, "the seeds gave the same map");
+    assert_eq!(reported(&mut bot), second_map);
+}
+
+#[test]
+fn lidar_measures_an_obstacle_dead_ahead() {
+    let server = TestServer::start();
+    let mut bot = server.connect();
+    let mut logic = server.game_logic.lock().unwrap();
+    let id = logic.entities[0].id;
+    logic.teleport_entity(id, 600.0, 500.0);
+    let heading = logic.physics_engine.bodies[logic.entities[0].handle].rotation().angle();
+    // Un disque de rayon 10 dont le centre est à 100 unités devant : le rayon avant touche son bord à 90
+    let (x, y) = (600.0 + 100.0 * heading.cos(), 500.0 + 100.0 * heading.sin());
+    logic.add_obstacle_at(x, y, ObstacleShape::Circle { radius: 10.0 }).unwrap();
+    // Le pas met à jour les requêtes de la physique
+    logic.step();
+    drop(logic);
+
+    let reply = bot.command(&format!("{}{}4", AppDefines::QUERY_LIDAR, AppDefines::ARGUMENT_SEP)).unwrap();
+    assert_eq!((reply.code.as_str(), reply.arg::<usize>(0)), (AppDefines::QUERY_LIDAR, Some(4)));
+    let ahead = reply.arg::<f32>(1).unwrap();
+    assert!((ahead - 90.0).abs() < 0.5, "the front ray reported {}", ahead);
+    // Les autres rayons vont jusqu'aux murs de l'arène, plus loin
+    assert!((2..=4).all(|ray| reply.arg::<f32>(ray).is_some_and(|distance| distance > 300.0)), "{:?}", reply);
+}
+
+#[test]
+fn sensors_report_the_whole_state_in_fixed_fields() {
+    let server = TestServer::start();
+    let mut alice = server.connect();
+    let mut bob = server.connect();
+    alice.set_name("Alice").unwrap();
+    bob.set_name("Bob").unwrap();
+    let mut logic = server.game_logic.lock().unwrap();
+    // Alice regarde vers +x, Bob est 100 unités plus loin et 100 plus haut, tourné d'un quart de tour
+    // Les deux viennent de tirer : il reste 500 ms, moins le pas qui suit
+    let now = logic.sim_time();
+    for (name, x, y, rotation) in [("Alice", 300.0, 300.0, 0.0), ("Bob", 400.0, 400.0, std::f32::consts::FRAC_PI_2)] {
+        let id = logic.entity_by_name(name).unwrap().id;
+        logic.teleport_entity(id, x, y);
+        let entity = logic.get_entity_mut(id).unwrap();
+        entity.score = 3;
+        entity.fire_cooldown = std::time::Duration::from_millis(500);
+        let gun = entity.gun_mut();
+        (gun.orientation, gun.traverse, gun.last_shot) = (0.25, 0.25, now);
+        let handle = entity.handle;
+        logic.physics_engine.bodies[handle].set_rotation(rapier2d::prelude::Rotation::new(rotation), true);
+    }
+    logic.step();
+    drop(logic);
+
+    assert_eq!(alice.command(AppDefines::QUERY_SENSORS).unwrap().args.join("="), "300.0=300.0=0.000=4.712=0.0=0.0=0.000=1=3=484=141.4=45.0=EMPTY=EMPTY");
+    assert_eq!(bob.command(AppDefines::QUERY_SENSORS).unwrap().args.join("="), "400.0=400.0=1.571=6.283=0.0=0.0=0.000=1=3=484=141.4=135.0=EMPTY=EMPTY");
+}
+
+#[test]
+fn events_are_read_from_a_cursor_per_client() {
+    let server = TestServer::start();
+    // Le type et la source de chaque événement, sans le numéro ni le pas
+    let events = |bot: &mut BotClient| -> Vec<String> {
+        let reply = bot.command(AppDefines::QUERY_EVENTS).unwrap();
+        assert_eq!(reply.arg::<usize>(0), Some(reply.args.len() - 1), "{:?}", reply);
+        reply.args[1..].iter().map(|record| record.split(',').skip(2).take(2).collect::<Vec<_>>().join(",")).collect()
+    };
+    let mut alice = server.connect();
+    let alice_id = server.game_logic.lock().unwrap().entities[0].id;
+    assert_eq!(events(&mut alice), [format!("spawn,{}", alice_id)], "the events since alice connected");
+    let mut bob = server.connect();
+    let bob_id = server.game_logic.lock().unwrap().entities[1].id;
+
+    assert_eq!(events(&mut alice), [format!("spawn,{}", bob_id)]);
+    assert!(events(&mut alice).is_empty(), "alice read the same events twice");
+    assert_eq!(events(&mut bob), [format!("spawn,{}", bob_id)], "bob does not see what happened before connecting");
+
+    let mut logic = server.game_logic.lock().unwrap();
+    logic.kill_entity(bob_id);
+    logic.step();
+    drop(logic);
+    let kill = format!("kill,{}", bob_id);
+    assert!(events(&mut alice).contains(&kill), "alice missed the kill");
+    assert!(events(&mut alice).is_empty());
+}
@@ -0,0 +1,134 @@
+use universal_rust_server_software::app_defines::AppDefines;
+use universal_rust_server_software::game_logic::control_point::Side;
+use universal_rust_server_software::server::state_delta::{BulletState, DeltaEncoder, DeltaFrame, EntityState, WorldFrame};
+
+/// The largest gap between a position rebuilt by a subscriber and the true one:
+/// what a delta does not send, plus the rounding to one decimal.
+const POSITION_TOLERANCE: f32 = AppDefines::DELTA_POSITION_EPSILON + 0.05;
+
+/// The world after `step` steps of a scripted game: moving, slow, idle, turning, hit and scoring entities,
+/// an entity joining and one leaving, and bullets fired and expiring.
+fn scripted_world(step: u32) -> WorldFrame {
+    let t = step as f32;
+    let mut world = WorldFrame::default();
+    let mut entity = |id: u32, x: f32, y: f32, orientation: f64, health: i32, score: i32| {
+        let name = format!("Bot,{}", id);
+        world.entities.insert(id, EntityState { x, y, orientation, health, score, name });
+    };
+    entity(1, 100.0 + 3.0 * t, 200.0, 0.0, 100, 0);
+    entity(2, 400.0, 300.0 + 0.1 * t, 0.0, 100, 0);
+    entity(3, 600.0, 600.0, 0.05 * t as f64, 100 - (step / 10) as i32, (step / 25) as i32);
+    if step < 70 {
+        entity(4, 50.0, 50.0, 1.0, 100, 0);
+    }
+    if step >= 30 {
+        entity(5, 700.0 - t, 100.0, 3.0, 100, 0);
+    }
+    entity(6, 300.0, 900.0, 2.0, 100, 0);
+    for fired in step.saturating_sub(20)..=step {
+        if fired % 4 == 0 {
+            let age = (step - fired) as f32;
+            world.bullets.insert(fired as u64 + (1 << 32), BulletState { x: 100.0 + 8.0 * age, y: 200.0 - 2.0 * age });
+        }
+    }
+    world
+}
+
+/// Checks that a rebuilt world holds the same entities and bullets as the true one, within the tolerance.
+fn assert_rebuilt(rebuilt: &WorldFrame, world: &WorldFrame, step: u32) {
+    assert_eq!(rebuilt.entities.keys().collect::<Vec<_>>(), world.entities.keys().collect::<Vec<_>>(), "entities at step {}", step);
+    assert_eq!(rebuilt.bullets.keys().collect::<Vec<_>>(), world.bullets.keys().collect::<Vec<_>>(), "bullets at step {}", step);
+    for (id, entity) in &world.entities {
+        let rebuilt = &rebuilt.entities[id];
+        assert!((rebuilt.x - entity.x).abs() <= POSITION_TOLERANCE, "x of {} at step {}", id, step);
+        assert!((rebuilt.y - entity.y).abs() <= POSITION_TOLERANCE, "y of {} at step {}", id, step);
+        assert!((rebuilt.orientation - entity.orientation).abs() <= AppDefines::DELTA_ORIENTATION_EPSILON + 0.0005, "orientation of {} at step {}", id, step);
+        assert_eq!((rebuilt.health, rebuilt.score, &rebuilt.name), (entity.health, entity.score, &entity.name), "entity {} at step {}", id, step);
+    }
+    for (id, bullet) in &world.bullets {
+        let rebuilt = &rebuilt.bullets[id];
+        assert!((rebuilt.x - bullet.x).abs() <= POSITION_TOLERANCE && (rebuilt.y - bullet.y).abs() <= POSITION_TOLERANCE, "bullet {} at step {}", id, step);
+    }
+}
+
+#[test]
+fn subscribers_rebuild_the_world_from_the_frames() {
+    let mut encoder = DeltaEncoder::new(AppDefines::DELTA_KEYFRAME_INTERVAL);
+    let mut rebuilt = WorldFrame::default();
+    let mut keyframes = Vec::new();
+    for step in 0..100 {
+        let world = scripted_world(step);
+        let frame = encoder.encode(&world);
+        let line = frame.to_legacy();
+        let received = DeltaFrame::parse(&line).unwrap_or_else(|| panic!("unreadable frame at step {}: {}", step, line));
+        assert_eq!(received.keyframe, frame.keyframe);
+        assert_eq!(received.len(), frame.len());
+        if frame.keyframe {
+            keyframes.push(step);
+        } else {
+            // L'entité 6 ne change jamais : un delta ne la renvoie pas
+            assert!(received.entities.iter().all(|(id, _)| *id != 6), "a delta should leave the idle entities out at step {}", step);
+        }
+        encoder.acknowledge(&frame);
+        rebuilt.apply(&received);
+        assert_rebuilt(&rebuilt, &world, step);
+    }
+    assert_eq!(keyframes, vec![0, 40, 80]);
+}
+
+#[test]
+fn dropped_frames_are_covered_by_the_next_one() {
+    let mut encoder = DeltaEncoder::new(AppDefines::DELTA_KEYFRAME_INTERVAL);
+    let mut rebuilt = WorldFrame::default();
+    for step in 0..60 {
+        let world = scripted_world(step);
+        let frame = encoder.encode(&world);
+        // Une trame sur trois n'est jamais écrite : la suivante part de la même base
+        if step % 3 == 1 {
+            continue;
+        }
+        encoder.acknowledge(&frame);
+        rebuilt.apply(&DeltaFrame::parse(&frame.to_legacy()).unwrap());
+        assert_rebuilt(&rebuilt, &world, step);
+    }
+}
+
+#[test]
+fn keyframes_are_sent_on_request() {
+    let mut encoder = DeltaEncoder::new(AppDefines::DELTA_KEYFRAME_INTERVAL);
+    let world = scripted_world(10);
+    let first = encoder.encode(&world);
+    assert!(first.keyframe, "the first frame is a keyframe");
+    encoder.acknowledge(&first);
+
+    let unchanged = encoder.encode(&world);
+    assert!(!unchanged.keyframe);
+    assert!(unchanged.is_empty(), "nothing changed: {:?}", unchanged);
+    assert_eq!(unchanged.to_legacy(), format!("{}=0", AppDefines::PUSH_DELTA));
+
+    encoder.request_keyframe();
+    let resync = encoder.encode(&world);
+    assert!(resync.keyframe);
+    assert_eq!(resync.len(), world.entities.len() + world.bullets.len());
+    assert!(resync.to_legacy().starts_with(AppDefines::PUSH_KEYFRAME));
+
+    // Tant que le keyframe n'est pas écrit, il reste dû
+    assert!(encoder.encode(&world).keyframe);
+    encoder.acknowledge(&resync);
+    assert!(!encoder.encode(&world).keyframe);
+}
+
+#[test]
+fn malformed_frames_are_refused() {
+    for line in ["STATE=0", "DELTA", "DELTA=x", "DELTA=2=B,1,0.0,0.0", "DELTA=1=B,1,0.0", "DELTA=1=Z,1", "KEYFRAME=1=-E,abc", "DELTA=0=-B,1"] {
+        assert_eq!(DeltaFrame::parse(line), None, "{}", line);
+    }
+    let frame = DeltaFrame::parse("DELTA=2=E,7,1.5,2.5,0.125,90,3,A,B=-B,4294967296").unwrap();
+    assert_eq!(frame.entities[0].1.name, "A,B");
+    assert_eq!(frame.removed_bullets, vec![1 << 32]);
+    assert_eq!(DeltaFrame::parse("DELTA=1=P,0,1.0,2.0,50.0,X3,0.5,hill"), None, "an unknown side");
+    let frame = DeltaFrame::parse("DELTA=3=P,0,1.0,2.0,50.0,T2,0.500,hill=P,1,1.0,2.0,50.0,EMPTY,0.000,dip=-P,4").unwrap();
+    assert_eq!(frame.control_points.iter().map(|(index, point)| (*index, point.owner)).collect::<Vec<_>>(), [(0, Some(Side::Team(2))), (1, None)]);
+    assert_eq!(frame.removed_control_points, vec![4]);
+    assert_eq!(DeltaFrame::parse(&frame.to_legacy()), Some(frame));
+}
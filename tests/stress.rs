@@ -0,0 +1,27 @@
+use universal_rust_server_software::game_logic::stress::StressScenario;
+
+#[test]
+fn scenarios_are_deterministic() {
+    let scenario = StressScenario { bots: 5, ..StressScenario::new(20, 100, 25) };
+    let positions = || {
+        let mut logic = scenario.build();
+        for _ in 0..30 {
+            logic.update_ai();
+            logic.step();
+        }
+        (logic.entities.iter().map(|entity| (entity.x, entity.y)).collect::<Vec<_>>(), logic.bullets.len())
+    };
+    assert_eq!(positions(), positions());
+}
+
+#[test]
+fn loaded_games_keep_their_entities() {
+    let mut logic = StressScenario::new(200, 1000, 100).build();
+    assert_eq!(logic.entities.len(), 200);
+    assert_eq!(logic.obstacles.len(), 100);
+    assert!(logic.bullets.len() > 500, "most bullets should still fly after one step, {} do", logic.bullets.len());
+    for _ in 0..60 {
+        logic.step();
+    }
+    assert_eq!(logic.entities.len(), 200, "no entity dies in a stress scenario");
+}
@@ -0,0 +1,119 @@
+mod harness;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use harness::{read_text_frame, send_text_frame, wait_for, web_socket_upgrade, TestServer};
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use universal_rust_server_software::app_defines::AppDefines;
+
+/// A TLS session of a test client.
+type TlsClient = StreamOwned<ClientConnection, TcpStream>;
+
+/// Writes a self-signed certificate for `localhost` and its key to the temporary directory.
+///
+/// # Returns
+/// The certificate and key files, and the certificate the clients trust.
+fn self_signed(name: &str) -> (PathBuf, PathBuf, CertificateDer<'static>) {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let dir = std::env::temp_dir();
+    let cert_path = dir.join(format!("{}_{}.crt", name, std::process::id()));
+    let key_path = dir.join(format!("{}_{}.key", name, std::process::id()));
+    fs::write(&cert_path, certified.cert.pem()).unwrap();
+    fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+    (cert_path, key_path, certified.cert.der().clone())
+}
+
+/// Starts a server whose TCP and WebSocket clients must speak TLS.
+///
+/// # Returns
+/// The server and the certificate the clients trust.
+fn start_tls_server(name: &str, web_socket_port: u16) -> (TestServer, CertificateDer<'static>) {
+    let (cert_path, key_path, cert) = self_signed(name);
+    let server = TestServer::start_with(|settings, _| {
+        settings.enable_tls = true;
+        settings.tls_cert_path = cert_path.to_string_lossy().into_owned();
+        settings.tls_key_path = key_path.to_string_lossy().into_owned();
+        settings.web_socket_port = web_socket_port;
+    });
+    // Le serveur a lu les fichiers en démarrant
+    let _ = fs::remove_file(&cert_path);
+    let _ = fs::remove_file(&key_path);
+    (server, cert)
+}
+
+/// Opens a TLS session to a port of the loopback, trusting only the certificate of the test.
+fn connect_tls(port: u16, cert: &CertificateDer<'static>) -> TlsClient {
+    let mut roots = RootCertStore::empty();
+    roots.add(cert.clone()).unwrap();
+    let config = ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+    let conn = ClientConnection::new(Arc::new(config), ServerName::try_from("localhost").unwrap()).unwrap();
+    let socket = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    StreamOwned::new(conn, socket)
+}
+
+/// Reads the next line sent by the server, without its line ending.
+fn read_reply(reader: &mut BufReader<TlsClient>) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line.trim_end().to_string()
+}
+
+#[test]
+fn tcp_clients_play_over_tls() {
+    let (server, cert) = start_tls_server("tls_tcp", 0);
+    let mut reader = BufReader::new(connect_tls(server.port, &cert));
+    let greeting = read_reply(&mut reader);
+    assert!(greeting.starts_with(AppDefines::HELLO), "no greeting: {}", greeting);
+
+    let sep = AppDefines::ARGUMENT_SEP;
+    writeln!(reader.get_mut(), "{}{sep}{}", AppDefines::HELLO, AppDefines::PROTOCOL_VERSION).unwrap();
+    let hello = read_reply(&mut reader);
+    assert!(hello.starts_with(&format!("{}{sep}{}", AppDefines::OK_REPLY, AppDefines::HELLO)), "the handshake failed: {}", hello);
+    assert!(wait_for(|| server.entity_count() == 1), "the TLS client got no entity");
+    writeln!(reader.get_mut(), "{}", AppDefines::QUERY_STATUS).unwrap();
+    let status = read_reply(&mut reader);
+    assert!(status.contains(&format!("{}{sep}ALIVE", AppDefines::QUERY_STATUS)), "no status: {}", status);
+}
+
+#[test]
+fn web_socket_clients_play_over_tls() {
+    let web_socket_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let (server, cert) = start_tls_server("tls_web_socket", web_socket_port);
+    let mut stream = connect_tls(web_socket_port, &cert);
+    let response = web_socket_upgrade(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 101"), "the upgrade was refused: {}", response);
+    assert!(response.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="), "wrong accept key: {}", response);
+    let greeting = read_text_frame(&mut stream);
+    assert!(greeting.starts_with(AppDefines::HELLO), "no greeting: {}", greeting);
+
+    let sep = AppDefines::ARGUMENT_SEP;
+    send_text_frame(&mut stream, &format!("{}{sep}{}", AppDefines::HELLO, AppDefines::PROTOCOL_VERSION));
+    let hello = read_text_frame(&mut stream);
+    assert!(hello.starts_with(&format!("{}{sep}{}", AppDefines::OK_REPLY, AppDefines::HELLO)), "the handshake failed: {}", hello);
+    assert!(wait_for(|| server.entity_count() == 1), "the WebSocket client got no entity");
+    send_text_frame(&mut stream, AppDefines::QUERY_STATUS);
+    let status = read_text_frame(&mut stream);
+    assert!(status.contains(&format!("{}{sep}ALIVE", AppDefines::QUERY_STATUS)), "no status: {}", status);
+}
+
+#[test]
+fn plaintext_clients_are_dropped_without_an_entity() {
+    let (server, _) = start_tls_server("tls_plaintext", 0);
+    let mut client = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    writeln!(client, "{}{}{}", AppDefines::HELLO, AppDefines::ARGUMENT_SEP, AppDefines::PROTOCOL_VERSION).unwrap();
+
+    // Le serveur répond par une alerte TLS au plus, puis ferme la connexion
+    let mut received = Vec::new();
+    let _ = client.read_to_end(&mut received);
+    assert!(!String::from_utf8_lossy(&received).contains(AppDefines::HELLO), "a plaintext client was greeted");
+    assert!(wait_for(|| server.message_texts().iter().any(|text| text.contains("TLS handshake with"))), "the failed handshake was not logged");
+    assert_eq!(server.entity_count(), 0);
+}